@@ -16,7 +16,7 @@ NOTES:
     keys on its account.
 */
 use near_contract_standards::fungible_token::metadata::{
-    FungibleTokenMetadata, FungibleTokenMetadataProvider, FT_METADATA_SPEC,
+    FungibleTokenMetadata, FungibleTokenMetadataProvider,
 };
 use near_contract_standards::fungible_token::FungibleToken;
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
@@ -52,13 +52,12 @@ impl Contract {
             owner_id,
             total_supply,
             FungibleTokenMetadata {
-                spec: FT_METADATA_SPEC.to_string(),
-                name: "Example NEAR fungible token".to_string(),
-                symbol: "EXAMPLE".to_string(),
                 icon: Some(DATA_IMAGE_SVG_NEAR_ICON.to_string()),
-                reference: None,
-                reference_hash: None,
-                decimals: 24,
+                ..FungibleTokenMetadata::new(
+                    "Example NEAR fungible token".to_string(),
+                    "EXAMPLE".to_string(),
+                    24,
+                )
             },
         )
     }
@@ -74,7 +73,11 @@ impl Contract {
             metadata: LazyOption::new(StorageKey::Metadata, Some(&metadata)),
         };
         this.token.internal_register_account(&owner_id);
-        this.token.internal_deposit(&owner_id, total_supply.into());
+        this.token.internal_mint(
+            &owner_id,
+            total_supply.into(),
+            Some("Initial tokens supply is minted"),
+        );
         this
     }
 
@@ -95,6 +98,10 @@ impl FungibleTokenMetadataProvider for Contract {
     fn ft_metadata(&self) -> FungibleTokenMetadata {
         self.metadata.get().unwrap()
     }
+
+    fn ft_supports_spec(&self, spec: String) -> bool {
+        self.metadata.get().unwrap().supports_spec(&spec)
+    }
 }
 
 #[cfg(all(test, not(target_arch = "wasm32")))]
@@ -2,7 +2,7 @@ use crate::utils::init;
 use near_contract_standards::non_fungible_token::Token;
 use near_primitives::views::FinalExecutionStatus;
 use near_sdk::{AccountId, ONE_NEAR, ONE_YOCTO};
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::convert::TryFrom;
 
 pub const TOKEN_ID: &str = "0";
@@ -56,7 +56,7 @@ async fn simulate_simple_approve() -> anyhow::Result<()> {
         .view()
         .await?
         .json::<Token>()?;
-    let mut expected_approvals: HashMap<AccountId, u64> = HashMap::new();
+    let mut expected_approvals: BTreeMap<AccountId, u64> = BTreeMap::new();
     expected_approvals.insert(AccountId::try_from(alice.id().to_string())?, 1);
     assert_eq!(token.approved_account_ids.unwrap(), expected_approvals);
 
@@ -307,3 +307,59 @@ async fn simulate_revoke_all() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn simulate_rejected_transfer_call_restores_approvals() -> anyhow::Result<()> {
+    let worker = workspaces::sandbox();
+    let (nft_contract, alice, token_receiver_contract, approval_receiver_contract) =
+        init(&worker).await?;
+
+    // root approves alice and approval_receiver
+    for account_id in [alice.id(), approval_receiver_contract.id()] {
+        let res = nft_contract
+            .call(&worker, "nft_approve")
+            .args_json((TOKEN_ID, account_id, Option::<String>::None))?
+            .gas(300_000_000_000_000)
+            .deposit(510000000000000000000)
+            .transact()
+            .await?;
+        assert!(matches!(res.status, FinalExecutionStatus::SuccessValue(_)));
+    }
+
+    // root transfers to token_receiver, which returns the token
+    let res = nft_contract
+        .call(&worker, "nft_transfer_call")
+        .args_json((
+            token_receiver_contract.id(),
+            TOKEN_ID,
+            Option::<u64>::None,
+            Option::<String>::None,
+            "return-it-now",
+        ))?
+        .gas(300_000_000_000_000)
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(matches!(res.status, FinalExecutionStatus::SuccessValue(_)));
+
+    // the approvals are restored with their original approval IDs
+    for (account_id, approval_id) in [(alice.id(), 1u64), (approval_receiver_contract.id(), 2)] {
+        let approved = nft_contract
+            .call(&worker, "nft_is_approved")
+            .args_json((TOKEN_ID, account_id, Some(approval_id)))?
+            .view()
+            .await?
+            .json::<bool>()?;
+        assert!(approved);
+    }
+
+    let token = nft_contract
+        .call(&worker, "nft_token")
+        .args_json((TOKEN_ID,))?
+        .view()
+        .await?
+        .json::<Token>()?;
+    assert_eq!(token.owner_id.to_string(), nft_contract.id().to_string());
+
+    Ok(())
+}
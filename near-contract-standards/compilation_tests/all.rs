@@ -0,0 +1,11 @@
+#[rustversion::stable]
+#[test]
+fn compilation_tests() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("compilation_tests/misspelled_field.rs");
+    t.compile_fail("compilation_tests/wrong_field_type.rs");
+    t.pass("compilation_tests/nft_macros.rs");
+    t.pass("compilation_tests/nft_macro_combined.rs");
+    t.pass("compilation_tests/nft_macro_gas.rs");
+    t.pass("compilation_tests/ft_nft_combined.rs");
+}
@@ -0,0 +1,15 @@
+//! A misspelled token field is reported at the macro invocation.
+
+use near_contract_standards::non_fungible_token::{NonFungibleToken, Token};
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::{near_bindgen, AccountId, PanicOnDefault};
+
+#[near_bindgen]
+#[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+pub struct Contract {
+    tokens: NonFungibleToken,
+}
+
+near_contract_standards::impl_non_fungible_token_enumeration!(Contract, token);
+
+fn main() {}
@@ -0,0 +1,25 @@
+//! `impl_non_fungible_token!` implements every NFT standard, here with the contract metadata
+//! kept off-chain.
+
+use near_contract_standards::non_fungible_token::metadata::MetadataSource;
+use near_contract_standards::non_fungible_token::{NonFungibleToken, Token, TokenId};
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::{near_bindgen, AccountId, PanicOnDefault, Promise, PromiseOrValue};
+
+#[near_bindgen]
+#[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+pub struct Contract {
+    tokens: NonFungibleToken,
+    metadata: MetadataSource,
+}
+
+near_contract_standards::impl_non_fungible_token!(Contract, tokens, metadata);
+
+#[near_bindgen]
+impl Contract {
+    pub fn standards(&self) -> Vec<near_contract_standards::standards::Standard<'static>> {
+        near_contract_standards::standards_manifest!()
+    }
+}
+
+fn main() {}
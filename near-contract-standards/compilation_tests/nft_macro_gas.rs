@@ -0,0 +1,21 @@
+//! `impl_non_fungible_token_core!` with the gas reserved by `nft_transfer_call`.
+
+use near_contract_standards::non_fungible_token::{
+    NftTransferCallGas, NonFungibleToken, Token, TokenId,
+};
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::{near_bindgen, AccountId, Gas, PanicOnDefault, PromiseOrValue};
+
+#[near_bindgen]
+#[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+pub struct Contract {
+    tokens: NonFungibleToken,
+}
+
+near_contract_standards::impl_non_fungible_token_core!(
+    Contract,
+    tokens,
+    [gas = NftTransferCallGas { resolve_transfer: Gas(10 * Gas::ONE_TERA.0), ..NftTransferCallGas::DEFAULT }]
+);
+
+fn main() {}
@@ -0,0 +1,16 @@
+//! A token field of the wrong type is reported at the macro invocation.
+
+use near_contract_standards::non_fungible_token::NonFungibleToken;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::{near_bindgen, PanicOnDefault};
+
+#[near_bindgen]
+#[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+pub struct Contract {
+    tokens: NonFungibleToken,
+}
+
+near_contract_standards::impl_fungible_token_treasury!(Contract, tokens);
+
+fn main() {}
@@ -0,0 +1,178 @@
+//! Metadata of the source code of a contract, as described in
+//! [NEP-330](https://nomicon.io/Standards/SourceMetadata).
+//!
+//! The metadata is returned by the `contract_source_metadata` view method, which
+//! [`impl_contract_source_metadata!`](crate::impl_contract_source_metadata) exposes either from a
+//! `LazyOption` field, which the owner can update, or from an expression like
+//! [`contract_source_metadata!`](crate::contract_source_metadata), built when the contract is
+//! compiled.
+
+use crate::standards::Standard;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+
+/// A standard implemented by a contract, and the version of the standard. The owned counterpart
+/// of [`Standard`], to be stored in the state of a contract.
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StandardId {
+    pub standard: String,
+    pub version: String,
+}
+
+impl From<Standard<'_>> for StandardId {
+    fn from(standard: Standard<'_>) -> Self {
+        Self { standard: standard.standard.to_string(), version: standard.version.to_string() }
+    }
+}
+
+/// The source metadata of a contract.
+#[derive(
+    BorshDeserialize, BorshSerialize, Deserialize, Serialize, Debug, Clone, Default, PartialEq, Eq,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ContractSourceMetadata {
+    /// Version of the source code, e.g. a version number or a commit hash.
+    pub version: Option<String>,
+    /// Link to the source code, e.g. a repository or an IPFS CID.
+    pub link: Option<String>,
+    /// Standards implemented by the contract.
+    pub standards: Vec<StandardId>,
+}
+
+pub trait ContractSourceMetadataTrait {
+    fn contract_source_metadata(&self) -> ContractSourceMetadata;
+}
+
+/// Builds the [`ContractSourceMetadata`] of the crate being compiled: its version is the
+/// `CARGO_PKG_VERSION` of the crate, and its standards are the ones registered by the `impl_*`
+/// macros, see [`standards_manifest!`](crate::standards_manifest!). The link is optional.
+///
+/// This must be used in the module in which the macros were invoked.
+///
+/// # Examples
+/// ```ignore
+/// near_contract_standards::impl_contract_source_metadata!(
+///     Contract,
+///     const near_contract_standards::contract_source_metadata!("https://github.com/near/example")
+/// );
+/// ```
+#[macro_export]
+macro_rules! contract_source_metadata {
+    () => {
+        $crate::contract_metadata::ContractSourceMetadata {
+            version: Some(env!("CARGO_PKG_VERSION").to_string()),
+            link: None,
+            standards: $crate::standards_manifest!().into_iter().map(Into::into).collect(),
+        }
+    };
+    ($link: expr) => {
+        $crate::contract_metadata::ContractSourceMetadata {
+            link: Some(String::from($link)),
+            ..$crate::contract_source_metadata!()
+        }
+    };
+}
+
+/// The `contract_source_metadata` view method of [NEP-330], registering the standard. Takes the
+/// name of the contract struct and either:
+///
+/// - The inner field holding the metadata, a `LazyOption<ContractSourceMetadata>`. The view returns
+///   the default metadata until it's set, and `set_contract_source_metadata` replaces it. Only the
+///   owner can call it, checked by the `assert_owner` method of the contract, like the one of
+///   [`impl_ownable!`](crate::impl_ownable).
+/// - `const` followed by an expression building the metadata, like
+///   [`contract_source_metadata!`](crate::contract_source_metadata).
+///
+/// [NEP-330]: https://nomicon.io/Standards/SourceMetadata
+#[macro_export]
+macro_rules! impl_contract_source_metadata {
+    ($contract: ident, const $metadata: expr) => {
+        use $crate::contract_metadata::ContractSourceMetadataTrait;
+        $crate::__register_standard!(nep330);
+
+        #[near_bindgen]
+        impl ContractSourceMetadataTrait for $contract {
+            fn contract_source_metadata(&self) -> $crate::contract_metadata::ContractSourceMetadata {
+                $metadata
+            }
+        }
+    };
+    ($contract: ident, $metadata: ident) => {
+        $crate::__token_field!(
+            $contract.$metadata: near_sdk::collections::LazyOption<
+                $crate::contract_metadata::ContractSourceMetadata,
+            >,
+            __contract_source_metadata,
+            __contract_source_metadata_mut
+        );
+        use $crate::contract_metadata::ContractSourceMetadataTrait;
+        $crate::__register_standard!(nep330);
+
+        #[near_bindgen]
+        impl ContractSourceMetadataTrait for $contract {
+            fn contract_source_metadata(&self) -> $crate::contract_metadata::ContractSourceMetadata {
+                self.__contract_source_metadata().get().unwrap_or_default()
+            }
+        }
+
+        #[near_bindgen]
+        impl $contract {
+            /// Replaces the source metadata of the contract. Only the owner can call it.
+            pub fn set_contract_source_metadata(
+                &mut self,
+                metadata: $crate::contract_metadata::ContractSourceMetadata,
+            ) {
+                self.assert_owner();
+                self.__contract_source_metadata_mut().set(&metadata);
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::standards::{NEP141, NEP330};
+
+    #[test]
+    fn json() {
+        let metadata = ContractSourceMetadata {
+            version: Some("1.0.0".to_string()),
+            link: Some("https://github.com/near/example".to_string()),
+            standards: vec![NEP141.into(), NEP330.into()],
+        };
+        let json = r#"{"version":"1.0.0","link":"https://github.com/near/example","standards":[{"standard":"nep141","version":"1.0.0"},{"standard":"nep330","version":"1.0.0"}]}"#;
+        assert_eq!(serde_json::to_string(&metadata).unwrap(), json);
+        assert_eq!(serde_json::from_str::<ContractSourceMetadata>(json).unwrap(), metadata);
+    }
+
+    #[test]
+    fn json_without_version_and_link() {
+        assert_eq!(
+            serde_json::to_string(&ContractSourceMetadata::default()).unwrap(),
+            r#"{"version":null,"link":null,"standards":[]}"#
+        );
+    }
+
+    mod contract {
+        crate::__register_standard!(nep141);
+        crate::__register_standard!(nep330);
+
+        pub fn metadata() -> super::ContractSourceMetadata {
+            crate::contract_source_metadata!("https://github.com/near/example")
+        }
+    }
+
+    #[test]
+    fn package_metadata() {
+        assert_eq!(
+            contract::metadata(),
+            ContractSourceMetadata {
+                version: Some(env!("CARGO_PKG_VERSION").to_string()),
+                link: Some("https://github.com/near/example".to_string()),
+                standards: vec![NEP141.into(), NEP330.into()],
+            }
+        );
+    }
+}
@@ -1,29 +1,463 @@
+//! Events of the standards, in the events format (nep-297):
+//! <https://github.com/near/NEPs/blob/master/specs/Standards/EventsFormat.md>
+//!
+//! Contracts emit them through the borrowed data types of
+//! [`fungible_token::events`](crate::fungible_token::events),
+//! [`non_fungible_token::events`](crate::non_fungible_token::events) and
+//! [`multi_token::events`](crate::multi_token::events). Off-chain tools, such as
+//! indexers, can parse the logs back into an owned [`NearEvent`](crate::event::NearEvent) with
+//! [`NearEvent::from_log`](crate::event::NearEvent::from_log).
+//!
+//! Contracts defining their own standard implement [`NearEventData`](crate::event::NearEventData)
+//! for the data of their events and emit them with [`emit_event`](crate::event::emit_event) or
+//! [`emit_events`](crate::event::emit_events).
+
+use crate::fungible_token::events::Nep141Event;
+use crate::multi_token::events::Nep245Event;
+use crate::non_fungible_token::events::{
+    Nep171Event, Nep171EventKind, NftContractMetadataUpdateData, NftMetadataUpdateData,
+};
 use near_sdk::env;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// The prefix of the logs holding an event.
+pub const EVENT_JSON_PREFIX: &str = "EVENT_JSON:";
+
+/// Versions of each standard's events that can be parsed.
+const SUPPORTED_VERSIONS: &[(&str, &[&str])] =
+    &[("nep141", &["1.0.0"]), ("nep171", &["1.0.0", "1.1.0"]), ("nep245", &["1.0.0"])];
+
+/// The data of an event of a standard. Implement it to emit the events of a custom standard with
+/// [`emit_event`] or [`emit_events`].
+///
+/// # Examples
+/// ```
+/// use near_contract_standards::event::{emit_event, NearEventData};
+/// use near_sdk::serde::Serialize;
+/// use near_sdk::AccountId;
+///
+/// #[derive(Serialize)]
+/// #[serde(crate = "near_sdk::serde")]
+/// struct Listed<'a> {
+///     seller_id: &'a AccountId,
+///     token_id: &'a str,
+/// }
+///
+/// impl NearEventData for Listed<'_> {
+///     const STANDARD: &'static str = "marketplace";
+///     const VERSION: &'static str = "1.0.0";
+///     const EVENT: &'static str = "listed";
+/// }
+///
+/// let seller_id: AccountId = "alice".parse().unwrap();
+/// emit_event(&Listed { seller_id: &seller_id, token_id: "1" });
+/// ```
+pub trait NearEventData: Serialize {
+    /// Name of the standard, such as `nep171`.
+    const STANDARD: &'static str;
+    /// Version of the standard, such as `1.0.0`.
+    const VERSION: &'static str;
+    /// Name of the event, such as `nft_mint`.
+    const EVENT: &'static str;
+}
+
+#[derive(Serialize)]
+struct EventEnvelope<'a, T> {
+    standard: &'static str,
+    version: &'static str,
+    event: &'static str,
+    data: &'a [T],
+}
 
-#[derive(Serialize, Debug)]
+/// Returns the `EVENT_JSON:` log of the events `data`.
+pub fn event_log<T: NearEventData>(data: &[T]) -> String {
+    let envelope =
+        EventEnvelope { standard: T::STANDARD, version: T::VERSION, event: T::EVENT, data };
+    // Events cannot fail to serialize so fine to panic on error
+    #[allow(clippy::redundant_closure)]
+    let json = serde_json::to_string(&envelope).ok().unwrap_or_else(|| env::abort());
+    format!("{}{}", EVENT_JSON_PREFIX, json)
+}
+
+/// Logs an event holding `data`, through [`env::log_str`](near_sdk::env::log_str).
+pub fn emit_event<T: NearEventData>(data: &T) {
+    emit_events(std::slice::from_ref(data))
+}
+
+/// Logs a single event holding every element of `data`, through
+/// [`env::log_str`](near_sdk::env::log_str).
+pub fn emit_events<T: NearEventData>(data: &[T]) {
+    env::log_str(&event_log(data));
+}
+
+/// An event of one of the standards, owning its data.
+///
+/// # Examples
+/// ```
+/// use near_contract_standards::event::NearEvent;
+/// use near_contract_standards::fungible_token::events::Nep141EventKind;
+///
+/// let log = r#"EVENT_JSON:{"standard":"nep141","version":"1.0.0","event":"ft_mint","data":[{"owner_id":"bob","amount":"100"}]}"#;
+/// match NearEvent::from_log(log).unwrap() {
+///     NearEvent::Nep141(event) => match event.event_kind {
+///         Nep141EventKind::FtMint(mints) => assert_eq!(mints[0].amount.0, 100),
+///         _ => unreachable!(),
+///     },
+///     _ => unreachable!(),
+/// }
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(tag = "standard")]
-#[must_use = "don't forget to `.emit()` this event"]
 #[serde(rename_all = "snake_case")]
-pub(crate) enum NearEvent<'a> {
-    Nep171(crate::non_fungible_token::events::Nep171Event<'a>),
-    Nep141(crate::fungible_token::events::Nep141Event<'a>),
+pub enum NearEvent {
+    Nep171(Nep171Event),
+    Nep141(Nep141Event),
+    Nep245(Nep245Event),
 }
 
-impl<'a> NearEvent<'a> {
-    fn to_json_string(&self) -> String {
-        // Events cannot fail to serialize so fine to panic on error
-        #[allow(clippy::redundant_closure)]
-        serde_json::to_string(self).ok().unwrap_or_else(|| env::abort())
+impl NearEvent {
+    /// Parses an `EVENT_JSON:` log of one of the standards.
+    pub fn from_log(log: &str) -> Result<Self, EventParseError> {
+        let json = log.strip_prefix(EVENT_JSON_PREFIX).ok_or(EventParseError::MissingPrefix)?;
+        let value: serde_json::Value = serde_json::from_str(json)
+            .map_err(|err| EventParseError::InvalidJson(err.to_string()))?;
+        let field = |name: &str| match value.get(name) {
+            Some(serde_json::Value::String(field)) => Ok(field.clone()),
+            _ => Err(EventParseError::InvalidJson(format!("missing string field `{}`", name))),
+        };
+        let standard = field("standard")?;
+        let version = field("version")?;
+        let versions = match SUPPORTED_VERSIONS.iter().find(|(name, _)| *name == standard) {
+            Some((_, versions)) => versions,
+            None => return Err(EventParseError::UnknownStandard(standard)),
+        };
+        if !versions.contains(&version.as_str()) {
+            return Err(EventParseError::UnsupportedVersion { standard, version });
+        }
+        let event: Self = serde_json::from_value(value)
+            .map_err(|err| EventParseError::InvalidJson(err.to_string()))?;
+        // Events introduced by a version can't be logged with an older one.
+        if let Self::Nep171(Nep171Event { event_kind, .. }) = &event {
+            if version.as_str() < event_kind.version() {
+                return Err(EventParseError::UnsupportedVersion { standard, version });
+            }
+        }
+        Ok(event)
+    }
+
+    /// An `nft_metadata_update` event for `token_ids`.
+    pub fn nft_metadata_update(token_ids: Vec<String>, memo: Option<String>) -> Self {
+        Self::nep171(Nep171EventKind::NftMetadataUpdate(vec![NftMetadataUpdateData {
+            token_ids,
+            memo,
+        }]))
     }
 
-    fn to_json_event_string(&self) -> String {
-        format!("EVENT_JSON:{}", self.to_json_string())
+    /// A `contract_metadata_update` event of the nep171 standard.
+    pub fn nft_contract_metadata_update(memo: Option<String>) -> Self {
+        Self::nep171(Nep171EventKind::NftContractMetadataUpdate(vec![
+            NftContractMetadataUpdateData { memo },
+        ]))
+    }
+
+    fn nep171(event_kind: Nep171EventKind) -> Self {
+        Self::Nep171(Nep171Event { version: event_kind.version().to_string(), event_kind })
+    }
+
+    /// Returns the log of the event, as it is emitted by the contracts.
+    pub fn to_log(&self) -> String {
+        // The owned events serialize like the emitted ones, which cannot fail.
+        format!("{}{}", EVENT_JSON_PREFIX, serde_json::to_string(self).unwrap())
+    }
+}
+
+/// Error returned by [`NearEvent::from_log`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EventParseError {
+    /// The log doesn't start with `EVENT_JSON:`, so it isn't an event.
+    MissingPrefix,
+    /// The event isn't valid JSON, or doesn't match the data of its standard.
+    InvalidJson(String),
+    /// The event belongs to a standard other than the ones of this crate.
+    UnknownStandard(String),
+    /// The version of the standard's events isn't supported.
+    UnsupportedVersion { standard: String, version: String },
+}
+
+impl fmt::Display for EventParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingPrefix => write!(f, "The log doesn't start with {}", EVENT_JSON_PREFIX),
+            Self::InvalidJson(err) => write!(f, "Invalid event: {}", err),
+            Self::UnknownStandard(standard) => write!(f, "Unknown standard {}", standard),
+            Self::UnsupportedVersion { standard, version } => {
+                write!(f, "Unsupported version {} of {} events", version, standard)
+            }
+        }
+    }
+}
+
+impl std::error::Error for EventParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fungible_token::events::{
+        FtBurn, FtBurnData, FtMint, FtMintData, FtTransfer, FtTransferData, Nep141EventKind,
+    };
+    use crate::multi_token::events::{
+        MtBurn, MtBurnData, MtTransfer, MtTransferData, Nep245EventKind,
+    };
+    use crate::non_fungible_token::events::{
+        NftBurn, NftBurnData, NftContractMetadataUpdate, NftMetadataUpdate, NftMint, NftMintData,
+        NftTransfer, NftTransferData,
+    };
+    use near_sdk::json_types::U128;
+    use near_sdk::test_utils::{accounts, get_logs};
+    use near_sdk::AccountId;
+
+    fn nep141(event_kind: Nep141EventKind) -> NearEvent {
+        NearEvent::Nep141(Nep141Event { version: "1.0.0".to_string(), event_kind })
+    }
+
+    fn nep171(event_kind: Nep171EventKind) -> NearEvent {
+        NearEvent::Nep171(Nep171Event { version: "1.0.0".to_string(), event_kind })
+    }
+
+    /// Checks that the last log parses to `expected`, and that `expected` serializes to the log.
+    fn assert_round_trip(expected: NearEvent) {
+        let log = get_logs().pop().unwrap();
+        assert_eq!(NearEvent::from_log(&log), Ok(expected.clone()));
+        assert_eq!(expected.to_log(), log);
+    }
+
+    #[test]
+    fn ft_events() {
+        let (alice, bob) = (accounts(0), accounts(1));
+
+        FtMint::emit_many(&[
+            FtMint { owner_id: &alice, amount: &U128(100), memo: None },
+            FtMint { owner_id: &bob, amount: &U128(200), memo: Some("memo") },
+        ]);
+        assert_round_trip(nep141(Nep141EventKind::FtMint(vec![
+            FtMintData { owner_id: alice.clone(), amount: U128(100), memo: None },
+            FtMintData { owner_id: bob.clone(), amount: U128(200), memo: Some("memo".to_string()) },
+        ])));
+
+        FtTransfer { old_owner_id: &alice, new_owner_id: &bob, amount: &U128(5), memo: None }
+            .emit();
+        assert_round_trip(nep141(Nep141EventKind::FtTransfer(vec![FtTransferData {
+            old_owner_id: alice.clone(),
+            new_owner_id: bob.clone(),
+            amount: U128(5),
+            memo: None,
+        }])));
+
+        FtBurn { owner_id: &bob, amount: &U128(1), memo: Some("burn") }.emit();
+        assert_round_trip(nep141(Nep141EventKind::FtBurn(vec![FtBurnData {
+            owner_id: bob,
+            amount: U128(1),
+            memo: Some("burn".to_string()),
+        }])));
+    }
+
+    #[test]
+    fn mt_events() {
+        let (alice, bob) = (accounts(0), accounts(1));
+        let nep245 = |event_kind| {
+            NearEvent::Nep245(Nep245Event { version: "1.0.0".to_string(), event_kind })
+        };
+
+        MtTransfer {
+            old_owner_id: &alice,
+            new_owner_id: &bob,
+            token_ids: &["0", "1"],
+            amounts: &[U128(5), U128(1)],
+            authorized_id: Some(&bob),
+            memo: None,
+        }
+        .emit();
+        assert_round_trip(nep245(Nep245EventKind::MtTransfer(vec![MtTransferData {
+            old_owner_id: alice.clone(),
+            new_owner_id: bob.clone(),
+            token_ids: vec!["0".to_string(), "1".to_string()],
+            amounts: vec![U128(5), U128(1)],
+            authorized_id: Some(bob.clone()),
+            memo: None,
+        }])));
+
+        MtBurn {
+            owner_id: &alice,
+            token_ids: &["0"],
+            amounts: &[U128(2)],
+            authorized_id: None,
+            memo: Some("burn"),
+        }
+        .emit();
+        assert_round_trip(nep245(Nep245EventKind::MtBurn(vec![MtBurnData {
+            owner_id: alice,
+            token_ids: vec!["0".to_string()],
+            amounts: vec![U128(2)],
+            authorized_id: None,
+            memo: Some("burn".to_string()),
+        }])));
+    }
+
+    #[test]
+    fn nft_events() {
+        let (alice, bob) = (accounts(0), accounts(1));
+
+        NftMint { owner_id: &alice, token_ids: &["0", "1"], memo: None }.emit();
+        assert_round_trip(nep171(Nep171EventKind::NftMint(vec![NftMintData {
+            owner_id: alice.clone(),
+            token_ids: vec!["0".to_string(), "1".to_string()],
+            memo: None,
+        }])));
+
+        NftTransfer::emit_many(&[
+            NftTransfer {
+                old_owner_id: &alice,
+                new_owner_id: &bob,
+                token_ids: &["0"],
+                authorized_id: Some(&bob),
+                memo: Some("memo"),
+            },
+            NftTransfer {
+                old_owner_id: &bob,
+                new_owner_id: &alice,
+                token_ids: &["1"],
+                authorized_id: None,
+                memo: None,
+            },
+        ]);
+        assert_round_trip(nep171(Nep171EventKind::NftTransfer(vec![
+            NftTransferData {
+                old_owner_id: alice.clone(),
+                new_owner_id: bob.clone(),
+                token_ids: vec!["0".to_string()],
+                authorized_id: Some(bob.clone()),
+                memo: Some("memo".to_string()),
+            },
+            NftTransferData {
+                old_owner_id: bob.clone(),
+                new_owner_id: alice.clone(),
+                token_ids: vec!["1".to_string()],
+                authorized_id: None,
+                memo: None,
+            },
+        ])));
+
+        NftBurn { owner_id: &alice, token_ids: &["1"], authorized_id: Some(&bob), memo: None }
+            .emit();
+        assert_round_trip(nep171(Nep171EventKind::NftBurn(vec![NftBurnData {
+            owner_id: alice,
+            token_ids: vec!["1".to_string()],
+            authorized_id: Some(bob),
+            memo: None,
+        }])));
+    }
+
+    #[derive(Serialize)]
+    struct Listed<'a> {
+        seller_id: &'a AccountId,
+        token_id: &'a str,
+        price: U128,
+    }
+
+    impl NearEventData for Listed<'_> {
+        const STANDARD: &'static str = "marketplace";
+        const VERSION: &'static str = "1.2.0";
+        const EVENT: &'static str = "listed";
+    }
+
+    #[test]
+    fn custom_standard() {
+        let seller_id = accounts(0);
+        emit_event(&Listed { seller_id: &seller_id, token_id: "1", price: U128(10) });
+        emit_events(&[
+            Listed { seller_id: &seller_id, token_id: "2", price: U128(20) },
+            Listed { seller_id: &seller_id, token_id: "3", price: U128(30) },
+        ]);
+        assert_eq!(
+            get_logs(),
+            vec![
+                r#"EVENT_JSON:{"standard":"marketplace","version":"1.2.0","event":"listed","data":[{"seller_id":"alice","token_id":"1","price":"10"}]}"#,
+                r#"EVENT_JSON:{"standard":"marketplace","version":"1.2.0","event":"listed","data":[{"seller_id":"alice","token_id":"2","price":"20"},{"seller_id":"alice","token_id":"3","price":"30"}]}"#,
+            ]
+        );
+        // The log isn't one of the standards of this crate.
+        assert_eq!(
+            NearEvent::from_log(&get_logs()[0]),
+            Err(EventParseError::UnknownStandard("marketplace".to_string()))
+        );
+    }
+
+    #[test]
+    fn nft_metadata_update_events() {
+        NftMetadataUpdate { token_ids: &["0", "1"], memo: Some("reveal") }.emit();
+        assert_round_trip(NearEvent::nft_metadata_update(
+            vec!["0".to_string(), "1".to_string()],
+            Some("reveal".to_string()),
+        ));
+
+        NftContractMetadataUpdate { memo: None }.emit();
+        assert_round_trip(NearEvent::nft_contract_metadata_update(None));
+
+        // Mint events are still logged with the first version, and can be parsed at the new one.
+        let log = r#"EVENT_JSON:{"standard":"nep171","version":"1.1.0","event":"nft_mint","data":[{"owner_id":"alice","token_ids":["0"]}]}"#;
+        assert!(NearEvent::from_log(log).is_ok());
+        assert_eq!(
+            NearEvent::from_log(
+                r#"EVENT_JSON:{"standard":"nep171","version":"1.0.0","event":"nft_metadata_update","data":[{"token_ids":["0"]}]}"#
+            ),
+            Err(EventParseError::UnsupportedVersion {
+                standard: "nep171".to_string(),
+                version: "1.0.0".to_string()
+            })
+        );
     }
 
-    /// Logs the event to the host. This is required to ensure that the event is triggered
-    /// and to consume the event.
-    pub(crate) fn emit(self) {
-        near_sdk::env::log_str(&self.to_json_event_string());
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            NearEvent::from_log(r#"{"standard":"nep141","version":"1.0.0"}"#),
+            Err(EventParseError::MissingPrefix)
+        );
+        assert!(matches!(
+            NearEvent::from_log("EVENT_JSON:{"),
+            Err(EventParseError::InvalidJson(_))
+        ));
+        assert!(matches!(
+            NearEvent::from_log(r#"EVENT_JSON:{"version":"1.0.0"}"#),
+            Err(EventParseError::InvalidJson(_))
+        ));
+        assert_eq!(
+            NearEvent::from_log(
+                r#"EVENT_JSON:{"standard":"nep999","version":"1.0.0","event":"x","data":[]}"#
+            ),
+            Err(EventParseError::UnknownStandard("nep999".to_string()))
+        );
+        assert_eq!(
+            NearEvent::from_log(
+                r#"EVENT_JSON:{"standard":"nep171","version":"2.0.0","event":"nft_mint","data":[]}"#
+            ),
+            Err(EventParseError::UnsupportedVersion {
+                standard: "nep171".to_string(),
+                version: "2.0.0".to_string()
+            })
+        );
+        assert!(matches!(
+            NearEvent::from_log(
+                r#"EVENT_JSON:{"standard":"nep171","version":"1.0.0","event":"nft_melt","data":[]}"#
+            ),
+            Err(EventParseError::InvalidJson(_))
+        ));
+        assert!(matches!(
+            NearEvent::from_log(
+                r#"EVENT_JSON:{"standard":"nep141","version":"1.0.0","event":"ft_mint","data":[{"owner_id":"bob"}]}"#
+            ),
+            Err(EventParseError::InvalidJson(_))
+        ));
     }
 }
@@ -0,0 +1,334 @@
+//! Allowances of fungible tokens, for contracts ported from chains where a spender moves the
+//! tokens of an owner with an approve and transfer-from flow. NEP-141 prefers `ft_transfer_call`,
+//! so this extension is opt-in.
+//!
+//! The allowances are kept in a [`FungibleTokenAllowances`] next to the [`FungibleToken`], under
+//! their own prefix, so adding them doesn't change the storage layout of the token. The owner
+//! pays for the storage of each allowance when approving, and gets it back once the allowance is
+//! revoked or used up.
+//!
+//! Approving and revoking emit [`FtApprove`] and [`FtRevoke`] events, in the `ft_allowance`
+//! standard, which isn't part of NEP-141. Transfers emit the usual `ft_transfer` event.
+
+use crate::event::{emit_event, NearEventData};
+use crate::fungible_token::FungibleToken;
+use crate::limits::check_memo;
+use crate::non_fungible_token::assert_at_least_one_yocto;
+use crate::utils::{measure_storage, refund_storage_delta, refund_storage_delta_to_account};
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::LookupMap;
+use near_sdk::json_types::U128;
+use near_sdk::{assert_one_yocto, env, require, AccountId, Balance, IntoStorageKey};
+use serde::Serialize;
+
+/// Methods of the allowance extension, implemented by the contract with
+/// [`impl_fungible_token_allowance!`](crate::impl_fungible_token_allowance).
+pub trait FungibleTokenAllowance {
+    /// Allows `spender_id` to transfer up to `amount` of the tokens of the caller, replacing the
+    /// previous allowance. An amount of 0 revokes the allowance.
+    ///
+    /// Requirements:
+    /// * Caller of the method must attach a deposit of at least 1 yoctoⓃ, covering the storage
+    ///   of a new allowance. The rest of the deposit, and the storage of a revoked allowance, is
+    ///   refunded.
+    /// * The caller must be registered, and can't approve itself.
+    fn ft_approve(&mut self, spender_id: AccountId, amount: U128);
+
+    /// Returns the amount `spender_id` can still transfer on behalf of `owner_id`.
+    fn ft_allowance(&self, owner_id: AccountId, spender_id: AccountId) -> U128;
+
+    /// Transfers `amount` of the tokens of `owner_id` to `receiver_id`, on behalf of the owner,
+    /// decreasing the allowance of the caller. The owner can call it without an allowance.
+    ///
+    /// Requirements:
+    /// * Caller of the method must attach a deposit of 1 yoctoⓃ for security purposes
+    /// * The allowance of the caller must cover the amount
+    fn ft_transfer_from(
+        &mut self,
+        owner_id: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+    );
+}
+
+/// Data to log for an FT approve event. To log this event, call [`.emit()`](FtApprove::emit).
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+pub struct FtApprove<'a> {
+    pub owner_id: &'a AccountId,
+    pub spender_id: &'a AccountId,
+    pub amount: &'a U128,
+}
+
+impl FtApprove<'_> {
+    /// Logs the event to the host.
+    pub fn emit(self) {
+        emit_event(&self)
+    }
+}
+
+impl NearEventData for FtApprove<'_> {
+    const STANDARD: &'static str = "ft_allowance";
+    const VERSION: &'static str = "1.0.0";
+    const EVENT: &'static str = "ft_approve";
+}
+
+/// Data to log for an FT revoke event, when an allowance is set to 0. To log this event, call
+/// [`.emit()`](FtRevoke::emit).
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+pub struct FtRevoke<'a> {
+    pub owner_id: &'a AccountId,
+    pub spender_id: &'a AccountId,
+}
+
+impl FtRevoke<'_> {
+    /// Logs the event to the host.
+    pub fn emit(self) {
+        emit_event(&self)
+    }
+}
+
+impl NearEventData for FtRevoke<'_> {
+    const STANDARD: &'static str = "ft_allowance";
+    const VERSION: &'static str = "1.0.0";
+    const EVENT: &'static str = "ft_revoke";
+}
+
+/// Allowances given by the owners of a [`FungibleToken`] to spenders.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct FungibleTokenAllowances {
+    /// (Owner, spender) -> Amount the spender can transfer.
+    pub allowances: LookupMap<(AccountId, AccountId), Balance>,
+}
+
+impl FungibleTokenAllowances {
+    /// The prefix must differ from those of the token.
+    pub fn new<S>(prefix: S) -> Self
+    where
+        S: IntoStorageKey,
+    {
+        Self { allowances: LookupMap::new(prefix) }
+    }
+
+    /// Returns the amount `spender_id` can transfer on behalf of `owner_id`, 0 without an
+    /// allowance.
+    pub fn allowance(&self, owner_id: &AccountId, spender_id: &AccountId) -> Balance {
+        self.allowances.get(&(owner_id.clone(), spender_id.clone())).unwrap_or(0)
+    }
+
+    /// Sets the allowance of `spender_id`, removing it if `amount` is 0. Returns the change of
+    /// storage usage.
+    fn internal_set_allowance(
+        &mut self,
+        owner_id: &AccountId,
+        spender_id: &AccountId,
+        amount: Balance,
+    ) -> i64 {
+        let key = (owner_id.clone(), spender_id.clone());
+        let ((), storage_delta) = measure_storage(|| {
+            if amount == 0 {
+                self.allowances.remove(&key);
+            } else {
+                self.allowances.insert(&key, &amount);
+            }
+        });
+        storage_delta
+    }
+
+    /// `ft_approve` for the allowances of `token`.
+    pub fn ft_approve(&mut self, token: &FungibleToken, spender_id: AccountId, amount: U128) {
+        assert_at_least_one_yocto();
+        let owner_id = env::predecessor_account_id();
+        require!(owner_id != spender_id, "The owner can't approve itself");
+        token.internal_unwrap_balance_of(&owner_id);
+
+        let storage_delta = self.internal_set_allowance(&owner_id, &spender_id, amount.0);
+        if amount.0 == 0 {
+            FtRevoke { owner_id: &owner_id, spender_id: &spender_id }.emit();
+        } else {
+            FtApprove { owner_id: &owner_id, spender_id: &spender_id, amount: &amount }.emit();
+        }
+        refund_storage_delta(storage_delta);
+    }
+
+    /// `ft_transfer_from` for the allowances of `token`. The storage of an allowance which is used
+    /// up is refunded to the owner.
+    pub fn ft_transfer_from(
+        &mut self,
+        token: &mut FungibleToken,
+        owner_id: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+    ) {
+        assert_one_yocto();
+        let spender_id = env::predecessor_account_id();
+        if spender_id != owner_id {
+            let allowance = self.allowance(&owner_id, &spender_id);
+            require!(
+                allowance >= amount.0,
+                format!(
+                    "The allowance of {} is {}, which is less than the amount {}",
+                    spender_id, allowance, amount.0
+                )
+            );
+            let storage_delta =
+                self.internal_set_allowance(&owner_id, &spender_id, allowance - amount.0);
+            if storage_delta < 0 {
+                refund_storage_delta_to_account(storage_delta, owner_id.clone());
+            }
+        }
+        token.internal_transfer(&owner_id, &receiver_id, amount.0, check_memo(memo));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fungible_token::core::FungibleTokenCore;
+    use near_sdk::mock::VmAction;
+    use near_sdk::test_utils::{accounts, get_created_receipts, get_logs, VMContextBuilder};
+    use near_sdk::{testing_env, ONE_NEAR, ONE_YOCTO};
+
+    fn set_context(predecessor: AccountId, attached_deposit: Balance) {
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(accounts(0))
+            .predecessor_account_id(predecessor)
+            .attached_deposit(attached_deposit)
+            .build());
+    }
+
+    /// A token where `accounts(1)` holds 100 and approved `accounts(2)` for 40.
+    fn approved() -> (FungibleToken, FungibleTokenAllowances) {
+        set_context(accounts(1), ONE_NEAR);
+        let mut token = FungibleToken::new(b"t".to_vec());
+        token.internal_register_account(&accounts(1));
+        token.internal_register_account(&accounts(3));
+        token.internal_deposit(&accounts(1), 100);
+        let mut allowances = FungibleTokenAllowances::new(b"a".to_vec());
+        allowances.ft_approve(&token, accounts(2), U128(40));
+        (token, allowances)
+    }
+
+    fn memo() -> Option<String> {
+        Some("rent".to_string())
+    }
+
+    /// Returns the amounts transferred by the receipts of the last call, with their receiver.
+    fn transfers() -> Vec<(AccountId, Balance)> {
+        get_created_receipts()
+            .into_iter()
+            .filter_map(|receipt| match receipt.actions[..] {
+                [VmAction::Transfer { deposit }] => Some((receipt.receiver_id, deposit)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn approve_emits_event() {
+        let (_, allowances) = approved();
+        assert_eq!(allowances.allowance(&accounts(1), &accounts(2)), 40);
+        assert_eq!(
+            get_logs(),
+            [
+                r#"EVENT_JSON:{"standard":"ft_allowance","version":"1.0.0","event":"ft_approve","data":[{"owner_id":"bob","spender_id":"charlie","amount":"40"}]}"#
+            ]
+        );
+        // The deposit above the storage of the allowance is refunded.
+        let refund = transfers();
+        assert_eq!(refund.len(), 1);
+        assert!(refund[0].1 < ONE_NEAR);
+    }
+
+    #[test]
+    fn transfer_from_decreases_allowance() {
+        let (mut token, mut allowances) = approved();
+        set_context(accounts(2), ONE_YOCTO);
+        allowances.ft_transfer_from(&mut token, accounts(1), accounts(3), U128(15), memo());
+        assert_eq!(allowances.allowance(&accounts(1), &accounts(2)), 25);
+        assert_eq!(token.ft_balance_of(accounts(1)), U128(85));
+        assert_eq!(token.ft_balance_of(accounts(3)), U128(15));
+        assert_eq!(
+            get_logs(),
+            [
+                r#"EVENT_JSON:{"standard":"nep141","version":"1.0.0","event":"ft_transfer","data":[{"old_owner_id":"bob","new_owner_id":"danny","amount":"15","memo":"rent"}]}"#
+            ]
+        );
+        assert!(transfers().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "The allowance of charlie is 40, which is less than the amount 41")]
+    fn transfer_from_over_allowance() {
+        let (mut token, mut allowances) = approved();
+        set_context(accounts(2), ONE_YOCTO);
+        allowances.ft_transfer_from(&mut token, accounts(1), accounts(3), U128(41), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "The allowance of danny is 0, which is less than the amount 1")]
+    fn transfer_from_without_allowance() {
+        let (mut token, mut allowances) = approved();
+        set_context(accounts(3), ONE_YOCTO);
+        allowances.ft_transfer_from(&mut token, accounts(1), accounts(3), U128(1), None);
+    }
+
+    #[test]
+    fn used_up_allowance_releases_storage() {
+        let (mut token, mut allowances) = approved();
+        set_context(accounts(2), ONE_YOCTO);
+        let ((), storage_delta) = measure_storage(|| {
+            allowances.ft_transfer_from(&mut token, accounts(1), accounts(3), U128(40), None)
+        });
+        assert!(storage_delta < 0);
+        assert_eq!(allowances.allowance(&accounts(1), &accounts(2)), 0);
+        let freed = Balance::from(storage_delta.unsigned_abs()) * env::storage_byte_cost();
+        assert_eq!(transfers(), [(accounts(1), freed)]);
+    }
+
+    #[test]
+    fn approve_zero_revokes() {
+        let (token, mut allowances) = approved();
+        set_context(accounts(1), ONE_YOCTO);
+        let ((), storage_delta) =
+            measure_storage(|| allowances.ft_approve(&token, accounts(2), U128(0)));
+        assert!(storage_delta < 0);
+        assert_eq!(allowances.allowance(&accounts(1), &accounts(2)), 0);
+        assert_eq!(
+            get_logs(),
+            [
+                r#"EVENT_JSON:{"standard":"ft_allowance","version":"1.0.0","event":"ft_revoke","data":[{"owner_id":"bob","spender_id":"charlie"}]}"#
+            ]
+        );
+        let freed = Balance::from(storage_delta.unsigned_abs()) * env::storage_byte_cost();
+        assert_eq!(transfers(), [(accounts(1), freed)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "The owner can't approve itself")]
+    fn approve_self() {
+        let (token, mut allowances) = approved();
+        set_context(accounts(1), ONE_YOCTO);
+        allowances.ft_approve(&token, accounts(1), U128(10));
+    }
+
+    #[test]
+    fn owner_transfers_without_allowance() {
+        let (mut token, mut allowances) = approved();
+        set_context(accounts(1), ONE_YOCTO);
+        allowances.ft_transfer_from(&mut token, accounts(1), accounts(3), U128(60), None);
+        assert_eq!(token.ft_balance_of(accounts(3)), U128(60));
+        assert_eq!(allowances.allowance(&accounts(1), &accounts(2)), 40);
+    }
+
+    #[test]
+    #[should_panic(expected = "The account alice is not registered")]
+    fn approve_unregistered() {
+        let (token, mut allowances) = approved();
+        set_context(accounts(0), ONE_NEAR);
+        allowances.ft_approve(&token, accounts(2), U128(10));
+    }
+}
@@ -1,12 +1,14 @@
 use crate::fungible_token::core::FungibleTokenCore;
-use crate::fungible_token::events::FtTransfer;
+use crate::fungible_token::events::{FtBurn, FtMint, FtTransfer};
 use crate::fungible_token::resolver::FungibleTokenResolver;
+use crate::limits::{check_memo, check_msg};
+use crate::storage_management::MAX_ACCOUNT_ID_LEN;
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::collections::LookupMap;
 use near_sdk::json_types::U128;
 use near_sdk::{
-    assert_one_yocto, env, ext_contract, log, require, AccountId, Balance, Gas, IntoStorageKey,
-    PromiseOrValue, PromiseResult, StorageUsage,
+    env, ext_contract, log, require, AccountId, Balance, BorshStorageKey, CallContext, Gas,
+    IntoStorageKey, PromiseOrValue, PromiseResult, StorageUsage,
 };
 
 const GAS_FOR_RESOLVE_TRANSFER: Gas = Gas(5_000_000_000_000);
@@ -14,6 +16,35 @@ const GAS_FOR_FT_TRANSFER_CALL: Gas = Gas(25_000_000_000_000 + GAS_FOR_RESOLVE_T
 
 const NO_DEPOSIT: Balance = 0;
 
+/// Memo of the `ft_burn` event of the tokens `ft_resolve_transfer` can't refund, because the
+/// sender unregistered.
+pub const REFUND_BURN_MEMO: &str = "refund";
+
+/// Gas reserved by `ft_transfer_call`, see [`FungibleToken::ft_transfer_call_with_gas`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FtTransferCallGas {
+    /// Gas attached to the `ft_resolve_transfer` callback. It can't be less than the default,
+    /// which the standard resolver needs to refund the sender.
+    pub resolve_transfer: Gas,
+    /// Gas kept for the transfer itself and the callback. The gas left after this and
+    /// `resolve_transfer` is attached to `ft_on_transfer`.
+    pub transfer_call: Gas,
+}
+
+impl FtTransferCallGas {
+    /// The gas used by [`FungibleToken::ft_transfer_call`].
+    pub const DEFAULT: Self = Self {
+        resolve_transfer: GAS_FOR_RESOLVE_TRANSFER,
+        transfer_call: GAS_FOR_FT_TRANSFER_CALL,
+    };
+}
+
+impl Default for FtTransferCallGas {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
 #[ext_contract(ext_self)]
 trait FungibleTokenResolver {
     fn ft_resolve_transfer(
@@ -74,7 +105,21 @@ pub struct FungibleToken {
     pub account_storage_usage: StorageUsage,
 }
 
+/// Storage keys of [`FungibleToken::new_default`], in the `ft_standard` namespace so that they
+/// can't collide with the storage keys of the contract.
+#[derive(BorshStorageKey, BorshSerialize)]
+#[storage_key(namespace = "ft_standard")]
+pub enum FungibleTokenStorageKey {
+    Accounts = 0,
+}
+
 impl FungibleToken {
+    /// Creates a token storing its accounts under the namespaced
+    /// [`FungibleTokenStorageKey::Accounts`] prefix, so the contract can use any other prefix.
+    pub fn new_default() -> Self {
+        Self::new(FungibleTokenStorageKey::Accounts)
+    }
+
     pub fn new<S>(prefix: S) -> Self
     where
         S: IntoStorageKey,
@@ -87,7 +132,7 @@ impl FungibleToken {
 
     fn measure_account_storage_usage(&mut self) {
         let initial_storage_usage = env::storage_usage();
-        let tmp_account_id = AccountId::new_unchecked("a".repeat(64));
+        let tmp_account_id = AccountId::new_unchecked("a".repeat(MAX_ACCOUNT_ID_LEN));
         self.accounts.insert(&tmp_account_id, &0u128);
         self.account_storage_usage = env::storage_usage() - initial_storage_usage;
         self.accounts.remove(&tmp_account_id);
@@ -128,6 +173,20 @@ impl FungibleToken {
         }
     }
 
+    /// Mints `amount` to `account_id`, which must be registered, and emits an `ft_mint` event.
+    pub fn internal_mint(&mut self, account_id: &AccountId, amount: Balance, memo: Option<&str>) {
+        require!(amount > 0, "The amount should be a positive number");
+        self.internal_deposit(account_id, amount);
+        FtMint { owner_id: account_id, amount: &U128(amount), memo }.emit();
+    }
+
+    /// Burns `amount` from the balance of `account_id` and emits an `ft_burn` event.
+    pub fn internal_burn(&mut self, account_id: &AccountId, amount: Balance, memo: Option<&str>) {
+        require!(amount > 0, "The amount should be a positive number");
+        self.internal_withdraw(account_id, amount);
+        FtBurn { owner_id: account_id, amount: &U128(amount), memo }.emit();
+    }
+
     pub fn internal_transfer(
         &mut self,
         sender_id: &AccountId,
@@ -148,36 +207,50 @@ impl FungibleToken {
         .emit();
     }
 
-    pub fn internal_register_account(&mut self, account_id: &AccountId) {
-        if self.accounts.insert(account_id, &0).is_some() {
-            env::panic_str("The account is already registered");
-        }
-    }
-}
-
-impl FungibleTokenCore for FungibleToken {
-    fn ft_transfer(&mut self, receiver_id: AccountId, amount: U128, memo: Option<String>) {
-        assert_one_yocto();
-        let sender_id = env::predecessor_account_id();
-        let amount: Balance = amount.into();
-        self.internal_transfer(&sender_id, &receiver_id, amount, memo);
+    /// Transfers `amount` from the predecessor of `ctx` to `receiver_id`, checking that exactly
+    /// 1 yoctoNEAR was attached, as `ft_transfer` does. Returns the sender.
+    pub fn internal_transfer_from_context(
+        &mut self,
+        ctx: &CallContext,
+        receiver_id: &AccountId,
+        amount: Balance,
+        memo: Option<String>,
+    ) -> AccountId {
+        ctx.assert_one_yocto();
+        let sender_id = ctx.predecessor_account_id.clone();
+        self.internal_transfer(&sender_id, receiver_id, amount, memo);
+        sender_id
     }
 
-    fn ft_transfer_call(
+    /// `ft_transfer_call` reserving `gas` instead of [`FtTransferCallGas::DEFAULT`], for
+    /// contracts whose resolver does more work than the standard one.
+    ///
+    /// Panics if `gas.resolve_transfer` is less than the default.
+    pub fn ft_transfer_call_with_gas(
         &mut self,
         receiver_id: AccountId,
         amount: U128,
         memo: Option<String>,
         msg: String,
+        gas: FtTransferCallGas,
     ) -> PromiseOrValue<U128> {
-        assert_one_yocto();
+        let ctx = CallContext::from_env();
+        ctx.assert_one_yocto();
+        let memo = check_memo(memo);
+        let msg = check_msg(msg);
         require!(
-            env::prepaid_gas() > GAS_FOR_FT_TRANSFER_CALL + GAS_FOR_RESOLVE_TRANSFER,
-            "More gas is required"
+            gas.resolve_transfer >= GAS_FOR_RESOLVE_TRANSFER,
+            format!(
+                "ft_resolve_transfer needs at least {} gas, {} is reserved",
+                GAS_FOR_RESOLVE_TRANSFER.0, gas.resolve_transfer.0
+            )
         );
-        let sender_id = env::predecessor_account_id();
+        let receiver_gas = crate::remaining_gas_after(&[
+            ("ft_transfer_call", gas.transfer_call),
+            ("ft_resolve_transfer", gas.resolve_transfer),
+        ]);
         let amount: Balance = amount.into();
-        self.internal_transfer(&sender_id, &receiver_id, amount, memo);
+        let sender_id = self.internal_transfer_from_context(&ctx, &receiver_id, amount, memo);
         // Initiating receiver's call and the callback
         ext_fungible_token_receiver::ft_on_transfer(
             sender_id.clone(),
@@ -185,19 +258,44 @@ impl FungibleTokenCore for FungibleToken {
             msg,
             receiver_id.clone(),
             NO_DEPOSIT,
-            env::prepaid_gas() - GAS_FOR_FT_TRANSFER_CALL,
+            receiver_gas,
         )
         .then(ext_self::ft_resolve_transfer(
             sender_id,
             receiver_id,
             amount.into(),
-            env::current_account_id(),
+            ctx.current_account_id,
             NO_DEPOSIT,
-            GAS_FOR_RESOLVE_TRANSFER,
+            gas.resolve_transfer,
         ))
         .into()
     }
 
+    pub fn internal_register_account(&mut self, account_id: &AccountId) {
+        if self.accounts.insert(account_id, &0).is_some() {
+            env::panic_str("The account is already registered");
+        }
+    }
+}
+
+impl FungibleTokenCore for FungibleToken {
+    fn ft_transfer(&mut self, receiver_id: AccountId, amount: U128, memo: Option<String>) {
+        // Only the deposit and the predecessor are checked, reading the whole `CallContext` would
+        // charge for the account IDs it doesn't use.
+        let ctx = CallContext::caller_from_env();
+        self.internal_transfer_from_context(&ctx, &receiver_id, amount.into(), check_memo(memo));
+    }
+
+    fn ft_transfer_call(
+        &mut self,
+        receiver_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+        msg: String,
+    ) -> PromiseOrValue<U128> {
+        self.ft_transfer_call_with_gas(receiver_id, amount, memo, msg, FtTransferCallGas::DEFAULT)
+    }
+
     fn ft_total_supply(&self) -> U128 {
         self.total_supply.into()
     }
@@ -210,6 +308,8 @@ impl FungibleTokenCore for FungibleToken {
 impl FungibleToken {
     /// Internal method that returns the amount of burned tokens in a corner case when the sender
     /// has deleted (unregistered) their account while the `ft_transfer_call` was still in flight.
+    /// The burned tokens are taken from the receiver and removed from the total supply, with an
+    /// `ft_burn` event whose memo is [`REFUND_BURN_MEMO`].
     /// Returns (Used token amount, Burned token amount)
     pub fn internal_ft_resolve_transfer(
         &mut self,
@@ -246,6 +346,12 @@ impl FungibleToken {
                     // Sender's account was deleted, so we need to burn tokens.
                     self.total_supply -= refund_amount;
                     log!("The account of the sender was deleted");
+                    FtBurn {
+                        owner_id: &receiver_id,
+                        amount: &U128(refund_amount),
+                        memo: Some(REFUND_BURN_MEMO),
+                    }
+                    .emit();
                     return (amount, refund_amount);
                 }
             }
@@ -264,3 +370,277 @@ impl FungibleTokenResolver for FungibleToken {
         self.internal_ft_resolve_transfer(&sender_id, receiver_id, amount).0.into()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::mock::VmAction;
+    use near_sdk::test_utils::{accounts, get_created_receipts, get_logs, VMContextBuilder};
+    use near_sdk::{testing_env, RuntimeFeesConfig, VMConfig};
+
+    #[test]
+    fn default_storage_keys() {
+        // Golden value: a change moves the accounts of every contract using `new_default`.
+        assert_eq!(
+            FungibleTokenStorageKey::Accounts.into_storage_key(),
+            [0x9d, 0x48, 0x93, 0x02, 0xe3, 0x4e, 0xa0, 0x7f, 0]
+        );
+
+        // The keys of a contract using the token, which previously had to avoid the prefix of the
+        // token.
+        #[derive(BorshStorageKey, BorshSerialize)]
+        enum ContractKey {
+            Accounts,
+            Metadata,
+        }
+        let mut token = FungibleToken::new_default();
+        let mut accounts_map = LookupMap::new(ContractKey::Accounts);
+        let mut metadata = LookupMap::new(ContractKey::Metadata);
+        token.internal_register_account(&accounts(1));
+        token.internal_deposit(&accounts(1), 100);
+        accounts_map.insert(&accounts(1), &1u128);
+        metadata.insert(&accounts(1), &2u128);
+        assert_eq!(token.ft_balance_of(accounts(1)), U128(100));
+        assert_eq!(accounts_map.get(&accounts(1)), Some(1));
+    }
+
+    fn token() -> FungibleToken {
+        let mut token = FungibleToken::new(b"t".to_vec());
+        token.internal_register_account(&accounts(1));
+        token.internal_register_account(&accounts(2));
+        token.internal_deposit(&accounts(1), 100);
+        token
+    }
+
+    fn context(predecessor: AccountId, attached_deposit: Balance) -> CallContext {
+        CallContext {
+            predecessor_account_id: predecessor.clone(),
+            signer_account_id: predecessor,
+            current_account_id: accounts(0),
+            attached_deposit,
+            block_timestamp: 0,
+            prepaid_gas: Gas(300 * Gas::ONE_TERA.0),
+        }
+    }
+
+    #[test]
+    fn transfer_from_context() {
+        let mut token = token();
+        let sender_id =
+            token.internal_transfer_from_context(&context(accounts(1), 1), &accounts(2), 40, None);
+        assert_eq!(sender_id, accounts(1));
+        assert_eq!(token.ft_balance_of(accounts(1)).0, 60);
+        assert_eq!(token.ft_balance_of(accounts(2)).0, 40);
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn transfer_from_context_without_deposit() {
+        token().internal_transfer_from_context(&context(accounts(1), 0), &accounts(2), 40, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "The account doesn't have enough balance")]
+    fn transfer_from_context_sends_from_predecessor() {
+        token().internal_transfer_from_context(&context(accounts(2), 1), &accounts(1), 40, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "length 257 exceeds maximum 256 for `memo`")]
+    fn transfer_rejects_long_memo() {
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build());
+        token().ft_transfer(accounts(2), U128(40), Some("m".repeat(257)));
+    }
+
+    fn transfer_call_with_prepaid_gas(prepaid_gas: Gas) {
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .prepaid_gas(prepaid_gas)
+            .build());
+        let _ = token().ft_transfer_call(accounts(2), U128(40), None, "".to_string());
+    }
+
+    #[test]
+    fn transfer_call_attaches_gas_left() {
+        transfer_call_with_prepaid_gas(Gas::ONE_TERA * 100);
+        let receiver_gas = get_created_receipts()
+            .into_iter()
+            .flat_map(|receipt| receipt.actions)
+            .find_map(|action| match action {
+                VmAction::FunctionCall { function_name, gas, .. }
+                    if function_name == "ft_on_transfer" =>
+                {
+                    Some(gas)
+                }
+                _ => None,
+            })
+            .unwrap();
+        let reserved = GAS_FOR_FT_TRANSFER_CALL + GAS_FOR_RESOLVE_TRANSFER;
+        assert!(receiver_gas <= Gas::ONE_TERA * 100 - reserved);
+        assert!(receiver_gas > Gas::ONE_TERA * 99 - reserved);
+    }
+
+    #[test]
+    fn transfer_call_configured_gas() {
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .prepaid_gas(Gas::ONE_TERA * 100)
+            .build());
+        let gas = FtTransferCallGas {
+            resolve_transfer: Gas::ONE_TERA * 20,
+            transfer_call: Gas::ONE_TERA * 40,
+        };
+        let _ = token().ft_transfer_call_with_gas(accounts(2), U128(40), None, "".to_string(), gas);
+        let gas_by_function: Vec<(String, Gas)> = get_created_receipts()
+            .into_iter()
+            .flat_map(|receipt| receipt.actions)
+            .filter_map(|action| match action {
+                VmAction::FunctionCall { function_name, gas, .. } => Some((function_name, gas)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(gas_by_function[1], ("ft_resolve_transfer".to_string(), Gas::ONE_TERA * 20));
+        let (function_name, receiver_gas) = &gas_by_function[0];
+        assert_eq!(function_name, "ft_on_transfer");
+        assert!(*receiver_gas <= Gas::ONE_TERA * 40);
+        assert!(*receiver_gas > Gas::ONE_TERA * 39);
+    }
+
+    #[test]
+    #[should_panic(expected = "ft_resolve_transfer needs at least 5000000000000 gas, \
+                               1000000000000 is reserved")]
+    fn transfer_call_resolve_gas_below_default() {
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .prepaid_gas(Gas::ONE_TERA * 100)
+            .build());
+        let gas =
+            FtTransferCallGas { resolve_transfer: Gas::ONE_TERA, ..FtTransferCallGas::DEFAULT };
+        let _ = token().ft_transfer_call_with_gas(accounts(2), U128(40), None, "".to_string(), gas);
+    }
+
+    #[test]
+    #[should_panic(expected = "reserving 30000000000000 for ft_transfer_call, 5000000000000 for \
+                               ft_resolve_transfer. ft_transfer_call does not fit")]
+    fn transfer_call_without_gas_for_transfer() {
+        transfer_call_with_prepaid_gas(Gas::ONE_TERA * 20);
+    }
+
+    #[test]
+    #[should_panic(expected = "reserving 30000000000000 for ft_transfer_call, 5000000000000 for \
+                               ft_resolve_transfer. ft_resolve_transfer does not fit")]
+    fn transfer_call_without_gas_for_resolve() {
+        transfer_call_with_prepaid_gas(Gas::ONE_TERA * 32);
+    }
+
+    #[test]
+    fn mint_and_burn_emit_events() {
+        let mut token = token();
+        token.internal_mint(&accounts(2), 50, Some("airdrop"));
+        token.internal_burn(&accounts(1), 30, None);
+        assert_eq!(token.ft_balance_of(accounts(1)).0, 70);
+        assert_eq!(token.ft_balance_of(accounts(2)).0, 50);
+        assert_eq!(token.ft_total_supply().0, 120);
+        assert_eq!(
+            get_logs(),
+            [
+                r#"EVENT_JSON:{"standard":"nep141","version":"1.0.0","event":"ft_mint","data":[{"owner_id":"charlie","amount":"50","memo":"airdrop"}]}"#,
+                r#"EVENT_JSON:{"standard":"nep141","version":"1.0.0","event":"ft_burn","data":[{"owner_id":"bob","amount":"30"}]}"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn transfer_emits_event() {
+        let mut token = token();
+        token.internal_transfer(&accounts(1), &accounts(2), 40, Some("rent".to_string()));
+        assert_eq!(
+            get_logs(),
+            [
+                r#"EVENT_JSON:{"standard":"nep141","version":"1.0.0","event":"ft_transfer","data":[{"old_owner_id":"bob","new_owner_id":"charlie","amount":"40","memo":"rent"}]}"#
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "The account doesn't have enough balance")]
+    fn burn_over_balance() {
+        token().internal_burn(&accounts(1), 101, None);
+    }
+
+    #[test]
+    fn transfer_from_env() {
+        let mut token = token();
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build());
+        token.ft_transfer(accounts(2), U128(40), None);
+        assert_eq!(token.ft_balance_of(accounts(1)).0, 60);
+        assert_eq!(token.ft_balance_of(accounts(2)).0, 40);
+    }
+
+    /// Resolves a transfer of 100 tokens from `accounts(1)` to `accounts(2)`, whose receiver
+    /// returned `unused` tokens.
+    fn resolve(token: &mut FungibleToken, unused: Balance) -> (Balance, Balance) {
+        testing_env!(
+            VMContextBuilder::new()
+                .current_account_id(accounts(0))
+                .predecessor_account_id(accounts(0))
+                .build(),
+            VMConfig::test(),
+            RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(format!("\"{}\"", unused).into_bytes())],
+        );
+        token.internal_ft_resolve_transfer(&accounts(1), accounts(2), U128(100))
+    }
+
+    fn transferred_token() -> FungibleToken {
+        let mut token = token();
+        token.internal_transfer(&accounts(1), &accounts(2), 100, None);
+        token
+    }
+
+    #[test]
+    fn resolve_receiver_keeps_everything() {
+        let mut token = transferred_token();
+        assert_eq!(resolve(&mut token, 0), (100, 0));
+        assert_eq!(token.ft_balance_of(accounts(2)).0, 100);
+        assert!(get_logs().is_empty());
+    }
+
+    #[test]
+    fn resolve_partial_refund() {
+        let mut token = transferred_token();
+        assert_eq!(resolve(&mut token, 30), (70, 0));
+        assert_eq!(token.ft_balance_of(accounts(1)).0, 30);
+        assert_eq!(token.ft_balance_of(accounts(2)).0, 70);
+        assert_eq!(token.ft_total_supply().0, 100);
+    }
+
+    #[test]
+    fn resolve_burns_refund_of_unregistered_sender() {
+        let mut token = transferred_token();
+        token.accounts.remove(&accounts(1));
+        assert_eq!(resolve(&mut token, 30), (100, 30));
+        assert_eq!(token.ft_balance_of(accounts(2)).0, 70);
+        assert_eq!(token.ft_total_supply().0, 70);
+        assert_eq!(
+            get_logs(),
+            [
+                "The account of the sender was deleted",
+                r#"EVENT_JSON:{"standard":"nep141","version":"1.0.0","event":"ft_burn","data":[{"owner_id":"charlie","amount":"30","memo":"refund"}]}"#,
+            ]
+        );
+    }
+}
@@ -12,11 +12,20 @@
 //! These events can be logged by calling `.emit()` on them if a single event, or calling
 //! [`FtMint::emit_many`], [`FtTransfer::emit_many`],
 //! or [`FtBurn::emit_many`] respectively.
+//!
+//! Their owned counterparts [`FtMintData`], [`FtTransferData`] and [`FtBurnData`] are what
+//! [`NearEvent::from_log`](crate::event::NearEvent::from_log) parses the logs into.
+//!
+//! [`FungibleToken`](crate::fungible_token::FungibleToken) emits them itself: [`FtTransfer`] on
+//! every transfer, and [`FtMint`] and [`FtBurn`] from
+//! [`internal_mint`](crate::fungible_token::FungibleToken::internal_mint) and
+//! [`internal_burn`](crate::fungible_token::FungibleToken::internal_burn). The lower level
+//! `internal_deposit` and `internal_withdraw` don't emit events.
 
-use crate::event::NearEvent;
+use crate::event::{emit_events, NearEventData};
 use near_sdk::json_types::U128;
 use near_sdk::AccountId;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 /// Data to log for an FT mint event. To log this event, call [`.emit()`](FtMint::emit).
 #[must_use]
@@ -38,10 +47,16 @@ impl FtMint<'_> {
     /// Emits an FT mint event, through [`env::log_str`](near_sdk::env::log_str),
     /// where each [`FtMint`] represents the data of each mint.
     pub fn emit_many(data: &[FtMint<'_>]) {
-        new_141_v1(Nep141EventKind::FtMint(data)).emit()
+        emit_events(data)
     }
 }
 
+impl NearEventData for FtMint<'_> {
+    const STANDARD: &'static str = "nep141";
+    const VERSION: &'static str = "1.0.0";
+    const EVENT: &'static str = "ft_mint";
+}
+
 /// Data to log for an FT transfer event. To log this event,
 /// call [`.emit()`](FtTransfer::emit).
 #[must_use]
@@ -64,10 +79,16 @@ impl FtTransfer<'_> {
     /// Emits an FT transfer event, through [`env::log_str`](near_sdk::env::log_str),
     /// where each [`FtTransfer`] represents the data of each transfer.
     pub fn emit_many(data: &[FtTransfer<'_>]) {
-        new_141_v1(Nep141EventKind::FtTransfer(data)).emit()
+        emit_events(data)
     }
 }
 
+impl NearEventData for FtTransfer<'_> {
+    const STANDARD: &'static str = "nep141";
+    const VERSION: &'static str = "1.0.0";
+    const EVENT: &'static str = "ft_transfer";
+}
+
 /// Data to log for an FT burn event. To log this event, call [`.emit()`](FtBurn::emit).
 #[must_use]
 #[derive(Serialize, Debug, Clone)]
@@ -88,33 +109,62 @@ impl FtBurn<'_> {
     /// Emits an FT burn event, through [`env::log_str`](near_sdk::env::log_str),
     /// where each [`FtBurn`] represents the data of each burn.
     pub fn emit_many<'a>(data: &'a [FtBurn<'a>]) {
-        new_141_v1(Nep141EventKind::FtBurn(data)).emit()
+        emit_events(data)
     }
 }
 
-#[derive(Serialize, Debug)]
-pub(crate) struct Nep141Event<'a> {
-    version: &'static str,
-    #[serde(flatten)]
-    event_kind: Nep141EventKind<'a>,
+impl NearEventData for FtBurn<'_> {
+    const STANDARD: &'static str = "nep141";
+    const VERSION: &'static str = "1.0.0";
+    const EVENT: &'static str = "ft_burn";
 }
 
-#[derive(Serialize, Debug)]
-#[serde(tag = "event", content = "data")]
-#[serde(rename_all = "snake_case")]
-#[allow(clippy::enum_variant_names)]
-enum Nep141EventKind<'a> {
-    FtMint(&'a [FtMint<'a>]),
-    FtTransfer(&'a [FtTransfer<'a>]),
-    FtBurn(&'a [FtBurn<'a>]),
+/// Owned data of an [`FtMint`] event, as parsed by
+/// [`NearEvent::from_log`](crate::event::NearEvent::from_log).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FtMintData {
+    pub owner_id: AccountId,
+    pub amount: U128,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
 }
 
-fn new_141<'a>(version: &'static str, event_kind: Nep141EventKind<'a>) -> NearEvent<'a> {
-    NearEvent::Nep141(Nep141Event { version, event_kind })
+/// Owned data of an [`FtTransfer`] event.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FtTransferData {
+    pub old_owner_id: AccountId,
+    pub new_owner_id: AccountId,
+    pub amount: U128,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+}
+
+/// Owned data of an [`FtBurn`] event.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FtBurnData {
+    pub owner_id: AccountId,
+    pub amount: U128,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+}
+
+/// An owned nep141 event.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Nep141Event {
+    pub version: String,
+    #[serde(flatten)]
+    pub event_kind: Nep141EventKind,
 }
 
-fn new_141_v1(event_kind: Nep141EventKind) -> NearEvent {
-    new_141("1.0.0", event_kind)
+/// The kind and the data of an owned nep141 event.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "event", content = "data")]
+#[serde(rename_all = "snake_case")]
+#[allow(clippy::enum_variant_names)]
+pub enum Nep141EventKind {
+    FtMint(Vec<FtMintData>),
+    FtTransfer(Vec<FtTransferData>),
+    FtBurn(Vec<FtBurnData>),
 }
 
 #[cfg(test)]
@@ -1,10 +1,45 @@
 /// The core methods for a basic fungible token. Extension standards may be
 /// added in addition to this macro.
+///
+/// Takes the name of the contract struct, the inner field for the token and optionally the name
+/// of a method called with the sender and the amount when tokens are burned because the sender
+/// unregistered during `ft_transfer_call`, once the `ft_burn` event is emitted. The following options can be added after it, each in
+/// brackets and in this order:
+///
+/// - `[guard = method]`: called with `&self` at the start of `ft_transfer` and
+///   `ft_transfer_call`, for example to reject transfers while the contract is paused. It should
+///   panic to reject the call.
+/// - `[on_transfer = method]`: called after `ft_transfer` and `ft_transfer_call` moved the tokens,
+///   as `method(&mut self, sender_id: AccountId, receiver_id: AccountId, amount: Balance)`. Tokens
+///   later refunded by `ft_resolve_transfer` are reported to `on_resolve`.
+/// - `[on_resolve = method]`: called at the end of `ft_resolve_transfer`, after the standard
+///   resolution, as `method(&mut self, sender_id: AccountId, receiver_id: AccountId,
+///   used_amount: Balance)`, where `used_amount` is the amount the receiver kept, which is also
+///   returned.
+/// - `[gas = expr]`: the [`FtTransferCallGas`](crate::fungible_token::FtTransferCallGas)
+///   reserved by `ft_transfer_call`, for resolvers which need more gas than the default.
+///
+/// ```ignore
+/// near_contract_standards::impl_fungible_token_core!(
+///     Contract,
+///     token,
+///     on_tokens_burned,
+///     [guard = assert_not_paused],
+///     [on_transfer = after_ft_transfer],
+///     [gas = FtTransferCallGas { resolve_transfer: Gas(10 * Gas::ONE_TERA.0), ..FtTransferCallGas::DEFAULT }]
+/// );
+/// ```
 #[macro_export]
 macro_rules! impl_fungible_token_core {
     ($contract: ident, $token: ident $(, $on_tokens_burned_fn:ident)?) => {
+        $crate::__token_field!(
+            $contract.$token: $crate::fungible_token::FungibleToken,
+            __ft_core_token,
+            __ft_core_token_mut
+        );
         use $crate::fungible_token::core::FungibleTokenCore;
         use $crate::fungible_token::resolver::FungibleTokenResolver;
+        $crate::__register_standard!(nep141);
 
         #[near_bindgen]
         impl FungibleTokenCore for $contract {
@@ -15,7 +50,7 @@ macro_rules! impl_fungible_token_core {
                 amount: U128,
                 memo: Option<String>,
             ) {
-                self.$token.ft_transfer(receiver_id, amount, memo)
+                self.__ft_core_token_mut().ft_transfer(receiver_id, amount, memo)
             }
 
             #[payable]
@@ -26,15 +61,15 @@ macro_rules! impl_fungible_token_core {
                 memo: Option<String>,
                 msg: String,
             ) -> PromiseOrValue<U128> {
-                self.$token.ft_transfer_call(receiver_id, amount, memo, msg)
+                self.__ft_core_token_mut().ft_transfer_call(receiver_id, amount, memo, msg)
             }
 
             fn ft_total_supply(&self) -> U128 {
-                self.$token.ft_total_supply()
+                self.__ft_core_token().ft_total_supply()
             }
 
             fn ft_balance_of(&self, account_id: AccountId) -> U128 {
-                self.$token.ft_balance_of(account_id)
+                self.__ft_core_token().ft_balance_of(account_id)
             }
         }
 
@@ -47,8 +82,9 @@ macro_rules! impl_fungible_token_core {
                 receiver_id: AccountId,
                 amount: U128,
             ) -> U128 {
-                let (used_amount, burned_amount) =
-                    self.$token.internal_ft_resolve_transfer(&sender_id, receiver_id, amount);
+                let (used_amount, burned_amount) = self
+                    .__ft_core_token_mut()
+                    .internal_ft_resolve_transfer(&sender_id, receiver_id, amount);
                 if burned_amount > 0 {
                     $(self.$on_tokens_burned_fn(sender_id, burned_amount);)?
                 }
@@ -56,6 +92,107 @@ macro_rules! impl_fungible_token_core {
             }
         }
     };
+    ($contract: ident, $token: ident, $on_tokens_burned_fn:ident, $([$($option:tt)*]),+) => {
+        $crate::impl_fungible_token_core!(
+            $contract,
+            $token,
+            [on_tokens_burned = $on_tokens_burned_fn],
+            $([$($option)*]),+
+        );
+    };
+    ($contract: ident, $token: ident
+        $(, [on_tokens_burned = $on_tokens_burned_fn:ident])?
+        $(, [guard = $guard_fn:ident])?
+        $(, [on_transfer = $on_transfer_fn:ident])?
+        $(, [on_resolve = $on_resolve_fn:ident])?
+        $(, [gas = $gas:expr])?
+    ) => {
+        $crate::__token_field!(
+            $contract.$token: $crate::fungible_token::FungibleToken,
+            __ft_core_token,
+            __ft_core_token_mut
+        );
+        use $crate::fungible_token::core::FungibleTokenCore;
+        use $crate::fungible_token::resolver::FungibleTokenResolver;
+        $crate::__register_standard!(nep141);
+
+        #[near_bindgen]
+        impl FungibleTokenCore for $contract {
+            #[payable]
+            fn ft_transfer(
+                &mut self,
+                receiver_id: AccountId,
+                amount: U128,
+                memo: Option<String>,
+            ) {
+                $(self.$guard_fn();)?
+                self.__ft_core_token_mut().ft_transfer(receiver_id.clone(), amount, memo);
+                $(self.$on_transfer_fn(near_sdk::env::predecessor_account_id(), receiver_id, amount.0);)?
+            }
+
+            #[payable]
+            fn ft_transfer_call(
+                &mut self,
+                receiver_id: AccountId,
+                amount: U128,
+                memo: Option<String>,
+                msg: String,
+            ) -> PromiseOrValue<U128> {
+                $(self.$guard_fn();)?
+                let result = self.__ft_core_token_mut().ft_transfer_call_with_gas(
+                    receiver_id.clone(),
+                    amount,
+                    memo,
+                    msg,
+                    $crate::__or_default!(
+                        ($crate::fungible_token::FtTransferCallGas::DEFAULT) $($gas)?
+                    ),
+                );
+                $(self.$on_transfer_fn(near_sdk::env::predecessor_account_id(), receiver_id, amount.0);)?
+                result
+            }
+
+            fn ft_total_supply(&self) -> U128 {
+                self.__ft_core_token().ft_total_supply()
+            }
+
+            fn ft_balance_of(&self, account_id: AccountId) -> U128 {
+                self.__ft_core_token().ft_balance_of(account_id)
+            }
+        }
+
+        #[near_bindgen]
+        impl FungibleTokenResolver for $contract {
+            #[private]
+            fn ft_resolve_transfer(
+                &mut self,
+                sender_id: AccountId,
+                receiver_id: AccountId,
+                amount: U128,
+            ) -> U128 {
+                let (used_amount, burned_amount) = self
+                    .__ft_core_token_mut()
+                    .internal_ft_resolve_transfer(&sender_id, receiver_id.clone(), amount);
+                if burned_amount > 0 {
+                    $(self.$on_tokens_burned_fn(sender_id.clone(), burned_amount);)?
+                }
+                $(self.$on_resolve_fn(sender_id, receiver_id, used_amount);)?
+                used_amount.into()
+            }
+        }
+    };
+}
+
+/// Expands to the expression in parentheses, or to the expression after it if there is one.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __or_default {
+    (($default:expr)) => {
+        $default
+    };
+    (($default:expr) $value:expr) => {
+        $value
+    };
 }
 
 /// Ensures that when fungible token storage grows by collections adding entries,
@@ -66,9 +203,15 @@ macro_rules! impl_fungible_token_core {
 #[macro_export]
 macro_rules! impl_fungible_token_storage {
     ($contract: ident, $token: ident $(, $on_account_closed_fn:ident)?) => {
+        $crate::__token_field!(
+            $contract.$token: $crate::fungible_token::FungibleToken,
+            __ft_storage_token,
+            __ft_storage_token_mut
+        );
         use $crate::storage_management::{
             StorageManagement, StorageBalance, StorageBalanceBounds
         };
+        $crate::__register_standard!(nep145);
 
         #[near_bindgen]
         impl StorageManagement for $contract {
@@ -78,18 +221,19 @@ macro_rules! impl_fungible_token_storage {
                 account_id: Option<AccountId>,
                 registration_only: Option<bool>,
             ) -> StorageBalance {
-                self.$token.storage_deposit(account_id, registration_only)
+                self.__ft_storage_token_mut().storage_deposit(account_id, registration_only)
             }
 
             #[payable]
             fn storage_withdraw(&mut self, amount: Option<U128>) -> StorageBalance {
-                self.$token.storage_withdraw(amount)
+                self.__ft_storage_token_mut().storage_withdraw(amount)
             }
 
             #[payable]
             fn storage_unregister(&mut self, force: Option<bool>) -> bool {
+                let closed = self.__ft_storage_token_mut().internal_storage_unregister(force);
                 #[allow(unused_variables)]
-                if let Some((account_id, balance)) = self.$token.internal_storage_unregister(force) {
+                if let Some((account_id, balance)) = closed {
                     $(self.$on_account_closed_fn(account_id, balance);)?
                     true
                 } else {
@@ -98,11 +242,76 @@ macro_rules! impl_fungible_token_storage {
             }
 
             fn storage_balance_bounds(&self) -> StorageBalanceBounds {
-                self.$token.storage_balance_bounds()
+                self.__ft_storage_token().storage_balance_bounds()
             }
 
             fn storage_balance_of(&self, account_id: AccountId) -> Option<StorageBalance> {
-                self.$token.storage_balance_of(account_id)
+                self.__ft_storage_token().storage_balance_of(account_id)
+            }
+        }
+    };
+}
+
+/// Exposes the views of the balance held by the token contract itself, `ft_treasury_balance` and
+/// `ft_circulating_supply`.
+/// Takes name of the Contract struct and the inner field for the token.
+#[macro_export]
+macro_rules! impl_fungible_token_treasury {
+    ($contract: ident, $token: ident) => {
+        $crate::__token_field!(
+            $contract.$token: $crate::fungible_token::FungibleToken,
+            __ft_treasury_token
+        );
+        use $crate::fungible_token::treasury::FungibleTokenTreasury;
+
+        #[near_bindgen]
+        impl FungibleTokenTreasury for $contract {
+            fn ft_treasury_balance(&self) -> U128 {
+                self.__ft_treasury_token().ft_treasury_balance()
+            }
+
+            fn ft_circulating_supply(&self) -> U128 {
+                self.__ft_treasury_token().ft_circulating_supply()
+            }
+        }
+    };
+}
+
+/// The allowance extension, `ft_approve`, `ft_allowance` and `ft_transfer_from`, for a token
+/// stored in `$token` and its allowances stored in `$allowances`, a
+/// [`FungibleTokenAllowances`](crate::fungible_token::allowance::FungibleTokenAllowances).
+/// Takes name of the Contract struct and the inner fields for the token and the allowances.
+#[macro_export]
+macro_rules! impl_fungible_token_allowance {
+    ($contract: ident, $token: ident, $allowances: ident) => {
+        use $crate::fungible_token::allowance::FungibleTokenAllowance;
+
+        #[near_bindgen]
+        impl FungibleTokenAllowance for $contract {
+            #[payable]
+            fn ft_approve(&mut self, spender_id: AccountId, amount: U128) {
+                self.$allowances.ft_approve(&self.$token, spender_id, amount)
+            }
+
+            fn ft_allowance(&self, owner_id: AccountId, spender_id: AccountId) -> U128 {
+                self.$allowances.allowance(&owner_id, &spender_id).into()
+            }
+
+            #[payable]
+            fn ft_transfer_from(
+                &mut self,
+                owner_id: AccountId,
+                receiver_id: AccountId,
+                amount: U128,
+                memo: Option<String>,
+            ) {
+                self.$allowances.ft_transfer_from(
+                    &mut self.$token,
+                    owner_id,
+                    receiver_id,
+                    amount,
+                    memo,
+                )
             }
         }
     };
@@ -1,10 +1,18 @@
+use crate::spec::{assert_supported_spec, supports_spec};
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::json_types::Base64VecU8;
-use near_sdk::require;
 use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, require};
 
 pub const FT_METADATA_SPEC: &str = "ft-1.0.0";
 
+/// Maximum number of decimals of a token, those of NEAR itself.
+pub const MAX_FT_DECIMALS: u8 = 24;
+
+/// Maximum length of the icon data URL, in bytes. Wallets render the icon of every token they
+/// list, and larger icons make the metadata expensive to store and to fetch.
+pub const MAX_FT_ICON_LEN: usize = 10 * 1024;
+
 #[derive(BorshDeserialize, BorshSerialize, Clone, Deserialize, Serialize)]
 #[serde(crate = "near_sdk::serde")]
 pub struct FungibleTokenMetadata {
@@ -19,14 +27,145 @@ pub struct FungibleTokenMetadata {
 
 pub trait FungibleTokenMetadataProvider {
     fn ft_metadata(&self) -> FungibleTokenMetadata;
+
+    /// Returns `true` if the token implements a version of the metadata standard compatible with
+    /// `spec`, see [`SpecVersion::is_compatible_with`](crate::spec::SpecVersion::is_compatible_with).
+    fn ft_supports_spec(&self, spec: String) -> bool {
+        self.ft_metadata().supports_spec(&spec)
+    }
 }
 
 impl FungibleTokenMetadata {
+    /// Metadata of the current [`FT_METADATA_SPEC`], without an icon or a reference.
+    pub fn new(name: String, symbol: String, decimals: u8) -> Self {
+        Self {
+            spec: FT_METADATA_SPEC.to_string(),
+            name,
+            symbol,
+            icon: None,
+            reference: None,
+            reference_hash: None,
+            decimals,
+        }
+    }
+
+    /// Panics if the spec isn't a version of [`FT_METADATA_SPEC`] with the same major version,
+    /// there are more than [`MAX_FT_DECIMALS`] decimals, the icon is longer than
+    /// [`MAX_FT_ICON_LEN`], or the reference hash is invalid.
     pub fn assert_valid(&self) {
-        require!(self.spec == FT_METADATA_SPEC);
-        require!(self.reference.is_some() == self.reference_hash.is_some());
-        if let Some(reference_hash) = &self.reference_hash {
-            require!(reference_hash.0.len() == 32, "Hash has to be 32 bytes");
+        assert_supported_spec(&self.spec, FT_METADATA_SPEC, "Spec is not FT metadata");
+        require!(
+            self.decimals <= MAX_FT_DECIMALS,
+            format!("Decimals can't be more than {}", MAX_FT_DECIMALS)
+        );
+        if let Some(icon) = &self.icon {
+            require!(
+                icon.len() <= MAX_FT_ICON_LEN,
+                format!("Icon can't be longer than {} bytes", MAX_FT_ICON_LEN)
+            );
+        }
+        match (&self.reference, &self.reference_hash) {
+            (Some(_), None) => env::panic_str("Reference hash is required with a reference"),
+            (None, Some(_)) => env::panic_str("Reference hash requires a reference"),
+            (_, Some(hash)) => require!(hash.0.len() == 32, "Hash has to be 32 bytes"),
+            (None, None) => {}
+        }
+    }
+
+    /// Returns `true` if the spec of this metadata is compatible with `spec`. Malformed specs are
+    /// not supported.
+    pub fn supports_spec(&self, spec: &str) -> bool {
+        supports_spec(&self.spec, spec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata(spec: &str) -> FungibleTokenMetadata {
+        FungibleTokenMetadata {
+            spec: spec.to_string(),
+            ..FungibleTokenMetadata::new("Example".to_string(), "EXAMPLE".to_string(), 24)
+        }
+    }
+
+    #[test]
+    fn valid_specs() {
+        metadata(FT_METADATA_SPEC).assert_valid();
+        metadata("ft-1.1.0").assert_valid();
+    }
+
+    #[test]
+    #[should_panic(expected = "Malformed spec")]
+    fn malformed_spec() {
+        metadata("ft-1.0").assert_valid();
+    }
+
+    #[test]
+    #[should_panic(expected = "Spec is not FT metadata")]
+    fn newer_major_spec() {
+        metadata("ft-2.0.0").assert_valid();
+    }
+
+    #[test]
+    fn supports_spec() {
+        let metadata = metadata("ft-1.1.0");
+        assert!(metadata.supports_spec("ft-1.0.0"));
+        assert!(metadata.supports_spec("ft-1.1.0"));
+        assert!(!metadata.supports_spec("ft-1.2.0"));
+        assert!(!metadata.supports_spec("ft-2.0.0"));
+        assert!(!metadata.supports_spec("ft-1"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Decimals can't be more than 24")]
+    fn too_many_decimals() {
+        FungibleTokenMetadata { decimals: 25, ..metadata(FT_METADATA_SPEC) }.assert_valid();
+    }
+
+    #[test]
+    fn icon_size() {
+        let icon = format!("data:image/svg+xml,{}", "a".repeat(MAX_FT_ICON_LEN - 19));
+        assert_eq!(icon.len(), MAX_FT_ICON_LEN);
+        FungibleTokenMetadata { icon: Some(icon), ..metadata(FT_METADATA_SPEC) }.assert_valid();
+    }
+
+    #[test]
+    #[should_panic(expected = "Icon can't be longer than 10240 bytes")]
+    fn icon_too_large() {
+        let icon = format!("data:image/svg+xml,{}", "a".repeat(MAX_FT_ICON_LEN));
+        FungibleTokenMetadata { icon: Some(icon), ..metadata(FT_METADATA_SPEC) }.assert_valid();
+    }
+
+    #[test]
+    #[should_panic(expected = "Reference hash is required with a reference")]
+    fn reference_without_hash() {
+        FungibleTokenMetadata {
+            reference: Some("https://example.com/ft.json".to_string()),
+            ..metadata(FT_METADATA_SPEC)
+        }
+        .assert_valid();
+    }
+
+    #[test]
+    #[should_panic(expected = "Reference hash requires a reference")]
+    fn hash_without_reference() {
+        FungibleTokenMetadata {
+            reference_hash: Some(Base64VecU8(vec![0; 32])),
+            ..metadata(FT_METADATA_SPEC)
+        }
+        .assert_valid();
+    }
+
+    #[test]
+    #[should_panic(expected = "Hash has to be 32 bytes")]
+    fn short_reference_hash() {
+        FungibleTokenMetadata {
+            reference: Some("https://example.com/ft.json".to_string()),
+            reference_hash: Some(Base64VecU8(vec![0; 31])),
+            ..metadata(FT_METADATA_SPEC)
         }
+        .assert_valid();
     }
 }
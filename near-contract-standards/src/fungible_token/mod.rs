@@ -1,11 +1,14 @@
+pub mod allowance;
 pub mod core;
 pub mod core_impl;
 pub mod events;
 pub mod macros;
 pub mod metadata;
 pub mod receiver;
+pub mod registry;
 pub mod resolver;
 pub mod storage_impl;
+pub mod treasury;
 
-pub use core_impl::FungibleToken;
+pub use core_impl::{FtTransferCallGas, FungibleToken, FungibleTokenStorageKey, REFUND_BURN_MEMO};
 pub use macros::*;
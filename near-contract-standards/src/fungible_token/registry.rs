@@ -0,0 +1,407 @@
+//! Balances of many fungible tokens held by a contract on behalf of its users, as kept by
+//! exchanges and treasuries.
+//!
+//! Users deposit a token by calling `ft_transfer_call` on the token contract with the contract
+//! holding the [`TokenRegistry`] as the receiver, which credits the sender in `ft_on_transfer`.
+//! They withdraw with [`TokenRegistry::withdraw_to_wallet`], which debits their balance and
+//! transfers the tokens back, restoring the balance in
+//! [`TokenRegistryResolver::ft_resolve_withdraw`] if the transfer fails.
+//!
+//! The registry doesn't charge for the storage of the balances it adds, so contracts should only
+//! register tokens and accept deposits from accounts which paid for their storage, for example
+//! with [storage management](crate::storage_management).
+
+use crate::fungible_token::core_impl::ext_fungible_token;
+use crate::fungible_token::receiver::FungibleTokenReceiver;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::{LookupMap, PrefixAllocator, UnorderedMap, UnorderedSet};
+use near_sdk::json_types::U128;
+use near_sdk::{
+    assert_one_yocto, env, ext_contract, require, AccountId, Balance, Gas, IntoStorageKey, Promise,
+    PromiseOrValue, PromiseResult, ONE_YOCTO,
+};
+use std::fmt;
+
+const GAS_FOR_FT_TRANSFER: Gas = Gas(10_000_000_000_000);
+const GAS_FOR_RESOLVE_WITHDRAW: Gas = Gas(5_000_000_000_000);
+const NO_DEPOSIT: Balance = 0;
+
+/// Callback of [`TokenRegistry::withdraw_to_wallet`], implemented by the contract holding the
+/// registry by calling [`TokenRegistry::internal_resolve_withdraw`]. The method must be private.
+pub trait TokenRegistryResolver {
+    /// Restores the balance of `account_id` if the transfer of `amount` of `token_id` failed.
+    /// Returns `true` if the tokens were transferred.
+    fn ft_resolve_withdraw(
+        &mut self,
+        token_id: AccountId,
+        account_id: AccountId,
+        amount: U128,
+    ) -> bool;
+}
+
+#[ext_contract(ext_self)]
+trait TokenRegistryResolver {
+    fn ft_resolve_withdraw(
+        &mut self,
+        token_id: AccountId,
+        account_id: AccountId,
+        amount: U128,
+    ) -> bool;
+}
+
+/// Error returned by [`TokenRegistry::withdraw`] when the balance doesn't cover the amount.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InsufficientBalance {
+    /// The token contract.
+    pub token_id: AccountId,
+    /// The account withdrawing.
+    pub account_id: AccountId,
+    /// The balance of the account.
+    pub available: Balance,
+    /// The amount which was requested.
+    pub requested: Balance,
+}
+
+impl fmt::Display for InsufficientBalance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "The account {} doesn't have enough balance of {}: requested {}, available {}",
+            self.account_id, self.token_id, self.requested, self.available
+        )
+    }
+}
+
+impl std::error::Error for InsufficientBalance {}
+
+/// Balances of the users of a contract in each registered token contract.
+///
+/// The balances of each token are kept in a nested map, whose prefix is generated by a
+/// [`PrefixAllocator`]. The tokens each account holds a balance of are kept in a set per account,
+/// so that they can be listed without visiting every token.
+///
+/// # Examples
+/// ```
+/// use near_contract_standards::fungible_token::registry::TokenRegistry;
+///
+/// let mut registry = TokenRegistry::new(b"r");
+/// let (usdc, alice) = ("usdc.near".parse().unwrap(), "alice.near".parse().unwrap());
+/// registry.register_token(&usdc);
+/// registry.deposit(&usdc, &alice, 100);
+/// registry.withdraw(&usdc, &alice, 40).unwrap();
+/// assert_eq!(registry.balance_of(&usdc, &alice), 60);
+/// assert!(registry.withdraw(&usdc, &alice, 100).is_err());
+/// ```
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct TokenRegistry {
+    /// Balances of the accounts in each token.
+    balances: UnorderedMap<AccountId, LookupMap<AccountId, Balance>>,
+    /// Tokens each account has a positive balance of.
+    tokens_per_account: LookupMap<AccountId, UnorderedSet<AccountId>>,
+    prefixes: PrefixAllocator,
+}
+
+impl TokenRegistry {
+    pub fn new<S>(prefix: S) -> Self
+    where
+        S: IntoStorageKey,
+    {
+        let prefix = prefix.into_storage_key();
+        Self {
+            balances: UnorderedMap::new([prefix.clone(), b"b".to_vec()].concat()),
+            tokens_per_account: LookupMap::new([prefix.clone(), b"t".to_vec()].concat()),
+            prefixes: PrefixAllocator::new([prefix, b"p".to_vec()].concat()),
+        }
+    }
+
+    /// Allows deposits of `token_id`. Does nothing if the token is already registered.
+    pub fn register_token(&mut self, token_id: &AccountId) {
+        if self.balances.get(token_id).is_none() {
+            self.balances.insert(token_id, &LookupMap::new(self.prefixes.next()));
+        }
+    }
+
+    /// Returns `true` if deposits of `token_id` are allowed.
+    pub fn is_registered(&self, token_id: &AccountId) -> bool {
+        self.balances.get(token_id).is_some()
+    }
+
+    /// Returns up to `limit` registered tokens, starting at `from_index`.
+    pub fn tokens(&self, from_index: u64, limit: u64) -> Vec<AccountId> {
+        self.balances.keys_as_vector().to_paginated_vec(from_index, limit)
+    }
+
+    fn token_balances(&self, token_id: &AccountId) -> LookupMap<AccountId, Balance> {
+        self.balances
+            .get(token_id)
+            .unwrap_or_else(|| env::panic_str(&format!("Token {} is not registered", token_id)))
+    }
+
+    /// Returns the balance of `account_id` in `token_id`, 0 if it has none.
+    pub fn balance_of(&self, token_id: &AccountId, account_id: &AccountId) -> Balance {
+        self.balances.get(token_id).and_then(|balances| balances.get(account_id)).unwrap_or(0)
+    }
+
+    /// Returns up to `limit` of the tokens `account_id` has a balance of, with the balances,
+    /// starting at `from_index`.
+    pub fn tokens_of(
+        &self,
+        account_id: &AccountId,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<(AccountId, U128)> {
+        match self.tokens_per_account.get(account_id) {
+            Some(tokens) => tokens
+                .to_page(from_index, limit)
+                .into_iter()
+                .map(|token_id| {
+                    let balance = self.balance_of(&token_id, account_id);
+                    (token_id, U128(balance))
+                })
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Credits `amount` of `token_id` to `account_id`. The token must be registered.
+    pub fn deposit(&mut self, token_id: &AccountId, account_id: &AccountId, amount: Balance) {
+        require!(amount > 0, "The amount should be a positive number");
+        let mut balances = self.token_balances(token_id);
+        let balance = balances.get(account_id).unwrap_or(0);
+        let new_balance =
+            balance.checked_add(amount).unwrap_or_else(|| env::panic_str("Balance overflow"));
+        balances.insert(account_id, &new_balance);
+        if balance == 0 {
+            let mut tokens = match self.tokens_per_account.get(account_id) {
+                Some(tokens) => tokens,
+                None => UnorderedSet::new(self.prefixes.next()),
+            };
+            tokens.insert(token_id);
+            self.tokens_per_account.insert(account_id, &tokens);
+        }
+    }
+
+    /// Debits `amount` of `token_id` from `account_id`. Returns an error, without changing the
+    /// balance, if the balance doesn't cover the amount.
+    pub fn withdraw(
+        &mut self,
+        token_id: &AccountId,
+        account_id: &AccountId,
+        amount: Balance,
+    ) -> Result<(), InsufficientBalance> {
+        require!(amount > 0, "The amount should be a positive number");
+        let mut balances = self.token_balances(token_id);
+        let balance = balances.get(account_id).unwrap_or(0);
+        let new_balance = balance.checked_sub(amount).ok_or_else(|| InsufficientBalance {
+            token_id: token_id.clone(),
+            account_id: account_id.clone(),
+            available: balance,
+            requested: amount,
+        })?;
+        if new_balance > 0 {
+            balances.insert(account_id, &new_balance);
+        } else {
+            balances.remove(account_id);
+            if let Some(mut tokens) = self.tokens_per_account.get(account_id) {
+                tokens.remove(token_id);
+                if tokens.is_empty() {
+                    self.tokens_per_account.remove(account_id);
+                } else {
+                    self.tokens_per_account.insert(account_id, &tokens);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Debits `amount` of `token_id` from the predecessor and transfers it to the predecessor's
+    /// wallet with `ft_transfer`. Requires exactly 1 yoctoNEAR attached, which is forwarded to the
+    /// token contract. The balance is restored by `ft_resolve_withdraw` if the transfer fails.
+    pub fn withdraw_to_wallet(&mut self, token_id: AccountId, amount: Balance) -> Promise {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        self.withdraw(&token_id, &account_id, amount)
+            .unwrap_or_else(|err| env::panic_str(&err.to_string()));
+        ext_fungible_token::ft_transfer(
+            account_id.clone(),
+            U128(amount),
+            None,
+            token_id.clone(),
+            ONE_YOCTO,
+            GAS_FOR_FT_TRANSFER,
+        )
+        .then(ext_self::ft_resolve_withdraw(
+            token_id,
+            account_id,
+            U128(amount),
+            env::current_account_id(),
+            NO_DEPOSIT,
+            GAS_FOR_RESOLVE_WITHDRAW,
+        ))
+    }
+
+    /// Restores the balance debited by [`withdraw_to_wallet`](Self::withdraw_to_wallet) if the
+    /// transfer failed. Returns `true` if the tokens were transferred.
+    pub fn internal_resolve_withdraw(
+        &mut self,
+        token_id: &AccountId,
+        account_id: &AccountId,
+        amount: Balance,
+    ) -> bool {
+        match env::promise_result(0) {
+            PromiseResult::NotReady => env::abort(),
+            PromiseResult::Successful(_) => true,
+            PromiseResult::Failed => {
+                // The token may have been unregistered since, in which case the tokens are kept
+                // by the contract rather than failing the callback.
+                if self.is_registered(token_id) {
+                    self.deposit(token_id, account_id, amount);
+                }
+                false
+            }
+        }
+    }
+}
+
+impl FungibleTokenReceiver for TokenRegistry {
+    /// Credits the transferred tokens to the sender, in the token of the predecessor, which is the
+    /// token contract. Panics if the token isn't registered, which makes the token contract
+    /// refund the transfer.
+    fn ft_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        amount: U128,
+        _msg: String,
+    ) -> PromiseOrValue<U128> {
+        let token_id = env::predecessor_account_id();
+        require!(self.is_registered(&token_id), "The predecessor is not a registered token");
+        self.deposit(&token_id, &sender_id, amount.0);
+        PromiseOrValue::Value(U128(0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::mock::VmAction;
+    use near_sdk::test_utils::{accounts, get_created_receipts, VMContextBuilder};
+    use near_sdk::{testing_env, RuntimeFeesConfig, VMConfig};
+
+    fn token(i: usize) -> AccountId {
+        format!("token{}.near", i).parse().unwrap()
+    }
+
+    fn set_context(predecessor: AccountId, attached_deposit: Balance) {
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(accounts(0))
+            .predecessor_account_id(predecessor)
+            .attached_deposit(attached_deposit)
+            .build());
+    }
+
+    fn set_callback_context(result: PromiseResult) {
+        testing_env!(
+            VMContextBuilder::new()
+                .current_account_id(accounts(0))
+                .predecessor_account_id(accounts(0))
+                .build(),
+            VMConfig::test(),
+            RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![result],
+        );
+    }
+
+    fn registry() -> TokenRegistry {
+        set_context(accounts(0), 0);
+        let mut registry = TokenRegistry::new(b"r");
+        registry.register_token(&token(1));
+        registry.register_token(&token(2));
+        registry
+    }
+
+    #[test]
+    fn deposit_via_transfer_call() {
+        let mut registry = registry();
+        set_context(token(1), 0);
+        let unused = registry.ft_on_transfer(accounts(1), U128(100), String::new());
+        assert!(matches!(unused, PromiseOrValue::Value(U128(0))));
+        set_context(token(2), 0);
+        registry.ft_on_transfer(accounts(1), U128(5), String::new());
+
+        assert_eq!(registry.balance_of(&token(1), &accounts(1)), 100);
+        assert_eq!(
+            registry.tokens_of(&accounts(1), 0, 10),
+            [(token(1), U128(100)), (token(2), U128(5))]
+        );
+        assert_eq!(registry.tokens_of(&accounts(1), 1, 10), [(token(2), U128(5))]);
+        assert!(registry.tokens_of(&accounts(2), 0, 10).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "The predecessor is not a registered token")]
+    fn deposit_from_unregistered_token() {
+        let mut registry = registry();
+        set_context(token(3), 0);
+        registry.ft_on_transfer(accounts(1), U128(100), String::new());
+    }
+
+    #[test]
+    fn withdraw_all_removes_token() {
+        let mut registry = registry();
+        registry.deposit(&token(1), &accounts(1), 10);
+        registry.deposit(&token(2), &accounts(1), 10);
+        let err = registry.withdraw(&token(1), &accounts(1), 11).unwrap_err();
+        assert_eq!((err.available, err.requested), (10, 11));
+        assert_eq!(registry.balance_of(&token(1), &accounts(1)), 10);
+
+        registry.withdraw(&token(1), &accounts(1), 10).unwrap();
+        assert_eq!(registry.tokens_of(&accounts(1), 0, 10), [(token(2), U128(10))]);
+        registry.withdraw(&token(2), &accounts(1), 10).unwrap();
+        assert!(registry.tokens_of(&accounts(1), 0, 10).is_empty());
+    }
+
+    #[test]
+    fn withdraw_to_wallet_transfers() {
+        let mut registry = registry();
+        registry.deposit(&token(1), &accounts(1), 100);
+
+        set_context(accounts(1), 1);
+        registry.withdraw_to_wallet(token(1), 40);
+        assert_eq!(registry.balance_of(&token(1), &accounts(1)), 60);
+        let receipts = get_created_receipts();
+        assert_eq!(receipts[0].receiver_id, token(1));
+        assert!(matches!(
+            &receipts[0].actions[..],
+            [VmAction::FunctionCall { function_name, deposit: 1, .. }] if function_name == "ft_transfer"
+        ));
+
+        set_callback_context(PromiseResult::Successful(Vec::new()));
+        assert!(registry.internal_resolve_withdraw(&token(1), &accounts(1), 40));
+        assert_eq!(registry.balance_of(&token(1), &accounts(1)), 60);
+    }
+
+    #[test]
+    fn failed_withdraw_rolls_back() {
+        let mut registry = registry();
+        registry.deposit(&token(1), &accounts(1), 100);
+
+        set_context(accounts(1), 1);
+        registry.withdraw_to_wallet(token(1), 100);
+        assert!(registry.tokens_of(&accounts(1), 0, 10).is_empty());
+
+        set_callback_context(PromiseResult::Failed);
+        assert!(!registry.internal_resolve_withdraw(&token(1), &accounts(1), 100));
+        assert_eq!(registry.balance_of(&token(1), &accounts(1)), 100);
+        assert_eq!(registry.tokens_of(&accounts(1), 0, 10), [(token(1), U128(100))]);
+    }
+
+    #[test]
+    #[should_panic(expected = "doesn't have enough balance of token1.near")]
+    fn withdraw_to_wallet_over_balance() {
+        let mut registry = registry();
+        registry.deposit(&token(1), &accounts(1), 100);
+        set_context(accounts(1), 1);
+        registry.withdraw_to_wallet(token(1), 101);
+    }
+}
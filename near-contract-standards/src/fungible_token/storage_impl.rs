@@ -1,38 +1,127 @@
+use crate::fungible_token::events::FtBurn;
 use crate::fungible_token::FungibleToken;
-use crate::storage_management::{StorageBalance, StorageBalanceBounds, StorageManagement};
+use crate::storage_management::{
+    self, AccountStorageHook, StorageBalance, StorageBalanceBounds, StorageBalances,
+    StorageManagement, StorageRequirements, StorageRequirementsView,
+};
+use near_sdk::collections::LookupMap;
 use near_sdk::json_types::U128;
-use near_sdk::{assert_one_yocto, env, log, AccountId, Balance, Promise};
+use near_sdk::{assert_predecessor_is_signer, env, AccountId, Balance, StorageUsage};
+
+/// The storage balances of the accounts of a [`FungibleToken`], derived from its accounts: every
+/// registered account has exactly the storage balance covering `account_storage_usage`, so it
+/// isn't stored. Unregistering an account with `force` burns its balance.
+struct FtStorageBalances<'a> {
+    accounts: &'a mut LookupMap<AccountId, Balance>,
+    total_supply: &'a mut Balance,
+    account_storage_usage: StorageUsage,
+    direct_force_only: bool,
+    closed_balance: Balance,
+}
+
+impl<'a> FtStorageBalances<'a> {
+    fn new(token: &'a mut FungibleToken, direct_force_only: bool) -> Self {
+        Self {
+            accounts: &mut token.accounts,
+            total_supply: &mut token.total_supply,
+            account_storage_usage: token.account_storage_usage,
+            direct_force_only,
+            closed_balance: 0,
+        }
+    }
+}
+
+impl AccountStorageHook for FtStorageBalances<'_> {
+    fn on_account_closed(&mut self, account_id: &AccountId) {
+        let balance = self.accounts.get(account_id).unwrap_or(0);
+        if balance > 0 {
+            env::panic_str(&format!(
+                "Can't unregister the account with the positive balance without force, the balance is {}",
+                balance
+            ))
+        }
+    }
+
+    fn on_account_force_closed(&mut self, account_id: &AccountId) {
+        let balance = self.accounts.get(account_id).unwrap_or(0);
+        if balance > 0 {
+            if self.direct_force_only {
+                assert_predecessor_is_signer();
+            }
+            *self.total_supply -= balance;
+            FtBurn { owner_id: account_id, amount: &U128(balance), memo: None }.emit();
+        }
+        self.closed_balance = balance;
+    }
+}
+
+impl StorageBalances for FtStorageBalances<'_> {
+    fn bounds(&self) -> StorageBalanceBounds {
+        storage_balance_bounds(self.account_storage_usage)
+    }
+
+    fn get(&self, account_id: &AccountId) -> Option<StorageBalance> {
+        storage_balance_of(self.accounts, self.account_storage_usage, account_id)
+    }
+
+    fn set(&mut self, account_id: &AccountId, _balance: &StorageBalance) {
+        // The storage balance of an account can't change, it's only set when it registers.
+        if !self.accounts.contains_key(account_id) {
+            self.accounts.insert(account_id, &0);
+        }
+    }
+
+    fn remove(&mut self, account_id: &AccountId) {
+        self.accounts.remove(account_id);
+    }
+}
+
+fn storage_balance_bounds(account_storage_usage: StorageUsage) -> StorageBalanceBounds {
+    let required_storage_balance = Balance::from(account_storage_usage) * env::storage_byte_cost();
+    StorageBalanceBounds {
+        min: required_storage_balance.into(),
+        max: Some(required_storage_balance.into()),
+    }
+}
+
+fn storage_balance_of(
+    accounts: &LookupMap<AccountId, Balance>,
+    account_storage_usage: StorageUsage,
+    account_id: &AccountId,
+) -> Option<StorageBalance> {
+    if accounts.contains_key(account_id) {
+        Some(StorageBalance {
+            total: storage_balance_bounds(account_storage_usage).min,
+            available: 0.into(),
+        })
+    } else {
+        None
+    }
+}
 
 impl FungibleToken {
     /// Internal method that returns the Account ID and the balance in case the account was
-    /// unregistered.
+    /// unregistered. With `force`, the balance of the account is burned, with an `ft_burn` event.
+    /// Without it, unregistering an account with a positive balance panics.
+    ///
+    /// With the `direct-force-unregister` feature, burning a positive balance with `force` panics
+    /// unless the account called the contract directly, see
+    /// [`assert_predecessor_is_signer`](near_sdk::assert_predecessor_is_signer).
     pub fn internal_storage_unregister(
         &mut self,
         force: Option<bool>,
     ) -> Option<(AccountId, Balance)> {
-        assert_one_yocto();
-        let account_id = env::predecessor_account_id();
-        let force = force.unwrap_or(false);
-        if let Some(balance) = self.accounts.get(&account_id) {
-            if balance == 0 || force {
-                self.accounts.remove(&account_id);
-                self.total_supply -= balance;
-                Promise::new(account_id.clone()).transfer(self.storage_balance_bounds().min.0 + 1);
-                Some((account_id, balance))
-            } else {
-                env::panic_str(
-                    "Can't unregister the account with the positive balance without force",
-                )
-            }
-        } else {
-            log!("The account {} is not registered", &account_id);
-            None
-        }
+        self.storage_unregister_checked(force, cfg!(feature = "direct-force-unregister"))
     }
 
-    fn internal_storage_balance_of(&self, account_id: &AccountId) -> Option<StorageBalance> {
-        if self.accounts.contains_key(account_id) {
-            Some(StorageBalance { total: self.storage_balance_bounds().min, available: 0.into() })
+    fn storage_unregister_checked(
+        &mut self,
+        force: Option<bool>,
+        direct_force_only: bool,
+    ) -> Option<(AccountId, Balance)> {
+        let mut balances = FtStorageBalances::new(self, direct_force_only);
+        if storage_management::storage_unregister(&mut balances, force) {
+            Some((env::predecessor_account_id(), balances.closed_balance))
         } else {
             None
         }
@@ -40,56 +129,26 @@ impl FungibleToken {
 }
 
 impl StorageManagement for FungibleToken {
-    // `registration_only` doesn't affect the implementation for vanilla fungible token.
-    #[allow(unused_variables)]
+    /// Registers the account, refunding the deposit above the minimum storage balance. The storage
+    /// balance of a registered account can't grow, so a deposit for it is refunded.
     fn storage_deposit(
         &mut self,
         account_id: Option<AccountId>,
         registration_only: Option<bool>,
     ) -> StorageBalance {
-        let amount: Balance = env::attached_deposit();
-        let account_id = account_id.unwrap_or_else(env::predecessor_account_id);
-        if self.accounts.contains_key(&account_id) {
-            log!("The account is already registered, refunding the deposit");
-            if amount > 0 {
-                Promise::new(env::predecessor_account_id()).transfer(amount);
-            }
-        } else {
-            let min_balance = self.storage_balance_bounds().min.0;
-            if amount < min_balance {
-                env::panic_str("The attached deposit is less than the minimum storage balance");
-            }
-
-            self.internal_register_account(&account_id);
-            let refund = amount - min_balance;
-            if refund > 0 {
-                Promise::new(env::predecessor_account_id()).transfer(refund);
-            }
-        }
-        self.internal_storage_balance_of(&account_id).unwrap()
+        storage_management::storage_deposit(
+            &mut FtStorageBalances::new(self, false),
+            account_id,
+            registration_only,
+        )
     }
 
-    /// While storage_withdraw normally allows the caller to retrieve `available` balance, the basic
-    /// Fungible Token implementation sets storage_balance_bounds.min == storage_balance_bounds.max,
-    /// which means available balance will always be 0. So this implementation:
-    /// * panics if `amount > 0`
-    /// * never transfers Ⓝ to caller
-    /// * returns a `storage_balance` struct if `amount` is 0
+    /// Withdraws from the available storage balance of the predecessor, with the checks of
+    /// [`AccountStorage`](crate::storage_management::AccountStorage). The storage balance of a
+    /// registered account is always the minimum, so none of it is available: this panics if
+    /// `amount` is positive, never transfers Ⓝ, and returns the storage balance otherwise.
     fn storage_withdraw(&mut self, amount: Option<U128>) -> StorageBalance {
-        assert_one_yocto();
-        let predecessor_account_id = env::predecessor_account_id();
-        if let Some(storage_balance) = self.internal_storage_balance_of(&predecessor_account_id) {
-            match amount {
-                Some(amount) if amount.0 > 0 => {
-                    env::panic_str("The amount is greater than the available storage balance");
-                }
-                _ => storage_balance,
-            }
-        } else {
-            env::panic_str(
-                format!("The account {} is not registered", &predecessor_account_id).as_str(),
-            );
-        }
+        storage_management::storage_withdraw(&mut FtStorageBalances::new(self, false), amount)
     }
 
     fn storage_unregister(&mut self, force: Option<bool>) -> bool {
@@ -97,15 +156,173 @@ impl StorageManagement for FungibleToken {
     }
 
     fn storage_balance_bounds(&self) -> StorageBalanceBounds {
-        let required_storage_balance =
-            Balance::from(self.account_storage_usage) * env::storage_byte_cost();
-        StorageBalanceBounds {
-            min: required_storage_balance.into(),
-            max: Some(required_storage_balance.into()),
-        }
+        storage_balance_bounds(self.account_storage_usage)
     }
 
     fn storage_balance_of(&self, account_id: AccountId) -> Option<StorageBalance> {
-        self.internal_storage_balance_of(&account_id)
+        storage_balance_of(&self.accounts, self.account_storage_usage, &account_id)
+    }
+}
+
+impl StorageRequirementsView for FungibleToken {
+    fn storage_requirements(&self) -> StorageRequirements {
+        StorageRequirements::default().with("storage_deposit", self.account_storage_usage)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fungible_token::core::FungibleTokenCore;
+    use near_sdk::mock::VmAction;
+    use near_sdk::test_utils::{accounts, get_created_receipts, get_logs, VMContextBuilder};
+    use near_sdk::{testing_env, ONE_YOCTO};
+
+    /// Calls the token directly as the signer `accounts(1)`, or from the contract `accounts(2)`
+    /// called by the signer unless `direct`.
+    fn set_context(direct: bool) {
+        let signer = accounts(1);
+        let predecessor = if direct { accounts(1) } else { accounts(2) };
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(accounts(0))
+            .signer_account_id(signer)
+            .predecessor_account_id(predecessor)
+            .attached_deposit(ONE_YOCTO)
+            .build());
+    }
+
+    fn token() -> FungibleToken {
+        let mut token = FungibleToken::new(b"t".to_vec());
+        token.internal_register_account(&accounts(1));
+        token.internal_register_account(&accounts(2));
+        token.internal_deposit(&accounts(1), 1_000);
+        token.internal_deposit(&accounts(2), 1_000);
+        token
+    }
+
+    #[test]
+    fn direct_force_unregister() {
+        set_context(true);
+        let mut token = token();
+        assert_eq!(token.storage_unregister_checked(Some(true), true), Some((accounts(1), 1_000)));
+        assert_eq!(token.total_supply, 1_000);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Method must be called directly by the signer, not through another contract"
+    )]
+    fn intermediated_force_unregister() {
+        set_context(false);
+        let mut token = token();
+        token.storage_unregister_checked(Some(true), true);
+    }
+
+    #[test]
+    fn intermediated_force_unregister_without_flag() {
+        set_context(false);
+        let mut token = token();
+        assert_eq!(token.storage_unregister_checked(Some(true), false), Some((accounts(2), 1_000)));
+    }
+
+    #[test]
+    fn intermediated_unregister_without_balance() {
+        set_context(false);
+        let mut token = token();
+        token.internal_withdraw(&accounts(2), 1_000);
+        assert_eq!(token.storage_unregister_checked(Some(true), true), Some((accounts(2), 0)));
+    }
+
+    /// Returns the amount refunded to `accounts(1)` by the last call.
+    fn refund() -> Balance {
+        let receipts = get_created_receipts();
+        assert_eq!(receipts.len(), 1);
+        assert_eq!(receipts[0].receiver_id, accounts(1));
+        match receipts[0].actions[..] {
+            [VmAction::Transfer { deposit }] => deposit,
+            _ => panic!("Expected a transfer, got {:?}", receipts[0].actions),
+        }
+    }
+
+    #[test]
+    fn force_unregister_burns_balance() {
+        set_context(true);
+        let mut token = token();
+        let min = token.storage_balance_bounds().min.0;
+        assert!(token.storage_unregister(Some(true)));
+        assert_eq!(refund(), min + 1);
+        assert_eq!(token.ft_total_supply(), U128(1_000));
+        assert_eq!(token.ft_balance_of(accounts(1)), U128(0));
+        assert_eq!(token.storage_balance_of(accounts(1)), None);
+        assert_eq!(
+            get_logs(),
+            [
+                r#"EVENT_JSON:{"standard":"nep141","version":"1.0.0","event":"ft_burn","data":[{"owner_id":"bob","amount":"1000"}]}"#
+            ]
+        );
+    }
+
+    #[test]
+    fn force_unregister_without_balance() {
+        set_context(true);
+        let mut token = token();
+        token.internal_withdraw(&accounts(1), 1_000);
+        assert!(token.storage_unregister(Some(true)));
+        assert_eq!(refund(), token.storage_balance_bounds().min.0 + 1);
+        assert_eq!(token.ft_total_supply(), U128(1_000));
+        assert!(get_logs().is_empty());
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Can't unregister the account with the positive balance without force, the balance is 1000"
+    )]
+    fn unregister_with_balance() {
+        set_context(true);
+        token().storage_unregister(None);
+    }
+
+    #[test]
+    fn unregister_twice() {
+        set_context(true);
+        let mut token = token();
+        token.internal_withdraw(&accounts(1), 1_000);
+        assert!(token.storage_unregister(None));
+        set_context(true);
+        assert!(!token.storage_unregister(None));
+        assert!(!token.storage_unregister(Some(true)));
+        assert_eq!(get_logs(), ["The account bob is not registered"; 2]);
+    }
+
+    fn withdraw_context(predecessor: AccountId) {
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(accounts(0))
+            .predecessor_account_id(predecessor)
+            .attached_deposit(ONE_YOCTO)
+            .build());
+    }
+
+    #[test]
+    fn withdraw_without_available_balance() {
+        withdraw_context(accounts(1));
+        let mut token = token();
+        let min = token.storage_balance_bounds().min;
+        let balance = StorageBalance { total: min, available: U128(0) };
+        assert_eq!(token.storage_withdraw(None), balance);
+        assert_eq!(token.storage_withdraw(Some(U128(0))), balance);
+    }
+
+    #[test]
+    #[should_panic(expected = "The amount is greater than the available storage balance of 0")]
+    fn withdraw_over_available_balance() {
+        withdraw_context(accounts(1));
+        token().storage_withdraw(Some(U128(1)));
+    }
+
+    #[test]
+    #[should_panic(expected = "The account danny is not registered")]
+    fn withdraw_unregistered() {
+        withdraw_context(accounts(3));
+        token().storage_withdraw(None);
     }
 }
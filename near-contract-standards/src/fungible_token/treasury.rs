@@ -0,0 +1,212 @@
+use crate::fungible_token::events::FtTransfer;
+use crate::fungible_token::FungibleToken;
+use near_sdk::borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::{env, require, AccountId, Balance};
+
+/// Views of the balance held by the token contract itself, see
+/// [`FungibleToken::treasury_balance`].
+pub trait FungibleTokenTreasury {
+    /// Returns the balance held by the contract itself.
+    fn ft_treasury_balance(&self) -> U128;
+
+    /// Returns the total supply, excluding the balance held by the contract itself.
+    fn ft_circulating_supply(&self) -> U128;
+}
+
+impl FungibleToken {
+    /// Returns the balance held by the contract itself, e.g. accrued fees, without registering the
+    /// contract as an account. It's included in `total_supply`.
+    ///
+    /// The balance is stored under the prefix of [`accounts`](Self::accounts) followed by `t`
+    /// rather than in the token, so the token keeps its layout and each token of a contract has
+    /// its own treasury.
+    pub fn treasury_balance(&self) -> Balance {
+        match env::storage_read(&self.treasury_key()) {
+            Some(raw) => Balance::try_from_slice(&raw)
+                .unwrap_or_else(|_| env::panic_str("Cannot deserialize the treasury balance")),
+            None => 0,
+        }
+    }
+
+    fn set_treasury_balance(&mut self, balance: Balance) {
+        env::storage_write(&self.treasury_key(), &balance.try_to_vec().unwrap());
+    }
+
+    /// Can't collide with the keys of the accounts, which are longer as they start with the length
+    /// of the account ID.
+    fn treasury_key(&self) -> Vec<u8> {
+        [self.accounts.key_prefix(), b"t"].concat()
+    }
+
+    /// Moves `amount` from the balance of `from` to the treasury. Emits an `ft_transfer` event to
+    /// the contract account.
+    pub fn internal_move_to_treasury(&mut self, from: &AccountId, amount: Balance) {
+        require!(amount > 0, "The amount should be a positive number");
+        let balance = self.internal_unwrap_balance_of(from);
+        let new_balance = balance
+            .checked_sub(amount)
+            .unwrap_or_else(|| env::panic_str("The account doesn't have enough balance"));
+        let treasury_balance = self
+            .treasury_balance()
+            .checked_add(amount)
+            .unwrap_or_else(|| env::panic_str("Treasury balance overflow"));
+        self.set_treasury_balance(treasury_balance);
+        self.accounts.insert(from, &new_balance);
+        FtTransfer {
+            old_owner_id: from,
+            new_owner_id: &env::current_account_id(),
+            amount: &U128(amount),
+            memo: None,
+        }
+        .emit();
+    }
+
+    /// Moves `amount` from the treasury to the balance of `to`, which must be registered. Emits an
+    /// `ft_transfer` event from the contract account.
+    pub fn internal_move_from_treasury(&mut self, to: &AccountId, amount: Balance) {
+        require!(amount > 0, "The amount should be a positive number");
+        let balance = self.internal_unwrap_balance_of(to);
+        let treasury_balance = self
+            .treasury_balance()
+            .checked_sub(amount)
+            .unwrap_or_else(|| env::panic_str("The treasury doesn't have enough balance"));
+        self.set_treasury_balance(treasury_balance);
+        let new_balance =
+            balance.checked_add(amount).unwrap_or_else(|| env::panic_str("Balance overflow"));
+        self.accounts.insert(to, &new_balance);
+        FtTransfer {
+            old_owner_id: &env::current_account_id(),
+            new_owner_id: to,
+            amount: &U128(amount),
+            memo: None,
+        }
+        .emit();
+    }
+}
+
+impl FungibleTokenTreasury for FungibleToken {
+    fn ft_treasury_balance(&self) -> U128 {
+        self.treasury_balance().into()
+    }
+
+    fn ft_circulating_supply(&self) -> U128 {
+        (self.total_supply - self.treasury_balance()).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fungible_token::core::FungibleTokenCore;
+    use near_sdk::test_utils::{accounts, get_logs, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    /// Fee skimmed by `transfer_with_fee`, in basis points.
+    const FEE_BPS: Balance = 100;
+
+    fn set_context() {
+        testing_env!(VMContextBuilder::new().current_account_id(accounts(0)).build());
+    }
+
+    fn token() -> FungibleToken {
+        let mut token = FungibleToken::new(b"t".to_vec());
+        token.internal_register_account(&accounts(1));
+        token.internal_register_account(&accounts(2));
+        token.internal_deposit(&accounts(1), 10_000);
+        token
+    }
+
+    /// A transfer hook which moves a fee from the amount received into the treasury.
+    fn transfer_with_fee(
+        token: &mut FungibleToken,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        amount: Balance,
+    ) {
+        token.internal_transfer(sender_id, receiver_id, amount, None);
+        token.internal_move_to_treasury(receiver_id, amount * FEE_BPS / 10_000);
+    }
+
+    #[test]
+    fn skim_fees() {
+        set_context();
+        let mut token = token();
+        transfer_with_fee(&mut token, &accounts(1), &accounts(2), 1_000);
+        transfer_with_fee(&mut token, &accounts(1), &accounts(2), 2_000);
+
+        assert_eq!(token.ft_balance_of(accounts(1)).0, 7_000);
+        assert_eq!(token.ft_balance_of(accounts(2)).0, 2_970);
+        assert_eq!(token.ft_treasury_balance().0, 30);
+        assert_eq!(token.ft_total_supply().0, 10_000);
+        assert_eq!(token.ft_circulating_supply().0, 9_970);
+        // The contract isn't registered as an account.
+        assert_eq!(token.ft_balance_of(accounts(0)).0, 0);
+        assert_eq!(
+            get_logs().last().unwrap(),
+            r#"EVENT_JSON:{"standard":"nep141","version":"1.0.0","event":"ft_transfer","data":[{"old_owner_id":"charlie","new_owner_id":"alice","amount":"20"}]}"#
+        );
+    }
+
+    #[test]
+    fn withdraw_from_treasury() {
+        set_context();
+        let mut token = token();
+        token.internal_move_to_treasury(&accounts(1), 500);
+        token.internal_move_from_treasury(&accounts(2), 200);
+
+        assert_eq!(token.ft_balance_of(accounts(1)).0, 9_500);
+        assert_eq!(token.ft_balance_of(accounts(2)).0, 200);
+        assert_eq!(token.ft_treasury_balance().0, 300);
+        assert_eq!(token.ft_circulating_supply().0, 9_700);
+        assert_eq!(
+            get_logs().last().unwrap(),
+            r#"EVENT_JSON:{"standard":"nep141","version":"1.0.0","event":"ft_transfer","data":[{"old_owner_id":"alice","new_owner_id":"charlie","amount":"200"}]}"#
+        );
+    }
+
+    #[test]
+    fn treasury_is_stored_outside_of_token() {
+        set_context();
+        let mut token = token();
+        let state = token.try_to_vec().unwrap();
+        token.internal_move_to_treasury(&accounts(1), 500);
+        assert_eq!(token.try_to_vec().unwrap(), state);
+        assert_eq!(token.treasury_balance(), 500);
+    }
+
+    #[test]
+    fn tokens_have_separate_treasuries() {
+        set_context();
+        let mut token = token();
+        let mut other = FungibleToken::new(b"o".to_vec());
+        other.internal_register_account(&accounts(1));
+        other.internal_deposit(&accounts(1), 100);
+
+        token.internal_move_to_treasury(&accounts(1), 500);
+        assert_eq!(other.ft_treasury_balance().0, 0);
+        assert_eq!(other.ft_circulating_supply().0, 100);
+        other.internal_move_to_treasury(&accounts(1), 40);
+        assert_eq!(token.ft_treasury_balance().0, 500);
+        assert_eq!(other.ft_treasury_balance().0, 40);
+        assert_eq!(other.ft_circulating_supply().0, 60);
+    }
+
+    #[test]
+    #[should_panic(expected = "The treasury doesn't have enough balance")]
+    fn withdraw_more_than_treasury() {
+        set_context();
+        let mut token = token();
+        token.internal_move_to_treasury(&accounts(1), 500);
+        token.internal_move_from_treasury(&accounts(2), 501);
+    }
+
+    #[test]
+    #[should_panic(expected = "The account danny is not registered")]
+    fn withdraw_to_unregistered_account() {
+        set_context();
+        let mut token = token();
+        token.internal_move_to_treasury(&accounts(1), 500);
+        token.internal_move_from_treasury(&accounts(3), 100);
+    }
+}
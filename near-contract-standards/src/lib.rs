@@ -1,5 +1,8 @@
 /// Fungible tokens as described in [by the spec](https://nomicon.io/Standards/FungibleToken/README.html).
 pub mod fungible_token;
+/// Multi tokens, fungible and non-fungible tokens held by a single contract, as described in
+/// [the spec](https://nomicon.io/Standards/Tokens/MultiToken/Core).
+pub mod multi_token;
 /// Non-fungible tokens as described in [by the spec](https://nomicon.io/Standards/NonFungibleToken/README.html).
 pub mod non_fungible_token;
 /// Storage management deals with handling [state storage](https://docs.near.org/docs/concepts/storage-staking) on NEAR. This follows the [storage management standard](https://nomicon.io/Standards/StorageManagement.html).
@@ -8,4 +11,81 @@ pub mod storage_management;
 /// blob, allowing it to be stored for a period of time before deployed.
 pub mod upgrade;
 
-pub(crate) mod event;
+/// Length limits and bounded argument types used by the standards.
+pub mod limits;
+
+/// Helpers for receiver contracts checking they have enough gas before accepting tokens.
+pub mod receiver_utils;
+
+/// Parsing and comparison of the `spec` versions of metadata.
+pub mod spec;
+
+/// Registration of the standards implemented by a contract, for tooling and views.
+pub mod standards;
+pub use standards::standards_manifest;
+
+/// Metadata of the source code of a contract, as described in
+/// [NEP-330](https://nomicon.io/Standards/SourceMetadata).
+pub mod contract_metadata;
+
+/// Ownership of a contract, transferred in two steps, for methods only the owner can call.
+pub mod ownable;
+
+/// Pausing features of a contract during incidents, by the owner of the contract.
+pub mod pausable;
+
+/// Role-based access control, with roles administered by other roles.
+pub mod rbac;
+
+/// Events of the standards, and parsing of event logs for off-chain tools.
+pub mod event;
+
+/// Refunds of the deposit attached to pay for storage, and measurement of the storage used by an
+/// operation.
+pub mod utils;
+
+/// Generates accessors to the field `$token` of `$contract`, typed as `$ty`. The macros
+/// implementing the standards only reach the token through them, so that a misspelled field or a
+/// field of the wrong type is reported at the macro invocation rather than in every generated
+/// method.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __token_field {
+    ($contract:ident . $token:ident : $ty:ty, $getter:ident) => {
+        impl $contract {
+            #[doc(hidden)]
+            // Macros only mutating the token, such as `impl_non_fungible_token_burn!`, don't use it.
+            #[allow(dead_code)]
+            fn $getter(&self) -> &$ty {
+                &self.$token
+            }
+        }
+    };
+    ($contract:ident . $token:ident : $ty:ty, $getter:ident, $getter_mut:ident) => {
+        $crate::__token_field!($contract.$token: $ty, $getter);
+
+        impl $contract {
+            #[doc(hidden)]
+            fn $getter_mut(&mut self) -> &mut $ty {
+                &mut self.$token
+            }
+        }
+    };
+}
+
+/// Returns the gas left in the current call after the `reserved` amounts, given as pairs of a
+/// name and an amount. Panics with a message listing every reservation if they don't fit.
+pub(crate) fn remaining_gas_after(reserved: &[(&str, near_sdk::Gas)]) -> near_sdk::Gas {
+    let amounts: Vec<near_sdk::Gas> = reserved.iter().map(|&(_, gas)| gas).collect();
+    near_sdk::Gas::remaining_after(&amounts).unwrap_or_else(|err| {
+        let reservations: Vec<String> =
+            reserved.iter().map(|(name, gas)| format!("{} for {}", gas.0, name)).collect();
+        near_sdk::env::panic_str(&format!(
+            "More gas is required: {} prepaid and {} used, reserving {}. {} does not fit",
+            err.prepaid.0,
+            err.used.0,
+            reservations.join(", "),
+            reserved[err.index].0
+        ))
+    })
+}
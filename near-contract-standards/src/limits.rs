@@ -0,0 +1,60 @@
+//! Upper bounds on free-form arguments accepted by the standards. The standards take these
+//! arguments as `String`, so their traits and JSON interface are unchanged, and check them with
+//! [`check_memo`](crate::limits::check_memo) and [`check_msg`](crate::limits::check_msg), which
+//! panic with an error naming the argument.
+
+use near_sdk::env;
+use near_sdk::json_types::BoundedString;
+use std::convert::TryFrom;
+
+/// Maximum length in bytes of a `memo` argument.
+pub const MAX_MEMO_LEN: usize = 256;
+
+/// Maximum length in bytes of a `msg` argument passed along to a receiver contract.
+pub const MAX_MSG_LEN: usize = 4096;
+
+/// Free form `memo` attached to a transfer.
+pub type Memo = BoundedString<MAX_MEMO_LEN>;
+
+/// `msg` forwarded to the receiver contract in `*_transfer_call` methods.
+pub type Msg = BoundedString<MAX_MSG_LEN>;
+
+/// Returns the `memo` argument, panicking if it is longer than [`MAX_MEMO_LEN`] bytes.
+pub fn check_memo(memo: Option<String>) -> Option<String> {
+    memo.map(|memo| check::<MAX_MEMO_LEN>("memo", memo))
+}
+
+/// Returns the `msg` argument, panicking if it is longer than [`MAX_MSG_LEN`] bytes.
+pub fn check_msg(msg: String) -> String {
+    check::<MAX_MSG_LEN>("msg", msg)
+}
+
+fn check<const MAX: usize>(argument: &str, value: String) -> String {
+    BoundedString::<MAX>::try_from(value)
+        .unwrap_or_else(|err| env::panic_str(&err.for_argument(argument).to_string()))
+        .into_inner()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn at_max() {
+        assert_eq!(check_memo(Some("m".repeat(MAX_MEMO_LEN))).unwrap().len(), MAX_MEMO_LEN);
+        assert_eq!(check_memo(None), None);
+        assert_eq!(check_msg("m".repeat(MAX_MSG_LEN)).len(), MAX_MSG_LEN);
+    }
+
+    #[test]
+    #[should_panic(expected = "length 257 exceeds maximum 256 for `memo`")]
+    fn memo_over_max() {
+        check_memo(Some("m".repeat(MAX_MEMO_LEN + 1)));
+    }
+
+    #[test]
+    #[should_panic(expected = "length 4097 exceeds maximum 4096 for `msg`")]
+    fn msg_over_max() {
+        check_msg("m".repeat(MAX_MSG_LEN + 1));
+    }
+}
@@ -0,0 +1,230 @@
+use crate::multi_token::approval::MultiTokenApproval;
+use crate::multi_token::core::ConsumedApproval;
+use crate::multi_token::token::{Approval, TokenId};
+use crate::multi_token::MultiToken;
+use crate::non_fungible_token::assert_at_least_one_yocto;
+use crate::utils::{measure_storage, refund_storage_delta};
+use near_sdk::json_types::U128;
+use near_sdk::{assert_one_yocto, env, ext_contract, require, AccountId, Balance, Gas, Promise};
+use std::collections::BTreeMap;
+
+const GAS_FOR_MT_APPROVE: Gas = Gas(10_000_000_000_000);
+const NO_DEPOSIT: Balance = 0;
+
+#[ext_contract(ext_approval_receiver)]
+pub trait MultiTokenApprovalReceiver {
+    fn mt_on_approve(
+        &mut self,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+        owner_id: AccountId,
+        approval_ids: Vec<u64>,
+        msg: String,
+    );
+}
+
+fn expect_approvals<T>(option: Option<T>) -> T {
+    option.unwrap_or_else(|| env::panic_str("MT does not support Approval Management"))
+}
+
+impl MultiToken {
+    /// Returns the approvals given by `owner_id` for `token_id`.
+    fn internal_approvals(
+        &self,
+        owner_id: &AccountId,
+        token_id: &str,
+    ) -> BTreeMap<AccountId, Approval> {
+        expect_approvals(self.approvals_by_id.as_ref())
+            .get(&(token_id.to_string(), owner_id.clone()))
+            .unwrap_or_default()
+    }
+
+    /// Stores the approvals given by `owner_id` for `token_id`, removing the entry if there are
+    /// none.
+    fn internal_set_approvals(
+        &mut self,
+        owner_id: &AccountId,
+        token_id: &str,
+        approvals: &BTreeMap<AccountId, Approval>,
+    ) {
+        let approvals_by_id = expect_approvals(self.approvals_by_id.as_mut());
+        let key = (token_id.to_string(), owner_id.clone());
+        if approvals.is_empty() {
+            approvals_by_id.remove(&key);
+        } else {
+            approvals_by_id.insert(&key, approvals);
+        }
+    }
+
+    /// Decreases the amount `account_id` is approved for by `owner_id` by `amount`, for a transfer
+    /// of `token_id`, removing the approval once it's used up. The storage of a used up approval
+    /// isn't refunded.
+    ///
+    /// Panics if `account_id` isn't approved with `approval_id` for at least `amount`.
+    pub(crate) fn internal_use_approval(
+        &mut self,
+        owner_id: &AccountId,
+        token_id: &str,
+        account_id: &AccountId,
+        approval_id: u64,
+        amount: Balance,
+    ) -> ConsumedApproval {
+        let mut approvals = self.internal_approvals(owner_id, token_id);
+        let approval = approvals
+            .get(account_id)
+            .copied()
+            .unwrap_or_else(|| env::panic_str("Sender not approved"));
+        require!(
+            approval.approval_id == approval_id,
+            format!(
+                "The actual approval_id {} is different from the given approval_id {}",
+                approval.approval_id, approval_id
+            )
+        );
+        require!(approval.amount.0 >= amount, "The approved amount is too low");
+        let remaining = approval.amount.0 - amount;
+        if remaining == 0 {
+            approvals.remove(account_id);
+        } else {
+            approvals.insert(account_id.clone(), Approval { approval_id, amount: U128(remaining) });
+        }
+        self.internal_set_approvals(owner_id, token_id, &approvals);
+        (account_id.clone(), approval_id, approval.amount)
+    }
+
+    /// Gives back `refund` of the amount of an approval consumed by a transfer whose tokens were
+    /// returned to `owner_id`, up to the amount approved before the transfer. The approval isn't
+    /// restored if the account was approved again since, with another approval ID.
+    pub(crate) fn internal_restore_approval(
+        &mut self,
+        owner_id: &AccountId,
+        token_id: &str,
+        (account_id, approval_id, approved_amount): ConsumedApproval,
+        refund: Balance,
+    ) {
+        if self.approvals_by_id.is_none() {
+            return;
+        }
+        let mut approvals = self.internal_approvals(owner_id, token_id);
+        let current_amount = match approvals.get(&account_id) {
+            Some(approval) if approval.approval_id != approval_id => return,
+            Some(approval) => approval.amount.0,
+            None => 0,
+        };
+        let amount = std::cmp::min(current_amount.saturating_add(refund), approved_amount.0);
+        approvals.insert(account_id, Approval { approval_id, amount: U128(amount) });
+        self.internal_set_approvals(owner_id, token_id, &approvals);
+    }
+}
+
+impl MultiTokenApproval for MultiToken {
+    fn mt_approve(
+        &mut self,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+        account_id: AccountId,
+        msg: Option<String>,
+    ) -> Option<Promise> {
+        assert_at_least_one_yocto();
+        expect_approvals(self.approvals_by_id.as_ref());
+        require!(
+            token_ids.len() == amounts.len(),
+            "The number of token IDs and amounts should be the same"
+        );
+        let owner_id = env::predecessor_account_id();
+
+        let (approval_ids, storage_delta) = measure_storage(|| {
+            token_ids
+                .iter()
+                .zip(&amounts)
+                .map(|(token_id, amount)| {
+                    require!(
+                        self.internal_balance_of(&owner_id, token_id) >= amount.0,
+                        "Not enough balance to approve"
+                    );
+                    let next_approval_id_by_id =
+                        expect_approvals(self.next_approval_id_by_id.as_mut());
+                    let approval_id = next_approval_id_by_id.get(token_id).unwrap_or(1u64);
+                    next_approval_id_by_id.insert(token_id, &(approval_id + 1));
+
+                    let mut approvals = self.internal_approvals(&owner_id, token_id);
+                    approvals.insert(account_id.clone(), Approval { approval_id, amount: *amount });
+                    self.internal_set_approvals(&owner_id, token_id, &approvals);
+                    approval_id
+                })
+                .collect::<Vec<u64>>()
+        });
+        refund_storage_delta(storage_delta);
+
+        // if given `msg`, schedule call to `mt_on_approve` and return it. Else, return None.
+        msg.map(|msg| {
+            let receiver_gas = crate::remaining_gas_after(&[("mt_approve", GAS_FOR_MT_APPROVE)]);
+            ext_approval_receiver::mt_on_approve(
+                token_ids,
+                amounts,
+                owner_id,
+                approval_ids,
+                msg,
+                account_id,
+                NO_DEPOSIT,
+                receiver_gas,
+            )
+        })
+    }
+
+    fn mt_revoke(&mut self, token_ids: Vec<TokenId>, account_id: AccountId) {
+        assert_one_yocto();
+        expect_approvals(self.approvals_by_id.as_ref());
+        let owner_id = env::predecessor_account_id();
+        let ((), storage_delta) = measure_storage(|| {
+            for token_id in &token_ids {
+                let mut approvals = self.internal_approvals(&owner_id, token_id);
+                if approvals.remove(&account_id).is_some() {
+                    self.internal_set_approvals(&owner_id, token_id, &approvals);
+                }
+            }
+        });
+        refund_storage_delta(storage_delta);
+    }
+
+    fn mt_revoke_all(&mut self, token_ids: Vec<TokenId>) {
+        assert_one_yocto();
+        let owner_id = env::predecessor_account_id();
+        let approvals_by_id = expect_approvals(self.approvals_by_id.as_mut());
+        let ((), storage_delta) = measure_storage(|| {
+            for token_id in token_ids {
+                approvals_by_id.remove(&(token_id, owner_id.clone()));
+            }
+        });
+        refund_storage_delta(storage_delta);
+    }
+
+    fn mt_is_approved(
+        &self,
+        owner_id: AccountId,
+        token_ids: Vec<TokenId>,
+        approved_account_id: AccountId,
+        amounts: Vec<U128>,
+        approval_ids: Option<Vec<u64>>,
+    ) -> bool {
+        require!(
+            token_ids.len() == amounts.len(),
+            "The number of token IDs and amounts should be the same"
+        );
+        if let Some(approval_ids) = &approval_ids {
+            require!(
+                token_ids.len() == approval_ids.len(),
+                "The number of token IDs and approval IDs should be the same"
+            );
+        }
+        token_ids.iter().zip(&amounts).enumerate().all(|(i, (token_id, amount))| {
+            match self.internal_approvals(&owner_id, token_id).get(&approved_account_id) {
+                Some(approval) => {
+                    approval.amount.0 >= amount.0
+                        && approval_ids.as_ref().map_or(true, |ids| ids[i] == approval.approval_id)
+                }
+                None => false,
+            }
+        })
+    }
+}
@@ -0,0 +1,88 @@
+mod approval_impl;
+mod approval_receiver;
+
+pub use approval_impl::*;
+pub use approval_receiver::*;
+
+use crate::multi_token::token::TokenId;
+use near_sdk::json_types::U128;
+use near_sdk::AccountId;
+use near_sdk::Promise;
+
+/// Trait used when it's desired to have multi tokens with an approval system, following the
+/// [approval management standard]. This allows Alice to allow Bob to take up to 10 of the tokens
+/// with the identifier "gold" but not others. An approval is given by an owner for its own
+/// balance, so each holder of a token has its own approvals.
+///
+/// [approval management standard]: https://nomicon.io/Standards/Tokens/MultiToken/ApprovalManagement
+pub trait MultiTokenApproval {
+    /// Approves `account_id` to transfer up to the amount at the same position in `amounts` of
+    /// each of `token_ids`, on behalf of the caller. Approving an account again replaces its
+    /// approval, with a new approval ID.
+    ///
+    /// Requirements
+    /// * Caller of the method must attach a deposit of at least 1 yoctoⓃ for
+    ///   security purposes
+    /// * Contract MAY require caller to attach larger deposit, to cover cost of
+    ///   storing approver data
+    /// * Contract MUST panic if the caller doesn't hold the amount of each token
+    /// * Contract MUST increment approval ID even if re-approving an account
+    /// * If successfully approved, and if `msg` is present, contract MUST call `mt_on_approve` on
+    ///   `account_id`.
+    ///
+    /// Arguments:
+    /// * `token_ids`: the tokens for which to add an approval
+    /// * `amounts`: the amount of each token to approve
+    /// * `account_id`: the account to approve
+    /// * `msg`: optional string to be passed to `mt_on_approve`
+    ///
+    /// Returns void, if no `msg` given. Otherwise, returns promise call to
+    /// `mt_on_approve`, which can resolve with whatever it wants.
+    fn mt_approve(
+        &mut self,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+        account_id: AccountId,
+        msg: Option<String>,
+    ) -> Option<Promise>;
+
+    /// Revokes the approval of `account_id` for each of `token_ids`, given by the caller.
+    ///
+    /// Requirements
+    /// * Caller of the method must attach a deposit of 1 yoctoⓃ for security
+    ///   purposes
+    /// * Contract MUST refund the storage deposit of the revoked approvals to the caller
+    fn mt_revoke(&mut self, token_ids: Vec<TokenId>, account_id: AccountId);
+
+    /// Revokes every approval given by the caller for each of `token_ids`.
+    ///
+    /// Requirements
+    /// * Caller of the method must attach a deposit of 1 yoctoⓃ for security
+    ///   purposes
+    /// * Contract MUST refund the storage deposit of the revoked approvals to the caller
+    fn mt_revoke_all(&mut self, token_ids: Vec<TokenId>);
+
+    /// Checks whether `approved_account_id` is approved by `owner_id` for at least the amount
+    /// at the same position in `amounts` of each of `token_ids`.
+    ///
+    /// Unlike the other methods, this takes the owner of the tokens: a token is held by any
+    /// number of accounts, each with its own approvals.
+    ///
+    /// Arguments:
+    /// * `owner_id`: the account which gave the approvals
+    /// * `token_ids`: the tokens for which to check an approval
+    /// * `approved_account_id`: the account to check the existence of in the approvals
+    /// * `amounts`: the amount of each token which must be approved
+    /// * `approval_ids`: an optional approval ID for each token, to check against the current
+    ///   approval ID of `approved_account_id`
+    ///
+    /// Returns `true` if `approved_account_id` is approved for every token, otherwise `false`.
+    fn mt_is_approved(
+        &self,
+        owner_id: AccountId,
+        token_ids: Vec<TokenId>,
+        approved_account_id: AccountId,
+        amounts: Vec<U128>,
+        approval_ids: Option<Vec<u64>>,
+    ) -> bool;
+}
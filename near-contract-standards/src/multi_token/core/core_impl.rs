@@ -0,0 +1,641 @@
+use super::resolver::{ConsumedApproval, MultiTokenResolver};
+use crate::limits::{check_memo, check_msg};
+use crate::multi_token::core::MultiTokenCore;
+use crate::multi_token::events::{MtBurn, MtMint, MtTransfer};
+use crate::multi_token::metadata::MtTokenMetadata;
+use crate::multi_token::token::{Approval, Token, TokenId};
+use crate::utils::{measure_storage, refund_storage_delta};
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::{LookupMap, TreeMap, UnorderedSet};
+use near_sdk::json_types::U128;
+use near_sdk::{
+    assert_one_yocto, env, ext_contract, require, AccountId, Balance, BorshStorageKey, Gas,
+    IntoStorageKey, PromiseOrValue, PromiseResult,
+};
+use std::collections::BTreeMap;
+
+const GAS_FOR_RESOLVE_TRANSFER: Gas = Gas(5_000_000_000_000);
+/// Gas added to `GAS_FOR_RESOLVE_TRANSFER` for each token of a batch, which the resolver may
+/// have to return.
+const GAS_FOR_RESOLVE_TRANSFER_PER_TOKEN: Gas = Gas(2_000_000_000_000);
+const GAS_FOR_MT_TRANSFER_CALL: Gas = Gas(25_000_000_000_000);
+
+const NO_DEPOSIT: Balance = 0;
+
+/// Memo of the `mt_transfer` event emitted when `mt_resolve_transfer` returns unused tokens to
+/// their previous owners.
+pub const REFUND_MEMO: &str = "refund";
+
+#[ext_contract(ext_self)]
+trait MTResolver {
+    fn mt_resolve_transfer(
+        &mut self,
+        previous_owner_ids: Vec<AccountId>,
+        receiver_id: AccountId,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+        approvals: Option<Vec<Option<ConsumedApproval>>>,
+    ) -> Vec<U128>;
+}
+
+#[ext_contract(ext_receiver)]
+pub trait MultiTokenReceiver {
+    /// Returns the amount of each token which should be returned to its previous owner.
+    fn mt_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        previous_owner_ids: Vec<AccountId>,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+        msg: String,
+    ) -> PromiseOrValue<Vec<U128>>;
+}
+
+/// Implementation of the multi token standard.
+/// Allows to include NEP-245 compatible tokens to any contract: any number of tokens, each with
+/// its own supply, which can be fungible or, with a supply of one, non-fungible.
+/// There are next traits that any contract may implement:
+///     - MultiTokenCore -- interface with mt_transfer methods. MultiToken provides methods for it.
+///     - MultiTokenApproval -- interface with mt_approve methods. MultiToken provides methods for it.
+///     - MultiTokenEnumeration -- interface for getting lists of tokens. MultiToken provides methods for it.
+///     - MultiTokenMetadata -- return metadata for the tokens. MultiToken provides methods for it.
+///     - MultiTokenMetadataProvider -- return metadata for the contract, up to contract to implement.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct MultiToken {
+    // owner of contract
+    pub owner_id: AccountId,
+
+    // balance of each token held by each account, zero balances aren't stored
+    pub balances: LookupMap<(TokenId, AccountId), Balance>,
+
+    // total supply of each minted token, which keeps existing once its supply is burnt
+    pub total_supply: TreeMap<TokenId, Balance>,
+
+    // required by metadata extension
+    pub token_metadata_by_id: Option<LookupMap<TokenId, MtTokenMetadata>>,
+
+    // required by enumeration extension
+    pub tokens_per_owner: Option<LookupMap<AccountId, UnorderedSet<TokenId>>>,
+
+    // required by approval extension, the approvals given by each owner of each token
+    pub approvals_by_id: Option<LookupMap<(TokenId, AccountId), BTreeMap<AccountId, Approval>>>,
+    pub next_approval_id_by_id: Option<LookupMap<TokenId, u64>>,
+}
+
+/// Storage keys of the tokens of each owner, in the `mt_standard_owners` namespace so that they
+/// can't collide with the storage keys of the contract or of a `NonFungibleToken`.
+#[derive(BorshStorageKey, BorshSerialize)]
+#[storage_key(namespace = "mt_standard_owners")]
+pub enum StorageKey {
+    TokensPerOwner { account_hash: Vec<u8> },
+}
+
+/// Storage keys of [`MultiToken::new_default`], in the `mt_standard` namespace so that they can't
+/// collide with the storage keys of the contract.
+#[derive(BorshStorageKey, BorshSerialize)]
+#[storage_key(namespace = "mt_standard")]
+pub enum MultiTokenStorageKey {
+    Tokens = 0,
+    TokenMetadata = 1,
+    Enumeration = 2,
+    Approvals = 3,
+}
+
+/// Groups `token_ids` and `amounts` by the account at the same position in `account_ids`, in the
+/// order the accounts first appear, for the events of a batch.
+fn group_by_account<'a>(
+    account_ids: &'a [AccountId],
+    token_ids: &'a [TokenId],
+    amounts: &[U128],
+) -> Vec<(&'a AccountId, Vec<&'a str>, Vec<U128>)> {
+    let mut groups: Vec<(&AccountId, Vec<&str>, Vec<U128>)> = Vec::new();
+    for ((account_id, token_id), amount) in account_ids.iter().zip(token_ids).zip(amounts) {
+        match groups.iter_mut().find(|(id, _, _)| *id == account_id) {
+            Some((_, ids, group_amounts)) => {
+                ids.push(token_id);
+                group_amounts.push(*amount);
+            }
+            None => groups.push((account_id, vec![token_id.as_str()], vec![*amount])),
+        }
+    }
+    groups
+}
+
+impl MultiToken {
+    /// Creates a token with the metadata, enumeration and approval extensions, storing them under
+    /// the namespaced [`MultiTokenStorageKey`] prefixes, so the contract can use any other prefix.
+    ///
+    /// The tokens of each owner are still stored under the prefixes of [`StorageKey`], which are
+    /// derived from the hash of the owner.
+    pub fn new_default(owner_id: AccountId) -> Self {
+        Self::new(
+            MultiTokenStorageKey::Tokens,
+            owner_id,
+            Some(MultiTokenStorageKey::TokenMetadata),
+            Some(MultiTokenStorageKey::Enumeration),
+            Some(MultiTokenStorageKey::Approvals),
+        )
+    }
+
+    /// Creates a token storing the balances and the supply of the tokens under `prefix`, and each
+    /// extension under its own prefix, if given.
+    pub fn new<Q, R, S, T>(
+        prefix: Q,
+        owner_id: AccountId,
+        token_metadata_prefix: Option<R>,
+        enumeration_prefix: Option<S>,
+        approval_prefix: Option<T>,
+    ) -> Self
+    where
+        Q: IntoStorageKey,
+        R: IntoStorageKey,
+        S: IntoStorageKey,
+        T: IntoStorageKey,
+    {
+        let prefix = prefix.into_storage_key();
+        let (approvals_by_id, next_approval_id_by_id) = if let Some(prefix) = approval_prefix {
+            let prefix: Vec<u8> = prefix.into_storage_key();
+            (
+                Some(LookupMap::new(prefix.clone())),
+                Some(LookupMap::new([prefix, "n".into()].concat())),
+            )
+        } else {
+            (None, None)
+        };
+
+        Self {
+            owner_id,
+            balances: LookupMap::new([prefix.clone(), "b".into()].concat()),
+            total_supply: TreeMap::new([prefix, "s".into()].concat()),
+            token_metadata_by_id: token_metadata_prefix.map(LookupMap::new),
+            tokens_per_owner: enumeration_prefix.map(LookupMap::new),
+            approvals_by_id,
+            next_approval_id_by_id,
+        }
+    }
+
+    /// Returns the balance of `token_id` held by `account_id`.
+    pub fn internal_balance_of(
+        &self,
+        account_id: &AccountId,
+        #[allow(clippy::ptr_arg)] token_id: &TokenId,
+    ) -> Balance {
+        self.balances.get(&(token_id.clone(), account_id.clone())).unwrap_or(0)
+    }
+
+    /// Adds `amount` of `token_id` to the balance of `account_id`, without changing the supply
+    /// or emitting an event.
+    pub fn internal_deposit(
+        &mut self,
+        account_id: &AccountId,
+        #[allow(clippy::ptr_arg)] token_id: &TokenId,
+        amount: Balance,
+    ) {
+        let balance = self.internal_balance_of(account_id, token_id);
+        let new_balance =
+            balance.checked_add(amount).unwrap_or_else(|| env::panic_str("Balance overflow"));
+        self.balances.insert(&(token_id.clone(), account_id.clone()), &new_balance);
+        if balance == 0 {
+            if let Some(tokens_per_owner) = &mut self.tokens_per_owner {
+                let mut owner_tokens = tokens_per_owner.get(account_id).unwrap_or_else(|| {
+                    UnorderedSet::new(StorageKey::TokensPerOwner {
+                        account_hash: env::sha256(account_id.as_bytes()),
+                    })
+                });
+                owner_tokens.insert(token_id);
+                tokens_per_owner.insert(account_id, &owner_tokens);
+            }
+        }
+    }
+
+    /// Removes `amount` of `token_id` from the balance of `account_id`, without changing the
+    /// supply or emitting an event.
+    ///
+    /// Panics if the balance is less than `amount`.
+    pub fn internal_withdraw(
+        &mut self,
+        account_id: &AccountId,
+        #[allow(clippy::ptr_arg)] token_id: &TokenId,
+        amount: Balance,
+    ) {
+        let balance = self.internal_balance_of(account_id, token_id);
+        let new_balance = balance
+            .checked_sub(amount)
+            .unwrap_or_else(|| env::panic_str("The account doesn't have enough balance"));
+        let key = (token_id.clone(), account_id.clone());
+        if new_balance > 0 {
+            self.balances.insert(&key, &new_balance);
+            return;
+        }
+        self.balances.remove(&key);
+        if let Some(tokens_per_owner) = &mut self.tokens_per_owner {
+            if let Some(mut owner_tokens) = tokens_per_owner.get(account_id) {
+                owner_tokens.remove(token_id);
+                if owner_tokens.is_empty() {
+                    tokens_per_owner.remove(account_id);
+                } else {
+                    tokens_per_owner.insert(account_id, &owner_tokens);
+                }
+            }
+        }
+    }
+
+    /// Mints `amount` of `token_id` to `owner_id` and emits an `mt_mint` event. A token is
+    /// created the first time it's minted, with `token_metadata`, which is required then if the
+    /// metadata extension is used, and ignored for an existing token.
+    ///
+    /// The storage is paid from the attached deposit, and the rest is refunded to the
+    /// predecessor.
+    pub fn internal_mint(
+        &mut self,
+        token_id: TokenId,
+        owner_id: AccountId,
+        amount: Balance,
+        token_metadata: Option<MtTokenMetadata>,
+    ) {
+        require!(amount > 0, "The amount should be a positive number");
+        let ((), storage_delta) = measure_storage(|| {
+            let supply = match self.total_supply.get(&token_id) {
+                Some(supply) => supply,
+                None => {
+                    if let Some(token_metadata_by_id) = &mut self.token_metadata_by_id {
+                        let token_metadata = token_metadata
+                            .unwrap_or_else(|| env::panic_str("Must provide metadata"));
+                        token_metadata.assert_valid();
+                        token_metadata_by_id.insert(&token_id, &token_metadata);
+                    }
+                    0
+                }
+            };
+            let supply = supply
+                .checked_add(amount)
+                .unwrap_or_else(|| env::panic_str("Total supply overflow"));
+            self.total_supply.insert(&token_id, &supply);
+            self.internal_deposit(&owner_id, &token_id, amount);
+        });
+        MtMint {
+            owner_id: &owner_id,
+            token_ids: &[token_id.as_str()],
+            amounts: &[U128(amount)],
+            memo: None,
+        }
+        .emit();
+        refund_storage_delta(storage_delta);
+    }
+
+    /// Burns `amount` of `token_id` held by `owner_id`, and emits an `mt_burn` event. The token
+    /// keeps existing with a lower supply, even if it's zero.
+    ///
+    /// Panics if `owner_id` holds less than `amount` of the token.
+    pub fn internal_burn(
+        &mut self,
+        owner_id: &AccountId,
+        token_id: &TokenId,
+        amount: Balance,
+        memo: Option<&str>,
+    ) {
+        require!(amount > 0, "The amount should be a positive number");
+        self.internal_withdraw(owner_id, token_id, amount);
+        let supply = self.total_supply.get(token_id).unwrap_or(0);
+        self.total_supply.insert(token_id, &(supply - amount));
+        MtBurn {
+            owner_id,
+            token_ids: &[token_id.as_str()],
+            amounts: &[U128(amount)],
+            authorized_id: None,
+            memo,
+        }
+        .emit();
+    }
+
+    /// Transfers `amount` of `token_id` from its owner to `receiver_id`, on behalf of
+    /// `sender_id`. Without `approval`, the tokens of `sender_id` are transferred. Otherwise,
+    /// `approval` is the owner and the approval ID of `sender_id`, which must be approved for at
+    /// least `amount`, and the approved amount is decreased by `amount`.
+    ///
+    /// Returns the owner of the tokens and the approval consumed by the transfer, if any. Doesn't
+    /// emit an event, see [`internal_batch_transfer`](Self::internal_batch_transfer).
+    pub fn internal_transfer(
+        &mut self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        #[allow(clippy::ptr_arg)] token_id: &TokenId,
+        amount: Balance,
+        approval: Option<(AccountId, u64)>,
+    ) -> (AccountId, Option<ConsumedApproval>) {
+        require!(amount > 0, "The amount should be a positive number");
+        let (owner_id, consumed_approval) = match approval {
+            Some((owner_id, approval_id)) if &owner_id != sender_id => {
+                let consumed =
+                    self.internal_use_approval(&owner_id, token_id, sender_id, approval_id, amount);
+                (owner_id, Some(consumed))
+            }
+            _ => (sender_id.clone(), None),
+        };
+        require!(&owner_id != receiver_id, "The owner and the receiver should be different");
+        self.internal_withdraw(&owner_id, token_id, amount);
+        self.internal_deposit(receiver_id, token_id, amount);
+        (owner_id, consumed_approval)
+    }
+
+    /// Transfers the `amounts` of `token_ids` to `receiver_id` like
+    /// [`internal_transfer`](Self::internal_transfer), with the approval at the same position in
+    /// `approvals`, and emits an `mt_transfer` event for each previous owner.
+    ///
+    /// Returns the previous owner of each token and the approval consumed by each transfer.
+    pub fn internal_batch_transfer(
+        &mut self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        token_ids: &[TokenId],
+        amounts: &[U128],
+        approvals: Option<Vec<Option<(AccountId, u64)>>>,
+        memo: Option<&str>,
+    ) -> (Vec<AccountId>, Vec<Option<ConsumedApproval>>) {
+        require!(!token_ids.is_empty(), "At least one token should be transferred");
+        require!(
+            token_ids.len() == amounts.len(),
+            "The number of token IDs and amounts should be the same"
+        );
+        if let Some(approvals) = &approvals {
+            require!(
+                token_ids.len() == approvals.len(),
+                "The number of token IDs and approvals should be the same"
+            );
+        }
+        let mut approvals = approvals.map(Vec::into_iter);
+        let mut previous_owner_ids = Vec::with_capacity(token_ids.len());
+        let mut consumed_approvals = Vec::with_capacity(token_ids.len());
+        for (token_id, amount) in token_ids.iter().zip(amounts) {
+            let approval = approvals.as_mut().and_then(|approvals| approvals.next().flatten());
+            let (owner_id, consumed) =
+                self.internal_transfer(sender_id, receiver_id, token_id, amount.0, approval);
+            previous_owner_ids.push(owner_id);
+            consumed_approvals.push(consumed);
+        }
+
+        let groups = group_by_account(&previous_owner_ids, token_ids, amounts);
+        let events: Vec<MtTransfer> = groups
+            .iter()
+            .map(|(owner_id, ids, amounts)| MtTransfer {
+                old_owner_id: owner_id,
+                new_owner_id: receiver_id,
+                token_ids: ids,
+                amounts,
+                authorized_id: Some(sender_id).filter(|sender_id| sender_id != owner_id),
+                memo,
+            })
+            .collect();
+        MtTransfer::emit_many(&events);
+        (previous_owner_ids, consumed_approvals)
+    }
+
+    /// Transfers the `amounts` of `token_ids` to `receiver_id` like `mt_batch_transfer`, then calls
+    /// `mt_on_transfer` on the receiver, followed by `mt_resolve_transfer` on this contract. The
+    /// gas of the resolver grows with the number of tokens it may have to return.
+    pub fn internal_batch_transfer_call(
+        &mut self,
+        receiver_id: AccountId,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+        approvals: Option<Vec<Option<(AccountId, u64)>>>,
+        memo: Option<String>,
+        msg: String,
+    ) -> PromiseOrValue<Vec<U128>> {
+        assert_one_yocto();
+        let memo = check_memo(memo);
+        let msg = check_msg(msg);
+        let resolve_gas =
+            GAS_FOR_RESOLVE_TRANSFER + GAS_FOR_RESOLVE_TRANSFER_PER_TOKEN * token_ids.len() as u64;
+        let receiver_gas = crate::remaining_gas_after(&[
+            ("mt_transfer_call", GAS_FOR_MT_TRANSFER_CALL),
+            ("mt_resolve_transfer", resolve_gas),
+        ]);
+        let sender_id = env::predecessor_account_id();
+        let (previous_owner_ids, consumed_approvals) = self.internal_batch_transfer(
+            &sender_id,
+            &receiver_id,
+            &token_ids,
+            &amounts,
+            approvals,
+            memo.as_deref(),
+        );
+        let consumed_approvals =
+            Some(consumed_approvals).filter(|approvals| approvals.iter().any(Option::is_some));
+
+        // Initiating receiver's call and the callback
+        ext_receiver::mt_on_transfer(
+            sender_id,
+            previous_owner_ids.clone(),
+            token_ids.clone(),
+            amounts.clone(),
+            msg,
+            receiver_id.clone(),
+            NO_DEPOSIT,
+            receiver_gas,
+        )
+        .then(ext_self::mt_resolve_transfer(
+            previous_owner_ids,
+            receiver_id,
+            token_ids,
+            amounts,
+            consumed_approvals,
+            env::current_account_id(),
+            NO_DEPOSIT,
+            resolve_gas,
+        ))
+        .into()
+    }
+
+    /// Returns the tokens the receiver of `mt_batch_transfer_call` didn't use to their previous
+    /// owners, as much of them as the receiver still holds, and restores the approvals consumed
+    /// by the transfer by the amount returned. The tokens are returned with a single
+    /// `mt_transfer` event for each previous owner, with the [`REFUND_MEMO`].
+    ///
+    /// The receiver uses none of the tokens if it failed, or if the amounts it returns can't be
+    /// deserialized or aren't one for each token.
+    ///
+    /// Returns the amount of each token used by the receiver.
+    pub fn internal_resolve_transfer(
+        &mut self,
+        previous_owner_ids: &[AccountId],
+        receiver_id: &AccountId,
+        token_ids: &[TokenId],
+        amounts: &[U128],
+        approvals: Option<Vec<Option<ConsumedApproval>>>,
+    ) -> Vec<U128> {
+        let unused_amounts: Vec<Balance> = match env::promise_result(0) {
+            PromiseResult::NotReady => env::abort(),
+            PromiseResult::Successful(value) => {
+                match near_sdk::serde_json::from_slice::<Vec<U128>>(&value) {
+                    Ok(unused) if unused.len() == amounts.len() => unused
+                        .iter()
+                        .zip(amounts)
+                        .map(|(unused, amount)| std::cmp::min(unused.0, amount.0))
+                        .collect(),
+                    _ => amounts.iter().map(|amount| amount.0).collect(),
+                }
+            }
+            PromiseResult::Failed => amounts.iter().map(|amount| amount.0).collect(),
+        };
+
+        let mut used_amounts = Vec::with_capacity(amounts.len());
+        let mut refunded_owner_ids = Vec::new();
+        let mut refunded_token_ids = Vec::new();
+        let mut refunded_amounts = Vec::new();
+        for (i, token_id) in token_ids.iter().enumerate() {
+            // The receiver may have transferred or burnt some of the tokens already.
+            let receiver_balance = self.internal_balance_of(receiver_id, token_id);
+            let refund = std::cmp::min(unused_amounts[i], receiver_balance);
+            if refund > 0 {
+                let previous_owner_id = &previous_owner_ids[i];
+                self.internal_withdraw(receiver_id, token_id, refund);
+                self.internal_deposit(previous_owner_id, token_id, refund);
+                if let Some(approval) = approvals.as_ref().and_then(|a| a.get(i).cloned().flatten())
+                {
+                    self.internal_restore_approval(previous_owner_id, token_id, approval, refund);
+                }
+                refunded_owner_ids.push(previous_owner_id.clone());
+                refunded_token_ids.push(token_id.clone());
+                refunded_amounts.push(U128(refund));
+            }
+            used_amounts.push(U128(amounts[i].0 - refund));
+        }
+
+        if !refunded_token_ids.is_empty() {
+            let groups =
+                group_by_account(&refunded_owner_ids, &refunded_token_ids, &refunded_amounts);
+            let events: Vec<MtTransfer> = groups
+                .iter()
+                .map(|(owner_id, ids, amounts)| MtTransfer {
+                    old_owner_id: receiver_id,
+                    new_owner_id: owner_id,
+                    token_ids: ids,
+                    amounts,
+                    authorized_id: None,
+                    memo: Some(REFUND_MEMO),
+                })
+                .collect();
+            MtTransfer::emit_many(&events);
+        }
+        used_amounts
+    }
+}
+
+impl MultiTokenCore for MultiToken {
+    fn mt_transfer(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        amount: U128,
+        approval: Option<(AccountId, u64)>,
+        memo: Option<String>,
+    ) {
+        self.mt_batch_transfer(
+            receiver_id,
+            vec![token_id],
+            vec![amount],
+            approval.map(|approval| vec![Some(approval)]),
+            memo,
+        )
+    }
+
+    fn mt_batch_transfer(
+        &mut self,
+        receiver_id: AccountId,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+        approvals: Option<Vec<Option<(AccountId, u64)>>>,
+        memo: Option<String>,
+    ) {
+        assert_one_yocto();
+        let memo = check_memo(memo);
+        let sender_id = env::predecessor_account_id();
+        self.internal_batch_transfer(
+            &sender_id,
+            &receiver_id,
+            &token_ids,
+            &amounts,
+            approvals,
+            memo.as_deref(),
+        );
+    }
+
+    fn mt_transfer_call(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        amount: U128,
+        approval: Option<(AccountId, u64)>,
+        memo: Option<String>,
+        msg: String,
+    ) -> PromiseOrValue<Vec<U128>> {
+        self.internal_batch_transfer_call(
+            receiver_id,
+            vec![token_id],
+            vec![amount],
+            approval.map(|approval| vec![Some(approval)]),
+            memo,
+            msg,
+        )
+    }
+
+    fn mt_batch_transfer_call(
+        &mut self,
+        receiver_id: AccountId,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+        approvals: Option<Vec<Option<(AccountId, u64)>>>,
+        memo: Option<String>,
+        msg: String,
+    ) -> PromiseOrValue<Vec<U128>> {
+        self.internal_batch_transfer_call(receiver_id, token_ids, amounts, approvals, memo, msg)
+    }
+
+    fn mt_token(&self, token_ids: Vec<TokenId>) -> Vec<Option<Token>> {
+        token_ids
+            .into_iter()
+            .map(|token_id| {
+                self.total_supply.get(&token_id).map(|_| Token { token_id, owner_id: None })
+            })
+            .collect()
+    }
+
+    fn mt_balance_of(&self, account_id: AccountId, token_id: TokenId) -> U128 {
+        self.internal_balance_of(&account_id, &token_id).into()
+    }
+
+    fn mt_batch_balance_of(&self, account_id: AccountId, token_ids: Vec<TokenId>) -> Vec<U128> {
+        token_ids
+            .iter()
+            .map(|token_id| self.internal_balance_of(&account_id, token_id).into())
+            .collect()
+    }
+
+    fn mt_supply(&self, token_id: TokenId) -> Option<U128> {
+        self.total_supply.get(&token_id).map(U128)
+    }
+
+    fn mt_batch_supply(&self, token_ids: Vec<TokenId>) -> Vec<Option<U128>> {
+        token_ids.iter().map(|token_id| self.total_supply.get(token_id).map(U128)).collect()
+    }
+}
+
+impl MultiTokenResolver for MultiToken {
+    fn mt_resolve_transfer(
+        &mut self,
+        previous_owner_ids: Vec<AccountId>,
+        receiver_id: AccountId,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+        approvals: Option<Vec<Option<ConsumedApproval>>>,
+    ) -> Vec<U128> {
+        self.internal_resolve_transfer(
+            &previous_owner_ids,
+            &receiver_id,
+            &token_ids,
+            &amounts,
+            approvals,
+        )
+    }
+}
@@ -0,0 +1,132 @@
+mod core_impl;
+
+mod receiver;
+mod resolver;
+
+pub use self::core_impl::*;
+
+pub use self::receiver::*;
+pub use self::resolver::*;
+
+use crate::multi_token::token::{Token, TokenId};
+use near_sdk::json_types::U128;
+use near_sdk::AccountId;
+use near_sdk::PromiseOrValue;
+
+/// Used for all multi tokens. The specification for the [core multi token standard] lays out the
+/// reasoning for each method. It's important to check out
+/// [MultiTokenReceiver](crate::multi_token::core::MultiTokenReceiver) and
+/// [MultiTokenResolver](crate::multi_token::core::MultiTokenResolver) to understand how the
+/// cross-contract call work.
+///
+/// The batch methods take the token IDs and their amounts as two arrays of the same length, and
+/// the approvals, if any, as a third one.
+///
+/// [core multi token standard]: <https://nomicon.io/Standards/Tokens/MultiToken/Core>
+pub trait MultiTokenCore {
+    /// Simple transfer. Transfer `amount` of `token_id` from the current owner to
+    /// `receiver_id`.
+    ///
+    /// Requirements
+    /// * Caller of the method must attach a deposit of 1 yoctoⓃ for security purposes
+    /// * Contract MUST panic if called by someone other than the owner of the tokens or, if using
+    ///   Approval Management, an account approved for at least `amount`
+    /// * Contract MUST panic if the owner doesn't hold `amount` of the token
+    /// * If using Approval Management, the approved amount is decreased by the transferred amount
+    ///
+    /// Arguments:
+    /// * `receiver_id`: the valid NEAR account receiving the tokens
+    /// * `token_id`: the token to transfer
+    /// * `amount`: the number of tokens to transfer
+    /// * `approval`: the owner of the tokens and the expected approval ID, for use with Approval
+    ///    Management. `None` transfers the tokens of the caller.
+    /// * `memo` (optional): for use cases that may benefit from indexing or
+    ///    providing information for a transfer
+    fn mt_transfer(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        amount: U128,
+        approval: Option<(AccountId, u64)>,
+        memo: Option<String>,
+    );
+
+    /// Transfers the `amounts` of several tokens to `receiver_id`, in one call. Like
+    /// `mt_transfer` for each token, and the whole batch fails if one of them fails.
+    ///
+    /// Arguments:
+    /// * `receiver_id`: the valid NEAR account receiving the tokens
+    /// * `token_ids`: the tokens to transfer
+    /// * `amounts`: the number of tokens to transfer, for each of `token_ids`
+    /// * `approvals`: the owner and the expected approval ID for each of `token_ids`, or `None`
+    ///    for the tokens of the caller
+    /// * `memo` (optional): for use cases that may benefit from indexing or
+    ///    providing information for a transfer
+    fn mt_batch_transfer(
+        &mut self,
+        receiver_id: AccountId,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+        approvals: Option<Vec<Option<(AccountId, u64)>>>,
+        memo: Option<String>,
+    );
+
+    /// Transfer tokens and call a method on a receiver contract. A successful
+    /// workflow will end in a success execution outcome to the callback on the MT
+    /// contract at the method `mt_resolve_transfer`.
+    ///
+    /// Requirements:
+    /// * Like `mt_transfer`
+    /// * The receiving contract must implement `mt_on_transfer` according to the
+    ///   standard. If it does not, MT contract's `mt_resolve_transfer` MUST deal
+    ///   with the resulting failed cross-contract call and roll back the transfer.
+    /// * Contract MUST implement the behavior described in `mt_resolve_transfer`
+    ///
+    /// Arguments:
+    /// * Like `mt_transfer`
+    /// * `msg`: specifies information needed by the receiving contract in
+    ///    order to properly handle the transfer. Can indicate both a function to
+    ///    call and the parameters to pass to that function.
+    ///
+    /// Returns the amount of the token which was used by the receiver, in a list of a single
+    /// amount.
+    fn mt_transfer_call(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        amount: U128,
+        approval: Option<(AccountId, u64)>,
+        memo: Option<String>,
+        msg: String,
+    ) -> PromiseOrValue<Vec<U128>>;
+
+    /// Transfers several tokens like `mt_batch_transfer`, and calls `mt_on_transfer` on the
+    /// receiver once for the whole batch, like `mt_transfer_call`.
+    ///
+    /// Returns the amount of each token which was used by the receiver.
+    #[allow(clippy::too_many_arguments)]
+    fn mt_batch_transfer_call(
+        &mut self,
+        receiver_id: AccountId,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+        approvals: Option<Vec<Option<(AccountId, u64)>>>,
+        memo: Option<String>,
+        msg: String,
+    ) -> PromiseOrValue<Vec<U128>>;
+
+    /// Returns each of `token_ids` in the same order, or `null` for a token which doesn't exist.
+    fn mt_token(&self, token_ids: Vec<TokenId>) -> Vec<Option<Token>>;
+
+    /// Returns the balance of `token_id` held by `account_id`.
+    fn mt_balance_of(&self, account_id: AccountId, token_id: TokenId) -> U128;
+
+    /// Returns the balance of each of `token_ids` held by `account_id`, in the same order.
+    fn mt_batch_balance_of(&self, account_id: AccountId, token_ids: Vec<TokenId>) -> Vec<U128>;
+
+    /// Returns the total supply of `token_id`, or `null` if the token doesn't exist.
+    fn mt_supply(&self, token_id: TokenId) -> Option<U128>;
+
+    /// Returns the total supply of each of `token_ids`, in the same order.
+    fn mt_batch_supply(&self, token_ids: Vec<TokenId>) -> Vec<Option<U128>>;
+}
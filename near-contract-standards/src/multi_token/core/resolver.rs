@@ -0,0 +1,49 @@
+use crate::multi_token::token::TokenId;
+use near_sdk::json_types::U128;
+use near_sdk::AccountId;
+
+/// An approval consumed by a transfer of `mt_transfer_call`: the account which made the transfer
+/// on behalf of the owner, its approval ID, and the amount it was approved for before the
+/// transfer.
+pub type ConsumedApproval = (AccountId, u64, U128);
+
+/// Used when multi tokens are transferred using `mt_transfer_call` or `mt_batch_transfer_call`.
+/// This is the method that's called after `mt_on_transfer`. This trait is implemented on the MT
+/// contract.
+pub trait MultiTokenResolver {
+    /// Finalize an `mt_transfer_call` or `mt_batch_transfer_call` chain of cross-contract calls.
+    ///
+    /// The `mt_transfer_call` process:
+    ///
+    /// 1. Sender calls `mt_transfer_call` on MT contract
+    /// 2. MT contract transfers tokens from sender to receiver
+    /// 3. MT contract calls `mt_on_transfer` on receiver contract
+    /// 4+. [receiver contract may make other cross-contract calls]
+    /// N. MT contract resolves promise chain with `mt_resolve_transfer`, and may
+    ///    return the unused tokens to their previous owners
+    ///
+    /// Requirements:
+    /// * Contract MUST forbid calls to this function by any account except self
+    /// * If promise chain failed, contract MUST revert the whole transfer
+    /// * If promise chain resolves with an amount of each token, contract MUST return that amount
+    ///   of each token to its previous owner, or as much of it as the receiver still holds
+    ///
+    /// Arguments:
+    /// * `previous_owner_ids`: the owner of each token prior to the call to `mt_transfer_call`
+    /// * `receiver_id`: the `receiver_id` argument given to `mt_transfer_call`
+    /// * `token_ids`: the `token_ids` argument given to `mt_batch_transfer_call`
+    /// * `amounts`: the `amounts` argument given to `mt_batch_transfer_call`
+    /// * `approvals`: if using Approval Management, the approval consumed by the transfer of each
+    ///   token, which contract MUST restore by the amount returned to the owner.
+    ///
+    /// Returns the amount of each token which was used by the receiver, in the same order as
+    /// `token_ids`.
+    fn mt_resolve_transfer(
+        &mut self,
+        previous_owner_ids: Vec<AccountId>,
+        receiver_id: AccountId,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+        approvals: Option<Vec<Option<ConsumedApproval>>>,
+    ) -> Vec<U128>;
+}
@@ -0,0 +1,50 @@
+use super::MultiTokenEnumeration;
+use crate::multi_token::token::Token;
+use crate::multi_token::MultiToken;
+use near_sdk::json_types::U128;
+use near_sdk::{env, require, AccountId};
+
+/// Checks the pagination of `len` tokens and returns the index to start from and the number of
+/// tokens to return.
+fn page(len: u64, from_index: Option<U128>, limit: Option<u64>) -> (u64, u64) {
+    let limit = limit.unwrap_or(u64::MAX);
+    require!(limit != 0, "Cannot provide limit of 0.");
+    let start_index: u128 = from_index.map(From::from).unwrap_or_default();
+    require!(len as u128 > start_index, "Out of bounds, please use a smaller from_index.");
+    (start_index as u64, limit)
+}
+
+impl MultiTokenEnumeration for MultiToken {
+    fn mt_tokens(&self, from_index: Option<U128>, limit: Option<u64>) -> Vec<Token> {
+        let (start_index, limit) = page(self.total_supply.len(), from_index, limit);
+        self.total_supply
+            .keys()
+            .skip(start_index as usize)
+            .take(limit as usize)
+            .map(|token_id| Token { token_id, owner_id: None })
+            .collect()
+    }
+
+    fn mt_tokens_for_owner(
+        &self,
+        account_id: AccountId,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<Token> {
+        let tokens_per_owner = self.tokens_per_owner.as_ref().unwrap_or_else(|| {
+            env::panic_str("Could not find tokens_per_owner when calling a method on the enumeration standard.")
+        });
+        let token_set = if let Some(token_set) = tokens_per_owner.get(&account_id) {
+            token_set
+        } else {
+            return vec![];
+        };
+        let (start_index, limit) = page(token_set.len(), from_index, limit);
+        token_set
+            .as_vector()
+            .to_paginated_vec(start_index, limit)
+            .into_iter()
+            .map(|token_id| Token { token_id, owner_id: None })
+            .collect()
+    }
+}
@@ -0,0 +1,38 @@
+mod enumeration_impl;
+
+use crate::multi_token::token::Token;
+use near_sdk::json_types::U128;
+use near_sdk::AccountId;
+
+/// Offers methods to page through every token of the contract, or the tokens held by an account.
+pub trait MultiTokenEnumeration {
+    /// Get a list of all tokens, sorted by token ID
+    ///
+    /// Arguments:
+    /// * `from_index`: a string representing an unsigned 128-bit integer,
+    ///    representing the starting index of tokens to return
+    /// * `limit`: the maximum number of tokens to return
+    ///
+    /// Returns an array of Token objects, as described in Core standard
+    fn mt_tokens(
+        &self,
+        from_index: Option<U128>, // default: "0"
+        limit: Option<u64>,       // default: unlimited (could fail due to gas limit)
+    ) -> Vec<Token>;
+
+    /// Get list of all tokens of which a given account holds a positive balance
+    ///
+    /// Arguments:
+    /// * `account_id`: a valid NEAR account
+    /// * `from_index`: a string representing an unsigned 128-bit integer,
+    ///    representing the starting index of tokens to return
+    /// * `limit`: the maximum number of tokens to return
+    ///
+    /// Returns a paginated list of the tokens held by this account
+    fn mt_tokens_for_owner(
+        &self,
+        account_id: AccountId,
+        from_index: Option<U128>, // default: "0"
+        limit: Option<u64>,       // default: unlimited (could fail due to gas limit)
+    ) -> Vec<Token>;
+}
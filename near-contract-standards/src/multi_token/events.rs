@@ -0,0 +1,251 @@
+//! Standard for nep245 (Multi Token) events.
+//!
+//! These events will be picked up by the NEAR indexer.
+//!
+//! <https://github.com/near/NEPs/blob/master/specs/Standards/Tokens/MultiToken/Events.md>
+//!
+//! This is an extension of the events format (nep-297):
+//! <https://github.com/near/NEPs/blob/master/specs/Standards/EventsFormat.md>
+//!
+//! The three events in this standard are [`MtMint`](crate::multi_token::events::MtMint),
+//! [`MtTransfer`](crate::multi_token::events::MtTransfer), and
+//! [`MtBurn`](crate::multi_token::events::MtBurn). Each of them lists token IDs with the amount of
+//! each token, in the same order.
+//!
+//! These events can be logged by calling `.emit()` on them if a single event, or calling
+//! [`MtMint::emit_many`](crate::multi_token::events::MtMint::emit_many),
+//! [`MtTransfer::emit_many`](crate::multi_token::events::MtTransfer::emit_many), or
+//! [`MtBurn::emit_many`](crate::multi_token::events::MtBurn::emit_many) respectively.
+//!
+//! Their owned counterparts [`MtMintData`](crate::multi_token::events::MtMintData),
+//! [`MtTransferData`](crate::multi_token::events::MtTransferData) and
+//! [`MtBurnData`](crate::multi_token::events::MtBurnData) are what
+//! [`NearEvent::from_log`](crate::event::NearEvent::from_log) parses the logs into.
+
+use crate::event::{emit_events, NearEventData};
+use near_sdk::json_types::U128;
+use near_sdk::AccountId;
+use serde::{Deserialize, Serialize};
+
+/// Data to log for an MT mint event. To log this event, call [`.emit()`](MtMint::emit).
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+pub struct MtMint<'a> {
+    pub owner_id: &'a AccountId,
+    pub token_ids: &'a [&'a str],
+    pub amounts: &'a [U128],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<&'a str>,
+}
+
+impl MtMint<'_> {
+    /// Logs the event to the host. This is required to ensure that the event is triggered
+    /// and to consume the event.
+    pub fn emit(self) {
+        Self::emit_many(&[self])
+    }
+
+    /// Emits an MT mint event, through [`env::log_str`](near_sdk::env::log_str),
+    /// where each [`MtMint`] represents the data of each mint.
+    pub fn emit_many(data: &[MtMint<'_>]) {
+        emit_events(data)
+    }
+}
+
+impl NearEventData for MtMint<'_> {
+    const STANDARD: &'static str = "nep245";
+    const VERSION: &'static str = "1.0.0";
+    const EVENT: &'static str = "mt_mint";
+}
+
+/// Data to log for an MT transfer event. To log this event,
+/// call [`.emit()`](MtTransfer::emit).
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+pub struct MtTransfer<'a> {
+    pub old_owner_id: &'a AccountId,
+    pub new_owner_id: &'a AccountId,
+    pub token_ids: &'a [&'a str],
+    pub amounts: &'a [U128],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub authorized_id: Option<&'a AccountId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<&'a str>,
+}
+
+impl MtTransfer<'_> {
+    /// Logs the event to the host. This is required to ensure that the event is triggered
+    /// and to consume the event.
+    pub fn emit(self) {
+        Self::emit_many(&[self])
+    }
+
+    /// Emits an MT transfer event, through [`env::log_str`](near_sdk::env::log_str),
+    /// where each [`MtTransfer`] represents the data of each transfer.
+    pub fn emit_many(data: &[MtTransfer<'_>]) {
+        emit_events(data)
+    }
+}
+
+impl NearEventData for MtTransfer<'_> {
+    const STANDARD: &'static str = "nep245";
+    const VERSION: &'static str = "1.0.0";
+    const EVENT: &'static str = "mt_transfer";
+}
+
+/// Data to log for an MT burn event. To log this event, call [`.emit()`](MtBurn::emit).
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+pub struct MtBurn<'a> {
+    pub owner_id: &'a AccountId,
+    pub token_ids: &'a [&'a str],
+    pub amounts: &'a [U128],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub authorized_id: Option<&'a AccountId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<&'a str>,
+}
+
+impl MtBurn<'_> {
+    /// Logs the event to the host. This is required to ensure that the event is triggered
+    /// and to consume the event.
+    pub fn emit(self) {
+        Self::emit_many(&[self])
+    }
+
+    /// Emits an MT burn event, through [`env::log_str`](near_sdk::env::log_str),
+    /// where each [`MtBurn`] represents the data of each burn.
+    pub fn emit_many(data: &[MtBurn<'_>]) {
+        emit_events(data)
+    }
+}
+
+impl NearEventData for MtBurn<'_> {
+    const STANDARD: &'static str = "nep245";
+    const VERSION: &'static str = "1.0.0";
+    const EVENT: &'static str = "mt_burn";
+}
+
+/// Owned data of an [`MtMint`] event, as parsed by
+/// [`NearEvent::from_log`](crate::event::NearEvent::from_log).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MtMintData {
+    pub owner_id: AccountId,
+    pub token_ids: Vec<String>,
+    pub amounts: Vec<U128>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+}
+
+/// Owned data of an [`MtTransfer`] event.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MtTransferData {
+    pub old_owner_id: AccountId,
+    pub new_owner_id: AccountId,
+    pub token_ids: Vec<String>,
+    pub amounts: Vec<U128>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub authorized_id: Option<AccountId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+}
+
+/// Owned data of an [`MtBurn`] event.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MtBurnData {
+    pub owner_id: AccountId,
+    pub token_ids: Vec<String>,
+    pub amounts: Vec<U128>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub authorized_id: Option<AccountId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+}
+
+/// An owned nep245 event.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Nep245Event {
+    pub version: String,
+    #[serde(flatten)]
+    pub event_kind: Nep245EventKind,
+}
+
+/// The kind and the data of an owned nep245 event.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "event", content = "data")]
+#[serde(rename_all = "snake_case")]
+#[allow(clippy::enum_variant_names)]
+pub enum Nep245EventKind {
+    MtMint(Vec<MtMintData>),
+    MtTransfer(Vec<MtTransferData>),
+    MtBurn(Vec<MtBurnData>),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::{test_utils, AccountId};
+
+    fn bob() -> AccountId {
+        AccountId::new_unchecked("bob".to_string())
+    }
+
+    fn alice() -> AccountId {
+        AccountId::new_unchecked("alice".to_string())
+    }
+
+    #[test]
+    fn mt_mint() {
+        let owner_id = &bob();
+        MtMint { owner_id, token_ids: &["0", "1"], amounts: &[U128(1), U128(100)], memo: None }
+            .emit();
+        assert_eq!(
+            test_utils::get_logs()[0],
+            r#"EVENT_JSON:{"standard":"nep245","version":"1.0.0","event":"mt_mint","data":[{"owner_id":"bob","token_ids":["0","1"],"amounts":["1","100"]}]}"#
+        );
+    }
+
+    #[test]
+    fn mt_burns() {
+        let owner_id = &bob();
+        MtBurn::emit_many(&[
+            MtBurn {
+                owner_id: &alice(),
+                token_ids: &["0"],
+                amounts: &[U128(20)],
+                authorized_id: Some(owner_id),
+                memo: Some("has memo"),
+            },
+            MtBurn {
+                owner_id,
+                token_ids: &["1"],
+                amounts: &[U128(1)],
+                authorized_id: None,
+                memo: None,
+            },
+        ]);
+        assert_eq!(
+            test_utils::get_logs()[0],
+            r#"EVENT_JSON:{"standard":"nep245","version":"1.0.0","event":"mt_burn","data":[{"owner_id":"alice","token_ids":["0"],"amounts":["20"],"authorized_id":"bob","memo":"has memo"},{"owner_id":"bob","token_ids":["1"],"amounts":["1"]}]}"#
+        );
+    }
+
+    #[test]
+    fn mt_transfer() {
+        let old_owner_id = &bob();
+        let new_owner_id = &alice();
+        MtTransfer {
+            old_owner_id,
+            new_owner_id,
+            token_ids: &["0", "1"],
+            amounts: &[U128(5), U128(1)],
+            authorized_id: None,
+            memo: None,
+        }
+        .emit();
+        assert_eq!(
+            test_utils::get_logs()[0],
+            r#"EVENT_JSON:{"standard":"nep245","version":"1.0.0","event":"mt_transfer","data":[{"old_owner_id":"bob","new_owner_id":"alice","token_ids":["0","1"],"amounts":["5","1"]}]}"#
+        );
+    }
+}
@@ -0,0 +1,297 @@
+/// The core methods for a basic multi token. Extension standards may be added in addition to
+/// this macro.
+///
+/// Takes the name of the contract struct and the inner field for the token.
+#[macro_export]
+macro_rules! impl_multi_token_core {
+    ($contract: ident, $token: ident) => {
+        $crate::__token_field!(
+            $contract.$token: $crate::multi_token::MultiToken,
+            __mt_core_token,
+            __mt_core_token_mut
+        );
+        use $crate::multi_token::core::MultiTokenCore;
+        use $crate::multi_token::core::MultiTokenResolver;
+        $crate::__register_standard!(nep245);
+
+        #[near_bindgen]
+        impl MultiTokenCore for $contract {
+            #[payable]
+            fn mt_transfer(
+                &mut self,
+                receiver_id: AccountId,
+                token_id: $crate::multi_token::TokenId,
+                amount: near_sdk::json_types::U128,
+                approval: Option<(AccountId, u64)>,
+                memo: Option<String>,
+            ) {
+                self.__mt_core_token_mut().mt_transfer(receiver_id, token_id, amount, approval, memo)
+            }
+
+            #[payable]
+            fn mt_batch_transfer(
+                &mut self,
+                receiver_id: AccountId,
+                token_ids: Vec<$crate::multi_token::TokenId>,
+                amounts: Vec<near_sdk::json_types::U128>,
+                approvals: Option<Vec<Option<(AccountId, u64)>>>,
+                memo: Option<String>,
+            ) {
+                self.__mt_core_token_mut().mt_batch_transfer(
+                    receiver_id,
+                    token_ids,
+                    amounts,
+                    approvals,
+                    memo,
+                )
+            }
+
+            #[payable]
+            fn mt_transfer_call(
+                &mut self,
+                receiver_id: AccountId,
+                token_id: $crate::multi_token::TokenId,
+                amount: near_sdk::json_types::U128,
+                approval: Option<(AccountId, u64)>,
+                memo: Option<String>,
+                msg: String,
+            ) -> PromiseOrValue<Vec<near_sdk::json_types::U128>> {
+                self.__mt_core_token_mut().mt_transfer_call(
+                    receiver_id,
+                    token_id,
+                    amount,
+                    approval,
+                    memo,
+                    msg,
+                )
+            }
+
+            #[payable]
+            fn mt_batch_transfer_call(
+                &mut self,
+                receiver_id: AccountId,
+                token_ids: Vec<$crate::multi_token::TokenId>,
+                amounts: Vec<near_sdk::json_types::U128>,
+                approvals: Option<Vec<Option<(AccountId, u64)>>>,
+                memo: Option<String>,
+                msg: String,
+            ) -> PromiseOrValue<Vec<near_sdk::json_types::U128>> {
+                self.__mt_core_token_mut().mt_batch_transfer_call(
+                    receiver_id,
+                    token_ids,
+                    amounts,
+                    approvals,
+                    memo,
+                    msg,
+                )
+            }
+
+            fn mt_token(
+                &self,
+                token_ids: Vec<$crate::multi_token::TokenId>,
+            ) -> Vec<Option<$crate::multi_token::Token>> {
+                self.__mt_core_token().mt_token(token_ids)
+            }
+
+            fn mt_balance_of(
+                &self,
+                account_id: AccountId,
+                token_id: $crate::multi_token::TokenId,
+            ) -> near_sdk::json_types::U128 {
+                self.__mt_core_token().mt_balance_of(account_id, token_id)
+            }
+
+            fn mt_batch_balance_of(
+                &self,
+                account_id: AccountId,
+                token_ids: Vec<$crate::multi_token::TokenId>,
+            ) -> Vec<near_sdk::json_types::U128> {
+                self.__mt_core_token().mt_batch_balance_of(account_id, token_ids)
+            }
+
+            fn mt_supply(
+                &self,
+                token_id: $crate::multi_token::TokenId,
+            ) -> Option<near_sdk::json_types::U128> {
+                self.__mt_core_token().mt_supply(token_id)
+            }
+
+            fn mt_batch_supply(
+                &self,
+                token_ids: Vec<$crate::multi_token::TokenId>,
+            ) -> Vec<Option<near_sdk::json_types::U128>> {
+                self.__mt_core_token().mt_batch_supply(token_ids)
+            }
+        }
+
+        #[near_bindgen]
+        impl MultiTokenResolver for $contract {
+            #[private]
+            fn mt_resolve_transfer(
+                &mut self,
+                previous_owner_ids: Vec<AccountId>,
+                receiver_id: AccountId,
+                token_ids: Vec<$crate::multi_token::TokenId>,
+                amounts: Vec<near_sdk::json_types::U128>,
+                approvals: Option<Vec<Option<$crate::multi_token::core::ConsumedApproval>>>,
+            ) -> Vec<near_sdk::json_types::U128> {
+                self.__mt_core_token_mut().mt_resolve_transfer(
+                    previous_owner_ids,
+                    receiver_id,
+                    token_ids,
+                    amounts,
+                    approvals,
+                )
+            }
+        }
+    };
+}
+
+/// Multi token approval management allows accounts to transfer up to an approved amount of the
+/// tokens of an owner.
+#[macro_export]
+macro_rules! impl_multi_token_approval {
+    ($contract: ident, $token: ident) => {
+        $crate::__token_field!(
+            $contract.$token: $crate::multi_token::MultiToken,
+            __mt_approval_token,
+            __mt_approval_token_mut
+        );
+        use $crate::multi_token::approval::MultiTokenApproval;
+
+        #[near_bindgen]
+        impl MultiTokenApproval for $contract {
+            #[payable]
+            fn mt_approve(
+                &mut self,
+                token_ids: Vec<$crate::multi_token::TokenId>,
+                amounts: Vec<near_sdk::json_types::U128>,
+                account_id: AccountId,
+                msg: Option<String>,
+            ) -> Option<Promise> {
+                self.__mt_approval_token_mut().mt_approve(token_ids, amounts, account_id, msg)
+            }
+
+            #[payable]
+            fn mt_revoke(
+                &mut self,
+                token_ids: Vec<$crate::multi_token::TokenId>,
+                account_id: AccountId,
+            ) {
+                self.__mt_approval_token_mut().mt_revoke(token_ids, account_id)
+            }
+
+            #[payable]
+            fn mt_revoke_all(&mut self, token_ids: Vec<$crate::multi_token::TokenId>) {
+                self.__mt_approval_token_mut().mt_revoke_all(token_ids)
+            }
+
+            fn mt_is_approved(
+                &self,
+                owner_id: AccountId,
+                token_ids: Vec<$crate::multi_token::TokenId>,
+                approved_account_id: AccountId,
+                amounts: Vec<near_sdk::json_types::U128>,
+                approval_ids: Option<Vec<u64>>,
+            ) -> bool {
+                self.__mt_approval_token().mt_is_approved(
+                    owner_id,
+                    token_ids,
+                    approved_account_id,
+                    amounts,
+                    approval_ids,
+                )
+            }
+        }
+    };
+}
+
+/// Multi token enumeration adds view-only methods to page through every token, or the tokens
+/// held by an account. The token must be created with an enumeration prefix to list the tokens
+/// of an account.
+#[macro_export]
+macro_rules! impl_multi_token_enumeration {
+    ($contract: ident, $token: ident) => {
+        $crate::__token_field!(
+            $contract.$token: $crate::multi_token::MultiToken,
+            __mt_enumeration_token
+        );
+        use $crate::multi_token::enumeration::MultiTokenEnumeration;
+
+        #[near_bindgen]
+        impl MultiTokenEnumeration for $contract {
+            fn mt_tokens(
+                &self,
+                from_index: Option<near_sdk::json_types::U128>,
+                limit: Option<u64>,
+            ) -> Vec<$crate::multi_token::Token> {
+                self.__mt_enumeration_token().mt_tokens(from_index, limit)
+            }
+
+            fn mt_tokens_for_owner(
+                &self,
+                account_id: AccountId,
+                from_index: Option<near_sdk::json_types::U128>,
+                limit: Option<u64>,
+            ) -> Vec<$crate::multi_token::Token> {
+                self.__mt_enumeration_token().mt_tokens_for_owner(account_id, from_index, limit)
+            }
+        }
+    };
+}
+
+/// Multi token metadata exposes `mt_metadata_contract` and `mt_supports_spec`, returning the
+/// contract metadata stored in `$metadata`, and `mt_metadata_token_by_token_id`, returning the
+/// metadata of the tokens of `$token`. The `$metadata` field can be a
+/// `LazyOption<MtContractMetadata>` or any other type implementing `MultiTokenMetadataProvider`.
+/// `mt_metadata_contract` panics if the stored metadata isn't valid.
+#[macro_export]
+macro_rules! impl_multi_token_metadata {
+    ($contract: ident, $token: ident, $metadata: ident) => {
+        $crate::__token_field!(
+            $contract.$token: $crate::multi_token::MultiToken,
+            __mt_metadata_token
+        );
+        $crate::__token_field!(
+            $contract.$metadata: dyn $crate::multi_token::metadata::MultiTokenMetadataProvider,
+            __mt_metadata_provider
+        );
+        use $crate::multi_token::metadata::{MultiTokenMetadata, MultiTokenMetadataProvider};
+
+        #[near_bindgen]
+        impl MultiTokenMetadataProvider for $contract {
+            fn mt_metadata_contract(&self) -> $crate::multi_token::metadata::MtContractMetadata {
+                let metadata = self.__mt_metadata_provider().mt_metadata_contract();
+                metadata.assert_valid();
+                metadata
+            }
+
+            fn mt_supports_spec(&self, spec: String) -> bool {
+                self.__mt_metadata_provider().mt_supports_spec(spec)
+            }
+        }
+
+        #[near_bindgen]
+        impl MultiTokenMetadata for $contract {
+            fn mt_metadata_token_by_token_id(
+                &self,
+                token_ids: Vec<$crate::multi_token::TokenId>,
+            ) -> Vec<Option<$crate::multi_token::metadata::MtTokenMetadata>> {
+                self.__mt_metadata_token().mt_metadata_token_by_token_id(token_ids)
+            }
+        }
+    };
+}
+
+/// Implements the core, approval management, enumeration and metadata parts of the multi token
+/// standard, for a `MultiToken` stored in `$token` and its contract metadata stored in
+/// `$metadata`. See [`impl_multi_token_metadata!`] for the types `$metadata` can have.
+#[macro_export]
+macro_rules! impl_multi_token {
+    ($contract: ident, $token: ident, $metadata: ident) => {
+        $crate::impl_multi_token_core!($contract, $token);
+        $crate::impl_multi_token_approval!($contract, $token);
+        $crate::impl_multi_token_enumeration!($contract, $token);
+        $crate::impl_multi_token_metadata!($contract, $token, $metadata);
+    };
+}
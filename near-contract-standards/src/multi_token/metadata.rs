@@ -0,0 +1,167 @@
+use crate::multi_token::token::TokenId;
+use crate::multi_token::MultiToken;
+use crate::spec::{assert_supported_spec, supports_spec};
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::LazyOption;
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, require};
+
+/// This spec can be treated like a version of the standard.
+pub const MT_METADATA_SPEC: &str = "mt-1.0.0";
+
+/// Metadata for the multi token contract itself.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MtContractMetadata {
+    pub spec: String, // required, essentially a version like "mt-1.0.0"
+    pub name: String, // required, ex. "Zoink's Digitial Sword Collection"
+}
+
+/// Metadata of a token of the contract.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MtTokenMetadata {
+    pub title: Option<String>, // ex. "Arch Nemesis: Mail Carrier" or "Parcel #5055"
+    pub description: Option<String>, // free-form description
+    pub media: Option<String>, // URL to associated media, preferably to decentralized, content-addressed storage
+    pub media_hash: Option<Base64VecU8>, // Base64-encoded sha256 hash of content referenced by the `media` field. Required if `media` is included.
+    pub issued_at: Option<String>, // When token was issued or minted, Unix epoch in milliseconds
+    pub expires_at: Option<String>, // When token expires, Unix epoch in milliseconds
+    pub starts_at: Option<String>, // When token starts being valid, Unix epoch in milliseconds
+    pub updated_at: Option<String>, // When token was last updated, Unix epoch in milliseconds
+    pub extra: Option<String>, // Anything extra the MT wants to store on-chain. Can be stringified JSON.
+    pub reference: Option<String>, // URL to an off-chain JSON file with more info.
+    pub reference_hash: Option<Base64VecU8>, // Base64-encoded sha256 hash of JSON from reference field. Required if `reference` is included.
+}
+
+/// Offers details on the contract-level metadata.
+pub trait MultiTokenMetadataProvider {
+    fn mt_metadata_contract(&self) -> MtContractMetadata;
+
+    /// Returns `true` if the contract implements a version of the metadata standard compatible
+    /// with `spec`, see [`SpecVersion::is_compatible_with`](crate::spec::SpecVersion::is_compatible_with).
+    fn mt_supports_spec(&self, spec: String) -> bool {
+        self.mt_metadata_contract().supports_spec(&spec)
+    }
+}
+
+/// Offers the metadata of the tokens. Implemented by
+/// [`MultiToken`](crate::multi_token::MultiToken), from the metadata given when minting.
+pub trait MultiTokenMetadata {
+    /// Returns the metadata of each of `token_ids`, in the same order, or `null` for a token
+    /// which doesn't exist.
+    fn mt_metadata_token_by_token_id(
+        &self,
+        token_ids: Vec<TokenId>,
+    ) -> Vec<Option<MtTokenMetadata>>;
+}
+
+impl MultiTokenMetadataProvider for LazyOption<MtContractMetadata> {
+    fn mt_metadata_contract(&self) -> MtContractMetadata {
+        self.get()
+            .unwrap_or_else(|| env::panic_str("The contract metadata is missing from storage"))
+    }
+}
+
+impl MultiTokenMetadata for MultiToken {
+    fn mt_metadata_token_by_token_id(
+        &self,
+        token_ids: Vec<TokenId>,
+    ) -> Vec<Option<MtTokenMetadata>> {
+        let token_metadata_by_id = self
+            .token_metadata_by_id
+            .as_ref()
+            .unwrap_or_else(|| env::panic_str("MT does not support the metadata of the tokens"));
+        token_ids.iter().map(|token_id| token_metadata_by_id.get(token_id)).collect()
+    }
+}
+
+impl MtContractMetadata {
+    /// Panics if the spec isn't a version of [`MT_METADATA_SPEC`] with the same major version, or
+    /// the name is empty.
+    pub fn assert_valid(&self) {
+        assert_supported_spec(&self.spec, MT_METADATA_SPEC, "Spec is not MT metadata");
+        require!(!self.name.is_empty(), "Name can't be empty");
+    }
+
+    /// Returns `true` if the spec of this metadata is compatible with `spec`. Malformed specs are
+    /// not supported.
+    pub fn supports_spec(&self, spec: &str) -> bool {
+        supports_spec(&self.spec, spec)
+    }
+}
+
+impl MtTokenMetadata {
+    /// Panics if a hash is given without the content it's the hash of, or the other way around,
+    /// or if a hash isn't 32 bytes.
+    pub fn assert_valid(&self) {
+        require!(
+            self.media.is_some() == self.media_hash.is_some(),
+            "Media hash is required with media"
+        );
+        if let Some(media_hash) = &self.media_hash {
+            require!(media_hash.0.len() == 32, "Media hash has to be 32 bytes");
+        }
+
+        require!(
+            self.reference.is_some() == self.reference_hash.is_some(),
+            "Reference hash is required with a reference"
+        );
+        if let Some(reference_hash) = &self.reference_hash {
+            require!(reference_hash.0.len() == 32, "Reference hash has to be 32 bytes");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token_metadata() -> MtTokenMetadata {
+        MtTokenMetadata {
+            title: Some("Sword".to_string()),
+            description: None,
+            media: Some("https://example.com/sword.png".to_string()),
+            media_hash: Some(Base64VecU8(vec![0; 32])),
+            issued_at: None,
+            expires_at: None,
+            starts_at: None,
+            updated_at: None,
+            extra: None,
+            reference: None,
+            reference_hash: None,
+        }
+    }
+
+    #[test]
+    fn valid_metadata() {
+        MtContractMetadata { spec: MT_METADATA_SPEC.to_string(), name: "Items".to_string() }
+            .assert_valid();
+        token_metadata().assert_valid();
+    }
+
+    #[test]
+    #[should_panic(expected = "Spec is not MT metadata")]
+    fn nft_spec() {
+        MtContractMetadata { spec: "nft-1.0.0".to_string(), name: "Items".to_string() }
+            .assert_valid();
+    }
+
+    #[test]
+    #[should_panic(expected = "Media hash is required with media")]
+    fn media_without_hash() {
+        MtTokenMetadata { media_hash: None, ..token_metadata() }.assert_valid();
+    }
+
+    #[test]
+    #[should_panic(expected = "Reference hash has to be 32 bytes")]
+    fn short_reference_hash() {
+        MtTokenMetadata {
+            reference: Some("https://example.com/sword.json".to_string()),
+            reference_hash: Some(Base64VecU8(vec![0; 31])),
+            ..token_metadata()
+        }
+        .assert_valid();
+    }
+}
@@ -0,0 +1,22 @@
+/// The approval management part of the [multi token standard](https://nomicon.io/Standards/Tokens/MultiToken/ApprovalManagement),
+/// approving accounts to transfer up to an amount of a token on behalf of its owner.
+pub mod approval;
+/// The [core multi token standard](https://nomicon.io/Standards/Tokens/MultiToken/Core), with
+/// single and batch transfers of fungible and non-fungible tokens held by one contract.
+pub mod core;
+/// The enumeration part of the [multi token standard](https://nomicon.io/Standards/Tokens/MultiToken/Enumeration),
+/// paging through every token or the tokens of an owner.
+pub mod enumeration;
+/// Events of the multi token standard.
+pub mod events;
+/// Macros typically used by a contract wanting to take advantage of the multi token NEAR contract
+/// standard approach.
+mod macros;
+/// Metadata traits and implementation according to the [multi token metadata standard](https://nomicon.io/Standards/Tokens/MultiToken/Metadata).
+pub mod metadata;
+/// The Token struct for the multi token.
+mod token;
+pub use self::token::{Approval, Token, TokenId};
+
+pub use self::core::MultiToken;
+pub use macros::*;
@@ -0,0 +1,28 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::AccountId;
+
+/// Token IDs of multi tokens are strings, like the IDs of non-fungible tokens.
+pub type TokenId = String;
+
+/// A token of a multi token contract, as returned by `mt_token` and the enumeration methods.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Token {
+    pub token_id: TokenId,
+    /// The owner of a non-fungible token. Tokens of this implementation can have any supply and
+    /// be held by many accounts, so it's always `None`, and the balances are read with
+    /// `mt_balance_of`.
+    pub owner_id: Option<AccountId>,
+}
+
+/// An approval of an account to transfer up to `amount` of a token of the owner.
+#[derive(
+    Debug, Clone, Copy, Serialize, Deserialize, PartialEq, BorshDeserialize, BorshSerialize,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Approval {
+    pub approval_id: u64,
+    pub amount: U128,
+}
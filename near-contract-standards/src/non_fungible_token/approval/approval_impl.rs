@@ -1,17 +1,37 @@
 /// Common implementation of the [approval management standard](https://nomicon.io/Standards/NonFungibleToken/ApprovalManagement.html) for NFTs.
 /// on the contract/account that has just been approved. This is not required to implement.
-use crate::non_fungible_token::approval::NonFungibleTokenApproval;
+use crate::non_fungible_token::approval::{
+    NonFungibleTokenApproval, NonFungibleTokenRevokeAllContinuation,
+};
 use crate::non_fungible_token::token::TokenId;
 use crate::non_fungible_token::utils::{
-    assert_at_least_one_yocto, bytes_for_approved_account_id, refund_approved_account_ids,
-    refund_approved_account_ids_iter, refund_deposit,
+    assert_at_least_one_yocto, bytes_for_approved_account_id, refund_approved_account_ids_iter,
+    refund_deposit,
 };
 use crate::non_fungible_token::NonFungibleToken;
-use near_sdk::{assert_one_yocto, env, ext_contract, require, AccountId, Balance, Gas, Promise};
+use near_sdk::{env, ext_contract, require, AccountId, Balance, CallContext, Gas, Promise};
 
 const GAS_FOR_NFT_APPROVE: Gas = Gas(10_000_000_000_000);
+/// Gas kept by `nft_revoke_all` when scheduling `nft_revoke_all_continue`, which gets the rest.
+/// It pays for the function call action.
+const GAS_FOR_SCHEDULING_CONTINUE: Gas = Gas(10_000_000_000_000);
+/// Minimum gas attached to `nft_revoke_all_continue`, enough to revoke at least one approval and
+/// save the approvals left.
+const MIN_GAS_FOR_NFT_REVOKE_ALL_CONTINUE: Gas = Gas(10_000_000_000_000);
+/// Gas left unused by the loop of `nft_revoke_all`, to refund the approvals it revoked, save the
+/// ones it didn't and schedule `nft_revoke_all_continue` for them.
+const GAS_FOR_NFT_REVOKE_ALL_CONTINUE: Gas =
+    Gas(GAS_FOR_SCHEDULING_CONTINUE.0 + MIN_GAS_FOR_NFT_REVOKE_ALL_CONTINUE.0 + 5_000_000_000_000);
 const NO_DEPOSIT: Balance = 0;
 
+/// Default maximum number of accounts approved for a single token. `nft_revoke_all` revokes this
+/// many approvals well within the gas of a single call.
+pub const DEFAULT_MAX_APPROVALS_PER_TOKEN: u64 = 32;
+
+/// Extension key of the maximum number of approvals, see
+/// [`NonFungibleToken::max_approvals_per_token`].
+const MAX_APPROVALS_EXTENSION: u8 = b'm';
+
 fn expect_token_found<T>(option: Option<T>) -> T {
     option.unwrap_or_else(|| env::panic_str("Token not found"))
 }
@@ -20,6 +40,16 @@ fn expect_approval<T>(option: Option<T>) -> T {
     option.unwrap_or_else(|| env::panic_str("next_approval_by_id must be set for approval ext"))
 }
 
+#[ext_contract(ext_self)]
+trait NFTRevokeAllContinuation {
+    fn nft_revoke_all_continue(
+        &mut self,
+        token_id: TokenId,
+        owner_id: AccountId,
+        next_approval_id: u64,
+    );
+}
+
 #[ext_contract(ext_approval_receiver)]
 pub trait NonFungibleTokenReceiver {
     fn nft_on_approve(
@@ -31,6 +61,90 @@ pub trait NonFungibleTokenReceiver {
     );
 }
 
+/// Panics unless the predecessor of `ctx` is `owner_id`, as approvals can only be changed by the
+/// owner of the token.
+fn assert_approval_owner(ctx: &CallContext, owner_id: &AccountId) {
+    require!(&ctx.predecessor_account_id == owner_id, "Predecessor must be token owner.");
+}
+
+impl NonFungibleToken {
+    /// Sets the maximum number of accounts approved for a single token, which is
+    /// [`DEFAULT_MAX_APPROVALS_PER_TOKEN`] by default. `nft_approve` panics instead of approving
+    /// another account once a token has this many approvals, so that `nft_revoke_all` stays
+    /// within the gas limit as the standard requires.
+    ///
+    /// Lowering the maximum doesn't revoke existing approvals, tokens over it just can't approve
+    /// new accounts.
+    pub fn with_max_approvals_per_token(mut self, max_approvals_per_token: u64) -> Self {
+        self.write_extension(MAX_APPROVALS_EXTENSION, &max_approvals_per_token);
+        self
+    }
+
+    /// Returns the maximum number of accounts approved for a single token, see
+    /// [`with_max_approvals_per_token`](Self::with_max_approvals_per_token). It's stored outside
+    /// of the token, so that setting it doesn't change its layout.
+    pub fn max_approvals_per_token(&self) -> u64 {
+        self.read_extension(MAX_APPROVALS_EXTENSION).unwrap_or(DEFAULT_MAX_APPROVALS_PER_TOKEN)
+    }
+
+    /// Revokes every approval of `token_id` and refunds their storage to `owner_id`.
+    ///
+    /// The approvals are revoked one at a time until the gas used gets within
+    /// `GAS_FOR_NFT_REVOKE_ALL_CONTINUE` of the prepaid gas. The approvals left are then saved and
+    /// `nft_revoke_all_continue` is scheduled on this contract with the remaining gas to revoke
+    /// them, so that revoking can't fail by running out of gas. At least one approval is revoked
+    /// by each call. Panics if the gas left can't cover `MIN_GAS_FOR_NFT_REVOKE_ALL_CONTINUE`.
+    fn internal_revoke_all(&mut self, token_id: TokenId, owner_id: AccountId) {
+        let approvals_by_id = self.approvals_by_id.as_mut().unwrap_or_else(|| {
+            env::panic_str("NFT does not support Approval Management");
+        });
+
+        // if token has no approvals, do nothing
+        let mut approved_account_ids = match approvals_by_id.get(&token_id) {
+            Some(approved_account_ids) => approved_account_ids,
+            None => return,
+        };
+
+        let gas_limit = env::prepaid_gas().0.saturating_sub(GAS_FOR_NFT_REVOKE_ALL_CONTINUE.0);
+        let mut revoked = Vec::new();
+        while let Some(account_id) = approved_account_ids.keys().next().cloned() {
+            approved_account_ids.remove(&account_id);
+            revoked.push(account_id);
+            if env::used_gas().0 >= gas_limit {
+                break;
+            }
+        }
+        // refund owner for storage costs of the revoked approvals
+        refund_approved_account_ids_iter(owner_id.clone(), revoked.iter());
+
+        if approved_account_ids.is_empty() {
+            // remove whole map of approvals
+            approvals_by_id.remove(&token_id);
+        } else {
+            // save the approvals left and revoke them in a new call
+            approvals_by_id.insert(&token_id, &approved_account_ids);
+            // Revoking continues with the gas left, which must be enough to make progress.
+            let gas = Gas::remaining_after(&[GAS_FOR_SCHEDULING_CONTINUE])
+                .ok()
+                .filter(|&gas| gas >= MIN_GAS_FOR_NFT_REVOKE_ALL_CONTINUE)
+                .unwrap_or_else(|| {
+                    env::panic_str("Not enough gas left to continue revoking the approvals")
+                });
+            // Approving an account changes the next approval ID, which stops the continuation.
+            let next_approval_id =
+                expect_approval(self.next_approval_id_by_id.as_ref()).get(&token_id).unwrap_or(1);
+            ext_self::nft_revoke_all_continue(
+                token_id,
+                owner_id,
+                next_approval_id,
+                env::current_account_id(),
+                NO_DEPOSIT,
+                gas,
+            );
+        }
+    }
+}
+
 impl NonFungibleTokenApproval for NonFungibleToken {
     fn nft_approve(
         &mut self,
@@ -41,21 +155,34 @@ impl NonFungibleTokenApproval for NonFungibleToken {
         assert_at_least_one_yocto();
         let approvals_by_id = self
             .approvals_by_id
-            .as_mut()
+            .as_ref()
             .unwrap_or_else(|| env::panic_str("NFT does not support Approval Management"));
 
+        let ctx = CallContext::from_env();
         let owner_id = expect_token_found(self.owner_by_id.get(&token_id));
+        assert_approval_owner(&ctx, &owner_id);
 
-        require!(env::predecessor_account_id() == owner_id, "Predecessor must be token owner.");
-
+        // update map of approvals for this token
+        let mut approved_account_ids = approvals_by_id.get(&token_id).unwrap_or_default();
+        // Approving an account again replaces its approval, so only new accounts count, and the
+        // maximum is only read for them.
+        if !approved_account_ids.contains_key(&account_id) {
+            let max_approvals_per_token = self.max_approvals_per_token();
+            require!(
+                (approved_account_ids.len() as u64) < max_approvals_per_token,
+                format!(
+                    "Token {} already has the maximum of {} approvals, revoke one first",
+                    token_id, max_approvals_per_token
+                )
+            );
+        }
         let next_approval_id_by_id = expect_approval(self.next_approval_id_by_id.as_mut());
-        // update HashMap of approvals for this token
-        let approved_account_ids = &mut approvals_by_id.get(&token_id).unwrap_or_default();
         let approval_id: u64 = next_approval_id_by_id.get(&token_id).unwrap_or(1u64);
         let old_approval_id = approved_account_ids.insert(account_id.clone(), approval_id);
 
-        // save updated approvals HashMap to contract's LookupMap
-        approvals_by_id.insert(&token_id, approved_account_ids);
+        // save updated approvals map to contract's LookupMap
+        // `approvals_by_id` was checked above.
+        self.approvals_by_id.as_mut().unwrap().insert(&token_id, &approved_account_ids);
 
         // increment next_approval_id for this token
         next_approval_id_by_id.insert(&token_id, &(approval_id + 1));
@@ -69,6 +196,7 @@ impl NonFungibleTokenApproval for NonFungibleToken {
 
         // if given `msg`, schedule call to `nft_on_approve` and return it. Else, return None.
         msg.map(|msg| {
+            let receiver_gas = crate::remaining_gas_after(&[("nft_approve", GAS_FOR_NFT_APPROVE)]);
             ext_approval_receiver::nft_on_approve(
                 token_id,
                 owner_id,
@@ -76,35 +204,31 @@ impl NonFungibleTokenApproval for NonFungibleToken {
                 msg,
                 account_id,
                 NO_DEPOSIT,
-                env::prepaid_gas() - GAS_FOR_NFT_APPROVE,
+                receiver_gas,
             )
         })
     }
 
     fn nft_revoke(&mut self, token_id: TokenId, account_id: AccountId) {
-        assert_one_yocto();
+        let ctx = CallContext::from_env();
+        ctx.assert_one_yocto();
         let approvals_by_id = self.approvals_by_id.as_mut().unwrap_or_else(|| {
             env::panic_str("NFT does not support Approval Management");
         });
 
         let owner_id = expect_token_found(self.owner_by_id.get(&token_id));
-        let predecessor_account_id = env::predecessor_account_id();
-
-        require!(predecessor_account_id == owner_id, "Predecessor must be token owner.");
+        assert_approval_owner(&ctx, &owner_id);
 
         // if token has no approvals, do nothing
         if let Some(approved_account_ids) = &mut approvals_by_id.get(&token_id) {
             // if account_id was already not approved, do nothing
             if approved_account_ids.remove(&account_id).is_some() {
-                refund_approved_account_ids_iter(
-                    predecessor_account_id,
-                    core::iter::once(&account_id),
-                );
-                // if this was the last approval, remove the whole HashMap to save space.
+                refund_approved_account_ids_iter(owner_id, core::iter::once(&account_id));
+                // if this was the last approval, remove the whole map to save space.
                 if approved_account_ids.is_empty() {
                     approvals_by_id.remove(&token_id);
                 } else {
-                    // otherwise, update approvals_by_id with updated HashMap
+                    // otherwise, update approvals_by_id with updated map
                     approvals_by_id.insert(&token_id, approved_account_ids);
                 }
             }
@@ -112,23 +236,14 @@ impl NonFungibleTokenApproval for NonFungibleToken {
     }
 
     fn nft_revoke_all(&mut self, token_id: TokenId) {
-        assert_one_yocto();
-        let approvals_by_id = self.approvals_by_id.as_mut().unwrap_or_else(|| {
-            env::panic_str("NFT does not support Approval Management");
-        });
+        let ctx = CallContext::from_env();
+        ctx.assert_one_yocto();
+        require!(self.approvals_by_id.is_some(), "NFT does not support Approval Management");
 
         let owner_id = expect_token_found(self.owner_by_id.get(&token_id));
-        let predecessor_account_id = env::predecessor_account_id();
-
-        require!(predecessor_account_id == owner_id, "Predecessor must be token owner.");
+        assert_approval_owner(&ctx, &owner_id);
 
-        // if token has no approvals, do nothing
-        if let Some(approved_account_ids) = &mut approvals_by_id.get(&token_id) {
-            // otherwise, refund owner for storage costs of all approvals...
-            refund_approved_account_ids(predecessor_account_id, approved_account_ids);
-            // ...and remove whole HashMap of approvals
-            approvals_by_id.remove(&token_id);
-        }
+        self.internal_revoke_all(token_id, owner_id);
     }
 
     fn nft_is_approved(
@@ -156,7 +271,7 @@ impl NonFungibleTokenApproval for NonFungibleToken {
         let actual_approval_id = if let Some(id) = approved_account_ids.get(&approved_account_id) {
             id
         } else {
-            // account not in approvals HashMap
+            // account not in approvals map
             return false;
         };
 
@@ -168,3 +283,298 @@ impl NonFungibleTokenApproval for NonFungibleToken {
         }
     }
 }
+
+impl NonFungibleTokenRevokeAllContinuation for NonFungibleToken {
+    fn nft_revoke_all_continue(
+        &mut self,
+        token_id: TokenId,
+        owner_id: AccountId,
+        next_approval_id: u64,
+    ) {
+        // The approvals were cleared if the token changed hands in the meantime, and any approval
+        // set since, by a new owner or by the owner again, must not be revoked.
+        if self.owner_by_id.get(&token_id).as_ref() != Some(&owner_id) {
+            return;
+        }
+        let next_approval_id_by_id = expect_approval(self.next_approval_id_by_id.as_ref());
+        if next_approval_id_by_id.get(&token_id).unwrap_or(1) != next_approval_id {
+            return;
+        }
+        self.internal_revoke_all(token_id, owner_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::borsh::{BorshDeserialize, BorshSerialize};
+    use near_sdk::mock::{with_mocked_blockchain, VmAction};
+    use near_sdk::test_utils::{accounts, get_created_receipts, VMContextBuilder};
+    use near_sdk::{testing_env, ONE_NEAR, ONE_YOCTO};
+
+    const TOKEN_ID: &str = "t";
+
+    fn set_context(predecessor: AccountId, attached_deposit: Balance, prepaid_gas: Gas) {
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(accounts(0))
+            .predecessor_account_id(predecessor)
+            .attached_deposit(attached_deposit)
+            .prepaid_gas(prepaid_gas)
+            .build());
+    }
+
+    /// Mints a token to `accounts(1)` and approves `accounts(2)` to `accounts(5)`.
+    fn approved_token() -> NonFungibleToken {
+        set_context(accounts(0), ONE_NEAR, Gas(300 * Gas::ONE_TERA.0));
+        let mut token = NonFungibleToken::new(
+            b"o".to_vec(),
+            accounts(0),
+            None::<Vec<u8>>,
+            None::<Vec<u8>>,
+            Some(b"a".to_vec()),
+        );
+        token.internal_mint(TOKEN_ID.to_string(), accounts(1), None);
+
+        set_context(accounts(1), ONE_NEAR, Gas(300 * Gas::ONE_TERA.0));
+        for i in 2..6 {
+            token.nft_approve(TOKEN_ID.to_string(), accounts(i), None);
+        }
+        token
+    }
+
+    fn approval_count(token: &NonFungibleToken) -> usize {
+        (2..6).filter(|&i| token.nft_is_approved(TOKEN_ID.to_string(), accounts(i), None)).count()
+    }
+
+    fn next_approval_id(token: &NonFungibleToken) -> u64 {
+        token.next_approval_id_by_id.as_ref().unwrap().get(&TOKEN_ID.to_string()).unwrap()
+    }
+
+    /// Returns the gas attached to the `nft_revoke_all_continue` call scheduled in this context.
+    fn scheduled_continuation() -> Option<Gas> {
+        get_created_receipts()
+            .into_iter()
+            .filter(|receipt| receipt.receiver_id == accounts(0))
+            .find_map(|receipt| {
+                receipt.actions.into_iter().find_map(|action| match action {
+                    VmAction::FunctionCall { function_name, gas, .. }
+                        if function_name == "nft_revoke_all_continue" =>
+                    {
+                        Some(gas)
+                    }
+                    _ => None,
+                })
+            })
+    }
+
+    #[test]
+    fn revoke_all_in_one_call() {
+        let mut token = approved_token();
+        set_context(accounts(1), ONE_YOCTO, Gas(300 * Gas::ONE_TERA.0));
+        token.nft_revoke_all(TOKEN_ID.to_string());
+
+        assert_eq!(approval_count(&token), 0);
+        // The refund is the only receipt.
+        let receipts = get_created_receipts();
+        assert_eq!(receipts.len(), 1);
+        assert_eq!(receipts[0].receiver_id, accounts(1));
+        assert!(matches!(receipts[0].actions[..], [VmAction::Transfer { .. }]));
+    }
+
+    #[test]
+    fn revoke_all_continues_when_gas_runs_low() {
+        let mut token = approved_token();
+        // Leave a single unit of gas to the loop, and use it up before calling.
+        set_context(accounts(1), ONE_YOCTO, GAS_FOR_NFT_REVOKE_ALL_CONTINUE + Gas(1));
+        with_mocked_blockchain(|b| b.gas(1));
+        token.nft_revoke_all(TOKEN_ID.to_string());
+
+        assert_eq!(approval_count(&token), 3);
+        assert!(scheduled_continuation().unwrap() >= MIN_GAS_FOR_NFT_REVOKE_ALL_CONTINUE);
+
+        let next_approval_id = next_approval_id(&token);
+        let mut calls = 0;
+        loop {
+            set_context(accounts(0), 0, GAS_FOR_NFT_REVOKE_ALL_CONTINUE + Gas(1));
+            with_mocked_blockchain(|b| b.gas(1));
+            token.nft_revoke_all_continue(TOKEN_ID.to_string(), accounts(1), next_approval_id);
+            calls += 1;
+            if scheduled_continuation().is_none() {
+                break;
+            }
+        }
+        assert_eq!(calls, 3);
+        assert_eq!(approval_count(&token), 0);
+        assert!(token.approvals_by_id.as_ref().unwrap().get(&TOKEN_ID.to_string()).is_none());
+    }
+
+    #[test]
+    fn continuation_after_transfer_does_nothing() {
+        let mut token = approved_token();
+        set_context(accounts(1), ONE_YOCTO, GAS_FOR_NFT_REVOKE_ALL_CONTINUE + Gas(1));
+        with_mocked_blockchain(|b| b.gas(1));
+        token.nft_revoke_all(TOKEN_ID.to_string());
+        let next_approval_id = next_approval_id(&token);
+
+        // Transferring the token clears the approvals left, and the new owner approves again.
+        set_context(accounts(1), ONE_YOCTO, Gas(300 * Gas::ONE_TERA.0));
+        token.internal_transfer(&accounts(1), &accounts(2), &TOKEN_ID.to_string(), None, None);
+        set_context(accounts(2), ONE_NEAR, Gas(300 * Gas::ONE_TERA.0));
+        token.nft_approve(TOKEN_ID.to_string(), accounts(3), None);
+
+        // The continuation scheduled for the previous owner leaves the new approval alone.
+        set_context(accounts(0), 0, Gas(300 * Gas::ONE_TERA.0));
+        token.nft_revoke_all_continue(TOKEN_ID.to_string(), accounts(1), next_approval_id);
+        assert_eq!(approval_count(&token), 1);
+        assert!(token.nft_is_approved(TOKEN_ID.to_string(), accounts(3), None));
+        assert!(get_created_receipts().is_empty());
+    }
+
+    #[test]
+    fn continuation_after_transfer_back_does_nothing() {
+        let mut token = approved_token();
+        set_context(accounts(1), ONE_YOCTO, GAS_FOR_NFT_REVOKE_ALL_CONTINUE + Gas(1));
+        with_mocked_blockchain(|b| b.gas(1));
+        token.nft_revoke_all(TOKEN_ID.to_string());
+        let next_approval_id = next_approval_id(&token);
+
+        // The token comes back to its owner, who approves an account again.
+        set_context(accounts(1), ONE_YOCTO, Gas(300 * Gas::ONE_TERA.0));
+        token.internal_transfer(&accounts(1), &accounts(2), &TOKEN_ID.to_string(), None, None);
+        token.internal_transfer(&accounts(2), &accounts(1), &TOKEN_ID.to_string(), None, None);
+        set_context(accounts(1), ONE_NEAR, Gas(300 * Gas::ONE_TERA.0));
+        token.nft_approve(TOKEN_ID.to_string(), accounts(3), None);
+
+        // The continuation scheduled before leaves the new approval alone.
+        set_context(accounts(0), 0, Gas(300 * Gas::ONE_TERA.0));
+        token.nft_revoke_all_continue(TOKEN_ID.to_string(), accounts(1), next_approval_id);
+        assert_eq!(approval_count(&token), 1);
+        assert!(token.nft_is_approved(TOKEN_ID.to_string(), accounts(3), None));
+        assert!(get_created_receipts().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "Not enough gas left to continue revoking the approvals")]
+    fn revoke_all_without_gas_for_continuation() {
+        let mut token = approved_token();
+        // Less than the minimum of the continuation is left once the loop stops.
+        set_context(accounts(1), ONE_YOCTO, MIN_GAS_FOR_NFT_REVOKE_ALL_CONTINUE);
+        token.nft_revoke_all(TOKEN_ID.to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "reserving 10000000000000 for nft_approve. nft_approve does not fit")]
+    fn approve_with_msg_without_gas_for_approve() {
+        let mut token = approved_token();
+        set_context(accounts(1), ONE_NEAR, Gas::ONE_TERA * 8);
+        token.nft_approve(TOKEN_ID.to_string(), accounts(2), Some("list".to_string()));
+    }
+
+    fn approved_account(i: u64) -> AccountId {
+        format!("approved{}.near", i).parse().unwrap()
+    }
+
+    /// Mints a token to `accounts(1)` and approves as many accounts as `max_approvals` allows.
+    fn token_at_approval_cap(max_approvals: u64) -> NonFungibleToken {
+        set_context(accounts(0), ONE_NEAR, Gas(300 * Gas::ONE_TERA.0));
+        let mut token = NonFungibleToken::new(
+            b"o".to_vec(),
+            accounts(0),
+            None::<Vec<u8>>,
+            None::<Vec<u8>>,
+            Some(b"a".to_vec()),
+        )
+        .with_max_approvals_per_token(max_approvals);
+        token.internal_mint(TOKEN_ID.to_string(), accounts(1), None);
+
+        set_context(accounts(1), ONE_NEAR, Gas(300 * Gas::ONE_TERA.0));
+        for i in 0..max_approvals {
+            token.nft_approve(TOKEN_ID.to_string(), approved_account(i), None);
+        }
+        token
+    }
+
+    #[test]
+    #[should_panic(expected = "Token t already has the maximum of 3 approvals, revoke one first")]
+    fn approve_over_cap() {
+        let mut token = token_at_approval_cap(3);
+        token.nft_approve(TOKEN_ID.to_string(), accounts(2), None);
+    }
+
+    #[test]
+    fn approve_again_at_cap() {
+        let mut token = token_at_approval_cap(3);
+        token.nft_approve(TOKEN_ID.to_string(), approved_account(1), None);
+        assert!(token.nft_is_approved(TOKEN_ID.to_string(), approved_account(1), Some(4)));
+        assert!(!token.nft_is_approved(TOKEN_ID.to_string(), approved_account(1), Some(2)));
+    }
+
+    #[test]
+    fn approve_again_does_not_read_cap() {
+        let token = token_at_approval_cap(3);
+        // A token loaded in a new call, with a maximum that can't be read.
+        let mut token = NonFungibleToken::try_from_slice(&token.try_to_vec().unwrap()).unwrap();
+        env::storage_write(b"om", &[]);
+        token.nft_approve(TOKEN_ID.to_string(), approved_account(1), None);
+        assert!(token.nft_is_approved(TOKEN_ID.to_string(), approved_account(1), Some(4)));
+    }
+
+    #[test]
+    fn revoke_frees_slot() {
+        let mut token = token_at_approval_cap(3);
+        set_context(accounts(1), ONE_YOCTO, Gas(300 * Gas::ONE_TERA.0));
+        token.nft_revoke(TOKEN_ID.to_string(), approved_account(0));
+
+        set_context(accounts(1), ONE_NEAR, Gas(300 * Gas::ONE_TERA.0));
+        token.nft_approve(TOKEN_ID.to_string(), accounts(2), None);
+        assert!(token.nft_is_approved(TOKEN_ID.to_string(), accounts(2), None));
+    }
+
+    #[test]
+    fn revoke_all_at_default_cap() {
+        let mut token = token_at_approval_cap(DEFAULT_MAX_APPROVALS_PER_TOKEN);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            token.nft_approve(TOKEN_ID.to_string(), accounts(2), None)
+        }));
+        assert!(result.is_err());
+
+        // Every approval is revoked in a single call, well within the gas of a call.
+        set_context(accounts(1), ONE_YOCTO, Gas(300 * Gas::ONE_TERA.0));
+        token.nft_revoke_all(TOKEN_ID.to_string());
+        assert!(scheduled_continuation().is_none());
+        assert!(token.approvals_by_id.as_ref().unwrap().get(&TOKEN_ID.to_string()).is_none());
+        assert!(env::used_gas() < Gas(30 * Gas::ONE_TERA.0), "{:?}", env::used_gas());
+    }
+
+    fn context(predecessor: AccountId) -> CallContext {
+        CallContext {
+            predecessor_account_id: predecessor.clone(),
+            signer_account_id: predecessor,
+            current_account_id: accounts(0),
+            attached_deposit: ONE_YOCTO,
+            block_timestamp: 0,
+            prepaid_gas: Gas(300 * Gas::ONE_TERA.0),
+        }
+    }
+
+    #[test]
+    fn approval_owner_from_context() {
+        assert_approval_owner(&context(accounts(1)), &accounts(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "Predecessor must be token owner.")]
+    fn approval_owner_from_context_rejects_others() {
+        // The signer being the owner doesn't matter.
+        let ctx = CallContext { signer_account_id: accounts(1), ..context(accounts(2)) };
+        assert_approval_owner(&ctx, &accounts(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "Predecessor must be token owner.")]
+    fn revoke_from_env_rejects_others() {
+        let mut token = approved_token();
+        set_context(accounts(2), ONE_YOCTO, Gas(300 * Gas::ONE_TERA.0));
+        token.nft_revoke(TOKEN_ID.to_string(), accounts(3));
+    }
+}
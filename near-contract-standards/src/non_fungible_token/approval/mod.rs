@@ -70,8 +70,14 @@ pub trait NonFungibleTokenApproval {
     ///   MUST refund all associated storage deposit when owner revokes approvals
     /// * Contract MUST panic if called by someone other than token owner
     ///
+    /// The implementation of [`NonFungibleToken`](crate::non_fungible_token::NonFungibleToken)
+    /// revokes the approvals left in a call to [`nft_revoke_all_continue`] when it runs low on
+    /// gas.
+    ///
     /// Arguments:
     /// * `token_id`: the token with approvals to revoke
+    ///
+    /// [`nft_revoke_all_continue`]: NonFungibleTokenRevokeAllContinuation::nft_revoke_all_continue
     fn nft_revoke_all(&mut self, token_id: TokenId);
 
     /// Check if a token is approved for transfer by a given account, optionally
@@ -92,3 +98,27 @@ pub trait NonFungibleTokenApproval {
         approval_id: Option<u64>,
     ) -> bool;
 }
+
+/// Revokes the approvals left by an `nft_revoke_all` which ran low on gas. `nft_revoke_all`
+/// schedules a call to `nft_revoke_all_continue` on the contract itself for the approvals it
+/// couldn't revoke, instead of exceeding its prepaid gas.
+pub trait NonFungibleTokenRevokeAllContinuation {
+    /// Revokes the approvals of `token_id` and refunds their storage to `owner_id`, scheduling
+    /// another call to itself if they still can't all be revoked with the prepaid gas. Nothing is
+    /// revoked if `owner_id` no longer owns the token, or if an account was approved since the
+    /// call was scheduled.
+    ///
+    /// Requirements:
+    /// * Contract MUST forbid calls to this function by any account except self
+    ///
+    /// Arguments:
+    /// * `token_id`: the token with approvals to revoke
+    /// * `owner_id`: the owner of the token when `nft_revoke_all` was called
+    /// * `next_approval_id`: the next approval ID of the token when the call was scheduled
+    fn nft_revoke_all_continue(
+        &mut self,
+        token_id: TokenId,
+        owner_id: AccountId,
+        next_approval_id: u64,
+    );
+}
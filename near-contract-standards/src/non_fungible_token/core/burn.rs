@@ -0,0 +1,25 @@
+use crate::non_fungible_token::token::TokenId;
+use crate::non_fungible_token::NonFungibleToken;
+use near_sdk::{assert_one_yocto, env};
+
+/// Burning of tokens by their owner or an approved account. Not part of the core standard, but
+/// exposed by `impl_non_fungible_token_burn!` for contracts with burnable tokens.
+pub trait NonFungibleTokenBurn {
+    /// Burns `token_id`, removing it from the contract.
+    ///
+    /// Requirements
+    /// * Caller of the method must attach a deposit of 1 yoctoⓃ for security purposes
+    /// * Contract MUST panic if called by someone other than token owner or, if using Approval
+    ///   Management, one of the approved accounts
+    ///
+    /// Arguments:
+    /// * `token_id`: the token to burn
+    fn nft_burn(&mut self, token_id: TokenId);
+}
+
+impl NonFungibleTokenBurn for NonFungibleToken {
+    fn nft_burn(&mut self, token_id: TokenId) {
+        assert_one_yocto();
+        self.internal_burn(&token_id, &env::predecessor_account_id());
+    }
+}
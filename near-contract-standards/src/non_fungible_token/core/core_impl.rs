@@ -1,11 +1,14 @@
 use super::resolver::NonFungibleTokenResolver;
-use crate::non_fungible_token::core::NonFungibleTokenCore;
-use crate::non_fungible_token::events::{NftMint, NftTransfer};
+use crate::limits::{check_memo, check_msg};
+use crate::non_fungible_token::core::{NonFungibleTokenCore, NonFungibleTokenTransferHook};
+use crate::non_fungible_token::events::{sort_token_ids, NftBurn, NftMint, NftTransfer};
 use crate::non_fungible_token::metadata::TokenMetadata;
 use crate::non_fungible_token::token::{Token, TokenId};
 use crate::non_fungible_token::utils::{
-    hash_account_id, refund_approved_account_ids, refund_deposit_to_account,
+    bytes_for_approved_account_id, hash_account_id, refund_approved_account_ids,
+    refund_deposit_to_account,
 };
+use crate::storage_management::{StorageRequirements, StorageRequirementsView, MAX_ACCOUNT_ID_LEN};
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::collections::{LookupMap, TreeMap, UnorderedSet};
 use near_sdk::json_types::Base64VecU8;
@@ -13,13 +16,43 @@ use near_sdk::{
     assert_one_yocto, env, ext_contract, require, AccountId, Balance, BorshStorageKey, CryptoHash,
     Gas, IntoStorageKey, PromiseOrValue, PromiseResult, StorageUsage,
 };
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashSet};
 
 const GAS_FOR_RESOLVE_TRANSFER: Gas = Gas(5_000_000_000_000);
 const GAS_FOR_NFT_TRANSFER_CALL: Gas = Gas(25_000_000_000_000 + GAS_FOR_RESOLVE_TRANSFER.0);
 
 const NO_DEPOSIT: Balance = 0;
 
+/// Gas reserved by `nft_transfer_call`, see [`NonFungibleToken::nft_transfer_call_with_gas`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NftTransferCallGas {
+    /// Gas attached to the `nft_resolve_transfer` callback. It can't be less than the default,
+    /// which the standard resolver needs to return the token.
+    pub resolve_transfer: Gas,
+    /// Gas kept for the transfer itself and the callback. The gas left after this and
+    /// `resolve_transfer` is attached to `nft_on_transfer`.
+    pub transfer_call: Gas,
+}
+
+impl NftTransferCallGas {
+    /// The gas used by [`NonFungibleToken::nft_transfer_call`].
+    pub const DEFAULT: Self = Self {
+        resolve_transfer: GAS_FOR_RESOLVE_TRANSFER,
+        transfer_call: GAS_FOR_NFT_TRANSFER_CALL,
+    };
+}
+
+impl Default for NftTransferCallGas {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Memo of the `nft_transfer` event emitted when `nft_resolve_transfer` returns a token to its
+/// previous owner.
+pub const ROLLBACK_MEMO: &str = "rollback";
+
 #[ext_contract(ext_self)]
 trait NFTResolver {
     fn nft_resolve_transfer(
@@ -27,7 +60,7 @@ trait NFTResolver {
         previous_owner_id: AccountId,
         receiver_id: AccountId,
         token_id: TokenId,
-        approved_account_ids: Option<HashMap<AccountId, u64>>,
+        approved_account_ids: Option<BTreeMap<AccountId, u64>>,
     ) -> bool;
 }
 
@@ -50,6 +83,12 @@ pub trait NonFungibleTokenReceiver {
 ///     - NonFungibleTokenApproval -- interface with nft_approve methods. NonFungibleToken provides methods for it.
 ///     - NonFungibleTokenEnumeration -- interface for getting lists of tokens. NonFungibleToken provides methods for it.
 ///     - NonFungibleTokenMetadata -- return metadata for the token in NEP-177, up to contract to implement.
+///     - NonFungibleTokenPayout -- interface with nft_payout methods, paying royalties set with
+///       `with_royalties`. NonFungibleToken provides methods for it.
+///
+/// The keys made of the prefix of `owner_by_id` followed by `r`, `m` or `s` are reserved for the
+/// state of the royalties, the approval limit and the sorted enumeration. The contract must not
+/// store anything under them.
 ///
 /// For example usage, see examples/non-fungible-token/src/lib.rs.
 #[derive(BorshDeserialize, BorshSerialize)]
@@ -70,17 +109,51 @@ pub struct NonFungibleToken {
     pub tokens_per_owner: Option<LookupMap<AccountId, UnorderedSet<TokenId>>>,
 
     // required by approval extension
-    pub approvals_by_id: Option<LookupMap<TokenId, HashMap<AccountId, u64>>>,
+    pub approvals_by_id: Option<LookupMap<TokenId, BTreeMap<AccountId, u64>>>,
     pub next_approval_id_by_id: Option<LookupMap<TokenId, u64>>,
+
+    #[borsh_skip]
+    /// State of the extensions read or written in the current call, `None` if it isn't stored.
+    extensions: RefCell<BTreeMap<u8, Option<Vec<u8>>>>,
 }
 
 #[derive(BorshStorageKey, BorshSerialize)]
 pub enum StorageKey {
     TokensPerOwner { account_hash: Vec<u8> },
     TokenPerOwnerInner { account_id_hash: CryptoHash },
+    SortedTokensPerOwner { account_hash: Vec<u8> },
+}
+
+/// Storage keys of [`NonFungibleToken::new_default`], in the `nft_standard` namespace so that they
+/// can't collide with the storage keys of the contract.
+#[derive(BorshStorageKey, BorshSerialize)]
+#[storage_key(namespace = "nft_standard")]
+pub enum NonFungibleTokenStorageKey {
+    OwnerById = 0,
+    TokenMetadata = 1,
+    Enumeration = 2,
+    Approvals = 3,
+    Royalties = 4,
 }
 
 impl NonFungibleToken {
+    /// Creates a token with the metadata, enumeration, approval and payout extensions, storing
+    /// them under the namespaced [`NonFungibleTokenStorageKey`] prefixes, so the contract can use
+    /// any other prefix.
+    ///
+    /// The tokens of each owner are still stored under the prefixes of [`StorageKey`], which are
+    /// derived from the hash of the owner.
+    pub fn new_default(owner_id: AccountId) -> Self {
+        Self::new(
+            NonFungibleTokenStorageKey::OwnerById,
+            owner_id,
+            Some(NonFungibleTokenStorageKey::TokenMetadata),
+            Some(NonFungibleTokenStorageKey::Enumeration),
+            Some(NonFungibleTokenStorageKey::Approvals),
+        )
+        .with_royalties(NonFungibleTokenStorageKey::Royalties)
+    }
+
     pub fn new<Q, R, S, T>(
         owner_by_id_prefix: Q,
         owner_id: AccountId,
@@ -112,17 +185,47 @@ impl NonFungibleToken {
             tokens_per_owner: enumeration_prefix.map(LookupMap::new),
             approvals_by_id,
             next_approval_id_by_id,
+            extensions: Default::default(),
         };
         this.measure_min_token_storage_cost();
         this
     }
 
+    /// Key of the state of the extension `extension`, derived from the prefix of `owner_by_id`.
+    /// The extensions added after the layout of the token was fixed keep their state under these
+    /// keys, so that the token keeps its layout. They can't collide with the keys of
+    /// `owner_by_id`, which are longer, but they aren't registered as storage prefixes, so they
+    /// are reserved in the documentation of [`NonFungibleToken`].
+    fn extension_key(&self, extension: u8) -> Vec<u8> {
+        let mut key = self.owner_by_id.key_prefix();
+        key.push(extension);
+        key
+    }
+
+    /// Reads the state of `extension`. The state is read from storage at most once per call, so
+    /// the operations which check whether an extension is used pay for a single read.
+    pub(crate) fn read_extension<T: BorshDeserialize>(&self, extension: u8) -> Option<T> {
+        let mut extensions = self.extensions.borrow_mut();
+        let raw = extensions
+            .entry(extension)
+            .or_insert_with(|| env::storage_read(&self.extension_key(extension)));
+        raw.as_ref().map(|raw| {
+            T::try_from_slice(raw)
+                .unwrap_or_else(|_| env::panic_str("Cannot deserialize the extension state"))
+        })
+    }
+
+    pub(crate) fn write_extension<T: BorshSerialize>(&mut self, extension: u8, state: &T) {
+        let raw = state.try_to_vec().unwrap();
+        env::storage_write(&self.extension_key(extension), &raw);
+        self.extensions.get_mut().insert(extension, Some(raw));
+    }
+
     // TODO: does this seem reasonable?
-    fn measure_min_token_storage_cost(&mut self) {
+    pub(crate) fn measure_min_token_storage_cost(&mut self) {
         let initial_storage_usage = env::storage_usage();
-        // 64 Length because this is the max account id length
-        let tmp_token_id = "a".repeat(64);
-        let tmp_owner_id = AccountId::new_unchecked("a".repeat(64));
+        let tmp_token_id = "a".repeat(MAX_ACCOUNT_ID_LEN);
+        let tmp_owner_id = AccountId::new_unchecked("a".repeat(MAX_ACCOUNT_ID_LEN));
 
         // 1. set some dummy data
         self.owner_by_id.insert(&tmp_token_id, &tmp_owner_id);
@@ -145,15 +248,25 @@ impl NonFungibleToken {
                 },
             );
         }
-        if let Some(tokens_per_owner) = &mut self.tokens_per_owner {
-            let u = &mut UnorderedSet::new(StorageKey::TokensPerOwner {
+        let mut owner_tokens = self.tokens_per_owner.as_mut().map(|tokens_per_owner| {
+            let mut owner_tokens = UnorderedSet::new(StorageKey::TokensPerOwner {
                 account_hash: env::sha256(tmp_owner_id.as_bytes()),
             });
-            u.insert(&tmp_token_id);
-            tokens_per_owner.insert(&tmp_owner_id, u);
-        }
+            owner_tokens.insert(&tmp_token_id);
+            tokens_per_owner.insert(&tmp_owner_id, &owner_tokens);
+            owner_tokens
+        });
+        let mut sorted_tokens_per_owner = self.sorted_tokens_per_owner();
+        let mut sorted_owner_tokens = sorted_tokens_per_owner.as_mut().map(|by_owner| {
+            let mut owner_tokens = TreeMap::new(StorageKey::SortedTokensPerOwner {
+                account_hash: env::sha256(tmp_owner_id.as_bytes()),
+            });
+            owner_tokens.insert(&tmp_token_id, &());
+            by_owner.insert(&tmp_owner_id, &owner_tokens);
+            owner_tokens
+        });
         if let Some(approvals_by_id) = &mut self.approvals_by_id {
-            let mut approvals = HashMap::new();
+            let mut approvals = BTreeMap::new();
             approvals.insert(tmp_owner_id.clone(), 1u64);
             approvals_by_id.insert(&tmp_token_id, &approvals);
         }
@@ -188,6 +301,15 @@ impl NonFungibleToken {
         if let Some(tokens_per_owner) = &mut self.tokens_per_owner {
             tokens_per_owner.remove(&tmp_owner_id);
         }
+        if let Some(owner_tokens) = &mut owner_tokens {
+            owner_tokens.clear();
+        }
+        if let Some(by_owner) = &mut sorted_tokens_per_owner {
+            by_owner.remove(&tmp_owner_id);
+        }
+        if let Some(owner_tokens) = &mut sorted_owner_tokens {
+            owner_tokens.clear();
+        }
         self.owner_by_id.remove(&tmp_token_id);
     }
 
@@ -204,26 +326,8 @@ impl NonFungibleToken {
         self.owner_by_id.insert(token_id, to);
 
         // if using Enumeration standard, update old & new owner's token lists
-        if let Some(tokens_per_owner) = &mut self.tokens_per_owner {
-            // owner_tokens should always exist, so call `unwrap` without guard
-            let mut owner_tokens = tokens_per_owner.get(from).unwrap_or_else(|| {
-                env::panic_str("Unable to access tokens per owner in unguarded call.")
-            });
-            owner_tokens.remove(token_id);
-            if owner_tokens.is_empty() {
-                tokens_per_owner.remove(from);
-            } else {
-                tokens_per_owner.insert(from, &owner_tokens);
-            }
-
-            let mut receiver_tokens = tokens_per_owner.get(to).unwrap_or_else(|| {
-                UnorderedSet::new(StorageKey::TokensPerOwner {
-                    account_hash: env::sha256(to.as_bytes()),
-                })
-            });
-            receiver_tokens.insert(token_id);
-            tokens_per_owner.insert(to, &receiver_tokens);
-        }
+        self.internal_remove_token_from_owner(from, token_id);
+        self.internal_add_tokens_to_owner(to, [token_id.as_str()]);
     }
 
     /// Transfer from current owner to receiver_id, checking that sender is allowed to transfer.
@@ -236,14 +340,37 @@ impl NonFungibleToken {
         #[allow(clippy::ptr_arg)] token_id: &TokenId,
         approval_id: Option<u64>,
         memo: Option<String>,
-    ) -> (AccountId, Option<HashMap<AccountId, u64>>) {
+    ) -> (AccountId, Option<BTreeMap<AccountId, u64>>) {
+        self.internal_transfer_with_hook(
+            &mut (),
+            sender_id,
+            receiver_id,
+            token_id,
+            approval_id,
+            memo,
+        )
+    }
+
+    /// [`internal_transfer`](Self::internal_transfer) running `hook` around the transfer. The
+    /// transfer is authorized before calling [`before_nft_transfer`], so that it can panic before
+    /// any state changes.
+    ///
+    /// [`before_nft_transfer`]: NonFungibleTokenTransferHook::before_nft_transfer
+    pub fn internal_transfer_with_hook<H: NonFungibleTokenTransferHook + ?Sized>(
+        &mut self,
+        hook: &mut H,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        #[allow(clippy::ptr_arg)] token_id: &TokenId,
+        approval_id: Option<u64>,
+        memo: Option<String>,
+    ) -> (AccountId, Option<BTreeMap<AccountId, u64>>) {
         let owner_id =
             self.owner_by_id.get(token_id).unwrap_or_else(|| env::panic_str("Token not found"));
 
-        // clear approvals, if using Approval Management extension
-        // this will be rolled back by a panic if sending fails
+        // approvals, if using Approval Management extension, are cleared once authorized
         let approved_account_ids =
-            self.approvals_by_id.as_mut().and_then(|by_id| by_id.remove(token_id));
+            self.approvals_by_id.as_ref().and_then(|by_id| by_id.get(token_id));
 
         // check if authorized
         let sender_id = if sender_id != &owner_id {
@@ -274,10 +401,21 @@ impl NonFungibleToken {
 
         require!(&owner_id != receiver_id, "Current and next owner must differ");
 
+        hook.before_nft_transfer(&owner_id, receiver_id, token_id);
+
+        // clear approvals; this will be rolled back by a panic if sending fails
+        if approved_account_ids.is_some() {
+            if let Some(by_id) = &mut self.approvals_by_id {
+                by_id.remove(token_id);
+            }
+        }
+
         self.internal_transfer_unguarded(token_id, &owner_id, receiver_id);
 
         NonFungibleToken::emit_transfer(&owner_id, receiver_id, token_id, sender_id, memo);
 
+        hook.after_nft_transfer(&owner_id, receiver_id, token_id);
+
         // return previous owner & approvals
         (owner_id, approved_account_ids)
     }
@@ -292,13 +430,64 @@ impl NonFungibleToken {
         NftTransfer {
             old_owner_id: owner_id,
             new_owner_id: receiver_id,
-            token_ids: &[token_id],
-            authorized_id: sender_id.filter(|sender_id| *sender_id == owner_id),
+            token_ids: &sort_token_ids([token_id]),
+            authorized_id: sender_id,
             memo: memo.as_deref(),
         }
         .emit();
     }
 
+    /// Burns `token_id`, removing its owner, metadata, approvals, royalties and entry in the
+    /// tokens of its owner, and emits an `nft_burn` event. The storage of the approvals is refunded
+    /// to the owner. The token can be burned by its owner or, if using the approval management
+    /// extension, by an approved account, which is then the `authorized_id` of the event.
+    ///
+    /// Returns the burned token.
+    pub fn internal_burn(
+        &mut self,
+        #[allow(clippy::ptr_arg)] token_id: &TokenId,
+        caller: &AccountId,
+    ) -> Token {
+        let owner_id =
+            self.owner_by_id.get(token_id).unwrap_or_else(|| env::panic_str("Token not found"));
+
+        // clear approvals, if using Approval Management extension
+        // this will be rolled back by a panic if the caller isn't authorized
+        let approved_account_ids =
+            self.approvals_by_id.as_mut().map(|by_id| by_id.remove(token_id).unwrap_or_default());
+        let authorized_id = if caller != &owner_id {
+            require!(
+                approved_account_ids.as_ref().map_or(false, |ids| ids.contains_key(caller)),
+                "Unauthorized"
+            );
+            Some(caller)
+        } else {
+            None
+        };
+
+        self.owner_by_id.remove(token_id);
+        let metadata = self.token_metadata_by_id.as_mut().and_then(|by_id| by_id.remove(token_id));
+        self.internal_remove_token_from_owner(&owner_id, token_id);
+        if let Some(next_approval_id_by_id) = &mut self.next_approval_id_by_id {
+            next_approval_id_by_id.remove(token_id);
+        }
+        if let Some(mut royalties_by_id) = self.royalties_by_id() {
+            royalties_by_id.remove(token_id);
+        }
+        if let Some(approvals) = approved_account_ids.as_ref().filter(|a| !a.is_empty()) {
+            refund_approved_account_ids(owner_id.clone(), approvals);
+        }
+
+        NftBurn {
+            owner_id: &owner_id,
+            token_ids: &sort_token_ids([token_id.as_str()]),
+            authorized_id,
+            memo: None,
+        }
+        .emit();
+        Token { token_id: token_id.clone(), owner_id, metadata, approved_account_ids }
+    }
+
     /// Mint a new token. Not part of official standard, but needed in most situations.
     /// Consuming contract expected to wrap this with an `nft_mint` function.
     ///
@@ -326,6 +515,8 @@ impl NonFungibleToken {
     /// * Whether the caller id is equal to the `owner_id`
     /// * Assumes there will be a refund to the predecessor after covering the storage costs
     ///
+    /// The metadata is checked with [`TokenMetadata::assert_valid`].
+    ///
     /// Returns the newly minted token and emits the mint event
     pub fn internal_mint(
         &mut self,
@@ -333,13 +524,21 @@ impl NonFungibleToken {
         token_owner_id: AccountId,
         token_metadata: Option<TokenMetadata>,
     ) -> Token {
+        if let Some(token_metadata) = &token_metadata {
+            token_metadata.assert_valid();
+        }
         let token = self.internal_mint_with_refund(
             token_id,
             token_owner_id,
             token_metadata,
             Some(env::predecessor_account_id()),
         );
-        NftMint { owner_id: &token.owner_id, token_ids: &[&token.token_id], memo: None }.emit();
+        NftMint {
+            owner_id: &token.owner_id,
+            token_ids: &sort_token_ids([token.token_id.as_str()]),
+            memo: None,
+        }
+        .emit();
         token
     }
 
@@ -380,19 +579,11 @@ impl NonFungibleToken {
             .and_then(|by_id| by_id.insert(&token_id, token_metadata.as_ref().unwrap()));
 
         // Enumeration extension: Record tokens_per_owner for use with enumeration view methods.
-        if let Some(tokens_per_owner) = &mut self.tokens_per_owner {
-            let mut token_ids = tokens_per_owner.get(&owner_id).unwrap_or_else(|| {
-                UnorderedSet::new(StorageKey::TokensPerOwner {
-                    account_hash: env::sha256(owner_id.as_bytes()),
-                })
-            });
-            token_ids.insert(&token_id);
-            tokens_per_owner.insert(&owner_id, &token_ids);
-        }
+        self.internal_add_tokens_to_owner(&owner_id, [token_id.as_str()]);
 
-        // Approval Management extension: return empty HashMap as part of Token
+        // Approval Management extension: return empty map as part of Token
         let approved_account_ids =
-            if self.approvals_by_id.is_some() { Some(HashMap::new()) } else { None };
+            if self.approvals_by_id.is_some() { Some(BTreeMap::new()) } else { None };
 
         if let Some((id, storage_usage)) = initial_storage_usage {
             refund_deposit_to_account(env::storage_usage() - storage_usage, id)
@@ -402,11 +593,111 @@ impl NonFungibleToken {
 
         Token { token_id, owner_id, metadata: token_metadata, approved_account_ids }
     }
-}
 
-impl NonFungibleTokenCore for NonFungibleToken {
-    fn nft_transfer(
+    /// Mints several tokens, each given by its token ID, owner and metadata, and emits a single
+    /// `nft_mint` event listing the minted tokens of each owner. The storage of the whole batch is
+    /// paid from the attached deposit, and the rest is refunded to the predecessor.
+    ///
+    /// Every token is validated before any is minted: the call panics without minting anything if
+    /// a token ID already exists or appears twice in the batch, or if metadata is missing while
+    /// the metadata extension is used or is invalid, see [`TokenMetadata::assert_valid`].
+    ///
+    /// Returns the minted tokens, in the order of `specs`.
+    pub fn internal_mint_batch(
+        &mut self,
+        specs: Vec<(TokenId, AccountId, Option<TokenMetadata>)>,
+    ) -> Vec<Token> {
+        let mut batch_token_ids = HashSet::with_capacity(specs.len());
+        for (token_id, _, token_metadata) in &specs {
+            match token_metadata {
+                Some(token_metadata) => token_metadata.assert_valid(),
+                None if self.token_metadata_by_id.is_some() => {
+                    env::panic_str("Must provide metadata")
+                }
+                None => {}
+            }
+            require!(
+                batch_token_ids.insert(token_id.as_str()),
+                format!("token_id {} is minted twice in the batch", token_id)
+            );
+            require!(!self.owner_by_id.contains_key(token_id), "token_id must be unique");
+        }
+        if specs.is_empty() {
+            return Vec::new();
+        }
+        let initial_storage_usage = env::storage_usage();
+
+        let approved_account_ids =
+            if self.approvals_by_id.is_some() { Some(BTreeMap::new()) } else { None };
+        let tokens: Vec<Token> = specs
+            .into_iter()
+            .map(|(token_id, owner_id, metadata)| Token {
+                token_id,
+                owner_id,
+                metadata,
+                approved_account_ids: approved_account_ids.clone(),
+            })
+            .collect();
+
+        let mut token_ids_per_owner: BTreeMap<&AccountId, Vec<&str>> = BTreeMap::new();
+        for token in &tokens {
+            self.owner_by_id.insert(&token.token_id, &token.owner_id);
+            if let Some(token_metadata_by_id) = &mut self.token_metadata_by_id {
+                token_metadata_by_id.insert(&token.token_id, token.metadata.as_ref().unwrap());
+            }
+            token_ids_per_owner.entry(&token.owner_id).or_default().push(&token.token_id);
+        }
+
+        // Enumeration extension: load the tokens of each owner once for the whole batch.
+        for (&owner_id, token_ids) in &token_ids_per_owner {
+            self.internal_add_tokens_to_owner(owner_id, token_ids.iter().copied());
+        }
+
+        refund_deposit_to_account(
+            env::storage_usage() - initial_storage_usage,
+            env::predecessor_account_id(),
+        );
+
+        let token_ids_per_owner: Vec<(&AccountId, Vec<&str>)> = token_ids_per_owner
+            .into_iter()
+            .map(|(owner_id, token_ids)| (owner_id, sort_token_ids(token_ids)))
+            .collect();
+        let events: Vec<NftMint> = token_ids_per_owner
+            .iter()
+            .map(|(owner_id, token_ids)| NftMint { owner_id, token_ids, memo: None })
+            .collect();
+        NftMint::emit_many(&events);
+        tokens
+    }
+
+    /// `nft_transfer_call` reserving `gas` instead of [`NftTransferCallGas::DEFAULT`], for
+    /// receivers or resolvers which need more gas than the default.
+    ///
+    /// Panics if `gas.resolve_transfer` is less than the default.
+    pub fn nft_transfer_call_with_gas(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        approval_id: Option<u64>,
+        memo: Option<String>,
+        msg: String,
+        gas: NftTransferCallGas,
+    ) -> PromiseOrValue<bool> {
+        self.nft_transfer_call_with_hook(
+            &mut (),
+            receiver_id,
+            token_id,
+            approval_id,
+            memo,
+            msg,
+            gas,
+        )
+    }
+
+    /// `nft_transfer` running `hook` around the transfer.
+    pub fn nft_transfer_with_hook<H: NonFungibleTokenTransferHook + ?Sized>(
         &mut self,
+        hook: &mut H,
         receiver_id: AccountId,
         token_id: TokenId,
         approval_id: Option<u64>,
@@ -414,25 +705,54 @@ impl NonFungibleTokenCore for NonFungibleToken {
     ) {
         assert_one_yocto();
         let sender_id = env::predecessor_account_id();
-        self.internal_transfer(&sender_id, &receiver_id, &token_id, approval_id, memo);
+        self.internal_transfer_with_hook(
+            hook,
+            &sender_id,
+            &receiver_id,
+            &token_id,
+            approval_id,
+            check_memo(memo),
+        );
     }
 
-    fn nft_transfer_call(
+    /// [`nft_transfer_call_with_gas`](Self::nft_transfer_call_with_gas) running `hook` around the
+    /// transfer. The resolver should be
+    /// [`nft_resolve_transfer_with_hook`](Self::nft_resolve_transfer_with_hook), with the same
+    /// hook, so that it's notified when the token is returned.
+    #[allow(clippy::too_many_arguments)]
+    pub fn nft_transfer_call_with_hook<H: NonFungibleTokenTransferHook + ?Sized>(
         &mut self,
+        hook: &mut H,
         receiver_id: AccountId,
         token_id: TokenId,
         approval_id: Option<u64>,
         memo: Option<String>,
         msg: String,
+        gas: NftTransferCallGas,
     ) -> PromiseOrValue<bool> {
         assert_one_yocto();
+        let memo = check_memo(memo);
+        let msg = check_msg(msg);
         require!(
-            env::prepaid_gas() > GAS_FOR_NFT_TRANSFER_CALL + GAS_FOR_RESOLVE_TRANSFER,
-            "More gas is required"
+            gas.resolve_transfer >= GAS_FOR_RESOLVE_TRANSFER,
+            format!(
+                "nft_resolve_transfer needs at least {} gas, {} is reserved",
+                GAS_FOR_RESOLVE_TRANSFER.0, gas.resolve_transfer.0
+            )
         );
+        let receiver_gas = crate::remaining_gas_after(&[
+            ("nft_transfer_call", gas.transfer_call),
+            ("nft_resolve_transfer", gas.resolve_transfer),
+        ]);
         let sender_id = env::predecessor_account_id();
-        let (old_owner, old_approvals) =
-            self.internal_transfer(&sender_id, &receiver_id, &token_id, approval_id, memo);
+        let (old_owner, old_approvals) = self.internal_transfer_with_hook(
+            hook,
+            &sender_id,
+            &receiver_id,
+            &token_id,
+            approval_id,
+            memo,
+        );
         // Initiating receiver's call and the callback
         ext_receiver::nft_on_transfer(
             sender_id,
@@ -441,7 +761,7 @@ impl NonFungibleTokenCore for NonFungibleToken {
             msg,
             receiver_id.clone(),
             NO_DEPOSIT,
-            env::prepaid_gas() - GAS_FOR_NFT_TRANSFER_CALL,
+            receiver_gas,
         )
         .then(ext_self::nft_resolve_transfer(
             old_owner,
@@ -450,30 +770,24 @@ impl NonFungibleTokenCore for NonFungibleToken {
             old_approvals,
             env::current_account_id(),
             NO_DEPOSIT,
-            GAS_FOR_RESOLVE_TRANSFER,
+            gas.resolve_transfer,
         ))
         .into()
     }
 
-    fn nft_token(&self, token_id: TokenId) -> Option<Token> {
-        let owner_id = self.owner_by_id.get(&token_id)?;
-        let metadata = self.token_metadata_by_id.as_ref().and_then(|by_id| by_id.get(&token_id));
-        let approved_account_ids = self
-            .approvals_by_id
-            .as_ref()
-            .and_then(|by_id| by_id.get(&token_id).or_else(|| Some(HashMap::new())));
-        Some(Token { token_id, owner_id, metadata, approved_account_ids })
-    }
-}
-
-impl NonFungibleTokenResolver for NonFungibleToken {
-    /// Returns true if token was successfully transferred to `receiver_id`.
-    fn nft_resolve_transfer(
+    /// `nft_resolve_transfer` calling [`on_nft_transfer_reverted`] once the token is returned to
+    /// `previous_owner_id`, if it's returned. The hook can't veto returning the token, so
+    /// [`before_nft_transfer`] isn't called.
+    ///
+    /// [`on_nft_transfer_reverted`]: NonFungibleTokenTransferHook::on_nft_transfer_reverted
+    /// [`before_nft_transfer`]: NonFungibleTokenTransferHook::before_nft_transfer
+    pub fn nft_resolve_transfer_with_hook<H: NonFungibleTokenTransferHook + ?Sized>(
         &mut self,
+        hook: &mut H,
         previous_owner_id: AccountId,
         receiver_id: AccountId,
         token_id: TokenId,
-        approved_account_ids: Option<HashMap<AccountId, u64>>,
+        approved_account_ids: Option<BTreeMap<AccountId, u64>>,
     ) -> bool {
         // Get whether token should be returned
         let must_revert = match env::promise_result(0) {
@@ -514,16 +828,664 @@ impl NonFungibleTokenResolver for NonFungibleToken {
 
         // If using Approval Management extension,
         // 1. revert any approvals receiver already set, refunding storage costs
-        // 2. reset approvals to what previous owner had set before call to nft_transfer_call
+        // 2. reset approvals to what previous owner had set before call to nft_transfer_call,
+        //    including their approval IDs. `nft_transfer_call` removed them without refunding
+        //    their storage, so the storage they take again is still paid for.
         if let Some(by_id) = &mut self.approvals_by_id {
-            if let Some(receiver_approvals) = by_id.get(&token_id) {
+            if let Some(receiver_approvals) = by_id.remove(&token_id) {
                 refund_approved_account_ids(receiver_id.clone(), &receiver_approvals);
             }
             if let Some(previous_owner_approvals) = approved_account_ids {
                 by_id.insert(&token_id, &previous_owner_approvals);
             }
         }
-        NonFungibleToken::emit_transfer(&receiver_id, &previous_owner_id, &token_id, None, None);
+        NonFungibleToken::emit_transfer(
+            &receiver_id,
+            &previous_owner_id,
+            &token_id,
+            None,
+            Some(ROLLBACK_MEMO.to_string()),
+        );
+        hook.on_nft_transfer_reverted(&receiver_id, &previous_owner_id, &token_id);
         false
     }
 }
+
+impl StorageRequirementsView for NonFungibleToken {
+    /// `nft_mint` is measured with a token ID and owner ID of `MAX_ACCOUNT_ID_LEN` bytes and
+    /// metadata with a 64 byte `title`, `description`, `media` and `media_hash`.
+    fn storage_requirements(&self) -> StorageRequirements {
+        let requirements =
+            StorageRequirements::default().with("nft_mint", self.extra_storage_in_bytes_per_token);
+        if self.approvals_by_id.is_some() {
+            let account_id = AccountId::new_unchecked("a".repeat(MAX_ACCOUNT_ID_LEN));
+            requirements.with("nft_approve", bytes_for_approved_account_id(&account_id))
+        } else {
+            requirements
+        }
+    }
+}
+
+impl NonFungibleTokenCore for NonFungibleToken {
+    fn nft_transfer(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        approval_id: Option<u64>,
+        memo: Option<String>,
+    ) {
+        self.nft_transfer_with_hook(&mut (), receiver_id, token_id, approval_id, memo)
+    }
+
+    fn nft_transfer_call(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        approval_id: Option<u64>,
+        memo: Option<String>,
+        msg: String,
+    ) -> PromiseOrValue<bool> {
+        self.nft_transfer_call_with_gas(
+            receiver_id,
+            token_id,
+            approval_id,
+            memo,
+            msg,
+            NftTransferCallGas::DEFAULT,
+        )
+    }
+
+    fn nft_token(&self, token_id: TokenId) -> Option<Token> {
+        let owner_id = self.owner_by_id.get(&token_id)?;
+        let metadata = self.token_metadata_by_id.as_ref().and_then(|by_id| by_id.get(&token_id));
+        let approved_account_ids = self
+            .approvals_by_id
+            .as_ref()
+            .and_then(|by_id| by_id.get(&token_id).or_else(|| Some(BTreeMap::new())));
+        Some(Token { token_id, owner_id, metadata, approved_account_ids })
+    }
+}
+
+impl NonFungibleTokenResolver for NonFungibleToken {
+    /// Returns true if token was successfully transferred to `receiver_id`.
+    fn nft_resolve_transfer(
+        &mut self,
+        previous_owner_id: AccountId,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        approved_account_ids: Option<BTreeMap<AccountId, u64>>,
+    ) -> bool {
+        self.nft_resolve_transfer_with_hook(
+            &mut (),
+            previous_owner_id,
+            receiver_id,
+            token_id,
+            approved_account_ids,
+        )
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::non_fungible_token::approval::{
+        NonFungibleTokenApproval, DEFAULT_MAX_APPROVALS_PER_TOKEN,
+    };
+    use crate::non_fungible_token::core::NonFungibleTokenBurn;
+    use near_sdk::mock::VmAction;
+    use near_sdk::test_utils::{
+        accounts, get_created_receipts, get_logs, storage_snapshot, VMContextBuilder,
+    };
+    use near_sdk::{testing_env, RuntimeFeesConfig, VMConfig, ONE_NEAR, ONE_YOCTO};
+
+    const TOKEN_ID: &str = "t";
+
+    fn set_context(predecessor: AccountId, attached_deposit: Balance) {
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(accounts(0))
+            .predecessor_account_id(predecessor)
+            .attached_deposit(attached_deposit)
+            .build());
+    }
+
+    /// Mints a token to `accounts(1)`, approves `accounts(2)` and `accounts(3)` and transfers the
+    /// token to `accounts(4)` like `nft_transfer_call` does.
+    fn transfer_approved_token() -> (NonFungibleToken, Option<BTreeMap<AccountId, u64>>) {
+        set_context(accounts(0), ONE_NEAR);
+        let mut token = NonFungibleToken::new(
+            b"o".to_vec(),
+            accounts(0),
+            None::<Vec<u8>>,
+            None::<Vec<u8>>,
+            Some(b"a".to_vec()),
+        );
+        token.internal_mint(TOKEN_ID.to_string(), accounts(1), None);
+
+        set_context(accounts(1), ONE_NEAR);
+        token.nft_approve(TOKEN_ID.to_string(), accounts(2), None);
+        token.nft_approve(TOKEN_ID.to_string(), accounts(3), None);
+
+        set_context(accounts(1), ONE_YOCTO);
+        let (_, approvals) =
+            token.internal_transfer(&accounts(1), &accounts(4), &TOKEN_ID.to_string(), None, None);
+        assert!(!token.nft_is_approved(TOKEN_ID.to_string(), accounts(2), None));
+        (token, approvals)
+    }
+
+    /// Sets the context of the callback, in which the receiver asked for the token back.
+    fn set_rejected_context() {
+        testing_env!(
+            VMContextBuilder::new()
+                .current_account_id(accounts(0))
+                .predecessor_account_id(accounts(0))
+                .build(),
+            VMConfig::test(),
+            RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(b"true".to_vec())],
+        );
+    }
+
+    #[test]
+    fn rollback_restores_approvals() {
+        let (mut token, approvals) = transfer_approved_token();
+
+        set_rejected_context();
+        let transferred =
+            token.nft_resolve_transfer(accounts(1), accounts(4), TOKEN_ID.to_string(), approvals);
+        assert!(!transferred);
+
+        assert_eq!(token.owner_by_id.get(&TOKEN_ID.to_string()), Some(accounts(1)));
+        assert!(token.nft_is_approved(TOKEN_ID.to_string(), accounts(2), Some(1)));
+        assert!(token.nft_is_approved(TOKEN_ID.to_string(), accounts(3), Some(2)));
+        // The storage of the restored approvals was never refunded, so nothing is charged or
+        // refunded now.
+        assert!(get_created_receipts().is_empty());
+        assert_eq!(
+            get_logs(),
+            [
+                r#"EVENT_JSON:{"standard":"nep171","version":"1.0.0","event":"nft_transfer","data":[{"old_owner_id":"eugene","new_owner_id":"bob","token_ids":["t"],"memo":"rollback"}]}"#
+            ]
+        );
+    }
+
+    fn token_metadata() -> TokenMetadata {
+        TokenMetadata {
+            title: Some("t".to_string()),
+            description: None,
+            media: None,
+            media_hash: None,
+            copies: None,
+            issued_at: None,
+            expires_at: None,
+            starts_at: None,
+            updated_at: None,
+            extra: None,
+            reference: None,
+            reference_hash: None,
+        }
+    }
+
+    #[test]
+    fn default_storage_keys() {
+        // Golden values: a change moves the tokens of every contract using `new_default`.
+        const NAMESPACE: [u8; 8] = [0x61, 0xd1, 0x55, 0x69, 0xff, 0x19, 0x5b, 0x7a];
+        assert_eq!(
+            NonFungibleTokenStorageKey::OwnerById.into_storage_key(),
+            [&NAMESPACE[..], &[0]].concat()
+        );
+        assert_eq!(
+            NonFungibleTokenStorageKey::Royalties.into_storage_key(),
+            [&NAMESPACE[..], &[4]].concat()
+        );
+
+        // The keys of the example NFT contract, which had to avoid the prefixes it passed to
+        // `NonFungibleToken::new`.
+        #[derive(BorshStorageKey, BorshSerialize)]
+        enum ContractKey {
+            NonFungibleToken,
+            Metadata,
+            TokenMetadata,
+            Enumeration,
+            Approval,
+        }
+        let contract_keys: Vec<Vec<u8>> = vec![
+            ContractKey::NonFungibleToken.into_storage_key(),
+            ContractKey::Metadata.into_storage_key(),
+            ContractKey::TokenMetadata.into_storage_key(),
+            ContractKey::Enumeration.into_storage_key(),
+            ContractKey::Approval.into_storage_key(),
+        ];
+        let standard_keys = [
+            NonFungibleTokenStorageKey::OwnerById,
+            NonFungibleTokenStorageKey::TokenMetadata,
+            NonFungibleTokenStorageKey::Enumeration,
+            NonFungibleTokenStorageKey::Approvals,
+            NonFungibleTokenStorageKey::Royalties,
+        ];
+        for standard_key in standard_keys {
+            let standard_key = standard_key.into_storage_key();
+            for contract_key in &contract_keys {
+                assert!(!standard_key.starts_with(contract_key));
+            }
+        }
+
+        set_context(accounts(0), ONE_NEAR);
+        let mut token = NonFungibleToken::new_default(accounts(0));
+        let mut owners = LookupMap::new(ContractKey::NonFungibleToken);
+        owners.insert(&TOKEN_ID.to_string(), &accounts(2));
+        token.internal_mint(TOKEN_ID.to_string(), accounts(1), Some(token_metadata()));
+        assert_eq!(token.nft_token(TOKEN_ID.to_string()).unwrap().owner_id, accounts(1));
+        assert_eq!(owners.get(&TOKEN_ID.to_string()), Some(accounts(2)));
+    }
+
+    fn minted_token() -> NonFungibleToken {
+        set_context(accounts(0), ONE_NEAR);
+        let mut token = NonFungibleToken::new(
+            b"o".to_vec(),
+            accounts(0),
+            None::<Vec<u8>>,
+            Some(b"e".to_vec()),
+            Some(b"a".to_vec()),
+        );
+        token.internal_mint(TOKEN_ID.to_string(), accounts(1), None);
+        token
+    }
+
+    /// The state of [`minted_token`] written by near-contract-standards 4.0.0-pre.6.
+    const PRE_6_STATE: &str =
+        "05000000616c69636562040000000000000000000000000000020000006f76010000\
+        0000000000020000006f6e000101000000650101000000610102000000616e";
+
+    #[test]
+    fn reads_state_of_pre_6_contracts() {
+        let token = minted_token();
+        let state: Vec<u8> = (0..PRE_6_STATE.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&PRE_6_STATE[i..i + 2], 16).unwrap())
+            .collect();
+        assert_eq!(token.try_to_vec().unwrap(), state);
+
+        // The extensions added since are disabled in a contract deployed before.
+        let token = NonFungibleToken::try_from_slice(&state).unwrap();
+        assert_eq!(token.nft_token(TOKEN_ID.to_string()).unwrap().owner_id, accounts(1));
+        assert!(token.royalties_by_id().is_none());
+        assert!(token.sorted_tokens_per_owner().is_none());
+        assert_eq!(token.max_approvals_per_token(), DEFAULT_MAX_APPROVALS_PER_TOKEN);
+    }
+
+    fn transfer_call_with_prepaid_gas(prepaid_gas: Gas) {
+        transfer_call_with_gas(prepaid_gas, NftTransferCallGas::DEFAULT);
+    }
+
+    fn transfer_call_with_gas(prepaid_gas: Gas, gas: NftTransferCallGas) {
+        let mut token = minted_token();
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(ONE_YOCTO)
+            .prepaid_gas(prepaid_gas)
+            .build());
+        let _ = token.nft_transfer_call_with_gas(
+            accounts(2),
+            TOKEN_ID.to_string(),
+            None,
+            None,
+            "".to_string(),
+            gas,
+        );
+    }
+
+    /// Returns the gas attached to the call of `function_name` scheduled in this context.
+    fn scheduled_gas(function_name: &str) -> Gas {
+        get_created_receipts()
+            .into_iter()
+            .flat_map(|receipt| receipt.actions)
+            .find_map(|action| match action {
+                VmAction::FunctionCall { function_name: name, gas, .. }
+                    if name == function_name =>
+                {
+                    Some(gas)
+                }
+                _ => None,
+            })
+            .unwrap_or_else(|| panic!("{} was not scheduled", function_name))
+    }
+
+    #[test]
+    fn transfer_call_default_gas() {
+        transfer_call_with_prepaid_gas(Gas::ONE_TERA * 100);
+        assert_eq!(scheduled_gas("nft_resolve_transfer"), GAS_FOR_RESOLVE_TRANSFER);
+        let receiver_gas = scheduled_gas("nft_on_transfer");
+        let reserved = GAS_FOR_NFT_TRANSFER_CALL + GAS_FOR_RESOLVE_TRANSFER;
+        assert!(receiver_gas <= Gas::ONE_TERA * 100 - reserved);
+        assert!(receiver_gas > Gas::ONE_TERA * 99 - reserved);
+    }
+
+    #[test]
+    fn transfer_call_configured_gas() {
+        let gas = NftTransferCallGas {
+            resolve_transfer: Gas::ONE_TERA * 20,
+            transfer_call: Gas::ONE_TERA * 40,
+        };
+        transfer_call_with_gas(Gas::ONE_TERA * 100, gas);
+        assert_eq!(scheduled_gas("nft_resolve_transfer"), Gas::ONE_TERA * 20);
+        let receiver_gas = scheduled_gas("nft_on_transfer");
+        assert!(receiver_gas <= Gas::ONE_TERA * 40);
+        assert!(receiver_gas > Gas::ONE_TERA * 39);
+    }
+
+    #[test]
+    #[should_panic(expected = "nft_resolve_transfer needs at least 5000000000000 gas, \
+                               4000000000000 is reserved")]
+    fn transfer_call_resolve_gas_below_default() {
+        let gas = NftTransferCallGas {
+            resolve_transfer: Gas::ONE_TERA * 4,
+            ..NftTransferCallGas::DEFAULT
+        };
+        transfer_call_with_gas(Gas::ONE_TERA * 100, gas);
+    }
+
+    #[test]
+    #[should_panic(expected = "reserving 30000000000000 for nft_transfer_call, 5000000000000 for \
+                               nft_resolve_transfer. nft_transfer_call does not fit")]
+    fn transfer_call_without_gas_for_transfer() {
+        transfer_call_with_prepaid_gas(Gas::ONE_TERA * 20);
+    }
+
+    #[test]
+    #[should_panic(expected = "reserving 30000000000000 for nft_transfer_call, 5000000000000 for \
+                               nft_resolve_transfer. nft_resolve_transfer does not fit")]
+    fn transfer_call_without_gas_for_resolve() {
+        transfer_call_with_prepaid_gas(Gas::ONE_TERA * 32);
+    }
+
+    #[test]
+    fn mint_emits_event() {
+        minted_token();
+        assert_eq!(
+            get_logs(),
+            [
+                r#"EVENT_JSON:{"standard":"nep171","version":"1.0.0","event":"nft_mint","data":[{"owner_id":"bob","token_ids":["t"]}]}"#
+            ]
+        );
+    }
+
+    #[test]
+    fn owner_transfer_emits_event() {
+        let mut token = minted_token();
+        set_context(accounts(1), ONE_YOCTO);
+        token.nft_transfer(accounts(2), TOKEN_ID.to_string(), None, Some("gift".to_string()));
+        assert_eq!(
+            get_logs(),
+            [
+                r#"EVENT_JSON:{"standard":"nep171","version":"1.0.0","event":"nft_transfer","data":[{"old_owner_id":"bob","new_owner_id":"charlie","token_ids":["t"],"memo":"gift"}]}"#
+            ]
+        );
+    }
+
+    #[test]
+    fn approved_transfer_emits_authorized_id() {
+        let mut token = minted_token();
+        set_context(accounts(1), ONE_NEAR);
+        token.nft_approve(TOKEN_ID.to_string(), accounts(2), None);
+
+        set_context(accounts(2), ONE_YOCTO);
+        token.nft_transfer(accounts(3), TOKEN_ID.to_string(), Some(1), None);
+        assert_eq!(
+            get_logs(),
+            [
+                r#"EVENT_JSON:{"standard":"nep171","version":"1.0.0","event":"nft_transfer","data":[{"old_owner_id":"bob","new_owner_id":"danny","token_ids":["t"],"authorized_id":"charlie"}]}"#
+            ]
+        );
+    }
+
+    #[test]
+    fn burn_emits_event() {
+        let mut token = minted_token();
+        set_context(accounts(1), ONE_NEAR);
+        token.nft_approve(TOKEN_ID.to_string(), accounts(2), None);
+
+        set_context(accounts(1), ONE_YOCTO);
+        let burned = token.internal_burn(&TOKEN_ID.to_string(), &accounts(1));
+        assert_eq!(burned.owner_id, accounts(1));
+        assert_eq!(burned.approved_account_ids.map(|a| a.len()), Some(1));
+        assert!(token.nft_token(TOKEN_ID.to_string()).is_none());
+        assert!(token.tokens_per_owner.as_ref().unwrap().get(&accounts(1)).is_none());
+        // The approval's storage is refunded to the owner.
+        assert_eq!(get_created_receipts().len(), 1);
+        assert_eq!(
+            get_logs(),
+            [
+                r#"EVENT_JSON:{"standard":"nep171","version":"1.0.0","event":"nft_burn","data":[{"owner_id":"bob","token_ids":["t"]}]}"#
+            ]
+        );
+    }
+
+    /// Mints, transfers and burns several tokens, and returns the logs.
+    fn scenario_logs() -> Vec<String> {
+        // Each run starts from empty storage, so the same tokens can be minted again.
+        near_sdk::mock::with_mocked_blockchain(|b| b.take_storage());
+        let mut token = minted_token();
+        for token_id in ["b", "a", "c"] {
+            token.internal_mint(token_id.to_string(), accounts(1), None);
+        }
+        // Switching the context clears the logs, so they are collected before each switch.
+        let mut logs = get_logs();
+        set_context(accounts(1), ONE_YOCTO);
+        for token_id in ["c", TOKEN_ID, "a"] {
+            token.nft_transfer(accounts(2), token_id.to_string(), None, None);
+        }
+        logs.extend(get_logs());
+        set_context(accounts(2), ONE_YOCTO);
+        token.internal_burn(&"a".to_string(), &accounts(2));
+        logs.extend(get_logs());
+        logs
+    }
+
+    #[test]
+    fn events_are_deterministic() {
+        let logs = scenario_logs();
+        assert_eq!(logs.len(), 8);
+        assert_eq!(scenario_logs(), logs);
+    }
+
+    #[test]
+    fn measuring_storage_leaves_no_entries() {
+        set_context(accounts(0), 0);
+        let token = NonFungibleToken::new(
+            b"o".to_vec(),
+            accounts(0),
+            Some(b"m".to_vec()),
+            Some(b"e".to_vec()),
+            Some(b"a".to_vec()),
+        );
+        assert!(storage_snapshot().is_empty());
+        // Only the prefix of the sorted tokens is kept.
+        token.with_sorted_enumeration(b"s".to_vec());
+        assert_eq!(storage_snapshot().len(), 1);
+    }
+
+    #[test]
+    fn burn_removes_every_entry() {
+        set_context(accounts(0), ONE_NEAR);
+        let mut token = NonFungibleToken::new_default(accounts(0));
+        token.internal_mint("other".to_string(), accounts(1), Some(token_metadata()));
+        // Storage usage is reset by each context switch, so the storage itself is compared.
+        let initial_storage = storage_snapshot();
+        token.internal_mint(TOKEN_ID.to_string(), accounts(1), Some(token_metadata()));
+        token.internal_set_royalties(&TOKEN_ID.to_string(), &[(accounts(3), 100)].into());
+        set_context(accounts(1), ONE_NEAR);
+        token.nft_approve(TOKEN_ID.to_string(), accounts(2), None);
+        token.nft_approve(TOKEN_ID.to_string(), accounts(3), None);
+        assert_ne!(storage_snapshot(), initial_storage);
+
+        set_context(accounts(2), ONE_YOCTO);
+        let burned = token.internal_burn(&TOKEN_ID.to_string(), &accounts(2));
+        assert_eq!(burned.metadata, Some(token_metadata()));
+        assert_eq!(storage_snapshot(), initial_storage);
+
+        let token_id = TOKEN_ID.to_string();
+        assert!(token.owner_by_id.get(&token_id).is_none());
+        assert!(token.token_metadata_by_id.as_ref().unwrap().get(&token_id).is_none());
+        assert!(token.approvals_by_id.as_ref().unwrap().get(&token_id).is_none());
+        assert!(token.next_approval_id_by_id.as_ref().unwrap().get(&token_id).is_none());
+        assert!(token.royalties_by_id().unwrap().get(&token_id).is_none());
+        let owner_tokens = token.tokens_per_owner.as_ref().unwrap().get(&accounts(1)).unwrap();
+        assert_eq!(owner_tokens.to_vec(), ["other".to_string()]);
+        assert_eq!(
+            get_logs(),
+            [
+                r#"EVENT_JSON:{"standard":"nep171","version":"1.0.0","event":"nft_burn","data":[{"owner_id":"bob","token_ids":["t"],"authorized_id":"charlie"}]}"#
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn nft_burn_requires_one_yocto() {
+        let mut token = minted_token();
+        set_context(accounts(1), 0);
+        token.nft_burn(TOKEN_ID.to_string());
+    }
+
+    #[test]
+    fn nft_burn_by_owner() {
+        let mut token = minted_token();
+        set_context(accounts(1), ONE_YOCTO);
+        token.nft_burn(TOKEN_ID.to_string());
+        assert!(token.nft_token(TOKEN_ID.to_string()).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "Unauthorized")]
+    fn burn_requires_owner() {
+        let mut token = minted_token();
+        token.internal_burn(&TOKEN_ID.to_string(), &accounts(2));
+    }
+
+    fn batch_token() -> NonFungibleToken {
+        // Starts from empty storage, so tests can create several tokens.
+        near_sdk::mock::with_mocked_blockchain(|b| b.take_storage());
+        set_context(accounts(0), ONE_NEAR);
+        let mut token = NonFungibleToken::new(
+            b"o".to_vec(),
+            accounts(0),
+            Some(b"m".to_vec()),
+            Some(b"e".to_vec()),
+            Some(b"a".to_vec()),
+        );
+        token.internal_mint("existing".to_string(), accounts(1), Some(token_metadata()));
+        set_context(accounts(0), ONE_NEAR);
+        token
+    }
+
+    fn batch_spec(
+        token_id: &str,
+        owner_id: AccountId,
+    ) -> (TokenId, AccountId, Option<TokenMetadata>) {
+        (token_id.to_string(), owner_id, Some(token_metadata()))
+    }
+
+    #[test]
+    fn mint_batch_groups_event_by_owner() {
+        let mut token = batch_token();
+        let tokens = token.internal_mint_batch(vec![
+            batch_spec("c", accounts(2)),
+            batch_spec("b", accounts(1)),
+            batch_spec("a", accounts(2)),
+        ]);
+        let token_ids: Vec<_> = tokens.iter().map(|token| token.token_id.as_str()).collect();
+        assert_eq!(token_ids, ["c", "b", "a"]);
+        assert_eq!(tokens[0].approved_account_ids, Some(BTreeMap::new()));
+        assert_eq!(
+            get_logs(),
+            [
+                r#"EVENT_JSON:{"standard":"nep171","version":"1.0.0","event":"nft_mint","data":[{"owner_id":"bob","token_ids":["b"]},{"owner_id":"charlie","token_ids":["a","c"]}]}"#
+            ]
+        );
+
+        assert_eq!(token.owner_by_id.get(&"a".to_string()), Some(accounts(2)));
+        assert_eq!(token.nft_token("b".to_string()).unwrap().metadata, Some(token_metadata()));
+        let tokens_per_owner = token.tokens_per_owner.as_ref().unwrap();
+        assert_eq!(tokens_per_owner.get(&accounts(1)).unwrap().len(), 2);
+        assert_eq!(tokens_per_owner.get(&accounts(2)).unwrap().len(), 2);
+        // The unused deposit is refunded once, for the whole batch.
+        assert_eq!(get_created_receipts().len(), 1);
+    }
+
+    #[test]
+    fn mint_batch_duplicate_aborts_without_changes() {
+        for duplicate in ["existing", "a"] {
+            let mut token = batch_token();
+            let before = storage_snapshot();
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                token.internal_mint_batch(vec![
+                    batch_spec("a", accounts(2)),
+                    batch_spec("b", accounts(3)),
+                    batch_spec(duplicate, accounts(4)),
+                ])
+            }));
+            assert!(result.is_err());
+            assert_eq!(storage_snapshot(), before);
+            assert!(get_logs().is_empty());
+            assert!(token.owner_by_id.get(&"a".to_string()).is_none());
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "token_id a is minted twice in the batch")]
+    fn mint_batch_rejects_duplicate_in_batch() {
+        let mut token = batch_token();
+        token.internal_mint_batch(vec![batch_spec("a", accounts(2)), batch_spec("a", accounts(3))]);
+    }
+
+    #[test]
+    fn mint_empty_batch() {
+        let mut token = batch_token();
+        assert!(token.internal_mint_batch(Vec::new()).is_empty());
+        assert!(get_logs().is_empty());
+        assert!(get_created_receipts().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "Copies can't be zero")]
+    fn mint_invalid_metadata() {
+        let mut token = batch_token();
+        let metadata = TokenMetadata { copies: Some(0), ..token_metadata() };
+        token.internal_mint("new".to_string(), accounts(1), Some(metadata));
+    }
+
+    #[test]
+    #[should_panic(expected = "Media hash is required with media")]
+    fn mint_batch_invalid_metadata() {
+        let mut token = batch_token();
+        let metadata =
+            TokenMetadata { media: Some("ipfs://media.png".to_string()), ..token_metadata() };
+        token.internal_mint_batch(vec![
+            batch_spec("a", accounts(1)),
+            ("b".to_string(), accounts(2), Some(metadata)),
+        ]);
+    }
+
+    #[test]
+    fn rollback_removes_receiver_approvals() {
+        let (mut token, _) = transfer_approved_token();
+
+        set_context(accounts(4), ONE_NEAR);
+        token.nft_approve(TOKEN_ID.to_string(), accounts(5), None);
+
+        // The previous owner had no approvals left to restore.
+        set_rejected_context();
+        token.nft_resolve_transfer(accounts(1), accounts(4), TOKEN_ID.to_string(), None);
+
+        assert!(!token.nft_is_approved(TOKEN_ID.to_string(), accounts(5), None));
+        assert_eq!(
+            token.nft_token(TOKEN_ID.to_string()).unwrap().approved_account_ids,
+            Some(BTreeMap::new())
+        );
+        // The receiver is refunded for the storage of its approval.
+        assert_eq!(get_created_receipts().len(), 1);
+    }
+}
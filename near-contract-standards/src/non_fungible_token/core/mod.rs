@@ -1,12 +1,16 @@
+mod burn;
 mod core_impl;
 
 mod receiver;
 mod resolver;
+mod transfer_hook;
 
+pub use self::burn::*;
 pub use self::core_impl::*;
 
 pub use self::receiver::*;
 pub use self::resolver::*;
+pub use self::transfer_hook::*;
 
 use crate::non_fungible_token::token::{Token, TokenId};
 use near_sdk::AccountId;
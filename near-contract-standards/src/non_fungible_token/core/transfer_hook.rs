@@ -0,0 +1,37 @@
+use crate::non_fungible_token::token::TokenId;
+use near_sdk::AccountId;
+
+/// Hooks run around every transfer made by the `_with_hook` methods of
+/// [`NonFungibleToken`](crate::non_fungible_token::NonFungibleToken), for contracts which need to
+/// veto transfers, like soulbound tokens, or react to them, like counting transfers.
+///
+/// The hook is usually another field of the contract, passed with `[hook = field]` to
+/// [`impl_non_fungible_token_core!`](crate::impl_non_fungible_token_core) and
+/// [`impl_non_fungible_token_payout!`](crate::impl_non_fungible_token_payout), so it's stored with
+/// the contract state. Every method does nothing by default, and `()` is the hook doing nothing.
+///
+/// Returning a token to its previous owner from `nft_resolve_transfer` can't be vetoed: it only
+/// calls [`on_nft_transfer_reverted`](Self::on_nft_transfer_reverted), once the token is back.
+pub trait NonFungibleTokenTransferHook {
+    /// Called when `token_id` is about to be transferred from `from` to `to`, once the transfer
+    /// is authorized and before any state changes. Panicking aborts the transfer.
+    fn before_nft_transfer(&mut self, from: &AccountId, to: &AccountId, token_id: &TokenId) {
+        let _ = (from, to, token_id);
+    }
+
+    /// Called once `token_id` was transferred from `from` to `to`, after the `nft_transfer` event
+    /// is emitted.
+    fn after_nft_transfer(&mut self, from: &AccountId, to: &AccountId, token_id: &TokenId) {
+        let _ = (from, to, token_id);
+    }
+
+    /// Called once `nft_resolve_transfer` returned `token_id` from the receiver `from` to its
+    /// previous owner `to`, after the `nft_transfer` event is emitted. It must not panic, which
+    /// would leave the token with the receiver. Calls
+    /// [`after_nft_transfer`](Self::after_nft_transfer) by default.
+    fn on_nft_transfer_reverted(&mut self, from: &AccountId, to: &AccountId, token_id: &TokenId) {
+        self.after_nft_transfer(from, to, token_id)
+    }
+}
+
+impl NonFungibleTokenTransferHook for () {}
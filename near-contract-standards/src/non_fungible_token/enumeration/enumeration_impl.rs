@@ -1,12 +1,152 @@
 use super::NonFungibleTokenEnumeration;
+use crate::non_fungible_token::core::StorageKey;
 use crate::non_fungible_token::token::Token;
 use crate::non_fungible_token::NonFungibleToken;
+use near_sdk::collections::{LookupMap, TreeMap, UnorderedSet};
 use near_sdk::json_types::U128;
-use near_sdk::{env, require, AccountId};
+use near_sdk::{env, require, AccountId, IntoStorageKey};
 
 type TokenId = String;
 
+/// Extension key of the sorted tokens of each owner, see
+/// [`NonFungibleToken::sorted_tokens_per_owner`].
+const SORTED_ENUMERATION_EXTENSION: u8 = b's';
+
 impl NonFungibleToken {
+    /// Lists the tokens of each owner in a [`TreeMap`] under `prefix` instead of an
+    /// [`UnorderedSet`], so that `nft_tokens_for_owner` returns them sorted by token ID. Removing
+    /// a token from an `UnorderedSet` moves the last token in its place, which reorders the pages
+    /// of the owner, while sorted tokens keep their order. Adding or removing a token of an owner
+    /// then reads and writes `O(log n)` storage entries, for an owner of `n` tokens, instead of
+    /// `O(1)`.
+    ///
+    /// This replaces the enumeration prefix passed to [`new`](Self::new), which can be `None`.
+    /// It changes how the tokens are stored, so existing contracts can't enable it without
+    /// migrating the tokens of every owner. The storage measured for `nft_mint` is measured again
+    /// with the sorted tokens.
+    ///
+    /// `nft_tokens_for_owner` still reads every token before `from_index`. Use
+    /// [`tokens_for_owner_from`](Self::tokens_for_owner_from) to seek a page by token ID.
+    pub fn with_sorted_enumeration<P: IntoStorageKey>(mut self, prefix: P) -> Self {
+        self.tokens_per_owner = None;
+        let sorted_tokens_per_owner: LookupMap<AccountId, TreeMap<TokenId, ()>> =
+            LookupMap::new(prefix);
+        self.write_extension(SORTED_ENUMERATION_EXTENSION, &sorted_tokens_per_owner);
+        self.measure_min_token_storage_cost();
+        self
+    }
+
+    /// Returns the sorted tokens of each owner if the token uses
+    /// [`with_sorted_enumeration`](Self::with_sorted_enumeration), which replaces
+    /// `tokens_per_owner`. They're stored outside of the token, so that sorting them doesn't
+    /// change its layout.
+    pub fn sorted_tokens_per_owner(&self) -> Option<LookupMap<AccountId, TreeMap<TokenId, ()>>> {
+        if self.tokens_per_owner.is_some() {
+            return None;
+        }
+        self.read_extension(SORTED_ENUMERATION_EXTENSION)
+    }
+
+    /// Adds `token_ids` to the tokens of `owner_id`, if the enumeration extension is used.
+    pub(crate) fn internal_add_tokens_to_owner<'a>(
+        &mut self,
+        owner_id: &AccountId,
+        token_ids: impl IntoIterator<Item = &'a str>,
+    ) {
+        if let Some(tokens_per_owner) = &mut self.tokens_per_owner {
+            let mut owner_tokens = tokens_per_owner.get(owner_id).unwrap_or_else(|| {
+                UnorderedSet::new(StorageKey::TokensPerOwner {
+                    account_hash: env::sha256(owner_id.as_bytes()),
+                })
+            });
+            for token_id in token_ids {
+                owner_tokens.insert(&token_id.to_string());
+            }
+            tokens_per_owner.insert(owner_id, &owner_tokens);
+        } else if let Some(mut tokens_per_owner) = self.sorted_tokens_per_owner() {
+            let mut owner_tokens = tokens_per_owner.get(owner_id).unwrap_or_else(|| {
+                TreeMap::new(StorageKey::SortedTokensPerOwner {
+                    account_hash: env::sha256(owner_id.as_bytes()),
+                })
+            });
+            for token_id in token_ids {
+                owner_tokens.insert(&token_id.to_string(), &());
+            }
+            tokens_per_owner.insert(owner_id, &owner_tokens);
+        }
+    }
+
+    /// Removes `token_id` from the tokens of `owner_id`, if the enumeration extension is used.
+    pub(crate) fn internal_remove_token_from_owner(
+        &mut self,
+        owner_id: &AccountId,
+        #[allow(clippy::ptr_arg)] token_id: &TokenId,
+    ) {
+        if let Some(tokens_per_owner) = &mut self.tokens_per_owner {
+            let mut owner_tokens =
+                tokens_per_owner.get(owner_id).unwrap_or_else(missing_owner_tokens);
+            owner_tokens.remove(token_id);
+            if owner_tokens.is_empty() {
+                tokens_per_owner.remove(owner_id);
+            } else {
+                tokens_per_owner.insert(owner_id, &owner_tokens);
+            }
+        } else if let Some(mut tokens_per_owner) = self.sorted_tokens_per_owner() {
+            let mut owner_tokens =
+                tokens_per_owner.get(owner_id).unwrap_or_else(missing_owner_tokens);
+            owner_tokens.remove(token_id);
+            if owner_tokens.is_empty() {
+                tokens_per_owner.remove(owner_id);
+            } else {
+                tokens_per_owner.insert(owner_id, &owner_tokens);
+            }
+        }
+    }
+
+    /// Returns up to `limit` tokens of `account_id` from `from_token_id` included, or from the
+    /// first one, sorted by token ID. Pass the ID following the last token of a page to get the
+    /// next one. The page is sought in the tree of the owner, so a page of `k` tokens reads
+    /// `O(log n + k)` storage entries, for an owner of `n` tokens.
+    ///
+    /// Panics unless the token uses [`with_sorted_enumeration`](Self::with_sorted_enumeration).
+    pub fn tokens_for_owner_from(
+        &self,
+        account_id: &AccountId,
+        from_token_id: Option<TokenId>,
+        limit: u64,
+    ) -> Vec<Token> {
+        require!(limit != 0, "Cannot provide limit of 0.");
+        let by_owner = self.sorted_tokens_per_owner().unwrap_or_else(|| {
+            env::panic_str("The tokens of each owner are not sorted, see with_sorted_enumeration.")
+        });
+        let owner_tokens = match by_owner.get(account_id) {
+            Some(owner_tokens) => owner_tokens,
+            None => return vec![],
+        };
+        let first = match from_token_id {
+            Some(from_token_id) => owner_tokens.ceil_key(&from_token_id),
+            None => owner_tokens.min(),
+        };
+        let first = match first {
+            Some(first) => first,
+            None => return vec![],
+        };
+        core::iter::once(first.clone())
+            .chain(owner_tokens.iter_from(first).map(|(token_id, ())| token_id))
+            .take(limit as usize)
+            .map(|token_id| self.enum_get_token(account_id.clone(), token_id))
+            .collect()
+    }
+
+    fn assert_enumeration(&self) {
+        if self.tokens_per_owner.is_none() && self.sorted_tokens_per_owner().is_none() {
+            env::panic_str(
+                "Could not find tokens_per_owner when calling a method on the \
+                enumeration standard.",
+            )
+        }
+    }
+
     /// Helper function used by a enumerations methods
     /// Note: this method is not exposed publicly to end users
     fn enum_get_token(&self, owner_id: AccountId, token_id: TokenId) -> Token {
@@ -44,16 +184,15 @@ impl NonFungibleTokenEnumeration for NonFungibleToken {
     }
 
     fn nft_supply_for_owner(&self, account_id: AccountId) -> U128 {
-        let tokens_per_owner = self.tokens_per_owner.as_ref().unwrap_or_else(|| {
-            env::panic_str(
-                "Could not find tokens_per_owner when calling a method on the \
-                enumeration standard.",
-            )
-        });
-        tokens_per_owner
-            .get(&account_id)
-            .map(|account_tokens| U128::from(account_tokens.len() as u128))
-            .unwrap_or(U128(0))
+        self.assert_enumeration();
+        let len = match &self.tokens_per_owner {
+            Some(tokens_per_owner) => tokens_per_owner.get(&account_id).map(|set| set.len()),
+            None => self
+                .sorted_tokens_per_owner()
+                .and_then(|tokens_per_owner| tokens_per_owner.get(&account_id))
+                .map(|map| map.len()),
+        };
+        U128::from(len.unwrap_or(0) as u128)
     }
 
     fn nft_tokens_for_owner(
@@ -62,29 +201,204 @@ impl NonFungibleTokenEnumeration for NonFungibleToken {
         from_index: Option<U128>,
         limit: Option<u64>,
     ) -> Vec<Token> {
-        let tokens_per_owner = self.tokens_per_owner.as_ref().unwrap_or_else(|| {
-            env::panic_str(
-                "Could not find tokens_per_owner when calling a method on the \
-                enumeration standard.",
-            )
-        });
-        let token_set = if let Some(token_set) = tokens_per_owner.get(&account_id) {
-            token_set
+        self.assert_enumeration();
+        let token_ids = if let Some(tokens_per_owner) = &self.tokens_per_owner {
+            let token_set = if let Some(token_set) = tokens_per_owner.get(&account_id) {
+                token_set
+            } else {
+                return vec![];
+            };
+            let (start_index, limit) = owner_page(token_set.len(), from_index, limit);
+            token_set.as_vector().to_paginated_vec(start_index, limit)
         } else {
-            return vec![];
+            let owner_tokens = if let Some(owner_tokens) =
+                self.sorted_tokens_per_owner().and_then(|by_owner| by_owner.get(&account_id))
+            {
+                owner_tokens
+            } else {
+                return vec![];
+            };
+            let (start_index, limit) = owner_page(owner_tokens.len(), from_index, limit);
+            owner_tokens.keys().skip(start_index as usize).take(limit as usize).collect()
         };
-        let limit = limit.map(|v| v as usize).unwrap_or(usize::MAX);
-        require!(limit != 0, "Cannot provide limit of 0.");
-        let start_index: u128 = from_index.map(From::from).unwrap_or_default();
-        require!(
-            token_set.len() as u128 > start_index,
-            "Out of bounds, please use a smaller from_index."
-        );
-        token_set
-            .iter()
-            .skip(start_index as usize)
-            .take(limit)
+        token_ids
+            .into_iter()
             .map(|token_id| self.enum_get_token(account_id.clone(), token_id))
             .collect()
     }
 }
+
+/// The tokens of an owner should always exist.
+fn missing_owner_tokens<T>() -> T {
+    env::panic_str("Unable to access tokens per owner in unguarded call.")
+}
+
+/// Checks the pagination of the `len` tokens of an owner and returns the index to start from and
+/// the number of tokens to return.
+fn owner_page(len: u64, from_index: Option<U128>, limit: Option<u64>) -> (u64, u64) {
+    let limit = limit.unwrap_or(u64::MAX);
+    require!(limit != 0, "Cannot provide limit of 0.");
+    let start_index: u128 = from_index.map(From::from).unwrap_or_default();
+    require!(len as u128 > start_index, "Out of bounds, please use a smaller from_index.");
+    (start_index as u64, limit)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::non_fungible_token::metadata::TokenMetadata;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::{testing_env, ONE_NEAR};
+
+    fn set_context(predecessor: AccountId) {
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(accounts(0))
+            .predecessor_account_id(predecessor)
+            .attached_deposit(ONE_NEAR)
+            .build());
+    }
+
+    fn new_token() -> NonFungibleToken {
+        set_context(accounts(0));
+        NonFungibleToken::new(
+            b"o".to_vec(),
+            accounts(0),
+            Some(b"m".to_vec()),
+            Some(b"e".to_vec()),
+            Some(b"a".to_vec()),
+        )
+    }
+
+    fn mint(token: &mut NonFungibleToken, token_id: &str, owner_id: AccountId) {
+        set_context(accounts(0));
+        let metadata = TokenMetadata {
+            title: Some(token_id.to_string()),
+            description: None,
+            media: None,
+            media_hash: None,
+            copies: None,
+            issued_at: None,
+            expires_at: None,
+            starts_at: None,
+            updated_at: None,
+            extra: None,
+            reference: None,
+            reference_hash: None,
+        };
+        token.internal_mint(token_id.to_string(), owner_id, Some(metadata));
+    }
+
+    fn page(
+        token: &NonFungibleToken,
+        owner_id: AccountId,
+        from_index: u128,
+        limit: u64,
+    ) -> Vec<TokenId> {
+        token
+            .nft_tokens_for_owner(owner_id, Some(U128(from_index)), Some(limit))
+            .into_iter()
+            .map(|token| token.token_id)
+            .collect()
+    }
+
+    #[test]
+    fn sorted_pagination_is_stable() {
+        let mut token = new_token().with_sorted_enumeration(b"s".to_vec());
+        assert!(token.tokens_per_owner.is_none());
+        for token_id in ["d", "b", "f", "a", "e", "c"] {
+            mint(&mut token, token_id, accounts(1));
+        }
+        assert_eq!(page(&token, accounts(1), 0, 2), ["a", "b"]);
+
+        // Tokens leaving and joining the holdings between pages don't reorder the others.
+        token.internal_burn(&"e".to_string(), &accounts(1));
+        token.internal_transfer(&accounts(1), &accounts(2), &"b".to_string(), None, None);
+        mint(&mut token, "g", accounts(2));
+        token.internal_transfer(&accounts(2), &accounts(1), &"g".to_string(), None, None);
+        mint(&mut token, "0", accounts(2));
+        assert_eq!(page(&token, accounts(1), 2, 2), ["d", "f"]);
+        assert_eq!(page(&token, accounts(1), 4, 2), ["g"]);
+        assert_eq!(page(&token, accounts(1), 0, 10), ["a", "c", "d", "f", "g"]);
+        assert_eq!(page(&token, accounts(2), 0, 10), ["0", "b"]);
+        assert_eq!(token.nft_supply_for_owner(accounts(1)), U128(5));
+        assert_eq!(token.nft_supply_for_owner(accounts(3)), U128(0));
+        assert!(token.nft_tokens_for_owner(accounts(3), None, None).is_empty());
+
+        for token_id in ["a", "c", "d", "f", "g"] {
+            token.internal_burn(&token_id.to_string(), &accounts(1));
+        }
+        assert_eq!(token.nft_supply_for_owner(accounts(1)), U128(0));
+        assert!(token.sorted_tokens_per_owner().unwrap().get(&accounts(1)).is_none());
+    }
+
+    #[test]
+    fn sorted_pages_by_token_id() {
+        let mut token = new_token().with_sorted_enumeration(b"s".to_vec());
+        for token_id in ["d", "b", "f", "a", "e", "c"] {
+            mint(&mut token, token_id, accounts(1));
+        }
+        let page_from = |token: &NonFungibleToken, from: Option<&str>| -> Vec<TokenId> {
+            token
+                .tokens_for_owner_from(&accounts(1), from.map(str::to_string), 2)
+                .into_iter()
+                .map(|token| token.token_id)
+                .collect()
+        };
+        assert_eq!(page_from(&token, None), ["a", "b"]);
+        assert_eq!(page_from(&token, Some("c")), ["c", "d"]);
+
+        // The page starts at the next token if the token sought is gone.
+        token.internal_burn(&"e".to_string(), &accounts(1));
+        assert_eq!(page_from(&token, Some("e")), ["f"]);
+        assert_eq!(page_from(&token, Some("bb")), ["c", "d"]);
+        assert!(page_from(&token, Some("g")).is_empty());
+        assert!(token.tokens_for_owner_from(&accounts(2), None, 2).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "The tokens of each owner are not sorted")]
+    fn unordered_pages_by_token_id() {
+        new_token().tokens_for_owner_from(&accounts(1), None, 2);
+    }
+
+    #[test]
+    fn sorted_enumeration_measures_storage() {
+        let unordered = new_token().extra_storage_in_bytes_per_token;
+        let sorted = new_token().with_sorted_enumeration(b"s".to_vec());
+        set_context(accounts(0));
+        let without_enumeration = NonFungibleToken::new(
+            b"o".to_vec(),
+            accounts(0),
+            Some(b"m".to_vec()),
+            None::<Vec<u8>>,
+            Some(b"a".to_vec()),
+        )
+        .with_sorted_enumeration(b"s".to_vec());
+        assert_ne!(sorted.extra_storage_in_bytes_per_token, unordered);
+        assert_eq!(
+            sorted.extra_storage_in_bytes_per_token,
+            without_enumeration.extra_storage_in_bytes_per_token
+        );
+    }
+
+    #[test]
+    fn unordered_by_default() {
+        let mut token = new_token();
+        assert!(token.sorted_tokens_per_owner().is_none());
+        for token_id in ["a", "b", "c"] {
+            mint(&mut token, token_id, accounts(1));
+        }
+        // Burning a token moves the last token in its place.
+        token.internal_burn(&"a".to_string(), &accounts(1));
+        assert_eq!(page(&token, accounts(1), 0, 10), ["c", "b"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Out of bounds, please use a smaller from_index.")]
+    fn sorted_page_out_of_bounds() {
+        let mut token = new_token().with_sorted_enumeration(b"s".to_vec());
+        mint(&mut token, "a", accounts(1));
+        page(&token, accounts(1), 1, 10);
+    }
+}
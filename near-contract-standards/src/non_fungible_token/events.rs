@@ -7,15 +7,23 @@
 //! This is an extension of the events format (nep-297):
 //! <https://github.com/near/NEPs/blob/master/specs/Standards/EventsFormat.md>
 //!
-//! The three events in this standard are [`NftMint`], [`NftTransfer`], and [`NftBurn`].
+//! The events in this standard are [`NftMint`], [`NftTransfer`] and [`NftBurn`], and since version
+//! 1.1.0 [`NftMetadataUpdate`] and [`NftContractMetadataUpdate`]. Each event is logged with the
+//! version which introduced it, so the logs of the first three are unchanged.
 //!
 //! These events can be logged by calling `.emit()` on them if a single event, or calling
-//! [`NftMint::emit_many`], [`NftTransfer::emit_many`],
-//! or [`NftBurn::emit_many`] respectively.
+//! [`NftMint::emit_many`], [`NftTransfer::emit_many`], [`NftBurn::emit_many`],
+//! or [`NftMetadataUpdate::emit_many`] respectively.
+//!
+//! The events emitted by this crate list their token ids in lexicographic order, whatever the
+//! order of the collections they come from, so that the same calls always log the same events.
+//!
+//! Their owned counterparts, such as [`NftMintData`], are what
+//! [`NearEvent::from_log`](crate::event::NearEvent::from_log) parses the logs into.
 
-use crate::event::NearEvent;
+use crate::event::{emit_events, NearEventData};
 use near_sdk::AccountId;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 /// Data to log for an NFT mint event. To log this event, call [`.emit()`](NftMint::emit).
 #[must_use]
@@ -37,10 +45,16 @@ impl NftMint<'_> {
     /// Emits an nft mint event, through [`env::log_str`](near_sdk::env::log_str),
     /// where each [`NftMint`] represents the data of each mint.
     pub fn emit_many(data: &[NftMint<'_>]) {
-        new_171_v1(Nep171EventKind::NftMint(data)).emit()
+        emit_events(data)
     }
 }
 
+impl NearEventData for NftMint<'_> {
+    const STANDARD: &'static str = "nep171";
+    const VERSION: &'static str = "1.0.0";
+    const EVENT: &'static str = "nft_mint";
+}
+
 /// Data to log for an NFT transfer event. To log this event,
 /// call [`.emit()`](NftTransfer::emit).
 #[must_use]
@@ -65,10 +79,16 @@ impl NftTransfer<'_> {
     /// Emits an nft transfer event, through [`env::log_str`](near_sdk::env::log_str),
     /// where each [`NftTransfer`] represents the data of each transfer.
     pub fn emit_many(data: &[NftTransfer<'_>]) {
-        new_171_v1(Nep171EventKind::NftTransfer(data)).emit()
+        emit_events(data)
     }
 }
 
+impl NearEventData for NftTransfer<'_> {
+    const STANDARD: &'static str = "nep171";
+    const VERSION: &'static str = "1.0.0";
+    const EVENT: &'static str = "nft_transfer";
+}
+
 /// Data to log for an NFT burn event. To log this event, call [`.emit()`](NftBurn::emit).
 #[must_use]
 #[derive(Serialize, Debug, Clone)]
@@ -91,33 +111,157 @@ impl NftBurn<'_> {
     /// Emits an nft burn event, through [`env::log_str`](near_sdk::env::log_str),
     /// where each [`NftBurn`] represents the data of each burn.
     pub fn emit_many<'a>(data: &'a [NftBurn<'a>]) {
-        new_171_v1(Nep171EventKind::NftBurn(data)).emit()
+        emit_events(data)
+    }
+}
+
+impl NearEventData for NftBurn<'_> {
+    const STANDARD: &'static str = "nep171";
+    const VERSION: &'static str = "1.0.0";
+    const EVENT: &'static str = "nft_burn";
+}
+
+/// Data to log for an NFT metadata update event, when the metadata of tokens changed. To log this
+/// event, call [`.emit()`](NftMetadataUpdate::emit).
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+pub struct NftMetadataUpdate<'a> {
+    pub token_ids: &'a [&'a str],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<&'a str>,
+}
+
+impl NftMetadataUpdate<'_> {
+    /// Logs the event to the host. This is required to ensure that the event is triggered
+    /// and to consume the event.
+    pub fn emit(self) {
+        Self::emit_many(&[self])
+    }
+
+    /// Emits an nft metadata update event, through [`env::log_str`](near_sdk::env::log_str),
+    /// where each [`NftMetadataUpdate`] represents the data of each update.
+    pub fn emit_many(data: &[NftMetadataUpdate<'_>]) {
+        emit_events(data)
+    }
+}
+
+impl NearEventData for NftMetadataUpdate<'_> {
+    const STANDARD: &'static str = "nep171";
+    const VERSION: &'static str = "1.1.0";
+    const EVENT: &'static str = "nft_metadata_update";
+}
+
+/// Data to log for a contract metadata update event, when the metadata of the contract changed.
+/// To log this event, call [`.emit()`](NftContractMetadataUpdate::emit).
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+pub struct NftContractMetadataUpdate<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<&'a str>,
+}
+
+impl NftContractMetadataUpdate<'_> {
+    /// Logs the event to the host. This is required to ensure that the event is triggered
+    /// and to consume the event.
+    pub fn emit(self) {
+        emit_events(&[self])
     }
 }
 
-#[derive(Serialize, Debug)]
-pub(crate) struct Nep171Event<'a> {
-    version: &'static str,
+impl NearEventData for NftContractMetadataUpdate<'_> {
+    const STANDARD: &'static str = "nep171";
+    const VERSION: &'static str = "1.1.0";
+    const EVENT: &'static str = "contract_metadata_update";
+}
+
+/// Returns `token_ids` in the order the events of this crate list them: sorted lexicographically.
+pub(crate) fn sort_token_ids<'a, I>(token_ids: I) -> Vec<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let mut token_ids: Vec<&str> = token_ids.into_iter().collect();
+    token_ids.sort_unstable();
+    token_ids
+}
+
+/// Owned data of an [`NftMint`] event, as parsed by
+/// [`NearEvent::from_log`](crate::event::NearEvent::from_log).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct NftMintData {
+    pub owner_id: AccountId,
+    pub token_ids: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+}
+
+/// Owned data of an [`NftTransfer`] event.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct NftTransferData {
+    pub old_owner_id: AccountId,
+    pub new_owner_id: AccountId,
+    pub token_ids: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub authorized_id: Option<AccountId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+}
+
+/// Owned data of an [`NftBurn`] event.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct NftBurnData {
+    pub owner_id: AccountId,
+    pub token_ids: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub authorized_id: Option<AccountId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+}
+
+/// Owned data of an [`NftMetadataUpdate`] event.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct NftMetadataUpdateData {
+    pub token_ids: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+}
+
+/// Owned data of an [`NftContractMetadataUpdate`] event.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct NftContractMetadataUpdateData {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+}
+
+/// An owned nep171 event.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Nep171Event {
+    pub version: String,
     #[serde(flatten)]
-    event_kind: Nep171EventKind<'a>,
+    pub event_kind: Nep171EventKind,
 }
 
-#[derive(Serialize, Debug)]
+/// The kind and the data of an owned nep171 event.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(tag = "event", content = "data")]
 #[serde(rename_all = "snake_case")]
 #[allow(clippy::enum_variant_names)]
-enum Nep171EventKind<'a> {
-    NftMint(&'a [NftMint<'a>]),
-    NftTransfer(&'a [NftTransfer<'a>]),
-    NftBurn(&'a [NftBurn<'a>]),
+pub enum Nep171EventKind {
+    NftMint(Vec<NftMintData>),
+    NftTransfer(Vec<NftTransferData>),
+    NftBurn(Vec<NftBurnData>),
+    NftMetadataUpdate(Vec<NftMetadataUpdateData>),
+    #[serde(rename = "contract_metadata_update")]
+    NftContractMetadataUpdate(Vec<NftContractMetadataUpdateData>),
 }
 
-fn new_171<'a>(version: &'static str, event_kind: Nep171EventKind<'a>) -> NearEvent<'a> {
-    NearEvent::Nep171(Nep171Event { version, event_kind })
-}
-
-fn new_171_v1(event_kind: Nep171EventKind) -> NearEvent {
-    new_171("1.0.0", event_kind)
+impl Nep171EventKind {
+    /// Version of the standard which introduced this kind of event, which it is logged with.
+    pub fn version(&self) -> &'static str {
+        match self {
+            Self::NftMint(_) | Self::NftTransfer(_) | Self::NftBurn(_) => "1.0.0",
+            Self::NftMetadataUpdate(_) | Self::NftContractMetadataUpdate(_) => "1.1.0",
+        }
+    }
 }
 
 #[cfg(test)]
@@ -222,4 +366,39 @@ mod tests {
             r#"EVENT_JSON:{"standard":"nep171","version":"1.0.0","event":"nft_transfer","data":[{"old_owner_id":"alice","new_owner_id":"bob","token_ids":["2","3"],"authorized_id":"bob","memo":"has memo"},{"old_owner_id":"bob","new_owner_id":"alice","token_ids":["0","1"]}]}"#
         );
     }
+
+    #[test]
+    fn nft_metadata_update() {
+        NftMetadataUpdate { token_ids: &["0", "1"], memo: None }.emit();
+        NftMetadataUpdate::emit_many(&[
+            NftMetadataUpdate { token_ids: &["2"], memo: Some("reveal") },
+            NftMetadataUpdate { token_ids: &["3"], memo: None },
+        ]);
+        assert_eq!(
+            test_utils::get_logs(),
+            [
+                r#"EVENT_JSON:{"standard":"nep171","version":"1.1.0","event":"nft_metadata_update","data":[{"token_ids":["0","1"]}]}"#,
+                r#"EVENT_JSON:{"standard":"nep171","version":"1.1.0","event":"nft_metadata_update","data":[{"token_ids":["2"],"memo":"reveal"},{"token_ids":["3"]}]}"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn nft_contract_metadata_update() {
+        NftContractMetadataUpdate { memo: None }.emit();
+        NftContractMetadataUpdate { memo: Some("new icon") }.emit();
+        assert_eq!(
+            test_utils::get_logs(),
+            [
+                r#"EVENT_JSON:{"standard":"nep171","version":"1.1.0","event":"contract_metadata_update","data":[{}]}"#,
+                r#"EVENT_JSON:{"standard":"nep171","version":"1.1.0","event":"contract_metadata_update","data":[{"memo":"new icon"}]}"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn sorted_token_ids() {
+        assert_eq!(sort_token_ids(vec!["b", "10", "a", "2", "1"]), ["1", "10", "2", "a", "b"]);
+        assert!(sort_token_ids(Vec::new()).is_empty());
+    }
 }
@@ -1,10 +1,49 @@
 /// The core methods for a basic non-fungible token. Extension standards may be
 /// added in addition to this macro.
+///
+/// Takes the name of the contract struct and the inner field for the token. The following options
+/// can be added after it, each in brackets and in this order:
+///
+/// - `[hook = field]`: a field of the contract implementing
+///   [`NonFungibleTokenTransferHook`](crate::non_fungible_token::core::NonFungibleTokenTransferHook),
+///   run around the transfers of `nft_transfer` and `nft_transfer_call`, and when
+///   `nft_resolve_transfer` returns a token.
+/// - `[gas = expr]`: the [`NftTransferCallGas`](crate::non_fungible_token::NftTransferCallGas)
+///   reserved by `nft_transfer_call`, for receivers or resolvers which need more gas than the
+///   default.
+///
+/// ```ignore
+/// near_contract_standards::impl_non_fungible_token_core!(
+///     Contract,
+///     tokens,
+///     [hook = transfer_hook],
+///     [gas = NftTransferCallGas { resolve_transfer: Gas(10 * Gas::ONE_TERA.0), ..NftTransferCallGas::DEFAULT }]
+/// );
+/// ```
 #[macro_export]
 macro_rules! impl_non_fungible_token_core {
-    ($contract: ident, $token: ident) => {
+    ($contract: ident, $token: ident $(, [hook = $hook:ident])? $(, [gas = $gas:expr])?) => {
+        $crate::__token_field!(
+            $contract.$token: $crate::non_fungible_token::NonFungibleToken,
+            __nft_core_token
+        );
         use $crate::non_fungible_token::core::NonFungibleTokenCore;
         use $crate::non_fungible_token::core::NonFungibleTokenResolver;
+        $crate::__register_standard!(nep171);
+
+        impl $contract {
+            /// Calls `f` with the token and the transfer hook.
+            #[doc(hidden)]
+            fn __nft_core_transfer<R>(
+                &mut self,
+                f: impl FnOnce(
+                    &mut $crate::non_fungible_token::NonFungibleToken,
+                    &mut dyn $crate::non_fungible_token::core::NonFungibleTokenTransferHook,
+                ) -> R,
+            ) -> R {
+                f(&mut self.$token, $crate::__or_default!((&mut ()) $(&mut self.$hook)?))
+            }
+        }
 
         #[near_bindgen]
         impl NonFungibleTokenCore for $contract {
@@ -16,7 +55,9 @@ macro_rules! impl_non_fungible_token_core {
                 approval_id: Option<u64>,
                 memo: Option<String>,
             ) {
-                self.$token.nft_transfer(receiver_id, token_id, approval_id, memo)
+                self.__nft_core_transfer(|token, hook| {
+                    token.nft_transfer_with_hook(hook, receiver_id, token_id, approval_id, memo)
+                })
             }
 
             #[payable]
@@ -28,11 +69,23 @@ macro_rules! impl_non_fungible_token_core {
                 memo: Option<String>,
                 msg: String,
             ) -> PromiseOrValue<bool> {
-                self.$token.nft_transfer_call(receiver_id, token_id, approval_id, memo, msg)
+                self.__nft_core_transfer(|token, hook| {
+                    token.nft_transfer_call_with_hook(
+                        hook,
+                        receiver_id,
+                        token_id,
+                        approval_id,
+                        memo,
+                        msg,
+                        $crate::__or_default!(
+                            ($crate::non_fungible_token::NftTransferCallGas::DEFAULT) $($gas)?
+                        ),
+                    )
+                })
             }
 
             fn nft_token(&self, token_id: TokenId) -> Option<Token> {
-                self.$token.nft_token(token_id)
+                self.__nft_core_token().nft_token(token_id)
             }
         }
 
@@ -44,14 +97,39 @@ macro_rules! impl_non_fungible_token_core {
                 previous_owner_id: AccountId,
                 receiver_id: AccountId,
                 token_id: TokenId,
-                approved_account_ids: Option<std::collections::HashMap<AccountId, u64>>,
+                approved_account_ids: Option<std::collections::BTreeMap<AccountId, u64>>,
             ) -> bool {
-                self.$token.nft_resolve_transfer(
-                    previous_owner_id,
-                    receiver_id,
-                    token_id,
-                    approved_account_ids,
-                )
+                self.__nft_core_transfer(|token, hook| {
+                    token.nft_resolve_transfer_with_hook(
+                        hook,
+                        previous_owner_id,
+                        receiver_id,
+                        token_id,
+                        approved_account_ids,
+                    )
+                })
+            }
+        }
+    };
+}
+
+/// Exposes `nft_burn`, with which the owner of a token or an approved account burns it. This isn't
+/// part of the standards, so contracts only use it for burnable tokens.
+#[macro_export]
+macro_rules! impl_non_fungible_token_burn {
+    ($contract: ident, $token: ident) => {
+        $crate::__token_field!(
+            $contract.$token: $crate::non_fungible_token::NonFungibleToken,
+            __nft_burn_token,
+            __nft_burn_token_mut
+        );
+        use $crate::non_fungible_token::core::NonFungibleTokenBurn;
+
+        #[near_bindgen]
+        impl NonFungibleTokenBurn for $contract {
+            #[payable]
+            fn nft_burn(&mut self, token_id: TokenId) {
+                self.__nft_burn_token_mut().nft_burn(token_id)
             }
         }
     };
@@ -62,7 +140,15 @@ macro_rules! impl_non_fungible_token_core {
 #[macro_export]
 macro_rules! impl_non_fungible_token_approval {
     ($contract: ident, $token: ident) => {
-        use $crate::non_fungible_token::approval::NonFungibleTokenApproval;
+        $crate::__token_field!(
+            $contract.$token: $crate::non_fungible_token::NonFungibleToken,
+            __nft_approval_token,
+            __nft_approval_token_mut
+        );
+        use $crate::non_fungible_token::approval::{
+            NonFungibleTokenApproval, NonFungibleTokenRevokeAllContinuation,
+        };
+        $crate::__register_standard!(nep178);
 
         #[near_bindgen]
         impl NonFungibleTokenApproval for $contract {
@@ -73,17 +159,17 @@ macro_rules! impl_non_fungible_token_approval {
                 account_id: AccountId,
                 msg: Option<String>,
             ) -> Option<Promise> {
-                self.$token.nft_approve(token_id, account_id, msg)
+                self.__nft_approval_token_mut().nft_approve(token_id, account_id, msg)
             }
 
             #[payable]
             fn nft_revoke(&mut self, token_id: TokenId, account_id: AccountId) {
-                self.$token.nft_revoke(token_id, account_id)
+                self.__nft_approval_token_mut().nft_revoke(token_id, account_id)
             }
 
             #[payable]
             fn nft_revoke_all(&mut self, token_id: TokenId) {
-                self.$token.nft_revoke_all(token_id)
+                self.__nft_approval_token_mut().nft_revoke_all(token_id)
             }
 
             fn nft_is_approved(
@@ -92,7 +178,28 @@ macro_rules! impl_non_fungible_token_approval {
                 approved_account_id: AccountId,
                 approval_id: Option<u64>,
             ) -> bool {
-                self.$token.nft_is_approved(token_id, approved_account_id, approval_id)
+                self.__nft_approval_token().nft_is_approved(
+                    token_id,
+                    approved_account_id,
+                    approval_id,
+                )
+            }
+        }
+
+        #[near_bindgen]
+        impl NonFungibleTokenRevokeAllContinuation for $contract {
+            #[private]
+            fn nft_revoke_all_continue(
+                &mut self,
+                token_id: TokenId,
+                owner_id: AccountId,
+                next_approval_id: u64,
+            ) {
+                self.__nft_approval_token_mut().nft_revoke_all_continue(
+                    token_id,
+                    owner_id,
+                    next_approval_id,
+                )
             }
         }
     };
@@ -103,12 +210,17 @@ macro_rules! impl_non_fungible_token_approval {
 #[macro_export]
 macro_rules! impl_non_fungible_token_enumeration {
     ($contract: ident, $token: ident) => {
+        $crate::__token_field!(
+            $contract.$token: $crate::non_fungible_token::NonFungibleToken,
+            __nft_enumeration_token
+        );
         use $crate::non_fungible_token::enumeration::NonFungibleTokenEnumeration;
+        $crate::__register_standard!(nep181);
 
         #[near_bindgen]
         impl NonFungibleTokenEnumeration for $contract {
             fn nft_total_supply(&self) -> near_sdk::json_types::U128 {
-                self.$token.nft_total_supply()
+                self.__nft_enumeration_token().nft_total_supply()
             }
 
             fn nft_tokens(
@@ -116,11 +228,11 @@ macro_rules! impl_non_fungible_token_enumeration {
                 from_index: Option<near_sdk::json_types::U128>,
                 limit: Option<u64>,
             ) -> Vec<Token> {
-                self.$token.nft_tokens(from_index, limit)
+                self.__nft_enumeration_token().nft_tokens(from_index, limit)
             }
 
             fn nft_supply_for_owner(&self, account_id: AccountId) -> near_sdk::json_types::U128 {
-                self.$token.nft_supply_for_owner(account_id)
+                self.__nft_enumeration_token().nft_supply_for_owner(account_id)
             }
 
             fn nft_tokens_for_owner(
@@ -129,7 +241,119 @@ macro_rules! impl_non_fungible_token_enumeration {
                 from_index: Option<near_sdk::json_types::U128>,
                 limit: Option<u64>,
             ) -> Vec<Token> {
-                self.$token.nft_tokens_for_owner(account_id, from_index, limit)
+                self.__nft_enumeration_token().nft_tokens_for_owner(account_id, from_index, limit)
+            }
+        }
+    };
+}
+
+/// Non-fungible token metadata exposes `nft_metadata` and `nft_supports_spec`, returning the
+/// contract metadata stored in `$metadata`. The field can be a
+/// `LazyOption<NFTContractMetadata>`, a `MetadataSource` or any other type implementing
+/// `NonFungibleTokenMetadataProvider`. `nft_metadata` panics if the stored metadata isn't valid.
+#[macro_export]
+macro_rules! impl_non_fungible_token_metadata {
+    ($contract: ident, $metadata: ident) => {
+        $crate::__token_field!(
+            $contract.$metadata: dyn $crate::non_fungible_token::metadata::NonFungibleTokenMetadataProvider,
+            __nft_metadata_provider
+        );
+        use $crate::non_fungible_token::metadata::NonFungibleTokenMetadataProvider;
+        $crate::__register_standard!(nep177);
+
+        #[near_bindgen]
+        impl NonFungibleTokenMetadataProvider for $contract {
+            fn nft_metadata(&self) -> $crate::non_fungible_token::metadata::NFTContractMetadata {
+                let metadata = self.__nft_metadata_provider().nft_metadata();
+                metadata.assert_valid();
+                metadata
+            }
+
+            fn nft_supports_spec(&self, spec: String) -> bool {
+                self.__nft_metadata_provider().nft_supports_spec(spec)
+            }
+        }
+    };
+}
+
+/// Implements the core, approval management, enumeration and metadata standards, for a
+/// `NonFungibleToken` stored in `$token` and its contract metadata stored in `$metadata`. See
+/// [`impl_non_fungible_token_metadata!`] for the types `$metadata` can have.
+#[macro_export]
+macro_rules! impl_non_fungible_token {
+    ($contract: ident, $token: ident, $metadata: ident) => {
+        $crate::impl_non_fungible_token_core!($contract, $token);
+        $crate::impl_non_fungible_token_approval!($contract, $token);
+        $crate::impl_non_fungible_token_enumeration!($contract, $token);
+        $crate::impl_non_fungible_token_metadata!($contract, $metadata);
+    };
+}
+
+/// Non-fungible token payouts let marketplaces pay the royalties of a token when selling it, with
+/// `nft_payout` and `nft_transfer_payout`. The token must be created with
+/// `NonFungibleToken::with_royalties`.
+///
+/// Takes the name of the contract struct and the inner field for the token, optionally followed
+/// by `[hook = field]`, the
+/// [`NonFungibleTokenTransferHook`](crate::non_fungible_token::core::NonFungibleTokenTransferHook)
+/// run around the transfers of `nft_transfer_payout`. It should be the hook given to
+/// [`impl_non_fungible_token_core!`](crate::impl_non_fungible_token_core).
+#[macro_export]
+macro_rules! impl_non_fungible_token_payout {
+    ($contract: ident, $token: ident $(, [hook = $hook:ident])?) => {
+        $crate::__token_field!(
+            $contract.$token: $crate::non_fungible_token::NonFungibleToken,
+            __nft_payout_token
+        );
+        use $crate::non_fungible_token::payout::NonFungibleTokenPayout;
+        $crate::__register_standard!(nep199);
+
+        impl $contract {
+            /// Calls `f` with the token and the transfer hook.
+            #[doc(hidden)]
+            fn __nft_payout_transfer<R>(
+                &mut self,
+                f: impl FnOnce(
+                    &mut $crate::non_fungible_token::NonFungibleToken,
+                    &mut dyn $crate::non_fungible_token::core::NonFungibleTokenTransferHook,
+                ) -> R,
+            ) -> R {
+                f(&mut self.$token, $crate::__or_default!((&mut ()) $(&mut self.$hook)?))
+            }
+        }
+
+        #[near_bindgen]
+        impl NonFungibleTokenPayout for $contract {
+            fn nft_payout(
+                &self,
+                token_id: TokenId,
+                balance: near_sdk::json_types::U128,
+                max_len_payout: Option<u32>,
+            ) -> $crate::non_fungible_token::payout::Payout {
+                self.__nft_payout_token().nft_payout(token_id, balance, max_len_payout)
+            }
+
+            #[payable]
+            fn nft_transfer_payout(
+                &mut self,
+                receiver_id: AccountId,
+                token_id: TokenId,
+                approval_id: Option<u64>,
+                memo: Option<String>,
+                balance: near_sdk::json_types::U128,
+                max_len_payout: Option<u32>,
+            ) -> $crate::non_fungible_token::payout::Payout {
+                self.__nft_payout_transfer(|token, hook| {
+                    token.nft_transfer_payout_with_hook(
+                        hook,
+                        receiver_id,
+                        token_id,
+                        approval_id,
+                        memo,
+                        balance,
+                        max_len_payout,
+                    )
+                })
             }
         }
     };
@@ -1,7 +1,10 @@
+use crate::spec::{assert_supported_spec, supports_spec};
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::LazyOption;
 use near_sdk::json_types::Base64VecU8;
-use near_sdk::require;
 use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, require, AccountId, IntoStorageKey};
+use std::cmp::Ordering;
 
 /// This spec can be treated like a version of the standard.
 pub const NFT_METADATA_SPEC: &str = "nft-1.0.0";
@@ -19,6 +22,29 @@ pub struct NFTContractMetadata {
     pub reference_hash: Option<Base64VecU8>, // Base64-encoded sha256 hash of JSON from reference field. Required if `reference` is included.
 }
 
+/// The fields of [`NFTContractMetadata`] required by wallets, for contracts keeping the rest of
+/// their metadata off-chain.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MinimalMetadata {
+    pub spec: String,
+    pub name: String,
+    pub symbol: String,
+}
+
+/// Where the contract metadata is stored.
+///
+/// `Inline` stores the whole [`NFTContractMetadata`] on-chain. `Reference` only stores the fields
+/// wallets require, along with the URI and the hash of a JSON file holding the full metadata, so
+/// that the contract doesn't pay storage for the rest of it. In both cases
+/// [`nft_metadata`](NonFungibleTokenMetadataProvider::nft_metadata) returns the metadata, with
+/// `reference` and `reference_hash` set from the URI and the hash in the `Reference` mode.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub enum MetadataSource {
+    Inline(LazyOption<NFTContractMetadata>),
+    Reference { uri: String, hash: Base64VecU8, minimal: MinimalMetadata },
+}
+
 /// Metadata on the individual token level.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, BorshDeserialize, BorshSerialize)]
 #[serde(crate = "near_sdk::serde")]
@@ -40,31 +66,457 @@ pub struct TokenMetadata {
 /// Offers details on the contract-level metadata.
 pub trait NonFungibleTokenMetadataProvider {
     fn nft_metadata(&self) -> NFTContractMetadata;
+
+    /// Returns `true` if the contract implements a version of the metadata standard compatible
+    /// with `spec`, see [`SpecVersion::is_compatible_with`](crate::spec::SpecVersion::is_compatible_with).
+    fn nft_supports_spec(&self, spec: String) -> bool {
+        self.nft_metadata().supports_spec(&spec)
+    }
 }
 
 impl NFTContractMetadata {
+    /// Panics if the spec isn't a version of [`NFT_METADATA_SPEC`] with the same major version,
+    /// the name or the symbol is empty, or the reference hash is invalid.
     pub fn assert_valid(&self) {
-        require!(self.spec == NFT_METADATA_SPEC, "Spec is not NFT metadata");
-        require!(
-            self.reference.is_some() == self.reference_hash.is_some(),
-            "Reference and reference hash must be present"
+        assert_supported_spec(&self.spec, NFT_METADATA_SPEC, "Spec is not NFT metadata");
+        assert_name_and_symbol(&self.name, &self.symbol);
+        assert_valid_hash(
+            &self.reference,
+            &self.reference_hash,
+            "Reference hash is required with a reference",
+            "Reference hash requires a reference",
+            "Hash has to be 32 bytes",
         );
-        if let Some(reference_hash) = &self.reference_hash {
-            require!(reference_hash.0.len() == 32, "Hash has to be 32 bytes");
+    }
+
+    /// Returns `true` if the spec of this metadata is compatible with `spec`. Malformed specs are
+    /// not supported.
+    pub fn supports_spec(&self, spec: &str) -> bool {
+        supports_spec(&self.spec, spec)
+    }
+}
+
+impl MinimalMetadata {
+    pub fn assert_valid(&self) {
+        assert_supported_spec(&self.spec, NFT_METADATA_SPEC, "Spec is not NFT metadata");
+        assert_name_and_symbol(&self.name, &self.symbol);
+    }
+}
+
+fn assert_name_and_symbol(name: &str, symbol: &str) {
+    require!(!name.is_empty(), "Name can't be empty");
+    require!(!symbol.is_empty(), "Symbol can't be empty");
+}
+
+/// Panics unless `hash` is present exactly when `url` is, and is a base64-encoded sha256 hash.
+fn assert_valid_hash(
+    url: &Option<String>,
+    hash: &Option<Base64VecU8>,
+    missing_hash: &str,
+    missing_url: &str,
+    invalid_length: &str,
+) {
+    match (url, hash) {
+        (Some(_), None) => env::panic_str(missing_hash),
+        (None, Some(_)) => env::panic_str(missing_url),
+        (_, Some(hash)) => require!(hash.0.len() == 32, invalid_length),
+        (None, None) => {}
+    }
+}
+
+/// Compares two timestamps of token metadata, either ISO 8601 datetimes in the same format, which
+/// sort like strings, or integers like the block timestamp. Timestamps in different formats
+/// can't be compared.
+fn compare_timestamps(a: &str, b: &str) -> Option<Ordering> {
+    match (a.parse::<u64>(), b.parse::<u64>()) {
+        (Ok(a), Ok(b)) => Some(a.cmp(&b)),
+        (Err(_), Err(_)) if a.len() == b.len() => Some(a.cmp(b)),
+        _ => None,
+    }
+}
+
+impl MetadataSource {
+    /// Stores `metadata` on-chain under `prefix`.
+    pub fn inline<S>(prefix: S, metadata: &NFTContractMetadata) -> Self
+    where
+        S: IntoStorageKey,
+    {
+        metadata.assert_valid();
+        Self::Inline(LazyOption::new(prefix, Some(metadata)))
+    }
+
+    /// Only stores `minimal` on-chain, with the full metadata in the JSON file at `uri`, whose
+    /// sha256 hash is `hash`.
+    pub fn reference(uri: String, hash: Base64VecU8, minimal: MinimalMetadata) -> Self {
+        minimal.assert_valid();
+        require!(hash.0.len() == 32, "Hash has to be 32 bytes");
+        Self::Reference { uri, hash, minimal }
+    }
+
+    /// Returns `true` if the whole metadata is stored on-chain.
+    pub fn is_inline(&self) -> bool {
+        matches!(self, Self::Inline(_))
+    }
+
+    /// Stores the whole `metadata` on-chain under `prefix`, removing the reference if there was
+    /// one. `prefix` is ignored if the metadata is already stored on-chain. Only `owner_id` can
+    /// change the metadata.
+    pub fn set_inline<S>(&mut self, owner_id: &AccountId, prefix: S, metadata: &NFTContractMetadata)
+    where
+        S: IntoStorageKey,
+    {
+        require!(env::predecessor_account_id() == *owner_id, "Unauthorized");
+        match self {
+            Self::Inline(stored) => {
+                metadata.assert_valid();
+                stored.set(metadata);
+            }
+            Self::Reference { .. } => *self = Self::inline(prefix, metadata),
+        }
+    }
+
+    /// Keeps the metadata in the JSON file at `uri`, whose sha256 hash is `hash`, and only stores
+    /// the fields wallets require on-chain. Metadata stored on-chain is removed from storage. Only
+    /// `owner_id` can change the metadata.
+    pub fn set_reference(&mut self, owner_id: &AccountId, uri: String, hash: Base64VecU8) {
+        require!(env::predecessor_account_id() == *owner_id, "Unauthorized");
+        let metadata = self.nft_metadata();
+        let minimal =
+            MinimalMetadata { spec: metadata.spec, name: metadata.name, symbol: metadata.symbol };
+        let previous = std::mem::replace(self, Self::reference(uri, hash, minimal));
+        if let Self::Inline(mut stored) = previous {
+            stored.remove();
+        }
+    }
+}
+
+impl NonFungibleTokenMetadataProvider for MetadataSource {
+    fn nft_metadata(&self) -> NFTContractMetadata {
+        match self {
+            Self::Inline(stored) => stored
+                .get()
+                .unwrap_or_else(|| env::panic_str("The contract metadata is missing from storage")),
+            Self::Reference { uri, hash, minimal } => NFTContractMetadata {
+                spec: minimal.spec.clone(),
+                name: minimal.name.clone(),
+                symbol: minimal.symbol.clone(),
+                icon: None,
+                base_uri: None,
+                reference: Some(uri.clone()),
+                reference_hash: Some(hash.clone()),
+            },
         }
     }
 }
 
+impl NonFungibleTokenMetadataProvider for LazyOption<NFTContractMetadata> {
+    fn nft_metadata(&self) -> NFTContractMetadata {
+        self.get()
+            .unwrap_or_else(|| env::panic_str("The contract metadata is missing from storage"))
+    }
+}
+
 impl TokenMetadata {
+    /// Panics if the media or reference hash is missing or invalid, `copies` is zero, or the token
+    /// expires before it starts.
     pub fn assert_valid(&self) {
-        require!(self.media.is_some() == self.media_hash.is_some());
-        if let Some(media_hash) = &self.media_hash {
-            require!(media_hash.0.len() == 32, "Media hash has to be 32 bytes");
+        assert_valid_hash(
+            &self.media,
+            &self.media_hash,
+            "Media hash is required with media",
+            "Media hash requires media",
+            "Media hash has to be 32 bytes",
+        );
+        assert_valid_hash(
+            &self.reference,
+            &self.reference_hash,
+            "Reference hash is required with a reference",
+            "Reference hash requires a reference",
+            "Reference hash has to be 32 bytes",
+        );
+        require!(self.copies != Some(0), "Copies can't be zero");
+        if let (Some(starts_at), Some(expires_at)) = (&self.starts_at, &self.expires_at) {
+            require!(
+                compare_timestamps(expires_at, starts_at) != Some(Ordering::Less),
+                "Token expires before it starts"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    fn set_predecessor(account_id: AccountId) {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(account_id).build());
+    }
+
+    fn full_metadata() -> NFTContractMetadata {
+        NFTContractMetadata {
+            spec: NFT_METADATA_SPEC.to_string(),
+            name: "Mosaics".to_string(),
+            symbol: "MOSAIC".to_string(),
+            icon: Some("data:image/svg+xml,<svg></svg>".to_string()),
+            base_uri: Some("https://example.com".to_string()),
+            reference: None,
+            reference_hash: None,
+        }
+    }
+
+    fn minimal_metadata() -> MinimalMetadata {
+        MinimalMetadata {
+            spec: NFT_METADATA_SPEC.to_string(),
+            name: "Mosaics".to_string(),
+            symbol: "MOSAIC".to_string(),
+        }
+    }
+
+    fn hash() -> Base64VecU8 {
+        Base64VecU8::from(vec![7; 32])
+    }
+
+    #[test]
+    fn inline() {
+        set_predecessor(accounts(0));
+        let source = MetadataSource::inline(b"m".to_vec(), &full_metadata());
+        assert!(source.is_inline());
+        assert_eq!(source.nft_metadata(), full_metadata());
+    }
+
+    #[test]
+    fn reference() {
+        set_predecessor(accounts(0));
+        let source = MetadataSource::reference(
+            "ipfs://metadata.json".to_string(),
+            hash(),
+            minimal_metadata(),
+        );
+        assert!(!source.is_inline());
+        assert_eq!(
+            source.nft_metadata(),
+            NFTContractMetadata {
+                icon: None,
+                base_uri: None,
+                reference: Some("ipfs://metadata.json".to_string()),
+                reference_hash: Some(hash()),
+                ..full_metadata()
+            }
+        );
+        // Nothing but the contract state is stored.
+        assert_eq!(near_sdk::test_utils::storage_snapshot().len(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Spec is not NFT metadata")]
+    fn reference_unsupported_spec() {
+        set_predecessor(accounts(0));
+        let minimal = MinimalMetadata { spec: "nft-2.0.0".to_string(), ..minimal_metadata() };
+        MetadataSource::reference("ipfs://metadata.json".to_string(), hash(), minimal);
+    }
+
+    #[test]
+    #[should_panic(expected = "Malformed spec")]
+    fn inline_malformed_spec() {
+        set_predecessor(accounts(0));
+        let metadata = NFTContractMetadata { spec: "nft-1.0".to_string(), ..full_metadata() };
+        MetadataSource::inline(b"m".to_vec(), &metadata);
+    }
+
+    #[test]
+    fn older_and_newer_minor_specs() {
+        for spec in ["nft-1.0.0", "nft-1.1.0", "nft-1.0.2"] {
+            NFTContractMetadata { spec: spec.to_string(), ..full_metadata() }.assert_valid();
+        }
+        let metadata = NFTContractMetadata { spec: "nft-1.1.0".to_string(), ..full_metadata() };
+        assert!(metadata.supports_spec(NFT_METADATA_SPEC));
+        assert!(metadata.supports_spec("nft-1.1.0"));
+        assert!(!metadata.supports_spec("nft-1.2.0"));
+        assert!(!metadata.supports_spec("nft-2.0.0"));
+        assert!(!metadata.supports_spec("nep171"));
+    }
+
+    #[test]
+    fn supports_spec_view() {
+        set_predecessor(accounts(0));
+        let source = MetadataSource::reference(
+            "ipfs://metadata.json".to_string(),
+            hash(),
+            minimal_metadata(),
+        );
+        assert!(source.nft_supports_spec(NFT_METADATA_SPEC.to_string()));
+        assert!(!source.nft_supports_spec("nft-2.0.0".to_string()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Hash has to be 32 bytes")]
+    fn reference_invalid_hash() {
+        set_predecessor(accounts(0));
+        MetadataSource::reference(
+            "ipfs://metadata.json".to_string(),
+            Base64VecU8::from(vec![7; 31]),
+            minimal_metadata(),
+        );
+    }
+
+    #[test]
+    fn switch_modes() {
+        set_predecessor(accounts(0));
+        let mut source = MetadataSource::inline(b"m".to_vec(), &full_metadata());
+
+        source.set_reference(&accounts(0), "ipfs://metadata.json".to_string(), hash());
+        assert!(!source.is_inline());
+        assert_eq!(source.nft_metadata().name, "Mosaics");
+        assert_eq!(source.nft_metadata().reference_hash, Some(hash()));
+        // The metadata stored on-chain was removed.
+        assert_eq!(near_sdk::test_utils::storage_snapshot().len(), 0);
+
+        let updated = NFTContractMetadata { name: "Mosaics 2".to_string(), ..full_metadata() };
+        source.set_inline(&accounts(0), b"m".to_vec(), &updated);
+        assert!(source.is_inline());
+        assert_eq!(source.nft_metadata(), updated);
+
+        // Updating the metadata stored on-chain keeps its prefix.
+        source.set_inline(&accounts(0), b"other".to_vec(), &full_metadata());
+        assert_eq!(source.nft_metadata(), full_metadata());
+        assert_eq!(
+            near_sdk::test_utils::storage_snapshot().keys().collect::<Vec<_>>(),
+            [&b"m".to_vec()]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Name can't be empty")]
+    fn empty_name() {
+        NFTContractMetadata { name: String::new(), ..full_metadata() }.assert_valid();
+    }
+
+    #[test]
+    #[should_panic(expected = "Symbol can't be empty")]
+    fn empty_symbol() {
+        set_predecessor(accounts(0));
+        let minimal = MinimalMetadata { symbol: String::new(), ..minimal_metadata() };
+        MetadataSource::reference("ipfs://metadata.json".to_string(), hash(), minimal);
+    }
+
+    #[test]
+    #[should_panic(expected = "Reference hash requires a reference")]
+    fn contract_reference_hash_without_reference() {
+        NFTContractMetadata { reference_hash: Some(hash()), ..full_metadata() }.assert_valid();
+    }
+
+    fn token_metadata() -> TokenMetadata {
+        TokenMetadata {
+            title: Some("Arch Nemesis".to_string()),
+            description: None,
+            media: Some("ipfs://media.png".to_string()),
+            media_hash: Some(hash()),
+            copies: Some(1),
+            issued_at: None,
+            expires_at: Some("2022-12-31T00:00:00Z".to_string()),
+            starts_at: Some("2022-01-01T00:00:00Z".to_string()),
+            updated_at: None,
+            extra: None,
+            reference: Some("ipfs://token.json".to_string()),
+            reference_hash: Some(hash()),
+        }
+    }
+
+    #[test]
+    fn valid_token_metadata() {
+        token_metadata().assert_valid();
+        TokenMetadata {
+            media: None,
+            media_hash: None,
+            reference: None,
+            reference_hash: None,
+            copies: None,
+            ..token_metadata()
+        }
+        .assert_valid();
+        // Integer timestamps, and timestamps in different formats which can't be compared.
+        for (starts_at, expires_at) in
+            [("1000", "1000"), ("999", "1000"), ("2022-01-01", "2022-01-01T00:00:00Z")]
+        {
+            TokenMetadata {
+                starts_at: Some(starts_at.to_string()),
+                expires_at: Some(expires_at.to_string()),
+                ..token_metadata()
+            }
+            .assert_valid();
         }
+    }
+
+    #[test]
+    #[should_panic(expected = "Media hash is required with media")]
+    fn media_without_hash() {
+        TokenMetadata { media_hash: None, ..token_metadata() }.assert_valid();
+    }
+
+    #[test]
+    #[should_panic(expected = "Media hash requires media")]
+    fn media_hash_without_media() {
+        TokenMetadata { media: None, ..token_metadata() }.assert_valid();
+    }
 
-        require!(self.reference.is_some() == self.reference_hash.is_some());
-        if let Some(reference_hash) = &self.reference_hash {
-            require!(reference_hash.0.len() == 32, "Reference hash has to be 32 bytes");
+    #[test]
+    #[should_panic(expected = "Media hash has to be 32 bytes")]
+    fn invalid_media_hash() {
+        TokenMetadata { media_hash: Some(Base64VecU8::from(vec![7; 64])), ..token_metadata() }
+            .assert_valid();
+    }
+
+    #[test]
+    #[should_panic(expected = "Reference hash is required with a reference")]
+    fn reference_without_hash() {
+        TokenMetadata { reference_hash: None, ..token_metadata() }.assert_valid();
+    }
+
+    #[test]
+    #[should_panic(expected = "Reference hash requires a reference")]
+    fn reference_hash_without_reference() {
+        TokenMetadata { reference: None, ..token_metadata() }.assert_valid();
+    }
+
+    #[test]
+    #[should_panic(expected = "Reference hash has to be 32 bytes")]
+    fn invalid_reference_hash() {
+        TokenMetadata { reference_hash: Some(Base64VecU8::from(vec![7; 31])), ..token_metadata() }
+            .assert_valid();
+    }
+
+    #[test]
+    #[should_panic(expected = "Copies can't be zero")]
+    fn zero_copies() {
+        TokenMetadata { copies: Some(0), ..token_metadata() }.assert_valid();
+    }
+
+    #[test]
+    #[should_panic(expected = "Token expires before it starts")]
+    fn expires_before_start() {
+        TokenMetadata { expires_at: Some("2021-12-31T23:59:59Z".to_string()), ..token_metadata() }
+            .assert_valid();
+    }
+
+    #[test]
+    #[should_panic(expected = "Token expires before it starts")]
+    fn expires_before_start_integer_timestamps() {
+        TokenMetadata {
+            starts_at: Some("1000".to_string()),
+            expires_at: Some("999".to_string()),
+            ..token_metadata()
         }
+        .assert_valid();
+    }
+
+    #[test]
+    #[should_panic(expected = "Unauthorized")]
+    fn switch_modes_requires_owner() {
+        set_predecessor(accounts(0));
+        let mut source = MetadataSource::inline(b"m".to_vec(), &full_metadata());
+        set_predecessor(accounts(1));
+        source.set_reference(&accounts(0), "ipfs://metadata.json".to_string(), hash());
     }
 }
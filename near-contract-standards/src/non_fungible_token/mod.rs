@@ -12,6 +12,9 @@ mod macros;
 /// Metadata traits and implementation according to the [NFT enumeration standard](https://nomicon.io/Standards/NonFungibleToken/Metadata.html).
 /// This covers both the contract metadata and the individual token metadata.
 pub mod metadata;
+/// The [royalties and payouts standard](https://nomicon.io/Standards/NonFungibleToken/Payout) for
+/// NFTs, used by marketplaces to pay the royalties of a token when selling it.
+pub mod payout;
 /// The Token struct for the non-fungible token.
 mod token;
 pub use self::token::{Token, TokenId};
@@ -20,7 +23,7 @@ pub use self::token::{Token, TokenId};
 mod utils;
 pub use utils::*;
 
-pub use self::core::NonFungibleToken;
+pub use self::core::{NftTransferCallGas, NonFungibleToken};
 pub use macros::*;
 
 pub mod events;
@@ -0,0 +1,69 @@
+mod payout_impl;
+
+pub use payout_impl::*;
+
+use crate::non_fungible_token::token::TokenId;
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::AccountId;
+use std::collections::{BTreeMap, HashMap};
+
+/// Royalties of a token: the share of each sale paid to each account, in basis points
+/// (1/100 of a percent). The shares of a token sum up to at most [`ROYALTY_BASIS_POINTS`], the
+/// rest of each sale is paid to the owner of the token.
+pub type Royalties = BTreeMap<AccountId, u16>;
+
+/// The basis points of the whole balance of a sale, 100%.
+pub const ROYALTY_BASIS_POINTS: u16 = 10_000;
+
+/// The amounts of a sale to pay to each account, returned by
+/// [`NonFungibleTokenPayout::nft_payout`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Payout {
+    pub payout: HashMap<AccountId, U128>,
+}
+
+/// Trait for the [NFT royalties and payouts standard](https://nomicon.io/Standards/NonFungibleToken/Payout),
+/// which lets marketplaces split the balance of a sale between the owner of a token and the
+/// accounts receiving royalties.
+pub trait NonFungibleTokenPayout {
+    /// Returns how `balance` would be paid out for a sale of `token_id`.
+    ///
+    /// Requirements
+    /// * Contract MUST panic if the payout would have more than `max_len_payout` accounts
+    /// * The amounts of the payout MUST sum up to `balance`
+    ///
+    /// Arguments:
+    /// * `token_id`: the token being sold
+    /// * `balance`: the amount the token is sold for
+    /// * `max_len_payout`: the maximum number of accounts the marketplace can pay out to
+    fn nft_payout(&self, token_id: TokenId, balance: U128, max_len_payout: Option<u32>) -> Payout;
+
+    /// Transfers `token_id` to `receiver_id` like `nft_transfer` and returns how `balance` is paid
+    /// out to the previous owner and royalty holders.
+    ///
+    /// Requirements
+    /// * Caller of the method must attach a deposit of 1 yoctoⓃ for security purposes
+    /// * Contract MUST panic if the payout would have more than `max_len_payout` accounts, in
+    ///   which case the token is not transferred
+    /// * The amounts of the payout MUST sum up to `balance`
+    ///
+    /// Arguments:
+    /// * `receiver_id`: the valid NEAR account receiving the token
+    /// * `token_id`: the token to transfer
+    /// * `approval_id`: expected approval ID, see `nft_transfer`
+    /// * `memo` (optional): for use cases that may benefit from indexing or providing information
+    ///   for a transfer
+    /// * `balance`: the amount the token is sold for
+    /// * `max_len_payout`: the maximum number of accounts the marketplace can pay out to
+    fn nft_transfer_payout(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        approval_id: Option<u64>,
+        memo: Option<String>,
+        balance: U128,
+        max_len_payout: Option<u32>,
+    ) -> Payout;
+}
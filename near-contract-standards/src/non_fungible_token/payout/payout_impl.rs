@@ -0,0 +1,319 @@
+use super::{NonFungibleTokenPayout, Payout, Royalties, ROYALTY_BASIS_POINTS};
+use crate::limits::check_memo;
+use crate::non_fungible_token::core::NonFungibleTokenTransferHook;
+use crate::non_fungible_token::token::TokenId;
+use crate::non_fungible_token::NonFungibleToken;
+use near_sdk::collections::LookupMap;
+use near_sdk::json_types::U128;
+use near_sdk::{assert_one_yocto, env, require, AccountId, Balance, IntoStorageKey};
+use std::collections::HashMap;
+
+/// Returns `basis_points` of `balance`, rounded down. `balance` is divided first so that the
+/// multiplication can't overflow.
+fn royalty_amount(balance: Balance, basis_points: u16) -> Balance {
+    let basis_points = Balance::from(basis_points);
+    let whole = Balance::from(ROYALTY_BASIS_POINTS);
+    balance / whole * basis_points + balance % whole * basis_points / whole
+}
+
+/// Extension key of the royalties, see [`NonFungibleToken::royalties_by_id`].
+const ROYALTIES_EXTENSION: u8 = b'r';
+
+impl NonFungibleToken {
+    /// Enables the payout extension, storing the royalties of tokens under `prefix`.
+    ///
+    /// This doesn't change the storage measured for `nft_mint`, so contracts setting royalties
+    /// when minting should charge for the storage they take.
+    pub fn with_royalties<P: IntoStorageKey>(mut self, prefix: P) -> Self {
+        let royalties_by_id: LookupMap<TokenId, Royalties> = LookupMap::new(prefix);
+        self.write_extension(ROYALTIES_EXTENSION, &royalties_by_id);
+        self
+    }
+
+    /// Returns the royalties of the tokens if the payout extension is enabled. They're stored
+    /// outside of the token, so that enabling the extension doesn't change its layout.
+    pub fn royalties_by_id(&self) -> Option<LookupMap<TokenId, Royalties>> {
+        self.read_extension(ROYALTIES_EXTENSION)
+    }
+
+    /// Sets the royalties of `token_id`, replacing its previous royalties. Empty royalties remove
+    /// them, so that the owner gets the whole balance of a sale.
+    ///
+    /// Panics if the token doesn't exist, if the payout extension isn't enabled or if the
+    /// royalties sum up to more than [`ROYALTY_BASIS_POINTS`].
+    pub fn internal_set_royalties(
+        &mut self,
+        #[allow(clippy::ptr_arg)] token_id: &TokenId,
+        royalties: &Royalties,
+    ) {
+        require!(self.owner_by_id.contains_key(token_id), "Token not found");
+        let total = royalties
+            .values()
+            .try_fold(0u16, |total, basis_points| total.checked_add(*basis_points))
+            .filter(|total| *total <= ROYALTY_BASIS_POINTS);
+        require!(
+            total.is_some(),
+            format!("Royalties can't sum up to more than {} basis points", ROYALTY_BASIS_POINTS)
+        );
+        let mut royalties_by_id =
+            self.royalties_by_id().unwrap_or_else(|| env::panic_str("Royalties are not enabled"));
+        if royalties.is_empty() {
+            royalties_by_id.remove(token_id);
+        } else {
+            royalties_by_id.insert(token_id, royalties);
+        }
+    }
+
+    /// Returns how `balance` is paid out for a sale of `token_id` by `owner_id`. Each royalty is
+    /// rounded down and the owner gets the rest, so the amounts sum up to `balance`.
+    ///
+    /// Panics if the payout has more than `max_len_payout` accounts.
+    pub fn internal_payout(
+        &self,
+        #[allow(clippy::ptr_arg)] token_id: &TokenId,
+        owner_id: &AccountId,
+        balance: Balance,
+        max_len_payout: Option<u32>,
+    ) -> Payout {
+        let royalties =
+            self.royalties_by_id().and_then(|by_id| by_id.get(token_id)).unwrap_or_default();
+        let len = royalties.len() + usize::from(!royalties.contains_key(owner_id));
+        if let Some(max_len_payout) = max_len_payout {
+            require!(
+                len <= max_len_payout as usize,
+                format!(
+                    "The payout has {} accounts, more than the max_len_payout of {}",
+                    len, max_len_payout
+                )
+            );
+        }
+
+        let mut payout = HashMap::with_capacity(len);
+        let mut paid = 0;
+        for (account_id, basis_points) in royalties {
+            let amount = royalty_amount(balance, basis_points);
+            paid += amount;
+            payout.insert(account_id, U128(amount));
+        }
+        // The royalties sum up to at most the whole balance, so this can't underflow.
+        payout.entry(owner_id.clone()).or_insert(U128(0)).0 += balance - paid;
+        Payout { payout }
+    }
+
+    /// `nft_transfer_payout` running `hook` around the transfer.
+    #[allow(clippy::too_many_arguments)]
+    pub fn nft_transfer_payout_with_hook<H: NonFungibleTokenTransferHook + ?Sized>(
+        &mut self,
+        hook: &mut H,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        approval_id: Option<u64>,
+        memo: Option<String>,
+        balance: U128,
+        max_len_payout: Option<u32>,
+    ) -> Payout {
+        assert_one_yocto();
+        // The payout is computed before transferring, so that a payout the marketplace can't pay
+        // fails the call without writing anything.
+        let payout = self.nft_payout(token_id.clone(), balance, max_len_payout);
+        let sender_id = env::predecessor_account_id();
+        self.internal_transfer_with_hook(
+            hook,
+            &sender_id,
+            &receiver_id,
+            &token_id,
+            approval_id,
+            check_memo(memo),
+        );
+        payout
+    }
+}
+
+impl NonFungibleTokenPayout for NonFungibleToken {
+    fn nft_payout(&self, token_id: TokenId, balance: U128, max_len_payout: Option<u32>) -> Payout {
+        let owner_id =
+            self.owner_by_id.get(&token_id).unwrap_or_else(|| env::panic_str("Token not found"));
+        self.internal_payout(&token_id, &owner_id, balance.into(), max_len_payout)
+    }
+
+    fn nft_transfer_payout(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        approval_id: Option<u64>,
+        memo: Option<String>,
+        balance: U128,
+        max_len_payout: Option<u32>,
+    ) -> Payout {
+        self.nft_transfer_payout_with_hook(
+            &mut (),
+            receiver_id,
+            token_id,
+            approval_id,
+            memo,
+            balance,
+            max_len_payout,
+        )
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::non_fungible_token::approval::NonFungibleTokenApproval;
+    use near_sdk::test_utils::{accounts, get_logs, VMContextBuilder};
+    use near_sdk::{testing_env, ONE_NEAR, ONE_YOCTO};
+
+    const TOKEN_ID: &str = "t";
+
+    fn set_context(predecessor: AccountId, attached_deposit: Balance) {
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(accounts(0))
+            .predecessor_account_id(predecessor)
+            .attached_deposit(attached_deposit)
+            .build());
+    }
+
+    /// Mints a token to `accounts(1)` with the given royalties.
+    fn token_with_royalties(royalties: &[(AccountId, u16)]) -> NonFungibleToken {
+        set_context(accounts(0), ONE_NEAR);
+        let mut token = NonFungibleToken::new(
+            b"o".to_vec(),
+            accounts(0),
+            None::<Vec<u8>>,
+            None::<Vec<u8>>,
+            Some(b"a".to_vec()),
+        )
+        .with_royalties(b"r".to_vec());
+        token.internal_mint(TOKEN_ID.to_string(), accounts(1), None);
+        token.internal_set_royalties(&TOKEN_ID.to_string(), &royalties.iter().cloned().collect());
+        token
+    }
+
+    fn payout(entries: &[(AccountId, u128)]) -> Payout {
+        Payout {
+            payout: entries
+                .iter()
+                .map(|(account_id, amount)| (account_id.clone(), U128(*amount)))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn without_royalties() {
+        let token = token_with_royalties(&[]);
+        assert_eq!(
+            token.nft_payout(TOKEN_ID.to_string(), U128(1000), Some(1)),
+            payout(&[(accounts(1), 1000)])
+        );
+    }
+
+    #[test]
+    fn remainder_goes_to_owner() {
+        let token = token_with_royalties(&[(accounts(2), 1000), (accounts(3), 333)]);
+        let result = token.nft_payout(TOKEN_ID.to_string(), U128(1001), None);
+        // 10% and 3.33% of 1001, rounded down.
+        assert_eq!(result, payout(&[(accounts(1), 868), (accounts(2), 100), (accounts(3), 33)]));
+        assert_eq!(result.payout.values().map(|amount| amount.0).sum::<u128>(), 1001);
+    }
+
+    #[test]
+    fn owner_with_royalty() {
+        let token = token_with_royalties(&[(accounts(1), 500), (accounts(2), 500)]);
+        assert_eq!(
+            token.nft_payout(TOKEN_ID.to_string(), U128(999), Some(2)),
+            payout(&[(accounts(1), 950), (accounts(2), 49)])
+        );
+    }
+
+    #[test]
+    fn large_balance() {
+        let token = token_with_royalties(&[(accounts(2), ROYALTY_BASIS_POINTS)]);
+        assert_eq!(
+            token.nft_payout(TOKEN_ID.to_string(), U128(u128::MAX), None),
+            payout(&[(accounts(1), 0), (accounts(2), u128::MAX)])
+        );
+        assert_eq!(royalty_amount(u128::MAX, 1), u128::MAX / 10_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "The payout has 3 accounts, more than the max_len_payout of 2")]
+    fn too_many_recipients() {
+        let token = token_with_royalties(&[(accounts(2), 1000), (accounts(3), 1000)]);
+        token.nft_payout(TOKEN_ID.to_string(), U128(1000), Some(2));
+    }
+
+    #[test]
+    #[should_panic(expected = "Royalties can't sum up to more than 10000 basis points")]
+    fn royalties_over_whole_balance() {
+        token_with_royalties(&[(accounts(2), 5000), (accounts(3), 5001)]);
+    }
+
+    #[test]
+    fn transfer_payout() {
+        let mut token = token_with_royalties(&[(accounts(2), 250)]);
+        set_context(accounts(1), ONE_NEAR);
+        token.nft_approve(TOKEN_ID.to_string(), accounts(3), None);
+
+        set_context(accounts(3), ONE_YOCTO);
+        let result = token.nft_transfer_payout(
+            accounts(4),
+            TOKEN_ID.to_string(),
+            Some(1),
+            None,
+            U128(ONE_NEAR),
+            Some(2),
+        );
+        assert_eq!(
+            result,
+            payout(&[(accounts(1), ONE_NEAR / 40 * 39), (accounts(2), ONE_NEAR / 40)])
+        );
+        assert_eq!(token.owner_by_id.get(&TOKEN_ID.to_string()), Some(accounts(4)));
+        assert_eq!(
+            get_logs(),
+            [
+                r#"EVENT_JSON:{"standard":"nep171","version":"1.0.0","event":"nft_transfer","data":[{"old_owner_id":"bob","new_owner_id":"eugene","token_ids":["t"],"authorized_id":"danny"}]}"#
+            ]
+        );
+
+        // The royalties stay with the token and the new owner gets the rest of the next sale.
+        set_context(accounts(4), ONE_YOCTO);
+        let result = token.nft_transfer_payout(
+            accounts(1),
+            TOKEN_ID.to_string(),
+            None,
+            None,
+            U128(400),
+            None,
+        );
+        assert_eq!(result, payout(&[(accounts(4), 390), (accounts(2), 10)]));
+    }
+
+    #[test]
+    fn transfer_payout_is_atomic() {
+        let mut token = token_with_royalties(&[(accounts(2), 1000), (accounts(3), 1000)]);
+        set_context(accounts(1), ONE_YOCTO);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            token.nft_transfer_payout(
+                accounts(4),
+                TOKEN_ID.to_string(),
+                None,
+                None,
+                U128(1000),
+                Some(1),
+            )
+        }));
+        assert!(result.is_err());
+        assert_eq!(token.owner_by_id.get(&TOKEN_ID.to_string()), Some(accounts(1)));
+        assert!(get_logs().is_empty());
+    }
+
+    #[test]
+    fn burn_removes_royalties() {
+        let mut token = token_with_royalties(&[(accounts(2), 1000)]);
+        set_context(accounts(1), ONE_YOCTO);
+        token.internal_burn(&TOKEN_ID.to_string(), &accounts(1));
+        assert!(token.royalties_by_id().unwrap().get(&TOKEN_ID.to_string()).is_none());
+    }
+}
@@ -1,7 +1,7 @@
 use crate::non_fungible_token::metadata::TokenMetadata;
 use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::AccountId;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 /// Note that token IDs for NFTs are strings on NEAR. It's still fine to use autoincrementing numbers as unique IDs if desired, but they should be stringified. This is to make IDs more future-proof as chain-agnostic conventions and standards arise, and allows for more flexibility with considerations like bridging NFTs across chains, etc.
 pub type TokenId = String;
@@ -13,5 +13,58 @@ pub struct Token {
     pub token_id: TokenId,
     pub owner_id: AccountId,
     pub metadata: Option<TokenMetadata>,
-    pub approved_account_ids: Option<HashMap<AccountId, u64>>,
+    /// Approved accounts mapped to their approval IDs. This is serialized as a JSON object, which
+    /// is the same format as the previously used `HashMap`, but with a deterministic key order.
+    pub approved_account_ids: Option<BTreeMap<AccountId, u64>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::borsh::BorshSerialize;
+    use std::collections::HashMap;
+
+    fn account(id: &str) -> AccountId {
+        AccountId::new_unchecked(id.to_string())
+    }
+
+    #[test]
+    fn approvals_accept_hash_map_json() {
+        let mut old: HashMap<AccountId, u64> = HashMap::new();
+        old.insert(account("charlie.near"), 3);
+        old.insert(account("alice.near"), 1);
+        old.insert(account("bob.near"), 2);
+        let old_json = near_sdk::serde_json::to_string(&Some(&old)).unwrap();
+
+        let new: Option<BTreeMap<AccountId, u64>> =
+            near_sdk::serde_json::from_str(&old_json).unwrap();
+        let new = new.unwrap();
+        assert_eq!(new.len(), old.len());
+        for (account_id, approval_id) in old.iter() {
+            assert_eq!(new.get(account_id), Some(approval_id));
+        }
+    }
+
+    #[test]
+    fn approvals_serialization_is_deterministic() {
+        let ids = ["d.near", "a.near", "c.near", "b.near"];
+        let forward: BTreeMap<AccountId, u64> =
+            ids.iter().enumerate().map(|(i, id)| (account(id), i as u64)).collect();
+        let reverse: BTreeMap<AccountId, u64> =
+            ids.iter().enumerate().rev().map(|(i, id)| (account(id), i as u64)).collect();
+
+        let json = near_sdk::serde_json::to_string(&forward).unwrap();
+        assert_eq!(json, near_sdk::serde_json::to_string(&reverse).unwrap());
+        assert_eq!(json, r#"{"a.near":1,"b.near":3,"c.near":2,"d.near":0}"#);
+    }
+
+    #[test]
+    fn approvals_borsh_matches_hash_map() {
+        // Approvals stored by previous versions must be readable, so the Borsh encodings must match.
+        let mut old: HashMap<AccountId, u64> = HashMap::new();
+        old.insert(account("bob.near"), 2);
+        old.insert(account("alice.near"), 1);
+        let new: BTreeMap<AccountId, u64> = old.clone().into_iter().collect();
+        assert_eq!(old.try_to_vec().unwrap(), new.try_to_vec().unwrap());
+    }
 }
@@ -1,5 +1,6 @@
+pub use crate::utils::{refund_deposit, refund_deposit_to_account};
 use near_sdk::{env, require, AccountId, Balance, CryptoHash, Promise};
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::mem::size_of;
 
 // TODO: need a way for end users to determine how much an approval will cost.
@@ -21,31 +22,11 @@ where
 
 pub fn refund_approved_account_ids(
     account_id: AccountId,
-    approved_account_ids: &HashMap<AccountId, u64>,
+    approved_account_ids: &BTreeMap<AccountId, u64>,
 ) -> Promise {
     refund_approved_account_ids_iter(account_id, approved_account_ids.keys())
 }
 
-pub fn refund_deposit_to_account(storage_used: u64, account_id: AccountId) {
-    let required_cost = env::storage_byte_cost() * Balance::from(storage_used);
-    let attached_deposit = env::attached_deposit();
-
-    require!(
-        required_cost <= attached_deposit,
-        format!("Must attach {} yoctoNEAR to cover storage", required_cost)
-    );
-
-    let refund = attached_deposit - required_cost;
-    if refund > 1 {
-        Promise::new(account_id).transfer(refund);
-    }
-}
-
-/// Assumes that the precedecessor will be refunded
-pub fn refund_deposit(storage_used: u64) {
-    refund_deposit_to_account(storage_used, env::predecessor_account_id())
-}
-
 pub fn hash_account_id(account_id: &AccountId) -> CryptoHash {
     let mut hash = CryptoHash::default();
     hash.copy_from_slice(&env::sha256(account_id.as_bytes()));
@@ -0,0 +1,285 @@
+//! Ownership of a contract, for methods only its owner can call.
+//!
+//! The owner is kept in an [`Ownable`](crate::ownable::Ownable) under its own prefix, and exposed
+//! by the contract with [`impl_ownable!`](crate::impl_ownable), which also gives the contract the
+//! `assert_owner` method checked by the `#[only_owner]` attribute of `near_bindgen` methods.
+//!
+//! Ownership is transferred in two steps, so that it can't be lost to a mistyped account: the
+//! owner proposes a new owner with
+//! [`transfer_ownership`](crate::ownable::Ownable::transfer_ownership), which becomes the owner
+//! once it calls [`accept_ownership`](crate::ownable::Ownable::accept_ownership). Until then,
+//! the current owner keeps the ownership and can propose another account. Changes of ownership
+//! emit events in the `ownable` standard.
+
+use crate::event::{emit_event, NearEventData};
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::LazyOption;
+use near_sdk::{env, AccountId, IntoStorageKey};
+use serde::Serialize;
+
+/// Data to log when the owner proposes a new owner. To log this event, call
+/// [`.emit()`](OwnershipTransferStarted::emit).
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+pub struct OwnershipTransferStarted<'a> {
+    pub owner_id: &'a AccountId,
+    pub pending_owner_id: &'a AccountId,
+}
+
+impl OwnershipTransferStarted<'_> {
+    /// Logs the event to the host.
+    pub fn emit(self) {
+        emit_event(&self)
+    }
+}
+
+impl NearEventData for OwnershipTransferStarted<'_> {
+    const STANDARD: &'static str = "ownable";
+    const VERSION: &'static str = "1.0.0";
+    const EVENT: &'static str = "ownership_transfer_started";
+}
+
+/// Data to log when the ownership changes, either accepted by a new owner or renounced, without a
+/// new owner. To log this event, call [`.emit()`](OwnershipTransferred::emit).
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+pub struct OwnershipTransferred<'a> {
+    pub old_owner_id: &'a AccountId,
+    pub new_owner_id: Option<&'a AccountId>,
+}
+
+impl OwnershipTransferred<'_> {
+    /// Logs the event to the host.
+    pub fn emit(self) {
+        emit_event(&self)
+    }
+}
+
+impl NearEventData for OwnershipTransferred<'_> {
+    const STANDARD: &'static str = "ownable";
+    const VERSION: &'static str = "1.0.0";
+    const EVENT: &'static str = "ownership_transferred";
+}
+
+/// The owner of a contract, and the account it proposed as the next owner.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Ownable {
+    owner_id: LazyOption<AccountId>,
+    pending_owner_id: LazyOption<AccountId>,
+}
+
+impl Ownable {
+    pub fn new<S>(prefix: S, owner_id: AccountId) -> Self
+    where
+        S: IntoStorageKey,
+    {
+        let prefix = prefix.into_storage_key();
+        Self {
+            owner_id: LazyOption::new([prefix.as_slice(), b"o"].concat(), Some(&owner_id)),
+            pending_owner_id: LazyOption::new([prefix.as_slice(), b"p"].concat(), None),
+        }
+    }
+
+    /// Returns the owner, or `None` once the ownership is renounced.
+    pub fn owner(&self) -> Option<AccountId> {
+        self.owner_id.get()
+    }
+
+    /// Returns the account proposed as the next owner, which didn't accept the ownership yet.
+    pub fn pending_owner(&self) -> Option<AccountId> {
+        self.pending_owner_id.get()
+    }
+
+    /// Panics if the predecessor account isn't the owner.
+    pub fn assert_owner(&self) {
+        if self.owner_id.get() != Some(env::predecessor_account_id()) {
+            env::panic_str("Only the owner can call this method");
+        }
+    }
+
+    /// Proposes `new_owner_id` as the next owner, replacing the previous proposal. The owner
+    /// doesn't change until `new_owner_id` calls [`accept_ownership`](Self::accept_ownership).
+    /// Only the owner can call it.
+    pub fn transfer_ownership(&mut self, new_owner_id: AccountId) {
+        self.assert_owner();
+        self.pending_owner_id.set(&new_owner_id);
+        OwnershipTransferStarted {
+            owner_id: &env::predecessor_account_id(),
+            pending_owner_id: &new_owner_id,
+        }
+        .emit();
+    }
+
+    /// Makes the predecessor account, which the owner proposed, the owner.
+    pub fn accept_ownership(&mut self) {
+        let account_id = env::predecessor_account_id();
+        if self.pending_owner_id.get().as_ref() != Some(&account_id) {
+            env::panic_str("Only the pending owner can accept the ownership");
+        }
+        self.pending_owner_id.remove();
+        // The ownership can only be proposed by an owner.
+        let old_owner_id = self.owner_id.replace(&account_id).unwrap();
+        OwnershipTransferred { old_owner_id: &old_owner_id, new_owner_id: Some(&account_id) }
+            .emit();
+    }
+
+    /// Leaves the contract without an owner, and cancels a proposed transfer. Methods only the
+    /// owner can call can't be called anymore. Only the owner can call it.
+    pub fn renounce_ownership(&mut self) {
+        self.assert_owner();
+        self.pending_owner_id.remove();
+        // The predecessor is the owner.
+        let old_owner_id = self.owner_id.take().unwrap();
+        OwnershipTransferred { old_owner_id: &old_owner_id, new_owner_id: None }.emit();
+    }
+}
+
+/// Exposes the ownership of the contract: `owner`, `pending_owner`, `transfer_ownership`,
+/// `accept_ownership` and `renounce_ownership`. The contract gets an `assert_owner` method, for its
+/// own checks and for methods marked `#[only_owner]`.
+///
+/// Takes the name of the contract struct and the inner field for the [`Ownable`].
+#[macro_export]
+macro_rules! impl_ownable {
+    ($contract: ident, $ownable: ident) => {
+        $crate::__token_field!(
+            $contract.$ownable: $crate::ownable::Ownable,
+            __ownable,
+            __ownable_mut
+        );
+
+        impl $contract {
+            /// Panics if the predecessor account isn't the owner of the contract.
+            pub fn assert_owner(&self) {
+                self.__ownable().assert_owner()
+            }
+        }
+
+        #[near_bindgen]
+        impl $contract {
+            pub fn owner(&self) -> Option<AccountId> {
+                self.__ownable().owner()
+            }
+
+            pub fn pending_owner(&self) -> Option<AccountId> {
+                self.__ownable().pending_owner()
+            }
+
+            pub fn transfer_ownership(&mut self, new_owner_id: AccountId) {
+                self.__ownable_mut().transfer_ownership(new_owner_id)
+            }
+
+            pub fn accept_ownership(&mut self) {
+                self.__ownable_mut().accept_ownership()
+            }
+
+            pub fn renounce_ownership(&mut self) {
+                self.__ownable_mut().renounce_ownership()
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::{accounts, get_logs, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    fn set_predecessor(predecessor: AccountId) {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(predecessor).build());
+    }
+
+    fn ownable() -> Ownable {
+        set_predecessor(accounts(0));
+        Ownable::new(b"o".to_vec(), accounts(0))
+    }
+
+    #[test]
+    fn two_step_transfer() {
+        let mut ownable = ownable();
+        ownable.transfer_ownership(accounts(1));
+        assert_eq!(
+            get_logs(),
+            [
+                r#"EVENT_JSON:{"standard":"ownable","version":"1.0.0","event":"ownership_transfer_started","data":[{"owner_id":"alice","pending_owner_id":"bob"}]}"#
+            ]
+        );
+        assert_eq!(ownable.owner(), Some(accounts(0)));
+        assert_eq!(ownable.pending_owner(), Some(accounts(1)));
+
+        set_predecessor(accounts(1));
+        ownable.accept_ownership();
+        assert_eq!(
+            get_logs(),
+            [
+                r#"EVENT_JSON:{"standard":"ownable","version":"1.0.0","event":"ownership_transferred","data":[{"old_owner_id":"alice","new_owner_id":"bob"}]}"#
+            ]
+        );
+        assert_eq!(ownable.owner(), Some(accounts(1)));
+        assert_eq!(ownable.pending_owner(), None);
+        ownable.assert_owner();
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn owner_until_accepted() {
+        let mut ownable = ownable();
+        ownable.transfer_ownership(accounts(1));
+        set_predecessor(accounts(1));
+        ownable.assert_owner();
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the pending owner can accept the ownership")]
+    fn accept_by_other_account() {
+        let mut ownable = ownable();
+        ownable.transfer_ownership(accounts(1));
+        set_predecessor(accounts(2));
+        ownable.accept_ownership();
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the pending owner can accept the ownership")]
+    fn accept_without_transfer() {
+        let mut ownable = ownable();
+        ownable.accept_ownership();
+    }
+
+    #[test]
+    fn transfer_replaces_proposal() {
+        let mut ownable = ownable();
+        ownable.transfer_ownership(accounts(1));
+        ownable.transfer_ownership(accounts(2));
+        assert_eq!(ownable.pending_owner(), Some(accounts(2)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn transfer_by_other_account() {
+        let mut ownable = ownable();
+        set_predecessor(accounts(1));
+        ownable.transfer_ownership(accounts(1));
+    }
+
+    #[test]
+    fn renounce() {
+        let mut ownable = ownable();
+        ownable.transfer_ownership(accounts(1));
+        ownable.renounce_ownership();
+        assert_eq!(
+            get_logs().last().unwrap(),
+            r#"EVENT_JSON:{"standard":"ownable","version":"1.0.0","event":"ownership_transferred","data":[{"old_owner_id":"alice","new_owner_id":null}]}"#
+        );
+        assert_eq!(ownable.owner(), None);
+        assert_eq!(ownable.pending_owner(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn renounced_has_no_owner() {
+        let mut ownable = ownable();
+        ownable.renounce_ownership();
+        ownable.assert_owner();
+    }
+}
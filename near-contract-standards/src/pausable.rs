@@ -0,0 +1,199 @@
+//! Pausing features of a contract during incidents, e.g. its transfers but not its views.
+//!
+//! The paused features are kept in a [`Pausable`](crate::pausable::Pausable) under its own prefix,
+//! each identified by a key chosen by the contract. [`impl_pausable!`](crate::impl_pausable)
+//! exposes them, letting the owner of the contract pause and unpause features, and gives the
+//! contract the `assert_not_paused` method checked by the `#[pause(feature = "...")]` attribute of
+//! `near_bindgen` methods. Pausing and unpausing emit events in the `pausable` standard.
+
+use crate::event::{emit_event, NearEventData};
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::LookupSet;
+use near_sdk::{env, AccountId, IntoStorageKey};
+use serde::Serialize;
+
+/// Data to log when a feature is paused. To log this event, call [`.emit()`](Pause::emit).
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+pub struct Pause<'a> {
+    pub key: &'a str,
+    pub account_id: &'a AccountId,
+}
+
+impl Pause<'_> {
+    /// Logs the event to the host.
+    pub fn emit(self) {
+        emit_event(&self)
+    }
+}
+
+impl NearEventData for Pause<'_> {
+    const STANDARD: &'static str = "pausable";
+    const VERSION: &'static str = "1.0.0";
+    const EVENT: &'static str = "pause";
+}
+
+/// Data to log when a feature is unpaused. To log this event, call [`.emit()`](Unpause::emit).
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+pub struct Unpause<'a> {
+    pub key: &'a str,
+    pub account_id: &'a AccountId,
+}
+
+impl Unpause<'_> {
+    /// Logs the event to the host.
+    pub fn emit(self) {
+        emit_event(&self)
+    }
+}
+
+impl NearEventData for Unpause<'_> {
+    const STANDARD: &'static str = "pausable";
+    const VERSION: &'static str = "1.0.0";
+    const EVENT: &'static str = "unpause";
+}
+
+/// The paused features of a contract.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Pausable {
+    paused: LookupSet<String>,
+}
+
+impl Pausable {
+    pub fn new<S>(prefix: S) -> Self
+    where
+        S: IntoStorageKey,
+    {
+        Self { paused: LookupSet::new(prefix) }
+    }
+
+    /// Returns `true` if the feature `key` is paused.
+    pub fn is_paused(&self, key: &str) -> bool {
+        self.paused.contains(&key.to_string())
+    }
+
+    /// Panics if the feature `key` is paused.
+    pub fn assert_not_paused(&self, key: &str) {
+        if self.is_paused(key) {
+            env::panic_str(&format!("The feature {} is paused", key));
+        }
+    }
+
+    /// Pauses the feature `key`. Panics if it's already paused. The caller is responsible for
+    /// checking that the predecessor can pause it.
+    pub fn pause(&mut self, key: String) {
+        if !self.paused.insert(&key) {
+            env::panic_str(&format!("The feature {} is already paused", key));
+        }
+        Pause { key: &key, account_id: &env::predecessor_account_id() }.emit();
+    }
+
+    /// Unpauses the feature `key`. Panics if it isn't paused. The caller is responsible for
+    /// checking that the predecessor can unpause it.
+    pub fn unpause(&mut self, key: String) {
+        if !self.paused.remove(&key) {
+            env::panic_str(&format!("The feature {} isn't paused", key));
+        }
+        Unpause { key: &key, account_id: &env::predecessor_account_id() }.emit();
+    }
+}
+
+/// Exposes the paused features of the contract: `is_paused`, and `pause` and `unpause`, which
+/// only the owner can call. The contract gets an `assert_not_paused` method, for its own checks
+/// and for methods marked `#[pause(feature = "...")]`.
+///
+/// The owner is checked by the `assert_owner` method of the contract, so this is used along with
+/// [`impl_ownable!`](crate::impl_ownable), which also provides the `owner` method read by
+/// `#[pause(feature = "...", except(owner))]`.
+///
+/// Takes the name of the contract struct and the inner field for the [`Pausable`].
+#[macro_export]
+macro_rules! impl_pausable {
+    ($contract: ident, $pausable: ident) => {
+        $crate::__token_field!(
+            $contract.$pausable: $crate::pausable::Pausable,
+            __pausable,
+            __pausable_mut
+        );
+
+        impl $contract {
+            /// Panics if the feature `key` of the contract is paused.
+            pub fn assert_not_paused(&self, key: &str) {
+                self.__pausable().assert_not_paused(key)
+            }
+        }
+
+        #[near_bindgen]
+        impl $contract {
+            pub fn is_paused(&self, key: String) -> bool {
+                self.__pausable().is_paused(&key)
+            }
+
+            pub fn pause(&mut self, key: String) {
+                self.assert_owner();
+                self.__pausable_mut().pause(key)
+            }
+
+            pub fn unpause(&mut self, key: String) {
+                self.assert_owner();
+                self.__pausable_mut().unpause(key)
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::{accounts, get_logs, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    fn pausable() -> Pausable {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        Pausable::new(b"p".to_vec())
+    }
+
+    #[test]
+    fn pause_and_unpause() {
+        let mut pausable = pausable();
+        pausable.pause("ft_transfer".to_string());
+        assert!(pausable.is_paused("ft_transfer"));
+        assert!(!pausable.is_paused("ft_burn"));
+        pausable.assert_not_paused("ft_burn");
+
+        pausable.unpause("ft_transfer".to_string());
+        assert!(!pausable.is_paused("ft_transfer"));
+        pausable.assert_not_paused("ft_transfer");
+        assert_eq!(
+            get_logs(),
+            [
+                r#"EVENT_JSON:{"standard":"pausable","version":"1.0.0","event":"pause","data":[{"key":"ft_transfer","account_id":"alice"}]}"#,
+                r#"EVENT_JSON:{"standard":"pausable","version":"1.0.0","event":"unpause","data":[{"key":"ft_transfer","account_id":"alice"}]}"#,
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "The feature ft_transfer is paused")]
+    fn paused_feature() {
+        let mut pausable = pausable();
+        pausable.pause("ft_transfer".to_string());
+        pausable.assert_not_paused("ft_transfer");
+    }
+
+    #[test]
+    #[should_panic(expected = "The feature ft_transfer is already paused")]
+    fn pause_twice() {
+        let mut pausable = pausable();
+        pausable.pause("ft_transfer".to_string());
+        pausable.pause("ft_transfer".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "The feature ft_transfer isn't paused")]
+    fn unpause_without_pause() {
+        let mut pausable = pausable();
+        pausable.unpause("ft_transfer".to_string());
+    }
+}
@@ -0,0 +1,389 @@
+//! Role-based access control, for contracts with separate roles such as minters, pausers and
+//! upgraders.
+//!
+//! The roles are variants of an enum defined by the contract, and the members of each role are
+//! kept in an [`Rbac`](crate::rbac::Rbac) under its own prefix. Each role can have an admin role,
+//! whose members grant and revoke it. A role without an admin role can only be granted by the
+//! contract itself with [`internal_grant_role`](crate::rbac::Rbac::internal_grant_role), e.g. when
+//! it's initialized.
+//!
+//! [`impl_rbac!`](crate::impl_rbac) exposes the roles, and gives the contract the `assert_any_role`
+//! method checked by the `#[access_control(roles(...))]` attribute of `near_bindgen` methods.
+//! Granting and revoking roles emit events in the `rbac` standard.
+
+use crate::event::{emit_event, NearEventData};
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::{LookupMap, UnorderedSet};
+use near_sdk::json_types::U128;
+use near_sdk::{env, require, AccountId, IntoStorageKey};
+use serde::Serialize;
+
+/// Data to log when a role is granted. To log this event, call [`.emit()`](RoleGranted::emit).
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+pub struct RoleGranted<'a, R> {
+    pub role: &'a R,
+    pub account_id: &'a AccountId,
+    pub sender_id: &'a AccountId,
+}
+
+impl<R: Serialize> RoleGranted<'_, R> {
+    /// Logs the event to the host.
+    pub fn emit(self) {
+        emit_event(&self)
+    }
+}
+
+impl<R: Serialize> NearEventData for RoleGranted<'_, R> {
+    const STANDARD: &'static str = "rbac";
+    const VERSION: &'static str = "1.0.0";
+    const EVENT: &'static str = "role_granted";
+}
+
+/// Data to log when a role is revoked. To log this event, call [`.emit()`](RoleRevoked::emit).
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+pub struct RoleRevoked<'a, R> {
+    pub role: &'a R,
+    pub account_id: &'a AccountId,
+    pub sender_id: &'a AccountId,
+}
+
+impl<R: Serialize> RoleRevoked<'_, R> {
+    /// Logs the event to the host.
+    pub fn emit(self) {
+        emit_event(&self)
+    }
+}
+
+impl<R: Serialize> NearEventData for RoleRevoked<'_, R> {
+    const STANDARD: &'static str = "rbac";
+    const VERSION: &'static str = "1.0.0";
+    const EVENT: &'static str = "role_revoked";
+}
+
+/// The members of the roles `R` of a contract, and the admin role of each role.
+///
+/// # Examples
+/// ```
+/// use near_contract_standards::rbac::Rbac;
+/// use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+/// use near_sdk::serde::Serialize;
+///
+/// #[derive(BorshDeserialize, BorshSerialize, Serialize, Clone, Copy, PartialEq)]
+/// #[serde(crate = "near_sdk::serde")]
+/// enum Role {
+///     Admin,
+///     Minter,
+/// }
+///
+/// let mut rbac = Rbac::new(b"r");
+/// rbac.internal_set_admin_role(&Role::Minter, &Role::Admin);
+/// let alice = "alice.near".parse().unwrap();
+/// rbac.internal_grant_role(&Role::Admin, &alice);
+/// assert!(rbac.has_role(&Role::Admin, &alice));
+/// assert!(!rbac.has_role(&Role::Minter, &alice));
+/// ```
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Rbac<R> {
+    prefix: Vec<u8>,
+    /// Role -> Members of the role.
+    members: LookupMap<R, UnorderedSet<AccountId>>,
+    /// Role -> Role administering it.
+    admin_roles: LookupMap<R, R>,
+}
+
+impl<R> Rbac<R>
+where
+    R: BorshSerialize + BorshDeserialize + Serialize,
+{
+    pub fn new<S>(prefix: S) -> Self
+    where
+        S: IntoStorageKey,
+    {
+        let prefix = prefix.into_storage_key();
+        Self {
+            members: LookupMap::new([prefix.as_slice(), b"m"].concat()),
+            admin_roles: LookupMap::new([prefix.as_slice(), b"a"].concat()),
+            prefix,
+        }
+    }
+
+    /// Returns `true` if `account_id` is a member of `role`.
+    pub fn has_role(&self, role: &R, account_id: &AccountId) -> bool {
+        self.members.get(role).map_or(false, |members| members.contains(account_id))
+    }
+
+    /// Panics if the predecessor account isn't a member of `role`.
+    pub fn assert_role(&self, role: &R) {
+        self.assert_any_role(std::slice::from_ref(role))
+    }
+
+    /// Panics if the predecessor account isn't a member of any of `roles`.
+    pub fn assert_any_role(&self, roles: &[R]) {
+        let account_id = env::predecessor_account_id();
+        if !roles.iter().any(|role| self.has_role(role, &account_id)) {
+            env::panic_str(&format!("The account {} doesn't have a required role", account_id));
+        }
+    }
+
+    /// Returns the role whose members grant and revoke `role`, if any.
+    pub fn admin_role_of(&self, role: &R) -> Option<R> {
+        self.admin_roles.get(role)
+    }
+
+    /// Makes the members of `admin_role` the admins of `role`, without checking the predecessor.
+    pub fn internal_set_admin_role(&mut self, role: &R, admin_role: &R) {
+        self.admin_roles.insert(role, admin_role);
+    }
+
+    fn assert_admin_of(&self, role: &R) {
+        let admin_role = self
+            .admin_role_of(role)
+            .unwrap_or_else(|| env::panic_str("The role doesn't have an admin role"));
+        let account_id = env::predecessor_account_id();
+        if !self.has_role(&admin_role, &account_id) {
+            env::panic_str(&format!("The account {} isn't an admin of the role", account_id));
+        }
+    }
+
+    /// Adds `account_id` to the members of `role`, without checking the predecessor. Returns
+    /// `false` if it's already a member.
+    pub fn internal_grant_role(&mut self, role: &R, account_id: &AccountId) -> bool {
+        let mut members = self.members.get(role).unwrap_or_else(|| {
+            UnorderedSet::new([self.prefix.as_slice(), b"r", &role.try_to_vec().unwrap()].concat())
+        });
+        if !members.insert(account_id) {
+            return false;
+        }
+        self.members.insert(role, &members);
+        RoleGranted { role, account_id, sender_id: &env::predecessor_account_id() }.emit();
+        true
+    }
+
+    /// Removes `account_id` from the members of `role`, without checking the predecessor. Returns
+    /// `false` if it isn't a member.
+    pub fn internal_revoke_role(&mut self, role: &R, account_id: &AccountId) -> bool {
+        let mut members = match self.members.get(role) {
+            Some(members) => members,
+            None => return false,
+        };
+        if !members.remove(account_id) {
+            return false;
+        }
+        if members.is_empty() {
+            self.members.remove(role);
+        } else {
+            self.members.insert(role, &members);
+        }
+        RoleRevoked { role, account_id, sender_id: &env::predecessor_account_id() }.emit();
+        true
+    }
+
+    /// Grants `role` to `account_id`. Only the members of the admin role of `role` can call it.
+    /// Returns `false` if the account already has the role.
+    pub fn grant_role(&mut self, role: &R, account_id: &AccountId) -> bool {
+        self.assert_admin_of(role);
+        self.internal_grant_role(role, account_id)
+    }
+
+    /// Revokes `role` from `account_id`. Only the members of the admin role of `role` can call it.
+    /// Returns `false` if the account doesn't have the role.
+    pub fn revoke_role(&mut self, role: &R, account_id: &AccountId) -> bool {
+        self.assert_admin_of(role);
+        self.internal_revoke_role(role, account_id)
+    }
+
+    /// Returns a page of the members of `role`, starting at `from_index`, 0 by default, with at
+    /// most `limit` accounts.
+    pub fn members_of(
+        &self,
+        role: &R,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<AccountId> {
+        let members = match self.members.get(role) {
+            Some(members) => members,
+            None => return vec![],
+        };
+        let limit = limit.unwrap_or(u64::MAX);
+        require!(limit != 0, "Cannot provide limit of 0.");
+        let start_index: u128 = from_index.map(From::from).unwrap_or_default();
+        require!(
+            members.len() as u128 > start_index,
+            "Out of bounds, please use a smaller from_index."
+        );
+        members.as_vector().to_paginated_vec(start_index as u64, limit)
+    }
+}
+
+/// Exposes the roles `$role` of the contract: `has_role`, `admin_role_of` and `role_members`, and
+/// `grant_role` and `revoke_role`, which only the members of the admin role can call. The contract
+/// gets `assert_role` and `assert_any_role` methods, for its own checks and for methods marked
+/// `#[access_control(roles(...))]`.
+///
+/// The roles are passed as JSON, so `$role` also implements `Serialize` and `Deserialize`.
+///
+/// Takes the name of the contract struct, the inner field for the [`Rbac`] and the type of the
+/// roles.
+#[macro_export]
+macro_rules! impl_rbac {
+    ($contract: ident, $rbac: ident, $role: ident) => {
+        $crate::__token_field!(
+            $contract.$rbac: $crate::rbac::Rbac<$role>,
+            __rbac,
+            __rbac_mut
+        );
+
+        impl $contract {
+            /// Panics if the predecessor account isn't a member of `role`.
+            pub fn assert_role(&self, role: &$role) {
+                self.__rbac().assert_role(role)
+            }
+
+            /// Panics if the predecessor account isn't a member of any of `roles`.
+            pub fn assert_any_role(&self, roles: &[$role]) {
+                self.__rbac().assert_any_role(roles)
+            }
+        }
+
+        #[near_bindgen]
+        impl $contract {
+            pub fn has_role(&self, role: $role, account_id: AccountId) -> bool {
+                self.__rbac().has_role(&role, &account_id)
+            }
+
+            pub fn admin_role_of(&self, role: $role) -> Option<$role> {
+                self.__rbac().admin_role_of(&role)
+            }
+
+            pub fn role_members(
+                &self,
+                role: $role,
+                from_index: Option<near_sdk::json_types::U128>,
+                limit: Option<u64>,
+            ) -> Vec<AccountId> {
+                self.__rbac().members_of(&role, from_index, limit)
+            }
+
+            pub fn grant_role(&mut self, role: $role, account_id: AccountId) -> bool {
+                self.__rbac_mut().grant_role(&role, &account_id)
+            }
+
+            pub fn revoke_role(&mut self, role: $role, account_id: AccountId) -> bool {
+                self.__rbac_mut().revoke_role(&role, &account_id)
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::{accounts, get_logs, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    #[derive(BorshDeserialize, BorshSerialize, Serialize, Debug, Clone, Copy, PartialEq)]
+    #[serde(crate = "near_sdk::serde")]
+    enum Role {
+        Admin,
+        Minter,
+        Pauser,
+    }
+
+    fn set_predecessor(predecessor: AccountId) {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(predecessor).build());
+    }
+
+    /// Alice is the admin of the minters and pausers.
+    fn rbac() -> Rbac<Role> {
+        set_predecessor(accounts(0));
+        let mut rbac = Rbac::new(b"r".to_vec());
+        rbac.internal_set_admin_role(&Role::Minter, &Role::Admin);
+        rbac.internal_set_admin_role(&Role::Pauser, &Role::Admin);
+        rbac.internal_grant_role(&Role::Admin, &accounts(0));
+        rbac
+    }
+
+    #[test]
+    fn grant_and_revoke() {
+        let mut rbac = rbac();
+        assert!(rbac.grant_role(&Role::Minter, &accounts(1)));
+        assert!(!rbac.grant_role(&Role::Minter, &accounts(1)));
+        assert!(rbac.has_role(&Role::Minter, &accounts(1)));
+        assert!(!rbac.has_role(&Role::Pauser, &accounts(1)));
+        assert!(rbac.revoke_role(&Role::Minter, &accounts(1)));
+        assert!(!rbac.revoke_role(&Role::Minter, &accounts(1)));
+        assert!(!rbac.has_role(&Role::Minter, &accounts(1)));
+        assert_eq!(
+            get_logs()[1..],
+            [
+                r#"EVENT_JSON:{"standard":"rbac","version":"1.0.0","event":"role_granted","data":[{"role":"Minter","account_id":"bob","sender_id":"alice"}]}"#,
+                r#"EVENT_JSON:{"standard":"rbac","version":"1.0.0","event":"role_revoked","data":[{"role":"Minter","account_id":"bob","sender_id":"alice"}]}"#,
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "The account bob isn't an admin of the role")]
+    fn non_admin_cant_grant() {
+        let mut rbac = rbac();
+        rbac.grant_role(&Role::Minter, &accounts(1));
+        set_predecessor(accounts(1));
+        rbac.grant_role(&Role::Minter, &accounts(2));
+    }
+
+    #[test]
+    #[should_panic(expected = "The account bob isn't an admin of the role")]
+    fn non_admin_cant_revoke() {
+        let mut rbac = rbac();
+        set_predecessor(accounts(1));
+        rbac.revoke_role(&Role::Minter, &accounts(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "The role doesn't have an admin role")]
+    fn role_without_admin_role() {
+        let mut rbac = rbac();
+        rbac.grant_role(&Role::Admin, &accounts(1));
+    }
+
+    #[test]
+    fn members_paginate() {
+        let mut rbac = rbac();
+        for i in 1..5 {
+            rbac.grant_role(&Role::Minter, &accounts(i));
+        }
+        assert_eq!(
+            rbac.members_of(&Role::Minter, None, Some(3)),
+            [accounts(1), accounts(2), accounts(3)]
+        );
+        assert_eq!(rbac.members_of(&Role::Minter, Some(U128(3)), Some(3)), [accounts(4)]);
+        assert!(rbac.members_of(&Role::Pauser, None, None).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "Out of bounds, please use a smaller from_index.")]
+    fn members_out_of_bounds() {
+        let mut rbac = rbac();
+        rbac.grant_role(&Role::Minter, &accounts(1));
+        rbac.members_of(&Role::Minter, Some(U128(1)), None);
+    }
+
+    #[test]
+    fn any_of_roles() {
+        let mut rbac = rbac();
+        rbac.grant_role(&Role::Pauser, &accounts(1));
+        set_predecessor(accounts(1));
+        rbac.assert_any_role(&[Role::Minter, Role::Pauser]);
+        rbac.assert_role(&Role::Pauser);
+    }
+
+    #[test]
+    #[should_panic(expected = "The account bob doesn't have a required role")]
+    fn none_of_roles() {
+        let rbac = rbac();
+        set_predecessor(accounts(1));
+        rbac.assert_any_role(&[Role::Minter, Role::Pauser]);
+    }
+}
@@ -0,0 +1,146 @@
+//! Helpers for contracts receiving tokens through `ft_on_transfer` or `nft_on_transfer`.
+//!
+//! A receiver which runs out of gas halfway through its own cross-contract calls can leave the
+//! transfer in a state where the tokens are neither used nor returned. Checking the gas up front
+//! with [`ensure_gas_for`](crate::receiver_utils::ensure_gas_for) lets the receiver return the
+//! tokens instead.
+
+use near_sdk::json_types::U128;
+use near_sdk::{env, Gas, PromiseOrValue};
+use std::fmt;
+
+/// Gas added on top of the declared plan in [`ensure_gas_for`], covering the execution of the
+/// receiver method itself after the check.
+pub const GAS_SAFETY_MARGIN: Gas = Gas(5_000_000_000_000);
+
+/// Error returned by [`ensure_gas_for`] when the remaining gas does not cover the plan.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InsufficientGas {
+    /// Gas needed for the plan, including [`GAS_SAFETY_MARGIN`].
+    pub required: Gas,
+    /// Gas left in the current call.
+    pub available: Gas,
+    /// Name of the first call in the plan which could not be covered.
+    pub call: &'static str,
+}
+
+impl InsufficientGas {
+    /// Response to `ft_on_transfer` returning all of the transferred `amount` to the sender.
+    pub fn refund_ft(&self, amount: U128) -> PromiseOrValue<U128> {
+        PromiseOrValue::Value(amount)
+    }
+
+    /// Response to `nft_on_transfer` returning the token to the previous owner.
+    pub fn return_nft(&self) -> PromiseOrValue<bool> {
+        PromiseOrValue::Value(true)
+    }
+}
+
+impl fmt::Display for InsufficientGas {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Not enough gas for {}: required {}, available {}",
+            self.call, self.required.0, self.available.0
+        )
+    }
+}
+
+impl std::error::Error for InsufficientGas {}
+
+/// Checks that the gas left in the current call covers every call in `plan`, given as pairs of a
+/// name and the gas attached to that call, plus [`GAS_SAFETY_MARGIN`].
+///
+/// # Examples
+/// ```
+/// use near_contract_standards::receiver_utils::ensure_gas_for;
+/// use near_sdk::json_types::U128;
+/// use near_sdk::{Gas, PromiseOrValue};
+///
+/// fn ft_on_transfer(amount: U128) -> PromiseOrValue<U128> {
+///     if let Err(err) = ensure_gas_for(&[("deposit", Gas(10_000_000_000_000))]) {
+///         return err.refund_ft(amount);
+///     }
+///     // Schedule the calls declared in the plan.
+///     PromiseOrValue::Value(U128(0))
+/// }
+/// ```
+pub fn ensure_gas_for(plan: &[(&'static str, Gas)]) -> Result<(), InsufficientGas> {
+    let available = Gas(env::prepaid_gas().0.saturating_sub(env::used_gas().0));
+    let mut required = GAS_SAFETY_MARGIN;
+    for &(call, gas) in plan {
+        required = Gas(required.0.saturating_add(gas.0));
+        if required > available {
+            return Err(InsufficientGas { required: plan_total(plan), available, call });
+        }
+    }
+    Ok(())
+}
+
+fn plan_total(plan: &[(&'static str, Gas)]) -> Gas {
+    plan.iter().fold(GAS_SAFETY_MARGIN, |total, (_, gas)| Gas(total.0.saturating_add(gas.0)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fungible_token::receiver::FungibleTokenReceiver;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::{testing_env, AccountId};
+
+    const GAS_FOR_DEPOSIT: Gas = Gas(10_000_000_000_000);
+    const GAS_FOR_CALLBACK: Gas = Gas(5_000_000_000_000);
+
+    #[derive(Default)]
+    struct Receiver {
+        deposits: u128,
+    }
+
+    impl FungibleTokenReceiver for Receiver {
+        fn ft_on_transfer(
+            &mut self,
+            _sender_id: AccountId,
+            amount: U128,
+            _msg: String,
+        ) -> PromiseOrValue<U128> {
+            if let Err(err) =
+                ensure_gas_for(&[("deposit", GAS_FOR_DEPOSIT), ("callback", GAS_FOR_CALLBACK)])
+            {
+                return err.refund_ft(amount);
+            }
+            self.deposits += amount.0;
+            PromiseOrValue::Value(U128(0))
+        }
+    }
+
+    fn unused(outcome: PromiseOrValue<U128>) -> u128 {
+        match outcome {
+            PromiseOrValue::Value(unused) => unused.0,
+            PromiseOrValue::Promise(_) => panic!("expected a value"),
+        }
+    }
+
+    #[test]
+    fn enough_gas() {
+        testing_env!(VMContextBuilder::new().prepaid_gas(Gas::ONE_TERA * 30).build());
+        let mut receiver = Receiver::default();
+        let outcome = receiver.ft_on_transfer("alice.near".parse().unwrap(), U128(10), "".into());
+        assert_eq!(unused(outcome), 0);
+        assert_eq!(receiver.deposits, 10);
+    }
+
+    #[test]
+    fn insufficient_gas_refunds_everything() {
+        testing_env!(VMContextBuilder::new().prepaid_gas(Gas::ONE_TERA * 18).build());
+        let mut receiver = Receiver::default();
+        let outcome = receiver.ft_on_transfer("alice.near".parse().unwrap(), U128(10), "".into());
+        assert_eq!(unused(outcome), 10);
+        assert_eq!(receiver.deposits, 0);
+
+        let err = ensure_gas_for(&[("deposit", GAS_FOR_DEPOSIT), ("callback", GAS_FOR_CALLBACK)])
+            .unwrap_err();
+        assert_eq!(err.call, "callback");
+        assert_eq!(err.required, GAS_SAFETY_MARGIN + GAS_FOR_DEPOSIT + GAS_FOR_CALLBACK);
+        assert!(matches!(err.return_nft(), PromiseOrValue::Value(true)));
+    }
+}
@@ -0,0 +1,173 @@
+use near_sdk::{env, require};
+use std::fmt;
+use std::str::FromStr;
+
+/// A parsed `spec` of contract metadata, such as `nft-1.0.0`: the name of the standard followed by
+/// its version.
+///
+/// Versions are compared like semver: a contract implementing `nft-1.2.0` supports clients
+/// requiring `nft-1.0.0` or `nft-1.2.0`, but not `nft-1.3.0` or `nft-2.0.0`.
+///
+/// # Examples
+/// ```
+/// use near_contract_standards::spec::SpecVersion;
+///
+/// let implemented: SpecVersion = "nft-1.2.0".parse().unwrap();
+/// assert!(implemented.is_compatible_with(&"nft-1.0.0".parse().unwrap()));
+/// assert!(!implemented.is_compatible_with(&"nft-2.0.0".parse().unwrap()));
+/// assert!("nep171".parse::<SpecVersion>().is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpecVersion {
+    /// The name of the standard, for example `nft`.
+    pub standard: String,
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl SpecVersion {
+    /// Returns `true` if a contract implementing this version can serve a client requiring
+    /// `required`: the standard and the major version are the same, and this version is not older.
+    pub fn is_compatible_with(&self, required: &SpecVersion) -> bool {
+        self.standard == required.standard
+            && self.major == required.major
+            && (self.minor, self.patch) >= (required.minor, required.patch)
+    }
+}
+
+/// Error returned when parsing a malformed [`SpecVersion`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpecParseError {
+    /// The spec which failed to parse.
+    pub spec: String,
+}
+
+impl fmt::Display for SpecParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Malformed spec \"{}\", expected a standard and a version like nft-1.0.0",
+            self.spec
+        )
+    }
+}
+
+impl std::error::Error for SpecParseError {}
+
+impl FromStr for SpecVersion {
+    type Err = SpecParseError;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let err = || SpecParseError { spec: spec.to_string() };
+        let (standard, version) = spec.split_once('-').ok_or_else(err)?;
+        if standard.is_empty()
+            || !standard.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit())
+        {
+            return Err(err());
+        }
+        let mut numbers = version.split('.').map(|number| {
+            // `u32::from_str` also accepts a leading `+`.
+            if number.is_empty() || !number.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(err());
+            }
+            number.parse::<u32>().map_err(|_| err())
+        });
+        let mut next = || numbers.next().unwrap_or_else(|| Err(err()));
+        let (major, minor, patch) = (next()?, next()?, next()?);
+        if numbers.next().is_some() {
+            return Err(err());
+        }
+        Ok(Self { standard: standard.to_string(), major, minor, patch })
+    }
+}
+
+impl fmt::Display for SpecVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}.{}.{}", self.standard, self.major, self.minor, self.patch)
+    }
+}
+
+/// Returns `true` if a contract whose metadata has `implemented` as spec supports clients
+/// requiring `required`. Malformed specs are not supported.
+pub(crate) fn supports_spec(implemented: &str, required: &str) -> bool {
+    match (implemented.parse::<SpecVersion>(), required.parse::<SpecVersion>()) {
+        (Ok(implemented), Ok(required)) => implemented.is_compatible_with(&required),
+        _ => false,
+    }
+}
+
+/// Panics if `spec` is malformed, or isn't a version of the standard of `supported` with the same
+/// major version.
+pub(crate) fn assert_supported_spec(spec: &str, supported: &str, message: &str) {
+    let spec: SpecVersion =
+        spec.parse().unwrap_or_else(|err: SpecParseError| env::panic_str(&err.to_string()));
+    let supported: SpecVersion = supported.parse().unwrap();
+    require!(spec.standard == supported.standard && spec.major == supported.major, message);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(spec: &str) -> SpecVersion {
+        spec.parse().unwrap()
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            version("nft-1.2.3"),
+            SpecVersion { standard: "nft".to_string(), major: 1, minor: 2, patch: 3 }
+        );
+        assert_eq!(version("nft-1.2.3").to_string(), "nft-1.2.3");
+        assert_eq!(version("nep171-10.0.0").standard, "nep171");
+    }
+
+    #[test]
+    fn parse_malformed() {
+        for spec in [
+            "",
+            "nft",
+            "nep171",
+            "nft-",
+            "nft-1",
+            "nft-1.0",
+            "nft-1.0.0.0",
+            "nft-1..0",
+            "nft-+1.0.0",
+            "nft-1.0.0-beta",
+            "NFT-1.0.0",
+            "-1.0.0",
+            "nft 1.0.0",
+            "nft-99999999999.0.0",
+        ] {
+            assert_eq!(
+                spec.parse::<SpecVersion>(),
+                Err(SpecParseError { spec: spec.to_string() }),
+                "{}",
+                spec
+            );
+        }
+    }
+
+    #[test]
+    fn compatibility() {
+        let implemented = version("nft-1.2.0");
+        assert!(implemented.is_compatible_with(&version("nft-1.0.0")));
+        assert!(implemented.is_compatible_with(&version("nft-1.1.5")));
+        assert!(implemented.is_compatible_with(&version("nft-1.2.0")));
+        assert!(!implemented.is_compatible_with(&version("nft-1.2.1")));
+        assert!(!implemented.is_compatible_with(&version("nft-1.3.0")));
+        assert!(!implemented.is_compatible_with(&version("nft-2.0.0")));
+        assert!(!implemented.is_compatible_with(&version("nft-0.2.0")));
+        assert!(!implemented.is_compatible_with(&version("ft-1.0.0")));
+    }
+
+    #[test]
+    fn supports() {
+        assert!(supports_spec("ft-1.0.0", "ft-1.0.0"));
+        assert!(!supports_spec("ft-1.0.0", "ft-1"));
+        assert!(!supports_spec("ft-1", "ft-1.0.0"));
+    }
+}
@@ -0,0 +1,248 @@
+//! Registration of the standards implemented by a contract.
+//!
+//! Each of the `impl_*` macros registers the standard it implements, so that tooling can detect
+//! the standards of a contract without calling any method:
+//!
+//! - In wasm builds, an entry is embedded in the `near_standards` custom section. The linker
+//!   concatenates the entries of all macros, so the section holds one JSON object per line, for
+//!   example `{"standard":"nep141","version":"1.0.0"}`.
+//! - At runtime, [`standards_manifest`](crate::standards_manifest()) returns the same entries,
+//!   for example to be returned from a view method. The entries are constants of the module in
+//!   which the macros were invoked, which the [`standards_manifest!`](crate::standards_manifest!)
+//!   macro passes to it.
+//!
+//! Every standard is registered under its own symbol names, so the macros of different standards
+//! can be used together.
+
+use serde::{Deserialize, Serialize};
+
+/// A standard implemented by a contract, and the version of the standard.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Standard<'a> {
+    pub standard: &'a str,
+    pub version: &'a str,
+}
+
+/// [Fungible token core](https://nomicon.io/Standards/FungibleToken/Core.html).
+pub const NEP141: Standard<'static> = Standard { standard: "nep141", version: "1.0.0" };
+/// [Storage management](https://nomicon.io/Standards/StorageManagement.html).
+pub const NEP145: Standard<'static> = Standard { standard: "nep145", version: "1.0.0" };
+/// [Non-fungible token core](https://nomicon.io/Standards/NonFungibleToken/Core.html).
+pub const NEP171: Standard<'static> = Standard { standard: "nep171", version: "1.0.0" };
+/// [Non-fungible token metadata](https://nomicon.io/Standards/NonFungibleToken/Metadata.html).
+pub const NEP177: Standard<'static> = Standard { standard: "nep177", version: "1.0.0" };
+/// [Non-fungible token approval management](https://nomicon.io/Standards/NonFungibleToken/ApprovalManagement.html).
+pub const NEP178: Standard<'static> = Standard { standard: "nep178", version: "1.0.0" };
+/// [Non-fungible token enumeration](https://nomicon.io/Standards/NonFungibleToken/Enumeration.html).
+pub const NEP181: Standard<'static> = Standard { standard: "nep181", version: "1.0.0" };
+/// [Non-fungible token royalties and payouts](https://nomicon.io/Standards/NonFungibleToken/Payout).
+pub const NEP199: Standard<'static> = Standard { standard: "nep199", version: "2.0.0" };
+/// [Multi token](https://nomicon.io/Standards/Tokens/MultiToken/Core).
+pub const NEP245: Standard<'static> = Standard { standard: "nep245", version: "1.0.0" };
+/// [Contract source metadata](https://nomicon.io/Standards/SourceMetadata).
+pub const NEP330: Standard<'static> = Standard { standard: "nep330", version: "1.0.0" };
+
+/// Name of the wasm custom section holding the registered standards.
+pub const SECTION_NAME: &str = "near_standards";
+
+/// Defaults for the registration constants of every standard, glob imported by the `impl_*`
+/// macros. A macro registering a standard defines a constant with the same name, which takes
+/// precedence over the glob import.
+#[doc(hidden)]
+pub mod unregistered {
+    use super::Standard;
+
+    pub const __NEAR_STANDARD_NEP141: Option<Standard<'static>> = None;
+    pub const __NEAR_STANDARD_NEP145: Option<Standard<'static>> = None;
+    pub const __NEAR_STANDARD_NEP171: Option<Standard<'static>> = None;
+    pub const __NEAR_STANDARD_NEP177: Option<Standard<'static>> = None;
+    pub const __NEAR_STANDARD_NEP178: Option<Standard<'static>> = None;
+    pub const __NEAR_STANDARD_NEP181: Option<Standard<'static>> = None;
+    pub const __NEAR_STANDARD_NEP199: Option<Standard<'static>> = None;
+    pub const __NEAR_STANDARD_NEP245: Option<Standard<'static>> = None;
+    pub const __NEAR_STANDARD_NEP330: Option<Standard<'static>> = None;
+}
+
+/// Copies `json` into an array to be embedded in the custom section.
+#[doc(hidden)]
+pub const fn section_entry<const N: usize>(json: &str) -> [u8; N] {
+    let bytes = json.as_bytes();
+    let mut entry = [0; N];
+    let mut i = 0;
+    while i < N {
+        entry[i] = bytes[i];
+        i += 1;
+    }
+    entry
+}
+
+/// Returns the standards in `registered` which were registered by the `impl_*` macros, skipping
+/// the `None` entries of the standards which weren't.
+///
+/// The registrations are constants of the module in which the macros were invoked, so this is
+/// usually called through [`standards_manifest!`](crate::standards_manifest!), which passes the
+/// registrations of every standard.
+pub fn standards_manifest(registered: &[Option<Standard<'static>>]) -> Vec<Standard<'static>> {
+    registered.iter().flatten().copied().collect()
+}
+
+/// Registers a standard in the current module. The constant is read by
+/// [`standards_manifest!`](crate::standards_manifest!), and the static is embedded in the custom
+/// section of wasm builds.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __register_standard {
+    (nep141) => {
+        $crate::__register_standard!(@entry __NEAR_STANDARD_NEP141, __NEAR_STANDARD_NEP141_SECTION, "nep141", "1.0.0");
+    };
+    (nep145) => {
+        $crate::__register_standard!(@entry __NEAR_STANDARD_NEP145, __NEAR_STANDARD_NEP145_SECTION, "nep145", "1.0.0");
+    };
+    (nep171) => {
+        $crate::__register_standard!(@entry __NEAR_STANDARD_NEP171, __NEAR_STANDARD_NEP171_SECTION, "nep171", "1.0.0");
+    };
+    (nep177) => {
+        $crate::__register_standard!(@entry __NEAR_STANDARD_NEP177, __NEAR_STANDARD_NEP177_SECTION, "nep177", "1.0.0");
+    };
+    (nep178) => {
+        $crate::__register_standard!(@entry __NEAR_STANDARD_NEP178, __NEAR_STANDARD_NEP178_SECTION, "nep178", "1.0.0");
+    };
+    (nep181) => {
+        $crate::__register_standard!(@entry __NEAR_STANDARD_NEP181, __NEAR_STANDARD_NEP181_SECTION, "nep181", "1.0.0");
+    };
+    (nep199) => {
+        $crate::__register_standard!(@entry __NEAR_STANDARD_NEP199, __NEAR_STANDARD_NEP199_SECTION, "nep199", "2.0.0");
+    };
+    (nep245) => {
+        $crate::__register_standard!(@entry __NEAR_STANDARD_NEP245, __NEAR_STANDARD_NEP245_SECTION, "nep245", "1.0.0");
+    };
+    (nep330) => {
+        $crate::__register_standard!(@entry __NEAR_STANDARD_NEP330, __NEAR_STANDARD_NEP330_SECTION, "nep330", "1.0.0");
+    };
+    (@entry $const_name:ident, $section_name:ident, $standard:literal, $version:literal) => {
+        #[allow(unused_imports)]
+        use $crate::standards::unregistered::*;
+
+        #[doc(hidden)]
+        #[allow(dead_code)]
+        const $const_name: Option<$crate::standards::Standard<'static>> =
+            Some($crate::standards::Standard { standard: $standard, version: $version });
+
+        #[cfg(target_arch = "wasm32")]
+        #[doc(hidden)]
+        #[used]
+        #[link_section = "near_standards"]
+        static $section_name: [u8; concat!(
+            "{\"standard\":\"",
+            $standard,
+            "\",\"version\":\"",
+            $version,
+            "\"}\n"
+        )
+        .len()] = $crate::standards::section_entry(concat!(
+            "{\"standard\":\"",
+            $standard,
+            "\",\"version\":\"",
+            $version,
+            "\"}\n"
+        ));
+    };
+}
+
+/// Returns the standards registered by the `impl_*` macros, as a `Vec<Standard>`, by calling
+/// [`standards_manifest`](crate::standards_manifest()) with the registrations of the current
+/// module.
+///
+/// This must be used in the module in which the macros were invoked, after at least one of them.
+///
+/// # Examples
+/// ```ignore
+/// near_contract_standards::impl_fungible_token_core!(Contract, token);
+/// near_contract_standards::impl_fungible_token_storage!(Contract, token);
+///
+/// #[near_bindgen]
+/// impl Contract {
+///     pub fn standards(&self) -> Vec<near_contract_standards::standards::Standard<'static>> {
+///         // Returns the entries for nep141 and nep145.
+///         near_contract_standards::standards_manifest!()
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! standards_manifest {
+    () => {
+        $crate::standards_manifest(&[
+            __NEAR_STANDARD_NEP141,
+            __NEAR_STANDARD_NEP145,
+            __NEAR_STANDARD_NEP171,
+            __NEAR_STANDARD_NEP177,
+            __NEAR_STANDARD_NEP178,
+            __NEAR_STANDARD_NEP181,
+            __NEAR_STANDARD_NEP199,
+            __NEAR_STANDARD_NEP245,
+            __NEAR_STANDARD_NEP330,
+        ])
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod fungible_token {
+        crate::__register_standard!(nep141);
+        crate::__register_standard!(nep145);
+
+        pub fn manifest() -> Vec<super::Standard<'static>> {
+            crate::standards_manifest!()
+        }
+    }
+
+    #[test]
+    fn registered_standards() {
+        assert_eq!(fungible_token::manifest(), [NEP141, NEP145]);
+    }
+
+    #[test]
+    fn every_standard() {
+        mod all {
+            crate::__register_standard!(nep141);
+            crate::__register_standard!(nep145);
+            crate::__register_standard!(nep171);
+            crate::__register_standard!(nep177);
+            crate::__register_standard!(nep178);
+            crate::__register_standard!(nep181);
+            crate::__register_standard!(nep199);
+            crate::__register_standard!(nep245);
+            crate::__register_standard!(nep330);
+
+            pub fn manifest() -> Vec<super::Standard<'static>> {
+                crate::standards_manifest!()
+            }
+        }
+        assert_eq!(
+            all::manifest(),
+            [NEP141, NEP145, NEP171, NEP177, NEP178, NEP181, NEP199, NEP245, NEP330]
+        );
+    }
+
+    #[test]
+    fn manifest_skips_unregistered() {
+        assert_eq!(standards_manifest(&[None, Some(NEP171), None, Some(NEP181)]), [NEP171, NEP181]);
+        assert!(standards_manifest(&[None, None]).is_empty());
+    }
+
+    #[test]
+    fn manifest_json() {
+        assert_eq!(
+            serde_json::to_string(&fungible_token::manifest()).unwrap(),
+            r#"[{"standard":"nep141","version":"1.0.0"},{"standard":"nep145","version":"1.0.0"}]"#
+        );
+    }
+
+    #[test]
+    fn section_entries() {
+        let entry: [u8; 5] = section_entry("ab\"c\n");
+        assert_eq!(&entry, b"ab\"c\n");
+    }
+}
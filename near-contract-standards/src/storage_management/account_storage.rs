@@ -0,0 +1,358 @@
+use crate::storage_management::{StorageBalance, StorageBalanceBounds, StorageManagement};
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::LookupMap;
+use near_sdk::json_types::U128;
+use near_sdk::{assert_one_yocto, env, log, require, AccountId, Balance, IntoStorageKey, Promise};
+
+/// Hooks run by [`AccountStorage`] when an account registers or unregisters, for the contract to
+/// create and clean up its own data of the account. Every method does nothing by default, and
+/// `()` is the hook of contracts which keep no data per account.
+pub trait AccountStorageHook {
+    /// Called when `account_id` registers with `storage_deposit`, once its storage balance is
+    /// recorded.
+    fn on_account_registered(&mut self, account_id: &AccountId) {
+        let _ = account_id;
+    }
+
+    /// Called when `account_id` unregisters without `force`, before its storage balance is
+    /// refunded. Panics if the account still holds something it would lose, like a positive
+    /// balance of tokens, which aborts the unregistration.
+    fn on_account_closed(&mut self, account_id: &AccountId) {
+        let _ = account_id;
+    }
+
+    /// Called when `account_id` unregisters with `force`, before its storage balance is refunded.
+    /// The data of the account should be removed even if it holds something, e.g. by burning its
+    /// tokens.
+    fn on_account_force_closed(&mut self, account_id: &AccountId) {
+        let _ = account_id;
+    }
+}
+
+impl AccountStorageHook for () {}
+
+/// Storage balances of the accounts registered with a contract, implementing
+/// [`StorageManagement`] for any contract, like a token or a registry.
+///
+/// An account registers by depositing at least the minimum of the bounds, which is locked for as
+/// long as it's registered. Anything deposited above it, up to the maximum, is available to pay
+/// for the storage the account uses, see [`internal_lock`](Self::internal_lock). The contract
+/// creates and removes its own data of the accounts with an [`AccountStorageHook`], passed to the
+/// `_with_hook` methods.
+///
+/// # Examples
+/// ```
+/// use near_contract_standards::storage_management::{AccountStorage, StorageBalanceBounds};
+/// use near_sdk::json_types::U128;
+///
+/// let bounds = StorageBalanceBounds { min: U128(100), max: Some(U128(1_000)) };
+/// let mut storage = AccountStorage::new(b"s", bounds);
+/// let alice = "alice.near".parse().unwrap();
+/// storage.internal_register(&alice, 500);
+/// storage.internal_lock(&alice, 150);
+/// assert_eq!(storage.internal_storage_balance_of(&alice).unwrap().available, U128(250));
+/// ```
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct AccountStorage {
+    /// AccountID -> Storage balance of the account.
+    pub balances: LookupMap<AccountId, StorageBalance>,
+
+    /// Bounds of the storage balance of an account.
+    pub bounds: StorageBalanceBounds,
+}
+
+/// Where the storage balances of the registered accounts are kept, with the hook run when they
+/// register and unregister. This lets a contract which already keeps a record per account, like
+/// [`FungibleToken`](crate::fungible_token::FungibleToken), derive the storage balances from it
+/// rather than storing them in an [`AccountStorage`].
+pub(crate) trait StorageBalances: AccountStorageHook {
+    fn bounds(&self) -> StorageBalanceBounds;
+
+    fn get(&self, account_id: &AccountId) -> Option<StorageBalance>;
+
+    /// Records the storage balance of `account_id`, which may not be registered yet.
+    fn set(&mut self, account_id: &AccountId, balance: &StorageBalance);
+
+    fn remove(&mut self, account_id: &AccountId);
+}
+
+/// An [`AccountStorage`] with the hook passed to one of its `_with_hook` methods.
+struct Hooked<'a, H: ?Sized> {
+    storage: &'a mut AccountStorage,
+    hook: &'a mut H,
+}
+
+impl<H: AccountStorageHook + ?Sized> AccountStorageHook for Hooked<'_, H> {
+    fn on_account_registered(&mut self, account_id: &AccountId) {
+        self.hook.on_account_registered(account_id)
+    }
+
+    fn on_account_closed(&mut self, account_id: &AccountId) {
+        self.hook.on_account_closed(account_id)
+    }
+
+    fn on_account_force_closed(&mut self, account_id: &AccountId) {
+        self.hook.on_account_force_closed(account_id)
+    }
+}
+
+impl<H: AccountStorageHook + ?Sized> StorageBalances for Hooked<'_, H> {
+    fn bounds(&self) -> StorageBalanceBounds {
+        self.storage.bounds.clone()
+    }
+
+    fn get(&self, account_id: &AccountId) -> Option<StorageBalance> {
+        self.storage.balances.get(account_id)
+    }
+
+    fn set(&mut self, account_id: &AccountId, balance: &StorageBalance) {
+        self.storage.balances.insert(account_id, balance);
+    }
+
+    fn remove(&mut self, account_id: &AccountId) {
+        self.storage.balances.remove(account_id);
+    }
+}
+
+fn unwrap_storage_balance_of<B: StorageBalances + ?Sized>(
+    balances: &B,
+    account_id: &AccountId,
+) -> StorageBalance {
+    balances.get(account_id).unwrap_or_else(|| {
+        env::panic_str(format!("The account {} is not registered", account_id).as_str())
+    })
+}
+
+/// See [`AccountStorage::internal_register`].
+pub(crate) fn internal_register<B: StorageBalances + ?Sized>(
+    balances: &mut B,
+    account_id: &AccountId,
+    total: Balance,
+) {
+    if balances.get(account_id).is_some() {
+        env::panic_str("The account is already registered");
+    }
+    register_unchecked(balances, account_id, total);
+}
+
+/// Registers `account_id`, which must not be registered yet, and returns its storage balance.
+fn register_unchecked<B: StorageBalances + ?Sized>(
+    balances: &mut B,
+    account_id: &AccountId,
+    total: Balance,
+) -> StorageBalance {
+    let bounds = balances.bounds();
+    require!(total >= bounds.min.0, "The storage balance is less than the minimum");
+    require!(
+        bounds.max.map_or(true, |max| total <= max.0),
+        "The storage balance is more than the maximum"
+    );
+    let balance = StorageBalance { total: U128(total), available: U128(total - bounds.min.0) };
+    balances.set(account_id, &balance);
+    balance
+}
+
+/// See [`AccountStorage::storage_deposit_with_hook`].
+pub(crate) fn storage_deposit<B: StorageBalances + ?Sized>(
+    balances: &mut B,
+    account_id: Option<AccountId>,
+    registration_only: Option<bool>,
+) -> StorageBalance {
+    let amount: Balance = env::attached_deposit();
+    let account_id = account_id.unwrap_or_else(env::predecessor_account_id);
+    let registration_only = registration_only.unwrap_or(false);
+    let bounds = balances.bounds();
+    let max = bounds.max.map_or(Balance::MAX, |max| max.0);
+    let (balance, refund) = match balances.get(&account_id) {
+        Some(mut balance) => {
+            let kept = if registration_only { 0 } else { amount.min(max - balance.total.0) };
+            if kept == 0 {
+                log!("The account is already registered, refunding the deposit");
+            } else {
+                balance.total.0 += kept;
+                balance.available.0 += kept;
+                balances.set(&account_id, &balance);
+            }
+            (balance, amount - kept)
+        }
+        None => {
+            let min_balance = bounds.min.0;
+            if amount < min_balance {
+                env::panic_str("The attached deposit is less than the minimum storage balance");
+            }
+            let kept = if registration_only { min_balance } else { amount.min(max) };
+            let balance = register_unchecked(balances, &account_id, kept);
+            balances.on_account_registered(&account_id);
+            (balance, amount - kept)
+        }
+    };
+    if refund > 0 {
+        Promise::new(env::predecessor_account_id()).transfer(refund);
+    }
+    balance
+}
+
+/// See [`AccountStorage::storage_withdraw`](StorageManagement::storage_withdraw).
+pub(crate) fn storage_withdraw<B: StorageBalances + ?Sized>(
+    balances: &mut B,
+    amount: Option<U128>,
+) -> StorageBalance {
+    assert_one_yocto();
+    let predecessor_account_id = env::predecessor_account_id();
+    let mut balance = unwrap_storage_balance_of(balances, &predecessor_account_id);
+    let amount = amount.map_or(balance.available.0, |amount| amount.0);
+    if amount > balance.available.0 {
+        env::panic_str(&format!(
+            "The amount is greater than the available storage balance of {}",
+            balance.available.0
+        ));
+    }
+    if amount > 0 {
+        balance.total.0 -= amount;
+        balance.available.0 -= amount;
+        balances.set(&predecessor_account_id, &balance);
+        Promise::new(predecessor_account_id).transfer(amount);
+    }
+    balance
+}
+
+/// See [`AccountStorage::storage_unregister_with_hook`].
+pub(crate) fn storage_unregister<B: StorageBalances + ?Sized>(
+    balances: &mut B,
+    force: Option<bool>,
+) -> bool {
+    assert_one_yocto();
+    let account_id = env::predecessor_account_id();
+    if let Some(balance) = balances.get(&account_id) {
+        if force.unwrap_or(false) {
+            balances.on_account_force_closed(&account_id);
+        } else {
+            balances.on_account_closed(&account_id);
+        }
+        balances.remove(&account_id);
+        // The attached yoctoNEAR is refunded with the storage balance.
+        Promise::new(account_id).transfer(balance.total.0 + 1);
+        true
+    } else {
+        log!("The account {} is not registered", &account_id);
+        false
+    }
+}
+
+impl AccountStorage {
+    pub fn new<S>(prefix: S, bounds: StorageBalanceBounds) -> Self
+    where
+        S: IntoStorageKey,
+    {
+        require!(
+            bounds.max.map_or(true, |max| max.0 >= bounds.min.0),
+            "The maximum storage balance can't be less than the minimum"
+        );
+        Self { balances: LookupMap::new(prefix), bounds }
+    }
+
+    fn hooked<'a, H: AccountStorageHook + ?Sized>(&'a mut self, hook: &'a mut H) -> Hooked<'a, H> {
+        Hooked { storage: self, hook }
+    }
+
+    /// Returns `true` if `account_id` is registered.
+    pub fn is_registered(&self, account_id: &AccountId) -> bool {
+        self.balances.contains_key(account_id)
+    }
+
+    pub fn internal_storage_balance_of(&self, account_id: &AccountId) -> Option<StorageBalance> {
+        self.balances.get(account_id)
+    }
+
+    fn internal_unwrap_storage_balance_of(&self, account_id: &AccountId) -> StorageBalance {
+        self.balances.get(account_id).unwrap_or_else(|| {
+            env::panic_str(format!("The account {} is not registered", account_id).as_str())
+        })
+    }
+
+    /// Registers `account_id` with a storage balance of `total`, which must be within the bounds,
+    /// without a deposit or running a hook. Panics if the account is already registered.
+    pub fn internal_register(&mut self, account_id: &AccountId, total: Balance) {
+        internal_register(&mut self.hooked(&mut ()), account_id, total)
+    }
+
+    /// Locks `amount` of the available storage balance of `account_id`, to pay for storage the
+    /// account uses. Panics if the account isn't registered or doesn't have enough available.
+    pub fn internal_lock(&mut self, account_id: &AccountId, amount: Balance) {
+        let mut balance = self.internal_unwrap_storage_balance_of(account_id);
+        balance.available.0 =
+            balance.available.0.checked_sub(amount).unwrap_or_else(|| {
+                env::panic_str("The account doesn't have enough storage balance")
+            });
+        self.balances.insert(account_id, &balance);
+    }
+
+    /// Makes `amount` of the storage balance of `account_id` locked with
+    /// [`internal_lock`](Self::internal_lock) available again, once the storage is freed.
+    pub fn internal_unlock(&mut self, account_id: &AccountId, amount: Balance) {
+        let mut balance = self.internal_unwrap_storage_balance_of(account_id);
+        let available = balance.available.0 + amount;
+        require!(
+            available <= balance.total.0 - self.bounds.min.0,
+            "The unlocked amount is more than the locked storage balance"
+        );
+        balance.available.0 = available;
+        self.balances.insert(account_id, &balance);
+    }
+
+    /// `storage_deposit` calling [`on_account_registered`] when the account registers.
+    ///
+    /// [`on_account_registered`]: AccountStorageHook::on_account_registered
+    pub fn storage_deposit_with_hook<H: AccountStorageHook + ?Sized>(
+        &mut self,
+        hook: &mut H,
+        account_id: Option<AccountId>,
+        registration_only: Option<bool>,
+    ) -> StorageBalance {
+        storage_deposit(&mut self.hooked(hook), account_id, registration_only)
+    }
+
+    /// `storage_unregister` calling [`on_account_closed`] or [`on_account_force_closed`] before
+    /// the storage balance of the account is refunded.
+    ///
+    /// [`on_account_closed`]: AccountStorageHook::on_account_closed
+    /// [`on_account_force_closed`]: AccountStorageHook::on_account_force_closed
+    pub fn storage_unregister_with_hook<H: AccountStorageHook + ?Sized>(
+        &mut self,
+        hook: &mut H,
+        force: Option<bool>,
+    ) -> bool {
+        storage_unregister(&mut self.hooked(hook), force)
+    }
+}
+
+impl StorageManagement for AccountStorage {
+    /// Registers the account or adds the deposit to its storage balance, see
+    /// [`storage_deposit_with_hook`](Self::storage_deposit_with_hook).
+    fn storage_deposit(
+        &mut self,
+        account_id: Option<AccountId>,
+        registration_only: Option<bool>,
+    ) -> StorageBalance {
+        self.storage_deposit_with_hook(&mut (), account_id, registration_only)
+    }
+
+    /// Transfers `amount` of the available storage balance of the predecessor, or all of it
+    /// without an amount, and returns the storage balance left. The minimum of the bounds and the
+    /// storage balance locked by [`internal_lock`](Self::internal_lock) can't be withdrawn.
+    fn storage_withdraw(&mut self, amount: Option<U128>) -> StorageBalance {
+        storage_withdraw(&mut self.hooked(&mut ()), amount)
+    }
+
+    fn storage_unregister(&mut self, force: Option<bool>) -> bool {
+        self.storage_unregister_with_hook(&mut (), force)
+    }
+
+    fn storage_balance_bounds(&self) -> StorageBalanceBounds {
+        self.bounds.clone()
+    }
+
+    fn storage_balance_of(&self, account_id: AccountId) -> Option<StorageBalance> {
+        self.internal_storage_balance_of(&account_id)
+    }
+}
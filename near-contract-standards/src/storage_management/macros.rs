@@ -0,0 +1,21 @@
+/// The `storage_requirements` view, returning the storage each operation of the token stored in
+/// `$token` requires to be paid for by the caller, so frontends know how much deposit to attach.
+/// Takes name of the Contract struct and the inner field for the token, a
+/// [`FungibleToken`](crate::fungible_token::FungibleToken) or a
+/// [`NonFungibleToken`](crate::non_fungible_token::NonFungibleToken).
+///
+/// This isn't part of the FT and NFT macros, so that a contract using both picks the token whose
+/// requirements it returns.
+#[macro_export]
+macro_rules! impl_storage_requirements {
+    ($contract: ident, $token: ident) => {
+        #[near_bindgen]
+        impl $crate::storage_management::StorageRequirementsView for $contract {
+            fn storage_requirements(&self) -> $crate::storage_management::StorageRequirements {
+                $crate::storage_management::StorageRequirementsView::storage_requirements(
+                    &self.$token,
+                )
+            }
+        }
+    };
+}
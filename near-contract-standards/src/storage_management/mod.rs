@@ -1,16 +1,27 @@
+mod account_storage;
+mod macros;
+
+pub use self::account_storage::*;
+pub use macros::*;
+
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::json_types::U128;
+use near_sdk::json_types::{U128, U64};
 use near_sdk::serde::{Deserialize, Serialize};
-use near_sdk::AccountId;
+use near_sdk::{env, AccountId, Balance, StorageUsage};
+use std::collections::BTreeMap;
 
-#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
+/// Maximum length of an account ID. Storage is measured with account IDs of this length so that
+/// the estimates cover any account.
+pub const MAX_ACCOUNT_ID_LEN: usize = 64;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(crate = "near_sdk::serde")]
 pub struct StorageBalance {
     pub total: U128,
     pub available: U128,
 }
 
-#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(crate = "near_sdk::serde")]
 pub struct StorageBalanceBounds {
     pub min: U128,
@@ -59,3 +70,140 @@ pub trait StorageManagement {
 
     fn storage_balance_of(&self, account_id: AccountId) -> Option<StorageBalance>;
 }
+
+/// Storage used by a single operation, in bytes and in yoctoNEAR at the current storage price.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StorageRequirement {
+    pub bytes: U64,
+    pub cost: U128,
+}
+
+impl StorageRequirement {
+    pub fn new(bytes: StorageUsage) -> Self {
+        Self { bytes: bytes.into(), cost: (Balance::from(bytes) * env::storage_byte_cost()).into() }
+    }
+}
+
+/// Storage required by the operations of a contract, keyed by the method name of the operation.
+/// This allows frontends to know how much deposit to attach before calling a method.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StorageRequirements {
+    /// Length of the account IDs the requirements were measured with.
+    pub max_account_id_len: u64,
+    pub operations: BTreeMap<String, StorageRequirement>,
+}
+
+impl Default for StorageRequirements {
+    fn default() -> Self {
+        Self { max_account_id_len: MAX_ACCOUNT_ID_LEN as u64, operations: BTreeMap::new() }
+    }
+}
+
+impl StorageRequirements {
+    /// Adds the storage used by the method `operation`.
+    pub fn with(mut self, operation: &str, bytes: StorageUsage) -> Self {
+        self.operations.insert(operation.to_string(), StorageRequirement::new(bytes));
+        self
+    }
+}
+
+pub trait StorageRequirementsView {
+    /// Returns the storage each operation requires to be paid for by the caller.
+    fn storage_requirements(&self) -> StorageRequirements;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fungible_token::FungibleToken;
+    use crate::non_fungible_token::approval::NonFungibleTokenApproval;
+    use crate::non_fungible_token::metadata::TokenMetadata;
+    use crate::non_fungible_token::NonFungibleToken;
+    use near_sdk::json_types::Base64VecU8;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::{testing_env, ONE_NEAR};
+
+    fn max_len_account(c: char) -> AccountId {
+        AccountId::new_unchecked(c.to_string().repeat(MAX_ACCOUNT_ID_LEN))
+    }
+
+    fn metadata() -> TokenMetadata {
+        TokenMetadata {
+            title: Some("t".repeat(64)),
+            description: Some("d".repeat(64)),
+            media: Some("m".repeat(64)),
+            media_hash: Some(Base64VecU8::from("h".repeat(64).into_bytes())),
+            copies: Some(1),
+            issued_at: None,
+            expires_at: None,
+            starts_at: None,
+            updated_at: None,
+            extra: None,
+            reference: None,
+            reference_hash: None,
+        }
+    }
+
+    fn bytes(requirements: &StorageRequirements, operation: &str) -> u64 {
+        requirements.operations[operation].bytes.0
+    }
+
+    #[test]
+    fn ft_registration_matches_measured() {
+        let mut token = FungibleToken::new(b"a");
+        let requirements = token.storage_requirements();
+        assert_eq!(requirements.max_account_id_len, MAX_ACCOUNT_ID_LEN as u64);
+        assert_eq!(
+            requirements.operations["storage_deposit"].cost.0,
+            Balance::from(bytes(&requirements, "storage_deposit")) * env::storage_byte_cost()
+        );
+
+        let before = env::storage_usage();
+        token.internal_register_account(&max_len_account('b'));
+        assert_eq!(env::storage_usage() - before, bytes(&requirements, "storage_deposit"));
+    }
+
+    #[test]
+    fn nft_requirements_match_measured() {
+        let owner_id = max_len_account('o');
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(owner_id.clone())
+            .attached_deposit(ONE_NEAR)
+            .build());
+
+        let mut token = NonFungibleToken::new(
+            b"o".to_vec(),
+            owner_id.clone(),
+            Some(b"m".to_vec()),
+            Some(b"e".to_vec()),
+            None::<Vec<u8>>,
+        );
+        let requirements = token.storage_requirements();
+        assert!(!requirements.operations.contains_key("nft_approve"));
+
+        let before = env::storage_usage();
+        token.internal_mint_with_refund("t".repeat(64), owner_id.clone(), Some(metadata()), None);
+        let used = env::storage_usage() - before;
+        let expected = bytes(&requirements, "nft_mint");
+        // The measurement stores a slightly shorter prefix for the owner's token set.
+        assert!(used.max(expected) - used.min(expected) <= 16, "used {} vs {}", used, expected);
+
+        let mut token = NonFungibleToken::new(
+            b"O".to_vec(),
+            owner_id.clone(),
+            Some(b"M".to_vec()),
+            Some(b"E".to_vec()),
+            Some(b"A".to_vec()),
+        );
+        let requirements = token.storage_requirements();
+        let token_id = "T".repeat(64);
+        token.internal_mint_with_refund(token_id.clone(), owner_id, Some(metadata()), None);
+        token.nft_approve(token_id.clone(), "first.near".parse().unwrap(), None);
+
+        let before = env::storage_usage();
+        token.nft_approve(token_id, max_len_account('a'), None);
+        assert_eq!(env::storage_usage() - before, bytes(&requirements, "nft_approve"));
+    }
+}
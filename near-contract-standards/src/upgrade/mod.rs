@@ -2,6 +2,9 @@ use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::json_types::U64;
 use near_sdk::{env, require, AccountId, Duration, Promise, Timestamp};
 
+mod staged;
+pub use self::staged::*;
+
 type WrappedDuration = U64;
 
 pub trait Ownable {
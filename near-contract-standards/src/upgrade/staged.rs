@@ -0,0 +1,372 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::LazyOption;
+use near_sdk::{
+    env, require, CryptoHash, Duration, Gas, IntoStorageKey, Promise, PromiseResult, Timestamp,
+};
+
+/// Gas attached to the `up_on_deploy_code` callback.
+const GAS_FOR_ON_DEPLOY_CODE: Gas = Gas(5_000_000_000_000);
+
+/// The hash of the staged code and when it was staged.
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone, PartialEq)]
+pub struct StagedCode {
+    pub hash: CryptoHash,
+    pub staged_at: Timestamp,
+    /// Whether the code is being deployed, until the deployment is confirmed or fails.
+    pub deploying: bool,
+}
+
+/// Method called on the contract right after its new code is deployed, in the same receipt, to
+/// migrate its state.
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone, PartialEq)]
+pub struct Migration {
+    pub method_name: String,
+    pub args: Vec<u8>,
+    pub gas: Gas,
+}
+
+/// Upgrades of a contract in two steps: the owner stages the new code, which anyone can deploy
+/// once the timelock has elapsed, giving the users of the contract time to review it.
+///
+/// The code is kept under its own prefix, and only read when it's deployed. Staging code again
+/// replaces the staged code and restarts the timelock.
+///
+/// The staged code is only removed once the `up_on_deploy_code` callback confirms the deployment,
+/// so a failed migration, which reverts the deployment, leaves the code staged to be deployed
+/// again. The callback runs on the new code if the deployment succeeded, so the new code must
+/// expose it too, for example with [`impl_upgradable!`](crate::impl_upgradable).
+///
+/// # Examples
+/// ```
+/// use near_contract_standards::upgrade::StagedUpgrade;
+/// use near_sdk::Gas;
+///
+/// // A day, in nanoseconds.
+/// let timelock = 24 * 60 * 60 * 1_000_000_000;
+/// let upgrade = StagedUpgrade::new(b"u", timelock).with_migration(
+///     "migrate",
+///     b"{\"version\":2}".to_vec(),
+///     Gas(50_000_000_000_000),
+/// );
+/// assert_eq!(upgrade.staged_code_hash(), None);
+/// ```
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct StagedUpgrade {
+    code: LazyOption<Vec<u8>>,
+    staged: LazyOption<StagedCode>,
+    /// Minimum time between staging the code and deploying it, in nanoseconds.
+    pub timelock: Duration,
+    /// Migration called after the code is deployed, if any.
+    pub migration: Option<Migration>,
+}
+
+impl StagedUpgrade {
+    pub fn new<S>(prefix: S, timelock: Duration) -> Self
+    where
+        S: IntoStorageKey,
+    {
+        let prefix = prefix.into_storage_key();
+        Self {
+            code: LazyOption::new([prefix.as_slice(), b"c"].concat(), None),
+            staged: LazyOption::new([prefix.as_slice(), b"s"].concat(), None),
+            timelock,
+            migration: None,
+        }
+    }
+
+    /// Calls `method_name` of the contract with `args` right after deploying its new code.
+    pub fn with_migration(mut self, method_name: &str, args: Vec<u8>, gas: Gas) -> Self {
+        self.migration = Some(Migration { method_name: method_name.to_string(), args, gas });
+        self
+    }
+
+    /// Returns the hash and staging time of the staged code, if any.
+    pub fn staged_code(&self) -> Option<StagedCode> {
+        self.staged.get()
+    }
+
+    /// Returns the sha256 hash of the staged code, if any.
+    pub fn staged_code_hash(&self) -> Option<CryptoHash> {
+        self.staged.get().map(|staged| staged.hash)
+    }
+
+    /// Returns the earliest timestamp at which the staged code can be deployed, if any. A
+    /// timelock past the last representable timestamp saturates at it.
+    pub fn deployable_at(&self) -> Option<Timestamp> {
+        self.staged.get().map(|staged| staged.staged_at.saturating_add(self.timelock))
+    }
+
+    /// Stages `code`, replacing the staged code. The caller is responsible for checking that the
+    /// predecessor can stage code. Panics if the staged code is being deployed.
+    pub fn stage_code(&mut self, code: Vec<u8>) {
+        require!(!code.is_empty(), "The staged code can't be empty");
+        require!(
+            !self.staged.get().map_or(false, |staged| staged.deploying),
+            "The staged code is being deployed"
+        );
+        let staged = StagedCode {
+            hash: env::sha256_array(&code),
+            staged_at: env::block_timestamp(),
+            deploying: false,
+        };
+        self.code.set(&code);
+        self.staged.set(&staged);
+    }
+
+    /// Deploys the staged code to the contract, followed by the migration if any, and then calls
+    /// `up_on_deploy_code` on the contract. Panics if no code is staged, the timelock hasn't
+    /// elapsed or the code is already being deployed.
+    pub fn deploy_code(&mut self) -> Promise {
+        let staged = self.staged.get().unwrap_or_else(|| env::panic_str("No code is staged"));
+        require!(!staged.deploying, "The staged code is already being deployed");
+        let deployable_at = staged.staged_at.saturating_add(self.timelock);
+        if env::block_timestamp() < deployable_at {
+            env::panic_str(&format!("The staged code can't be deployed before {}", deployable_at));
+        }
+        // The code is staged along with its hash, and kept until the deployment is confirmed.
+        let code = self.code.get().unwrap();
+        self.staged.set(&StagedCode { deploying: true, ..staged });
+        let promise = Promise::new(env::current_account_id()).deploy_contract(code);
+        let promise = match &self.migration {
+            Some(Migration { method_name, args, gas }) => {
+                promise.function_call(method_name.clone(), args.clone(), 0, *gas)
+            }
+            None => promise,
+        };
+        promise.then(Promise::new(env::current_account_id()).function_call(
+            "up_on_deploy_code".to_string(),
+            vec![],
+            0,
+            GAS_FOR_ON_DEPLOY_CODE,
+        ))
+    }
+
+    /// Handles the result of [`deploy_code`](Self::deploy_code): removes the staged code if it
+    /// was deployed and migrated, or keeps it staged to be deployed again otherwise. Returns
+    /// whether the code was deployed.
+    pub fn on_deploy_code(&mut self) -> bool {
+        match env::promise_result(0) {
+            PromiseResult::NotReady => env::abort(),
+            PromiseResult::Successful(_) => {
+                self.code.remove();
+                self.staged.remove();
+                true
+            }
+            PromiseResult::Failed => {
+                if let Some(staged) = self.staged.get() {
+                    self.staged.set(&StagedCode { deploying: false, ..staged });
+                }
+                false
+            }
+        }
+    }
+}
+
+/// Exposes the staged upgrades of the contract: `up_stage_code`, which only the owner can call,
+/// `up_staged_code_hash`, `up_deployable_at` and `up_deploy_code`, which anyone can call once the
+/// timelock has elapsed. The code is passed to `up_stage_code` with Borsh, as raw bytes. The
+/// private `up_on_deploy_code` callback removes the staged code once it's deployed.
+///
+/// The owner is checked by the `assert_owner` method of the contract, like the one of
+/// [`impl_ownable!`](crate::impl_ownable).
+///
+/// Takes the name of the contract struct and the inner field for the [`StagedUpgrade`].
+#[macro_export]
+macro_rules! impl_upgradable {
+    ($contract: ident, $upgrade: ident) => {
+        $crate::__token_field!(
+            $contract.$upgrade: $crate::upgrade::StagedUpgrade,
+            __upgrade,
+            __upgrade_mut
+        );
+
+        #[near_bindgen]
+        impl $contract {
+            pub fn up_stage_code(&mut self, #[serializer(borsh)] code: Vec<u8>) {
+                self.assert_owner();
+                self.__upgrade_mut().stage_code(code)
+            }
+
+            pub fn up_staged_code_hash(&self) -> Option<near_sdk::json_types::Base58CryptoHash> {
+                self.__upgrade().staged_code_hash().map(Into::into)
+            }
+
+            pub fn up_deployable_at(&self) -> Option<near_sdk::json_types::U64> {
+                self.__upgrade().deployable_at().map(Into::into)
+            }
+
+            pub fn up_deploy_code(&mut self) -> Promise {
+                self.__upgrade_mut().deploy_code()
+            }
+
+            #[private]
+            pub fn up_on_deploy_code(&mut self) -> bool {
+                self.__upgrade_mut().on_deploy_code()
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::mock::VmAction;
+    use near_sdk::test_utils::{accounts, get_created_receipts, VMContextBuilder};
+    use near_sdk::{testing_env, RuntimeFeesConfig, VMConfig};
+
+    const TIMELOCK: Duration = 1_000;
+    const MIGRATE_GAS: Gas = Gas(50_000_000_000_000);
+
+    fn set_block_timestamp(block_timestamp: Timestamp) {
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(accounts(0))
+            .block_timestamp(block_timestamp)
+            .build());
+    }
+
+    /// Sets the context of the `up_on_deploy_code` callback, with the result of the deployment.
+    fn set_deploy_result(result: PromiseResult) {
+        testing_env!(
+            VMContextBuilder::new()
+                .current_account_id(accounts(0))
+                .predecessor_account_id(accounts(0))
+                .block_timestamp(2_000)
+                .build(),
+            VMConfig::test(),
+            RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![result],
+        );
+    }
+
+    fn staged(code: &[u8]) -> StagedUpgrade {
+        set_block_timestamp(100);
+        let mut upgrade = StagedUpgrade::new(b"u".to_vec(), TIMELOCK);
+        upgrade.stage_code(code.to_vec());
+        upgrade
+    }
+
+    #[test]
+    fn reports_staged_code_hash() {
+        let mut upgrade = staged(b"v2");
+        assert_eq!(upgrade.staged_code_hash(), Some(env::sha256_array(b"v2")));
+        assert_eq!(upgrade.deployable_at(), Some(1_100));
+
+        set_block_timestamp(500);
+        upgrade.stage_code(b"v3".to_vec());
+        assert_eq!(
+            upgrade.staged_code(),
+            Some(StagedCode { hash: env::sha256_array(b"v3"), staged_at: 500, deploying: false })
+        );
+        assert_eq!(upgrade.deployable_at(), Some(1_500));
+    }
+
+    #[test]
+    fn deployable_at_saturates() {
+        let mut upgrade = staged(b"v2");
+        upgrade.timelock = Duration::MAX;
+        assert_eq!(upgrade.deployable_at(), Some(Timestamp::MAX));
+    }
+
+    #[test]
+    #[should_panic(expected = "The staged code can't be deployed before 1100")]
+    fn premature_deploy() {
+        let mut upgrade = staged(b"v2");
+        set_block_timestamp(1_099);
+        upgrade.deploy_code();
+    }
+
+    #[test]
+    #[should_panic(expected = "No code is staged")]
+    fn deploy_without_code() {
+        set_block_timestamp(100);
+        StagedUpgrade::new(b"u".to_vec(), TIMELOCK).deploy_code();
+    }
+
+    #[test]
+    fn deploy_keeps_code_until_confirmed() {
+        let mut upgrade = staged(b"v2");
+        set_block_timestamp(1_100);
+        drop(upgrade.deploy_code());
+        let receipts = get_created_receipts();
+        assert_eq!(receipts.len(), 2);
+        assert_eq!(receipts[0].receiver_id, accounts(0));
+        assert_eq!(receipts[0].actions, [VmAction::DeployContract { code: b"v2".to_vec() }]);
+        assert_eq!(receipts[1].receiver_id, accounts(0));
+        assert_eq!(
+            receipts[1].actions,
+            [VmAction::FunctionCall {
+                function_name: "up_on_deploy_code".to_string(),
+                args: vec![],
+                gas: GAS_FOR_ON_DEPLOY_CODE,
+                deposit: 0,
+            }]
+        );
+        assert!(upgrade.staged_code().unwrap().deploying);
+
+        set_deploy_result(PromiseResult::Successful(vec![]));
+        assert!(upgrade.on_deploy_code());
+        assert_eq!(upgrade.staged_code_hash(), None);
+        assert_eq!(upgrade.deployable_at(), None);
+    }
+
+    #[test]
+    fn failed_deploy_keeps_code() {
+        let mut upgrade = staged(b"v2");
+        set_block_timestamp(1_100);
+        drop(upgrade.deploy_code());
+
+        set_deploy_result(PromiseResult::Failed);
+        assert!(!upgrade.on_deploy_code());
+        assert_eq!(
+            upgrade.staged_code(),
+            Some(StagedCode { hash: env::sha256_array(b"v2"), staged_at: 100, deploying: false })
+        );
+
+        // The code can be deployed again.
+        set_block_timestamp(2_000);
+        drop(upgrade.deploy_code());
+        assert_eq!(
+            get_created_receipts()[0].actions,
+            [VmAction::DeployContract { code: b"v2".to_vec() }]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "The staged code is already being deployed")]
+    fn deploy_twice() {
+        let mut upgrade = staged(b"v2");
+        set_block_timestamp(1_100);
+        drop(upgrade.deploy_code());
+        upgrade.deploy_code();
+    }
+
+    #[test]
+    #[should_panic(expected = "The staged code is being deployed")]
+    fn stage_while_deploying() {
+        let mut upgrade = staged(b"v2");
+        set_block_timestamp(1_100);
+        drop(upgrade.deploy_code());
+        upgrade.stage_code(b"v3".to_vec());
+    }
+
+    #[test]
+    fn deploy_with_migration() {
+        let mut upgrade =
+            staged(b"v2").with_migration("migrate", b"{\"from\":1}".to_vec(), MIGRATE_GAS);
+        set_block_timestamp(2_000);
+        drop(upgrade.deploy_code());
+        assert_eq!(
+            get_created_receipts()[0].actions,
+            [
+                VmAction::DeployContract { code: b"v2".to_vec() },
+                VmAction::FunctionCall {
+                    function_name: "migrate".to_string(),
+                    args: b"{\"from\":1}".to_vec(),
+                    gas: MIGRATE_GAS,
+                    deposit: 0,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,161 @@
+use near_sdk::utils::DepositSplitter;
+use near_sdk::{env, AccountId, Balance, Promise};
+
+/// Pays for `storage_used` bytes from the attached deposit and refunds the rest to `account_id`.
+/// The 1 yoctoNEAR required to call a method is kept rather than refunded.
+///
+/// # Panics
+///
+/// Panics if the attached deposit doesn't cover the storage, with a message stating the
+/// shortfall.
+pub fn refund_deposit_to_account(storage_used: u64, account_id: AccountId) {
+    let mut deposit = DepositSplitter::new();
+    deposit.take_storage_cost(storage_used);
+
+    // Keep the 1 yoctoNEAR required to call the method rather than refunding it.
+    if deposit.remaining() > 1 {
+        deposit.refund_remainder(&account_id);
+    } else {
+        deposit.keep_remainder();
+    }
+}
+
+/// Assumes that the precedecessor will be refunded
+pub fn refund_deposit(storage_used: u64) {
+    refund_deposit_to_account(storage_used, env::predecessor_account_id())
+}
+
+/// Like [`refund_deposit_to_account`], for a change of storage measured by [`measure_storage`],
+/// which is negative when storage was freed. The cost of the freed bytes is then refunded along
+/// with the attached deposit.
+pub fn refund_storage_delta_to_account(storage_delta: i64, account_id: AccountId) {
+    if storage_delta >= 0 {
+        return refund_deposit_to_account(storage_delta as u64, account_id);
+    }
+    let freed = Balance::from(storage_delta.unsigned_abs()) * env::storage_byte_cost();
+    let attached = env::attached_deposit();
+    // Keep the 1 yoctoNEAR required to call the method rather than refunding it.
+    let refund = if attached > 1 { attached + freed } else { freed };
+    Promise::new(account_id).transfer(refund);
+}
+
+/// Like [`refund_storage_delta_to_account`], refunding the predecessor.
+pub fn refund_storage_delta(storage_delta: i64) {
+    refund_storage_delta_to_account(storage_delta, env::predecessor_account_id())
+}
+
+/// Calls `f` and returns its result with the number of bytes by which it changed the storage
+/// usage of the contract, negative if it freed storage.
+///
+/// # Examples
+///
+/// ```
+/// use near_contract_standards::utils::{measure_storage, refund_storage_delta};
+/// use near_sdk::collections::LookupMap;
+/// use near_sdk::test_utils::VMContextBuilder;
+/// use near_sdk::{testing_env, ONE_NEAR};
+///
+/// testing_env!(VMContextBuilder::new().attached_deposit(ONE_NEAR).build());
+/// let mut balances = LookupMap::new(b"b");
+/// let (_, storage_delta) = measure_storage(|| balances.insert(&"alice".to_string(), &1u128));
+/// assert!(storage_delta > 0);
+/// refund_storage_delta(storage_delta);
+/// ```
+pub fn measure_storage<R>(f: impl FnOnce() -> R) -> (R, i64) {
+    let initial_storage_usage = env::storage_usage();
+    let result = f();
+    (result, env::storage_usage() as i64 - initial_storage_usage as i64)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::mock::VmAction;
+    use near_sdk::test_utils::{accounts, get_created_receipts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    fn attach(deposit: Balance) {
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(deposit)
+            .build());
+    }
+
+    /// Returns the receivers and amounts of the transfers made in this context.
+    fn refunds() -> Vec<(AccountId, Balance)> {
+        get_created_receipts()
+            .into_iter()
+            .flat_map(|receipt| {
+                let receiver_id = receipt.receiver_id;
+                receipt.actions.into_iter().filter_map(move |action| match action {
+                    VmAction::Transfer { deposit } => Some((receiver_id.clone(), deposit)),
+                    _ => None,
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn refund_excess() {
+        let cost = 100 * env::storage_byte_cost();
+        attach(cost + 1_000);
+        refund_deposit(100);
+        assert_eq!(refunds(), [(accounts(1), 1_000)]);
+
+        attach(cost + 1_000);
+        refund_deposit_to_account(100, accounts(2));
+        assert_eq!(refunds(), [(accounts(2), 1_000)]);
+    }
+
+    #[test]
+    fn keeps_one_yocto() {
+        attach(100 * env::storage_byte_cost() + 1);
+        refund_deposit(100);
+        assert!(refunds().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "to cover storage, the attached deposit is short by")]
+    fn insufficient_deposit() {
+        attach(100 * env::storage_byte_cost() - 1);
+        refund_deposit(100);
+    }
+
+    #[test]
+    fn measure_increase_and_decrease() {
+        attach(0);
+        let (_, delta) = measure_storage(|| env::storage_write(b"key", b"value"));
+        assert!(delta > 0);
+        let (removed, freed) = measure_storage(|| env::storage_remove(b"key"));
+        assert!(removed);
+        assert_eq!(freed, -delta);
+    }
+
+    #[test]
+    fn refund_storage_delta_increase() {
+        attach(100 * env::storage_byte_cost() + 1_000);
+        refund_storage_delta(100);
+        assert_eq!(refunds(), [(accounts(1), 1_000)]);
+    }
+
+    #[test]
+    fn refund_storage_delta_decrease() {
+        let freed = 100 * env::storage_byte_cost();
+        attach(1_000);
+        refund_storage_delta(-100);
+        assert_eq!(refunds(), [(accounts(1), freed + 1_000)]);
+
+        // The 1 yoctoNEAR attached to call the method is kept.
+        attach(1);
+        refund_storage_delta_to_account(-100, accounts(2));
+        assert_eq!(refunds(), [(accounts(2), freed)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "to cover storage, the attached deposit is short by")]
+    fn refund_storage_delta_insufficient_deposit() {
+        attach(0);
+        refund_storage_delta(1);
+    }
+}
@@ -0,0 +1,281 @@
+//! `AccountStorage` used on its own by a registry of names, paying for each name from the storage
+//! balance of its account.
+
+use near_contract_standards::storage_management::{StorageBalance, StorageManagement};
+use near_sdk::json_types::U128;
+use near_sdk::mock::VmAction;
+use near_sdk::test_utils::{accounts, get_created_receipts, get_logs, VMContextBuilder};
+use near_sdk::{env, testing_env, AccountId, Balance, ONE_YOCTO};
+
+mod registry {
+    use near_contract_standards::storage_management::{
+        AccountStorage, AccountStorageHook, StorageBalance, StorageBalanceBounds, StorageManagement,
+    };
+    use near_contract_standards::utils::measure_storage;
+    use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+    use near_sdk::collections::LookupMap;
+    use near_sdk::json_types::U128;
+    use near_sdk::{env, near_bindgen, AccountId, Balance, PanicOnDefault};
+
+    /// The name of each registered account.
+    #[derive(BorshDeserialize, BorshSerialize)]
+    pub struct Names(pub LookupMap<AccountId, String>);
+
+    impl AccountStorageHook for Names {
+        fn on_account_closed(&mut self, account_id: &AccountId) {
+            if self.0.contains_key(account_id) {
+                env::panic_str("Can't unregister the account with a name without force");
+            }
+        }
+
+        fn on_account_force_closed(&mut self, account_id: &AccountId) {
+            self.0.remove(account_id);
+        }
+    }
+
+    #[near_bindgen]
+    #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+    pub struct Registry {
+        pub storage: AccountStorage,
+        pub names: Names,
+    }
+
+    #[near_bindgen]
+    impl Registry {
+        #[init]
+        pub fn new(min: U128, max: Option<U128>) -> Self {
+            Self {
+                storage: AccountStorage::new(b"s".to_vec(), StorageBalanceBounds { min, max }),
+                names: Names(LookupMap::new(b"n".to_vec())),
+            }
+        }
+
+        /// Sets the name of the caller, paid for by its available storage balance.
+        pub fn set_name(&mut self, name: String) {
+            let account_id = env::predecessor_account_id();
+            let names = &mut self.names.0;
+            let ((), storage_delta) = measure_storage(|| {
+                names.insert(&account_id, &name);
+            });
+            let cost = Balance::from(storage_delta.unsigned_abs()) * env::storage_byte_cost();
+            if storage_delta > 0 {
+                self.storage.internal_lock(&account_id, cost);
+            } else {
+                self.storage.internal_unlock(&account_id, cost);
+            }
+        }
+
+        pub fn name_of(&self, account_id: AccountId) -> Option<String> {
+            self.names.0.get(&account_id)
+        }
+    }
+
+    #[near_bindgen]
+    impl StorageManagement for Registry {
+        #[payable]
+        fn storage_deposit(
+            &mut self,
+            account_id: Option<AccountId>,
+            registration_only: Option<bool>,
+        ) -> StorageBalance {
+            self.storage.storage_deposit_with_hook(&mut self.names, account_id, registration_only)
+        }
+
+        #[payable]
+        fn storage_withdraw(&mut self, amount: Option<U128>) -> StorageBalance {
+            self.storage.storage_withdraw(amount)
+        }
+
+        #[payable]
+        fn storage_unregister(&mut self, force: Option<bool>) -> bool {
+            self.storage.storage_unregister_with_hook(&mut self.names, force)
+        }
+
+        fn storage_balance_bounds(&self) -> StorageBalanceBounds {
+            self.storage.storage_balance_bounds()
+        }
+
+        fn storage_balance_of(&self, account_id: AccountId) -> Option<StorageBalance> {
+            self.storage.storage_balance_of(account_id)
+        }
+    }
+}
+
+use registry::Registry;
+
+const MIN: Balance = 1_000;
+const MAX: Balance = 10u128.pow(24);
+
+fn set_context(predecessor: AccountId, attached_deposit: Balance) {
+    testing_env!(VMContextBuilder::new()
+        .current_account_id(accounts(0))
+        .predecessor_account_id(predecessor)
+        .attached_deposit(attached_deposit)
+        .build());
+}
+
+fn registry() -> Registry {
+    set_context(accounts(0), 0);
+    Registry::new(U128(MIN), Some(U128(MAX)))
+}
+
+fn balance(total: Balance, available: Balance) -> StorageBalance {
+    StorageBalance { total: U128(total), available: U128(available) }
+}
+
+/// Returns the amounts transferred by the receipts created by the last call, with their receiver.
+fn transfers() -> Vec<(AccountId, Balance)> {
+    get_created_receipts()
+        .into_iter()
+        .flat_map(|receipt| {
+            let receiver_id = receipt.receiver_id;
+            receipt.actions.into_iter().filter_map(move |action| match action {
+                VmAction::Transfer { deposit } => Some((receiver_id.clone(), deposit)),
+                _ => None,
+            })
+        })
+        .collect()
+}
+
+#[test]
+fn deposit_registers_and_tops_up() {
+    let mut registry = registry();
+    set_context(accounts(1), 5_000);
+    assert_eq!(registry.storage_deposit(None, None), balance(5_000, 4_000));
+    assert!(transfers().is_empty());
+
+    // Deposits for another account are refunded to the caller above the maximum.
+    set_context(accounts(2), MAX);
+    assert_eq!(registry.storage_deposit(Some(accounts(1)), None), balance(MAX, MAX - MIN));
+    assert_eq!(transfers(), [(accounts(2), 5_000)]);
+
+    set_context(accounts(1), 10);
+    assert_eq!(registry.storage_deposit(None, None), balance(MAX, MAX - MIN));
+    assert_eq!(transfers(), [(accounts(1), 10)]);
+    assert_eq!(get_logs(), ["The account is already registered, refunding the deposit"]);
+}
+
+#[test]
+fn registration_only_refunds_above_minimum() {
+    let mut registry = registry();
+    set_context(accounts(1), 5_000);
+    assert_eq!(registry.storage_deposit(None, Some(true)), balance(MIN, 0));
+    assert_eq!(transfers(), [(accounts(1), 4_000)]);
+
+    set_context(accounts(1), 5_000);
+    assert_eq!(registry.storage_deposit(None, Some(true)), balance(MIN, 0));
+    assert_eq!(transfers(), [(accounts(1), 5_000)]);
+}
+
+#[test]
+#[should_panic(expected = "The attached deposit is less than the minimum storage balance")]
+fn deposit_below_minimum() {
+    let mut registry = registry();
+    set_context(accounts(1), MIN - 1);
+    registry.storage_deposit(None, None);
+}
+
+#[test]
+fn names_lock_storage_balance() {
+    let mut registry = registry();
+    set_context(accounts(1), MAX);
+    registry.storage_deposit(None, None);
+
+    let before = env::storage_usage();
+    registry.set_name("alice".to_string());
+    let cost = Balance::from(env::storage_usage() - before) * env::storage_byte_cost();
+    assert_eq!(registry.name_of(accounts(1)), Some("alice".to_string()));
+    assert_eq!(registry.storage_balance_of(accounts(1)), Some(balance(MAX, MAX - MIN - cost)));
+
+    // Only the available balance can be withdrawn.
+    set_context(accounts(1), ONE_YOCTO);
+    assert_eq!(
+        registry.storage_withdraw(Some(U128(100))),
+        balance(MAX - 100, MAX - MIN - cost - 100)
+    );
+    assert_eq!(transfers(), [(accounts(1), 100)]);
+    set_context(accounts(1), ONE_YOCTO);
+    assert_eq!(registry.storage_withdraw(None), balance(MIN + cost, 0));
+    assert_eq!(transfers(), [(accounts(1), MAX - MIN - cost - 100)]);
+}
+
+#[test]
+#[should_panic(expected = "The account doesn't have enough storage balance")]
+fn name_over_available_balance() {
+    let mut registry = registry();
+    set_context(accounts(1), MIN);
+    registry.storage_deposit(None, None);
+    registry.set_name("alice".to_string());
+}
+
+#[test]
+fn withdraw_exactly_available_balance() {
+    let mut registry = registry();
+    set_context(accounts(1), 5_000);
+    registry.storage_deposit(None, None);
+    set_context(accounts(1), ONE_YOCTO);
+    assert_eq!(registry.storage_withdraw(Some(U128(4_000))), balance(MIN, 0));
+    assert_eq!(transfers(), [(accounts(1), 4_000)]);
+    assert_eq!(registry.storage_balance_of(accounts(1)), Some(balance(MIN, 0)));
+
+    // Nothing is left to withdraw.
+    set_context(accounts(1), ONE_YOCTO);
+    assert_eq!(registry.storage_withdraw(None), balance(MIN, 0));
+    assert!(transfers().is_empty());
+}
+
+#[test]
+#[should_panic(expected = "The account bob is not registered")]
+fn withdraw_without_deposit() {
+    let mut registry = registry();
+    set_context(accounts(1), ONE_YOCTO);
+    registry.storage_withdraw(None);
+}
+
+#[test]
+#[should_panic(expected = "The amount is greater than the available storage balance of 4000")]
+fn withdraw_over_available_balance() {
+    let mut registry = registry();
+    set_context(accounts(1), 5_000);
+    registry.storage_deposit(None, None);
+    set_context(accounts(1), ONE_YOCTO);
+    registry.storage_withdraw(Some(U128(4_001)));
+}
+
+#[test]
+#[should_panic(expected = "Can't unregister the account with a name without force")]
+fn unregister_with_name() {
+    let mut registry = registry();
+    set_context(accounts(1), MAX);
+    registry.storage_deposit(None, None);
+    registry.set_name("alice".to_string());
+    set_context(accounts(1), ONE_YOCTO);
+    registry.storage_unregister(None);
+}
+
+#[test]
+fn unregister_refunds_storage_balance() {
+    let mut registry = registry();
+    set_context(accounts(1), 5_000);
+    registry.storage_deposit(None, None);
+    set_context(accounts(1), ONE_YOCTO);
+    assert!(registry.storage_unregister(None));
+    assert_eq!(transfers(), [(accounts(1), 5_000 + ONE_YOCTO)]);
+    assert_eq!(registry.storage_balance_of(accounts(1)), None);
+
+    set_context(accounts(1), ONE_YOCTO);
+    assert!(!registry.storage_unregister(None));
+    assert_eq!(get_logs(), [format!("The account {} is not registered", accounts(1))]);
+}
+
+#[test]
+fn force_unregister_removes_name() {
+    let mut registry = registry();
+    set_context(accounts(1), MAX);
+    registry.storage_deposit(None, None);
+    registry.set_name("alice".to_string());
+    set_context(accounts(1), ONE_YOCTO);
+    assert!(registry.storage_unregister(Some(true)));
+    assert_eq!(registry.name_of(accounts(1)), None);
+    assert_eq!(transfers(), [(accounts(1), MAX + ONE_YOCTO)]);
+}
@@ -0,0 +1,128 @@
+//! Contracts using `impl_contract_source_metadata!`, with the metadata in a field or built when
+//! the contract is compiled.
+
+use near_contract_standards::contract_metadata::{
+    ContractSourceMetadata, ContractSourceMetadataTrait,
+};
+use near_contract_standards::ownable::Ownable;
+use near_contract_standards::standards::{NEP141, NEP145, NEP330};
+use near_sdk::collections::LazyOption;
+use near_sdk::test_utils::{accounts, VMContextBuilder};
+use near_sdk::{testing_env, AccountId};
+
+mod stored {
+    use near_contract_standards::contract_metadata::ContractSourceMetadata;
+    use near_contract_standards::ownable::Ownable;
+    use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+    use near_sdk::collections::LazyOption;
+    use near_sdk::{near_bindgen, AccountId, PanicOnDefault};
+
+    #[near_bindgen]
+    #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+    pub struct Contract {
+        pub ownable: Ownable,
+        pub metadata: LazyOption<ContractSourceMetadata>,
+    }
+
+    near_contract_standards::impl_ownable!(Contract, ownable);
+    near_contract_standards::impl_contract_source_metadata!(Contract, metadata);
+
+    impl Contract {
+        pub fn standards(&self) -> Vec<near_contract_standards::standards::Standard<'static>> {
+            near_contract_standards::standards_manifest!()
+        }
+    }
+}
+
+mod compiled {
+    use near_contract_standards::fungible_token::FungibleToken;
+    use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+    use near_sdk::json_types::U128;
+    use near_sdk::{near_bindgen, AccountId, PanicOnDefault, PromiseOrValue};
+
+    #[near_bindgen]
+    #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+    pub struct Contract {
+        pub token: FungibleToken,
+    }
+
+    near_contract_standards::impl_fungible_token_core!(Contract, token);
+    near_contract_standards::impl_fungible_token_storage!(Contract, token);
+    near_contract_standards::impl_contract_source_metadata!(
+        Contract,
+        const near_contract_standards::contract_source_metadata!("https://github.com/near/example")
+    );
+}
+
+fn set_predecessor(predecessor: AccountId) {
+    testing_env!(VMContextBuilder::new().predecessor_account_id(predecessor).build());
+}
+
+fn stored(metadata: Option<&ContractSourceMetadata>) -> stored::Contract {
+    set_predecessor(accounts(0));
+    stored::Contract {
+        ownable: Ownable::new(b"o".to_vec(), accounts(0)),
+        metadata: LazyOption::new(b"m".to_vec(), metadata),
+    }
+}
+
+fn metadata() -> ContractSourceMetadata {
+    ContractSourceMetadata {
+        version: Some("1.2.0".to_string()),
+        link: Some("https://github.com/near/example".to_string()),
+        standards: vec![NEP330.into()],
+    }
+}
+
+#[test]
+fn returns_stored_metadata() {
+    let contract = stored(Some(&metadata()));
+    assert_eq!(contract.contract_source_metadata(), metadata());
+    assert_eq!(contract.standards(), [NEP330]);
+}
+
+#[test]
+fn returns_default_until_set() {
+    let mut contract = stored(None);
+    assert_eq!(contract.contract_source_metadata(), ContractSourceMetadata::default());
+
+    contract.set_contract_source_metadata(metadata());
+    assert_eq!(contract.contract_source_metadata(), metadata());
+}
+
+#[test]
+#[should_panic(expected = "Only the owner can call this method")]
+fn only_owner_sets_metadata() {
+    let mut contract = stored(None);
+    set_predecessor(accounts(1));
+    contract.set_contract_source_metadata(metadata());
+}
+
+#[test]
+fn compiled_metadata_json() {
+    testing_env!(VMContextBuilder::new().build());
+    let contract = compiled::Contract {
+        token: near_contract_standards::fungible_token::FungibleToken::new(b"t".to_vec()),
+    };
+    let metadata = contract.contract_source_metadata();
+    assert_eq!(
+        metadata,
+        ContractSourceMetadata {
+            version: Some(env!("CARGO_PKG_VERSION").to_string()),
+            link: Some("https://github.com/near/example".to_string()),
+            standards: vec![NEP141.into(), NEP145.into(), NEP330.into()],
+        }
+    );
+    assert_eq!(
+        serde_json::to_value(&metadata).unwrap(),
+        serde_json::json!({
+            "version": env!("CARGO_PKG_VERSION"),
+            "link": "https://github.com/near/example",
+            "standards": [
+                { "standard": "nep141", "version": "1.0.0" },
+                { "standard": "nep145", "version": "1.0.0" },
+                { "standard": "nep330", "version": "1.0.0" },
+            ],
+        })
+    );
+}
@@ -0,0 +1,265 @@
+//! Contracts using the optional arguments of `impl_fungible_token_core!`.
+
+use near_contract_standards::fungible_token::core::FungibleTokenCore;
+use near_contract_standards::fungible_token::resolver::FungibleTokenResolver;
+use near_contract_standards::fungible_token::{FtTransferCallGas, FungibleToken};
+use near_sdk::json_types::U128;
+use near_sdk::mock::VmAction;
+use near_sdk::test_utils::{accounts, get_created_receipts, VMContextBuilder};
+use near_sdk::{
+    testing_env, AccountId, Gas, PromiseResult, RuntimeFeesConfig, VMConfig, ONE_YOCTO,
+};
+
+const CUSTOM_GAS: FtTransferCallGas =
+    FtTransferCallGas { resolve_transfer: Gas(20_000_000_000_000), ..FtTransferCallGas::DEFAULT };
+
+mod plain {
+    use near_contract_standards::fungible_token::FungibleToken;
+    use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+    use near_sdk::json_types::U128;
+    use near_sdk::{near_bindgen, AccountId, PanicOnDefault, PromiseOrValue};
+
+    #[near_bindgen]
+    #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+    pub struct Contract {
+        pub token: FungibleToken,
+    }
+
+    near_contract_standards::impl_fungible_token_core!(Contract, token);
+}
+
+mod hooked {
+    use near_contract_standards::fungible_token::FungibleToken;
+    use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+    use near_sdk::json_types::U128;
+    use near_sdk::{near_bindgen, require, AccountId, Balance, PanicOnDefault, PromiseOrValue};
+
+    #[near_bindgen]
+    #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+    pub struct Contract {
+        pub token: FungibleToken,
+        pub paused: bool,
+        pub transfers: Vec<(AccountId, AccountId, Balance)>,
+        pub resolved: Vec<(AccountId, AccountId, Balance)>,
+        pub burned: Vec<(AccountId, Balance)>,
+    }
+
+    impl Contract {
+        fn assert_not_paused(&self) {
+            require!(!self.paused, "Paused");
+        }
+
+        fn after_ft_transfer(
+            &mut self,
+            sender_id: AccountId,
+            receiver_id: AccountId,
+            amount: Balance,
+        ) {
+            self.transfers.push((sender_id, receiver_id, amount));
+        }
+
+        fn after_ft_resolve(
+            &mut self,
+            sender_id: AccountId,
+            receiver_id: AccountId,
+            used_amount: Balance,
+        ) {
+            self.resolved.push((sender_id, receiver_id, used_amount));
+        }
+
+        fn on_tokens_burned(&mut self, account_id: AccountId, amount: Balance) {
+            self.burned.push((account_id, amount));
+        }
+    }
+
+    near_contract_standards::impl_fungible_token_core!(
+        Contract,
+        token,
+        on_tokens_burned,
+        [guard = assert_not_paused],
+        [on_transfer = after_ft_transfer],
+        [on_resolve = after_ft_resolve],
+        [gas = super::CUSTOM_GAS]
+    );
+}
+
+mod guarded {
+    use near_contract_standards::fungible_token::FungibleToken;
+    use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+    use near_sdk::json_types::U128;
+    use near_sdk::{near_bindgen, require, AccountId, PanicOnDefault, PromiseOrValue};
+
+    #[near_bindgen]
+    #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+    pub struct Contract {
+        pub token: FungibleToken,
+        pub paused: bool,
+    }
+
+    impl Contract {
+        fn assert_not_paused(&self) {
+            require!(!self.paused, "Paused");
+        }
+    }
+
+    near_contract_standards::impl_fungible_token_core!(
+        Contract,
+        token,
+        [guard = assert_not_paused]
+    );
+}
+
+fn set_context(predecessor: AccountId) {
+    testing_env!(VMContextBuilder::new()
+        .current_account_id(accounts(0))
+        .predecessor_account_id(predecessor)
+        .attached_deposit(ONE_YOCTO)
+        .prepaid_gas(Gas(300 * Gas::ONE_TERA.0))
+        .build());
+}
+
+/// Sets the context of `ft_resolve_transfer`, in which `ft_on_transfer` returned `unused`.
+fn set_resolve_context(unused: u128) {
+    testing_env!(
+        VMContextBuilder::new()
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(0))
+            .build(),
+        VMConfig::test(),
+        RuntimeFeesConfig::test(),
+        Default::default(),
+        vec![PromiseResult::Successful(format!("\"{}\"", unused).into_bytes())],
+    );
+}
+
+fn token_with_balance(
+    account_id: &AccountId,
+    receiver_id: &AccountId,
+    balance: u128,
+) -> FungibleToken {
+    let mut token = FungibleToken::new(b"t".to_vec());
+    token.internal_register_account(account_id);
+    token.internal_register_account(receiver_id);
+    token.internal_deposit(account_id, balance);
+    token
+}
+
+fn hooked_contract() -> hooked::Contract {
+    set_context(accounts(1));
+    hooked::Contract {
+        token: token_with_balance(&accounts(1), &accounts(2), 1000),
+        paused: false,
+        transfers: vec![],
+        resolved: vec![],
+        burned: vec![],
+    }
+}
+
+fn msg() -> String {
+    String::new()
+}
+
+/// Returns the gas attached to the `ft_resolve_transfer` callback created by `ft_transfer_call`.
+fn resolve_transfer_gas() -> Gas {
+    get_created_receipts()
+        .into_iter()
+        .flat_map(|receipt| receipt.actions)
+        .find_map(|action| match action {
+            VmAction::FunctionCall { function_name, gas, .. }
+                if function_name == "ft_resolve_transfer" =>
+            {
+                Some(gas)
+            }
+            _ => None,
+        })
+        .expect("ft_resolve_transfer was not scheduled")
+}
+
+#[test]
+fn plain_uses_default_gas() {
+    set_context(accounts(1));
+    let mut contract =
+        plain::Contract { token: token_with_balance(&accounts(1), &accounts(2), 1000) };
+    contract.ft_transfer_call(accounts(2), U128(100), None, msg());
+    assert_eq!(resolve_transfer_gas(), FtTransferCallGas::DEFAULT.resolve_transfer);
+}
+
+#[test]
+fn gas_override() {
+    let mut contract = hooked_contract();
+    contract.ft_transfer_call(accounts(2), U128(100), None, msg());
+    assert_eq!(resolve_transfer_gas(), CUSTOM_GAS.resolve_transfer);
+}
+
+#[test]
+#[should_panic(expected = "Paused")]
+fn guard_rejects_transfer() {
+    let mut contract = hooked_contract();
+    contract.paused = true;
+    contract.ft_transfer(accounts(2), U128(100), None);
+}
+
+#[test]
+#[should_panic(expected = "Paused")]
+fn guard_rejects_transfer_call() {
+    let mut contract = hooked_contract();
+    contract.paused = true;
+    contract.ft_transfer_call(accounts(2), U128(100), None, msg());
+}
+
+#[test]
+fn guard_without_other_options() {
+    set_context(accounts(1));
+    let mut contract = guarded::Contract {
+        token: token_with_balance(&accounts(1), &accounts(2), 1000),
+        paused: false,
+    };
+    contract.ft_transfer(accounts(2), U128(100), None);
+    assert_eq!(contract.ft_balance_of(accounts(2)), U128(100));
+
+    contract.paused = true;
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        contract.ft_transfer(accounts(2), U128(100), None)
+    }));
+    assert!(result.is_err());
+}
+
+#[test]
+fn on_transfer_hook() {
+    let mut contract = hooked_contract();
+    contract.ft_transfer(accounts(2), U128(100), None);
+    contract.ft_transfer_call(accounts(2), U128(50), None, msg());
+    assert_eq!(
+        contract.transfers,
+        vec![(accounts(1), accounts(2), 100), (accounts(1), accounts(2), 50)]
+    );
+    assert_eq!(contract.ft_balance_of(accounts(2)), U128(150));
+}
+
+#[test]
+fn on_resolve_hook_receives_used_amount() {
+    let mut contract = hooked_contract();
+    contract.ft_transfer_call(accounts(2), U128(100), None, msg());
+    assert!(contract.resolved.is_empty());
+
+    set_resolve_context(30);
+    let used = contract.ft_resolve_transfer(accounts(1), accounts(2), U128(100));
+    assert_eq!(used, U128(70));
+    assert_eq!(contract.resolved, vec![(accounts(1), accounts(2), 70)]);
+    assert_eq!(contract.ft_balance_of(accounts(1)), U128(930));
+    assert!(contract.burned.is_empty());
+}
+
+#[test]
+fn on_tokens_burned_with_options() {
+    let mut contract = hooked_contract();
+    contract.ft_transfer_call(accounts(2), U128(100), None, msg());
+
+    // The sender closed their account before the refund.
+    contract.token.accounts.remove(&accounts(1));
+    set_resolve_context(30);
+    let used = contract.ft_resolve_transfer(accounts(1), accounts(2), U128(100));
+    assert_eq!(used, U128(100));
+    assert_eq!(contract.burned, vec![(accounts(1), 30)]);
+    assert_eq!(contract.resolved, vec![(accounts(1), accounts(2), 100)]);
+}
@@ -0,0 +1,220 @@
+//! Regression suite for the gas and storage used by the standards.
+//!
+//! Each operation is run against the mocked blockchain, measuring the gas charged for host
+//! functions and the change in storage usage. The measurements are compared against
+//! `tests/gas_baseline.json`, failing with a diff of every measurement that changed by more than
+//! `GAS_TOLERANCE_PERCENT` of gas or by any number of bytes of storage. The baseline was measured
+//! with 4.0.0-pre.6, and the test fails if it is missing.
+//!
+//! Gas is only charged for host functions by the mocked blockchain, so this catches changes to
+//! storage access patterns, serialization sizes and logs, but not to the wasm instructions
+//! executed.
+//!
+//! To regenerate the baseline after an intentional change, run:
+//!
+//! ```sh
+//! NEAR_GAS_BASELINE_UPDATE=1 cargo test -p near-contract-standards --test gas_baseline
+//! ```
+
+use near_contract_standards::fungible_token::core::FungibleTokenCore;
+use near_contract_standards::fungible_token::FungibleToken;
+use near_contract_standards::non_fungible_token::core::NonFungibleTokenCore;
+use near_contract_standards::non_fungible_token::metadata::TokenMetadata;
+use near_contract_standards::non_fungible_token::NonFungibleToken;
+use near_contract_standards::storage_management::StorageManagement;
+use near_sdk::json_types::{Base64VecU8, U128};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::test_utils::{accounts, VMContextBuilder};
+use near_sdk::{env, testing_env, AccountId, Balance, ONE_NEAR, ONE_YOCTO};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+const GAS_TOLERANCE_PERCENT: u64 = 5;
+const UPDATE_VAR: &str = "NEAR_GAS_BASELINE_UPDATE";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+struct Measurement {
+    /// Gas charged for host functions.
+    gas: u64,
+    /// Change in storage usage, in bytes.
+    storage_bytes: i64,
+}
+
+type Baseline = BTreeMap<String, Measurement>;
+
+fn baseline_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("gas_baseline.json")
+}
+
+fn set_context(predecessor: AccountId, attached_deposit: Balance) {
+    testing_env!(VMContextBuilder::new()
+        .current_account_id(accounts(0))
+        .predecessor_account_id(predecessor)
+        .attached_deposit(attached_deposit)
+        .build());
+}
+
+/// Measures the gas and storage used by `f`. The context must be set up before calling this.
+fn measure<F: FnOnce()>(f: F) -> Measurement {
+    let gas = env::used_gas();
+    let storage = env::storage_usage();
+    f();
+    Measurement {
+        gas: (env::used_gas() - gas).0,
+        storage_bytes: env::storage_usage() as i64 - storage as i64,
+    }
+}
+
+fn fungible_token() -> FungibleToken {
+    let mut token = FungibleToken::new(b"t".to_vec());
+    token.internal_register_account(&accounts(1));
+    token.internal_deposit(&accounts(1), 1_000);
+    token.internal_register_account(&accounts(2));
+    token
+}
+
+fn non_fungible_token() -> NonFungibleToken {
+    NonFungibleToken::new(
+        b"o".to_vec(),
+        accounts(0),
+        Some(b"m".to_vec()),
+        Some(b"e".to_vec()),
+        Some(b"a".to_vec()),
+    )
+}
+
+fn metadata() -> TokenMetadata {
+    TokenMetadata {
+        title: Some("title".to_string()),
+        description: Some("description".to_string()),
+        media: Some("https://example.com/media.png".to_string()),
+        media_hash: Some(Base64VecU8::from(vec![0; 32])),
+        copies: Some(1),
+        issued_at: None,
+        expires_at: None,
+        starts_at: None,
+        updated_at: None,
+        extra: None,
+        reference: None,
+        reference_hash: None,
+    }
+}
+
+fn measure_all() -> Baseline {
+    let mut measurements = Baseline::new();
+
+    set_context(accounts(1), 0);
+    let mut token = fungible_token();
+    set_context(accounts(3), ONE_NEAR);
+    measurements.insert(
+        "ft_storage_deposit".to_string(),
+        measure(|| {
+            token.storage_deposit(None, None);
+        }),
+    );
+    set_context(accounts(1), ONE_YOCTO);
+    measurements.insert(
+        "ft_transfer".to_string(),
+        measure(|| token.ft_transfer(accounts(2), U128(10), None)),
+    );
+
+    set_context(accounts(0), ONE_NEAR);
+    let mut token = non_fungible_token();
+    measurements.insert(
+        "nft_mint".to_string(),
+        measure(|| {
+            token.internal_mint("token".to_string(), accounts(1), Some(metadata()));
+        }),
+    );
+    set_context(accounts(1), ONE_YOCTO);
+    measurements.insert(
+        "nft_transfer".to_string(),
+        measure(|| token.nft_transfer(accounts(2), "token".to_string(), None, None)),
+    );
+
+    measurements
+}
+
+/// Returns a line for every measurement which differs from the baseline beyond the tolerance.
+fn diff(baseline: &Baseline, measured: &Baseline) -> Vec<String> {
+    let mut lines = Vec::new();
+    for (name, current) in measured {
+        let expected = match baseline.get(name) {
+            Some(expected) => expected,
+            None => {
+                lines.push(format!("{}: missing from the baseline", name));
+                continue;
+            }
+        };
+        let gas_delta = current.gas.max(expected.gas) - current.gas.min(expected.gas);
+        if gas_delta * 100 > expected.gas * GAS_TOLERANCE_PERCENT {
+            lines.push(format!(
+                "{}.gas: baseline {}, measured {} ({:+.1}%)",
+                name,
+                expected.gas,
+                current.gas,
+                (current.gas as f64 - expected.gas as f64) * 100.0 / expected.gas as f64
+            ));
+        }
+        if current.storage_bytes != expected.storage_bytes {
+            lines.push(format!(
+                "{}.storage_bytes: baseline {}, measured {}",
+                name, expected.storage_bytes, current.storage_bytes
+            ));
+        }
+    }
+    for name in baseline.keys().filter(|name| !measured.contains_key(*name)) {
+        lines.push(format!("{}: in the baseline but no longer measured", name));
+    }
+    lines
+}
+
+#[test]
+fn gas_baseline() {
+    let measured = measure_all();
+    let path = baseline_path();
+
+    if std::env::var_os(UPDATE_VAR).is_some() {
+        let json = serde_json::to_string_pretty(&measured).unwrap();
+        std::fs::write(&path, json + "\n").unwrap();
+        return;
+    }
+
+    let json = std::fs::read_to_string(&path).unwrap_or_else(|err| {
+        panic!("Cannot read {}: {}. Run with {}=1 to write it.", path.display(), err, UPDATE_VAR)
+    });
+    let baseline: Baseline = serde_json::from_str(&json).unwrap();
+    let lines = diff(&baseline, &measured);
+    assert!(
+        lines.is_empty(),
+        "Gas usage deviates from {}:\n  {}\nRun with {}=1 to update the baseline.",
+        path.display(),
+        lines.join("\n  "),
+        UPDATE_VAR
+    );
+}
+
+#[test]
+fn diff_reports_deviations() {
+    let baseline: Baseline = vec![
+        ("a".to_string(), Measurement { gas: 100, storage_bytes: 10 }),
+        ("b".to_string(), Measurement { gas: 100, storage_bytes: 10 }),
+    ]
+    .into_iter()
+    .collect();
+    let mut measured = baseline.clone();
+    measured.insert("a".to_string(), Measurement { gas: 105, storage_bytes: 10 });
+    assert!(diff(&baseline, &measured).is_empty());
+
+    measured.insert("a".to_string(), Measurement { gas: 120, storage_bytes: 11 });
+    measured.remove("b");
+    assert_eq!(
+        diff(&baseline, &measured),
+        vec![
+            "a.gas: baseline 100, measured 120 (+20.0%)".to_string(),
+            "a.storage_bytes: baseline 10, measured 11".to_string(),
+            "b: in the baseline but no longer measured".to_string(),
+        ]
+    );
+}
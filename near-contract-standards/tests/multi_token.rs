@@ -0,0 +1,379 @@
+//! A contract using `impl_multi_token!`, with batch transfers and their resolution.
+
+use near_contract_standards::event::NearEvent;
+use near_contract_standards::multi_token::approval::MultiTokenApproval;
+use near_contract_standards::multi_token::core::{MultiTokenCore, MultiTokenResolver, REFUND_MEMO};
+use near_contract_standards::multi_token::enumeration::MultiTokenEnumeration;
+use near_contract_standards::multi_token::events::{MtTransferData, Nep245Event, Nep245EventKind};
+use near_contract_standards::multi_token::metadata::{MtContractMetadata, MT_METADATA_SPEC};
+use near_contract_standards::multi_token::{MultiToken, Token};
+use near_contract_standards::standards::NEP245;
+use near_sdk::collections::LazyOption;
+use near_sdk::json_types::U128;
+use near_sdk::test_utils::{accounts, get_logs, VMContextBuilder};
+use near_sdk::{
+    testing_env, AccountId, PromiseResult, RuntimeFeesConfig, VMConfig, ONE_NEAR, ONE_YOCTO,
+};
+
+mod contract {
+    use near_contract_standards::multi_token::metadata::MtContractMetadata;
+    use near_contract_standards::multi_token::MultiToken;
+    use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+    use near_sdk::collections::LazyOption;
+    use near_sdk::{near_bindgen, AccountId, PanicOnDefault, Promise, PromiseOrValue};
+
+    #[near_bindgen]
+    #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+    pub struct Contract {
+        pub tokens: MultiToken,
+        pub metadata: LazyOption<MtContractMetadata>,
+    }
+
+    near_contract_standards::impl_multi_token!(Contract, tokens, metadata);
+
+    impl Contract {
+        pub fn standards(&self) -> Vec<near_contract_standards::standards::Standard<'static>> {
+            near_contract_standards::standards_manifest!()
+        }
+    }
+}
+
+use contract::Contract;
+
+fn set_context(predecessor: AccountId, attached_deposit: u128) {
+    testing_env!(VMContextBuilder::new()
+        .current_account_id(accounts(0))
+        .predecessor_account_id(predecessor)
+        .attached_deposit(attached_deposit)
+        .build());
+}
+
+/// Sets the context of `mt_resolve_transfer`, called with the result of `mt_on_transfer`.
+fn set_resolve_context(result: PromiseResult) {
+    testing_env!(
+        VMContextBuilder::new()
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(0))
+            .build(),
+        VMConfig::test(),
+        RuntimeFeesConfig::test(),
+        Default::default(),
+        vec![result],
+    );
+}
+
+fn ids(token_ids: &[&str]) -> Vec<String> {
+    token_ids.iter().map(|token_id| token_id.to_string()).collect()
+}
+
+fn amounts(amounts: &[u128]) -> Vec<U128> {
+    amounts.iter().copied().map(U128).collect()
+}
+
+fn msg() -> String {
+    String::new()
+}
+
+/// Mints 100 gold, 50 silver and a sword to `accounts(1)`.
+fn contract() -> Contract {
+    set_context(accounts(0), ONE_NEAR);
+    let metadata =
+        MtContractMetadata { spec: MT_METADATA_SPEC.to_string(), name: "Items".to_string() };
+    let mut contract = Contract {
+        tokens: MultiToken::new(
+            b"t".to_vec(),
+            accounts(0),
+            None::<Vec<u8>>,
+            Some(b"e".to_vec()),
+            Some(b"a".to_vec()),
+        ),
+        metadata: LazyOption::new(b"m".to_vec(), Some(&metadata)),
+    };
+    for (token_id, amount) in [("gold", 100), ("silver", 50), ("sword", 1)] {
+        contract.tokens.internal_mint(token_id.to_string(), accounts(1), amount, None);
+    }
+    contract
+}
+
+fn balances(contract: &Contract, account_id: AccountId) -> Vec<U128> {
+    contract.mt_batch_balance_of(account_id, ids(&["gold", "silver", "sword"]))
+}
+
+/// Transfers 50 gold, 20 silver and the sword from `accounts(1)` to `accounts(3)`, with
+/// `mt_batch_transfer_call`.
+fn transfer_call(contract: &mut Contract) {
+    set_context(accounts(1), ONE_YOCTO);
+    let _ = contract.mt_batch_transfer_call(
+        accounts(3),
+        ids(&["gold", "silver", "sword"]),
+        amounts(&[50, 20, 1]),
+        None,
+        None,
+        msg(),
+    );
+}
+
+fn resolve(contract: &mut Contract, result: PromiseResult) -> Vec<U128> {
+    set_resolve_context(result);
+    contract.mt_resolve_transfer(
+        vec![accounts(1); 3],
+        accounts(3),
+        ids(&["gold", "silver", "sword"]),
+        amounts(&[50, 20, 1]),
+        None,
+    )
+}
+
+fn transfer_event(log: &str) -> Vec<MtTransferData> {
+    match NearEvent::from_log(log).unwrap() {
+        NearEvent::Nep245(Nep245Event {
+            event_kind: Nep245EventKind::MtTransfer(transfers),
+            ..
+        }) => transfers,
+        event => panic!("Unexpected event {:?}", event),
+    }
+}
+
+#[test]
+fn mint() {
+    let contract = contract();
+    assert_eq!(balances(&contract, accounts(1)), amounts(&[100, 50, 1]));
+    assert_eq!(contract.mt_supply("gold".to_string()), Some(U128(100)));
+    assert_eq!(contract.mt_batch_supply(ids(&["sword", "shield"])), [Some(U128(1)), None]);
+    assert_eq!(
+        contract.mt_token(ids(&["sword", "shield"])),
+        [Some(Token { token_id: "sword".to_string(), owner_id: None }), None]
+    );
+    assert_eq!(
+        get_logs().last().unwrap(),
+        r#"EVENT_JSON:{"standard":"nep245","version":"1.0.0","event":"mt_mint","data":[{"owner_id":"bob","token_ids":["sword"],"amounts":["1"]}]}"#
+    );
+    assert_eq!(contract.standards(), [NEP245]);
+}
+
+#[test]
+fn batch_transfer() {
+    let mut contract = contract();
+    set_context(accounts(1), ONE_YOCTO);
+    contract.mt_batch_transfer(accounts(2), ids(&["gold", "sword"]), amounts(&[10, 1]), None, None);
+
+    assert_eq!(balances(&contract, accounts(1)), amounts(&[90, 50, 0]));
+    assert_eq!(balances(&contract, accounts(2)), amounts(&[10, 0, 1]));
+    // The supply doesn't change.
+    assert_eq!(contract.mt_supply("gold".to_string()), Some(U128(100)));
+    assert_eq!(
+        get_logs(),
+        [
+            r#"EVENT_JSON:{"standard":"nep245","version":"1.0.0","event":"mt_transfer","data":[{"old_owner_id":"bob","new_owner_id":"charlie","token_ids":["gold","sword"],"amounts":["10","1"]}]}"#
+        ]
+    );
+
+    let token_ids = |tokens: Vec<Token>| -> Vec<String> {
+        let mut ids: Vec<String> = tokens.into_iter().map(|token| token.token_id).collect();
+        ids.sort();
+        ids
+    };
+    assert_eq!(
+        token_ids(contract.mt_tokens_for_owner(accounts(1), None, None)),
+        ["gold", "silver"]
+    );
+    assert_eq!(token_ids(contract.mt_tokens_for_owner(accounts(2), None, None)), ["gold", "sword"]);
+    assert_eq!(token_ids(contract.mt_tokens(Some(U128(1)), Some(1))), ["silver"]);
+}
+
+#[test]
+#[should_panic(expected = "The account doesn't have enough balance")]
+fn batch_transfer_over_balance() {
+    let mut contract = contract();
+    set_context(accounts(1), ONE_YOCTO);
+    contract.mt_batch_transfer(accounts(2), ids(&["gold", "sword"]), amounts(&[10, 2]), None, None);
+}
+
+#[test]
+#[should_panic(expected = "The number of token IDs and amounts should be the same")]
+fn batch_transfer_mismatched_amounts() {
+    let mut contract = contract();
+    set_context(accounts(1), ONE_YOCTO);
+    contract.mt_batch_transfer(accounts(2), ids(&["gold", "sword"]), amounts(&[10]), None, None);
+}
+
+#[test]
+fn transfer_call_partial_refund() {
+    let mut contract = contract();
+    transfer_call(&mut contract);
+    assert_eq!(balances(&contract, accounts(3)), amounts(&[50, 20, 1]));
+
+    // The receiver returns 10 gold and the sword, and keeps the silver.
+    let used = resolve(&mut contract, PromiseResult::Successful(br#"["10","0","1"]"#.to_vec()));
+    assert_eq!(used, amounts(&[40, 20, 0]));
+    assert_eq!(balances(&contract, accounts(1)), amounts(&[60, 30, 1]));
+    assert_eq!(balances(&contract, accounts(3)), amounts(&[40, 20, 0]));
+    assert_eq!(
+        transfer_event(&get_logs()[0]),
+        [MtTransferData {
+            old_owner_id: accounts(3),
+            new_owner_id: accounts(1),
+            token_ids: ids(&["gold", "sword"]),
+            amounts: amounts(&[10, 1]),
+            authorized_id: None,
+            memo: Some(REFUND_MEMO.to_string()),
+        }]
+    );
+}
+
+#[test]
+fn transfer_call_refund_capped_by_receiver_balance() {
+    let mut contract = contract();
+    transfer_call(&mut contract);
+    // The receiver spends 45 gold before returning all of it.
+    set_context(accounts(3), ONE_YOCTO);
+    contract.mt_transfer(accounts(4), "gold".to_string(), U128(45), None, None);
+
+    let used = resolve(&mut contract, PromiseResult::Successful(br#"["50","0","0"]"#.to_vec()));
+    assert_eq!(used, amounts(&[45, 20, 1]));
+    assert_eq!(balances(&contract, accounts(1)), amounts(&[55, 30, 0]));
+    assert_eq!(balances(&contract, accounts(3)), amounts(&[0, 20, 1]));
+}
+
+#[test]
+fn transfer_call_failure_refunds_everything() {
+    let mut contract = contract();
+    transfer_call(&mut contract);
+    assert_eq!(resolve(&mut contract, PromiseResult::Failed), amounts(&[0, 0, 0]));
+    assert_eq!(balances(&contract, accounts(1)), amounts(&[100, 50, 1]));
+    assert!(contract.mt_tokens_for_owner(accounts(3), None, None).is_empty());
+
+    // So does a result which doesn't have an amount for each token.
+    transfer_call(&mut contract);
+    let used = resolve(&mut contract, PromiseResult::Successful(br#"["10"]"#.to_vec()));
+    assert_eq!(used, amounts(&[0, 0, 0]));
+    assert_eq!(balances(&contract, accounts(1)), amounts(&[100, 50, 1]));
+}
+
+#[test]
+fn transfer_call_receiver_keeps_everything() {
+    let mut contract = contract();
+    transfer_call(&mut contract);
+    let used = resolve(&mut contract, PromiseResult::Successful(br#"["0","0","0"]"#.to_vec()));
+    assert_eq!(used, amounts(&[50, 20, 1]));
+    assert!(get_logs().is_empty());
+}
+
+#[test]
+fn approved_transfer_call_restores_approval() {
+    let mut contract = contract();
+    set_context(accounts(1), ONE_NEAR);
+    contract.mt_approve(ids(&["gold"]), amounts(&[30]), accounts(2), None);
+    assert!(contract.mt_is_approved(
+        accounts(1),
+        ids(&["gold"]),
+        accounts(2),
+        amounts(&[30]),
+        Some(vec![1])
+    ));
+
+    set_context(accounts(2), ONE_YOCTO);
+    let _ = contract.mt_transfer_call(
+        accounts(3),
+        "gold".to_string(),
+        U128(20),
+        Some((accounts(1), 1)),
+        None,
+        msg(),
+    );
+    assert_eq!(transfer_event(&get_logs()[0])[0].authorized_id, Some(accounts(2)));
+    assert!(!contract.mt_is_approved(
+        accounts(1),
+        ids(&["gold"]),
+        accounts(2),
+        amounts(&[11]),
+        None
+    ));
+    assert!(contract.mt_is_approved(
+        accounts(1),
+        ids(&["gold"]),
+        accounts(2),
+        amounts(&[10]),
+        None
+    ));
+
+    // 15 gold are returned, giving back 15 of the approved amount.
+    set_resolve_context(PromiseResult::Successful(br#"["15"]"#.to_vec()));
+    let used = contract.mt_resolve_transfer(
+        vec![accounts(1)],
+        accounts(3),
+        ids(&["gold"]),
+        amounts(&[20]),
+        Some(vec![Some((accounts(2), 1, U128(30)))]),
+    );
+    assert_eq!(used, amounts(&[5]));
+    assert_eq!(contract.mt_balance_of(accounts(1), "gold".to_string()), U128(95));
+    assert!(contract.mt_is_approved(
+        accounts(1),
+        ids(&["gold"]),
+        accounts(2),
+        amounts(&[25]),
+        Some(vec![1])
+    ));
+    assert!(!contract.mt_is_approved(
+        accounts(1),
+        ids(&["gold"]),
+        accounts(2),
+        amounts(&[26]),
+        None
+    ));
+}
+
+#[test]
+#[should_panic(expected = "The approved amount is too low")]
+fn approved_transfer_over_approval() {
+    let mut contract = contract();
+    set_context(accounts(1), ONE_NEAR);
+    contract.mt_approve(ids(&["gold"]), amounts(&[30]), accounts(2), None);
+    set_context(accounts(2), ONE_YOCTO);
+    contract.mt_transfer(accounts(3), "gold".to_string(), U128(31), Some((accounts(1), 1)), None);
+}
+
+#[test]
+fn revoke() {
+    let mut contract = contract();
+    set_context(accounts(1), ONE_NEAR);
+    contract.mt_approve(ids(&["gold", "silver"]), amounts(&[30, 5]), accounts(2), None);
+    set_context(accounts(1), ONE_YOCTO);
+    contract.mt_revoke(ids(&["gold"]), accounts(2));
+    assert!(!contract.mt_is_approved(
+        accounts(1),
+        ids(&["gold"]),
+        accounts(2),
+        amounts(&[1]),
+        None
+    ));
+    assert!(contract.mt_is_approved(
+        accounts(1),
+        ids(&["silver"]),
+        accounts(2),
+        amounts(&[5]),
+        None
+    ));
+    contract.mt_revoke_all(ids(&["silver"]));
+    assert!(!contract.mt_is_approved(
+        accounts(1),
+        ids(&["silver"]),
+        accounts(2),
+        amounts(&[1]),
+        None
+    ));
+}
+
+#[test]
+fn burn() {
+    let mut contract = contract();
+    contract.tokens.internal_burn(&accounts(1), &"gold".to_string(), 100, None);
+    assert_eq!(contract.mt_supply("gold".to_string()), Some(U128(0)));
+    let tokens = contract.mt_tokens_for_owner(accounts(1), None, None);
+    assert!(tokens.iter().all(|token| token.token_id != "gold"));
+    assert_eq!(
+        get_logs().last().unwrap(),
+        r#"EVENT_JSON:{"standard":"nep245","version":"1.0.0","event":"mt_burn","data":[{"owner_id":"bob","token_ids":["gold"],"amounts":["100"]}]}"#
+    );
+}
@@ -0,0 +1,71 @@
+//! Contracts using `impl_non_fungible_token_metadata!` and `impl_non_fungible_token!`.
+
+use near_contract_standards::non_fungible_token::metadata::{
+    NFTContractMetadata, NonFungibleTokenMetadataProvider, NFT_METADATA_SPEC,
+};
+use near_contract_standards::non_fungible_token::NonFungibleToken;
+use near_contract_standards::standards::{NEP171, NEP177, NEP178, NEP181};
+use near_sdk::collections::LazyOption;
+use near_sdk::test_utils::accounts;
+
+mod contract {
+    use near_contract_standards::non_fungible_token::metadata::NFTContractMetadata;
+    use near_contract_standards::non_fungible_token::{NonFungibleToken, Token, TokenId};
+    use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+    use near_sdk::collections::LazyOption;
+    use near_sdk::{near_bindgen, AccountId, PanicOnDefault, Promise, PromiseOrValue};
+
+    #[near_bindgen]
+    #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+    pub struct Contract {
+        pub tokens: NonFungibleToken,
+        pub metadata: LazyOption<NFTContractMetadata>,
+    }
+
+    near_contract_standards::impl_non_fungible_token!(Contract, tokens, metadata);
+
+    impl Contract {
+        pub fn standards(&self) -> Vec<near_contract_standards::standards::Standard<'static>> {
+            near_contract_standards::standards_manifest!()
+        }
+    }
+}
+
+fn metadata(spec: &str) -> NFTContractMetadata {
+    NFTContractMetadata {
+        spec: spec.to_string(),
+        name: "Mosaics".to_string(),
+        symbol: "MOSAIC".to_string(),
+        icon: None,
+        base_uri: None,
+        reference: None,
+        reference_hash: None,
+    }
+}
+
+fn contract(metadata: &NFTContractMetadata) -> contract::Contract {
+    contract::Contract {
+        tokens: NonFungibleToken::new_default(accounts(0)),
+        metadata: LazyOption::new(b"m".to_vec(), Some(metadata)),
+    }
+}
+
+#[test]
+fn returns_stored_metadata() {
+    let contract = contract(&metadata(NFT_METADATA_SPEC));
+    assert_eq!(contract.nft_metadata(), metadata(NFT_METADATA_SPEC));
+    assert!(contract.nft_supports_spec(NFT_METADATA_SPEC.to_string()));
+    assert!(!contract.nft_supports_spec("nft-2.0.0".to_string()));
+}
+
+#[test]
+#[should_panic(expected = "Spec is not NFT metadata")]
+fn rejects_invalid_metadata() {
+    contract(&metadata("ft-1.0.0")).nft_metadata();
+}
+
+#[test]
+fn registers_every_standard() {
+    let contract = contract(&metadata(NFT_METADATA_SPEC));
+    assert_eq!(contract.standards(), [NEP171, NEP177, NEP178, NEP181]);
+}
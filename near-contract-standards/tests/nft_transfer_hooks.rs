@@ -0,0 +1,271 @@
+//! Transfer hooks passed to `impl_non_fungible_token_core!` and `impl_non_fungible_token_payout!`.
+
+use near_contract_standards::non_fungible_token::approval::NonFungibleTokenApproval;
+use near_contract_standards::non_fungible_token::core::{
+    NonFungibleTokenCore, NonFungibleTokenResolver,
+};
+use near_contract_standards::non_fungible_token::payout::NonFungibleTokenPayout;
+use near_contract_standards::non_fungible_token::NonFungibleToken;
+use near_sdk::json_types::U128;
+use near_sdk::test_utils::{accounts, get_logs, storage_snapshot, VMContextBuilder};
+use near_sdk::{
+    testing_env, AccountId, PromiseResult, RuntimeFeesConfig, VMConfig, ONE_NEAR, ONE_YOCTO,
+};
+
+const TOKEN_ID: &str = "t";
+
+mod soulbound {
+    use near_contract_standards::non_fungible_token::core::NonFungibleTokenTransferHook;
+    use near_contract_standards::non_fungible_token::{NonFungibleToken, Token, TokenId};
+    use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+    use near_sdk::{env, near_bindgen, AccountId, PanicOnDefault, PromiseOrValue};
+
+    #[derive(BorshDeserialize, BorshSerialize)]
+    pub struct NonTransferable;
+
+    impl NonFungibleTokenTransferHook for NonTransferable {
+        fn before_nft_transfer(&mut self, _from: &AccountId, _to: &AccountId, _token_id: &TokenId) {
+            env::panic_str("Token is non-transferable");
+        }
+    }
+
+    #[near_bindgen]
+    #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+    pub struct Contract {
+        pub tokens: NonFungibleToken,
+        pub hook: NonTransferable,
+    }
+
+    near_contract_standards::impl_non_fungible_token_core!(Contract, tokens, [hook = hook]);
+    near_contract_standards::impl_non_fungible_token_payout!(Contract, tokens, [hook = hook]);
+}
+
+mod counted {
+    use near_contract_standards::non_fungible_token::core::NonFungibleTokenTransferHook;
+    use near_contract_standards::non_fungible_token::{NonFungibleToken, Token, TokenId};
+    use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+    use near_sdk::{near_bindgen, AccountId, PanicOnDefault, PromiseOrValue};
+
+    #[derive(BorshDeserialize, BorshSerialize, Default)]
+    pub struct TransferCounter {
+        pub before: u64,
+        pub after: u64,
+        pub transfers: Vec<(AccountId, AccountId, TokenId)>,
+    }
+
+    impl NonFungibleTokenTransferHook for TransferCounter {
+        fn before_nft_transfer(&mut self, _from: &AccountId, _to: &AccountId, _token_id: &TokenId) {
+            self.before += 1;
+        }
+
+        fn after_nft_transfer(&mut self, from: &AccountId, to: &AccountId, token_id: &TokenId) {
+            self.after += 1;
+            self.transfers.push((from.clone(), to.clone(), token_id.clone()));
+        }
+    }
+
+    #[near_bindgen]
+    #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+    pub struct Contract {
+        pub tokens: NonFungibleToken,
+        pub counter: TransferCounter,
+    }
+
+    near_contract_standards::impl_non_fungible_token_core!(Contract, tokens, [hook = counter]);
+}
+
+mod pausable {
+    use near_contract_standards::non_fungible_token::core::NonFungibleTokenTransferHook;
+    use near_contract_standards::non_fungible_token::{NonFungibleToken, Token, TokenId};
+    use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+    use near_sdk::{env, near_bindgen, AccountId, PanicOnDefault, PromiseOrValue};
+
+    #[derive(BorshDeserialize, BorshSerialize, Default)]
+    pub struct Pause {
+        pub paused: bool,
+        pub reverted: u64,
+    }
+
+    impl NonFungibleTokenTransferHook for Pause {
+        fn before_nft_transfer(&mut self, _from: &AccountId, _to: &AccountId, _token_id: &TokenId) {
+            if self.paused {
+                env::panic_str("Transfers are paused");
+            }
+        }
+
+        fn on_nft_transfer_reverted(
+            &mut self,
+            _from: &AccountId,
+            _to: &AccountId,
+            _token_id: &TokenId,
+        ) {
+            self.reverted += 1;
+        }
+    }
+
+    #[near_bindgen]
+    #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+    pub struct Contract {
+        pub tokens: NonFungibleToken,
+        pub pause: Pause,
+    }
+
+    near_contract_standards::impl_non_fungible_token_core!(Contract, tokens, [hook = pause]);
+}
+
+fn set_context(predecessor: AccountId, attached_deposit: u128) {
+    testing_env!(VMContextBuilder::new()
+        .current_account_id(accounts(0))
+        .predecessor_account_id(predecessor)
+        .attached_deposit(attached_deposit)
+        .build());
+}
+
+/// Mints a token to `accounts(1)`, approving `accounts(2)`.
+fn minted_token() -> NonFungibleToken {
+    set_context(accounts(0), ONE_NEAR);
+    let mut tokens = NonFungibleToken::new(
+        b"o".to_vec(),
+        accounts(0),
+        None::<Vec<u8>>,
+        None::<Vec<u8>>,
+        Some(b"a".to_vec()),
+    )
+    .with_royalties(b"r".to_vec());
+    tokens.internal_mint(TOKEN_ID.to_string(), accounts(1), None);
+    set_context(accounts(1), ONE_NEAR);
+    tokens.nft_approve(TOKEN_ID.to_string(), accounts(2), None);
+    tokens
+}
+
+fn msg() -> String {
+    String::new()
+}
+
+/// Runs `f` and checks that it panics without changing the state or logging anything.
+fn assert_aborts(f: impl FnOnce()) {
+    let before = storage_snapshot();
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(f));
+    assert!(result.is_err());
+    assert_eq!(storage_snapshot(), before);
+    assert!(get_logs().is_empty());
+}
+
+#[test]
+fn non_transferable_token() {
+    let mut contract =
+        soulbound::Contract { tokens: minted_token(), hook: soulbound::NonTransferable };
+
+    set_context(accounts(1), ONE_YOCTO);
+    assert_aborts(|| contract.nft_transfer(accounts(3), TOKEN_ID.to_string(), None, None));
+    // An approved account can't transfer it either, and keeps its approval.
+    set_context(accounts(2), ONE_YOCTO);
+    assert_aborts(|| {
+        let _ = contract.nft_transfer_call(accounts(3), TOKEN_ID.to_string(), Some(1), None, msg());
+    });
+    assert_aborts(|| {
+        contract.nft_transfer_payout(
+            accounts(3),
+            TOKEN_ID.to_string(),
+            None,
+            None,
+            U128(100),
+            None,
+        );
+    });
+
+    assert_eq!(contract.nft_token(TOKEN_ID.to_string()).unwrap().owner_id, accounts(1));
+    assert!(contract.tokens.nft_is_approved(TOKEN_ID.to_string(), accounts(2), Some(1)));
+}
+
+#[test]
+#[should_panic(expected = "Token is non-transferable")]
+fn non_transferable_token_message() {
+    let mut contract =
+        soulbound::Contract { tokens: minted_token(), hook: soulbound::NonTransferable };
+    set_context(accounts(1), ONE_YOCTO);
+    contract.nft_transfer(accounts(3), TOKEN_ID.to_string(), None, None);
+}
+
+#[test]
+fn transfer_counter() {
+    let mut contract = counted::Contract { tokens: minted_token(), counter: Default::default() };
+
+    set_context(accounts(1), ONE_YOCTO);
+    contract.nft_transfer(accounts(3), TOKEN_ID.to_string(), None, None);
+    set_context(accounts(3), ONE_YOCTO);
+    let _ = contract.nft_transfer_call(accounts(4), TOKEN_ID.to_string(), None, None, msg());
+    assert_eq!(contract.counter.before, 2);
+    assert_eq!(contract.counter.after, 2);
+
+    // The receiver returns the token, which counts as a transfer back to the previous owner.
+    testing_env!(
+        VMContextBuilder::new()
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(0))
+            .build(),
+        VMConfig::test(),
+        RuntimeFeesConfig::test(),
+        Default::default(),
+        vec![PromiseResult::Successful(b"true".to_vec())],
+    );
+    assert!(!contract.nft_resolve_transfer(accounts(3), accounts(4), TOKEN_ID.to_string(), None));
+    // Returning the token can't be vetoed, so only the transfer back is reported.
+    assert_eq!(contract.counter.before, 2);
+    assert_eq!(
+        contract.counter.transfers,
+        [
+            (accounts(1), accounts(3), TOKEN_ID.to_string()),
+            (accounts(3), accounts(4), TOKEN_ID.to_string()),
+            (accounts(4), accounts(3), TOKEN_ID.to_string()),
+        ]
+    );
+}
+
+#[test]
+fn failed_transfer_is_not_counted() {
+    let mut contract = counted::Contract { tokens: minted_token(), counter: Default::default() };
+
+    // Unauthorized transfers fail before the hook runs.
+    set_context(accounts(3), ONE_YOCTO);
+    assert_aborts(|| contract.nft_transfer(accounts(4), TOKEN_ID.to_string(), None, None));
+    assert_eq!(contract.counter.before, 0);
+
+    // A transfer the receiver keeps runs the hooks once.
+    set_context(accounts(1), ONE_YOCTO);
+    let _ = contract.nft_transfer_call(accounts(4), TOKEN_ID.to_string(), None, None, msg());
+    testing_env!(
+        VMContextBuilder::new()
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(0))
+            .build(),
+        VMConfig::test(),
+        RuntimeFeesConfig::test(),
+        Default::default(),
+        vec![PromiseResult::Successful(b"false".to_vec())],
+    );
+    assert!(contract.nft_resolve_transfer(accounts(1), accounts(4), TOKEN_ID.to_string(), None));
+    assert_eq!((contract.counter.before, contract.counter.after), (1, 1));
+}
+
+#[test]
+fn returning_token_is_not_vetoed() {
+    let mut contract = pausable::Contract { tokens: minted_token(), pause: Default::default() };
+
+    set_context(accounts(1), ONE_YOCTO);
+    let _ = contract.nft_transfer_call(accounts(4), TOKEN_ID.to_string(), None, None, msg());
+    contract.pause.paused = true;
+    testing_env!(
+        VMContextBuilder::new()
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(0))
+            .build(),
+        VMConfig::test(),
+        RuntimeFeesConfig::test(),
+        Default::default(),
+        vec![PromiseResult::Successful(b"true".to_vec())],
+    );
+    assert!(!contract.nft_resolve_transfer(accounts(1), accounts(4), TOKEN_ID.to_string(), None));
+    assert_eq!(contract.nft_token(TOKEN_ID.to_string()).unwrap().owner_id, accounts(1));
+    assert_eq!(contract.pause.reverted, 1);
+}
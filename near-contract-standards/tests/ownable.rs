@@ -0,0 +1,91 @@
+//! A contract using `impl_ownable!`, with methods marked `#[only_owner]`.
+
+use near_contract_standards::ownable::Ownable;
+use near_sdk::test_utils::{accounts, VMContextBuilder};
+use near_sdk::{testing_env, AccountId, ONE_YOCTO};
+
+mod contract {
+    use near_contract_standards::ownable::Ownable;
+    use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+    use near_sdk::{near_bindgen, AccountId, PanicOnDefault};
+
+    #[near_bindgen]
+    #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+    pub struct Contract {
+        pub ownable: Ownable,
+        pub fee: u16,
+    }
+
+    near_contract_standards::impl_ownable!(Contract, ownable);
+
+    #[near_bindgen]
+    impl Contract {
+        // `#[only_owner]` checks the owner in the method exported to wasm, so unit tests call
+        // `assert_owner` themselves.
+        #[only_owner]
+        pub fn set_fee(&mut self, fee: u16) {
+            self.fee = fee;
+        }
+
+        #[payable]
+        #[only_owner]
+        pub fn withdraw_fees(&mut self) -> u16 {
+            std::mem::take(&mut self.fee)
+        }
+    }
+}
+
+fn set_context(predecessor: AccountId, attached_deposit: u128) {
+    testing_env!(VMContextBuilder::new()
+        .predecessor_account_id(predecessor)
+        .attached_deposit(attached_deposit)
+        .build());
+}
+
+fn contract() -> contract::Contract {
+    set_context(accounts(0), 0);
+    contract::Contract { ownable: Ownable::new(b"o".to_vec(), accounts(0)), fee: 0 }
+}
+
+#[test]
+fn owner_calls_only_owner_methods() {
+    let mut contract = contract();
+    contract.assert_owner();
+    contract.set_fee(10);
+    set_context(accounts(0), ONE_YOCTO);
+    contract.assert_owner();
+    assert_eq!(contract.withdraw_fees(), 10);
+}
+
+#[test]
+#[should_panic(expected = "Only the owner can call this method")]
+fn other_account_fails_owner_check() {
+    let contract = contract();
+    set_context(accounts(1), ONE_YOCTO);
+    contract.assert_owner();
+}
+
+#[test]
+fn transfer_ownership() {
+    let mut contract = contract();
+    contract.transfer_ownership(accounts(1));
+    assert_eq!(contract.owner(), Some(accounts(0)));
+    assert_eq!(contract.pending_owner(), Some(accounts(1)));
+
+    set_context(accounts(1), 0);
+    contract.accept_ownership();
+    assert_eq!(contract.owner(), Some(accounts(1)));
+    contract.assert_owner();
+
+    contract.renounce_ownership();
+    assert_eq!(contract.owner(), None);
+}
+
+#[test]
+#[should_panic(expected = "Only the owner can call this method")]
+fn pending_owner_isnt_owner() {
+    let mut contract = contract();
+    contract.transfer_ownership(accounts(1));
+    set_context(accounts(1), 0);
+    contract.assert_owner();
+}
@@ -0,0 +1,122 @@
+//! A contract using `impl_pausable!` with `impl_ownable!`, with methods marked `#[pause]`.
+
+use near_contract_standards::ownable::Ownable;
+use near_contract_standards::pausable::Pausable;
+use near_sdk::test_utils::{accounts, VMContextBuilder};
+use near_sdk::{env, testing_env, AccountId};
+
+mod contract {
+    use near_contract_standards::ownable::Ownable;
+    use near_contract_standards::pausable::Pausable;
+    use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+    use near_sdk::{near_bindgen, AccountId, PanicOnDefault};
+
+    #[near_bindgen]
+    #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+    pub struct Contract {
+        pub ownable: Ownable,
+        pub pausable: Pausable,
+        pub transfers: u32,
+    }
+
+    near_contract_standards::impl_ownable!(Contract, ownable);
+    near_contract_standards::impl_pausable!(Contract, pausable);
+
+    #[near_bindgen]
+    impl Contract {
+        #[pause(feature = "transfer")]
+        pub fn transfer(&mut self) {
+            self.transfers += 1;
+        }
+
+        #[pause(feature = "transfer", except(owner))]
+        pub fn recover(&mut self) {
+            self.transfers = 0;
+        }
+    }
+}
+
+use contract::Contract;
+
+fn set_predecessor(predecessor: AccountId) {
+    testing_env!(VMContextBuilder::new().predecessor_account_id(predecessor).build());
+}
+
+fn contract() -> Contract {
+    set_predecessor(accounts(0));
+    Contract {
+        ownable: Ownable::new(b"o".to_vec(), accounts(0)),
+        pausable: Pausable::new(b"p".to_vec()),
+        transfers: 0,
+    }
+}
+
+// `#[pause]` checks the feature in the methods exported to wasm, these run the same checks before
+// calling the methods directly.
+
+fn transfer(contract: &mut Contract) {
+    contract.assert_not_paused("transfer");
+    contract.transfer();
+}
+
+fn recover(contract: &mut Contract) {
+    if contract.owner() != Some(env::predecessor_account_id()) {
+        contract.assert_not_paused("transfer");
+    }
+    contract.recover();
+}
+
+#[test]
+#[should_panic(expected = "The feature transfer is paused")]
+fn paused_method_panics() {
+    let mut contract = contract();
+    contract.pause("transfer".to_string());
+    set_predecessor(accounts(1));
+    transfer(&mut contract);
+}
+
+#[test]
+#[should_panic(expected = "The feature transfer is paused")]
+fn paused_method_panics_for_owner() {
+    let mut contract = contract();
+    contract.pause("transfer".to_string());
+    transfer(&mut contract);
+}
+
+#[test]
+fn owner_calls_paused_method_with_exception() {
+    let mut contract = contract();
+    transfer(&mut contract);
+    contract.pause("transfer".to_string());
+    assert!(contract.is_paused("transfer".to_string()));
+    recover(&mut contract);
+    assert_eq!(contract.transfers, 0);
+}
+
+#[test]
+#[should_panic(expected = "The feature transfer is paused")]
+fn paused_method_with_exception_panics_for_users() {
+    let mut contract = contract();
+    contract.pause("transfer".to_string());
+    set_predecessor(accounts(1));
+    recover(&mut contract);
+}
+
+#[test]
+fn unpause_restores_method() {
+    let mut contract = contract();
+    contract.pause("transfer".to_string());
+    contract.unpause("transfer".to_string());
+    assert!(!contract.is_paused("transfer".to_string()));
+    set_predecessor(accounts(1));
+    transfer(&mut contract);
+    assert_eq!(contract.transfers, 1);
+}
+
+#[test]
+#[should_panic(expected = "Only the owner can call this method")]
+fn only_owner_pauses() {
+    let mut contract = contract();
+    set_predecessor(accounts(1));
+    contract.pause("transfer".to_string());
+}
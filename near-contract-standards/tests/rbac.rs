@@ -0,0 +1,104 @@
+//! A contract using `impl_rbac!`, with methods marked `#[access_control]`.
+
+use contract::Role;
+use near_contract_standards::rbac::Rbac;
+use near_sdk::json_types::U128;
+use near_sdk::test_utils::{accounts, VMContextBuilder};
+use near_sdk::{testing_env, AccountId};
+
+mod contract {
+    use near_contract_standards::rbac::Rbac;
+    use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+    use near_sdk::serde::{Deserialize, Serialize};
+    use near_sdk::{near_bindgen, AccountId, PanicOnDefault};
+
+    #[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Debug, PartialEq)]
+    #[serde(crate = "near_sdk::serde")]
+    pub enum Role {
+        Admin,
+        Minter,
+        Pauser,
+    }
+
+    #[near_bindgen]
+    #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+    pub struct Contract {
+        pub rbac: Rbac<Role>,
+        pub supply: u64,
+    }
+
+    near_contract_standards::impl_rbac!(Contract, rbac, Role);
+
+    #[near_bindgen]
+    impl Contract {
+        // `#[access_control]` checks the roles in the method exported to wasm, so unit tests call
+        // `assert_any_role` themselves.
+        #[access_control(roles(Role::Minter, Role::Admin))]
+        pub fn mint(&mut self, amount: u64) {
+            self.supply += amount;
+        }
+    }
+}
+
+fn set_predecessor(predecessor: AccountId) {
+    testing_env!(VMContextBuilder::new().predecessor_account_id(predecessor).build());
+}
+
+/// Alice is the admin of the minters and pausers.
+fn contract() -> contract::Contract {
+    set_predecessor(accounts(0));
+    let mut rbac = Rbac::new(b"r".to_vec());
+    rbac.internal_set_admin_role(&Role::Minter, &Role::Admin);
+    rbac.internal_set_admin_role(&Role::Pauser, &Role::Admin);
+    rbac.internal_grant_role(&Role::Admin, &accounts(0));
+    contract::Contract { rbac, supply: 0 }
+}
+
+fn mint(contract: &mut contract::Contract, amount: u64) {
+    contract.assert_any_role(&[Role::Minter, Role::Admin]);
+    contract.mint(amount);
+}
+
+#[test]
+fn either_role_calls_guarded_method() {
+    let mut contract = contract();
+    mint(&mut contract, 1);
+    assert!(contract.grant_role(Role::Minter, accounts(1)));
+    set_predecessor(accounts(1));
+    mint(&mut contract, 2);
+    assert_eq!(contract.supply, 3);
+}
+
+#[test]
+#[should_panic(expected = "The account bob doesn't have a required role")]
+fn other_role_cant_call_guarded_method() {
+    let mut contract = contract();
+    contract.grant_role(Role::Pauser, accounts(1));
+    set_predecessor(accounts(1));
+    mint(&mut contract, 1);
+}
+
+#[test]
+#[should_panic(expected = "The account bob isn't an admin of the role")]
+fn non_admin_cant_grant() {
+    let mut contract = contract();
+    contract.grant_role(Role::Minter, accounts(1));
+    set_predecessor(accounts(1));
+    contract.grant_role(Role::Minter, accounts(2));
+}
+
+#[test]
+fn members_paginate() {
+    let mut contract = contract();
+    for i in 1..6 {
+        contract.grant_role(Role::Minter, accounts(i));
+    }
+    assert!(contract.revoke_role(Role::Minter, accounts(3)));
+    let mut members = contract.role_members(Role::Minter, None, Some(2));
+    members.extend(contract.role_members(Role::Minter, Some(U128(2)), Some(2)));
+    assert_eq!(members.len(), 4);
+    members.sort();
+    assert_eq!(members, [accounts(1), accounts(2), accounts(4), accounts(5)]);
+    assert_eq!(contract.admin_role_of(Role::Minter), Some(Role::Admin));
+    assert_eq!(contract.admin_role_of(Role::Admin), None);
+}
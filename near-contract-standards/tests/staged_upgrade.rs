@@ -0,0 +1,108 @@
+//! A contract using `impl_upgradable!` with `impl_ownable!`.
+
+use near_contract_standards::ownable::Ownable;
+use near_contract_standards::upgrade::StagedUpgrade;
+use near_sdk::json_types::{Base58CryptoHash, U64};
+use near_sdk::mock::VmAction;
+use near_sdk::test_utils::{accounts, get_created_receipts, VMContextBuilder};
+use near_sdk::{
+    env, testing_env, AccountId, Gas, PromiseResult, RuntimeFeesConfig, Timestamp, VMConfig,
+};
+
+const TIMELOCK: u64 = 1_000;
+const MIGRATE_GAS: Gas = Gas(50_000_000_000_000);
+
+mod contract {
+    use near_contract_standards::ownable::Ownable;
+    use near_contract_standards::upgrade::StagedUpgrade;
+    use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+    use near_sdk::{near_bindgen, AccountId, PanicOnDefault, Promise};
+
+    #[near_bindgen]
+    #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+    pub struct Contract {
+        pub ownable: Ownable,
+        pub upgrade: StagedUpgrade,
+    }
+
+    near_contract_standards::impl_ownable!(Contract, ownable);
+    near_contract_standards::impl_upgradable!(Contract, upgrade);
+}
+
+fn set_context(predecessor: AccountId, block_timestamp: Timestamp) {
+    testing_env!(VMContextBuilder::new()
+        .current_account_id(accounts(0))
+        .predecessor_account_id(predecessor)
+        .block_timestamp(block_timestamp)
+        .build());
+}
+
+fn contract() -> contract::Contract {
+    set_context(accounts(0), 100);
+    contract::Contract {
+        ownable: Ownable::new(b"o".to_vec(), accounts(0)),
+        upgrade: StagedUpgrade::new(b"u".to_vec(), TIMELOCK).with_migration(
+            "migrate",
+            vec![],
+            MIGRATE_GAS,
+        ),
+    }
+}
+
+#[test]
+fn stage_and_deploy() {
+    let mut contract = contract();
+    contract.up_stage_code(b"v2".to_vec());
+    assert_eq!(
+        contract.up_staged_code_hash(),
+        Some(Base58CryptoHash::from(env::sha256_array(b"v2")))
+    );
+    assert_eq!(contract.up_deployable_at(), Some(U64(1_100)));
+
+    // Anyone can deploy the staged code once the timelock has elapsed.
+    set_context(accounts(1), 1_100);
+    drop(contract.up_deploy_code());
+    assert_eq!(
+        get_created_receipts()[0].actions,
+        [
+            VmAction::DeployContract { code: b"v2".to_vec() },
+            VmAction::FunctionCall {
+                function_name: "migrate".to_string(),
+                args: vec![],
+                gas: MIGRATE_GAS,
+                deposit: 0,
+            },
+        ]
+    );
+    // The code stays staged until the callback confirms the deployment.
+    assert!(contract.up_staged_code_hash().is_some());
+    testing_env!(
+        VMContextBuilder::new()
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(0))
+            .build(),
+        VMConfig::test(),
+        RuntimeFeesConfig::test(),
+        Default::default(),
+        vec![PromiseResult::Successful(vec![])],
+    );
+    assert!(contract.up_on_deploy_code());
+    assert_eq!(contract.up_staged_code_hash(), None);
+}
+
+#[test]
+#[should_panic(expected = "Only the owner can call this method")]
+fn only_owner_stages_code() {
+    let mut contract = contract();
+    set_context(accounts(1), 100);
+    contract.up_stage_code(b"v2".to_vec());
+}
+
+#[test]
+#[should_panic(expected = "The staged code can't be deployed before 1100")]
+fn premature_deploy() {
+    let mut contract = contract();
+    contract.up_stage_code(b"v2".to_vec());
+    set_context(accounts(1), 1_000);
+    contract.up_deploy_code();
+}
@@ -0,0 +1,92 @@
+//! Builds the fungible token example to wasm and checks the standards embedded in its
+//! `near_standards` custom section.
+//!
+//! This needs the `wasm32-unknown-unknown` target and builds a separate workspace, so it is
+//! ignored by default. Run it with:
+//!
+//! ```sh
+//! cargo test -p near-contract-standards --test standards_section -- --ignored
+//! ```
+
+use near_contract_standards::standards::{Standard, NEP141, NEP145, SECTION_NAME};
+use std::path::PathBuf;
+use std::process::Command;
+
+fn read_leb128(bytes: &[u8], pos: &mut usize) -> usize {
+    let mut value = 0;
+    let mut shift = 0;
+    loop {
+        let byte = bytes[*pos];
+        *pos += 1;
+        value |= ((byte & 0x7f) as usize) << shift;
+        if byte & 0x80 == 0 {
+            return value;
+        }
+        shift += 7;
+    }
+}
+
+/// Returns the contents of the custom sections of `wasm` named `name`.
+fn custom_sections<'a>(wasm: &'a [u8], name: &str) -> Vec<&'a [u8]> {
+    assert_eq!(&wasm[..4], b"\0asm", "not a wasm module");
+    let mut sections = Vec::new();
+    let mut pos = 8;
+    while pos < wasm.len() {
+        let id = wasm[pos];
+        pos += 1;
+        let size = read_leb128(wasm, &mut pos);
+        let end = pos + size;
+        if id == 0 {
+            let mut name_pos = pos;
+            let name_len = read_leb128(wasm, &mut name_pos);
+            if &wasm[name_pos..name_pos + name_len] == name.as_bytes() {
+                sections.push(&wasm[name_pos + name_len..end]);
+            }
+        }
+        pos = end;
+    }
+    sections
+}
+
+#[test]
+#[ignore]
+fn fungible_token_section() {
+    let workspace = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("../examples/fungible-token")
+        .canonicalize()
+        .unwrap();
+    let status = Command::new(std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string()))
+        .current_dir(&workspace)
+        .args(&["build", "-p", "fungible-token", "--target", "wasm32-unknown-unknown", "--release"])
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let target = std::env::var_os("CARGO_TARGET_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| workspace.join("target"));
+    let wasm =
+        std::fs::read(target.join("wasm32-unknown-unknown/release/fungible_token.wasm")).unwrap();
+
+    let sections = custom_sections(&wasm, SECTION_NAME);
+    assert_eq!(sections.len(), 1);
+    let contents = std::str::from_utf8(sections[0]).unwrap();
+    let mut standards: Vec<Standard> =
+        contents.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+    standards.sort();
+    assert_eq!(standards, [NEP141, NEP145]);
+}
+
+#[test]
+fn parses_custom_sections() {
+    let mut wasm = b"\0asm\x01\0\0\0".to_vec();
+    // A type section, which is skipped.
+    wasm.extend_from_slice(&[1, 1, 0]);
+    for contents in &[&b"a\n"[..], b"b\n"] {
+        wasm.extend_from_slice(&[0, 3 + contents.len() as u8, 2]);
+        wasm.extend_from_slice(b"ns");
+        wasm.extend_from_slice(contents);
+    }
+    assert_eq!(custom_sections(&wasm, "ns"), [&b"a\n"[..], b"b\n"]);
+    assert!(custom_sections(&wasm, "other").is_empty());
+}
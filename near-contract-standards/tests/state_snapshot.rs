@@ -0,0 +1,226 @@
+//! Golden snapshots of the storage written by the standards.
+//!
+//! Each test runs a fixed scenario against the mocked blockchain, writes the token struct as the
+//! contract state and compares every storage key and value with a checked-in snapshot in
+//! `tests/state_snapshots`, failing with a diff of each entry that was added, removed or changed.
+//! The snapshots record the layout of 4.0.0-pre.6, so a failure means that contracts deployed
+//! with it can't read their state anymore.
+//!
+//! **A change to a snapshot is a state-breaking change.** Contracts deployed with the previous
+//! layout can no longer read their state after upgrading to the new version of the standards,
+//! unless they migrate it. Only regenerate the snapshots for an intentional layout change, and
+//! document it in the CHANGELOG. To regenerate them, run:
+//!
+//! ```sh
+//! NEAR_STATE_SNAPSHOT_UPDATE=1 cargo test -p near-contract-standards --test state_snapshot
+//! ```
+//!
+//! The scenarios must stay deterministic: fixed account ids and token ids, no randomness and no
+//! dependency on the block timestamp.
+
+use near_contract_standards::fungible_token::core::FungibleTokenCore;
+use near_contract_standards::fungible_token::FungibleToken;
+use near_contract_standards::non_fungible_token::approval::NonFungibleTokenApproval;
+use near_contract_standards::non_fungible_token::core::NonFungibleTokenCore;
+use near_contract_standards::non_fungible_token::metadata::TokenMetadata;
+use near_contract_standards::non_fungible_token::NonFungibleToken;
+use near_contract_standards::storage_management::StorageManagement;
+use near_sdk::borsh::BorshSerialize;
+use near_sdk::json_types::{Base64VecU8, U128};
+use near_sdk::test_utils::{accounts, storage_snapshot, VMContextBuilder};
+use near_sdk::{env, testing_env, AccountId, Balance, ONE_NEAR, ONE_YOCTO};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::PathBuf;
+
+const UPDATE_VAR: &str = "NEAR_STATE_SNAPSHOT_UPDATE";
+
+const HEADER: &str = "\
+# GOLDEN STATE SNAPSHOT, DO NOT EDIT BY HAND.
+#
+# Any change to this file changes the on-chain storage layout of the standards and breaks
+# contracts deployed with the previous layout on upgrade. See tests/state_snapshot.rs before
+# regenerating it.
+#
+# One entry per line: hex-encoded storage key, a space, hex-encoded value.
+";
+
+type Snapshot = BTreeMap<Vec<u8>, Vec<u8>>;
+
+fn snapshot_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("state_snapshots")
+        .join(format!("{}.txt", name))
+}
+
+fn set_context(predecessor: AccountId, attached_deposit: Balance) {
+    testing_env!(VMContextBuilder::new()
+        .current_account_id(accounts(0))
+        .predecessor_account_id(predecessor)
+        .attached_deposit(attached_deposit)
+        .build());
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    let mut hex = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        write!(hex, "{:02x}", byte).unwrap();
+    }
+    hex
+}
+
+fn from_hex(hex: &str) -> Vec<u8> {
+    assert!(hex.len() % 2 == 0, "odd number of hex digits in {:?}", hex);
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).expect("invalid hex digit"))
+        .collect()
+}
+
+fn format_snapshot(snapshot: &Snapshot) -> String {
+    let mut text = HEADER.to_string();
+    for (key, value) in snapshot {
+        writeln!(text, "{} {}", to_hex(key), to_hex(value)).unwrap();
+    }
+    text
+}
+
+fn parse_snapshot(text: &str) -> Snapshot {
+    text.lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let (key, value) = line.split_at(line.find(' ').expect("missing space in entry"));
+            (from_hex(key), from_hex(&value[1..]))
+        })
+        .collect()
+}
+
+/// Returns a line for every entry which was added, removed or changed.
+fn diff(expected: &Snapshot, current: &Snapshot) -> Vec<String> {
+    let mut lines = Vec::new();
+    for (key, value) in expected {
+        match current.get(key) {
+            None => lines.push(format!("- {} {}", to_hex(key), to_hex(value))),
+            Some(current_value) if current_value != value => {
+                lines.push(format!("- {} {}", to_hex(key), to_hex(value)));
+                lines.push(format!("+ {} {}", to_hex(key), to_hex(current_value)));
+            }
+            Some(_) => {}
+        }
+    }
+    for (key, value) in current.iter().filter(|(key, _)| !expected.contains_key(*key)) {
+        lines.push(format!("+ {} {}", to_hex(key), to_hex(value)));
+    }
+    lines
+}
+
+/// Writes `state` as the contract state and compares the whole storage with the snapshot
+/// `name`.
+fn check_snapshot<T: BorshSerialize>(name: &str, state: &T) {
+    env::state_write(state);
+    let current = storage_snapshot();
+    let path = snapshot_path(name);
+
+    if std::env::var_os(UPDATE_VAR).is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, format_snapshot(&current)).unwrap();
+        return;
+    }
+
+    let text = std::fs::read_to_string(&path).unwrap_or_else(|err| {
+        panic!("Cannot read {}: {}. Run with {}=1 to write it.", path.display(), err, UPDATE_VAR)
+    });
+    let expected = parse_snapshot(&text);
+    let lines = diff(&expected, &current);
+    assert!(
+        lines.is_empty(),
+        "Storage layout deviates from {}:\n  {}\nThis breaks deployed contracts on upgrade. If \
+         the change is intentional, run with {}=1 to update the snapshot.",
+        path.display(),
+        lines.join("\n  "),
+        UPDATE_VAR
+    );
+}
+
+fn metadata(title: &str) -> TokenMetadata {
+    TokenMetadata {
+        title: Some(title.to_string()),
+        description: Some("description".to_string()),
+        media: Some("https://example.com/media.png".to_string()),
+        media_hash: Some(Base64VecU8::from(vec![0; 32])),
+        copies: Some(1),
+        issued_at: None,
+        expires_at: None,
+        starts_at: None,
+        updated_at: None,
+        extra: None,
+        reference: None,
+        reference_hash: None,
+    }
+}
+
+#[test]
+fn non_fungible_token_state() {
+    set_context(accounts(0), ONE_NEAR);
+    let mut token = NonFungibleToken::new(
+        b"o".to_vec(),
+        accounts(0),
+        Some(b"m".to_vec()),
+        Some(b"e".to_vec()),
+        Some(b"a".to_vec()),
+    );
+    for token_id in &["0", "1", "2"] {
+        token.internal_mint(token_id.to_string(), accounts(1), Some(metadata(token_id)));
+    }
+
+    set_context(accounts(1), ONE_NEAR);
+    token.nft_approve("1".to_string(), accounts(3), None);
+    token.nft_approve("2".to_string(), accounts(3), None);
+
+    // Transferring a token clears its approvals.
+    set_context(accounts(1), ONE_YOCTO);
+    token.nft_transfer(accounts(2), "1".to_string(), None, None);
+
+    check_snapshot("non_fungible_token", &token);
+}
+
+#[test]
+fn fungible_token_state() {
+    set_context(accounts(0), 0);
+    let mut token = FungibleToken::new(b"t".to_vec());
+
+    for account in &[accounts(1), accounts(2)] {
+        set_context(account.clone(), ONE_NEAR);
+        token.storage_deposit(None, None);
+    }
+    token.internal_deposit(&accounts(1), 1_000);
+
+    set_context(accounts(1), ONE_YOCTO);
+    token.ft_transfer(accounts(2), U128(250), None);
+
+    check_snapshot("fungible_token", &token);
+}
+
+#[test]
+fn diff_reports_changes() {
+    let expected: Snapshot =
+        vec![(vec![1], vec![10]), (vec![2], vec![20]), (vec![3], vec![30])].into_iter().collect();
+    let mut current = expected.clone();
+    assert!(diff(&expected, &current).is_empty());
+
+    current.remove(&vec![1]);
+    current.insert(vec![2], vec![21]);
+    current.insert(vec![4, 0xff], vec![]);
+    assert_eq!(diff(&expected, &current), vec!["- 01 0a", "- 02 14", "+ 02 15", "+ 04ff "]);
+}
+
+#[test]
+fn snapshot_format_round_trip() {
+    let snapshot: Snapshot =
+        vec![(b"STATE".to_vec(), vec![0, 1, 0xab]), (vec![0xff], vec![])].into_iter().collect();
+    let text = format_snapshot(&snapshot);
+    assert!(text.starts_with("# GOLDEN STATE SNAPSHOT"));
+    assert!(text.ends_with("5354415445 0001ab\nff \n"));
+    assert_eq!(parse_snapshot(&text), snapshot);
+}
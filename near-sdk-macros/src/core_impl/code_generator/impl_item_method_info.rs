@@ -1,9 +1,10 @@
 use crate::core_impl::info_extractor::{
-    AttrSigInfo, ImplItemMethodInfo, InputStructType, MethodType, SerializerType,
+    AccessControlAttr, AttrSigInfo, DepositHandler, ImplItemMethodInfo, InputStructType,
+    MethodType, PauseAttr, SerializerType,
 };
 use proc_macro2::TokenStream as TokenStream2;
-use quote::quote;
-use syn::{ReturnType, Signature};
+use quote::{format_ident, quote};
+use syn::{ReturnType, Signature, Type};
 
 impl ImplItemMethodInfo {
     /// Generate wrapper method for the given method of the contract.
@@ -52,7 +53,13 @@ impl ImplItemMethodInfo {
             result_serializer,
             method_type,
             is_payable,
+            deposit_handler,
             is_private,
+            is_direct_call_only,
+            is_only_owner,
+            is_handles_result,
+            pause,
+            access_control,
             ..
         } = attr_signature_info;
         let deposit_check = if *is_payable || matches!(method_type, &MethodType::View) {
@@ -67,6 +74,44 @@ impl ImplItemMethodInfo {
                 }
             }
         };
+        // Handles the deposit in a hidden method of the contract, generated for every target so
+        // that the handler signature is checked when building for any target, and the handling can
+        // be called from unit tests.
+        let deposit_method = format_ident!("__near_handle_deposit_{}", ident);
+        let deposit_method_body = match deposit_handler {
+            Some(DepositHandler::Handler(path)) => {
+                let handler = handler_path(path, struct_type);
+                quote! {
+                    const HANDLER: fn(&mut #struct_type, near_sdk::Balance) = #handler;
+                    HANDLER(self, near_sdk::env::attached_deposit());
+                }
+            }
+            Some(DepositHandler::ForwardTo(expr)) => quote! {
+                let attached_deposit = near_sdk::env::attached_deposit();
+                if attached_deposit > 0 {
+                    near_sdk::Promise::new(::core::clone::Clone::clone(&#expr))
+                        .transfer(attached_deposit);
+                }
+            },
+            None => TokenStream2::new(),
+        };
+        let (deposit_method_impl, deposit_handling) = if deposit_handler.is_some() {
+            (
+                quote! {
+                    impl #struct_type {
+                        #[doc(hidden)]
+                        pub fn #deposit_method(&mut self) {
+                            #deposit_method_body
+                        }
+                    }
+                },
+                quote! {
+                    contract.#deposit_method();
+                },
+            )
+        } else {
+            (TokenStream2::new(), TokenStream2::new())
+        };
         let is_private_check = if *is_private {
             let error = format!("Method {} is private", ident);
             quote! {
@@ -77,6 +122,47 @@ impl ImplItemMethodInfo {
         } else {
             quote! {}
         };
+        let direct_call_check = if *is_direct_call_only {
+            let error = format!("Method {} must be called directly by the signer", ident);
+            quote! {
+                if near_sdk::env::signer_account_id() != near_sdk::env::predecessor_account_id() {
+                    near_sdk::env::panic_str(#error);
+                }
+            }
+        } else {
+            quote! {}
+        };
+        // Resolves to the `assert_owner` method of the contract, such as the one generated by
+        // `impl_ownable!` of near-contract-standards.
+        let owner_check = if *is_only_owner {
+            quote! {
+                contract.assert_owner();
+            }
+        } else {
+            quote! {}
+        };
+        // Resolves to the `assert_not_paused` method of the contract, and to its `owner` method
+        // with `except(owner)`, such as the ones generated by `impl_pausable!` and `impl_ownable!`
+        // of near-contract-standards.
+        let pause_check = match pause {
+            Some(PauseAttr { feature, except_owner: false }) => quote! {
+                contract.assert_not_paused(#feature);
+            },
+            Some(PauseAttr { feature, except_owner: true }) => quote! {
+                if contract.owner() != ::core::option::Option::Some(near_sdk::env::predecessor_account_id()) {
+                    contract.assert_not_paused(#feature);
+                }
+            },
+            None => quote! {},
+        };
+        // Resolves to the `assert_any_role` method of the contract, such as the one generated by
+        // `impl_rbac!` of near-contract-standards.
+        let role_check = match access_control {
+            Some(AccessControlAttr { roles }) => quote! {
+                contract.assert_any_role(&[#(#roles),*]);
+            },
+            None => quote! {},
+        };
         let body = if matches!(method_type, &MethodType::Init) {
             if matches!(returns, ReturnType::Default) {
                 return syn::Error::new(
@@ -133,7 +219,11 @@ impl ImplItemMethodInfo {
             match returns {
                 ReturnType::Default => quote! {
                     #contract_deser
+                    #pause_check
+                    #owner_check
+                    #role_check
                     #method_invocation;
+                    #deposit_handling
                     #contract_ser
                 },
                 ReturnType::Type(_, _) => {
@@ -145,12 +235,37 @@ impl ImplItemMethodInfo {
                             let result = near_sdk::borsh::BorshSerialize::try_to_vec(&result).expect("Failed to serialize the return value using Borsh.");
                         },
                     };
-                    quote! {
-                    #contract_deser
-                    let result = #method_invocation;
-                    #value_ser
-                    near_sdk::env::value_return(&result);
-                    #contract_ser
+                    if *is_handles_result {
+                        // The state is only written if the method returns `Ok`, as the execution
+                        // is aborted with the error otherwise.
+                        quote! {
+                        #contract_deser
+                        #pause_check
+                        #owner_check
+                        #role_check
+                        let result = #method_invocation;
+                        match result {
+                            ::core::result::Result::Ok(result) => {
+                                #value_ser
+                                near_sdk::env::value_return(&result);
+                                #deposit_handling
+                                #contract_ser
+                            }
+                            ::core::result::Result::Err(err) => near_sdk::FunctionError::panic(&err),
+                        }
+                        }
+                    } else {
+                        quote! {
+                        #contract_deser
+                        #pause_check
+                        #owner_check
+                        #role_check
+                        let result = #method_invocation;
+                        #value_ser
+                        near_sdk::env::value_return(&result);
+                        #deposit_handling
+                        #contract_ser
+                        }
                     }
                 }
             }
@@ -162,12 +277,14 @@ impl ImplItemMethodInfo {
             }
         });
         quote! {
+            #deposit_method_impl
             #non_bindgen_attrs
             #[cfg(target_arch = "wasm32")]
             #[no_mangle]
             pub extern "C" fn #ident() {
                 #panic_hook
                 #is_private_check
+                #direct_call_check
                 #deposit_check
                 #arg_struct
                 #arg_parsing
@@ -237,6 +354,18 @@ impl ImplItemMethodInfo {
     }
 }
 
+/// Resolves a leading `Self` in a deposit handler path to the contract type, since the handler is
+/// called from a free function.
+fn handler_path(path: &syn::Path, struct_type: &Type) -> TokenStream2 {
+    let mut segments = path.segments.iter();
+    match segments.next() {
+        Some(first) if path.leading_colon.is_none() && first.ident == "Self" => {
+            quote! { #struct_type #(:: #segments)* }
+        }
+        _ => quote! { #path },
+    }
+}
+
 fn json_serialize(attr_signature_info: &AttrSigInfo) -> TokenStream2 {
     let args: TokenStream2 = attr_signature_info
         .input_args()
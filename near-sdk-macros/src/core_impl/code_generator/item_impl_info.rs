@@ -685,6 +685,108 @@ mod tests {
         assert_eq!(expected.to_string(), actual.to_string());
     }
 
+    #[test]
+    fn payable_with_handler() {
+        let impl_type: Type = syn::parse_str("Hello").unwrap();
+        let mut method: ImplItemMethod = syn::parse_str(r#"#[payable(handler = "Self::record_deposit")] pub fn method(&mut self) -> u64 { 1 }"#).unwrap();
+        let method_info = ImplItemMethodInfo::new(&mut method, impl_type).unwrap();
+        let actual = method_info.method_wrapper();
+        let expected = quote!(
+            impl Hello {
+                #[doc(hidden)]
+                pub fn __near_handle_deposit_method(&mut self) {
+                    const HANDLER: fn(&mut Hello, near_sdk::Balance) = Hello::record_deposit;
+                    HANDLER(self, near_sdk::env::attached_deposit());
+                }
+            }
+            #[cfg(target_arch = "wasm32")]
+            #[no_mangle]
+            pub extern "C" fn method() {
+                near_sdk::env::setup_panic_hook();
+                let mut contract: Hello = near_sdk::env::state_read().unwrap_or_default();
+                let result = contract.method();
+                let result = near_sdk::serde_json::to_vec(&result).expect("Failed to serialize the return value using JSON.");
+                near_sdk::env::value_return(&result);
+                contract.__near_handle_deposit_method();
+                near_sdk::env::state_write(&contract);
+            }
+        );
+        assert_eq!(expected.to_string(), actual.to_string());
+    }
+
+    #[test]
+    fn payable_forward_to() {
+        let impl_type: Type = syn::parse_str("Hello").unwrap();
+        let mut method: ImplItemMethod = syn::parse_str(r#"#[payable(forward_to = "self.treasury_id")] pub fn method(&mut self) { }"#).unwrap();
+        let method_info = ImplItemMethodInfo::new(&mut method, impl_type).unwrap();
+        let actual = method_info.method_wrapper();
+        let expected = quote!(
+            impl Hello {
+                #[doc(hidden)]
+                pub fn __near_handle_deposit_method(&mut self) {
+                    let attached_deposit = near_sdk::env::attached_deposit();
+                    if attached_deposit > 0 {
+                        near_sdk::Promise::new(::core::clone::Clone::clone(&self.treasury_id))
+                            .transfer(attached_deposit);
+                    }
+                }
+            }
+            #[cfg(target_arch = "wasm32")]
+            #[no_mangle]
+            pub extern "C" fn method() {
+                near_sdk::env::setup_panic_hook();
+                let mut contract: Hello = near_sdk::env::state_read().unwrap_or_default();
+                contract.method();
+                contract.__near_handle_deposit_method();
+                near_sdk::env::state_write(&contract);
+            }
+        );
+        assert_eq!(expected.to_string(), actual.to_string());
+    }
+
+    #[test]
+    fn payable_handler_requires_mut_self() {
+        let impl_type: Type = syn::parse_str("Hello").unwrap();
+        let mut method: ImplItemMethod = syn::parse_str(r#"#[payable(handler = "Self::record_deposit")] pub fn method() { }"#).unwrap();
+        assert!(ImplItemMethodInfo::new(&mut method, impl_type).is_err());
+    }
+
+    #[test]
+    fn handle_result_method() {
+        let impl_type: Type = syn::parse_str("Hello").unwrap();
+        let mut method: ImplItemMethod = syn::parse_str("#[handle_result] pub fn method(&mut self) -> Result<u64, SdkError> { }").unwrap();
+        let method_info = ImplItemMethodInfo::new(&mut method, impl_type).unwrap();
+        let actual = method_info.method_wrapper();
+        let expected = quote!(
+            #[cfg(target_arch = "wasm32")]
+            #[no_mangle]
+            pub extern "C" fn method() {
+                near_sdk::env::setup_panic_hook();
+                if near_sdk::env::attached_deposit() != 0 {
+                    near_sdk::env::panic_str("Method method doesn't accept deposit");
+                }
+                let mut contract: Hello = near_sdk::env::state_read().unwrap_or_default();
+                let result = contract.method();
+                match result {
+                    ::core::result::Result::Ok(result) => {
+                        let result = near_sdk::serde_json::to_vec(&result).expect("Failed to serialize the return value using JSON.");
+                        near_sdk::env::value_return(&result);
+                        near_sdk::env::state_write(&contract);
+                    }
+                    ::core::result::Result::Err(err) => near_sdk::FunctionError::panic(&err),
+                }
+            }
+        );
+        assert_eq!(expected.to_string(), actual.to_string());
+    }
+
+    #[test]
+    fn handle_result_without_result() {
+        let impl_type: Type = syn::parse_str("Hello").unwrap();
+        let mut method: ImplItemMethod = syn::parse_str("#[handle_result] pub fn method(&mut self) { }").unwrap();
+        assert!(ImplItemMethodInfo::new(&mut method, impl_type).is_err());
+    }
+
     #[test]
     fn private_method() {
         let impl_type: Type = syn::parse_str("Hello").unwrap();
@@ -710,6 +812,148 @@ mod tests {
         assert_eq!(expected.to_string(), actual.to_string());
     }
 
+    #[test]
+    fn direct_call_only_method() {
+        let impl_type: Type = syn::parse_str("Hello").unwrap();
+        let mut method: ImplItemMethod = syn::parse_str("#[direct_call_only] pub fn method(&mut self) { }").unwrap();
+        let method_info = ImplItemMethodInfo::new(&mut method, impl_type).unwrap();
+        let actual = method_info.method_wrapper();
+        let expected = quote!(
+            #[cfg(target_arch = "wasm32")]
+            #[no_mangle]
+            pub extern "C" fn method() {
+                near_sdk::env::setup_panic_hook();
+                if near_sdk::env::signer_account_id() != near_sdk::env::predecessor_account_id() {
+                    near_sdk::env::panic_str("Method method must be called directly by the signer");
+                }
+                if near_sdk::env::attached_deposit() != 0 {
+                    near_sdk::env::panic_str("Method method doesn't accept deposit");
+                }
+                let mut contract: Hello = near_sdk::env::state_read().unwrap_or_default();
+                contract.method();
+                near_sdk::env::state_write(&contract);
+            }
+        );
+        assert_eq!(expected.to_string(), actual.to_string());
+    }
+
+    #[test]
+    fn only_owner_method() {
+        let impl_type: Type = syn::parse_str("Hello").unwrap();
+        let mut method: ImplItemMethod = syn::parse_str("#[payable] #[only_owner] pub fn method(&mut self) -> u64 { }").unwrap();
+        let method_info = ImplItemMethodInfo::new(&mut method, impl_type).unwrap();
+        let actual = method_info.method_wrapper();
+        let expected = quote!(
+            #[cfg(target_arch = "wasm32")]
+            #[no_mangle]
+            pub extern "C" fn method() {
+                near_sdk::env::setup_panic_hook();
+                let mut contract: Hello = near_sdk::env::state_read().unwrap_or_default();
+                contract.assert_owner();
+                let result = contract.method();
+                let result = near_sdk::serde_json::to_vec(&result).expect("Failed to serialize the return value using JSON.");
+                near_sdk::env::value_return(&result);
+                near_sdk::env::state_write(&contract);
+            }
+        );
+        assert_eq!(expected.to_string(), actual.to_string());
+    }
+
+    #[test]
+    fn only_owner_view_method() {
+        let impl_type: Type = syn::parse_str("Hello").unwrap();
+        let mut method: ImplItemMethod = syn::parse_str("#[only_owner] pub fn method(&self) { }").unwrap();
+        let actual = ImplItemMethodInfo::new(&mut method, impl_type).map(|_| ()).unwrap_err();
+        assert_eq!(actual.to_string(), "Only owner methods must be mutable (not view)");
+    }
+
+    #[test]
+    fn pause_method() {
+        let impl_type: Type = syn::parse_str("Hello").unwrap();
+        let mut method: ImplItemMethod = syn::parse_str(r#"#[pause(feature = "transfer")] pub fn method(&mut self) { }"#).unwrap();
+        let method_info = ImplItemMethodInfo::new(&mut method, impl_type).unwrap();
+        let actual = method_info.method_wrapper();
+        let expected = quote!(
+            #[cfg(target_arch = "wasm32")]
+            #[no_mangle]
+            pub extern "C" fn method() {
+                near_sdk::env::setup_panic_hook();
+                if near_sdk::env::attached_deposit() != 0 {
+                    near_sdk::env::panic_str("Method method doesn't accept deposit");
+                }
+                let mut contract: Hello = near_sdk::env::state_read().unwrap_or_default();
+                contract.assert_not_paused("transfer");
+                contract.method();
+                near_sdk::env::state_write(&contract);
+            }
+        );
+        assert_eq!(expected.to_string(), actual.to_string());
+    }
+
+    #[test]
+    fn pause_method_except_owner() {
+        let impl_type: Type = syn::parse_str("Hello").unwrap();
+        let mut method: ImplItemMethod = syn::parse_str(r#"#[pause(feature = "transfer", except(owner))] pub fn method(&mut self) { }"#).unwrap();
+        let method_info = ImplItemMethodInfo::new(&mut method, impl_type).unwrap();
+        let actual = method_info.method_wrapper();
+        let expected = quote!(
+            #[cfg(target_arch = "wasm32")]
+            #[no_mangle]
+            pub extern "C" fn method() {
+                near_sdk::env::setup_panic_hook();
+                if near_sdk::env::attached_deposit() != 0 {
+                    near_sdk::env::panic_str("Method method doesn't accept deposit");
+                }
+                let mut contract: Hello = near_sdk::env::state_read().unwrap_or_default();
+                if contract.owner() != ::core::option::Option::Some(near_sdk::env::predecessor_account_id()) {
+                    contract.assert_not_paused("transfer");
+                }
+                contract.method();
+                near_sdk::env::state_write(&contract);
+            }
+        );
+        assert_eq!(expected.to_string(), actual.to_string());
+    }
+
+    #[test]
+    fn pause_without_feature() {
+        let impl_type: Type = syn::parse_str("Hello").unwrap();
+        let mut method: ImplItemMethod = syn::parse_str("#[pause(except(owner))] pub fn method(&mut self) { }").unwrap();
+        let actual = ImplItemMethodInfo::new(&mut method, impl_type).map(|_| ()).unwrap_err();
+        assert_eq!(actual.to_string(), "The pause attribute requires a `feature`.");
+    }
+
+    #[test]
+    fn access_control_method() {
+        let impl_type: Type = syn::parse_str("Hello").unwrap();
+        let mut method: ImplItemMethod = syn::parse_str("#[access_control(roles(Role::Minter, Pauser))] pub fn method(&mut self) { }").unwrap();
+        let method_info = ImplItemMethodInfo::new(&mut method, impl_type).unwrap();
+        let actual = method_info.method_wrapper();
+        let expected = quote!(
+            #[cfg(target_arch = "wasm32")]
+            #[no_mangle]
+            pub extern "C" fn method() {
+                near_sdk::env::setup_panic_hook();
+                if near_sdk::env::attached_deposit() != 0 {
+                    near_sdk::env::panic_str("Method method doesn't accept deposit");
+                }
+                let mut contract: Hello = near_sdk::env::state_read().unwrap_or_default();
+                contract.assert_any_role(&[Role::Minter, Pauser]);
+                contract.method();
+                near_sdk::env::state_write(&contract);
+            }
+        );
+        assert_eq!(expected.to_string(), actual.to_string());
+    }
+
+    #[test]
+    fn access_control_without_roles() {
+        let impl_type: Type = syn::parse_str("Hello").unwrap();
+        let mut method: ImplItemMethod = syn::parse_str("#[access_control(roles())] pub fn method(&mut self) { }").unwrap();
+        let actual = ImplItemMethodInfo::new(&mut method, impl_type).map(|_| ()).unwrap_err();
+        assert_eq!(actual.to_string(), "The access_control attribute requires a role.");
+    }
+
     #[test]
     fn marshall_one_arg() {
         let impl_type: Type = syn::parse_str("Hello").unwrap();
@@ -8,13 +8,17 @@ impl ItemTraitInfo {
         let mut result = TokenStream2::new();
         for method in &self.methods {
             result.extend(method.method_wrapper());
+            result.extend(method.result_type_aliases());
         }
+        let metadata = self.methods.iter().map(|method| method.ext_metadata());
         let mod_name = &self.mod_name;
         quote! {
            pub mod #mod_name {
                 use super::*;
                 use near_sdk::{Gas, Balance, AccountId, Promise};
                 #result
+                /// Names of the methods of the contract, and whether each one is a view method.
+                pub const METHODS: &[near_sdk::ExtMethodMetadata] = &[#(#metadata),*];
             }
         }
     }
@@ -74,6 +78,10 @@ mod tests {
                         __gas,
                     )
                 }
+                #[doc = " Type returned by `merge_sort`."]
+                pub type MergeSortReturn = Vec<u8>;
+                #[doc = " Type of a `#[callback_result]` argument receiving the result of `merge_sort`."]
+                pub type MergeSortResult = std::result::Result<MergeSortReturn, near_sdk::PromiseError>;
                 pub fn merge(__account_id: AccountId, __balance: near_sdk::Balance, __gas: near_sdk::Gas) -> near_sdk::Promise {
                     let args = vec![];
                     near_sdk::Promise::new(__account_id).function_call(
@@ -83,6 +91,15 @@ mod tests {
                         __gas,
                     )
                 }
+                #[doc = " Type returned by `merge`."]
+                pub type MergeReturn = Vec<u8>;
+                #[doc = " Type of a `#[callback_result]` argument receiving the result of `merge`."]
+                pub type MergeResult = std::result::Result<MergeReturn, near_sdk::PromiseError>;
+                /// Names of the methods of the contract, and whether each one is a view method.
+                pub const METHODS: &[near_sdk::ExtMethodMetadata] = &[
+                    near_sdk::ExtMethodMetadata { name: "merge_sort", is_view: true },
+                    near_sdk::ExtMethodMetadata { name: "merge", is_view: true }
+                ];
             }
         };
         assert_eq!(actual.to_string(), expected.to_string());
@@ -125,8 +142,50 @@ mod tests {
                     __gas,
                 )
             }
+            #[doc = " Type returned by `test`."]
+            pub type TestReturn = Vec<String>;
+            #[doc = " Type of a `#[callback_result]` argument receiving the result of `test`."]
+            pub type TestResult = std::result::Result<TestReturn, near_sdk::PromiseError>;
+            /// Names of the methods of the contract, and whether each one is a view method.
+            pub const METHODS: &[near_sdk::ExtMethodMetadata] = &[
+                near_sdk::ExtMethodMetadata { name: "test", is_view: false }
+            ];
         }
         };
         assert_eq!(actual.to_string(), expected.to_string());
     }
+
+    #[test]
+    fn promise_return_types() {
+        let mut t: ItemTrait = syn::parse2(
+            quote!{
+                trait Nft {
+                    fn nft_transfer(&mut self, token_id: String);
+                    fn nft_transfer_call(&mut self, token_id: String) -> PromiseOrValue<bool>;
+                    fn nft_forward(&mut self) -> Promise;
+                }
+            }
+        ).unwrap();
+        let info = ItemTraitInfo::new(&mut t, None).unwrap();
+        let aliases: Vec<String> =
+            info.methods.iter().map(|method| method.result_type_aliases().to_string()).collect();
+
+        let expected = [
+            quote! {
+                #[doc = " Type returned by `nft_transfer`."]
+                pub type NftTransferReturn = ();
+                #[doc = " Type of a `#[callback_result]` argument receiving the result of `nft_transfer`."]
+                pub type NftTransferResult = std::result::Result<NftTransferReturn, near_sdk::PromiseError>;
+            },
+            quote! {
+                #[doc = " Type returned by `nft_transfer_call`."]
+                pub type NftTransferCallReturn = bool;
+                #[doc = " Type of a `#[callback_result]` argument receiving the result of `nft_transfer_call`."]
+                pub type NftTransferCallResult = std::result::Result<NftTransferCallReturn, near_sdk::PromiseError>;
+            },
+            quote! {},
+        ];
+        assert_eq!(aliases, expected.iter().map(|e| e.to_string()).collect::<Vec<_>>());
+        assert!(info.methods.iter().all(|method| !method.ext_metadata().to_string().contains("true")));
+    }
 }
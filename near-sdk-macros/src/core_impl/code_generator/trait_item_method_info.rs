@@ -1,9 +1,11 @@
 use crate::core_impl::{
-    info_extractor::{InputStructType, SerializerType, TraitItemMethodInfo},
+    info_extractor::{InputStructType, MethodType, SerializerType, TraitItemMethodInfo},
     AttrSigInfo,
 };
+use inflector::Inflector;
 use proc_macro2::TokenStream as TokenStream2;
-use quote::quote;
+use quote::{format_ident, quote};
+use syn::{GenericArgument, PathArguments, ReturnType, Type};
 
 impl TraitItemMethodInfo {
     /// Generate code that wraps the method.
@@ -29,6 +31,43 @@ impl TraitItemMethodInfo {
         }
     }
 
+    /// Generate the aliases of the type returned by the method and of the `#[callback_result]`
+    /// argument receiving it. Methods returning a `Promise` have no aliases, as the type of their
+    /// result isn't declared.
+    pub fn result_type_aliases(&self) -> TokenStream2 {
+        let ident = &self.attr_sig_info.ident;
+        let return_ty = match &self.attr_sig_info.returns {
+            ReturnType::Default => quote! { () },
+            ReturnType::Type(_, ty) => match resolved_type(ty) {
+                Some(ty) => quote! { #ty },
+                None => return TokenStream2::new(),
+            },
+        };
+        let name = ident.to_string().to_pascal_case();
+        let return_alias = format_ident!("{}Return", name);
+        let result_alias = format_ident!("{}Result", name);
+        let return_doc = format!(" Type returned by `{}`.", ident);
+        let result_doc = format!(
+            " Type of a `#[callback_result]` argument receiving the result of `{}`.",
+            ident
+        );
+        quote! {
+            #[doc = #return_doc]
+            pub type #return_alias = #return_ty;
+            #[doc = #result_doc]
+            pub type #result_alias = std::result::Result<#return_alias, near_sdk::PromiseError>;
+        }
+    }
+
+    /// Generate the metadata of the method.
+    pub fn ext_metadata(&self) -> TokenStream2 {
+        let name = &self.ident_byte_str;
+        let is_view = matches!(self.attr_sig_info.method_type, MethodType::View);
+        quote! {
+            near_sdk::ExtMethodMetadata { name: #name, is_view: #is_view }
+        }
+    }
+
     pub fn generate_serialier(
         attr_sig_info: &AttrSigInfo,
         serializer: &SerializerType,
@@ -56,3 +95,25 @@ impl TraitItemMethodInfo {
         }
     }
 }
+
+/// Returns the type of the value a method declared to return `ty` resolves to: `T` for
+/// `PromiseOrValue<T>`, `ty` itself for other types, and `None` for `Promise`.
+fn resolved_type(ty: &Type) -> Option<&Type> {
+    let segment = match ty {
+        Type::Path(path) => match path.path.segments.last() {
+            Some(segment) => segment,
+            None => return Some(ty),
+        },
+        _ => return Some(ty),
+    };
+    match (segment.ident.to_string().as_str(), &segment.arguments) {
+        ("Promise", PathArguments::None) => None,
+        ("PromiseOrValue", PathArguments::AngleBracketed(args)) if args.args.len() == 1 => {
+            match args.args.first() {
+                Some(GenericArgument::Type(inner)) => Some(inner),
+                _ => Some(ty),
+            }
+        }
+        _ => Some(ty),
+    }
+}
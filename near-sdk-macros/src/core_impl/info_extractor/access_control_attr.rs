@@ -0,0 +1,32 @@
+use proc_macro2::Ident;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{Error, Path, Token};
+
+/// Arguments of `#[access_control(roles(...))]`.
+pub struct AccessControlAttr {
+    /// Roles allowed to call the method, any of them is enough.
+    pub roles: Vec<Path>,
+}
+
+impl Parse for AccessControlAttr {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let content;
+        let _paren_token = syn::parenthesized!(content in input);
+        let ident: Ident = content.parse()?;
+        if ident != "roles" {
+            return Err(Error::new(
+                ident.span(),
+                "Unsupported access_control attribute, expected `roles`.",
+            ));
+        }
+        let roles_content;
+        let _paren_token = syn::parenthesized!(roles_content in content);
+        let roles: Punctuated<Path, Token![,]> =
+            roles_content.parse_terminated(Path::parse_mod_style)?;
+        if roles.is_empty() {
+            return Err(Error::new(ident.span(), "The access_control attribute requires a role."));
+        }
+        Ok(Self { roles: roles.into_iter().collect() })
+    }
+}
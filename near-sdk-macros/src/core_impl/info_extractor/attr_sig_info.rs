@@ -1,4 +1,7 @@
-use super::{ArgInfo, BindgenArgType, InitAttr, MethodType, SerializerAttr, SerializerType};
+use super::{
+    AccessControlAttr, ArgInfo, BindgenArgType, DepositHandler, InitAttr, MethodType, PauseAttr,
+    PayableAttr, SerializerAttr, SerializerType,
+};
 use proc_macro2::Span;
 use quote::ToTokens;
 use syn::spanned::Spanned;
@@ -16,8 +19,20 @@ pub struct AttrSigInfo {
     pub method_type: MethodType,
     /// Whether method accepting $NEAR.
     pub is_payable: bool,
+    /// How the attached deposit is handled after a payable method completes, if at all.
+    pub deposit_handler: Option<DepositHandler>,
     /// Whether method can accept calls from self (current account)
     pub is_private: bool,
+    /// Whether method can only be called directly by the signer of the transaction.
+    pub is_direct_call_only: bool,
+    /// Whether method can only be called by the owner of the contract.
+    pub is_only_owner: bool,
+    /// Whether the method returns a `Result` whose error aborts the execution.
+    pub is_handles_result: bool,
+    /// The feature which, while paused, makes the method panic, if any.
+    pub pause: Option<PauseAttr>,
+    /// The roles allowed to call the method, if it's restricted.
+    pub access_control: Option<AccessControlAttr>,
     /// The serializer that we use for `env::input()`.
     pub input_serializer: SerializerType,
     /// The serializer that we use for the return type.
@@ -60,7 +75,15 @@ impl AttrSigInfo {
         let mut args = vec![];
         let mut method_type = MethodType::Regular;
         let mut is_payable = false;
+        let mut deposit_handler = None;
         let mut is_private = false;
+        let mut is_direct_call_only = false;
+        let mut only_owner_attr = None;
+        let mut handle_result_attr = None;
+        let mut pause = None;
+        let mut pause_attr = None;
+        let mut access_control = None;
+        let mut access_control_attr = None;
         // By the default we serialize the result with JSON.
         let mut result_serializer = SerializerType::JSON;
 
@@ -77,12 +100,31 @@ impl AttrSigInfo {
                     }
                 }
                 "payable" => {
+                    let attr_args: PayableAttr = syn::parse2(attr.tokens.clone())?;
+                    deposit_handler = attr_args.deposit_handler;
                     payable_attr = Some(attr);
                     is_payable = true;
                 }
                 "private" => {
                     is_private = true;
                 }
+                "direct_call_only" => {
+                    is_direct_call_only = true;
+                }
+                "only_owner" => {
+                    only_owner_attr = Some(attr);
+                }
+                "handle_result" => {
+                    handle_result_attr = Some(attr);
+                }
+                "pause" => {
+                    pause = Some(syn::parse2::<PauseAttr>(attr.tokens.clone())?);
+                    pause_attr = Some(attr);
+                }
+                "access_control" => {
+                    access_control = Some(syn::parse2::<AccessControlAttr>(attr.tokens.clone())?);
+                    access_control_attr = Some(attr);
+                }
                 "result_serializer" => {
                     let serializer: SerializerAttr = syn::parse2(attr.tokens.clone())?;
                     result_serializer = serializer.serializer_type;
@@ -123,6 +165,58 @@ impl AttrSigInfo {
                     "Payable method must be mutable (not view)",
                 ));
             }
+            if deposit_handler.is_some()
+                && (receiver.is_none() || !matches!(method_type, MethodType::Regular))
+            {
+                return Err(Error::new(
+                    payable_attr.span(),
+                    "Payable deposit handlers are only supported on methods taking `&mut self`",
+                ));
+            }
+        }
+
+        let is_only_owner = only_owner_attr.is_some();
+        if let Some(only_owner_attr) = only_owner_attr {
+            if receiver.is_none() || !matches!(method_type, MethodType::Regular) {
+                return Err(Error::new(
+                    only_owner_attr.span(),
+                    "Only owner methods must be mutable (not view)",
+                ));
+            }
+        }
+
+        let is_handles_result = handle_result_attr.is_some();
+        if let Some(handle_result_attr) = handle_result_attr {
+            if !matches!(method_type, MethodType::Regular | MethodType::View) {
+                return Err(Error::new(
+                    handle_result_attr.span(),
+                    "Init methods can't be marked with handle_result",
+                ));
+            }
+            if matches!(original_sig.output, ReturnType::Default) {
+                return Err(Error::new(
+                    handle_result_attr.span(),
+                    "Methods marked with handle_result must return a Result",
+                ));
+            }
+        }
+
+        if let Some(pause_attr) = pause_attr {
+            if receiver.is_none() || !matches!(method_type, MethodType::Regular) {
+                return Err(Error::new(
+                    pause_attr.span(),
+                    "Pausable methods must be mutable (not view)",
+                ));
+            }
+        }
+
+        if let Some(access_control_attr) = access_control_attr {
+            if receiver.is_none() || !matches!(method_type, MethodType::Regular) {
+                return Err(Error::new(
+                    access_control_attr.span(),
+                    "Access controlled methods must be mutable (not view)",
+                ));
+            }
         }
 
         *original_attrs = non_bindgen_attrs.clone();
@@ -135,7 +229,13 @@ impl AttrSigInfo {
             input_serializer: SerializerType::JSON,
             method_type,
             is_payable,
+            deposit_handler,
             is_private,
+            is_direct_call_only,
+            is_only_owner,
+            is_handles_result,
+            pause,
+            access_control,
             result_serializer,
             receiver,
             returns,
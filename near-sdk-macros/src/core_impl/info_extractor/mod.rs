@@ -21,6 +21,15 @@ mod item_impl_info;
 mod init_attr;
 pub use init_attr::InitAttr;
 
+mod payable_attr;
+pub use payable_attr::{DepositHandler, PayableAttr};
+
+mod pause_attr;
+pub use pause_attr::PauseAttr;
+
+mod access_control_attr;
+pub use access_control_attr::AccessControlAttr;
+
 pub use item_impl_info::ItemImplInfo;
 
 /// Type of serialization we use.
@@ -0,0 +1,54 @@
+use proc_macro2::Ident;
+use syn::parse::{Parse, ParseStream};
+use syn::{Error, LitStr, Token};
+
+/// Arguments of `#[pause(feature = "...", except(owner))]`.
+pub struct PauseAttr {
+    /// Key of the feature which, while paused, makes the method panic.
+    pub feature: LitStr,
+    /// Whether the owner can still call the method while the feature is paused.
+    pub except_owner: bool,
+}
+
+impl Parse for PauseAttr {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let content;
+        let _paren_token = syn::parenthesized!(content in input);
+        let mut feature = None;
+        let mut except_owner = false;
+        while !content.is_empty() {
+            let ident: Ident = content.parse()?;
+            match ident.to_string().as_str() {
+                "feature" => {
+                    let _eq_token: Token![=] = content.parse()?;
+                    feature = Some(content.parse()?);
+                }
+                "except" => {
+                    let accounts;
+                    let _paren_token = syn::parenthesized!(accounts in content);
+                    let account: Ident = accounts.parse()?;
+                    if account != "owner" || !accounts.is_empty() {
+                        return Err(Error::new(
+                            account.span(),
+                            "Unsupported pause exception, expected `except(owner)`.",
+                        ));
+                    }
+                    except_owner = true;
+                }
+                _ => {
+                    return Err(Error::new(
+                        ident.span(),
+                        "Unsupported pause attribute, expected `feature` or `except`.",
+                    ))
+                }
+            }
+            if !content.is_empty() {
+                let _comma_token: Token![,] = content.parse()?;
+            }
+        }
+        let feature = feature.ok_or_else(|| {
+            Error::new(content.span(), "The pause attribute requires a `feature`.")
+        })?;
+        Ok(Self { feature, except_owner })
+    }
+}
@@ -0,0 +1,42 @@
+use proc_macro2::Ident;
+use syn::parse::{Parse, ParseStream};
+use syn::token::Paren;
+use syn::{Error, LitStr, Token};
+
+/// What to do with the attached deposit after a payable method completes.
+pub enum DepositHandler {
+    /// Function called with the contract and the attached deposit, e.g. `Self::record_deposit`.
+    Handler(syn::Path),
+    /// Expression evaluating to the account that the attached deposit is transferred to,
+    /// e.g. `self.treasury_id`.
+    ForwardTo(syn::Expr),
+}
+
+pub struct PayableAttr {
+    pub deposit_handler: Option<DepositHandler>,
+}
+
+impl Parse for PayableAttr {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let deposit_handler = if input.peek(Paren) {
+            let content;
+            let _paren_token = syn::parenthesized!(content in input);
+            let ident: Ident = content.parse()?;
+            let _eq_token: Token![=] = content.parse()?;
+            let value: LitStr = content.parse()?;
+            match ident.to_string().as_str() {
+                "handler" => Some(DepositHandler::Handler(value.parse()?)),
+                "forward_to" => Some(DepositHandler::ForwardTo(value.parse()?)),
+                _ => {
+                    return Err(Error::new(
+                        ident.span(),
+                        "Unsupported payable attribute, expected `handler` or `forward_to`.",
+                    ))
+                }
+            }
+        } else {
+            None
+        };
+        Ok(Self { deposit_handler })
+    }
+}
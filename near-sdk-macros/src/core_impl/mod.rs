@@ -1,6 +1,8 @@
 mod code_generator;
 mod info_extractor;
 mod metadata;
+mod storage_key;
 pub use code_generator::*;
 pub use info_extractor::*;
 pub use metadata::metadata_visitor::MetadataVisitor;
+pub use storage_key::{borsh_storage_key_enum, borsh_storage_key_struct};
@@ -0,0 +1,338 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use std::convert::TryFrom;
+use syn::spanned::Spanned;
+use syn::{
+    Attribute, Error, Expr, ExprLit, ItemEnum, ItemStruct, Lit, LitStr, Meta, MetaNameValue,
+    NestedMeta,
+};
+
+const NAMESPACE_USAGE: &str = "Expected `#[storage_key(namespace = \"...\")]`";
+
+/// Returns the namespace set with `#[storage_key(namespace = "...")]`, if any.
+fn namespace(attrs: &[Attribute]) -> syn::Result<Option<LitStr>> {
+    let mut namespace = None;
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("storage_key")) {
+        let list = match attr.parse_meta()? {
+            Meta::List(list) => list,
+            meta => return Err(Error::new(meta.span(), NAMESPACE_USAGE)),
+        };
+        for nested in list.nested {
+            match nested {
+                NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+                    path,
+                    lit: Lit::Str(lit),
+                    ..
+                })) if path.is_ident("namespace") => {
+                    if lit.value().is_empty() {
+                        return Err(Error::new(
+                            lit.span(),
+                            "Storage key namespaces can't be empty",
+                        ));
+                    }
+                    if namespace.is_some() {
+                        return Err(Error::new(lit.span(), "Storage key namespace is set twice"));
+                    }
+                    namespace = Some(lit);
+                }
+                nested => return Err(Error::new(nested.span(), NAMESPACE_USAGE)),
+            }
+        }
+    }
+    Ok(namespace)
+}
+
+/// Generates an `IntoStorageKey` implementation prefixing `key` with `namespace`.
+fn namespaced_into_storage_key(
+    input: &syn::Generics,
+    name: &syn::Ident,
+    namespace: &LitStr,
+    key: TokenStream2,
+) -> TokenStream2 {
+    let (impl_generics, ty_generics, where_clause) = input.split_for_impl();
+    quote! {
+        impl #impl_generics near_sdk::IntoStorageKey for #name #ty_generics #where_clause {
+            fn into_storage_key(self) -> ::std::vec::Vec<u8> {
+                near_sdk::storage_key::namespaced(#namespace, #key)
+            }
+        }
+    }
+}
+
+/// Returns the byte each variant of the enum starts its storage key with: the explicit
+/// discriminant of each variant if the enum has any, and the position of each variant otherwise,
+/// as Borsh serializes it. Explicit discriminants must be set on every variant.
+fn variant_bytes(input: &ItemEnum) -> syn::Result<(Vec<(String, u8)>, bool)> {
+    let explicit = input.variants.iter().any(|variant| variant.discriminant.is_some());
+    let mut bytes = Vec::with_capacity(input.variants.len());
+    for (index, variant) in input.variants.iter().enumerate() {
+        let name = variant.ident.to_string();
+        let byte = match &variant.discriminant {
+            Some((_, Expr::Lit(ExprLit { lit: Lit::Int(lit), .. }))) => {
+                lit.base10_parse::<u8>().map_err(|_| {
+                    Error::new(lit.span(), "Storage key discriminants must be from 0 to 255")
+                })?
+            }
+            Some((_, expr)) => {
+                return Err(Error::new(
+                    expr.span(),
+                    "Storage key discriminants must be integer literals",
+                ))
+            }
+            None if explicit => {
+                return Err(Error::new(
+                    variant.span(),
+                    format!(
+                        "Variant `{}` needs an explicit discriminant, as other variants of this \
+                         storage key have one. Without one its storage key would depend on its \
+                         position and change when variants are added before it",
+                        name
+                    ),
+                ))
+            }
+            None => u8::try_from(index).map_err(|_| {
+                Error::new(variant.span(), "Storage key enums can have at most 256 variants")
+            })?,
+        };
+        bytes.push((name, byte));
+    }
+    Ok((bytes, explicit))
+}
+
+/// FNV-1a hash of the namespace, if any, and of the variant names and their bytes, sorted by name
+/// so that reordering variants with explicit discriminants doesn't change it.
+fn fingerprint(namespace: Option<&str>, bytes: &[(String, u8)]) -> u64 {
+    let mut sorted: Vec<_> = bytes.iter().collect();
+    sorted.sort();
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for b in namespace.into_iter().flat_map(|namespace| namespace.bytes().chain(vec![0])) {
+        hash ^= u64::from(b);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    for (name, byte) in sorted {
+        for b in name.bytes().chain(vec![0, *byte]) {
+            hash ^= u64::from(b);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    hash
+}
+
+/// Generates the storage key implementation of an enum deriving `BorshStorageKey`, along with its
+/// layout and the fingerprint of the layout.
+pub fn borsh_storage_key_enum(input: &ItemEnum) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let (bytes, explicit) = variant_bytes(input)?;
+    let namespace = namespace(&input.attrs)?;
+    let fingerprint = fingerprint(namespace.as_ref().map(LitStr::value).as_deref(), &bytes);
+    let layout = bytes.iter().map(|(name, byte)| quote! { (#name, #byte) });
+    let namespace_const = match &namespace {
+        Some(namespace) => quote! { ::std::option::Option::Some(#namespace) },
+        None => quote! { ::std::option::Option::None },
+    };
+
+    // Borsh serializes the position of a variant rather than its discriminant, so enums with
+    // explicit discriminants, which can't have fields, are converted directly.
+    let into_storage_key = if let Some(namespace) = &namespace {
+        let key = if explicit {
+            quote! { ::std::vec![self as u8] }
+        } else {
+            quote! { near_sdk::borsh::BorshSerialize::try_to_vec(&self).unwrap() }
+        };
+        namespaced_into_storage_key(&input.generics, name, namespace, key)
+    } else if explicit {
+        quote! {
+            impl #impl_generics near_sdk::IntoStorageKey for #name #ty_generics #where_clause {
+                fn into_storage_key(self) -> ::std::vec::Vec<u8> {
+                    ::std::vec![self as u8]
+                }
+            }
+        }
+    } else {
+        quote! {
+            impl #impl_generics near_sdk::BorshIntoStorageKey for #name #ty_generics #where_clause {}
+        }
+    };
+    Ok(quote! {
+        #into_storage_key
+
+        #[allow(dead_code)]
+        impl #impl_generics #name #ty_generics #where_clause {
+            /// Name of each variant and the first byte of its storage key.
+            pub const STORAGE_KEY_LAYOUT: &'static [(&'static str, u8)] = &[#(#layout),*];
+
+            /// Namespace of the storage keys, set with `#[storage_key(namespace = "...")]`.
+            pub const STORAGE_KEY_NAMESPACE: ::std::option::Option<&'static str> = #namespace_const;
+
+            /// Hash of [`Self::STORAGE_KEY_LAYOUT`]. Compare it with a checked-in value in a
+            /// test, so that changes to the storage keys of existing variants are caught.
+            pub const fn layout_fingerprint() -> u64 {
+                #fingerprint
+            }
+        }
+    })
+}
+
+/// Generates the storage key implementation of a struct deriving `BorshStorageKey`.
+pub fn borsh_storage_key_struct(input: &ItemStruct) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    match namespace(&input.attrs)? {
+        Some(namespace) => Ok(namespaced_into_storage_key(
+            &input.generics,
+            name,
+            &namespace,
+            quote! { near_sdk::borsh::BorshSerialize::try_to_vec(&self).unwrap() },
+        )),
+        None => {
+            let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+            Ok(quote! {
+                impl #impl_generics near_sdk::BorshIntoStorageKey for #name #ty_generics #where_clause {}
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generate(input: &str) -> syn::Result<TokenStream2> {
+        borsh_storage_key_enum(&syn::parse_str(input).unwrap())
+    }
+
+    #[test]
+    fn implicit_discriminants() {
+        let actual = generate("enum StorageKey { Accounts, Tokens { owner: String } }").unwrap();
+        let fingerprint =
+            fingerprint(None, &[("Accounts".to_string(), 0), ("Tokens".to_string(), 1)]);
+        let expected = quote! {
+            impl near_sdk::BorshIntoStorageKey for StorageKey {}
+
+            #[allow(dead_code)]
+            impl StorageKey {
+                /// Name of each variant and the first byte of its storage key.
+                pub const STORAGE_KEY_LAYOUT: &'static [(&'static str, u8)] =
+                    &[("Accounts", 0u8), ("Tokens", 1u8)];
+
+                /// Namespace of the storage keys, set with `#[storage_key(namespace = "...")]`.
+                pub const STORAGE_KEY_NAMESPACE: ::std::option::Option<&'static str> =
+                    ::std::option::Option::None;
+
+                /// Hash of [`Self::STORAGE_KEY_LAYOUT`]. Compare it with a checked-in value in a
+                /// test, so that changes to the storage keys of existing variants are caught.
+                pub const fn layout_fingerprint() -> u64 {
+                    #fingerprint
+                }
+            }
+        };
+        assert_eq!(actual.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn explicit_discriminants() {
+        let actual = generate("enum StorageKey { Accounts = 0, TokensPerOwner = 3 }").unwrap();
+        let expected = quote! {
+            impl near_sdk::IntoStorageKey for StorageKey {
+                fn into_storage_key(self) -> ::std::vec::Vec<u8> {
+                    ::std::vec![self as u8]
+                }
+            }
+        };
+        assert!(actual.to_string().starts_with(&expected.to_string()));
+        assert!(actual.to_string().contains(&quote!(("TokensPerOwner", 3u8)).to_string()));
+    }
+
+    #[test]
+    fn mixed_discriminants() {
+        let err = generate("enum StorageKey { Accounts = 0, Metadata, Tokens = 2 }").unwrap_err();
+        assert!(err.to_string().starts_with("Variant `Metadata` needs an explicit discriminant"));
+    }
+
+    #[test]
+    fn invalid_discriminants() {
+        let err = generate("enum StorageKey { Accounts = 256 }").unwrap_err();
+        assert_eq!(err.to_string(), "Storage key discriminants must be from 0 to 255");
+        let err = generate("enum StorageKey { Accounts = 1 + 1 }").unwrap_err();
+        assert_eq!(err.to_string(), "Storage key discriminants must be integer literals");
+    }
+
+    #[test]
+    fn fingerprint_changes_with_layout() {
+        let layout = |names: &[&str]| {
+            let bytes: Vec<_> =
+                names.iter().enumerate().map(|(i, name)| (name.to_string(), i as u8)).collect();
+            fingerprint(None, &bytes)
+        };
+        let original = layout(&["Accounts", "Tokens", "Metadata"]);
+        assert_eq!(original, layout(&["Accounts", "Tokens", "Metadata"]));
+        // Inserting a variant moves the ones after it.
+        assert_ne!(original, layout(&["Accounts", "Approvals", "Tokens", "Metadata"]));
+        // Appending a variant keeps the existing keys, but is still a different layout.
+        assert_ne!(original, layout(&["Accounts", "Tokens", "Metadata", "Approvals"]));
+
+        // The order of variants with explicit discriminants doesn't matter.
+        let explicit = vec![("Tokens".to_string(), 3), ("Accounts".to_string(), 0)];
+        assert_eq!(
+            fingerprint(None, &explicit),
+            fingerprint(None, &[("Accounts".to_string(), 0), ("Tokens".to_string(), 3)])
+        );
+
+        // Changing the namespace moves every key.
+        assert_ne!(fingerprint(Some("lib"), &explicit), fingerprint(None, &explicit));
+        assert_ne!(fingerprint(Some("lib"), &explicit), fingerprint(Some("lib2"), &explicit));
+    }
+
+    #[test]
+    fn namespaced_enum() {
+        let actual = generate(
+            r#"#[storage_key(namespace = "lib")] enum StorageKey { Accounts, Tokens { id: u8 } }"#,
+        )
+        .unwrap();
+        let expected = quote! {
+            impl near_sdk::IntoStorageKey for StorageKey {
+                fn into_storage_key(self) -> ::std::vec::Vec<u8> {
+                    near_sdk::storage_key::namespaced(
+                        "lib",
+                        near_sdk::borsh::BorshSerialize::try_to_vec(&self).unwrap()
+                    )
+                }
+            }
+        };
+        assert!(actual.to_string().starts_with(&expected.to_string()));
+        assert!(actual
+            .to_string()
+            .contains(&quote!(::std::option::Option::Some("lib")).to_string()));
+
+        let actual =
+            generate(r#"#[storage_key(namespace = "lib")] enum StorageKey { Accounts = 2 }"#)
+                .unwrap();
+        assert!(actual.to_string().contains(
+            &quote!(near_sdk::storage_key::namespaced("lib", ::std::vec![self as u8])).to_string()
+        ));
+    }
+
+    #[test]
+    fn namespaced_struct() {
+        let input = r#"#[storage_key(namespace = "lib")] struct StorageKey { owner: String }"#;
+        let actual = borsh_storage_key_struct(&syn::parse_str(input).unwrap()).unwrap();
+        assert!(actual
+            .to_string()
+            .contains(&quote!(near_sdk::storage_key::namespaced).to_string()));
+    }
+
+    #[test]
+    fn invalid_namespaces() {
+        let err =
+            generate(r#"#[storage_key(namespace = "")] enum StorageKey { Accounts }"#).unwrap_err();
+        assert_eq!(err.to_string(), "Storage key namespaces can't be empty");
+        let err =
+            generate(r#"#[storage_key(prefix = "lib")] enum StorageKey { Accounts }"#).unwrap_err();
+        assert_eq!(err.to_string(), NAMESPACE_USAGE);
+        let err = generate(
+            r#"#[storage_key(namespace = "a")] #[storage_key(namespace = "b")] enum Key { A }"#,
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), "Storage key namespace is set twice");
+    }
+}
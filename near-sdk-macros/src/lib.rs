@@ -109,6 +109,170 @@ pub fn init(_attr: TokenStream, item: TokenStream) -> TokenStream {
     item
 }
 
+/// `direct_call_only` marks a method of a `#[near_bindgen]` impl which can only be called directly
+/// by the signer of the transaction, and is handled by `near_bindgen`. The generated method
+/// panics if the predecessor account, which made the call, isn't the signer account, which signed
+/// the transaction, like `near_sdk::assert_predecessor_is_signer` does.
+///
+/// # Threat model
+///
+/// A contract which authorizes calls by the signer account can be exploited by any contract the
+/// signer calls: when a user signs a transaction calling a malicious contract, the calls it makes
+/// to other contracts carry the same signer, and act with the user's authority. Contracts should
+/// therefore authorize calls by the predecessor account, which is the account making the call.
+///
+/// Checking the predecessor doesn't tell whether the account is a user or a contract acting on
+/// someone's behalf, as both are the predecessor of their calls. `direct_call_only` additionally
+/// rejects calls made by contracts, for methods with effects a user should only trigger
+/// deliberately, such as burning their tokens. Contracts, including multisig and DAO contracts,
+/// can't call such methods, so only use it when that restriction is intended. It doesn't replace
+/// authorization by the predecessor account.
+///
+/// ```ignore
+/// #[near_bindgen]
+/// impl Contract {
+///     #[direct_call_only]
+///     pub fn close_account(&mut self) {
+///         let account_id = near_sdk::env::predecessor_account_id();
+///         // ...
+///     }
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn direct_call_only(_attr: TokenStream, _item: TokenStream) -> TokenStream {
+    // `near_bindgen` removes the attribute, so this only runs outside of a `near_bindgen` impl,
+    // where the check wouldn't be generated.
+    TokenStream::from(
+        syn::Error::new(
+            Span::call_site(),
+            "direct_call_only can only be used on methods of a near_bindgen impl",
+        )
+        .to_compile_error(),
+    )
+}
+
+/// `handle_result` marks a method of a `#[near_bindgen]` impl returning a `Result`, and is handled
+/// by `near_bindgen`. The generated method returns the `Ok` value, serialized like any return
+/// value, and aborts the execution with the `Err` value, which must implement
+/// `near_sdk::FunctionError`, such as `near_sdk::errors::SdkError` or `String`. The state isn't
+/// written when the method returns an error.
+///
+/// ```ignore
+/// #[near_bindgen]
+/// impl Contract {
+///     #[handle_result]
+///     pub fn withdraw(&mut self, amount: U128) -> Result<U128, SdkError> {
+///         // ...
+///     }
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn handle_result(_attr: TokenStream, _item: TokenStream) -> TokenStream {
+    // `near_bindgen` removes the attribute, so this only runs outside of a `near_bindgen` impl,
+    // where the error wouldn't be handled.
+    TokenStream::from(
+        syn::Error::new(
+            Span::call_site(),
+            "handle_result can only be used on methods of a near_bindgen impl",
+        )
+        .to_compile_error(),
+    )
+}
+
+/// `only_owner` marks a method of a `#[near_bindgen]` impl which can only be called by the owner
+/// of the contract, and is handled by `near_bindgen`. The generated method calls the
+/// `assert_owner(&self)` method of the contract once its state is read, which panics if the
+/// predecessor account isn't the owner. `impl_ownable!` of near-contract-standards provides it.
+///
+/// The method must take `&mut self`: view methods don't have a predecessor account to check, so
+/// using `only_owner` on them is a compile error.
+///
+/// ```ignore
+/// #[near_bindgen]
+/// impl Contract {
+///     #[only_owner]
+///     pub fn set_fee(&mut self, fee: u16) {
+///         self.fee = fee;
+///     }
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn only_owner(_attr: TokenStream, _item: TokenStream) -> TokenStream {
+    // `near_bindgen` removes the attribute, so this only runs outside of a `near_bindgen` impl,
+    // where the check wouldn't be generated.
+    TokenStream::from(
+        syn::Error::new(
+            Span::call_site(),
+            "only_owner can only be used on methods of a near_bindgen impl",
+        )
+        .to_compile_error(),
+    )
+}
+
+/// `pause` marks a method of a `#[near_bindgen]` impl which panics while a feature of the contract
+/// is paused, and is handled by `near_bindgen`. The generated method calls the
+/// `assert_not_paused(&self, key: &str)` method of the contract with the key of the feature once
+/// its state is read. `impl_pausable!` of near-contract-standards provides it.
+///
+/// With `except(owner)`, the owner can still call the method while the feature is paused, e.g. to
+/// recover funds during an incident. The owner is read with the `owner(&self) -> Option<AccountId>`
+/// method of the contract, which `impl_ownable!` of near-contract-standards provides.
+///
+/// The method must take `&mut self`, view methods keep working while a feature is paused.
+///
+/// ```ignore
+/// #[near_bindgen]
+/// impl Contract {
+///     #[pause(feature = "ft_transfer", except(owner))]
+///     pub fn ft_transfer(&mut self, receiver_id: AccountId, amount: U128) {
+///         // ...
+///     }
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn pause(_attr: TokenStream, _item: TokenStream) -> TokenStream {
+    // `near_bindgen` removes the attribute, so this only runs outside of a `near_bindgen` impl,
+    // where the check wouldn't be generated.
+    TokenStream::from(
+        syn::Error::new(
+            Span::call_site(),
+            "pause can only be used on methods of a near_bindgen impl",
+        )
+        .to_compile_error(),
+    )
+}
+
+/// `access_control` marks a method of a `#[near_bindgen]` impl which can only be called by the
+/// members of some roles, and is handled by `near_bindgen`. The generated method calls the
+/// `assert_any_role(&self, roles: &[Role])` method of the contract with the roles once its state
+/// is read, so that the members of any of them can call it. `impl_rbac!` of near-contract-standards
+/// provides it.
+///
+/// The roles are paths to the values, such as `Role::Minter`, or the names of the variants when
+/// they're imported with `use Role::*`. The method must take `&mut self`.
+///
+/// ```ignore
+/// #[near_bindgen]
+/// impl Contract {
+///     #[access_control(roles(Role::Minter, Role::Admin))]
+///     pub fn mint(&mut self, receiver_id: AccountId, amount: U128) {
+///         // ...
+///     }
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn access_control(_attr: TokenStream, _item: TokenStream) -> TokenStream {
+    // `near_bindgen` removes the attribute, so this only runs outside of a `near_bindgen` impl,
+    // where the check wouldn't be generated.
+    TokenStream::from(
+        syn::Error::new(
+            Span::call_site(),
+            "access_control can only be used on methods of a near_bindgen impl",
+        )
+        .to_compile_error(),
+    )
+}
+
 /// `metadata` generates the metadata method and should be placed at the very end of the `lib.rs` file.
 // TODO: Once Rust allows inner attributes and custom procedural macros for modules we should switch this
 // to be `#![metadata]` attribute at the top of the contract file instead. https://github.com/rust-lang/rust/issues/54727
@@ -165,22 +329,93 @@ pub fn derive_no_default(item: TokenStream) -> TokenStream {
 /// `BorshStorageKey` generates implementation for `BorshIntoStorageKey` trait.
 /// It allows the type to be passed as a unique prefix for persistent collections.
 /// The type should also implement or derive `BorshSerialize` trait.
-#[proc_macro_derive(BorshStorageKey)]
+///
+/// The storage key of an enum variant starts with the position of the variant, so adding a
+/// variant before existing ones changes their keys, and the collections using them no longer find
+/// their entries after an upgrade. Enums deriving `BorshStorageKey` can set the first byte of
+/// each key with explicit discriminants instead, such as `TokensPerOwner = 3`, which must then be
+/// set on every variant, from 0 to 255. Enums also get a `STORAGE_KEY_LAYOUT` constant, listing
+/// the first byte of the key of each variant, and a `layout_fingerprint()` hash of it to compare
+/// with a checked-in value in a test:
+///
+/// ```ignore
+/// #[derive(BorshStorageKey, BorshSerialize)]
+/// enum StorageKey {
+///     Accounts,
+///     Tokens,
+/// }
+///
+/// #[test]
+/// fn storage_key_layout() {
+///     // Update only when appending variants, never when the existing keys changed.
+///     assert_eq!(StorageKey::layout_fingerprint(), 0xc10d_3891_c7fe_7a38);
+/// }
+/// ```
+///
+/// Libraries storing collections inside contracts should prefix their keys with a namespace with
+/// `#[storage_key(namespace = "...")]`, so that they can't collide with the keys of the contract.
+/// See `near_sdk::storage_key::namespaced`.
+#[proc_macro_derive(BorshStorageKey, attributes(storage_key))]
 pub fn borsh_storage_key(item: TokenStream) -> TokenStream {
-    let name = if let Ok(input) = syn::parse::<ItemEnum>(item.clone()) {
-        input.ident
+    if let Ok(input) = syn::parse::<ItemEnum>(item.clone()) {
+        match borsh_storage_key_enum(&input) {
+            Ok(generated) => TokenStream::from(generated),
+            Err(err) => TokenStream::from(err.to_compile_error()),
+        }
     } else if let Ok(input) = syn::parse::<ItemStruct>(item) {
-        input.ident
+        match borsh_storage_key_struct(&input) {
+            Ok(generated) => TokenStream::from(generated),
+            Err(err) => TokenStream::from(err.to_compile_error()),
+        }
     } else {
-        return TokenStream::from(
+        TokenStream::from(
             syn::Error::new(
                 Span::call_site(),
                 "BorshStorageKey can only be used as a derive on enums or structs.",
             )
             .to_compile_error(),
-        );
+        )
+    }
+}
+
+/// `NearClone` generates an implementation of `Clone` which clones every field, except for
+/// persistent collections which are duplicated with `duplicate_handle`. The resulting handles
+/// alias the same storage and should only be used for reads.
+#[proc_macro_derive(NearClone)]
+pub fn near_clone(item: TokenStream) -> TokenStream {
+    let input = match syn::parse::<ItemStruct>(item) {
+        Ok(input) => input,
+        Err(_) => {
+            return TokenStream::from(
+                syn::Error::new(Span::call_site(), "NearClone can only be used on structs.")
+                    .to_compile_error(),
+            )
+        }
+    };
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let body = match &input.fields {
+        syn::Fields::Named(fields) => {
+            let fields = fields.named.iter().map(|field| {
+                let ident = &field.ident;
+                quote! { #ident: near_sdk::collections::NearClone::near_clone(&self.#ident) }
+            });
+            quote! { Self { #(#fields),* } }
+        }
+        syn::Fields::Unnamed(fields) => {
+            let fields = (0..fields.unnamed.len()).map(|i| {
+                let index = syn::Index::from(i);
+                quote! { near_sdk::collections::NearClone::near_clone(&self.#index) }
+            });
+            quote! { Self(#(#fields),*) }
+        }
+        syn::Fields::Unit => quote! { Self },
     };
     TokenStream::from(quote! {
-        impl near_sdk::BorshIntoStorageKey for #name {}
+        impl #impl_generics Clone for #name #ty_generics #where_clause {
+            fn clone(&self) -> Self {
+                #body
+            }
+        }
     })
 }
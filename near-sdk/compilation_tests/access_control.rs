@@ -0,0 +1,42 @@
+//! Methods which can only be called by the members of some roles.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::{env, near_bindgen, require, AccountId, PanicOnDefault};
+
+#[derive(BorshDeserialize, BorshSerialize, PartialEq)]
+enum Role {
+    Admin,
+    Minter,
+}
+
+use Role::*;
+
+#[near_bindgen]
+#[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
+struct Incrementer {
+    admin_id: AccountId,
+    value: u32,
+}
+
+impl Incrementer {
+    fn assert_any_role(&self, roles: &[Role]) {
+        require!(roles.contains(&Admin) && env::predecessor_account_id() == self.admin_id);
+    }
+}
+
+#[near_bindgen]
+impl Incrementer {
+    #[access_control(roles(Role::Minter, Role::Admin))]
+    pub fn inc(&mut self, by: u32) -> u32 {
+        self.value += by;
+        self.value
+    }
+
+    #[payable]
+    #[access_control(roles(Admin))]
+    pub fn reset(&mut self) {
+        self.value = 0;
+    }
+}
+
+fn main() {}
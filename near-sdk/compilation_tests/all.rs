@@ -5,6 +5,13 @@ fn compilation_tests() {
     t.compile_fail("compilation_tests/invalid_arg_pat.rs");
     t.pass("compilation_tests/regular.rs");
     t.pass("compilation_tests/private.rs");
+    t.pass("compilation_tests/direct_call_only.rs");
+    t.pass("compilation_tests/handle_result.rs");
+    t.pass("compilation_tests/only_owner.rs");
+    t.compile_fail("compilation_tests/only_owner_view.rs");
+    t.pass("compilation_tests/pause.rs");
+    t.compile_fail("compilation_tests/pause_view.rs");
+    t.pass("compilation_tests/access_control.rs");
     t.pass("compilation_tests/trait_impl.rs");
     t.pass("compilation_tests/metadata.rs");
     t.compile_fail("compilation_tests/metadata_invalid_rust.rs");
@@ -18,5 +25,11 @@ fn compilation_tests() {
     t.pass("compilation_tests/lifetime_method.rs");
     t.pass("compilation_tests/cond_compilation.rs");
     t.compile_fail("compilation_tests/payable_view.rs");
+    t.pass("compilation_tests/payable_handler.rs");
+    t.compile_fail("compilation_tests/payable_handler_bad_sig.rs");
+    t.compile_fail("compilation_tests/payable_handler_view.rs");
     t.pass("compilation_tests/borsh_storage_key.rs");
+    t.pass("compilation_tests/near_clone.rs");
+    #[cfg(feature = "test-hooks")]
+    t.pass("compilation_tests/test_hooks.rs");
 }
@@ -15,11 +15,18 @@ enum StorageKeyEnum {
     SubAccounts { account_id: String },
 }
 
+#[derive(BorshStorageKey, BorshSerialize)]
+#[storage_key(namespace = "library")]
+enum NamespacedStorageKey {
+    Accounts,
+}
+
 #[near_bindgen]
 #[derive(BorshDeserialize, BorshSerialize)]
 struct Contract {
     map1: LookupMap<u64, u64>,
     map2: LookupMap<String, String>,
+    map3: LookupMap<String, String>,
 }
 
 impl Default for Contract {
@@ -27,6 +34,7 @@ impl Default for Contract {
         Self {
             map1: LookupMap::new(StorageKeyStruct { key: "bla".to_string() }),
             map2: LookupMap::new(StorageKeyEnum::Accounts),
+            map3: LookupMap::new(NamespacedStorageKey::Accounts),
         }
     }
 }
@@ -0,0 +1,26 @@
+//! Method which can only be called directly by the signer.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::near_bindgen;
+
+#[near_bindgen]
+#[derive(Default, BorshDeserialize, BorshSerialize)]
+struct Incrementer {
+    value: u32,
+}
+
+#[near_bindgen]
+impl Incrementer {
+    #[direct_call_only]
+    pub fn inc(&mut self, by: u32) {
+        self.value += by;
+    }
+
+    #[private]
+    #[direct_call_only]
+    pub fn reset(&mut self) {
+        self.value = 0;
+    }
+}
+
+fn main() {}
@@ -0,0 +1,31 @@
+//! Methods returning a `Result` whose error aborts the execution.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::errors::SdkError;
+use near_sdk::near_bindgen;
+
+#[near_bindgen]
+#[derive(Default, BorshDeserialize, BorshSerialize)]
+struct Ledger {
+    entries: Vec<u64>,
+}
+
+#[near_bindgen]
+impl Ledger {
+    #[handle_result]
+    pub fn entry(&self, index: u64) -> Result<u64, SdkError> {
+        let len = self.entries.len() as u64;
+        self.entries.get(index as usize).copied().ok_or(SdkError::IndexOutOfBounds { index, len })
+    }
+
+    #[handle_result]
+    pub fn record(&mut self, amount: u64) -> Result<u64, String> {
+        if amount == 0 {
+            return Err("The amount must be positive".to_string());
+        }
+        self.entries.push(amount);
+        Ok(self.entries.len() as u64)
+    }
+}
+
+fn main() {}
@@ -0,0 +1,35 @@
+//! Testing NearClone macro.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::collections::{LookupMap, Vector};
+use near_sdk::NearClone;
+
+#[derive(BorshDeserialize, BorshSerialize, NearClone)]
+struct Contract {
+    owner: String,
+    balances: LookupMap<String, u128>,
+    history: Vector<u64>,
+}
+
+#[derive(NearClone)]
+struct Pair(u64, Vector<u64>);
+
+fn main() {
+    let mut contract = Contract {
+        owner: "alice".to_string(),
+        balances: LookupMap::new(b"b"),
+        history: Vector::new(b"h"),
+    };
+    contract.balances.insert(&"bob".to_string(), &10);
+    contract.history.push(&1);
+
+    let view = contract.clone();
+    assert_eq!(view.owner, "alice");
+    assert_eq!(view.balances.get(&"bob".to_string()), Some(10));
+    assert_eq!(view.history.to_vec(), vec![1]);
+
+    let pair = Pair(1, Vector::new(b"p"));
+    let copy = pair.clone();
+    assert_eq!(copy.0, 1);
+    assert!(copy.1.is_empty());
+}
@@ -0,0 +1,34 @@
+//! Methods which can only be called by the owner of the contract.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::{env, near_bindgen, require, AccountId, PanicOnDefault};
+
+#[near_bindgen]
+#[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
+struct Incrementer {
+    owner_id: AccountId,
+    value: u32,
+}
+
+impl Incrementer {
+    fn assert_owner(&self) {
+        require!(env::predecessor_account_id() == self.owner_id, "Only the owner can call it");
+    }
+}
+
+#[near_bindgen]
+impl Incrementer {
+    #[only_owner]
+    pub fn inc(&mut self, by: u32) -> u32 {
+        self.value += by;
+        self.value
+    }
+
+    #[payable]
+    #[only_owner]
+    pub fn reset(&mut self) {
+        self.value = 0;
+    }
+}
+
+fn main() {}
@@ -0,0 +1,18 @@
+//! Only owner views are not valid
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::near_bindgen;
+
+#[near_bindgen]
+#[derive(Default, BorshDeserialize, BorshSerialize)]
+struct Test {}
+
+#[near_bindgen]
+impl Test {
+    #[only_owner]
+    pub fn value(&self) -> u32 {
+        0
+    }
+}
+
+fn main() {}
@@ -0,0 +1,40 @@
+//! Methods which panic while a feature of the contract is paused.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::{env, near_bindgen, require, AccountId, PanicOnDefault};
+
+#[near_bindgen]
+#[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
+struct Incrementer {
+    owner_id: AccountId,
+    paused: bool,
+    value: u32,
+}
+
+impl Incrementer {
+    fn owner(&self) -> Option<AccountId> {
+        Some(self.owner_id.clone())
+    }
+
+    fn assert_not_paused(&self, key: &str) {
+        require!(!self.paused, format!("The feature {} is paused", key));
+    }
+}
+
+#[near_bindgen]
+impl Incrementer {
+    #[pause(feature = "inc")]
+    pub fn inc(&mut self, by: u32) -> u32 {
+        self.value += by;
+        self.value
+    }
+
+    #[payable]
+    #[pause(feature = "reset", except(owner))]
+    pub fn reset(&mut self) {
+        require!(env::attached_deposit() > 0);
+        self.value = 0;
+    }
+}
+
+fn main() {}
@@ -0,0 +1,31 @@
+//! Payable methods with deposit handlers.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::{near_bindgen, AccountId, Balance, PanicOnDefault};
+
+#[near_bindgen]
+#[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
+struct Treasury {
+    treasury_id: AccountId,
+    deposits: Balance,
+}
+
+#[near_bindgen]
+impl Treasury {
+    #[payable(handler = "Self::record_deposit")]
+    pub fn donate(&mut self) {}
+
+    #[payable(handler = "Self::record_deposit")]
+    pub fn donate_with_result(&mut self) -> Balance {
+        self.deposits
+    }
+
+    #[payable(forward_to = "self.treasury_id")]
+    pub fn forward(&mut self) {}
+
+    fn record_deposit(&mut self, amount: Balance) {
+        self.deposits += amount;
+    }
+}
+
+fn main() {}
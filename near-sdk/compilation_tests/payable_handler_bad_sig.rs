@@ -0,0 +1,18 @@
+//! Deposit handlers must take `&mut self` and the deposit amount.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::near_bindgen;
+
+#[near_bindgen]
+#[derive(Default, BorshDeserialize, BorshSerialize)]
+struct Test {}
+
+#[near_bindgen]
+impl Test {
+    #[payable(handler = "Self::record_deposit")]
+    pub fn pay(&mut self) {}
+
+    fn record_deposit(&mut self) {}
+}
+
+fn main() {}
@@ -0,0 +1,18 @@
+//! Deposit handlers are only allowed on methods taking `&mut self`.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::{near_bindgen, Balance};
+
+#[near_bindgen]
+#[derive(Default, BorshDeserialize, BorshSerialize)]
+struct Test {}
+
+#[near_bindgen]
+impl Test {
+    #[payable(handler = "Self::record_deposit")]
+    pub fn pay() {}
+
+    fn record_deposit(&mut self, _amount: Balance) {}
+}
+
+fn main() {}
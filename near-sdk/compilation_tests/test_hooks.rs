@@ -0,0 +1,38 @@
+//! Testing impl_test_hooks macro with the `test-hooks` feature enabled.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::test_utils::{accounts, VMContextBuilder};
+use near_sdk::{env, near_bindgen, testing_env};
+
+#[near_bindgen]
+#[derive(Default, BorshDeserialize, BorshSerialize)]
+struct Contract {}
+
+near_sdk::testing::impl_test_hooks!(Contract);
+
+fn main() {
+    testing_env!(VMContextBuilder::new()
+        .current_account_id(accounts(0))
+        .predecessor_account_id(accounts(0))
+        .build());
+
+    let key = Base64VecU8(b"key".to_vec());
+    let usage = Contract::__storage_usage();
+    Contract::__set_raw(key.clone(), Base64VecU8(b"value".to_vec()), None);
+    assert_eq!(env::storage_read(b"key"), Some(b"value".to_vec()));
+    assert_eq!(Contract::__get_raw(key.clone()), Some(Base64VecU8(b"value".to_vec())));
+    assert!(Contract::__storage_usage() > usage);
+
+    assert!(Contract::__remove_raw(key.clone(), None));
+    assert_eq!(Contract::__get_raw(key), None);
+
+    Contract::__set_raw(Base64VecU8(b"STATE".to_vec()), Base64VecU8(vec![]), Some(true));
+    assert!(env::state_exists());
+
+    let refused = std::panic::catch_unwind(|| {
+        Contract::__remove_raw(Base64VecU8(b"STATE".to_vec()), None);
+    });
+    assert!(refused.is_err());
+    assert!(env::state_exists());
+}
@@ -0,0 +1,223 @@
+//! A set of `u64` indices stored as bits, for compact flags such as claimed airdrop indices.
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::collections::{append, append_slice, UnorderedSet};
+use crate::environment::register_prefix;
+use crate::errors::SdkError;
+use crate::{env, IntoStorageKey};
+
+/// Number of bytes of each page.
+const PAGE_BYTES: usize = 64;
+/// Number of bits of each page.
+const PAGE_BITS: u64 = PAGE_BYTES as u64 * 8;
+
+/// A set of `u64` indices stored as bits on the trie.
+///
+/// The bits are grouped into pages of 512 bits, each stored under its own key, so a flag takes a
+/// single bit when the indices are dense, instead of a storage entry per flag. Pages without any
+/// set bit are removed from storage. The indices of the stored pages are kept in an
+/// [`UnorderedSet`], so that iterating skips empty pages.
+///
+/// # Examples
+/// ```
+/// use near_sdk::collections::BitSet;
+///
+/// let mut claimed = BitSet::new(b"c");
+/// assert!(claimed.set(3));
+/// assert!(!claimed.set(3));
+/// assert!(claimed.get(3));
+/// claimed.set(1_000_000);
+/// assert_eq!(claimed.count_ones(), 2);
+/// assert_eq!(claimed.iter().collect::<Vec<_>>(), [3, 1_000_000]);
+/// ```
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct BitSet {
+    prefix: Vec<u8>,
+    pages: UnorderedSet<u64>,
+    count: u64,
+}
+
+impl BitSet {
+    /// Create new set with no bits set. Use `prefix` as a unique identifier.
+    pub fn new<S>(prefix: S) -> Self
+    where
+        S: IntoStorageKey,
+    {
+        let prefix = prefix.into_storage_key();
+        let _scope = register_prefix::<Self>(&prefix);
+        Self { pages: UnorderedSet::new(append(&prefix, b'p')), prefix, count: 0 }
+    }
+
+    fn page_key(&self, page: u64) -> Vec<u8> {
+        append_slice(&append(&self.prefix, b'b'), &page.to_le_bytes())
+    }
+
+    fn read_page(&self, page: u64) -> Option<[u8; PAGE_BYTES]> {
+        let raw_page = env::storage_read(&self.page_key(page))?;
+        let mut bytes = [0; PAGE_BYTES];
+        if raw_page.len() != PAGE_BYTES {
+            SdkError::CollectionInconsistent { collection: "BitSet" }.panic()
+        }
+        bytes.copy_from_slice(&raw_page);
+        Some(bytes)
+    }
+
+    /// Splits `index` into its page, the byte within the page and the mask of the bit.
+    fn locate(index: u64) -> (u64, usize, u8) {
+        let offset = index % PAGE_BITS;
+        (index / PAGE_BITS, (offset / 8) as usize, 1 << (offset % 8))
+    }
+
+    /// Returns the number of set bits.
+    pub fn count_ones(&self) -> u64 {
+        self.count
+    }
+
+    /// Returns `true` if no bit is set.
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Returns `true` if the bit at `index` is set.
+    pub fn get(&self, index: u64) -> bool {
+        let (page, byte, mask) = Self::locate(index);
+        self.read_page(page).map_or(false, |bytes| bytes[byte] & mask != 0)
+    }
+
+    /// Sets the bit at `index`. Returns `false` if it was already set.
+    pub fn set(&mut self, index: u64) -> bool {
+        let (page, byte, mask) = Self::locate(index);
+        let mut bytes = match self.read_page(page) {
+            Some(bytes) => bytes,
+            None => {
+                self.pages.insert(&page);
+                [0; PAGE_BYTES]
+            }
+        };
+        if bytes[byte] & mask != 0 {
+            return false;
+        }
+        bytes[byte] |= mask;
+        env::storage_write(&self.page_key(page), &bytes);
+        self.count += 1;
+        true
+    }
+
+    /// Clears the bit at `index`. Returns `false` if it wasn't set.
+    pub fn unset(&mut self, index: u64) -> bool {
+        let (page, byte, mask) = Self::locate(index);
+        let mut bytes = match self.read_page(page) {
+            Some(bytes) if bytes[byte] & mask != 0 => bytes,
+            _ => return false,
+        };
+        bytes[byte] &= !mask;
+        if bytes.iter().all(|&b| b == 0) {
+            env::storage_remove(&self.page_key(page));
+            self.pages.remove(&page);
+        } else {
+            env::storage_write(&self.page_key(page), &bytes);
+        }
+        self.count -= 1;
+        true
+    }
+
+    /// Iterates over the indices of the set bits in increasing order. The indices of the stored
+    /// pages are read and sorted first, and then each page is read when reaching it.
+    pub fn iter(&self) -> impl Iterator<Item = u64> + '_ {
+        let mut pages = self.pages.to_vec();
+        pages.sort_unstable();
+        pages.into_iter().flat_map(move |page| {
+            let bytes = self.read_page(page).unwrap_or_else(|| {
+                SdkError::CollectionInconsistent { collection: "BitSet" }.panic()
+            });
+            (0..PAGE_BITS)
+                .filter(move |offset| bytes[(offset / 8) as usize] & (1 << (offset % 8)) != 0)
+                .map(move |offset| page * PAGE_BITS + offset)
+        })
+    }
+
+    /// Clears every bit, removing all pages from storage.
+    pub fn clear(&mut self) {
+        for page in self.pages.iter() {
+            env::storage_remove(&self.page_key(page));
+        }
+        self.pages.clear();
+        self.count = 0;
+    }
+}
+
+impl std::fmt::Debug for BitSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BitSet")
+            .field("prefix", &self.prefix)
+            .field("count", &self.count)
+            .field("pages", &self.pages.len())
+            .finish()
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use super::BitSet;
+    use crate::collections::mocked_storage_suffixes;
+
+    #[test]
+    fn page_boundaries() {
+        let mut set = BitSet::new(b"s");
+        let indices = [0, 7, 8, 511, 512, 1023, 1024];
+        for &index in &indices {
+            assert!(!set.get(index));
+            assert!(set.set(index));
+        }
+        for &index in &indices {
+            assert!(set.get(index));
+        }
+        assert!(!set.get(1));
+        assert!(!set.get(510));
+        assert!(!set.get(513));
+        assert_eq!(set.count_ones(), indices.len() as u64);
+        assert_eq!(set.iter().collect::<Vec<_>>(), indices);
+
+        assert!(set.unset(512));
+        assert!(!set.unset(512));
+        assert!(!set.get(512));
+        assert!(set.get(511));
+        assert!(set.get(1023));
+        assert_eq!(set.count_ones(), indices.len() as u64 - 1);
+    }
+
+    #[test]
+    fn sparse_pages() {
+        let mut set = BitSet::new(b"s");
+        let indices = [u64::MAX, 3, 1 << 40, 5_000_000, 5_000_001];
+        for &index in &indices {
+            set.set(index);
+        }
+        assert_eq!(set.count_ones(), 5);
+        assert_eq!(set.iter().collect::<Vec<_>>(), [3, 5_000_000, 5_000_001, 1 << 40, u64::MAX]);
+
+        // Each page is removed once its last bit is cleared.
+        for &index in &indices {
+            assert!(set.unset(index));
+        }
+        assert!(set.is_empty());
+        assert_eq!(set.iter().count(), 0);
+        assert!(mocked_storage_suffixes(b"s", None).is_empty());
+    }
+
+    #[test]
+    fn clear() {
+        let mut set = BitSet::new(b"s");
+        for index in (0..2048).step_by(3) {
+            set.set(index);
+        }
+        assert_eq!(set.count_ones(), 683);
+        set.clear();
+        assert!(set.is_empty());
+        assert!(!set.get(3));
+        assert!(mocked_storage_suffixes(b"s", None).is_empty());
+        assert!(set.set(3));
+        assert_eq!(set.count_ones(), 1);
+    }
+}
@@ -0,0 +1,195 @@
+//! Buffering of repeated writes to the entries of a map within one call.
+use std::collections::BTreeMap;
+
+use borsh::BorshSerialize;
+
+use crate::collections::ValueCodec;
+use crate::errors::SdkError;
+
+/// A map whose writes can be buffered by [`Coalesced`].
+pub trait CoalesceTarget {
+    type Key: BorshSerialize;
+    type Value;
+    type Codec: ValueCodec<Self::Value>;
+
+    /// Reads the value of `key` from storage.
+    fn read(&self, key: &Self::Key) -> Option<Self::Value>;
+
+    /// Writes the serialized value of a serialized key to storage.
+    fn write_raw(&mut self, key_raw: &[u8], value_raw: &[u8]);
+
+    /// Removes a serialized key from storage.
+    fn remove_raw(&mut self, key_raw: &[u8]);
+}
+
+/// A view of a map which keeps the inserted and removed entries in memory and writes each
+/// modified key once, when flushed.
+///
+/// Created by the `coalesced` method of [`LookupMap`](crate::collections::LookupMap) and
+/// [`UnorderedMap`](crate::collections::UnorderedMap), which flushes the pending writes when the
+/// closure returns. Methods updating the same entry many times in one call, such as accruing
+/// rewards in a loop, then serialize and write the entry once instead of on every update. Reads
+/// return the pending value of a key if there is one, and read the map otherwise.
+///
+/// The pending writes are flushed in the order of the serialized keys, so new keys of an
+/// `UnorderedMap` are appended in that order rather than in the order they were inserted.
+///
+/// # Examples
+/// ```
+/// use near_sdk::collections::UnorderedMap;
+///
+/// let mut rewards: UnorderedMap<String, u128> = UnorderedMap::new(b"r");
+/// rewards.coalesced(|rewards| {
+///     for _ in 0..10 {
+///         let reward = rewards.get(&"alice".to_string()).unwrap_or(0);
+///         rewards.insert(&"alice".to_string(), &(reward + 5));
+///     }
+/// });
+/// assert_eq!(rewards.get(&"alice".to_string()), Some(50));
+/// ```
+pub struct Coalesced<'a, M: CoalesceTarget> {
+    map: &'a mut M,
+    pending: BTreeMap<Vec<u8>, Option<M::Value>>,
+}
+
+impl<'a, M: CoalesceTarget> Coalesced<'a, M> {
+    pub(crate) fn new(map: &'a mut M) -> Self {
+        Self { map, pending: BTreeMap::new() }
+    }
+
+    /// Returns the number of keys with pending writes.
+    pub fn pending_len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Writes the pending value of each modified key to the map.
+    pub fn flush(&mut self) {
+        for (key_raw, value) in std::mem::take(&mut self.pending) {
+            match value {
+                Some(value) => self.map.write_raw(&key_raw, &M::Codec::encode(&value)),
+                None => self.map.remove_raw(&key_raw),
+            }
+        }
+    }
+}
+
+impl<'a, M> Coalesced<'a, M>
+where
+    M: CoalesceTarget,
+    M::Value: Clone,
+{
+    fn serialize_key(key: &M::Key) -> Vec<u8> {
+        key.try_to_vec().unwrap_or_else(|_| SdkError::Serialization { context: "key" }.panic())
+    }
+
+    /// Returns the value of the key, which may not be written to storage yet.
+    pub fn get(&self, key: &M::Key) -> Option<M::Value> {
+        match self.pending.get(&Self::serialize_key(key)) {
+            Some(value) => value.clone(),
+            None => self.map.read(key),
+        }
+    }
+
+    /// Returns `true` if the key has a value, which may not be written to storage yet.
+    pub fn contains_key(&self, key: &M::Key) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Sets the value of the key. The value is written when the view is flushed.
+    pub fn insert(&mut self, key: &M::Key, value: &M::Value) {
+        self.pending.insert(Self::serialize_key(key), Some(value.clone()));
+    }
+
+    /// Removes the key. The entry is removed from storage when the view is flushed.
+    pub fn remove(&mut self, key: &M::Key) {
+        self.pending.insert(Self::serialize_key(key), None);
+    }
+}
+
+impl<'a, M: CoalesceTarget> Drop for Coalesced<'a, M> {
+    fn drop(&mut self) {
+        // Writing during a panic could hide its message behind a second one.
+        if !std::thread::panicking() {
+            self.flush();
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use crate::collections::{LookupMap, UnorderedMap};
+    use crate::mock::with_mocked_blockchain;
+
+    fn storage_writes() -> u64 {
+        with_mocked_blockchain(|b| b.storage_write_count())
+    }
+
+    #[test]
+    fn repeated_updates_write_once() {
+        let mut map: LookupMap<u64, u128> = LookupMap::new(b"m");
+        map.insert(&1, &0);
+        let writes = storage_writes();
+        map.coalesced(|map| {
+            for _ in 0..50 {
+                let reward = map.get(&1).unwrap();
+                map.insert(&1, &(reward + 3));
+            }
+            assert_eq!(map.pending_len(), 1);
+        });
+        assert_eq!(storage_writes() - writes, 1);
+        assert_eq!(map.get(&1), Some(150));
+
+        let mut map: UnorderedMap<u64, u128> = UnorderedMap::new(b"u");
+        map.insert(&1, &0);
+        let writes = storage_writes();
+        map.coalesced(|map| {
+            for _ in 0..50 {
+                let reward = map.get(&1).unwrap();
+                map.insert(&1, &(reward + 3));
+            }
+        });
+        assert_eq!(storage_writes() - writes, 1);
+        assert_eq!(map.get(&1), Some(150));
+    }
+
+    #[test]
+    fn reads_see_pending_writes() {
+        let mut map: UnorderedMap<u64, String> = UnorderedMap::new(b"m");
+        map.insert(&1, &"stored".to_string());
+        map.insert(&2, &"removed".to_string());
+        let writes = storage_writes();
+        map.coalesced(|map| {
+            assert_eq!(map.get(&1), Some("stored".to_string()));
+            map.insert(&1, &"pending".to_string());
+            assert_eq!(map.get(&1), Some("pending".to_string()));
+            map.remove(&2);
+            assert_eq!(map.get(&2), None);
+            assert!(!map.contains_key(&2));
+            map.insert(&3, &"new".to_string());
+            assert_eq!(map.get(&3), Some("new".to_string()));
+            // Nothing is written until the view is flushed.
+            assert_eq!(storage_writes(), writes);
+        });
+        assert_eq!(map.to_vec(), [(1, "pending".to_string()), (3, "new".to_string())]);
+        assert!(map.is_storage_clean());
+    }
+
+    #[test]
+    fn flush_within_scope() {
+        let mut map: LookupMap<u64, u64> = LookupMap::new(b"m");
+        let writes = storage_writes();
+        let total = map.coalesced(|map| {
+            map.insert(&1, &10);
+            map.insert(&1, &20);
+            map.flush();
+            assert_eq!(map.pending_len(), 0);
+            assert_eq!(storage_writes() - writes, 1);
+            map.insert(&1, &30);
+            map.remove(&1);
+            map.get(&1).unwrap_or_default()
+        });
+        assert_eq!(total, 0);
+        assert_eq!(map.get(&1), None);
+    }
+}
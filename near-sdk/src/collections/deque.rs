@@ -0,0 +1,387 @@
+//! A double-ended queue implemented on a trie. Elements are stored under keys derived from two
+//! cursors, so pushing and popping at either end only touches a single storage key.
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::collections::{append_slice, HandleId};
+use crate::env;
+use crate::environment::register_prefix;
+use crate::errors::SdkError;
+use crate::IntoStorageKey;
+
+/// A persistent double-ended queue. Pushing and popping at either end is `O(1)` storage
+/// operations.
+///
+/// The elements are stored between the `head` and `tail` cursors, which wrap around at the
+/// bounds of `u64`, so the cursors can move indefinitely as long as the queue holds fewer than
+/// `u64::MAX` elements. Only the cursors and the prefix are serialized with the queue.
+///
+/// # Examples
+/// ```
+/// use near_sdk::collections::Deque;
+///
+/// let mut jobs = Deque::new(b"j");
+/// jobs.push_back(&1u64);
+/// jobs.push_back(&2);
+/// jobs.push_front(&0);
+/// assert_eq!(jobs.pop_front(), Some(0));
+/// assert_eq!(jobs.iter().collect::<Vec<_>>(), vec![1, 2]);
+/// ```
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct Deque<T> {
+    head: u64,
+    tail: u64,
+    prefix: Vec<u8>,
+    #[borsh_skip]
+    el: PhantomData<T>,
+    #[borsh_skip]
+    handle: HandleId,
+}
+
+impl<T> Deque<T> {
+    /// Returns the number of elements in the queue.
+    pub fn len(&self) -> u64 {
+        self.tail.wrapping_sub(self.head)
+    }
+
+    /// Returns `true` if the queue contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.head == self.tail
+    }
+
+    /// Creates a new queue with zero elements. Use `prefix` as a unique identifier on the trie.
+    pub fn new<S>(prefix: S) -> Self
+    where
+        S: IntoStorageKey,
+    {
+        let prefix = prefix.into_storage_key();
+        let _scope = register_prefix::<Self>(&prefix);
+        Self { head: 0, tail: 0, prefix, el: PhantomData, handle: HandleId::default() }
+    }
+
+    /// Creates another handle to this queue, intended for read paths. The cursors are copied, so
+    /// the new handle does not observe elements pushed or popped through this one. With the
+    /// `check-handle-aliasing` feature enabled, writing through both handles within a call panics.
+    pub fn duplicate_handle(&self) -> Self {
+        Self {
+            head: self.head,
+            tail: self.tail,
+            prefix: self.prefix.clone(),
+            el: PhantomData,
+            handle: HandleId::default(),
+        }
+    }
+
+    fn index_to_lookup_key(&self, index: u64) -> Vec<u8> {
+        append_slice(&self.prefix, &index.to_le_bytes()[..])
+    }
+
+    fn get_raw(&self, index: u64) -> Vec<u8> {
+        env::storage_read(&self.index_to_lookup_key(index))
+            .unwrap_or_else(|| SdkError::CollectionInconsistent { collection: "Deque" }.panic())
+    }
+
+    fn take_raw(&mut self, index: u64) -> Vec<u8> {
+        self.handle.record_write(&self.prefix);
+        if env::storage_remove(&self.index_to_lookup_key(index)) {
+            env::storage_get_evicted()
+                .unwrap_or_else(|| SdkError::CollectionInconsistent { collection: "Deque" }.panic())
+        } else {
+            SdkError::CollectionInconsistent { collection: "Deque" }.panic()
+        }
+    }
+
+    fn check_capacity(&self) {
+        if self.len() == u64::MAX {
+            env::panic_str("Deque is full")
+        }
+    }
+
+    /// Returns `true` if the mocked storage holds exactly the elements of this queue, with no
+    /// stale entries outside of its cursors. Only available in unit tests.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn is_storage_clean(&self) -> bool {
+        let indices = crate::collections::mocked_storage_suffixes(&self.prefix, Some(8));
+        indices.len() as u64 == self.len()
+            && indices.iter().all(|index| {
+                let mut bytes = [0u8; 8];
+                bytes.copy_from_slice(index);
+                u64::from_le_bytes(bytes).wrapping_sub(self.head) < self.len()
+            })
+    }
+
+    /// Removes all elements from the queue. This is `O(n)` storage removals.
+    pub fn clear(&mut self) {
+        self.handle.record_write(&self.prefix);
+        let mut index = self.head;
+        while index != self.tail {
+            env::storage_remove(&self.index_to_lookup_key(index));
+            index = index.wrapping_add(1);
+        }
+        self.head = 0;
+        self.tail = 0;
+    }
+}
+
+impl<T> Deque<T>
+where
+    T: BorshSerialize + BorshDeserialize,
+{
+    fn serialize_element(element: &T) -> Vec<u8> {
+        element
+            .try_to_vec()
+            .unwrap_or_else(|_| SdkError::Serialization { context: "element" }.panic())
+    }
+
+    fn deserialize_element(raw_element: &[u8]) -> T {
+        T::try_from_slice(raw_element)
+            .unwrap_or_else(|_| SdkError::Deserialization { context: "element" }.panic())
+    }
+
+    /// Appends an element to the back of the queue.
+    pub fn push_back(&mut self, element: &T) {
+        self.check_capacity();
+        let raw_element = Self::serialize_element(element);
+        self.handle.record_write(&self.prefix);
+        env::storage_write(&self.index_to_lookup_key(self.tail), &raw_element);
+        self.tail = self.tail.wrapping_add(1);
+    }
+
+    /// Prepends an element to the front of the queue.
+    pub fn push_front(&mut self, element: &T) {
+        self.check_capacity();
+        let raw_element = Self::serialize_element(element);
+        self.handle.record_write(&self.prefix);
+        self.head = self.head.wrapping_sub(1);
+        env::storage_write(&self.index_to_lookup_key(self.head), &raw_element);
+    }
+
+    /// Removes the last element and returns it, or `None` if the queue is empty.
+    pub fn pop_back(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+        let index = self.tail.wrapping_sub(1);
+        let raw_element = self.take_raw(index);
+        self.tail = index;
+        Some(Self::deserialize_element(&raw_element))
+    }
+
+    /// Removes the first element and returns it, or `None` if the queue is empty.
+    pub fn pop_front(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+        let raw_element = self.take_raw(self.head);
+        self.head = self.head.wrapping_add(1);
+        Some(Self::deserialize_element(&raw_element))
+    }
+
+    /// Returns the first element, or `None` if the queue is empty.
+    pub fn front(&self) -> Option<T> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(Self::deserialize_element(&self.get_raw(self.head)))
+        }
+    }
+
+    /// Returns the last element, or `None` if the queue is empty.
+    pub fn back(&self) -> Option<T> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(Self::deserialize_element(&self.get_raw(self.tail.wrapping_sub(1))))
+        }
+    }
+
+    /// Iterates over the elements from front to back. Elements are read from storage as the
+    /// iterator advances.
+    pub fn iter(&self) -> Iter<T> {
+        Iter { deque: self, front: 0, back: self.len() }
+    }
+}
+
+impl<T> std::fmt::Debug for Deque<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Deque")
+            .field("head", &self.head)
+            .field("tail", &self.tail)
+            .field("prefix", &self.prefix)
+            .finish()
+    }
+}
+
+/// An iterator over the elements of a [`Deque`], from front to back.
+pub struct Iter<'a, T> {
+    deque: &'a Deque<T>,
+    /// Offsets from the head of the deque of the next elements to yield from each end.
+    front: u64,
+    back: u64,
+}
+
+impl<'a, T> Iter<'a, T>
+where
+    T: BorshSerialize + BorshDeserialize,
+{
+    fn element_at(&self, offset: u64) -> T {
+        Deque::<T>::deserialize_element(&self.deque.get_raw(self.deque.head.wrapping_add(offset)))
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T>
+where
+    T: BorshSerialize + BorshDeserialize,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        let element = self.element_at(self.front);
+        self.front += 1;
+        Some(element)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = (self.back - self.front) as usize;
+        (remaining, Some(remaining))
+    }
+
+    fn count(self) -> usize {
+        (self.back - self.front) as usize
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for Iter<'a, T>
+where
+    T: BorshSerialize + BorshDeserialize,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        self.back -= 1;
+        Some(self.element_at(self.back))
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T> where T: BorshSerialize + BorshDeserialize {}
+impl<'a, T> FusedIterator for Iter<'a, T> where T: BorshSerialize + BorshDeserialize {}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    /// Creates a deque whose cursors start at `start`, as if it had been used for that many
+    /// pushes and pops.
+    fn deque_at(start: u64) -> Deque<u64> {
+        let mut deque = Deque::new(b"d");
+        deque.head = start;
+        deque.tail = start;
+        deque
+    }
+
+    #[test]
+    fn test_push_pop() {
+        let mut deque = Deque::new(b"d");
+        assert_eq!(deque.pop_front(), None);
+        assert_eq!(deque.pop_back(), None);
+        deque.push_back(&1u64);
+        deque.push_back(&2);
+        deque.push_front(&0);
+        assert_eq!(deque.len(), 3);
+        assert_eq!(deque.front(), Some(0));
+        assert_eq!(deque.back(), Some(2));
+        assert_eq!(deque.pop_front(), Some(0));
+        assert_eq!(deque.pop_back(), Some(2));
+        assert_eq!(deque.pop_back(), Some(1));
+        assert!(deque.is_empty());
+        assert_eq!(deque.front(), None);
+        assert_eq!(deque.back(), None);
+    }
+
+    #[test]
+    fn test_wrapping_cursors() {
+        for &start in &[0, 5_000_000, u64::MAX - 500] {
+            let mut deque = deque_at(start);
+            let mut baseline = VecDeque::new();
+            for i in 0..1_000u64 {
+                deque.push_back(&i);
+                baseline.push_back(i);
+                if i % 3 == 0 {
+                    assert_eq!(deque.pop_front(), baseline.pop_front());
+                }
+            }
+            for i in 0..600u64 {
+                deque.push_front(&i);
+                baseline.push_front(i);
+            }
+            assert_eq!(deque.len(), baseline.len() as u64);
+            assert_eq!(
+                deque.iter().collect::<Vec<_>>(),
+                baseline.iter().copied().collect::<Vec<_>>()
+            );
+            assert_eq!(
+                deque.iter().rev().collect::<Vec<_>>(),
+                baseline.iter().rev().copied().collect::<Vec<_>>()
+            );
+            while let Some(element) = baseline.pop_back() {
+                assert_eq!(deque.pop_back(), Some(element));
+            }
+            assert!(deque.is_empty());
+            deque.clear();
+        }
+    }
+
+    #[test]
+    fn test_head_wraps_below_zero() {
+        let mut deque = deque_at(1);
+        deque.push_front(&1);
+        deque.push_front(&2);
+        assert_eq!(deque.head, u64::MAX);
+        assert_eq!(deque.len(), 2);
+        assert_eq!(deque.iter().collect::<Vec<_>>(), vec![2, 1]);
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut deque = deque_at(u64::MAX - 2);
+        let usage = env::storage_usage();
+        for i in 0..10u64 {
+            deque.push_back(&i);
+        }
+        deque.clear();
+        assert!(deque.is_empty());
+        assert!(deque.is_storage_clean());
+        assert_eq!(env::storage_usage(), usage);
+        deque.push_back(&1);
+        deque.push_front(&0);
+        assert!(deque.is_storage_clean());
+        assert_eq!(deque.front(), Some(0));
+    }
+
+    #[test]
+    fn test_serialized_layout() {
+        let mut deque = deque_at(7);
+        deque.push_back(&1);
+        let bytes = deque.try_to_vec().unwrap();
+        assert_eq!(bytes, (7u64, 8u64, b"d".to_vec()).try_to_vec().unwrap());
+        let restored = Deque::<u64>::try_from_slice(&bytes).unwrap();
+        assert_eq!(restored.front(), Some(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "The collection is an inconsistent state")]
+    fn test_inconsistent_state() {
+        let mut deque = Deque::new(b"d");
+        deque.push_back(&1u64);
+        env::storage_remove(&[b"d".as_ref(), &0u64.to_le_bytes()].concat());
+        deque.pop_front();
+    }
+}
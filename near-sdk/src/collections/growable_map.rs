@@ -0,0 +1,275 @@
+//! A map which is kept inline in the contract state while it is small, and moves itself into an
+//! [`UnorderedMap`] once it grows past a threshold.
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::mem;
+
+use borsh::maybestd::io;
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::collections::UnorderedMap;
+use crate::environment::{nested_scope, register_prefix};
+use crate::IntoStorageKey;
+
+/// A map which starts as a `HashMap` stored inline in the structure containing it, and migrates
+/// itself into an [`UnorderedMap`] under its prefix when an insert would make it hold more than
+/// `threshold` entries.
+///
+/// Small maps are cheapest to keep inline, as they are read and written together with the rest
+/// of the contract state. Once the map grows, reading and writing every entry on each call gets
+/// expensive, and the entries are moved into storage. The call inserting the entry past the
+/// threshold pays for writing every entry once. The map doesn't move back inline when entries are
+/// removed.
+///
+/// The iteration order of an inline map is arbitrary, while an [`UnorderedMap`] iterates in
+/// insertion order. When migrating, the entries are inserted in the order of their serialized keys.
+///
+/// # Borsh layout
+///
+/// The layout is stable, so a `GrowableMap` can be stored in the contract state:
+///
+/// - [`Inline`](Self::Inline): `0u8`, followed by the `prefix` as `Vec<u8>`, the `threshold` as
+///   `u32` and the entries as `HashMap<K, V>`, which Borsh serializes sorted by key.
+/// - [`Persistent`](Self::Persistent): `1u8`, followed by the [`UnorderedMap`].
+///
+/// # Examples
+/// ```
+/// use near_sdk::collections::GrowableMap;
+///
+/// let mut map = GrowableMap::new(b"m", 2);
+/// map.insert(&1u32, &"a".to_string());
+/// map.insert(&2, &"b".to_string());
+/// assert!(map.is_inline());
+///
+/// // Inserting a third entry moves all entries into storage.
+/// map.insert(&3, &"c".to_string());
+/// assert!(!map.is_inline());
+/// assert_eq!(map.get(&1), Some("a".to_string()));
+/// ```
+pub enum GrowableMap<K, V> {
+    /// Entries kept inline, until there are more than `threshold` of them.
+    Inline { prefix: Vec<u8>, threshold: u32, map: HashMap<K, V> },
+    /// Entries moved into storage.
+    Persistent(UnorderedMap<K, V>),
+}
+
+impl<K, V> BorshSerialize for GrowableMap<K, V>
+where
+    K: BorshSerialize + PartialOrd,
+    V: BorshSerialize,
+{
+    fn serialize<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        match self {
+            Self::Inline { prefix, threshold, map } => {
+                BorshSerialize::serialize(&0u8, writer)?;
+                BorshSerialize::serialize(prefix, writer)?;
+                BorshSerialize::serialize(threshold, writer)?;
+                BorshSerialize::serialize(map, writer)
+            }
+            Self::Persistent(map) => {
+                BorshSerialize::serialize(&1u8, writer)?;
+                BorshSerialize::serialize(map, writer)
+            }
+        }
+    }
+}
+
+impl<K, V> BorshDeserialize for GrowableMap<K, V>
+where
+    K: BorshDeserialize + Hash + Eq,
+    V: BorshDeserialize,
+{
+    fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+        match u8::deserialize(buf)? {
+            0 => Ok(Self::Inline {
+                prefix: BorshDeserialize::deserialize(buf)?,
+                threshold: BorshDeserialize::deserialize(buf)?,
+                map: BorshDeserialize::deserialize(buf)?,
+            }),
+            1 => Ok(Self::Persistent(BorshDeserialize::deserialize(buf)?)),
+            tag => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Unexpected GrowableMap variant {}", tag),
+            )),
+        }
+    }
+}
+
+impl<K, V> GrowableMap<K, V> {
+    /// Creates an empty inline map. Its entries are moved into an [`UnorderedMap`] with the given
+    /// `prefix` once it would hold more than `threshold` entries.
+    pub fn new<S>(prefix: S, threshold: u32) -> Self
+    where
+        S: IntoStorageKey,
+    {
+        let prefix = prefix.into_storage_key();
+        let _scope = register_prefix::<Self>(&prefix);
+        Self::Inline { prefix, threshold, map: HashMap::new() }
+    }
+
+    /// Returns `true` if the entries are still kept inline.
+    pub fn is_inline(&self) -> bool {
+        matches!(self, Self::Inline { .. })
+    }
+
+    /// Returns the number of entries in the map.
+    pub fn len(&self) -> u64 {
+        match self {
+            Self::Inline { map, .. } => map.len() as u64,
+            Self::Persistent(map) => map.len(),
+        }
+    }
+
+    /// Returns `true` if the map contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<K, V> GrowableMap<K, V>
+where
+    K: BorshSerialize + BorshDeserialize + Hash + Eq + Clone,
+    V: BorshSerialize + BorshDeserialize + Clone,
+{
+    /// Returns the value corresponding to the key.
+    pub fn get(&self, key: &K) -> Option<V> {
+        match self {
+            Self::Inline { map, .. } => map.get(key).cloned(),
+            Self::Persistent(map) => map.get(key),
+        }
+    }
+
+    /// Returns `true` if the map contains a value for the key.
+    pub fn contains_key(&self, key: &K) -> bool {
+        match self {
+            Self::Inline { map, .. } => map.contains_key(key),
+            Self::Persistent(map) => map.get(key).is_some(),
+        }
+    }
+
+    /// Inserts a key-value pair into the map, returning the previous value of the key. If the key
+    /// is new and the map already holds `threshold` entries inline, all entries are first moved
+    /// into storage.
+    pub fn insert(&mut self, key: &K, value: &V) -> Option<V> {
+        if let Self::Inline { threshold, map, .. } = self {
+            if map.len() >= *threshold as usize && !map.contains_key(key) {
+                self.migrate();
+            }
+        }
+        match self {
+            Self::Inline { map, .. } => map.insert(key.clone(), value.clone()),
+            Self::Persistent(map) => map.insert(key, value),
+        }
+    }
+
+    /// Removes a key from the map, returning its value if it was in the map.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        match self {
+            Self::Inline { map, .. } => map.remove(key),
+            Self::Persistent(map) => map.remove(key),
+        }
+    }
+
+    /// Iterates over the entries of the map.
+    pub fn iter(&self) -> impl Iterator<Item = (K, V)> + '_ {
+        let (inline, persistent) = match self {
+            Self::Inline { map, .. } => (Some(map.iter()), None),
+            Self::Persistent(map) => (None, Some(map.iter())),
+        };
+        inline
+            .into_iter()
+            .flatten()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .chain(persistent.into_iter().flatten())
+    }
+
+    /// Moves the entries into an [`UnorderedMap`].
+    fn migrate(&mut self) {
+        let (prefix, map) = match self {
+            Self::Inline { prefix, map, .. } => (mem::take(prefix), mem::take(map)),
+            Self::Persistent(_) => return,
+        };
+        // The map keeps the prefix registered by `new`.
+        let _scope = nested_scope();
+        *self = Self::Persistent(UnorderedMap::from_std(prefix, map));
+    }
+}
+
+impl<K, V> std::fmt::Debug for GrowableMap<K, V>
+where
+    K: std::fmt::Debug + BorshSerialize + BorshDeserialize,
+    V: std::fmt::Debug + BorshSerialize + BorshDeserialize,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Inline { prefix, threshold, map } => f
+                .debug_struct("Inline")
+                .field("prefix", prefix)
+                .field("threshold", threshold)
+                .field("map", map)
+                .finish(),
+            Self::Persistent(map) => f.debug_tuple("Persistent").field(map).finish(),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::env;
+
+    #[test]
+    fn migrates_past_threshold() {
+        let mut map = GrowableMap::new(b"m", 3);
+        let usage = env::storage_usage();
+        for i in 0u64..3 {
+            assert_eq!(map.insert(&i, &(i * 10)), None);
+        }
+        assert!(map.is_inline());
+        assert_eq!(env::storage_usage(), usage);
+
+        // Replacing an existing key doesn't migrate.
+        assert_eq!(map.insert(&0, &1), Some(0));
+        assert!(map.is_inline());
+        assert_eq!(map.get(&0), Some(1));
+        assert_eq!(map.get(&2), Some(20));
+
+        assert_eq!(map.insert(&3, &30), None);
+        assert!(!map.is_inline());
+        assert!(env::storage_usage() > usage);
+        assert_eq!(map.len(), 4);
+        assert_eq!(map.get(&0), Some(1));
+        assert_eq!(map.get(&3), Some(30));
+        assert!(!map.contains_key(&4));
+        assert_eq!(map.iter().collect::<Vec<_>>(), [(0, 1), (1, 10), (2, 20), (3, 30)]);
+
+        // Removing entries doesn't move the map back inline.
+        for i in 0u64..4 {
+            assert!(map.remove(&i).is_some());
+        }
+        assert!(map.is_empty());
+        assert!(!map.is_inline());
+    }
+
+    #[test]
+    fn borsh_layout() {
+        let mut map = GrowableMap::new(b"m".to_vec(), 2);
+        map.insert(&2u8, &20u8);
+        map.insert(&1u8, &10u8);
+        let bytes = map.try_to_vec().unwrap();
+        assert_eq!(bytes, [0, 1, 0, 0, 0, b'm', 2, 0, 0, 0, 2, 0, 0, 0, 1, 10, 2, 20]);
+        let map = GrowableMap::<u8, u8>::try_from_slice(&bytes).unwrap();
+        assert_eq!(map.get(&1), Some(10));
+
+        let mut map = map;
+        map.insert(&3, &30);
+        let bytes = map.try_to_vec().unwrap();
+        assert_eq!(bytes[0], 1);
+        let map = GrowableMap::<u8, u8>::try_from_slice(&bytes).unwrap();
+        assert!(!map.is_inline());
+        assert_eq!(map.get(&3), Some(30));
+
+        assert!(GrowableMap::<u8, u8>::try_from_slice(&[2]).is_err());
+    }
+}
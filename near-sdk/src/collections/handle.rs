@@ -0,0 +1,205 @@
+//! Detection of persistent collections being written through multiple handles.
+//!
+//! Two collections created with the same prefix alias the same storage, so writes through one are
+//! not reflected in the in-memory state of the other (for example the length of a [`Vector`]).
+//! With the `check-handle-aliasing` feature enabled, each handle records the prefixes it writes to
+//! and writing to the same prefix through a second live handle panics. The check is opt-in, as
+//! migrations legitimately keep handles to the old and the new layout of a prefix alive together.
+
+use crate::collections::key_encoding::KeyEncoding;
+use crate::collections::{
+    Deque, Lazy, LazyOption, LookupMap, LookupSet, TreeMap, UnorderedMap, UnorderedSet, Vector,
+};
+
+#[cfg(feature = "check-handle-aliasing")]
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+};
+
+#[cfg(feature = "check-handle-aliasing")]
+thread_local! {
+    static NEXT_HANDLE_ID: Cell<u64> = Cell::new(0);
+    static WRITERS: RefCell<HashMap<Vec<u8>, u64>> = RefCell::default();
+}
+
+/// Identity of a collection handle. A new identity is created every time a collection is created
+/// or deserialized.
+pub(crate) struct HandleId {
+    #[cfg(feature = "check-handle-aliasing")]
+    id: u64,
+}
+
+impl Default for HandleId {
+    #[cfg(feature = "check-handle-aliasing")]
+    fn default() -> Self {
+        let id = NEXT_HANDLE_ID.with(|next| {
+            let id = next.get();
+            next.set(id + 1);
+            id
+        });
+        Self { id }
+    }
+
+    #[cfg(not(feature = "check-handle-aliasing"))]
+    fn default() -> Self {
+        Self {}
+    }
+}
+
+impl HandleId {
+    /// Records a write to `prefix` through this handle.
+    ///
+    /// # Panics
+    ///
+    /// With the `check-handle-aliasing` feature enabled, panics if another live handle has written
+    /// to `prefix`.
+    #[cfg(feature = "check-handle-aliasing")]
+    pub(crate) fn record_write(&self, prefix: &[u8]) {
+        let aliased = WRITERS.with(|writers| {
+            let mut writers = writers.borrow_mut();
+            let writer = writers.entry(prefix.to_vec()).or_insert(self.id);
+            *writer != self.id
+        });
+        if aliased {
+            crate::env::panic_str(&format!(
+                "Collection with prefix {:?} was written through multiple handles. \
+                 Handles created with the same prefix or with `duplicate_handle` must only be \
+                 used for reads.",
+                prefix
+            ))
+        }
+    }
+
+    #[cfg(not(feature = "check-handle-aliasing"))]
+    #[inline]
+    pub(crate) fn record_write(&self, _prefix: &[u8]) {}
+}
+
+#[cfg(feature = "check-handle-aliasing")]
+impl Drop for HandleId {
+    fn drop(&mut self) {
+        let id = self.id;
+        // The registry may already be destroyed if the handle is dropped at thread exit.
+        let _ = WRITERS.try_with(|writers| {
+            if let Ok(mut writers) = writers.try_borrow_mut() {
+                writers.retain(|_, writer| *writer != id);
+            }
+        });
+    }
+}
+
+/// Clones a value, creating a new handle to the same storage for persistent collections through
+/// their `duplicate_handle` methods. This is used by `#[derive(NearClone)]` to clone structures
+/// containing collections.
+pub trait NearClone {
+    fn near_clone(&self) -> Self;
+}
+
+impl<T: Clone> NearClone for T {
+    fn near_clone(&self) -> Self {
+        self.clone()
+    }
+}
+
+macro_rules! impl_near_clone {
+    ($($ty:ident<$($param:ident),+>),+ $(,)?) => {
+        $(
+            impl<$($param),+> NearClone for $ty<$($param),+> {
+                fn near_clone(&self) -> Self {
+                    self.duplicate_handle()
+                }
+            }
+        )+
+    };
+}
+
+impl_near_clone!(
+    LookupMap<K, V>,
+    LookupSet<T>,
+    Vector<T>,
+    UnorderedMap<K, V>,
+    UnorderedSet<T>,
+    LazyOption<T>,
+    Lazy<T>,
+    Deque<T>,
+);
+
+impl<K, V, E: KeyEncoding<K>> NearClone for TreeMap<K, V, E> {
+    fn near_clone(&self) -> Self {
+        self.duplicate_handle()
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use super::NearClone;
+    use crate::collections::{LookupMap, TreeMap, UnorderedMap, Vector};
+
+    #[test]
+    fn duplicate_reads() {
+        let mut map = LookupMap::new(b"m");
+        map.insert(&1u64, &2u64);
+        let dup = map.duplicate_handle();
+        assert_eq!(dup.get(&1), Some(2));
+        map.insert(&3, &4);
+        assert_eq!(dup.get(&3), Some(4));
+
+        let mut vec = Vector::new(b"v");
+        vec.push(&1u64);
+        let dup = vec.near_clone();
+        assert_eq!(dup.to_vec(), vec![1]);
+        vec.push(&2);
+        // The length is not shared between handles.
+        assert_eq!(dup.len(), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "check-handle-aliasing")]
+    #[should_panic(expected = "was written through multiple handles")]
+    fn aliased_writes() {
+        let mut map = UnorderedMap::new(b"m");
+        map.insert(&1u64, &2u64);
+        let mut dup = map.duplicate_handle();
+        dup.insert(&3, &4);
+    }
+
+    #[test]
+    #[cfg(feature = "check-handle-aliasing")]
+    #[should_panic(expected = "Collection with prefix [118] was written through multiple handles")]
+    fn same_prefix_writes() {
+        let mut a = Vector::new(b"v");
+        let mut b = Vector::new(b"v");
+        a.push(&1u8);
+        b.push(&2u8);
+    }
+
+    #[test]
+    fn write_after_drop() {
+        let mut map = TreeMap::new(b"t");
+        {
+            let mut dup = map.duplicate_handle();
+            dup.insert(&1u64, &1u64);
+        }
+        map.insert(&2, &2);
+        assert_eq!(map.get(&2), Some(2));
+    }
+
+    #[test]
+    fn near_clone_fields() {
+        struct Contract {
+            owner: String,
+            balances: LookupMap<String, u128>,
+        }
+
+        let mut contract = Contract { owner: "alice".to_string(), balances: LookupMap::new(b"b") };
+        contract.balances.insert(&"bob".to_string(), &10);
+        let view = Contract {
+            owner: contract.owner.near_clone(),
+            balances: contract.balances.near_clone(),
+        };
+        assert_eq!(view.owner, "alice");
+        assert_eq!(view.balances.get(&"bob".to_string()), Some(10));
+    }
+}
@@ -0,0 +1,364 @@
+//! Integer keys whose serialized bytes sort in the same order as their values.
+//!
+//! Borsh serializes integers as little-endian, so the bytes of `u64` keys stored by a [`TreeMap`]
+//! or used in composite storage keys don't sort in numeric order. The types in this module
+//! serialize as big-endian instead, with signed values shifted so that negative values come
+//! first. This makes the order of the raw keys, as seen by indexers or when comparing composite
+//! keys byte by byte, match the numeric order.
+//!
+//! With JSON the types serialize as base-10 strings, like [`U64`](crate::json_types::U64).
+//!
+//! # Examples
+//! ```
+//! use near_sdk::collections::key_encoding::OrderedU64;
+//! use near_sdk::collections::TreeMap;
+//!
+//! let mut map = TreeMap::new(b"m");
+//! map.insert(&OrderedU64::from(256), &"b".to_string());
+//! map.insert(&OrderedU64::from(1), &"a".to_string());
+//! assert_eq!(map.min().map(u64::from), Some(1));
+//! ```
+//!
+//! A [`TreeMap`] can also keep plain integer keys and store them in this form, with
+//! [`OrderedKeyEncoding`].
+//!
+//! [`TreeMap`]: crate::collections::TreeMap
+
+use borsh::maybestd::io;
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+
+macro_rules! impl_ordered_key {
+    ($(#[$doc:meta])* $iden:ident, $ty:ty, $bits:ty, $to_bits:expr, $from_bits:expr) => {
+        $(#[$doc])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+        pub struct $iden(pub $ty);
+
+        impl From<$ty> for $iden {
+            fn from(v: $ty) -> Self {
+                Self(v)
+            }
+        }
+
+        impl From<$iden> for $ty {
+            fn from(v: $iden) -> $ty {
+                v.0
+            }
+        }
+
+        impl fmt::Display for $iden {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Display::fmt(&self.0, f)
+            }
+        }
+
+        impl BorshSerialize for $iden {
+            fn serialize<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+                let to_bits: fn($ty) -> $bits = $to_bits;
+                writer.write_all(&to_bits(self.0).to_be_bytes())
+            }
+        }
+
+        impl BorshDeserialize for $iden {
+            fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+                const LEN: usize = std::mem::size_of::<$bits>();
+                if buf.len() < LEN {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "Unexpected length of input",
+                    ));
+                }
+                let mut bytes = [0u8; LEN];
+                bytes.copy_from_slice(&buf[..LEN]);
+                *buf = &buf[LEN..];
+                let from_bits: fn($bits) -> $ty = $from_bits;
+                Ok(Self(from_bits(<$bits>::from_be_bytes(bytes))))
+            }
+        }
+
+        impl Serialize for $iden {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: Serializer,
+            {
+                serializer.serialize_str(&self.0.to_string())
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $iden {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                let s: String = Deserialize::deserialize(deserializer)?;
+                s.parse::<$ty>().map(Self).map_err(serde::de::Error::custom)
+            }
+        }
+    };
+}
+
+impl_ordered_key!(
+    /// `u8` as a key type matching the other ordered keys. A single byte already sorts in
+    /// numeric order.
+    OrderedU8,
+    u8,
+    u8,
+    |v| v,
+    |v| v
+);
+impl_ordered_key!(
+    /// `u16` serialized as big-endian, so that the serialized keys sort in numeric order.
+    OrderedU16,
+    u16,
+    u16,
+    |v| v,
+    |v| v
+);
+impl_ordered_key!(
+    /// `u32` serialized as big-endian, so that the serialized keys sort in numeric order.
+    OrderedU32,
+    u32,
+    u32,
+    |v| v,
+    |v| v
+);
+impl_ordered_key!(
+    /// `u64` serialized as big-endian, so that the serialized keys sort in numeric order.
+    OrderedU64,
+    u64,
+    u64,
+    |v| v,
+    |v| v
+);
+impl_ordered_key!(
+    /// `u128` serialized as big-endian, so that the serialized keys sort in numeric order.
+    OrderedU128,
+    u128,
+    u128,
+    |v| v,
+    |v| v
+);
+impl_ordered_key!(
+    /// `i8` with the sign bit flipped, so that negative values sort before positive ones.
+    OrderedI8,
+    i8,
+    u8,
+    |v| (v as u8) ^ (1 << 7),
+    |v| (v ^ (1 << 7)) as i8
+);
+impl_ordered_key!(
+    /// `i16` serialized as big-endian with the sign bit flipped, so that negative values sort
+    /// before positive ones.
+    OrderedI16,
+    i16,
+    u16,
+    |v| (v as u16) ^ (1 << 15),
+    |v| (v ^ (1 << 15)) as i16
+);
+impl_ordered_key!(
+    /// `i32` serialized as big-endian with the sign bit flipped, so that negative values sort
+    /// before positive ones.
+    OrderedI32,
+    i32,
+    u32,
+    |v| (v as u32) ^ (1 << 31),
+    |v| (v ^ (1 << 31)) as i32
+);
+impl_ordered_key!(
+    /// `i64` serialized as big-endian with the sign bit flipped, so that negative values sort
+    /// before positive ones.
+    OrderedI64,
+    i64,
+    u64,
+    |v| (v as u64) ^ (1 << 63),
+    |v| (v ^ (1 << 63)) as i64
+);
+impl_ordered_key!(
+    /// `i128` serialized as big-endian with the sign bit flipped, so that negative values sort
+    /// before positive ones.
+    OrderedI128,
+    i128,
+    u128,
+    |v| (v as u128) ^ (1 << 127),
+    |v| (v ^ (1 << 127)) as i128
+);
+
+/// How the keys of a [`TreeMap`] are stored, selected by its last type parameter.
+///
+/// Keys are converted to [`Stored`](Self::Stored) keys, which are what the tree compares and
+/// serializes. The order of the stored keys must match the order of the keys.
+///
+/// [`TreeMap`]: crate::collections::TreeMap
+pub trait KeyEncoding<K> {
+    /// Type the keys are stored as.
+    type Stored: Ord + Clone + BorshSerialize + BorshDeserialize;
+
+    /// Converts a key to its stored form.
+    fn to_stored(key: &K) -> Self::Stored;
+
+    /// Converts a stored key back to the key.
+    fn from_stored(stored: Self::Stored) -> K;
+}
+
+/// Stores keys with Borsh as they are. This is the default encoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BorshKeyEncoding;
+
+impl<K> KeyEncoding<K> for BorshKeyEncoding
+where
+    K: Ord + Clone + BorshSerialize + BorshDeserialize,
+{
+    type Stored = K;
+
+    fn to_stored(key: &K) -> K {
+        key.clone()
+    }
+
+    fn from_stored(stored: K) -> K {
+        stored
+    }
+}
+
+/// Stores integer keys as big-endian with a fixed width, with the sign bit of signed keys
+/// flipped, using the `Ordered*` types of this module. The raw bytes of the keys then sort in
+/// the same order as the keys.
+///
+/// Maps storing keys with Borsh can't switch to this encoding without migrating their entries,
+/// as the stored keys differ.
+///
+/// # Examples
+/// ```
+/// use near_sdk::collections::key_encoding::OrderedKeyEncoding;
+/// use near_sdk::collections::TreeMap;
+///
+/// let mut map: TreeMap<i32, String, OrderedKeyEncoding> = TreeMap::with_encoding(b"m");
+/// map.insert(&1, &"b".to_string());
+/// map.insert(&-1, &"a".to_string());
+/// assert_eq!(map.min(), Some(-1));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct OrderedKeyEncoding;
+
+macro_rules! impl_ordered_key_encoding {
+    ($($ty:ty => $stored:ident),+ $(,)?) => {
+        $(
+            impl KeyEncoding<$ty> for OrderedKeyEncoding {
+                type Stored = $stored;
+
+                fn to_stored(key: &$ty) -> $stored {
+                    $stored(*key)
+                }
+
+                fn from_stored(stored: $stored) -> $ty {
+                    stored.0
+                }
+            }
+        )+
+    };
+}
+
+impl_ordered_key_encoding!(
+    u8 => OrderedU8,
+    u16 => OrderedU16,
+    u32 => OrderedU32,
+    u64 => OrderedU64,
+    u128 => OrderedU128,
+    i8 => OrderedI8,
+    i16 => OrderedI16,
+    i32 => OrderedI32,
+    i64 => OrderedI64,
+    i128 => OrderedI128,
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_byte_order<T>(values: &[T])
+    where
+        T: BorshSerialize + BorshDeserialize + Ord + fmt::Debug + Copy,
+    {
+        for pair in values.windows(2) {
+            assert!(pair[0] < pair[1]);
+            assert!(pair[0].try_to_vec().unwrap() < pair[1].try_to_vec().unwrap());
+        }
+        for value in values {
+            let bytes = value.try_to_vec().unwrap();
+            assert_eq!(T::try_from_slice(&bytes).unwrap(), *value);
+        }
+    }
+
+    #[test]
+    fn u64_order() {
+        let values: Vec<OrderedU64> =
+            [0, 1, 255, 256, (1 << 32) - 1, 1 << 32, u64::MAX - 1, u64::MAX]
+                .iter()
+                .map(|&v| OrderedU64(v))
+                .collect();
+        assert_byte_order(&values);
+        assert_eq!(OrderedU64(1).try_to_vec().unwrap(), [0, 0, 0, 0, 0, 0, 0, 1]);
+    }
+
+    #[test]
+    fn u128_order() {
+        let values: Vec<OrderedU128> =
+            [0, 1, 1 << 32, 1 << 64, u128::MAX].iter().map(|&v| OrderedU128(v)).collect();
+        assert_byte_order(&values);
+    }
+
+    #[test]
+    fn i64_order() {
+        let values: Vec<OrderedI64> =
+            [i64::MIN, -(1 << 32), -256, -1, 0, 1, 256, 1 << 32, i64::MAX]
+                .iter()
+                .map(|&v| OrderedI64(v))
+                .collect();
+        assert_byte_order(&values);
+        assert_eq!(OrderedI64(0).try_to_vec().unwrap(), [128, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn narrow_and_wide_order() {
+        assert_byte_order(&[OrderedU8(0), OrderedU8(1), OrderedU8(u8::MAX)]);
+        assert_byte_order(&[OrderedU16(1), OrderedU16(256), OrderedU16(u16::MAX)]);
+        assert_byte_order(&[OrderedU32(255), OrderedU32(256), OrderedU32(u32::MAX)]);
+        assert_byte_order(&[OrderedI8(i8::MIN), OrderedI8(-1), OrderedI8(0), OrderedI8(i8::MAX)]);
+        assert_byte_order(&[OrderedI16(i16::MIN), OrderedI16(-256), OrderedI16(0), OrderedI16(1)]);
+        assert_byte_order(&[
+            OrderedI32(i32::MIN),
+            OrderedI32(-1),
+            OrderedI32(256),
+            OrderedI32(i32::MAX),
+        ]);
+        assert_byte_order(&[
+            OrderedI128(i128::MIN),
+            OrderedI128(-1),
+            OrderedI128(0),
+            OrderedI128(i128::MAX),
+        ]);
+        assert_eq!(OrderedI32(-1).try_to_vec().unwrap(), [127, 255, 255, 255]);
+    }
+
+    #[test]
+    fn composite_keys() {
+        let key = |epoch: u64, index: u64| (OrderedU64(epoch), OrderedU64(index));
+        let mut keys = vec![key(1, 256), key(0, 1 << 32), key(1, 1), key(0, 255)];
+        keys.sort_by_key(|k| k.try_to_vec().unwrap());
+        assert_eq!(keys, vec![key(0, 255), key(0, 1 << 32), key(1, 1), key(1, 256)]);
+    }
+
+    #[test]
+    fn json() {
+        assert_eq!(serde_json::to_string(&OrderedI64(-5)).unwrap(), "\"-5\"");
+        let v: OrderedU128 =
+            serde_json::from_str("\"340282366920938463463374607431768211455\"").unwrap();
+        assert_eq!(v.0, u128::MAX);
+        assert!(serde_json::from_str::<OrderedU64>("\"-1\"").is_err());
+    }
+
+    #[test]
+    fn short_input() {
+        assert!(OrderedU64::try_from_slice(&[0, 1]).is_err());
+    }
+}
@@ -0,0 +1,145 @@
+//! A persistent lazily loaded value which is always present. Like [`LazyOption`], the value is
+//! stored under its own key and only read from storage when requested, but it is set when the
+//! collection is created so reading it doesn't return an `Option`.
+//!
+//! `Lazy` uses the same storage layout as [`LazyOption`], so a field can be changed from one to
+//! the other by constructing the new type with the same storage key.
+//!
+//! [`LazyOption`]: crate::collections::LazyOption
+use std::marker::PhantomData;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::collections::HandleId;
+use crate::env;
+use crate::environment::register_prefix;
+use crate::errors::SdkError;
+use crate::IntoStorageKey;
+
+/// A persistent value which is loaded from storage only when it is accessed.
+///
+/// # Examples
+/// ```
+/// use near_sdk::collections::Lazy;
+///
+/// let mut metadata = Lazy::new(b"m", &"Token".to_string());
+/// assert_eq!(metadata.get(), "Token");
+/// metadata.update(|name| name.push_str(" v2"));
+/// assert_eq!(metadata.get(), "Token v2");
+/// ```
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct Lazy<T> {
+    storage_key: Vec<u8>,
+    #[borsh_skip]
+    el: PhantomData<T>,
+    #[borsh_skip]
+    handle: HandleId,
+}
+
+impl<T> Lazy<T> {
+    /// Creates another handle to this value, intended for read paths. With the
+    /// `check-handle-aliasing` feature enabled, setting the value through both handles within a
+    /// call panics.
+    pub fn duplicate_handle(&self) -> Self {
+        Self { storage_key: self.storage_key.clone(), el: PhantomData, handle: HandleId::default() }
+    }
+}
+
+impl<T> Lazy<T>
+where
+    T: BorshSerialize + BorshDeserialize,
+{
+    /// Creates a new lazy value with the given `storage_key`, writing `value` to storage.
+    pub fn new<S>(storage_key: S, value: &T) -> Self
+    where
+        S: IntoStorageKey,
+    {
+        let storage_key = storage_key.into_storage_key();
+        let _scope = register_prefix::<Self>(&storage_key);
+        let mut this = Self { storage_key, el: PhantomData, handle: HandleId::default() };
+        this.set(value);
+        this
+    }
+
+    /// Reads the value from storage.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is missing from storage.
+    pub fn get(&self) -> T {
+        let raw_value = env::storage_read(&self.storage_key)
+            .unwrap_or_else(|| SdkError::CollectionInconsistent { collection: "Lazy" }.panic());
+        T::try_from_slice(&raw_value)
+            .unwrap_or_else(|_| SdkError::Deserialization { context: "value" }.panic())
+    }
+
+    /// Writes `value` to storage, without reading the previous value.
+    pub fn set(&mut self, value: &T) {
+        let raw_value = value
+            .try_to_vec()
+            .unwrap_or_else(|_| SdkError::Serialization { context: "value" }.panic());
+        self.handle.record_write(&self.storage_key);
+        env::storage_write(&self.storage_key, &raw_value);
+    }
+
+    /// Reads the value, passes a mutable reference to it to `f` and writes it back.
+    pub fn update<F>(&mut self, f: F)
+    where
+        F: FnOnce(&mut T),
+    {
+        let mut value = self.get();
+        f(&mut value);
+        self.set(&value);
+    }
+}
+
+impl<T> std::fmt::Debug for Lazy<T>
+where
+    T: std::fmt::Debug + BorshSerialize + BorshDeserialize,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if cfg!(feature = "expensive-debug") {
+            self.get().fmt(f)
+        } else {
+            f.debug_struct("Lazy").field("storage_key", &self.storage_key).finish()
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collections::LazyOption;
+
+    #[test]
+    pub fn test_all() {
+        let mut a = Lazy::new(b"a", &1u32);
+        assert_eq!(a.get(), 1);
+        a.set(&2);
+        assert_eq!(a.get(), 2);
+        a.update(|v| *v += 1);
+        assert_eq!(a.get(), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "The collection is an inconsistent state")]
+    pub fn test_missing_value() {
+        let a = Lazy::new(b"a", &1u32);
+        env::storage_remove(b"a");
+        a.get();
+    }
+
+    #[test]
+    pub fn test_lazy_option_layout() {
+        let lazy = Lazy::new(b"a", &"value".to_string());
+        let option = LazyOption::<String>::new(b"a", None);
+        assert_eq!(option.get(), Some("value".to_string()));
+        drop(lazy);
+
+        let mut option = option;
+        option.set(&"other".to_string());
+        let lazy = Lazy::<String>::try_from_slice(&option.try_to_vec().unwrap()).unwrap();
+        assert_eq!(lazy.get(), "other");
+    }
+}
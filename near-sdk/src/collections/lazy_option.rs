@@ -7,18 +7,21 @@ use std::marker::PhantomData;
 
 use borsh::{BorshDeserialize, BorshSerialize};
 
+use crate::collections::relocate::{relocate, Relocate, RelocateProgress};
+use crate::collections::HandleId;
 use crate::env;
+use crate::environment::register_prefix;
+use crate::errors::SdkError;
 use crate::IntoStorageKey;
 
-const ERR_VALUE_SERIALIZATION: &str = "Cannot serialize value with Borsh";
-const ERR_VALUE_DESERIALIZATION: &str = "Cannot deserialize value with Borsh";
-
 /// An persistent lazy option, that stores a value in the storage.
 #[derive(BorshSerialize, BorshDeserialize)]
 pub struct LazyOption<T> {
     storage_key: Vec<u8>,
     #[borsh_skip]
     el: PhantomData<T>,
+    #[borsh_skip]
+    handle: HandleId,
 }
 
 impl<T> LazyOption<T> {
@@ -40,9 +43,22 @@ impl<T> LazyOption<T> {
     /// Removes the value from the storage.
     /// Returns true if the element was present.
     fn remove_raw(&mut self) -> bool {
+        self.handle.record_write(&self.storage_key);
         env::storage_remove(&self.storage_key)
     }
 
+    /// Returns the serialized value from storage without deserializing it, or `None` if it is not
+    /// present.
+    pub fn raw_bytes(&self) -> Option<Vec<u8>> {
+        self.get_raw()
+    }
+
+    /// Writes already serialized `bytes` as the value. The bytes are not checked to deserialize
+    /// as `T`.
+    pub fn set_raw(&mut self, bytes: Vec<u8>) {
+        self.write_raw(&bytes);
+    }
+
     /// Removes the raw value from the storage and returns it as an option.
     fn take_raw(&mut self) -> Option<Vec<u8>> {
         if self.remove_raw() {
@@ -52,17 +68,55 @@ impl<T> LazyOption<T> {
         }
     }
 
-    fn set_raw(&mut self, raw_value: &[u8]) -> bool {
+    fn write_raw(&mut self, raw_value: &[u8]) -> bool {
+        self.handle.record_write(&self.storage_key);
         env::storage_write(&self.storage_key, raw_value)
     }
 
     fn replace_raw(&mut self, raw_value: &[u8]) -> Option<Vec<u8>> {
-        if self.set_raw(raw_value) {
+        if self.write_raw(raw_value) {
             Some(env::storage_get_evicted().unwrap())
         } else {
             None
         }
     }
+
+    /// Moves the value to `new_storage_key`, keeping track of the progress under `cursor_key`.
+    /// Call this in consecutive function calls until [`RelocateProgress::Done`] is returned. The
+    /// value is copied in the first call and removed from the old key in the second.
+    pub fn relocate_to<S>(&mut self, new_storage_key: S, cursor_key: &[u8]) -> RelocateProgress
+    where
+        S: IntoStorageKey,
+    {
+        relocate(self, new_storage_key.into_storage_key(), 1, cursor_key)
+    }
+}
+
+impl<T> LazyOption<T> {
+    /// Creates another handle to the value of this option, intended for read paths. With the
+    /// `check-handle-aliasing` feature enabled, writing through more than one handle within a call
+    /// panics.
+    pub fn duplicate_handle(&self) -> Self {
+        Self { storage_key: self.storage_key.clone(), el: PhantomData, handle: HandleId::default() }
+    }
+}
+
+impl<T> Relocate for LazyOption<T> {
+    fn prefix(&self) -> Vec<u8> {
+        self.storage_key.clone()
+    }
+
+    fn set_prefix(&mut self, prefix: &[u8]) {
+        self.storage_key = prefix.to_vec();
+    }
+
+    fn entries_len(&self) -> u64 {
+        1
+    }
+
+    fn entry_keys(&self, _index: u64, prefix: &[u8]) -> Vec<Vec<u8>> {
+        vec![prefix.to_vec()]
+    }
 }
 
 impl<T> LazyOption<T>
@@ -74,7 +128,9 @@ where
     where
         S: IntoStorageKey,
     {
-        let mut this = Self { storage_key: storage_key.into_storage_key(), el: PhantomData };
+        let storage_key = storage_key.into_storage_key();
+        let _scope = register_prefix::<Self>(&storage_key);
+        let mut this = Self { storage_key, el: PhantomData, handle: HandleId::default() };
         if let Some(value) = value {
             this.set(value);
         }
@@ -84,19 +140,19 @@ where
     fn serialize_value(value: &T) -> Vec<u8> {
         match value.try_to_vec() {
             Ok(x) => x,
-            Err(_) => env::panic_str(ERR_VALUE_SERIALIZATION),
+            Err(_) => SdkError::Serialization { context: "value" }.panic(),
         }
     }
 
     fn deserialize_value(raw_value: &[u8]) -> T {
         match T::try_from_slice(raw_value) {
             Ok(x) => x,
-            Err(_) => env::panic_str(ERR_VALUE_DESERIALIZATION),
+            Err(_) => SdkError::Deserialization { context: "value" }.panic(),
         }
     }
 
-    /// Removes the value from storage without reading it.
-    /// Returns whether the value was present.
+    /// Removes the value from storage without reading it, so this can remove values stored in a
+    /// format which no longer deserializes as `T`. Returns whether the value was present.
     pub fn remove(&mut self) -> bool {
         self.remove_raw()
     }
@@ -111,16 +167,115 @@ where
         self.get_raw().map(|v| Self::deserialize_value(&v))
     }
 
+    /// Gets the value from storage like [`LazyOption::get`], but if it cannot be deserialized as
+    /// `T` the raw bytes are passed to `recover` instead of panicking. This allows reading values
+    /// stored with a previous layout of `T`, for example in a migration. The recovered value is
+    /// not written back to storage.
+    ///
+    /// # Examples
+    /// ```
+    /// use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+    /// use near_sdk::collections::LazyOption;
+    ///
+    /// #[derive(BorshSerialize, BorshDeserialize)]
+    /// struct OldMetadata {
+    ///     name: String,
+    /// }
+    ///
+    /// #[derive(BorshSerialize, BorshDeserialize)]
+    /// struct Metadata {
+    ///     name: String,
+    ///     symbol: String,
+    /// }
+    ///
+    /// let old = LazyOption::new(b"m", Some(&OldMetadata { name: "Token".to_string() }));
+    /// let mut metadata = LazyOption::<Metadata>::new(b"m", None);
+    /// let migrated = metadata
+    ///     .get_or_recover(|raw| {
+    ///         let old = OldMetadata::try_from_slice(&raw).unwrap();
+    ///         Metadata { name: old.name, symbol: "TKN".to_string() }
+    ///     })
+    ///     .unwrap();
+    /// metadata.set(&migrated);
+    /// assert_eq!(metadata.get().unwrap().symbol, "TKN");
+    /// ```
+    pub fn get_or_recover<F>(&self, recover: F) -> Option<T>
+    where
+        F: FnOnce(Vec<u8>) -> T,
+    {
+        self.get_raw().map(|raw_value| match T::try_from_slice(&raw_value) {
+            Ok(value) => value,
+            Err(_) => recover(raw_value),
+        })
+    }
+
     /// Sets the value into the storage without reading the previous value and returns whether the
-    /// previous value was present.
+    /// previous value was present. This can overwrite values stored in a format which no longer
+    /// deserializes as `T`.
     pub fn set(&mut self, value: &T) -> bool {
-        self.set_raw(&Self::serialize_value(value))
+        self.write_raw(&Self::serialize_value(value))
+    }
+
+    /// Sets the value into the storage. The previous value is neither read nor deserialized, so
+    /// this can overwrite values stored in a format which no longer deserializes as `T`.
+    ///
+    /// This behaves like [`LazyOption::set`] without returning whether a value was present.
+    pub fn set_no_return(&mut self, value: &T) {
+        self.write_raw(&Self::serialize_value(value));
+    }
+
+    /// Removes the value from storage without reading or deserializing it. Returns whether the
+    /// value was present.
+    ///
+    /// This behaves like [`LazyOption::remove`].
+    pub fn remove_no_return(&mut self) -> bool {
+        self.remove_raw()
     }
 
     /// Replaces the value in the storage and returns the previous value as an option.
     pub fn replace(&mut self, value: &T) -> Option<T> {
         self.replace_raw(&Self::serialize_value(value)).map(|v| Self::deserialize_value(&v))
     }
+
+    /// Returns the value from storage, first setting it to the result of `f` if it is not present.
+    pub fn get_or_insert_with<F>(&mut self, f: F) -> T
+    where
+        F: FnOnce() -> T,
+    {
+        match self.get() {
+            Some(value) => value,
+            None => {
+                let value = f();
+                self.set(&value);
+                value
+            }
+        }
+    }
+
+    /// Reads the value from storage once and maps it with `f`, returning `None` if it is not
+    /// present.
+    pub fn map<R, F>(&self, f: F) -> Option<R>
+    where
+        F: FnOnce(&T) -> R,
+    {
+        self.get().map(|value| f(&value))
+    }
+
+    /// Reads the value from storage, passes a mutable reference to it to `f` and writes it back.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is not present.
+    pub fn update<F>(&mut self, f: F)
+    where
+        F: FnOnce(&mut T),
+    {
+        let mut value = self
+            .get()
+            .unwrap_or_else(|| env::panic_str("Cannot update a LazyOption which has no value"));
+        f(&mut value);
+        self.set(&value);
+    }
 }
 
 impl<T> std::fmt::Debug for LazyOption<T>
@@ -187,6 +342,87 @@ mod tests {
         assert_eq!(a.get(), Some(42));
     }
 
+    #[test]
+    pub fn test_no_return_invalid_previous() {
+        // Not a valid Borsh serialized `String`.
+        env::storage_write(b"a", &[255, 255, 255, 255]);
+        let mut a = LazyOption::<String>::new(b"a", None);
+        a.set_no_return(&"new".to_string());
+        assert_eq!(a.get(), Some("new".to_string()));
+
+        env::storage_write(b"a", &[255, 255, 255, 255]);
+        assert!(a.remove_no_return());
+        assert!(a.is_none());
+        assert!(!a.remove_no_return());
+    }
+
+    #[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+    struct OldLayout {
+        name: String,
+    }
+
+    #[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+    struct NewLayout {
+        name: String,
+        decimals: u8,
+    }
+
+    #[test]
+    pub fn test_get_or_recover() {
+        let old = OldLayout { name: "token".to_string() };
+        let mut a = LazyOption::<NewLayout>::new(b"a", None);
+        a.set_raw(old.try_to_vec().unwrap());
+        assert_eq!(a.raw_bytes(), Some(old.try_to_vec().unwrap()));
+
+        let recovered = a
+            .get_or_recover(|raw| {
+                let old = OldLayout::try_from_slice(&raw).unwrap();
+                NewLayout { name: old.name, decimals: 18 }
+            })
+            .unwrap();
+        a.set(&recovered);
+        assert_eq!(a.get(), Some(NewLayout { name: "token".to_string(), decimals: 18 }));
+
+        // Values in the current layout are returned without calling the recovery closure.
+        assert_eq!(a.get_or_recover(|_| panic!("not called")).unwrap().decimals, 18);
+        assert!(a.remove());
+        assert!(a.get_or_recover(|_| panic!("not called")).is_none());
+        assert!(a.raw_bytes().is_none());
+    }
+
+    #[test]
+    pub fn test_get_or_insert_with() {
+        let mut a = LazyOption::new(b"a", None);
+        assert_eq!(a.get_or_insert_with(|| 1u32), 1);
+        assert_eq!(a.get(), Some(1));
+
+        let mut b = LazyOption::new(b"b", Some(&2u32));
+        assert_eq!(b.get_or_insert_with(|| panic!("value is present")), 2);
+    }
+
+    #[test]
+    pub fn test_map() {
+        let a = LazyOption::new(b"a", Some(&"near".to_string()));
+        assert_eq!(a.map(|s| s.len()), Some(4));
+
+        let b = LazyOption::<String>::new(b"b", None);
+        assert_eq!(b.map(|s| s.len()), None);
+    }
+
+    #[test]
+    pub fn test_update() {
+        let mut a = LazyOption::new(b"a", Some(&vec![1u8]));
+        a.update(|v| v.push(2));
+        assert_eq!(a.get(), Some(vec![1, 2]));
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot update a LazyOption which has no value")]
+    pub fn test_update_none() {
+        let mut a = LazyOption::<u32>::new(b"a", None);
+        a.update(|v| *v += 1);
+    }
+
     #[test]
     pub fn test_debug() {
         let mut lazy_option = LazyOption::new(b"m", None);
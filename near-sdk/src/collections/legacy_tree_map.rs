@@ -7,6 +7,8 @@ use std::ops::Bound;
 
 use crate::collections::UnorderedMap;
 use crate::collections::{append, Vector};
+use crate::environment::register_prefix;
+use crate::errors::SdkError;
 use crate::IntoStorageKey;
 
 /// TreeMap based on AVL-tree
@@ -54,6 +56,7 @@ where
         S: IntoStorageKey,
     {
         let prefix = prefix.into_storage_key();
+        let _scope = register_prefix::<Self>(&prefix);
         Self {
             root: 0,
             val: UnorderedMap::new(append(&prefix, b'v')),
@@ -61,6 +64,39 @@ where
         }
     }
 
+    /// Recreates the handle of a map stored under `prefix`, whose root and length were lost.
+    /// Every node is read once to find the root.
+    pub(crate) fn recover<S>(prefix: S) -> Self
+    where
+        S: IntoStorageKey,
+    {
+        let mut map = Self::new(prefix);
+        map.val.recover_len();
+        map.tree.recover_len();
+        if map.val.len() != map.tree.len() {
+            SdkError::CollectionInconsistent { collection: "LegacyTreeMap" }.panic()
+        }
+        let mut is_child = vec![false; map.len() as usize];
+        for node in map.tree.iter() {
+            for child in node.lft.iter().chain(node.rgt.iter()) {
+                match is_child.get_mut(*child as usize) {
+                    Some(is_child) => *is_child = true,
+                    None => {
+                        SdkError::CollectionInconsistent { collection: "LegacyTreeMap" }.panic()
+                    }
+                }
+            }
+        }
+        let mut roots = (0..map.len()).filter(|&id| !is_child[id as usize]);
+        if let Some(root) = roots.next() {
+            if roots.next().is_some() {
+                SdkError::CollectionInconsistent { collection: "LegacyTreeMap" }.panic()
+            }
+            map.root = root;
+        }
+        map
+    }
+
     #[allow(clippy::len_without_is_empty)]
     pub fn len(&self) -> u64 {
         self.tree.len()
@@ -5,19 +5,25 @@ use std::marker::PhantomData;
 
 use borsh::{BorshDeserialize, BorshSerialize};
 
-use crate::collections::append_slice;
+use crate::collections::{
+    append_slice, record_storage_cost, BorshCodec, CoalesceTarget, Coalesced, HandleId, JsonCodec,
+    ValueCodec,
+};
+use crate::environment::register_prefix;
+use crate::errors::{CollectionError, SdkError};
 use crate::{env, IntoStorageKey};
 
-const ERR_KEY_SERIALIZATION: &str = "Cannot serialize key with Borsh";
-const ERR_VALUE_DESERIALIZATION: &str = "Cannot deserialize value with Borsh";
-const ERR_VALUE_SERIALIZATION: &str = "Cannot serialize value with Borsh";
-
 /// An non-iterable implementation of a map that stores its content directly on the trie.
+///
+/// Values are stored with Borsh, or as JSON for maps created with [`new_json`](Self::new_json),
+/// see [`ValueCodec`].
 #[derive(BorshSerialize, BorshDeserialize)]
-pub struct LookupMap<K, V> {
+pub struct LookupMap<K, V, C = BorshCodec> {
     key_prefix: Vec<u8>,
     #[borsh_skip]
-    el: PhantomData<(K, V)>,
+    el: PhantomData<(K, V, C)>,
+    #[borsh_skip]
+    handle: HandleId,
 }
 
 impl<K, V> LookupMap<K, V> {
@@ -26,7 +32,69 @@ impl<K, V> LookupMap<K, V> {
     where
         S: IntoStorageKey,
     {
-        Self { key_prefix: key_prefix.into_storage_key(), el: PhantomData }
+        let key_prefix = key_prefix.into_storage_key();
+        let _scope = register_prefix::<Self>(&key_prefix);
+        Self::with_prefix(key_prefix)
+    }
+}
+
+impl<K, V> LookupMap<K, V, JsonCodec> {
+    /// Create a new map storing values as JSON. Use `key_prefix` as a unique prefix for keys.
+    /// Keys are still serialized with Borsh.
+    ///
+    /// # Examples
+    /// ```
+    /// use near_sdk::collections::LookupMap;
+    ///
+    /// let mut map = LookupMap::new_json(b"m");
+    /// map.insert(&1u64, &vec!["Zoë".to_string()]);
+    /// assert_eq!(map.get(&1), Some(vec!["Zoë".to_string()]));
+    /// ```
+    pub fn new_json<S>(key_prefix: S) -> Self
+    where
+        S: IntoStorageKey,
+    {
+        let key_prefix = key_prefix.into_storage_key();
+        let _scope = register_prefix::<Self>(&key_prefix);
+        Self::with_prefix(key_prefix)
+    }
+}
+
+impl<K, V, C> LookupMap<K, V, C> {
+    /// Create a new map storing values with the codec `C`, for example a custom [`ValueCodec`].
+    /// Use `key_prefix` as a unique prefix for keys. Keys are still serialized with Borsh.
+    ///
+    /// # Examples
+    /// ```
+    /// use near_sdk::collections::{JsonCodec, LookupMap};
+    ///
+    /// let mut map: LookupMap<u64, Vec<String>, JsonCodec> = LookupMap::with_codec(b"m");
+    /// map.insert(&1, &vec!["Zoë".to_string()]);
+    /// assert_eq!(map.get(&1), Some(vec!["Zoë".to_string()]));
+    /// ```
+    pub fn with_codec<S>(key_prefix: S) -> Self
+    where
+        S: IntoStorageKey,
+    {
+        let key_prefix = key_prefix.into_storage_key();
+        let _scope = register_prefix::<Self>(&key_prefix);
+        Self::with_prefix(key_prefix)
+    }
+
+    fn with_prefix(key_prefix: Vec<u8>) -> Self {
+        Self { key_prefix, el: PhantomData, handle: HandleId::default() }
+    }
+
+    /// Creates another handle to the storage of this map, intended for read paths. With the
+    /// `check-handle-aliasing` feature enabled, writing through more than one handle within a call
+    /// panics.
+    pub fn duplicate_handle(&self) -> Self {
+        Self::with_prefix(self.key_prefix.clone())
+    }
+
+    /// Returns the prefix of the storage keys of this map.
+    pub fn key_prefix(&self) -> &[u8] {
+        &self.key_prefix
     }
 
     fn raw_key_to_storage_key(&self, raw_key: &[u8]) -> Vec<u8> {
@@ -50,6 +118,7 @@ impl<K, V> LookupMap<K, V> {
     /// a serialized value. Note, the keys that have the same hash value are undistinguished by
     /// the implementation.
     pub fn insert_raw(&mut self, key_raw: &[u8], value_raw: &[u8]) -> Option<Vec<u8>> {
+        self.handle.record_write(&self.key_prefix);
         let storage_key = self.raw_key_to_storage_key(key_raw);
         if env::storage_write(&storage_key, value_raw) {
             Some(env::storage_get_evicted().unwrap())
@@ -61,6 +130,7 @@ impl<K, V> LookupMap<K, V> {
     /// Removes a serialized key from the map, returning the serialized value at the key if the key
     /// was previously in the map.
     pub fn remove_raw(&mut self, key_raw: &[u8]) -> Option<Vec<u8>> {
+        self.handle.record_write(&self.key_prefix);
         let storage_key = self.raw_key_to_storage_key(key_raw);
         if env::storage_remove(&storage_key) {
             Some(env::storage_get_evicted().unwrap())
@@ -70,47 +140,79 @@ impl<K, V> LookupMap<K, V> {
     }
 }
 
-impl<K, V> LookupMap<K, V>
+impl<K, V, C> LookupMap<K, V, C>
 where
     K: BorshSerialize,
-    V: BorshSerialize + BorshDeserialize,
+    C: ValueCodec<V>,
 {
-    fn serialize_key(key: &K) -> Vec<u8> {
-        match key.try_to_vec() {
-            Ok(x) => x,
-            Err(_) => env::panic_str(ERR_KEY_SERIALIZATION),
-        }
+    fn try_serialize_key(&self, key: &K) -> Result<Vec<u8>, CollectionError> {
+        key.try_to_vec().map_err(|_| {
+            CollectionError::new(SdkError::Serialization { context: "key" }, &self.key_prefix, &[])
+        })
     }
 
-    fn deserialize_value(raw_value: &[u8]) -> V {
-        match V::try_from_slice(raw_value) {
-            Ok(x) => x,
-            Err(_) => env::panic_str(ERR_VALUE_DESERIALIZATION),
-        }
+    fn serialize_key(&self, key: &K) -> Vec<u8> {
+        self.try_serialize_key(key).unwrap_or_else(|err| err.panic())
     }
 
-    fn serialize_value(value: &V) -> Vec<u8> {
-        match value.try_to_vec() {
-            Ok(x) => x,
-            Err(_) => env::panic_str(ERR_VALUE_SERIALIZATION),
-        }
+    fn try_deserialize_value(
+        &self,
+        raw_key: &[u8],
+        raw_value: &[u8],
+    ) -> Result<V, CollectionError> {
+        C::try_decode(raw_value).map_err(|err| CollectionError::new(err, &self.key_prefix, raw_key))
+    }
+
+    fn try_serialize_value(&self, raw_key: &[u8], value: &V) -> Result<Vec<u8>, CollectionError> {
+        C::try_encode(value).map_err(|err| CollectionError::new(err, &self.key_prefix, raw_key))
+    }
+
+    /// Returns the number of bytes of storage inserting `key` with `value` adds when `key` isn't
+    /// in the map yet: the serialized key and value, the prefix of the map and the overhead of a
+    /// storage record. Replacing the value of a key only changes the storage by the difference in
+    /// size of the serialized values.
+    ///
+    /// Contracts charging their users for storage can require the cost before inserting, instead
+    /// of measuring [`env::storage_usage`] before and after.
+    pub fn storage_cost_of(&self, key: &K, value: &V) -> u64 {
+        let raw_key = self.serialize_key(key);
+        let raw_value = self.try_serialize_value(&raw_key, value).unwrap_or_else(|err| err.panic());
+        record_storage_cost(self.key_prefix.len() + raw_key.len(), raw_value.len())
     }
 
     /// Returns true if the map contains a given key.
     pub fn contains_key(&self, key: &K) -> bool {
-        self.contains_key_raw(&Self::serialize_key(key))
+        self.contains_key_raw(&self.serialize_key(key))
     }
 
     /// Returns the value corresponding to the key.
     pub fn get(&self, key: &K) -> Option<V> {
-        self.get_raw(&Self::serialize_key(key)).map(|value_raw| Self::deserialize_value(&value_raw))
+        self.try_get(key).unwrap_or_else(|err| err.panic())
+    }
+
+    /// Returns the value corresponding to the key, or an error identifying the key if it can't be
+    /// serialized or the value can't be deserialized.
+    pub fn try_get(&self, key: &K) -> Result<Option<V>, CollectionError> {
+        let raw_key = self.try_serialize_key(key)?;
+        self.get_raw(&raw_key)
+            .map(|raw_value| self.try_deserialize_value(&raw_key, &raw_value))
+            .transpose()
     }
 
     /// Removes a key from the map, returning the value at the key if the key was previously in the
     /// map.
     pub fn remove(&mut self, key: &K) -> Option<V> {
-        self.remove_raw(&Self::serialize_key(key))
-            .map(|value_raw| Self::deserialize_value(&value_raw))
+        self.try_remove(key).unwrap_or_else(|err| err.panic())
+    }
+
+    /// Removes a key from the map, returning the value at the key if the key was previously in the
+    /// map. The entry is removed even if its value can't be deserialized, so this can be used to
+    /// discard corrupted entries.
+    pub fn try_remove(&mut self, key: &K) -> Result<Option<V>, CollectionError> {
+        let raw_key = self.try_serialize_key(key)?;
+        self.remove_raw(&raw_key)
+            .map(|raw_value| self.try_deserialize_value(&raw_key, &raw_value))
+            .transpose()
     }
 
     /// Inserts a key-value pair into the map.
@@ -118,8 +220,18 @@ where
     /// a value. Note, the keys that have the same hash value are undistinguished by
     /// the implementation.
     pub fn insert(&mut self, key: &K, value: &V) -> Option<V> {
-        self.insert_raw(&Self::serialize_key(key), &Self::serialize_value(value))
-            .map(|value_raw| Self::deserialize_value(&value_raw))
+        self.try_insert(key, value).unwrap_or_else(|err| err.panic())
+    }
+
+    /// Inserts a key-value pair into the map, returning the previous value. Nothing is written if
+    /// the key or the value can't be serialized. The value is written even if the previous value
+    /// can't be deserialized.
+    pub fn try_insert(&mut self, key: &K, value: &V) -> Result<Option<V>, CollectionError> {
+        let raw_key = self.try_serialize_key(key)?;
+        let raw_value = self.try_serialize_value(&raw_key, value)?;
+        self.insert_raw(&raw_key, &raw_value)
+            .map(|raw_old_value| self.try_deserialize_value(&raw_key, &raw_old_value))
+            .transpose()
     }
 
     pub fn extend<IT: IntoIterator<Item = (K, V)>>(&mut self, iter: IT) {
@@ -127,12 +239,45 @@ where
             self.insert(&el_key, &el_value);
         }
     }
+
+    /// Runs `f` with a [`Coalesced`] view of the map, which keeps the writes of `f` in memory and
+    /// writes each modified key once when `f` returns.
+    pub fn coalesced<'a, R, F>(&'a mut self, f: F) -> R
+    where
+        V: Clone,
+        F: FnOnce(&mut Coalesced<'a, Self>) -> R,
+    {
+        f(&mut Coalesced::new(self))
+    }
 }
 
-impl<K, V> std::fmt::Debug for LookupMap<K, V>
+impl<K, V, C> CoalesceTarget for LookupMap<K, V, C>
+where
+    K: BorshSerialize,
+    C: ValueCodec<V>,
+{
+    type Key = K;
+    type Value = V;
+    type Codec = C;
+
+    fn read(&self, key: &K) -> Option<V> {
+        self.get(key)
+    }
+
+    fn write_raw(&mut self, key_raw: &[u8], value_raw: &[u8]) {
+        self.insert_raw(key_raw, value_raw);
+    }
+
+    fn remove_raw(&mut self, key_raw: &[u8]) {
+        LookupMap::remove_raw(self, key_raw);
+    }
+}
+
+impl<K, V, C> std::fmt::Debug for LookupMap<K, V, C>
 where
     K: std::fmt::Debug + BorshSerialize,
-    V: std::fmt::Debug + BorshSerialize + BorshDeserialize,
+    V: std::fmt::Debug,
+    C: ValueCodec<V>,
 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("LookupMap").field("key_prefix", &self.key_prefix).finish()
@@ -143,6 +288,8 @@ where
 #[cfg(test)]
 mod tests {
     use crate::collections::LookupMap;
+    use crate::env;
+    use crate::errors::CollectionErrorKind;
     use rand::seq::SliceRandom;
     use rand::{Rng, SeedableRng};
     use std::collections::HashMap;
@@ -304,4 +451,49 @@ mod tests {
             format!("LookupMap {{ key_prefix: {:?} }}", map.key_prefix)
         );
     }
+
+    #[test]
+    fn try_accessors_report_corrupted_value() {
+        let mut map = LookupMap::new(b"m");
+        map.insert(&1u64, &7u64);
+        map.insert(&2u64, &8u64);
+        env::storage_write(&[b"m".as_ref(), &1u64.to_le_bytes()].concat(), &[0xff]);
+
+        let err = map.try_get(&1).unwrap_err();
+        assert_eq!(err.kind(), CollectionErrorKind::DeserializationFailed);
+        assert_eq!(err.prefix(), b"m");
+        assert_eq!(err.key(), 1u64.to_le_bytes());
+        assert_eq!(map.try_get(&2), Ok(Some(8)));
+
+        assert_eq!(map.try_insert(&1, &9).unwrap_err(), err);
+        assert_eq!(map.try_get(&1), Ok(Some(9)));
+        env::storage_write(&[b"m".as_ref(), &1u64.to_le_bytes()].concat(), &[0xff]);
+        // The corrupted entry is removed anyway.
+        assert_eq!(map.try_remove(&1).unwrap_err(), err);
+        assert_eq!(map.try_get(&1), Ok(None));
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Cannot deserialize value with Borsh (collection prefix [109], key [1, 0, 0, 0, 0, 0, 0, 0])"
+    )]
+    fn get_panics_with_prefix_and_key() {
+        let mut map = LookupMap::new(b"m");
+        map.insert(&1u64, &7u64);
+        env::storage_write(&[b"m".as_ref(), &1u64.to_le_bytes()].concat(), &[0xff]);
+        map.get(&1);
+    }
+
+    #[test]
+    fn storage_cost_of() {
+        let mut map = LookupMap::new(b"m");
+        for len in [0, 1, 10, 100, 1000].iter() {
+            let key = "k".repeat(*len);
+            let value = vec![7u8; len * 2];
+            let cost = map.storage_cost_of(&key, &value);
+            let usage = env::storage_usage();
+            map.insert(&key, &value);
+            assert_eq!(env::storage_usage() - usage, cost);
+        }
+    }
 }
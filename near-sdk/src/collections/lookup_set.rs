@@ -5,17 +5,19 @@ use std::marker::PhantomData;
 
 use borsh::{BorshDeserialize, BorshSerialize};
 
-use crate::collections::append_slice;
+use crate::collections::{append_slice, HandleId};
+use crate::environment::register_prefix;
+use crate::errors::SdkError;
 use crate::{env, IntoStorageKey};
 
-const ERR_ELEMENT_SERIALIZATION: &str = "Cannot serialize element with Borsh";
-
 /// An non-iterable implementation of a set that stores its content directly on the trie.
 #[derive(BorshSerialize, BorshDeserialize)]
 pub struct LookupSet<T> {
     element_prefix: Vec<u8>,
     #[borsh_skip]
     el: PhantomData<T>,
+    #[borsh_skip]
+    handle: HandleId,
 }
 
 impl<T> LookupSet<T> {
@@ -24,15 +26,29 @@ impl<T> LookupSet<T> {
     where
         S: IntoStorageKey,
     {
-        Self { element_prefix: element_prefix.into_storage_key(), el: PhantomData }
+        let element_prefix = element_prefix.into_storage_key();
+        let _scope = register_prefix::<Self>(&element_prefix);
+        Self { element_prefix, el: PhantomData, handle: HandleId::default() }
+    }
+
+    /// Returns a new handle to the elements of this set. Use it only to read; with the
+    /// `check-handle-aliasing` feature enabled, inserting or removing through two handles within a
+    /// call panics.
+    pub fn duplicate_handle(&self) -> Self {
+        Self {
+            element_prefix: self.element_prefix.clone(),
+            el: PhantomData,
+            handle: HandleId::default(),
+        }
     }
 
     fn raw_element_to_storage_key(&self, element_raw: &[u8]) -> Vec<u8> {
         append_slice(&self.element_prefix, element_raw)
     }
 
-    /// Returns `true` if the serialized key is present in the map.
-    fn contains_raw(&self, element_raw: &[u8]) -> bool {
+    /// Returns `true` if the serialized element is present in the set, for elements which are
+    /// already serialized, such as those taken from the call arguments.
+    pub fn contains_raw(&self, element_raw: &[u8]) -> bool {
         let storage_key = self.raw_element_to_storage_key(element_raw);
         env::storage_has_key(&storage_key)
     }
@@ -41,6 +57,7 @@ impl<T> LookupSet<T> {
     /// If the set did not have this value present, `true` is returned.
     /// If the set did have this value present, `false` is returned.
     pub fn insert_raw(&mut self, element_raw: &[u8]) -> bool {
+        self.handle.record_write(&self.element_prefix);
         let storage_key = self.raw_element_to_storage_key(element_raw);
         !env::storage_write(&storage_key, b"")
     }
@@ -48,6 +65,7 @@ impl<T> LookupSet<T> {
     /// Removes a serialized element from the set.
     /// Returns true if the element was present in the set.
     pub fn remove_raw(&mut self, element_raw: &[u8]) -> bool {
+        self.handle.record_write(&self.element_prefix);
         let storage_key = self.raw_element_to_storage_key(element_raw);
         env::storage_remove(&storage_key)
     }
@@ -60,7 +78,7 @@ where
     fn serialize_element(element: &T) -> Vec<u8> {
         match element.try_to_vec() {
             Ok(x) => x,
-            Err(_) => env::panic_str(ERR_ELEMENT_SERIALIZATION),
+            Err(_) => SdkError::Serialization { context: "set element" }.panic(),
         }
     }
 
@@ -69,6 +87,23 @@ where
         self.contains_raw(&Self::serialize_element(element))
     }
 
+    /// Returns whether the set contains each of `elements`, in the same order. The storage key of
+    /// each element is built in a single buffer starting with the prefix, rather than allocating
+    /// a key per element.
+    pub fn contains_many(&self, elements: &[T]) -> Vec<bool> {
+        let mut storage_key = self.element_prefix.clone();
+        elements
+            .iter()
+            .map(|element| {
+                storage_key.truncate(self.element_prefix.len());
+                if element.serialize(&mut storage_key).is_err() {
+                    SdkError::Serialization { context: "set element" }.panic()
+                }
+                env::storage_has_key(&storage_key)
+            })
+            .collect()
+    }
+
     /// Removes a value from the set. Returns whether the value was present in the set.
     pub fn remove(&mut self, element: &T) -> bool {
         self.remove_raw(&Self::serialize_element(element))
@@ -101,6 +136,8 @@ where
 #[cfg(test)]
 mod tests {
     use crate::collections::LookupSet;
+    use crate::mock::with_mocked_blockchain;
+    use borsh::{BorshDeserialize, BorshSerialize};
     use rand::seq::SliceRandom;
     use rand::{Rng, SeedableRng};
     use std::collections::HashSet;
@@ -214,6 +251,40 @@ mod tests {
         }
     }
 
+    thread_local! {
+        static DECODED: std::cell::Cell<usize> = std::cell::Cell::new(0);
+    }
+
+    /// An element which counts how many times it is deserialized.
+    #[derive(BorshSerialize)]
+    struct Counted(u64);
+
+    impl BorshDeserialize for Counted {
+        fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
+            DECODED.with(|decoded| decoded.set(decoded.get() + 1));
+            u64::deserialize(buf).map(Counted)
+        }
+    }
+
+    #[test]
+    fn test_contains_many() {
+        let mut set = LookupSet::new(b"s");
+        set.extend([1, 3, 4].iter().map(|&i| Counted(i)));
+        let has_key_count = || with_mocked_blockchain(|b| b.storage_has_key_count());
+
+        let before = has_key_count();
+        let elements: Vec<_> = [4, 0, 1, 2, 3, 4].iter().map(|&i| Counted(i)).collect();
+        assert_eq!(set.contains_many(&elements), [true, false, true, false, true, true]);
+        assert_eq!(has_key_count() - before, 6);
+        assert_eq!(DECODED.with(|decoded| decoded.get()), 0);
+
+        assert!(set.contains_many(&[]).is_empty());
+        assert!(set.contains_raw(&3u64.try_to_vec().unwrap()));
+        assert!(!set.contains_raw(&2u64.try_to_vec().unwrap()));
+        // Raw elements aren't checked against the element type.
+        assert!(!set.contains_raw(&[3]));
+    }
+
     #[test]
     fn test_debug() {
         let set: LookupSet<u64> = LookupSet::new(b"m");
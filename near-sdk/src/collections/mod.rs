@@ -34,6 +34,17 @@
 //!
 //! The efficiency of `LookupMap` comes at the cost, since it has fewer methods than `HashMap` and is not
 //! that seemlessly integrated with the rest of the Rust standard library.
+//!
+//! ## Panics during an operation
+//!
+//! A panic aborts the receipt and the runtime discards all of its storage changes, so on-chain a
+//! collection can't be left half-modified. Unit tests run against the mocked blockchain, which
+//! keeps the writes made before a panic caught with [`std::panic::catch_unwind`]. To keep both
+//! cases simple, `insert` and `remove` of [`UnorderedMap`] and [`TreeMap`], and
+//! [`Vector::swap_remove`], serialize the new key and value and deserialize the previous value
+//! before their first write, so a failure in any of these steps leaves the collection unchanged.
+//! The `try_*` variants write even if the previous value can't be deserialized, so that
+//! corrupted entries can be replaced or removed.
 
 mod legacy_tree_map;
 pub use legacy_tree_map::LegacyTreeMap;
@@ -47,21 +58,197 @@ pub use lookup_set::LookupSet;
 pub mod vector;
 pub use vector::Vector;
 
-mod unordered_map;
+pub mod unordered_map;
 pub use unordered_map::UnorderedMap;
 
 mod unordered_set;
 pub use unordered_set::UnorderedSet;
 
+mod unordered_multimap;
+pub use unordered_multimap::UnorderedMultimap;
+
 mod lazy_option;
 pub use lazy_option::LazyOption;
 
+mod lazy;
+pub use lazy::Lazy;
+
+pub mod deque;
+pub use deque::Deque;
+
 mod tree_map;
 pub use tree_map::TreeMap;
 
-pub const ERR_INCONSISTENT_STATE: &str = "The collection is an inconsistent state. Did previous smart contract execution terminate unexpectedly?";
-pub const ERR_ELEMENT_SERIALIZATION: &str = "Cannot serialize element with Borsh.";
-pub const ERR_ELEMENT_DESERIALIZATION: &str = "Cannot deserialize element with Borsh.";
+mod ordered_map;
+pub use ordered_map::{LegacyBackedTreeMap, OrderedMapOps};
+
+pub mod key_encoding;
+
+mod relocate;
+pub use relocate::RelocateProgress;
+
+mod handle;
+pub(crate) use handle::HandleId;
+pub use handle::NearClone;
+
+mod growable_map;
+pub use growable_map::GrowableMap;
+
+mod bit_set;
+pub use bit_set::BitSet;
+
+pub mod value_codec;
+pub use value_codec::{BorshCodec, JsonCodec, ValueCodec};
+
+mod prefix_allocator;
+pub use prefix_allocator::PrefixAllocator;
+
+mod coalesced;
+pub use coalesced::{CoalesceTarget, Coalesced};
+
+mod weighted_index;
+pub use weighted_index::WeightedIndex;
+
+pub const ERR_INCONSISTENT_STATE: &str =
+    crate::errors::SdkError::CollectionInconsistent { collection: "collections" }.as_str();
+pub const ERR_ELEMENT_SERIALIZATION: &str =
+    crate::errors::SdkError::Serialization { context: "set element" }.as_str();
+pub const ERR_ELEMENT_DESERIALIZATION: &str =
+    crate::errors::SdkError::Deserialization { context: "set element" }.as_str();
+
+/// Error returned when copying a collection into a `std` collection, if the collection has more
+/// elements than allowed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TooLarge {
+    /// Number of elements of the collection.
+    pub len: u64,
+    /// Maximum number of elements which was allowed.
+    pub max: u64,
+}
+
+impl std::fmt::Display for TooLarge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Collection has {} elements, more than the maximum of {}", self.len, self.max)
+    }
+}
+
+impl std::error::Error for TooLarge {}
+
+/// A page of the elements of a collection, which a view method can return directly.
+///
+/// Serialized as `{"items":[...],"total":25,"next_index":10}`. Pass `next_index` as the
+/// `from_index` of the next page. It's `None` on the last page.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ElementsPage<T> {
+    /// Elements of the page.
+    pub items: Vec<T>,
+    /// Number of elements of the collection.
+    pub total: u64,
+    /// Index of the first element of the next page, or `None` if this page reaches the end.
+    pub next_index: Option<u64>,
+}
+
+impl<T> ElementsPage<T> {
+    /// Creates the page of `items` starting at `from_index` in a collection of `total` elements.
+    pub(crate) fn new(items: Vec<T>, from_index: u64, total: u64) -> Self {
+        let end = from_index.saturating_add(items.len() as u64);
+        Self { items, total, next_index: if end < total { Some(end) } else { None } }
+    }
+}
+
+/// The result of counting the elements of a collection matching a predicate over a bounded number
+/// of elements, which a view method can return directly.
+///
+/// Pass `from_index + scanned` as the `from_index` of the next call to resume counting, until
+/// `complete` is set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct CountResult {
+    /// Number of scanned elements which match the predicate.
+    pub counted: u64,
+    /// Number of elements scanned by this call.
+    pub scanned: u64,
+    /// Whether the scan reached the end of the collection.
+    pub complete: bool,
+}
+
+/// Suffixes of the keys in the mocked storage which start with `prefix` and are `suffix_len` bytes
+/// longer than it.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn mocked_storage_suffixes(prefix: &[u8], suffix_len: Option<usize>) -> Vec<Vec<u8>> {
+    crate::mock::with_mocked_blockchain(|b| {
+        b.storage_snapshot()
+            .into_iter()
+            .map(|(key, _)| key)
+            .filter(|key| key.starts_with(prefix))
+            .map(|key| key[prefix.len()..].to_vec())
+            .filter(|suffix| suffix_len.map_or(true, |len| suffix.len() == len))
+            .collect()
+    })
+}
+
+/// A value whose serialization fails unless `serializable` is set, and whose deserialization always
+/// fails, to check that collections run the steps which can fail before writing to storage.
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+pub(crate) struct Fragile {
+    pub serializable: bool,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+impl borsh::BorshSerialize for Fragile {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        if self.serializable {
+            writer.write_all(&[0])
+        } else {
+            Err(std::io::Error::new(std::io::ErrorKind::Other, "unserializable"))
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+impl borsh::BorshDeserialize for Fragile {
+    fn deserialize(_buf: &mut &[u8]) -> std::io::Result<Self> {
+        Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "undecodable"))
+    }
+}
+
+/// Asserts that `f` panics without changing the mocked storage.
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+pub(crate) fn assert_panics_without_writes<R>(f: impl FnOnce() -> R) {
+    let state =
+        || crate::mock::with_mocked_blockchain(|b| (b.storage_write_count(), b.storage_snapshot()));
+    let before = state();
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(f));
+    assert!(result.is_err(), "expected a panic");
+    assert!(state() == before, "storage was changed before the panic");
+}
+
+/// Returns the number of storage reads, writes and `storage_has_key` calls made by `f`.
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+pub(crate) fn storage_ops(f: impl FnOnce()) -> (u64, u64, u64) {
+    let count = || {
+        crate::mock::with_mocked_blockchain(|b| {
+            (b.storage_read_count(), b.storage_write_count(), b.storage_has_key_count())
+        })
+    };
+    let before = count();
+    f();
+    let after = count();
+    (after.0 - before.0, after.1 - before.1, after.2 - before.2)
+}
+
+/// Number of bytes of storage charged for each record, on top of the bytes of its key and value.
+pub(crate) const STORAGE_BYTES_PER_RECORD: u64 = 40;
+
+/// Returns the bytes of storage used by a new record with a key of `key_len` bytes and a value of
+/// `value_len` bytes.
+pub(crate) fn record_storage_cost(key_len: usize, value_len: usize) -> u64 {
+    STORAGE_BYTES_PER_RECORD + key_len as u64 + value_len as u64
+}
 
 pub(crate) fn append(id: &[u8], chr: u8) -> Vec<u8> {
     append_slice(id, &[chr])
@@ -0,0 +1,377 @@
+//! A shared interface for ordered maps, so that code can be written once and used with both a
+//! [`TreeMap`] and a map still stored with the layout of [`LegacyTreeMap`].
+use borsh::{BorshDeserialize, BorshSerialize};
+use std::ops::Bound;
+
+use crate::collections::{LegacyTreeMap, TreeMap};
+use crate::IntoStorageKey;
+
+/// Operations shared by [`TreeMap`] and [`LegacyBackedTreeMap`].
+///
+/// # Examples
+/// ```
+/// use near_sdk::collections::{OrderedMapOps, TreeMap};
+///
+/// fn lowest_bid<M: OrderedMapOps<u128, String>>(bids: &M) -> Option<(u128, String)> {
+///     let price = bids.min()?;
+///     bids.get(&price).map(|bidder| (price, bidder))
+/// }
+///
+/// let mut bids = TreeMap::new(b"b");
+/// bids.insert(&20, &"alice".to_string());
+/// bids.insert(&10, &"bob".to_string());
+/// assert_eq!(lowest_bid(&bids), Some((10, "bob".to_string())));
+///
+/// let mut legacy_bids = TreeMap::wrap_legacy(b"l");
+/// legacy_bids.insert(&30, &"carol".to_string());
+/// assert_eq!(lowest_bid(&legacy_bids), Some((30, "carol".to_string())));
+/// ```
+pub trait OrderedMapOps<K, V> {
+    /// Returns the number of entries.
+    fn len(&self) -> u64;
+
+    /// Returns `true` if the map contains no entries.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns `true` if the map contains `key`.
+    fn contains_key(&self, key: &K) -> bool;
+
+    /// Returns the value for `key`.
+    fn get(&self, key: &K) -> Option<V>;
+
+    /// Inserts `value` for `key`, returning the previous value.
+    fn insert(&mut self, key: &K, value: &V) -> Option<V>;
+
+    /// Removes `key`, returning its value.
+    fn remove(&mut self, key: &K) -> Option<V>;
+
+    /// Returns the smallest key.
+    fn min(&self) -> Option<K>;
+
+    /// Returns the largest key.
+    fn max(&self) -> Option<K>;
+
+    /// Iterates over all entries in ascending order of keys.
+    fn iter<'a>(&'a self) -> Box<dyn Iterator<Item = (K, V)> + 'a>;
+
+    /// Iterates over the entries within the bounds in ascending order of keys.
+    ///
+    /// # Panics
+    ///
+    /// Panics if range start > end, or if range start == end and both bounds are excluded.
+    fn range<'a>(&'a self, r: (Bound<K>, Bound<K>)) -> Box<dyn Iterator<Item = (K, V)> + 'a>;
+}
+
+/// A map stored with the layout of [`LegacyTreeMap`], with the methods of [`TreeMap`], for
+/// contracts which keep their existing data instead of migrating it.
+///
+/// The Borsh layout is the same as the one of [`LegacyTreeMap`], so a field of a contract state
+/// can change its type from one to the other. When only the prefix of the map is known, use
+/// [`TreeMap::wrap_legacy`] to recreate it from storage.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct LegacyBackedTreeMap<K, V> {
+    inner: LegacyTreeMap<K, V>,
+}
+
+impl<K, V> LegacyBackedTreeMap<K, V>
+where
+    K: Ord + Clone + BorshSerialize + BorshDeserialize,
+    V: BorshSerialize + BorshDeserialize,
+{
+    /// Create new empty map with the legacy layout. Use `prefix` as a unique identifier.
+    pub fn new<S>(prefix: S) -> Self
+    where
+        S: IntoStorageKey,
+    {
+        Self { inner: LegacyTreeMap::new(prefix) }
+    }
+
+    /// Returns the wrapped [`LegacyTreeMap`].
+    pub fn into_legacy(self) -> LegacyTreeMap<K, V> {
+        self.inner
+    }
+
+    /// Returns the number of entries.
+    pub fn len(&self) -> u64 {
+        self.inner.len()
+    }
+
+    /// Returns `true` if the map contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.inner.len() == 0
+    }
+
+    /// Returns `true` if the map contains `key`.
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.inner.contains_key(key)
+    }
+
+    /// Returns the value for `key`.
+    pub fn get(&self, key: &K) -> Option<V> {
+        self.inner.get(key)
+    }
+
+    /// Inserts `value` for `key`, returning the previous value.
+    pub fn insert(&mut self, key: &K, value: &V) -> Option<V> {
+        self.inner.insert(key, value)
+    }
+
+    /// Removes `key`, returning its value.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.inner.remove(key)
+    }
+
+    /// Returns the smallest stored key from the tree
+    pub fn min(&self) -> Option<K> {
+        self.inner.min()
+    }
+
+    /// Returns the largest stored key from the tree
+    pub fn max(&self) -> Option<K> {
+        self.inner.max()
+    }
+
+    /// Iterate all entries in ascending order: min to max, both inclusive
+    pub fn iter(&self) -> impl Iterator<Item = (K, V)> + '_ {
+        self.inner.iter()
+    }
+
+    /// Iterate entries in ascending order according to specified bounds.
+    ///
+    /// # Panics
+    ///
+    /// Panics if range start > end.
+    /// Panics if range start == end and both bounds are Excluded.
+    pub fn range(&self, r: (Bound<K>, Bound<K>)) -> impl Iterator<Item = (K, V)> + '_ {
+        self.inner.range(r)
+    }
+
+    /// Helper function which creates a [`Vec<(K, V)>`] of all items in the map.
+    pub fn to_vec(&self) -> Vec<(K, V)> {
+        self.inner.to_vec()
+    }
+}
+
+impl<K, V> From<LegacyTreeMap<K, V>> for LegacyBackedTreeMap<K, V> {
+    fn from(inner: LegacyTreeMap<K, V>) -> Self {
+        Self { inner }
+    }
+}
+
+impl<K, V> TreeMap<K, V>
+where
+    K: Ord + Clone + BorshSerialize + BorshDeserialize,
+    V: BorshSerialize + BorshDeserialize,
+{
+    /// Recreates the handle of a [`LegacyTreeMap`] stored under `prefix`, to use it with the
+    /// methods of [`TreeMap`] without migrating its data.
+    ///
+    /// The root and the length of a legacy map are kept in its handle, not under its prefix, so
+    /// they are recovered by reading every node of the tree once. This is meant for maps whose
+    /// handle was lost. A map which is a field of the contract state can instead change the type of
+    /// the field to [`LegacyBackedTreeMap`], which reads nothing until used.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the nodes stored under `prefix` don't form a single tree.
+    pub fn wrap_legacy<S>(prefix: S) -> LegacyBackedTreeMap<K, V>
+    where
+        S: IntoStorageKey,
+    {
+        LegacyBackedTreeMap { inner: LegacyTreeMap::recover(prefix) }
+    }
+}
+
+impl<K, V> OrderedMapOps<K, V> for TreeMap<K, V>
+where
+    K: Ord + Clone + BorshSerialize + BorshDeserialize,
+    V: BorshSerialize + BorshDeserialize,
+{
+    fn len(&self) -> u64 {
+        self.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.is_empty()
+    }
+
+    fn contains_key(&self, key: &K) -> bool {
+        self.contains_key(key)
+    }
+
+    fn get(&self, key: &K) -> Option<V> {
+        self.get(key)
+    }
+
+    fn insert(&mut self, key: &K, value: &V) -> Option<V> {
+        self.insert(key, value)
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        self.remove(key)
+    }
+
+    fn min(&self) -> Option<K> {
+        self.min()
+    }
+
+    fn max(&self) -> Option<K> {
+        self.max()
+    }
+
+    fn iter<'a>(&'a self) -> Box<dyn Iterator<Item = (K, V)> + 'a> {
+        Box::new(self.iter())
+    }
+
+    fn range<'a>(&'a self, r: (Bound<K>, Bound<K>)) -> Box<dyn Iterator<Item = (K, V)> + 'a> {
+        Box::new(self.range(r))
+    }
+}
+
+impl<K, V> OrderedMapOps<K, V> for LegacyBackedTreeMap<K, V>
+where
+    K: Ord + Clone + BorshSerialize + BorshDeserialize,
+    V: BorshSerialize + BorshDeserialize,
+{
+    fn len(&self) -> u64 {
+        self.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.is_empty()
+    }
+
+    fn contains_key(&self, key: &K) -> bool {
+        self.contains_key(key)
+    }
+
+    fn get(&self, key: &K) -> Option<V> {
+        self.get(key)
+    }
+
+    fn insert(&mut self, key: &K, value: &V) -> Option<V> {
+        self.insert(key, value)
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        self.remove(key)
+    }
+
+    fn min(&self) -> Option<K> {
+        self.min()
+    }
+
+    fn max(&self) -> Option<K> {
+        self.max()
+    }
+
+    fn iter<'a>(&'a self) -> Box<dyn Iterator<Item = (K, V)> + 'a> {
+        Box::new(self.iter())
+    }
+
+    fn range<'a>(&'a self, r: (Bound<K>, Bound<K>)) -> Box<dyn Iterator<Item = (K, V)> + 'a> {
+        Box::new(self.range(r))
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    /// Runs the same operations against `map` and a `BTreeMap`, checking that they agree.
+    fn conformance<M: OrderedMapOps<u32, u32>>(map: &mut M) {
+        let mut expected = BTreeMap::new();
+        assert!(map.is_empty());
+        assert_eq!(map.min(), None);
+        assert_eq!(map.max(), None);
+
+        for i in 0..100u32 {
+            let key = (i * 37) % 101;
+            assert_eq!(map.insert(&key, &i), expected.insert(key, i));
+        }
+        for key in (0..101).step_by(3) {
+            assert_eq!(map.remove(&key), expected.remove(&key));
+        }
+        assert_eq!(map.insert(&50, &1000), expected.insert(50, 1000));
+        assert_eq!(map.remove(&500), None);
+
+        assert_eq!(map.len(), expected.len() as u64);
+        assert_eq!(map.min(), expected.keys().next().copied());
+        assert_eq!(map.max(), expected.keys().next_back().copied());
+        for key in 0..105 {
+            assert_eq!(map.contains_key(&key), expected.contains_key(&key));
+            assert_eq!(map.get(&key), expected.get(&key).copied());
+        }
+        assert_eq!(
+            map.iter().collect::<Vec<_>>(),
+            expected.iter().map(|(k, v)| (*k, *v)).collect::<Vec<_>>()
+        );
+        let bounds = [
+            (Bound::Included(10), Bound::Excluded(60)),
+            (Bound::Excluded(11), Bound::Included(13)),
+            (Bound::Unbounded, Bound::Included(40)),
+            (Bound::Included(90), Bound::Unbounded),
+            (Bound::Excluded(12), Bound::Excluded(13)),
+        ];
+        for &r in &bounds {
+            assert_eq!(
+                map.range(r).collect::<Vec<_>>(),
+                expected.range(r).map(|(k, v)| (*k, *v)).collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn tree_map_conformance() {
+        conformance(&mut TreeMap::new(b"t"));
+    }
+
+    #[test]
+    fn legacy_backed_conformance() {
+        conformance(&mut LegacyBackedTreeMap::new(b"l"));
+    }
+
+    #[test]
+    fn wrap_existing_legacy_map() {
+        let mut legacy = LegacyTreeMap::new(b"l");
+        for i in 0..50u32 {
+            legacy.insert(&((i * 7) % 50), &i);
+        }
+        for i in (0..50).step_by(4) {
+            legacy.remove(&i);
+        }
+        let entries = legacy.to_vec();
+        drop(legacy);
+
+        let mut map: LegacyBackedTreeMap<u32, u32> = TreeMap::wrap_legacy(b"l");
+        assert_eq!(map.len(), entries.len() as u64);
+        assert_eq!(map.to_vec(), entries);
+        assert_eq!(map.min(), Some(1));
+        map.insert(&100, &100);
+        map.remove(&1);
+        assert_eq!(map.min(), Some(2));
+        assert_eq!(map.max(), Some(100));
+        assert_eq!(map.into_legacy().len(), entries.len() as u64);
+    }
+
+    #[test]
+    fn wrap_empty_legacy_map() {
+        let mut map: LegacyBackedTreeMap<u32, u32> = TreeMap::wrap_legacy(b"l");
+        assert!(map.is_empty());
+        map.insert(&1, &1);
+        assert_eq!(map.to_vec(), [(1, 1)]);
+    }
+
+    #[test]
+    fn same_layout_as_legacy() {
+        let mut legacy = LegacyTreeMap::new(b"l");
+        legacy.insert(&1u32, &2u32);
+        let map =
+            LegacyBackedTreeMap::<u32, u32>::try_from_slice(&legacy.try_to_vec().unwrap()).unwrap();
+        assert_eq!(map.get(&1), Some(2));
+    }
+}
@@ -0,0 +1,147 @@
+//! Generation of unique prefixes for nested collections.
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::collections::append_slice;
+use crate::errors::SdkError;
+use crate::{env, IntoStorageKey};
+
+/// Hands out unique prefixes for nested collections, so that they don't have to be picked by hand.
+///
+/// The allocator keeps a counter in storage under its own key, and each call to
+/// [`next`](Self::next) returns that key followed by the counter encoded as LEB128, before
+/// incrementing the counter. LEB128 is prefix-free, so no generated prefix is the start of another
+/// one and the storage keys of the collections using them can't overlap. With a 1 byte key the
+/// prefixes are at most 5 bytes long for the first 2^28 allocations.
+///
+/// The key must not be the start of any other prefix used in the contract, as with any prefix.
+///
+/// # Examples
+/// ```
+/// use near_sdk::collections::{PrefixAllocator, UnorderedMap, UnorderedSet};
+///
+/// let mut prefixes = PrefixAllocator::new(b"p");
+/// let mut tokens_per_owner: UnorderedMap<String, UnorderedSet<u64>> = UnorderedMap::new(b"t");
+///
+/// let mut tokens = UnorderedSet::new(prefixes.next());
+/// tokens.insert(&1);
+/// tokens_per_owner.insert(&"alice".to_string(), &tokens);
+///
+/// let mut tokens = UnorderedSet::new(prefixes.next());
+/// tokens.insert(&2);
+/// tokens_per_owner.insert(&"bob".to_string(), &tokens);
+///
+/// assert_eq!(tokens_per_owner.get(&"alice".to_string()).unwrap().to_vec(), [1]);
+/// assert_eq!(prefixes.allocated(), 2);
+/// ```
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct PrefixAllocator {
+    key: Vec<u8>,
+}
+
+impl PrefixAllocator {
+    /// Create new allocator storing its counter under `key`. The generated prefixes start with
+    /// `key`.
+    pub fn new<S>(key: S) -> Self
+    where
+        S: IntoStorageKey,
+    {
+        Self { key: key.into_storage_key() }
+    }
+
+    /// Returns the number of prefixes generated so far.
+    pub fn allocated(&self) -> u64 {
+        match env::storage_read(&self.key) {
+            Some(raw_counter) => {
+                let mut counter = [0u8; 8];
+                if raw_counter.len() != counter.len() {
+                    SdkError::CollectionInconsistent { collection: "PrefixAllocator" }.panic()
+                }
+                counter.copy_from_slice(&raw_counter);
+                u64::from_le_bytes(counter)
+            }
+            None => 0,
+        }
+    }
+
+    /// Returns a new prefix, distinct from all prefixes previously generated by this allocator.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Vec<u8> {
+        let counter = self.allocated();
+        let next = counter.checked_add(1).unwrap_or_else(|| env::panic_str("Prefixes exhausted"));
+        env::storage_write(&self.key, &next.to_le_bytes());
+        append_slice(&self.key, &encode_leb128(counter))
+    }
+}
+
+/// Encodes `value` as unsigned LEB128, 7 bits per byte with the high bit set on all bytes but the
+/// last one.
+fn encode_leb128(mut value: u64) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(byte);
+            return bytes;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collections::{UnorderedMap, UnorderedSet};
+    use std::collections::HashSet;
+
+    #[test]
+    fn leb128() {
+        assert_eq!(encode_leb128(0), [0]);
+        assert_eq!(encode_leb128(127), [0x7f]);
+        assert_eq!(encode_leb128(128), [0x80, 0x01]);
+        assert_eq!(encode_leb128(300), [0xac, 0x02]);
+        assert_eq!(encode_leb128((1 << 28) - 1), [0xff, 0xff, 0xff, 0x7f]);
+        assert_eq!(encode_leb128(u64::MAX).len(), 10);
+    }
+
+    #[test]
+    fn nested_sets() {
+        let mut prefixes = PrefixAllocator::new(b"p");
+        let mut sets: UnorderedMap<u32, UnorderedSet<u32>> = UnorderedMap::new(b"m");
+        let mut generated = HashSet::new();
+        for i in 0..500 {
+            let prefix = prefixes.next();
+            assert!(prefix.len() <= 5);
+            assert!(generated.insert(prefix.clone()));
+            let mut set = UnorderedSet::new(prefix);
+            set.insert(&i);
+            set.insert(&(i + 1000));
+            sets.insert(&i, &set);
+        }
+        assert_eq!(prefixes.allocated(), 500);
+
+        // No generated prefix is the start of another one.
+        for a in &generated {
+            for b in &generated {
+                assert!(a == b || !b.starts_with(a));
+            }
+        }
+        for i in 0..500 {
+            let set = sets.get(&i).unwrap();
+            assert_eq!(set.len(), 2);
+            assert!(set.contains(&i));
+            assert!(set.contains(&(i + 1000)));
+        }
+    }
+
+    #[test]
+    fn counter_persists() {
+        let first = PrefixAllocator::new(b"p").next();
+        // A new handle to the same key continues from the stored counter.
+        let mut prefixes = PrefixAllocator::new(b"p");
+        assert_eq!(prefixes.allocated(), 1);
+        assert_ne!(prefixes.next(), first);
+        assert_eq!(prefixes.next(), [b'p', 2]);
+    }
+}
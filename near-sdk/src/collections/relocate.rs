@@ -0,0 +1,304 @@
+//! Moving collections to a new storage prefix over multiple function calls.
+//!
+//! Storage can't be iterated by prefix on-chain, so each collection lists the storage keys of its
+//! own entries through [`Relocate`]. Entries are first copied to the new prefix while the
+//! collection keeps reading from the old one. Once every entry is copied the collection switches
+//! to the new prefix and the entries under the old prefix are removed.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::errors::SdkError;
+use crate::{env, require};
+
+/// Progress of moving a collection to a new prefix, returned by the `relocate_to` methods of the
+/// collections.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelocateProgress {
+    /// Entries are being copied to the new prefix. The collection still uses the old prefix.
+    Copying { copied: u64, len: u64 },
+    /// The collection uses the new prefix and the entries under the old prefix are being removed.
+    Cleaning { removed: u64, len: u64 },
+    /// The collection was moved and no entries are left under the old prefix.
+    Done,
+}
+
+/// Collection which knows the storage keys of its entries, so that it can be moved to a new prefix.
+pub(crate) trait Relocate {
+    /// Prefix the collection was created with.
+    fn prefix(&self) -> Vec<u8>;
+
+    /// Updates the collection to use `prefix`, without touching storage.
+    fn set_prefix(&mut self, prefix: &[u8]);
+
+    /// Number of entries of the collection.
+    fn entries_len(&self) -> u64;
+
+    /// Storage keys of the entry at `index`, if the collection used `prefix`.
+    fn entry_keys(&self, index: u64, prefix: &[u8]) -> Vec<Vec<u8>>;
+}
+
+/// Cursor persisted between calls while a collection is being relocated.
+#[derive(BorshSerialize, BorshDeserialize)]
+struct RelocateCursor {
+    old_prefix: Vec<u8>,
+    new_prefix: Vec<u8>,
+    len: u64,
+    index: u64,
+    moved: bool,
+}
+
+/// Moves up to `batch` entries of `collection` to `new_prefix`, keeping track of the progress
+/// under `cursor_key`. The collection must not be modified until [`RelocateProgress::Done`] is
+/// returned. Panics if `batch` is 0, as the relocation would never progress.
+pub(crate) fn relocate<C: Relocate>(
+    collection: &mut C,
+    new_prefix: Vec<u8>,
+    batch: u64,
+    cursor_key: &[u8],
+) -> RelocateProgress {
+    require!(batch > 0, "The batch size must be positive");
+    let mut cursor = match env::storage_read(cursor_key) {
+        Some(raw_cursor) => RelocateCursor::try_from_slice(&raw_cursor)
+            .unwrap_or_else(|_| SdkError::Deserialization { context: "cursor" }.panic()),
+        None => {
+            let old_prefix = collection.prefix();
+            require!(old_prefix != new_prefix, "Collection already uses the new prefix");
+            RelocateCursor {
+                old_prefix,
+                new_prefix: new_prefix.clone(),
+                len: collection.entries_len(),
+                index: 0,
+                moved: false,
+            }
+        }
+    };
+    require!(
+        cursor.new_prefix == new_prefix,
+        "Relocation in progress under the cursor key is to a different prefix"
+    );
+
+    let end = cursor.index.saturating_add(batch).min(cursor.len);
+    let progress = if !cursor.moved {
+        for index in cursor.index..end {
+            let old_keys = collection.entry_keys(index, &cursor.old_prefix);
+            let new_keys = collection.entry_keys(index, &cursor.new_prefix);
+            for (old_key, new_key) in old_keys.iter().zip(new_keys.iter()) {
+                if let Some(value) = env::storage_read(old_key) {
+                    env::storage_write(new_key, &value);
+                }
+            }
+        }
+        cursor.index = end;
+        if end < cursor.len {
+            RelocateProgress::Copying { copied: end, len: cursor.len }
+        } else {
+            // Every entry is copied, so the collection can switch over to the new prefix.
+            collection.set_prefix(&cursor.new_prefix);
+            cursor.moved = true;
+            cursor.index = 0;
+            RelocateProgress::Cleaning { removed: 0, len: cursor.len }
+        }
+    } else {
+        for index in cursor.index..end {
+            for key in collection.entry_keys(index, &cursor.old_prefix) {
+                env::storage_remove(&key);
+            }
+        }
+        cursor.index = end;
+        RelocateProgress::Cleaning { removed: end, len: cursor.len }
+    };
+
+    if cursor.moved && cursor.index == cursor.len {
+        env::storage_remove(cursor_key);
+        RelocateProgress::Done
+    } else {
+        env::storage_write(
+            cursor_key,
+            &cursor
+                .try_to_vec()
+                .unwrap_or_else(|_| SdkError::Serialization { context: "cursor" }.panic()),
+        );
+        progress
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use super::RelocateProgress;
+    use crate::collections::{LazyOption, TreeMap, UnorderedMap, UnorderedSet, Vector};
+    use crate::env;
+
+    const CURSOR: &[u8] = b"cursor";
+
+    /// Calls `step` until relocation is done, returning the number of calls.
+    fn run<F>(mut step: F) -> usize
+    where
+        F: FnMut() -> RelocateProgress,
+    {
+        let mut calls = 0;
+        loop {
+            calls += 1;
+            if step() == RelocateProgress::Done {
+                assert!(!env::storage_has_key(CURSOR));
+                return calls;
+            }
+        }
+    }
+
+    #[test]
+    fn vector() {
+        let mut vec = Vector::new(b"a".to_vec());
+        vec.extend(0u64..10);
+        let usage = env::storage_usage();
+
+        let calls = run(|| {
+            let progress = vec.relocate_to(b"b".to_vec(), 4, CURSOR);
+            assert_eq!(vec.to_vec(), (0..10).collect::<Vec<u64>>());
+            progress
+        });
+        // 3 calls to copy and 3 to clean up.
+        assert_eq!(calls, 6);
+        for i in 0..10u64 {
+            assert!(!env::storage_has_key(&[b"a".as_ref(), &i.to_le_bytes()].concat()));
+            assert!(env::storage_has_key(&[b"b".as_ref(), &i.to_le_bytes()].concat()));
+        }
+        assert_eq!(env::storage_usage(), usage);
+    }
+
+    #[test]
+    fn vector_progress() {
+        let mut vec = Vector::new(b"a".to_vec());
+        vec.extend(0u64..4);
+        assert_eq!(
+            vec.relocate_to(b"b".to_vec(), 3, CURSOR),
+            RelocateProgress::Copying { copied: 3, len: 4 }
+        );
+        // Old keys are still present and used for reads.
+        assert!(env::storage_has_key(&[b"a".as_ref(), &3u64.to_le_bytes()].concat()));
+        assert!(!env::storage_has_key(&[b"b".as_ref(), &3u64.to_le_bytes()].concat()));
+        assert_eq!(vec.get(3), Some(3));
+        assert_eq!(
+            vec.relocate_to(b"b".to_vec(), 3, CURSOR),
+            RelocateProgress::Cleaning { removed: 0, len: 4 }
+        );
+        assert_eq!(
+            vec.relocate_to(b"b".to_vec(), 3, CURSOR),
+            RelocateProgress::Cleaning { removed: 3, len: 4 }
+        );
+        assert_eq!(vec.relocate_to(b"b".to_vec(), 3, CURSOR), RelocateProgress::Done);
+    }
+
+    #[test]
+    fn empty() {
+        let mut vec = Vector::<u64>::new(b"a".to_vec());
+        assert_eq!(vec.relocate_to(b"b".to_vec(), 4, CURSOR), RelocateProgress::Done);
+        vec.push(&1);
+        assert!(env::storage_has_key(&[b"b".as_ref(), &0u64.to_le_bytes()].concat()));
+    }
+
+    #[test]
+    #[should_panic(expected = "The batch size must be positive")]
+    fn empty_batch() {
+        let mut vec = Vector::new(b"a".to_vec());
+        vec.extend(0u64..4);
+        vec.relocate_to(b"b".to_vec(), 0, CURSOR);
+    }
+
+    #[test]
+    #[should_panic(expected = "Relocation in progress under the cursor key is to a different")]
+    fn different_prefix() {
+        let mut vec = Vector::new(b"a".to_vec());
+        vec.extend(0u64..4);
+        vec.relocate_to(b"b".to_vec(), 1, CURSOR);
+        vec.relocate_to(b"c".to_vec(), 1, CURSOR);
+    }
+
+    #[test]
+    fn unordered_map() {
+        let mut map = UnorderedMap::new(b"a".to_vec());
+        for i in 0..10u64 {
+            map.insert(&i, &(i * 2));
+        }
+        let usage = env::storage_usage();
+
+        run(|| {
+            let progress = map.relocate_to(b"b".to_vec(), 3, CURSOR);
+            assert_eq!(map.len(), 10);
+            for i in 0..10u64 {
+                assert_eq!(map.get(&i), Some(i * 2));
+            }
+            progress
+        });
+        assert_eq!(env::storage_usage(), usage);
+        assert!(!env::storage_has_key(&[b"ai".as_ref(), &1u64.to_le_bytes()].concat()));
+        assert!(env::storage_has_key(&[b"bi".as_ref(), &1u64.to_le_bytes()].concat()));
+
+        map.remove(&3);
+        map.insert(&20, &40);
+        assert_eq!(map.get(&20), Some(40));
+        assert_eq!(map.len(), 10);
+    }
+
+    #[test]
+    fn unordered_set() {
+        let mut set = UnorderedSet::new(b"a".to_vec());
+        for i in 0..10u64 {
+            set.insert(&i);
+        }
+        let usage = env::storage_usage();
+
+        run(|| {
+            let progress = set.relocate_to(b"b".to_vec(), 3, CURSOR);
+            for i in 0..10u64 {
+                assert!(set.contains(&i));
+            }
+            progress
+        });
+        assert_eq!(env::storage_usage(), usage);
+        assert!(!env::storage_has_key(&[b"ai".as_ref(), &1u64.to_le_bytes()].concat()));
+        assert!(env::storage_has_key(&[b"bi".as_ref(), &1u64.to_le_bytes()].concat()));
+
+        assert!(set.remove(&3));
+        assert!(!set.contains(&3));
+        assert_eq!(set.len(), 9);
+    }
+
+    #[test]
+    fn tree_map() {
+        let mut map = TreeMap::new(b"a".to_vec());
+        for i in 0..10u64 {
+            map.insert(&i, &(i * 2));
+        }
+        let usage = env::storage_usage();
+        let expected: Vec<(u64, u64)> = (0..10).map(|i| (i, i * 2)).collect();
+
+        run(|| {
+            let progress = map.relocate_to(b"b".to_vec(), 3, CURSOR);
+            assert_eq!(map.iter().collect::<Vec<_>>(), expected);
+            progress
+        });
+        assert_eq!(env::storage_usage(), usage);
+        assert!(!env::storage_has_key(&[b"av".as_ref(), &1u64.to_le_bytes()].concat()));
+        assert!(env::storage_has_key(&[b"bv".as_ref(), &1u64.to_le_bytes()].concat()));
+
+        map.insert(&20, &40);
+        assert_eq!(map.max(), Some(20));
+    }
+
+    #[test]
+    fn lazy_option() {
+        let mut value = LazyOption::new(b"a".to_vec(), Some(&42u64));
+        run(|| {
+            let progress = value.relocate_to(b"b".to_vec(), CURSOR);
+            assert_eq!(value.get(), Some(42));
+            progress
+        });
+        assert!(!env::storage_has_key(b"a"));
+        assert!(env::storage_has_key(b"b"));
+
+        let mut none = LazyOption::<u64>::new(b"c".to_vec(), None);
+        run(|| none.relocate_to(b"d".to_vec(), CURSOR));
+        assert!(none.get().is_none());
+    }
+}
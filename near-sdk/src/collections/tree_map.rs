@@ -1,8 +1,13 @@
 use borsh::{BorshDeserialize, BorshSerialize};
-use std::ops::Bound;
-
-use crate::collections::LookupMap;
-use crate::collections::{append, Vector};
+use std::marker::PhantomData;
+use std::ops::{Bound, RangeBounds};
+
+use crate::collections::key_encoding::{BorshKeyEncoding, KeyEncoding};
+use crate::collections::relocate::{relocate, Relocate, RelocateProgress};
+use crate::collections::{append, append_slice, Vector};
+use crate::collections::{BorshCodec, LookupMap, ValueCodec};
+use crate::environment::register_prefix;
+use crate::errors::{CollectionError, SdkError};
 use crate::{env, IntoStorageKey};
 
 /// TreeMap based on AVL-tree
@@ -11,14 +16,21 @@ use crate::{env, IntoStorageKey};
 /// - `get`/`contains_key`:     O(1) - UnorderedMap lookup
 /// - `insert`/`remove`:        O(log(N))
 /// - `min`/`max`:              O(log(N))
+/// - `min_entry`/`max_entry`:  O(log(N))
 /// - `above`/`below`:          O(log(N))
 /// - `range` of K elements:    O(Klog(N))
 ///
+/// Keys are stored with Borsh by default. Maps created with
+/// [`with_encoding`](Self::with_encoding) store them with another [`KeyEncoding`] instead, such
+/// as [`OrderedKeyEncoding`](crate::collections::key_encoding::OrderedKeyEncoding) for integer
+/// keys whose raw bytes should sort in numeric order.
 #[derive(BorshSerialize, BorshDeserialize)]
-pub struct TreeMap<K, V> {
+pub struct TreeMap<K, V, E: KeyEncoding<K> = BorshKeyEncoding> {
     root: u64,
-    val: LookupMap<K, V>,
-    tree: Vector<Node<K>>,
+    val: LookupMap<E::Stored, V>,
+    tree: Vector<Node<E::Stored>>,
+    #[borsh_skip]
+    encoding: PhantomData<(K, E)>,
 }
 
 #[derive(Clone, BorshSerialize, BorshDeserialize, Debug)]
@@ -39,20 +51,88 @@ where
     }
 }
 
+impl<K, V, E: KeyEncoding<K>> TreeMap<K, V, E> {
+    /// Creates another handle to this tree, intended for read paths. The root and the number of
+    /// nodes are copied, so changes made through this tree are not reflected in the new handle.
+    /// With the `check-handle-aliasing` feature enabled, writing through both handles within a call
+    /// panics.
+    pub fn duplicate_handle(&self) -> Self {
+        Self {
+            root: self.root,
+            val: self.val.duplicate_handle(),
+            tree: self.tree.duplicate_handle(),
+            encoding: PhantomData,
+        }
+    }
+
+    /// Returns the prefix of the storage keys of this map.
+    pub fn key_prefix(&self) -> Vec<u8> {
+        self.prefix()
+    }
+}
+
+impl<K, V, E: KeyEncoding<K>> Relocate for TreeMap<K, V, E> {
+    fn prefix(&self) -> Vec<u8> {
+        let tree_prefix = self.tree.prefix();
+        tree_prefix[..tree_prefix.len() - 1].to_vec()
+    }
+
+    fn set_prefix(&mut self, prefix: &[u8]) {
+        self.val = LookupMap::new(append(prefix, b'v'));
+        self.tree.set_prefix(&append(prefix, b'n'));
+    }
+
+    fn entries_len(&self) -> u64 {
+        self.tree.len()
+    }
+
+    fn entry_keys(&self, index: u64, prefix: &[u8]) -> Vec<Vec<u8>> {
+        let node = self
+            .tree
+            .get(index)
+            .unwrap_or_else(|| SdkError::CollectionInconsistent { collection: "TreeMap" }.panic());
+        let raw_key = node
+            .key
+            .try_to_vec()
+            .unwrap_or_else(|_| SdkError::Serialization { context: "key" }.panic());
+        let mut keys = vec![append_slice(&append(prefix, b'v'), &raw_key)];
+        keys.extend(self.tree.entry_keys(index, &append(prefix, b'n')));
+        keys
+    }
+}
+
 impl<K, V> TreeMap<K, V>
 where
     K: Ord + Clone + BorshSerialize + BorshDeserialize,
     V: BorshSerialize + BorshDeserialize,
 {
     pub fn new<S>(prefix: S) -> Self
+    where
+        S: IntoStorageKey,
+    {
+        Self::with_encoding(prefix)
+    }
+}
+
+impl<K, V, E> TreeMap<K, V, E>
+where
+    K: Ord + Clone + BorshSerialize + BorshDeserialize,
+    V: BorshSerialize + BorshDeserialize,
+    E: KeyEncoding<K>,
+{
+    /// Creates an empty map storing its keys with the encoding `E`. Use `prefix` as a unique
+    /// identifier.
+    pub fn with_encoding<S>(prefix: S) -> Self
     where
         S: IntoStorageKey,
     {
         let prefix = prefix.into_storage_key();
+        let _scope = register_prefix::<Self>(&prefix);
         Self {
             root: 0,
             val: LookupMap::new(append(&prefix, b'v')),
             tree: Vector::new(append(&prefix, b'n')),
+            encoding: PhantomData,
         }
     }
 
@@ -64,6 +144,33 @@ where
         self.tree.is_empty()
     }
 
+    /// Moves the map to `new_prefix`, processing up to `batch` entries per call and keeping track
+    /// of the progress under `cursor_key`. Call this in consecutive function calls until
+    /// [`RelocateProgress::Done`] is returned. The map keeps reading from the old prefix until
+    /// all entries are copied, after which the old entries are removed.
+    ///
+    /// The map must not be modified while it is being moved. Panics if `batch` is 0.
+    pub fn relocate_to<S>(
+        &mut self,
+        new_prefix: S,
+        batch: u64,
+        cursor_key: &[u8],
+    ) -> RelocateProgress
+    where
+        S: IntoStorageKey,
+    {
+        relocate(self, new_prefix.into_storage_key(), batch, cursor_key)
+    }
+
+    /// Returns `true` if the mocked storage holds exactly the nodes and values of this tree, with
+    /// no stale entries. Only available in unit tests.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn is_storage_clean(&self) -> bool {
+        let values =
+            crate::collections::mocked_storage_suffixes(&append(&self.prefix(), b'v'), None);
+        values.len() as u64 == self.len() && self.tree.is_storage_clean()
+    }
+
     pub fn clear(&mut self) {
         self.root = 0;
         for n in self.tree.iter() {
@@ -72,11 +179,21 @@ where
         self.tree.clear();
     }
 
-    fn node(&self, id: u64) -> Option<Node<K>> {
+    fn node(&self, id: u64) -> Option<Node<E::Stored>> {
         self.tree.get(id)
     }
 
-    fn save(&mut self, node: &Node<K>) {
+    /// Pushes the node `at` and its chain of left children onto `stack`, so that the smallest key
+    /// of the subtree is on top.
+    fn push_left_path(&self, at: u64, stack: &mut Vec<Node<E::Stored>>) {
+        let mut next = self.node(at);
+        while let Some(node) = next {
+            next = node.lft.and_then(|lft| self.node(lft));
+            stack.push(node);
+        }
+    }
+
+    fn save(&mut self, node: &Node<E::Stored>) {
         if node.id < self.len() {
             self.tree.replace(node.id, node);
         } else {
@@ -84,49 +201,185 @@ where
         }
     }
 
+    /// Reports an error of the map of values as an error of this map.
+    fn value_error(&self, err: CollectionError) -> CollectionError {
+        CollectionError::new(err.error().clone(), &Relocate::prefix(self), err.key())
+    }
+
     pub fn contains_key(&self, key: &K) -> bool {
-        self.val.get(key).is_some()
+        self.val.contains_key(&E::to_stored(key))
     }
 
     pub fn get(&self, key: &K) -> Option<V> {
-        self.val.get(key)
+        self.try_get(key).unwrap_or_else(|err| err.panic())
     }
 
-    pub fn insert(&mut self, key: &K, val: &V) -> Option<V> {
-        if !self.contains_key(key) {
-            self.root = self.insert_at(self.root, self.len(), key);
-        }
-        self.val.insert(key, val)
+    /// Returns the value corresponding to the key, or an error identifying the key if it can't be
+    /// serialized or the value can't be deserialized.
+    pub fn try_get(&self, key: &K) -> Result<Option<V>, CollectionError> {
+        self.try_get_stored(&E::to_stored(key))
     }
 
+    fn try_get_stored(&self, key: &E::Stored) -> Result<Option<V>, CollectionError> {
+        self.val.try_get(key).map_err(|err| self.value_error(err))
+    }
+
+    /// Returns the value of a key of the tree, which must be in the map of values.
+    fn expect_value(&self, key: &E::Stored) -> V {
+        self.try_get_stored(key)
+            .unwrap_or_else(|err| err.panic())
+            .unwrap_or_else(|| SdkError::CollectionInconsistent { collection: "TreeMap" }.panic())
+    }
+
+    /// Inserts a key-value pair into the map, returning the previous value.
+    ///
+    /// The key and the value are serialized and the previous value is deserialized before anything
+    /// is written, so the map is left unchanged if any of them fails.
+    pub fn insert(&mut self, key: &K, val: &V) -> Option<V> {
+        let key = E::to_stored(key);
+        let (raw_key, raw_value) =
+            self.try_encode_entry(&key, val).unwrap_or_else(|err| err.panic());
+        let old_value = self.try_get_stored(&key).unwrap_or_else(|err| err.panic());
+        if old_value.is_none() {
+            self.root = self.insert_at(self.root, self.len(), &key);
+        }
+        self.val.insert_raw(&raw_key, &raw_value);
+        old_value
+    }
+
+    /// Inserts a key-value pair into the map, returning the previous value. Nothing is written if
+    /// the key or the value can't be serialized. The value is written even if the previous value
+    /// can't be deserialized.
+    pub fn try_insert(&mut self, key: &K, val: &V) -> Result<Option<V>, CollectionError> {
+        let key = E::to_stored(key);
+        let (raw_key, raw_value) = self.try_encode_entry(&key, val)?;
+        // The write returns the previous value, so the key is only looked up once.
+        let raw_old_value = self.val.insert_raw(&raw_key, &raw_value);
+        if raw_old_value.is_none() {
+            self.root = self.insert_at(self.root, self.len(), &key);
+        }
+        raw_old_value
+            .map(|raw_old_value| {
+                <BorshCodec as ValueCodec<V>>::try_decode(&raw_old_value)
+                    .map_err(|error| CollectionError::new(error, &Relocate::prefix(self), &raw_key))
+            })
+            .transpose()
+    }
+
+    fn try_encode_entry(
+        &self,
+        key: &E::Stored,
+        val: &V,
+    ) -> Result<(Vec<u8>, Vec<u8>), CollectionError> {
+        let raw_key = key.try_to_vec().map_err(|_| {
+            let error = SdkError::Serialization { context: "key" };
+            CollectionError::new(error, &Relocate::prefix(self), &[])
+        })?;
+        let raw_value = <BorshCodec as ValueCodec<V>>::try_encode(val)
+            .map_err(|error| CollectionError::new(error, &Relocate::prefix(self), &raw_key))?;
+        Ok((raw_key, raw_value))
+    }
+
+    /// Removes a key from the map, returning the value at the key if the key was previously in the
+    /// map.
+    ///
+    /// The value is read and deserialized before anything is written, so the map is left unchanged
+    /// if it can't be deserialized. Use [`try_remove`](Self::try_remove) to remove such entries.
     pub fn remove(&mut self, key: &K) -> Option<V> {
-        if self.contains_key(key) {
-            self.root = self.do_remove(key);
-            self.val.remove(key)
-        } else {
+        let key = E::to_stored(key);
+        let value = self.try_get_stored(&key).unwrap_or_else(|err| err.panic())?;
+        self.root = self.do_remove(&key);
+        let raw_key =
+            key.try_to_vec().unwrap_or_else(|_| SdkError::Serialization { context: "key" }.panic());
+        self.val.remove_raw(&raw_key);
+        Some(value)
+    }
+
+    /// Removes a key from the map, returning the value at the key if the key was previously in the
+    /// map. The entry is removed even if its value can't be deserialized, so this can be used to
+    /// discard corrupted entries.
+    pub fn try_remove(&mut self, key: &K) -> Result<Option<V>, CollectionError> {
+        let key = E::to_stored(key);
+        let raw_key = key.try_to_vec().map_err(|_| {
+            let error = SdkError::Serialization { context: "key" };
+            CollectionError::new(error, &Relocate::prefix(self), &[])
+        })?;
+        // The removal returns the previous value, so the key is only looked up once.
+        match self.val.remove_raw(&raw_key) {
+            Some(raw_value) => {
+                self.root = self.do_remove(&key);
+                <BorshCodec as ValueCodec<V>>::try_decode(&raw_value)
+                    .map(Some)
+                    .map_err(|error| CollectionError::new(error, &Relocate::prefix(self), &raw_key))
+            }
             // no such key, nothing to do
-            None
+            None => Ok(None),
         }
     }
 
     /// Returns the smallest stored key from the tree
     pub fn min(&self) -> Option<K> {
-        self.min_at(self.root, self.root).map(|(n, _)| n.key)
+        self.min_stored().map(E::from_stored)
     }
 
     /// Returns the largest stored key from the tree
     pub fn max(&self) -> Option<K> {
+        self.max_stored().map(E::from_stored)
+    }
+
+    /// Returns the smallest key and its value. The value is read with the key found at the end of
+    /// the traversal, instead of a separate [`get`](Self::get) by the caller.
+    pub fn min_entry(&self) -> Option<(K, V)> {
+        self.min_stored().map(|key| self.entry(key))
+    }
+
+    /// Returns the largest key and its value, see [`min_entry`](Self::min_entry).
+    pub fn max_entry(&self) -> Option<(K, V)> {
+        self.max_stored().map(|key| self.entry(key))
+    }
+
+    /// Removes and returns the entry of [`min_entry`](Self::min_entry). The value is taken from
+    /// the removed storage entry, so it's only read once.
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        let key = self.min()?;
+        self.pop_entry(key)
+    }
+
+    /// Removes and returns the entry of [`max_entry`](Self::max_entry).
+    pub fn pop_last(&mut self) -> Option<(K, V)> {
+        let key = self.max()?;
+        self.pop_entry(key)
+    }
+
+    fn min_stored(&self) -> Option<E::Stored> {
+        self.min_at(self.root, self.root).map(|(n, _)| n.key)
+    }
+
+    fn max_stored(&self) -> Option<E::Stored> {
         self.max_at(self.root, self.root).map(|(n, _)| n.key)
     }
 
+    /// Returns a key of the tree and its value, which must be in the map of values.
+    fn entry(&self, key: E::Stored) -> (K, V) {
+        let value = self.expect_value(&key);
+        (E::from_stored(key), value)
+    }
+
+    fn pop_entry(&mut self, key: K) -> Option<(K, V)> {
+        let value = self
+            .remove(&key)
+            .unwrap_or_else(|| SdkError::CollectionInconsistent { collection: "TreeMap" }.panic());
+        Some((key, value))
+    }
+
     /// Returns the smallest key that is strictly greater than key given as the parameter
     pub fn higher(&self, key: &K) -> Option<K> {
-        self.above_at(self.root, key)
+        self.above_at(self.root, &E::to_stored(key)).map(E::from_stored)
     }
 
     /// Returns the largest key that is strictly less than key given as the parameter
     pub fn lower(&self, key: &K) -> Option<K> {
-        self.below_at(self.root, key)
+        self.below_at(self.root, &E::to_stored(key)).map(E::from_stored)
     }
 
     /// Returns the smallest key that is greater or equal to key given as the parameter
@@ -169,6 +422,13 @@ where
 
     /// Iterate entries in ascending order according to specified bounds.
     ///
+    /// Ranges are compared using the `Ord` implementation of the stored keys, which matches the
+    /// order of `K`, not the serialized keys. When keys are composed manually or their raw bytes
+    /// are scanned off-chain, prefer the types in
+    /// [`key_encoding`](crate::collections::key_encoding), or
+    /// [`OrderedKeyEncoding`](crate::collections::key_encoding::OrderedKeyEncoding) for integer
+    /// keys, so that the byte order of integer keys matches their numeric order.
+    ///
     /// # Panics
     ///
     /// Panics if range start > end.
@@ -182,7 +442,7 @@ where
             (lo, hi) => (lo, hi),
         };
 
-        Cursor::range(self, lo, hi)
+        Cursor::range(self, Self::stored_bound(bound_ref(&lo)), Self::stored_bound(bound_ref(&hi)))
     }
 
     /// Helper function which creates a [`Vec<(K, V)>`] of all items in the [`TreeMap`].
@@ -191,15 +451,85 @@ where
         self.iter().collect()
     }
 
+    /// Iterate all keys in ascending order, without reading the values. Each node of the tree is
+    /// read once.
+    pub fn keys(&self) -> impl Iterator<Item = K> + '_ {
+        self.stored_keys().map(E::from_stored)
+    }
+
+    /// Iterate all values in ascending order of their keys.
+    pub fn values(&self) -> impl Iterator<Item = V> + '_ {
+        self.stored_keys().map(move |key| self.expect_value(&key))
+    }
+
+    fn stored_keys(&self) -> impl Iterator<Item = E::Stored> + '_ {
+        let mut stack = Vec::new();
+        self.push_left_path(self.root, &mut stack);
+        core::iter::from_fn(move || {
+            let node = stack.pop()?;
+            if let Some(rgt) = node.rgt {
+                self.push_left_path(rgt, &mut stack);
+            }
+            Some(node.key)
+        })
+    }
+
+    /// Returns the number of keys within `range`, without reading the values. Only the nodes
+    /// within the range and the nodes on the paths to its bounds are read. Returns 0 if the start
+    /// of the range is after its end.
+    pub fn range_count<R: RangeBounds<K>>(&self, range: R) -> u64 {
+        let lo = Self::stored_bound(range.start_bound());
+        let hi = Self::stored_bound(range.end_bound());
+        let mut count = 0;
+        let mut stack = vec![self.root];
+        while let Some(id) = stack.pop() {
+            let node = match self.node(id) {
+                Some(node) => node,
+                None => continue,
+            };
+            let above_lo = match &lo {
+                Bound::Included(lo) => &node.key >= lo,
+                Bound::Excluded(lo) => &node.key > lo,
+                Bound::Unbounded => true,
+            };
+            let below_hi = match &hi {
+                Bound::Included(hi) => &node.key <= hi,
+                Bound::Excluded(hi) => &node.key < hi,
+                Bound::Unbounded => true,
+            };
+            if above_lo && below_hi {
+                count += 1;
+            }
+            // Smaller keys can only be in range if this key is above the lower bound, and larger
+            // keys if it is below the upper bound.
+            if above_lo {
+                stack.extend(node.lft);
+            }
+            if below_hi {
+                stack.extend(node.rgt);
+            }
+        }
+        count
+    }
+
     //
     // Internal utilities
     //
 
+    /// Converts a bound on keys to a bound on stored keys.
+    fn stored_bound(bound: Bound<&K>) -> Bound<E::Stored> {
+        match bound {
+            Bound::Included(key) => Bound::Included(E::to_stored(key)),
+            Bound::Excluded(key) => Bound::Excluded(E::to_stored(key)),
+            Bound::Unbounded => Bound::Unbounded,
+        }
+    }
+
     /// Returns (node, parent node) of left-most lower (min) node starting from given node `at`.
     /// As min_at only traverses the tree down, if a node `at` is the minimum node in a subtree,
     /// its parent must be explicitly provided in advance.
-    fn min_at(&self, mut at: u64, p: u64) -> Option<(Node<K>, Node<K>)> {
-        let mut parent: Option<Node<K>> = self.node(p);
+    fn min_at(&self, mut at: u64, p: u64) -> Option<(Node<E::Stored>, Node<E::Stored>)> {
+        let mut parent: Option<Node<E::Stored>> = self.node(p);
         loop {
             let node = self.node(at);
             match node.as_ref().and_then(|n| n.lft) {
@@ -217,8 +547,8 @@ where
     /// Returns (node, parent node) of right-most lower (max) node starting from given node `at`.
     /// As min_at only traverses the tree down, if a node `at` is the minimum node in a subtree,
     /// its parent must be explicitly provided in advance.
-    fn max_at(&self, mut at: u64, p: u64) -> Option<(Node<K>, Node<K>)> {
-        let mut parent: Option<Node<K>> = self.node(p);
+    fn max_at(&self, mut at: u64, p: u64) -> Option<(Node<E::Stored>, Node<E::Stored>)> {
+        let mut parent: Option<Node<E::Stored>> = self.node(p);
         loop {
             let node = self.node(at);
             match node.as_ref().and_then(|n| n.rgt) {
@@ -233,8 +563,8 @@ where
         }
     }
 
-    fn above_at(&self, mut at: u64, key: &K) -> Option<K> {
-        let mut seen: Option<K> = None;
+    fn above_at(&self, mut at: u64, key: &E::Stored) -> Option<E::Stored> {
+        let mut seen: Option<E::Stored> = None;
         loop {
             let node = self.node(at);
             match node.as_ref().map(|n| &n.key) {
@@ -258,8 +588,8 @@ where
         seen
     }
 
-    fn below_at(&self, mut at: u64, key: &K) -> Option<K> {
-        let mut seen: Option<K> = None;
+    fn below_at(&self, mut at: u64, key: &E::Stored) -> Option<E::Stored> {
+        let mut seen: Option<E::Stored> = None;
         loop {
             let node = self.node(at);
             match node.as_ref().map(|n| &n.key) {
@@ -283,7 +613,7 @@ where
         seen
     }
 
-    fn insert_at(&mut self, at: u64, id: u64, key: &K) -> u64 {
+    fn insert_at(&mut self, at: u64, id: u64, key: &E::Stored) -> u64 {
         match self.node(at) {
             None => {
                 self.save(&Node::of(id, key.clone()));
@@ -316,7 +646,7 @@ where
 
     // Calculate and save the height of a subtree at node `at`:
     // height[at] = 1 + max(height[at.L], height[at.R])
-    fn update_height(&mut self, node: &mut Node<K>) {
+    fn update_height(&mut self, node: &mut Node<E::Stored>) {
         let lft = node.lft.and_then(|id| self.node(id).map(|n| n.ht)).unwrap_or_default();
         let rgt = node.rgt.and_then(|id| self.node(id).map(|n| n.ht)).unwrap_or_default();
 
@@ -325,7 +655,7 @@ where
     }
 
     // Balance = difference in heights between left and right subtrees at given node.
-    fn get_balance(&self, node: &Node<K>) -> i64 {
+    fn get_balance(&self, node: &Node<E::Stored>) -> i64 {
         let lht = node.lft.and_then(|id| self.node(id).map(|n| n.ht)).unwrap_or_default();
         let rht = node.rgt.and_then(|id| self.node(id).map(|n| n.ht)).unwrap_or_default();
 
@@ -334,7 +664,7 @@ where
 
     // Left rotation of an AVL subtree with at node `at`.
     // New root of subtree is returned, caller is responsible for updating proper link from parent.
-    fn rotate_left(&mut self, node: &mut Node<K>) -> u64 {
+    fn rotate_left(&mut self, node: &mut Node<E::Stored>) -> u64 {
         let mut lft = node.lft.and_then(|id| self.node(id)).unwrap();
         let lft_rgt = lft.rgt;
 
@@ -353,7 +683,7 @@ where
 
     // Right rotation of an AVL subtree at node in `at`.
     // New root of subtree is returned, caller is responsible for updating proper link from parent.
-    fn rotate_right(&mut self, node: &mut Node<K>) -> u64 {
+    fn rotate_right(&mut self, node: &mut Node<E::Stored>) -> u64 {
         let mut rgt = node.rgt.and_then(|id| self.node(id)).unwrap();
         let rgt_lft = rgt.lft;
 
@@ -371,7 +701,7 @@ where
     }
 
     // Check balance at a given node and enforce it if necessary with respective rotations.
-    fn enforce_balance(&mut self, node: &mut Node<K>) -> u64 {
+    fn enforce_balance(&mut self, node: &mut Node<E::Stored>) -> u64 {
         let balance = self.get_balance(node);
         if balance > 1 {
             let mut lft = node.lft.and_then(|id| self.node(id)).unwrap();
@@ -394,8 +724,12 @@ where
 
     // Returns (node, parent node) for a node that holds the `key`.
     // For root node, same node is returned for node and parent node.
-    fn lookup_at(&self, mut at: u64, key: &K) -> Option<(Node<K>, Node<K>)> {
-        let mut p: Node<K> = self.node(at).unwrap();
+    fn lookup_at(
+        &self,
+        mut at: u64,
+        key: &E::Stored,
+    ) -> Option<(Node<E::Stored>, Node<E::Stored>)> {
+        let mut p: Node<E::Stored> = self.node(at).unwrap();
         while let Some(node) = self.node(at) {
             if node.key.eq(key) {
                 return Some((node, p));
@@ -422,7 +756,7 @@ where
 
     // Navigate from root to node holding `key` and backtrace back to the root
     // enforcing balance (if necessary) along the way.
-    fn check_balance(&mut self, at: u64, key: &K) -> u64 {
+    fn check_balance(&mut self, at: u64, key: &E::Stored) -> u64 {
         match self.node(at) {
             Some(mut node) => {
                 if !node.key.eq(key) {
@@ -451,7 +785,7 @@ where
     // - right-most (max) node of the left subtree (containing smaller keys) of node holding `key`
     // - or left-most (min) node of the right subtree (containing larger keys) of node holding `key`
     //
-    fn do_remove(&mut self, key: &K) -> u64 {
+    fn do_remove(&mut self, key: &E::Stored) -> u64 {
         // r_node - node containing key of interest
         // p_node - immediate parent node of r_node
         let (mut r_node, mut p_node) = match self.lookup_at(self.root, key) {
@@ -580,33 +914,37 @@ where
     }
 }
 
-impl<K, V> std::fmt::Debug for TreeMap<K, V>
+impl<K, V, E> std::fmt::Debug for TreeMap<K, V, E>
 where
     K: std::fmt::Debug + Ord + Clone + BorshSerialize + BorshDeserialize,
     V: std::fmt::Debug + BorshSerialize + BorshDeserialize,
+    E: KeyEncoding<K>,
+    E::Stored: std::fmt::Debug,
 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("TreeMap").field("root", &self.root).field("tree", &self.tree).finish()
     }
 }
 
-impl<'a, K, V> IntoIterator for &'a TreeMap<K, V>
+impl<'a, K, V, E> IntoIterator for &'a TreeMap<K, V, E>
 where
     K: Ord + Clone + BorshSerialize + BorshDeserialize,
     V: BorshSerialize + BorshDeserialize,
+    E: KeyEncoding<K>,
 {
     type Item = (K, V);
-    type IntoIter = Cursor<'a, K, V>;
+    type IntoIter = Cursor<'a, K, V, E>;
 
     fn into_iter(self) -> Self::IntoIter {
         Cursor::asc(self)
     }
 }
 
-impl<K, V> Iterator for Cursor<'_, K, V>
+impl<K, V, E> Iterator for Cursor<'_, K, V, E>
 where
     K: Ord + Clone + BorshSerialize + BorshDeserialize,
     V: BorshSerialize + BorshDeserialize,
+    E: KeyEncoding<K>,
 {
     type Item = (K, V);
 
@@ -640,28 +978,29 @@ where
         }
 
         let key = self.progress_key()?;
-        let value = self.map.get(&key)?;
+        let value = self.map.try_get_stored(&key).unwrap_or_else(|err| err.panic())?;
 
-        Some((key, value))
+        Some((E::from_stored(key), value))
     }
 
     fn last(mut self) -> Option<Self::Item> {
         if self.asc && matches!(self.hi, Bound::Unbounded) {
-            self.map.max().and_then(|k| self.map.get(&k).map(|v| (k, v)))
+            self.map.max_entry()
         } else if !self.asc && matches!(self.lo, Bound::Unbounded) {
-            self.map.min().and_then(|k| self.map.get(&k).map(|v| (k, v)))
+            self.map.min_entry()
         } else {
             // Cannot guarantee what the last is within the range, must load keys until last.
             let key = core::iter::from_fn(|| self.progress_key()).last();
-            key.and_then(|k| self.map.get(&k).map(|v| (k, v)))
+            key.map(|k| self.map.entry(k))
         }
     }
 }
 
-impl<K, V> std::iter::FusedIterator for Cursor<'_, K, V>
+impl<K, V, E> std::iter::FusedIterator for Cursor<'_, K, V, E>
 where
     K: Ord + Clone + BorshSerialize + BorshDeserialize,
     V: BorshSerialize + BorshDeserialize,
+    E: KeyEncoding<K>,
 {
 }
 
@@ -677,43 +1016,54 @@ fn fits<K: Ord>(key: &K, lo: &Bound<K>, hi: &Bound<K>) -> bool {
     })
 }
 
-pub struct Cursor<'a, K, V> {
+fn bound_ref<K>(bound: &Bound<K>) -> Bound<&K> {
+    match bound {
+        Bound::Included(key) => Bound::Included(key),
+        Bound::Excluded(key) => Bound::Excluded(key),
+        Bound::Unbounded => Bound::Unbounded,
+    }
+}
+
+/// An iterator over the entries of a [`TreeMap`], which walks the stored keys and converts each
+/// key it yields.
+pub struct Cursor<'a, K, V, E: KeyEncoding<K>> {
     asc: bool,
-    lo: Bound<K>,
-    hi: Bound<K>,
-    key: Option<K>,
-    map: &'a TreeMap<K, V>,
+    lo: Bound<E::Stored>,
+    hi: Bound<E::Stored>,
+    key: Option<E::Stored>,
+    map: &'a TreeMap<K, V, E>,
 }
 
-impl<'a, K, V> Cursor<'a, K, V>
+impl<'a, K, V, E> Cursor<'a, K, V, E>
 where
     K: Ord + Clone + BorshSerialize + BorshDeserialize,
     V: BorshSerialize + BorshDeserialize,
+    E: KeyEncoding<K>,
 {
-    fn asc(map: &'a TreeMap<K, V>) -> Self {
-        let key: Option<K> = map.min();
+    fn asc(map: &'a TreeMap<K, V, E>) -> Self {
+        let key = map.min_stored();
         Self { asc: true, key, lo: Bound::Unbounded, hi: Bound::Unbounded, map }
     }
 
-    fn asc_from(map: &'a TreeMap<K, V>, key: K) -> Self {
-        let key = map.higher(&key);
+    fn asc_from(map: &'a TreeMap<K, V, E>, key: K) -> Self {
+        let key = map.above_at(map.root, &E::to_stored(&key));
         Self { asc: true, key, lo: Bound::Unbounded, hi: Bound::Unbounded, map }
     }
 
-    fn desc(map: &'a TreeMap<K, V>) -> Self {
-        let key: Option<K> = map.max();
+    fn desc(map: &'a TreeMap<K, V, E>) -> Self {
+        let key = map.max_stored();
         Self { asc: false, key, lo: Bound::Unbounded, hi: Bound::Unbounded, map }
     }
 
-    fn desc_from(map: &'a TreeMap<K, V>, key: K) -> Self {
-        let key = map.lower(&key);
+    fn desc_from(map: &'a TreeMap<K, V, E>, key: K) -> Self {
+        let key = map.below_at(map.root, &E::to_stored(&key));
         Self { asc: false, key, lo: Bound::Unbounded, hi: Bound::Unbounded, map }
     }
 
-    fn range(map: &'a TreeMap<K, V>, lo: Bound<K>, hi: Bound<K>) -> Self {
+    fn range(map: &'a TreeMap<K, V, E>, lo: Bound<E::Stored>, hi: Bound<E::Stored>) -> Self {
         let key = match &lo {
-            Bound::Included(k) if map.contains_key(k) => Some(k.clone()),
-            Bound::Included(k) | Bound::Excluded(k) => map.higher(k),
+            Bound::Included(k) if map.val.contains_key(k) => Some(k.clone()),
+            Bound::Included(k) | Bound::Excluded(k) => map.above_at(map.root, k),
             _ => None,
         };
         let key = key.filter(|k| fits(k, &lo, &hi));
@@ -722,11 +1072,17 @@ where
     }
 
     /// Progresses the key one index, will return the previous key
-    fn progress_key(&mut self) -> Option<K> {
+    fn progress_key(&mut self) -> Option<E::Stored> {
         let new_key = self
             .key
             .as_ref()
-            .and_then(|k| if self.asc { self.map.higher(k) } else { self.map.lower(k) })
+            .and_then(|k| {
+                if self.asc {
+                    self.map.above_at(self.map.root, k)
+                } else {
+                    self.map.below_at(self.map.root, k)
+                }
+            })
             .filter(|k| fits(k, &self.lo, &self.hi));
         core::mem::replace(&mut self.key, new_key)
     }
@@ -736,6 +1092,8 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::collections::storage_ops;
+    use crate::errors::CollectionErrorKind;
     use crate::test_utils::{next_trie_id, test_env};
 
     extern crate rand;
@@ -779,6 +1137,64 @@ mod tests {
         h.ceil() as u64
     }
 
+    #[test]
+    fn reads_each_key_once() {
+        test_env::setup();
+        let mut map: TreeMap<u8, u8> = TreeMap::new(next_trie_id());
+        for key in 1..=3 {
+            map.insert(&key, &(key * 10));
+        }
+        // Replacing a value reads it once, and only to return it deserialized.
+        assert_eq!(storage_ops(|| assert_eq!(map.insert(&2, &21), Some(20))), (1, 1, 0));
+        assert_eq!(storage_ops(|| assert_eq!(map.try_insert(&2, &22), Ok(Some(21)))), (0, 1, 0));
+        assert_eq!(storage_ops(|| assert_eq!(map.try_remove(&4), Ok(None))), (0, 0, 0));
+        // New keys and removals are found from the value written or removed.
+        assert_eq!(storage_ops(|| assert_eq!(map.try_insert(&4, &40), Ok(None))).2, 0);
+        assert_eq!(storage_ops(|| assert_eq!(map.try_remove(&4), Ok(Some(40)))).2, 0);
+    }
+
+    #[test]
+    fn test_clear_reuse() {
+        test_env::setup();
+        let before = crate::test_utils::storage_snapshot();
+        let mut map = TreeMap::new(b"t");
+        for i in 0..20u32 {
+            map.insert(&i, &i);
+        }
+        map.clear();
+        assert_eq!(map.root, 0);
+        assert!(map.is_storage_clean());
+        assert_eq!(crate::test_utils::storage_snapshot(), before);
+
+        for i in (0..5u32).rev() {
+            map.insert(&i, &(i * 10));
+        }
+        assert!(map.is_storage_clean());
+        assert_eq!(map.iter().collect::<Vec<_>>(), (0..5).map(|i| (i, i * 10)).collect::<Vec<_>>());
+        assert_eq!(height(&map), 3);
+    }
+
+    #[test]
+    fn test_remove_all_reuse() {
+        test_env::setup();
+        let mut map = TreeMap::new(b"t");
+        for i in 0..20u32 {
+            map.insert(&i, &i);
+        }
+        for i in 0..20u32 {
+            map.remove(&i);
+            assert!(map.is_storage_clean());
+        }
+        assert!(map.is_empty());
+        // The root must point at the first node again once the tree is refilled.
+        map.insert(&7, &7);
+        assert_eq!(map.root, 0);
+        map.insert(&8, &8);
+        assert_eq!(map.min(), Some(7));
+        assert_eq!(map.max(), Some(8));
+        assert!(map.is_storage_clean());
+    }
+
     #[test]
     fn test_empty() {
         let map: TreeMap<u8, u8> = TreeMap::new(b't');
@@ -951,6 +1367,53 @@ mod tests {
         map.clear();
     }
 
+    #[test]
+    fn test_min_max_entry() {
+        test_env::setup();
+        let mut map: TreeMap<u32, String> = TreeMap::new(b"t");
+        assert_eq!(map.min_entry(), None);
+        assert_eq!(map.max_entry(), None);
+        assert_eq!(map.pop_first(), None);
+        assert_eq!(map.pop_last(), None);
+
+        // With a single node, the root is both the minimum and the maximum.
+        map.insert(&7, &"seven".to_string());
+        assert_eq!(map.min_entry(), Some((7, "seven".to_string())));
+        assert_eq!(map.max_entry(), Some((7, "seven".to_string())));
+        assert_eq!(map.pop_last(), Some((7, "seven".to_string())));
+        assert!(map.is_empty());
+        assert_eq!(map.min_entry(), None);
+        assert!(map.is_storage_clean());
+    }
+
+    #[test]
+    fn test_min_max_entry_after_rebalance() {
+        test_env::setup();
+        let mut map: TreeMap<u32, u32> = TreeMap::new(b"t");
+        let mut expected = BTreeMap::new();
+        for key in random(100) {
+            map.insert(&key, &(key * 2));
+            expected.insert(key, key * 2);
+        }
+
+        // Removing the minimum and maximum rebalances the tree along its edges.
+        while expected.len() > 2 {
+            let first = *expected.keys().next().unwrap();
+            let last = *expected.keys().next_back().unwrap();
+            assert_eq!(map.pop_first(), Some((first, first * 2)));
+            assert_eq!(map.pop_last(), Some((last, last * 2)));
+            expected.remove(&first);
+            expected.remove(&last);
+            assert!(is_balanced(&map, map.root));
+
+            let (&min, &min_value) = expected.iter().next().unwrap();
+            let (&max, &max_value) = expected.iter().next_back().unwrap();
+            assert_eq!(map.min_entry(), Some((min, min_value)));
+            assert_eq!(map.max_entry(), Some((max, max_value)));
+        }
+        assert_eq!(map.to_vec(), expected.into_iter().collect::<Vec<_>>());
+    }
+
     #[test]
     fn test_lower() {
         let mut map: TreeMap<u32, u32> = TreeMap::new(next_trie_id());
@@ -1369,6 +1832,78 @@ mod tests {
         map.clear();
     }
 
+    #[test]
+    fn test_keys_values() {
+        let mut map: TreeMap<u32, u32> = TreeMap::new(next_trie_id());
+        assert_eq!(map.keys().count(), 0);
+        assert_eq!(map.values().count(), 0);
+
+        let keys = random(200);
+        for &key in &keys {
+            map.insert(&key, &(key * 2));
+        }
+        let mut expected: Vec<u32> = keys.clone();
+        expected.sort_unstable();
+        expected.dedup();
+        assert_eq!(map.keys().collect::<Vec<_>>(), expected);
+        assert_eq!(
+            map.values().collect::<Vec<_>>(),
+            expected.iter().map(|key| key * 2).collect::<Vec<_>>()
+        );
+        map.clear();
+    }
+
+    #[test]
+    fn test_keys_without_values() {
+        let mut map: TreeMap<u32, u32> = TreeMap::new(b"t");
+        for key in [5, 1, 9, 3].iter() {
+            map.insert(key, key);
+        }
+        // Values which can't be deserialized aren't read.
+        for key in [5u32, 1, 9, 3].iter() {
+            env::storage_write(&[b"tv".as_ref(), &key.to_le_bytes()].concat(), &[0xff]);
+        }
+        assert_eq!(map.keys().collect::<Vec<_>>(), [1, 3, 5, 9]);
+        assert_eq!(map.range_count(2..=9), 3);
+    }
+
+    #[test]
+    fn test_range_count() {
+        for _ in 0..10 {
+            let mut map: TreeMap<u32, u32> = TreeMap::new(next_trie_id());
+            let mut keys = random(300);
+            for &key in &keys {
+                map.insert(&key, &key);
+            }
+            keys.sort_unstable();
+            keys.dedup();
+            let brute_force = |lo: Bound<u32>, hi: Bound<u32>| {
+                keys.iter().filter(|key| fits(*key, &lo, &hi)).count() as u64
+            };
+
+            let mut rng = rand::thread_rng();
+            for _ in 0..20 {
+                let (a, b) = (rng.next_u32() % 1100, rng.next_u32() % 1100);
+                assert_eq!(
+                    map.range_count(a..b),
+                    brute_force(Bound::Included(a), Bound::Excluded(b))
+                );
+                assert_eq!(
+                    map.range_count(a..=b),
+                    brute_force(Bound::Included(a), Bound::Included(b))
+                );
+                assert_eq!(map.range_count(a..), brute_force(Bound::Included(a), Bound::Unbounded));
+                assert_eq!(map.range_count(..b), brute_force(Bound::Unbounded, Bound::Excluded(b)));
+                assert_eq!(
+                    map.range_count((Bound::Excluded(a), Bound::Excluded(b))),
+                    brute_force(Bound::Excluded(a), Bound::Excluded(b))
+                );
+            }
+            assert_eq!(map.range_count(..), keys.len() as u64);
+            map.clear();
+        }
+    }
+
     #[test]
     fn test_iter_empty() {
         let map: TreeMap<u32, u32> = TreeMap::new(next_trie_id());
@@ -1660,10 +2195,11 @@ mod tests {
             .quickcheck(prop as fn(std::vec::Vec<(u32, u32)>, std::vec::Vec<u32>) -> bool);
     }
 
-    fn is_balanced<K, V>(map: &TreeMap<K, V>, root: u64) -> bool
+    fn is_balanced<K, V, E>(map: &TreeMap<K, V, E>, root: u64) -> bool
     where
-        K: std::fmt::Debug + Ord + Clone + BorshSerialize + BorshDeserialize,
-        V: std::fmt::Debug + BorshSerialize + BorshDeserialize,
+        K: Ord + Clone + BorshSerialize + BorshDeserialize,
+        V: BorshSerialize + BorshDeserialize,
+        E: KeyEncoding<K>,
     {
         let node = map.node(root).unwrap();
         let balance = map.get_balance(&node);
@@ -1780,4 +2316,130 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn try_accessors_report_corrupted_value() {
+        test_env::setup();
+        let mut map = TreeMap::new(b"t");
+        map.insert(&1u64, &7u64);
+        map.insert(&2u64, &8u64);
+        env::storage_write(&[b"tv".as_ref(), &1u64.to_le_bytes()].concat(), &[0xff]);
+
+        let err = map.try_get(&1).unwrap_err();
+        assert_eq!(err.kind(), CollectionErrorKind::DeserializationFailed);
+        assert_eq!(err.prefix(), b"t");
+        assert_eq!(err.key(), 1u64.to_le_bytes());
+        assert_eq!(map.try_get(&2), Ok(Some(8)));
+        assert!(map.contains_key(&1));
+
+        // The corrupted entry is removed anyway, and the tree stays balanced.
+        assert_eq!(map.try_remove(&1).unwrap_err(), err);
+        assert_eq!(map.to_vec(), [(2, 8)]);
+        assert_eq!(map.try_insert(&1, &9), Ok(None));
+        assert_eq!(map.to_vec(), [(1, 9), (2, 8)]);
+    }
+
+    fn collect<I: IntoIterator>(collection: I) -> Vec<I::Item> {
+        collection.into_iter().collect()
+    }
+
+    #[test]
+    fn test_into_iter() {
+        test_env::setup();
+        let mut map: TreeMap<u64, u64> = TreeMap::new(next_trie_id());
+        for i in (0..5).rev() {
+            map.insert(&i, &(i * 10));
+        }
+        assert_eq!(collect(&map), [(0, 0), (1, 10), (2, 20), (3, 30), (4, 40)]);
+        assert_eq!((&map).into_iter().nth(3), Some((3, 30)));
+        assert_eq!((&map).into_iter().size_hint(), (0, Some(5)));
+    }
+
+    #[test]
+    fn test_ordered_key_encoding_signed() {
+        use crate::collections::key_encoding::{OrderedI64, OrderedKeyEncoding};
+
+        test_env::setup();
+        let mut map: TreeMap<i64, u32, OrderedKeyEncoding> = TreeMap::with_encoding(b"m");
+        let mut keys =
+            vec![i64::MIN, -(1 << 40), -256, -255, -1, 0, 1, 255, 256, 1 << 40, i64::MAX];
+        let mut shuffled = keys.clone();
+        shuffled.reverse();
+        shuffled.rotate_left(4);
+        for (i, key) in shuffled.iter().enumerate() {
+            map.insert(key, &(i as u32));
+        }
+        assert!(is_balanced(&map, map.root));
+
+        let ascending: Vec<i64> = map.iter().map(|(key, _)| key).collect();
+        assert_eq!(ascending, keys);
+        assert_eq!(map.keys().collect::<Vec<_>>(), keys);
+        let descending: Vec<i64> = map.iter_rev().map(|(key, _)| key).collect();
+        keys.reverse();
+        assert_eq!(descending, keys);
+        keys.reverse();
+
+        assert_eq!(map.min(), Some(i64::MIN));
+        assert_eq!(map.max(), Some(i64::MAX));
+        assert_eq!(map.higher(&-1), Some(0));
+        assert_eq!(map.lower(&0), Some(-1));
+        assert_eq!(map.floor_key(&-100), Some(-255));
+        let range: Vec<i64> =
+            map.range((Bound::Included(-256), Bound::Excluded(1))).map(|(key, _)| key).collect();
+        assert_eq!(range, [-256, -255, -1, 0]);
+        assert_eq!(map.range_count(-256..1), 4);
+        assert_eq!(map.iter_from(-1).next().map(|(key, _)| key), Some(0));
+
+        // The raw keys of the values sort in the same order as the keys.
+        let mut raw_keys = crate::collections::mocked_storage_suffixes(b"mv", None);
+        raw_keys.sort();
+        let stored: Vec<i64> =
+            raw_keys.iter().map(|raw| OrderedI64::try_from_slice(raw).unwrap().0).collect();
+        assert_eq!(stored, keys);
+
+        map.remove(&0);
+        assert_eq!(map.higher(&-1), Some(1));
+        assert_eq!(map.len(), keys.len() as u64 - 1);
+        assert!(map.is_storage_clean());
+    }
+
+    #[test]
+    fn test_ordered_key_encoding_unsigned() {
+        use crate::collections::key_encoding::OrderedKeyEncoding;
+
+        test_env::setup();
+        let mut map: TreeMap<u32, u32, OrderedKeyEncoding> = TreeMap::with_encoding(b"m");
+        let mut expected = BTreeMap::new();
+        for key in random(200).into_iter().chain(vec![0, 255, 256, u32::MAX]) {
+            map.insert(&key, &key);
+            expected.insert(key, key);
+        }
+        assert_eq!(map.to_vec(), expected.into_iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_failures_write_nothing() {
+        use crate::collections::{assert_panics_without_writes, Fragile};
+
+        test_env::setup();
+        let mut map = TreeMap::new(b"m");
+        for key in 0..5u32 {
+            map.insert(&key, &Fragile { serializable: true });
+        }
+
+        // The value can't be serialized.
+        assert_panics_without_writes(|| map.insert(&10, &Fragile { serializable: false }));
+        // The previous value can't be deserialized.
+        assert_panics_without_writes(|| map.insert(&2, &Fragile { serializable: true }));
+        assert_panics_without_writes(|| map.remove(&2));
+        assert_eq!(map.len(), 5);
+        assert!(map.is_storage_clean());
+
+        // The fallible variant still removes undecodable entries.
+        assert!(map.try_remove(&2).is_err());
+        assert_eq!(map.len(), 4);
+        assert!(!map.contains_key(&2));
+        assert!(is_balanced(&map, map.root));
+        assert!(map.is_storage_clean());
+    }
 }
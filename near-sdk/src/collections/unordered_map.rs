@@ -1,52 +1,110 @@
 //! A map implemented on a trie. Unlike `std::collections::HashMap` the keys in this map are not
 //! hashed but are instead serialized.
-use crate::collections::{append, append_slice, Vector};
+use crate::collections::relocate::{relocate, Relocate, RelocateProgress};
+use crate::collections::vector::{self, RawIter};
+use crate::collections::{
+    append, append_slice, record_storage_cost, BorshCodec, CoalesceTarget, Coalesced, CountResult,
+    JsonCodec, TooLarge, ValueCodec, Vector,
+};
+use crate::environment::register_prefix;
+use crate::errors::{CollectionError, SdkError};
 use crate::{env, IntoStorageKey};
 use borsh::{BorshDeserialize, BorshSerialize};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
 use std::mem::size_of;
 
-const ERR_INCONSISTENT_STATE: &str = "The collection is an inconsistent state. Did previous smart contract execution terminate unexpectedly?";
-const ERR_KEY_SERIALIZATION: &str = "Cannot serialize key with Borsh";
-const ERR_VALUE_DESERIALIZATION: &str = "Cannot deserialize value with Borsh";
-const ERR_VALUE_SERIALIZATION: &str = "Cannot serialize value with Borsh";
-
 /// An iterable implementation of a map that stores its content directly on the trie.
+///
+/// Values are stored with Borsh, or as JSON for maps created with [`new_json`](Self::new_json),
+/// see [`ValueCodec`].
 #[derive(BorshSerialize, BorshDeserialize)]
-pub struct UnorderedMap<K, V> {
+pub struct UnorderedMap<K, V, C = BorshCodec> {
     key_index_prefix: Vec<u8>,
     keys: Vector<K>,
     values: Vector<V>,
+    #[borsh_skip]
+    codec: PhantomData<C>,
 }
 
 impl<K, V> UnorderedMap<K, V> {
+    /// Create new map with zero elements. Use `prefix` as a unique identifier.
+    pub fn new<S>(prefix: S) -> Self
+    where
+        S: IntoStorageKey,
+    {
+        let prefix = prefix.into_storage_key();
+        let _scope = register_prefix::<Self>(&prefix);
+        Self::with_prefix(prefix)
+    }
+}
+
+impl<K, V> UnorderedMap<K, V, JsonCodec> {
+    /// Create new map with zero elements, storing values as JSON. Use `prefix` as a unique
+    /// identifier. Keys are still serialized with Borsh.
+    ///
+    /// # Examples
+    /// ```
+    /// use near_sdk::collections::UnorderedMap;
+    ///
+    /// let mut map = UnorderedMap::new_json(b"m");
+    /// map.insert(&"alice".to_string(), &"Zoë".to_string());
+    /// assert_eq!(map.values().collect::<Vec<_>>(), ["Zoë".to_string()]);
+    /// ```
+    pub fn new_json<S>(prefix: S) -> Self
+    where
+        S: IntoStorageKey,
+    {
+        let prefix = prefix.into_storage_key();
+        let _scope = register_prefix::<Self>(&prefix);
+        Self::with_prefix(prefix)
+    }
+}
+
+impl<K, V, C> UnorderedMap<K, V, C> {
+    /// Create new map with zero elements, storing values with the codec `C`, for example a custom
+    /// [`ValueCodec`]. Use `prefix` as a unique identifier. Keys are still serialized with Borsh.
+    pub fn with_codec<S>(prefix: S) -> Self
+    where
+        S: IntoStorageKey,
+    {
+        let prefix = prefix.into_storage_key();
+        let _scope = register_prefix::<Self>(&prefix);
+        Self::with_prefix(prefix)
+    }
+
     /// Returns the number of elements in the map, also referred to as its size.
     pub fn len(&self) -> u64 {
         let keys_len = self.keys.len();
         let values_len = self.values.len();
         if keys_len != values_len {
-            env::panic_str(ERR_INCONSISTENT_STATE)
+            SdkError::CollectionInconsistent { collection: "UnorderedMap" }.panic()
         } else {
             keys_len
         }
     }
 
+    /// Recovers the length of a map recreated from its prefix alone, see
+    /// [`Vector::recover_len`].
+    pub(crate) fn recover_len(&mut self) {
+        self.keys.recover_len();
+        self.values.recover_len();
+    }
+
     /// Returns `true` if the map contains no elements.
     pub fn is_empty(&self) -> bool {
         let keys_is_empty = self.keys.is_empty();
         let values_is_empty = self.values.is_empty();
         if keys_is_empty != values_is_empty {
-            env::panic_str(ERR_INCONSISTENT_STATE)
+            SdkError::CollectionInconsistent { collection: "UnorderedMap" }.panic()
         } else {
             keys_is_empty
         }
     }
 
-    /// Create new map with zero elements. Use `prefix` as a unique identifier.
-    pub fn new<S>(prefix: S) -> Self
-    where
-        S: IntoStorageKey,
-    {
-        let prefix = prefix.into_storage_key();
+    fn with_prefix(prefix: Vec<u8>) -> Self {
         let key_index_prefix = append(&prefix, b'i');
         let index_key_id = append(&prefix, b'k');
         let index_value_id = append(&prefix, b'v');
@@ -55,6 +113,19 @@ impl<K, V> UnorderedMap<K, V> {
             key_index_prefix,
             keys: Vector::new(index_key_id),
             values: Vector::new(index_value_id),
+            codec: PhantomData,
+        }
+    }
+
+    /// Creates another handle to this map, intended for read paths. Entries inserted or removed
+    /// through this map are not reflected in the length of the new handle. With the
+    /// `check-handle-aliasing` feature enabled, writing through both handles within a call panics.
+    pub fn duplicate_handle(&self) -> Self {
+        Self {
+            key_index_prefix: self.key_index_prefix.clone(),
+            keys: self.keys.duplicate_handle(),
+            values: self.values.duplicate_handle(),
+            codec: PhantomData,
         }
     }
 
@@ -72,18 +143,44 @@ impl<K, V> UnorderedMap<K, V> {
         append_slice(&self.key_index_prefix, raw_key)
     }
 
-    /// Returns an index of the given raw key.
-    fn get_index_raw(&self, key_raw: &[u8]) -> Option<u64> {
+    /// Returns the storage key mapping the given raw key to its index, and the index if the key
+    /// is in the map.
+    fn index_entry(&self, key_raw: &[u8]) -> (Vec<u8>, Option<u64>) {
         let index_lookup = self.raw_key_to_index_lookup(key_raw);
-        env::storage_read(&index_lookup).map(|raw_index| Self::deserialize_index(&raw_index))
+        let index =
+            env::storage_read(&index_lookup).map(|raw_index| Self::deserialize_index(&raw_index));
+        (index_lookup, index)
+    }
+
+    fn error(&self, error: SdkError, key_raw: &[u8]) -> CollectionError {
+        let prefix = &self.key_index_prefix[..self.key_index_prefix.len() - 1];
+        CollectionError::new(error, prefix, key_raw)
+    }
+
+    fn inconsistent(&self, key_raw: &[u8]) -> CollectionError {
+        self.error(SdkError::CollectionInconsistent { collection: "UnorderedMap" }, key_raw)
+    }
+
+    /// Reports an inconsistency of the vectors as an error of the entry at `key_raw`.
+    fn entry_error(&self, key_raw: &[u8]) -> impl Fn(CollectionError) -> CollectionError + '_ {
+        let key_raw = key_raw.to_vec();
+        move |err| self.error(err.into(), &key_raw)
     }
 
     /// Returns the serialized value corresponding to the serialized key.
-    fn get_raw(&self, key_raw: &[u8]) -> Option<Vec<u8>> {
-        self.get_index_raw(key_raw).map(|index| match self.values.get_raw(index) {
-            Some(x) => x,
-            None => env::panic_str(ERR_INCONSISTENT_STATE),
-        })
+    fn try_get_raw(&self, key_raw: &[u8]) -> Result<Option<Vec<u8>>, CollectionError> {
+        match self.index_entry(key_raw).1 {
+            Some(index) => self.try_value_at(key_raw, index).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the serialized value of the entry of `key_raw` at `index`.
+    fn try_value_at(&self, key_raw: &[u8], index: u64) -> Result<Vec<u8>, CollectionError> {
+        match self.values.try_get_raw(index).map_err(self.entry_error(key_raw))? {
+            Some(x) => Ok(x),
+            None => Err(self.inconsistent(key_raw)),
+        }
     }
 
     /// Inserts a serialized key-value pair into the map.
@@ -91,21 +188,41 @@ impl<K, V> UnorderedMap<K, V> {
     /// a serialized value. Note, the keys that have the same hash value are undistinguished by
     /// the implementation.
     pub fn insert_raw(&mut self, key_raw: &[u8], value_raw: &[u8]) -> Option<Vec<u8>> {
-        let index_lookup = self.raw_key_to_index_lookup(key_raw);
-        match env::storage_read(&index_lookup) {
-            Some(index_raw) => {
+        self.try_insert_raw(key_raw, value_raw).unwrap_or_else(|err| err.panic())
+    }
+
+    fn try_insert_raw(
+        &mut self,
+        key_raw: &[u8],
+        value_raw: &[u8],
+    ) -> Result<Option<Vec<u8>>, CollectionError> {
+        let (index_lookup, index) = self.index_entry(key_raw);
+        self.try_insert_at(key_raw, &index_lookup, index, value_raw)
+    }
+
+    /// Writes the value of `key_raw`, given the lookup of its index read by
+    /// [`index_entry`](Self::index_entry), so that the index is only read once.
+    fn try_insert_at(
+        &mut self,
+        key_raw: &[u8],
+        index_lookup: &[u8],
+        index: Option<u64>,
+        value_raw: &[u8],
+    ) -> Result<Option<Vec<u8>>, CollectionError> {
+        match index {
+            Some(index) => {
                 // The element already exists.
-                let index = Self::deserialize_index(&index_raw);
-                Some(self.values.replace_raw(index, value_raw))
+                let old_value_raw = self.values.try_replace_raw(index, value_raw);
+                old_value_raw.map(Some).map_err(self.entry_error(key_raw))
             }
             None => {
                 // The element does not exist yet.
                 let next_index = self.len();
                 let next_index_raw = Self::serialize_index(next_index);
-                env::storage_write(&index_lookup, &next_index_raw);
+                env::storage_write(index_lookup, &next_index_raw);
                 self.keys.push_raw(key_raw);
                 self.values.push_raw(value_raw);
-                None
+                Ok(None)
             }
         }
     }
@@ -113,35 +230,96 @@ impl<K, V> UnorderedMap<K, V> {
     /// Removes a serialized key from the map, returning the serialized value at the key if the key
     /// was previously in the map.
     pub fn remove_raw(&mut self, key_raw: &[u8]) -> Option<Vec<u8>> {
-        let index_lookup = self.raw_key_to_index_lookup(key_raw);
-        match env::storage_read(&index_lookup) {
-            Some(index_raw) => {
-                #[allow(clippy::branches_sharing_code)]
-                if self.len() == 1 {
-                    // If there is only one element then swap remove simply removes it without
-                    // swapping with the last element.
-                    env::storage_remove(&index_lookup);
-                } else {
-                    // If there is more than one element then swap remove swaps it with the last
-                    // element.
-                    let last_key_raw = match self.keys.get_raw(self.len() - 1) {
-                        Some(x) => x,
-                        None => env::panic_str(ERR_INCONSISTENT_STATE),
-                    };
-                    env::storage_remove(&index_lookup);
-                    // If the removed element was the last element from keys, then we don't need to
-                    // reinsert the lookup back.
-                    if last_key_raw != key_raw {
-                        let last_lookup_key = self.raw_key_to_index_lookup(&last_key_raw);
-                        env::storage_write(&last_lookup_key, &index_raw);
-                    }
-                }
-                let index = Self::deserialize_index(&index_raw);
-                self.keys.swap_remove_raw(index);
-                Some(self.values.swap_remove_raw(index))
+        self.try_remove_raw(key_raw).unwrap_or_else(|err| err.panic())
+    }
+
+    fn try_remove_raw(&mut self, key_raw: &[u8]) -> Result<Option<Vec<u8>>, CollectionError> {
+        match self.index_entry(key_raw) {
+            (index_lookup, Some(index)) => {
+                self.try_remove_at(key_raw, &index_lookup, index).map(Some)
+            }
+            (_, None) => Ok(None),
+        }
+    }
+
+    /// Removes the entry of `key_raw` at `index`, given the lookup of its index read by
+    /// [`index_entry`](Self::index_entry), so that the index is only read once.
+    fn try_remove_at(
+        &mut self,
+        key_raw: &[u8],
+        index_lookup: &[u8],
+        index: u64,
+    ) -> Result<Vec<u8>, CollectionError> {
+        #[allow(clippy::branches_sharing_code)]
+        if self.len() == 1 {
+            // If there is only one element then swap remove simply removes it without
+            // swapping with the last element.
+            env::storage_remove(index_lookup);
+        } else {
+            // If there is more than one element then swap remove swaps it with the last
+            // element.
+            let last_key_raw =
+                match self.keys.try_get_raw(self.len() - 1).map_err(self.entry_error(key_raw))? {
+                    Some(x) => x,
+                    None => return Err(self.inconsistent(key_raw)),
+                };
+            env::storage_remove(index_lookup);
+            // If the removed element was the last element from keys, then we don't need to
+            // reinsert the lookup back.
+            if last_key_raw != key_raw {
+                let last_lookup_key = self.raw_key_to_index_lookup(&last_key_raw);
+                env::storage_write(&last_lookup_key, &Self::serialize_index(index));
             }
-            None => None,
         }
+        let removed = self
+            .keys
+            .try_swap_remove_raw(index)
+            .and_then(|_| self.values.try_swap_remove_raw(index));
+        removed.map_err(self.entry_error(key_raw))
+    }
+
+    /// Moves the map to `new_prefix`, processing up to `batch` entries per call and keeping track
+    /// of the progress under `cursor_key`. Call this in consecutive function calls until
+    /// [`RelocateProgress::Done`] is returned. The map keeps reading from the old prefix until
+    /// all entries are copied, after which the old entries are removed.
+    ///
+    /// The map must not be modified while it is being moved. Panics if `batch` is 0.
+    pub fn relocate_to<S>(
+        &mut self,
+        new_prefix: S,
+        batch: u64,
+        cursor_key: &[u8],
+    ) -> RelocateProgress
+    where
+        S: IntoStorageKey,
+    {
+        relocate(self, new_prefix.into_storage_key(), batch, cursor_key)
+    }
+}
+
+impl<K, V, C> Relocate for UnorderedMap<K, V, C> {
+    fn prefix(&self) -> Vec<u8> {
+        self.key_index_prefix[..self.key_index_prefix.len() - 1].to_vec()
+    }
+
+    fn set_prefix(&mut self, prefix: &[u8]) {
+        self.key_index_prefix = append(prefix, b'i');
+        self.keys.set_prefix(&append(prefix, b'k'));
+        self.values.set_prefix(&append(prefix, b'v'));
+    }
+
+    fn entries_len(&self) -> u64 {
+        self.len()
+    }
+
+    fn entry_keys(&self, index: u64, prefix: &[u8]) -> Vec<Vec<u8>> {
+        let raw_key = self.keys.get_raw(index).unwrap_or_else(|| {
+            SdkError::CollectionInconsistent { collection: "UnorderedMap" }.panic()
+        });
+        let mut keys = vec![append_slice(&append(prefix, b'i'), &raw_key)];
+        keys.extend(self.keys.entry_keys(index, &append(prefix, b'k')));
+        keys.extend(self.values.entry_keys(index, &append(prefix, b'v')));
+        keys
     }
 }
 
@@ -149,47 +327,177 @@ impl<K, V> UnorderedMap<K, V>
 where
     K: BorshSerialize + BorshDeserialize,
     V: BorshSerialize + BorshDeserialize,
+{
+    /// Creates a map with the given `prefix` holding the entries of `map`.
+    ///
+    /// The entries are inserted in the order of their serialized keys, so that the storage layout
+    /// doesn't depend on the iteration order of the `HashMap`.
+    pub fn from_std<S>(prefix: S, map: HashMap<K, V>) -> Self
+    where
+        S: IntoStorageKey,
+    {
+        let mut entries: Vec<(Vec<u8>, V)> =
+            map.into_iter().map(|(key, value)| (Self::serialize_key(&key), value)).collect();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut result = Self::new(prefix);
+        for (key_raw, value) in entries {
+            result.insert_raw(&key_raw, &Self::serialize_value(&value));
+        }
+        result
+    }
+
+    /// Returns a view of values as a vector.
+    /// It's sometimes useful to have random access to the values.
+    pub fn values_as_vector(&self) -> &Vector<V> {
+        &self.values
+    }
+}
+
+impl<K, V, C> UnorderedMap<K, V, C>
+where
+    K: BorshSerialize + BorshDeserialize,
+    C: ValueCodec<V>,
 {
     fn serialize_key(key: &K) -> Vec<u8> {
         match key.try_to_vec() {
             Ok(x) => x,
-            Err(_) => env::panic_str(ERR_KEY_SERIALIZATION),
+            Err(_) => SdkError::Serialization { context: "key" }.panic(),
         }
     }
 
     fn deserialize_value(raw_value: &[u8]) -> V {
-        match V::try_from_slice(raw_value) {
-            Ok(x) => x,
-            Err(_) => env::panic_str(ERR_VALUE_DESERIALIZATION),
-        }
+        C::decode(raw_value)
+    }
+
+    fn try_serialize_key(&self, key: &K) -> Result<Vec<u8>, CollectionError> {
+        key.try_to_vec().map_err(|_| self.error(SdkError::Serialization { context: "key" }, &[]))
+    }
+
+    fn try_deserialize_value(
+        &self,
+        key_raw: &[u8],
+        value_raw: &[u8],
+    ) -> Result<V, CollectionError> {
+        C::try_decode(value_raw).map_err(|err| self.error(err, key_raw))
     }
 
     fn serialize_value(value: &V) -> Vec<u8> {
-        match value.try_to_vec() {
-            Ok(x) => x,
-            Err(_) => env::panic_str(ERR_VALUE_SERIALIZATION),
+        C::encode(value)
+    }
+
+    /// Copies the entries into a `HashMap`. Returns [`TooLarge`] without reading any entry if the
+    /// map has more than `max` entries.
+    pub fn to_std(&self, max: u64) -> Result<HashMap<K, V>, TooLarge>
+    where
+        K: Hash + Eq,
+    {
+        let len = self.len();
+        if len > max {
+            return Err(TooLarge { len, max });
         }
+        Ok(self.iter().collect())
+    }
+
+    /// Returns the number of bytes of storage inserting `key` with `value` adds when `key` isn't
+    /// in the map yet: the record mapping the key to its index, and the records of the key and
+    /// the value in their vectors, each with its storage key and the overhead of a storage record.
+    /// Replacing the value of a key only changes the storage by the difference in size of the
+    /// serialized values.
+    ///
+    /// Contracts charging their users for storage can require the cost before inserting, instead
+    /// of measuring [`env::storage_usage`] before and after.
+    pub fn storage_cost_of(&self, key: &K, value: &V) -> u64 {
+        let raw_key = Self::serialize_key(key);
+        let raw_value = Self::serialize_value(value);
+        record_storage_cost(self.key_index_prefix.len() + raw_key.len(), size_of::<u64>())
+            + self.keys.storage_cost_of_raw(&raw_key)
+            + self.values.storage_cost_of_raw(&raw_value)
     }
 
     /// Returns the value corresponding to the key.
     pub fn get(&self, key: &K) -> Option<V> {
-        self.get_raw(&Self::serialize_key(key)).map(|value_raw| Self::deserialize_value(&value_raw))
+        self.try_get(key).unwrap_or_else(|err| err.panic())
+    }
+
+    /// Returns the value corresponding to the key, or an error identifying the key if it can't be
+    /// serialized, the value can't be deserialized or the entry is missing from storage.
+    pub fn try_get(&self, key: &K) -> Result<Option<V>, CollectionError> {
+        let key_raw = self.try_serialize_key(key)?;
+        self.try_get_raw(&key_raw)?
+            .map(|value_raw| self.try_deserialize_value(&key_raw, &value_raw))
+            .transpose()
     }
 
     /// Removes a key from the map, returning the value at the key if the key was previously in the
     /// map.
+    ///
+    /// The value is read and deserialized before anything is written, so the map is left unchanged
+    /// if it can't be deserialized. Use [`try_remove`](Self::try_remove) to remove such entries.
     pub fn remove(&mut self, key: &K) -> Option<V> {
-        self.remove_raw(&Self::serialize_key(key))
-            .map(|value_raw| Self::deserialize_value(&value_raw))
+        let key_raw = Self::serialize_key(key);
+        let (index_lookup, index) = self.index_entry(&key_raw);
+        let index = index?;
+        let value = self
+            .try_value_at(&key_raw, index)
+            .and_then(|value_raw| self.try_deserialize_value(&key_raw, &value_raw))
+            .unwrap_or_else(|err| err.panic());
+        self.try_remove_at(&key_raw, &index_lookup, index).unwrap_or_else(|err| err.panic());
+        Some(value)
+    }
+
+    /// Removes a key from the map, returning the value at the key if the key was previously in the
+    /// map. The entry is removed even if its value can't be deserialized, so this can be used to
+    /// discard corrupted entries.
+    pub fn try_remove(&mut self, key: &K) -> Result<Option<V>, CollectionError> {
+        let key_raw = self.try_serialize_key(key)?;
+        self.try_remove_raw(&key_raw)?
+            .map(|value_raw| self.try_deserialize_value(&key_raw, &value_raw))
+            .transpose()
     }
 
     /// Inserts a key-value pair into the map.
     /// If the map did not have this key present, `None` is returned. Otherwise returns
     /// a value. Note, the keys that have the same hash value are undistinguished by
     /// the implementation.
+    ///
+    /// The key and the value are serialized and the previous value is deserialized before anything
+    /// is written, so the map is left unchanged if any of them fails.
     pub fn insert(&mut self, key: &K, value: &V) -> Option<V> {
-        self.insert_raw(&Self::serialize_key(key), &Self::serialize_value(value))
-            .map(|value_raw| Self::deserialize_value(&value_raw))
+        let key_raw = Self::serialize_key(key);
+        let value_raw =
+            C::try_encode(value).unwrap_or_else(|err| self.error(err, &key_raw).panic());
+        let (index_lookup, index) = self.index_entry(&key_raw);
+        let old_value = index
+            .map(|index| {
+                self.try_value_at(&key_raw, index)
+                    .and_then(|old_value_raw| self.try_deserialize_value(&key_raw, &old_value_raw))
+            })
+            .transpose()
+            .unwrap_or_else(|err| err.panic());
+        self.try_insert_at(&key_raw, &index_lookup, index, &value_raw)
+            .unwrap_or_else(|err| err.panic());
+        old_value
+    }
+
+    /// Inserts a key-value pair into the map, returning the previous value. Nothing is written if
+    /// the key or the value can't be serialized. The value is written even if the previous value
+    /// can't be deserialized.
+    pub fn try_insert(&mut self, key: &K, value: &V) -> Result<Option<V>, CollectionError> {
+        let key_raw = self.try_serialize_key(key)?;
+        let value_raw = C::try_encode(value).map_err(|err| self.error(err, &key_raw))?;
+        self.try_insert_raw(&key_raw, &value_raw)?
+            .map(|old_value_raw| self.try_deserialize_value(&key_raw, &old_value_raw))
+            .transpose()
+    }
+
+    /// Returns `true` if the mocked storage holds exactly the entries of this map and their
+    /// indices, with no stale entries. Only available in unit tests.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn is_storage_clean(&self) -> bool {
+        let indices = crate::collections::mocked_storage_suffixes(&self.key_index_prefix, None);
+        indices.len() as u64 == self.len()
+            && self.keys.is_storage_clean()
+            && self.values.is_storage_clean()
     }
 
     /// Clears the map, removing all elements.
@@ -214,12 +522,12 @@ where
 
     /// An iterator visiting all values. The iterator element type is `V`.
     pub fn values(&self) -> impl Iterator<Item = V> + '_ {
-        self.values.iter()
+        self.values.iter_raw().map(|raw_value| Self::deserialize_value(&raw_value))
     }
 
     /// Iterate over deserialized keys and values.
-    pub fn iter(&self) -> impl Iterator<Item = (K, V)> + '_ {
-        self.keys.iter().zip(self.values.iter())
+    pub fn iter(&self) -> Iter<K, V, C> {
+        Iter { keys: self.keys.iter(), values: self.values.iter_raw(), codec: PhantomData }
     }
 
     pub fn extend<IT: IntoIterator<Item = (K, V)>>(&mut self, iter: IT) {
@@ -228,16 +536,210 @@ where
         }
     }
 
+    /// Runs `f` with a [`Coalesced`] view of the map, which keeps the writes of `f` in memory and
+    /// writes each modified key once when `f` returns. Keys inserted by `f` are appended to the
+    /// map in the order of their serialized keys.
+    pub fn coalesced<'a, R, F>(&'a mut self, f: F) -> R
+    where
+        V: Clone,
+        F: FnOnce(&mut Coalesced<'a, Self>) -> R,
+    {
+        f(&mut Coalesced::new(self))
+    }
+
+    /// Counts the entries matching `pred`, scanning at most `max_scan` entries starting at index
+    /// `from_index`, so that a view counting over a large map can be split over several calls.
+    /// Entries before `from_index` aren't read.
+    ///
+    /// Removing entries moves the last entry of the map into the removed slot, so an entry can be
+    /// missed or counted twice if the map changes between the calls.
+    ///
+    /// # Examples
+    /// ```
+    /// use near_sdk::collections::{CountResult, UnorderedMap};
+    ///
+    /// let mut balances: UnorderedMap<String, u128> = UnorderedMap::new(b"b");
+    /// balances.insert(&"alice".to_string(), &10);
+    /// balances.insert(&"bob".to_string(), &0);
+    /// balances.insert(&"carol".to_string(), &5);
+    ///
+    /// let first = balances.count_where(0, 2, |_, balance| *balance > 0);
+    /// assert_eq!(first, CountResult { counted: 1, scanned: 2, complete: false });
+    /// let rest = balances.count_where(first.scanned, 2, |_, balance| *balance > 0);
+    /// assert_eq!(rest, CountResult { counted: 1, scanned: 1, complete: true });
+    /// ```
+    pub fn count_where<F>(&self, from_index: u64, max_scan: u64, mut pred: F) -> CountResult
+    where
+        F: FnMut(&K, &V) -> bool,
+    {
+        let len = self.len();
+        let start = from_index.min(len);
+        let end = len.min(start.saturating_add(max_scan));
+        let counted = self
+            .iter()
+            .skip(start as usize)
+            .take((end - start) as usize)
+            .filter(|(key, value)| pred(key, value))
+            .count() as u64;
+        CountResult { counted, scanned: end - start, complete: end == len }
+    }
+
     /// Returns a view of keys as a vector.
     /// It's sometimes useful to have random access to the keys.
     pub fn keys_as_vector(&self) -> &Vector<K> {
         &self.keys
     }
 
-    /// Returns a view of values as a vector.
-    /// It's sometimes useful to have random access to the values.
-    pub fn values_as_vector(&self) -> &Vector<V> {
-        &self.values
+    /// Converts the values of the map to `V2` with `f`, rewriting up to `limit` values in place
+    /// per call, so that a large map can be migrated over several transactions. Returns the number
+    /// of values left to convert: once it returns 0, the map must be read as an
+    /// `UnorderedMap<K, V2, C>`, which has the same layout. The keys and the index of the map are
+    /// not rewritten.
+    ///
+    /// The index of the next value to convert is stored under the prefix of the values vector,
+    /// which no value uses, and removed when the last value is converted. Until then, reading the
+    /// values fails for the converted ones, and the map must not be modified, as inserting and
+    /// removing entries moves values across the cursor.
+    ///
+    /// # Examples
+    /// ```
+    /// use near_sdk::borsh::{BorshDeserialize, BorshSerialize};
+    /// use near_sdk::collections::UnorderedMap;
+    ///
+    /// let mut map: UnorderedMap<String, u32> = UnorderedMap::new(b"m");
+    /// map.insert(&"a".to_string(), &1);
+    /// map.insert(&"b".to_string(), &2);
+    /// assert_eq!(map.migrate_values(1, |_, value| u64::from(value) * 10), 1);
+    /// assert_eq!(map.migrate_values(1, |_, value| u64::from(value) * 10), 0);
+    ///
+    /// // The contract state now holds the map with the new value type.
+    /// let state = map.try_to_vec().unwrap();
+    /// let map = UnorderedMap::<String, u64>::try_from_slice(&state).unwrap();
+    /// assert_eq!(map.get(&"b".to_string()), Some(20));
+    /// ```
+    pub fn migrate_values<V2, F>(&mut self, limit: u64, mut f: F) -> u64
+    where
+        C: ValueCodec<V2>,
+        F: FnMut(K, V) -> V2,
+    {
+        let cursor_key = Relocate::prefix(&self.values);
+        let start = env::storage_read(&cursor_key).map_or(0, |raw| Self::deserialize_index(&raw));
+        let len = self.len();
+        let end = len.min(start.saturating_add(limit));
+        for index in start..end {
+            let key = self.keys.get(index).unwrap_or_else(|| self.inconsistent(&[]).panic());
+            let raw_value = self.values.get_raw(index).unwrap_or_else(|| {
+                self.inconsistent(&self.keys.get_raw(index).unwrap_or_default()).panic()
+            });
+            let value = Self::deserialize_value(&raw_value);
+            let raw_value = <C as ValueCodec<V2>>::encode(&f(key, value));
+            self.values.try_replace_raw(index, &raw_value).unwrap_or_else(|err| err.panic());
+        }
+        if end == len {
+            env::storage_remove(&cursor_key);
+        } else {
+            env::storage_write(&cursor_key, &Self::serialize_index(end));
+        }
+        len - end
+    }
+}
+
+impl<K, V, C> CoalesceTarget for UnorderedMap<K, V, C>
+where
+    K: BorshSerialize + BorshDeserialize,
+    C: ValueCodec<V>,
+{
+    type Key = K;
+    type Value = V;
+    type Codec = C;
+
+    fn read(&self, key: &K) -> Option<V> {
+        self.get(key)
+    }
+
+    fn write_raw(&mut self, key_raw: &[u8], value_raw: &[u8]) {
+        self.insert_raw(key_raw, value_raw);
+    }
+
+    fn remove_raw(&mut self, key_raw: &[u8]) {
+        UnorderedMap::remove_raw(self, key_raw);
+    }
+}
+
+impl<'a, K, V, C> IntoIterator for &'a UnorderedMap<K, V, C>
+where
+    K: BorshSerialize + BorshDeserialize,
+    C: ValueCodec<V>,
+{
+    type Item = (K, V);
+    type IntoIter = Iter<'a, K, V, C>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over the deserialized keys and values of an [`UnorderedMap`]. Skipped entries,
+/// such as with [`Iterator::nth`] or [`Iterator::skip`], aren't read from storage.
+pub struct Iter<'a, K, V, C = BorshCodec> {
+    keys: vector::Iter<'a, K>,
+    values: RawIter<'a, V>,
+    codec: PhantomData<C>,
+}
+
+impl<'a, K, V, C> Iterator for Iter<'a, K, V, C>
+where
+    K: BorshDeserialize,
+    C: ValueCodec<V>,
+{
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        <Self as Iterator>::nth(self, 0)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.keys.size_hint()
+    }
+
+    fn count(self) -> usize {
+        self.keys.count()
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        let key = self.keys.nth(n)?;
+        let raw_value = self.values.nth(n)?;
+        Some((key, C::decode(&raw_value)))
+    }
+}
+
+impl<'a, K, V, C> ExactSizeIterator for Iter<'a, K, V, C>
+where
+    K: BorshDeserialize,
+    C: ValueCodec<V>,
+{
+}
+
+impl<'a, K, V, C> FusedIterator for Iter<'a, K, V, C>
+where
+    K: BorshDeserialize,
+    C: ValueCodec<V>,
+{
+}
+
+impl<'a, K, V, C> DoubleEndedIterator for Iter<'a, K, V, C>
+where
+    K: BorshDeserialize,
+    C: ValueCodec<V>,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        <Self as DoubleEndedIterator>::nth_back(self, 0)
+    }
+
+    fn nth_back(&mut self, n: usize) -> Option<Self::Item> {
+        let key = self.keys.nth_back(n)?;
+        let raw_value = self.values.nth_back(n)?;
+        Some((key, C::decode(&raw_value)))
     }
 }
 
@@ -258,12 +760,35 @@ where
 #[cfg(not(target_arch = "wasm32"))]
 #[cfg(test)]
 mod tests {
-    use crate::collections::UnorderedMap;
+    use crate::collections::{
+        assert_panics_without_writes, storage_ops, CountResult, Fragile, TooLarge, UnorderedMap,
+    };
+    use crate::env;
+    use crate::errors::CollectionErrorKind;
+    use borsh::{BorshDeserialize, BorshSerialize};
     use rand::seq::SliceRandom;
     use rand::{Rng, SeedableRng};
     use std::collections::{HashMap, HashSet};
     use std::iter::FromIterator;
 
+    #[test]
+    fn reads_each_key_once() {
+        let mut map = UnorderedMap::new(b"m");
+        // The index of a new key is read, then the index, the key and the value are written.
+        assert_eq!(storage_ops(|| assert_eq!(map.insert(&1u8, &10u8), None)), (1, 3, 0));
+        map.insert(&2, &20);
+        map.insert(&3, &30);
+        // The index and the previous value are read, then the value is written.
+        assert_eq!(storage_ops(|| assert_eq!(map.insert(&1, &11), Some(10))), (2, 1, 0));
+        assert_eq!(storage_ops(|| assert_eq!(map.try_insert(&1, &12), Ok(Some(11)))), (1, 1, 0));
+        // The index, the value and the last key are read, then the last entry is moved into the
+        // removed one.
+        assert_eq!(storage_ops(|| assert_eq!(map.remove(&1), Some(12))), (3, 3, 0));
+        // Removing the last entry only reads its index and its key.
+        assert_eq!(storage_ops(|| assert_eq!(map.try_remove(&2), Ok(Some(20)))), (2, 0, 0));
+        assert_eq!(storage_ops(|| assert_eq!(map.remove(&1), None)), (1, 0, 0));
+    }
+
     #[test]
     pub fn test_insert_one() {
         let mut map = UnorderedMap::new(b"m");
@@ -378,6 +903,20 @@ mod tests {
         assert_eq!(actual, key_to_value);
     }
 
+    #[test]
+    pub fn test_std_conversion() {
+        let std_map: HashMap<u64, String> = (0..10).map(|i| (i, i.to_string())).collect();
+        let map = UnorderedMap::from_std(b"m", std_map.clone());
+        assert_eq!(map.len(), 10);
+        assert_eq!(map.get(&3), Some("3".to_string()));
+        assert_eq!(map.to_std(10), Ok(std_map));
+        assert_eq!(map.to_std(9), Err(TooLarge { len: 10, max: 9 }));
+
+        // The layout doesn't depend on the iteration order of the `HashMap`.
+        let keys: Vec<u64> = map.keys().collect();
+        assert_eq!(keys, (0..10).collect::<Vec<_>>());
+    }
+
     #[test]
     pub fn test_clear() {
         let mut map = UnorderedMap::new(b"m");
@@ -394,6 +933,26 @@ mod tests {
         }
     }
 
+    #[test]
+    pub fn test_clear_reuse() {
+        let before = crate::test_utils::storage_snapshot();
+        let mut map = UnorderedMap::new(b"m");
+        for i in 0..10u64 {
+            map.insert(&i, &i);
+        }
+        map.remove(&3);
+        map.clear();
+        assert!(map.is_storage_clean());
+        assert_eq!(crate::test_utils::storage_snapshot(), before);
+
+        map.insert(&3, &30);
+        map.insert(&20, &200);
+        assert!(map.is_storage_clean());
+        assert_eq!(map.get(&3), Some(30));
+        assert_eq!(map.remove(&20), Some(200));
+        assert_eq!(map.to_vec(), vec![(3, 30)]);
+    }
+
     #[test]
     pub fn test_keys_values() {
         let mut map = UnorderedMap::new(b"m");
@@ -478,4 +1037,224 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn try_accessors_report_corrupted_value() {
+        let mut map = UnorderedMap::new(b"m");
+        map.insert(&1u64, &7u64);
+        map.insert(&2u64, &8u64);
+        // The value of the first entry is stored at index 0 of the values vector.
+        env::storage_write(&[b"mv".as_ref(), &0u64.to_le_bytes()].concat(), &[0xff]);
+
+        let err = map.try_get(&1).unwrap_err();
+        assert_eq!(err.kind(), CollectionErrorKind::DeserializationFailed);
+        assert_eq!(err.prefix(), b"m");
+        assert_eq!(err.key(), 1u64.to_le_bytes());
+        assert_eq!(map.try_get(&2), Ok(Some(8)));
+
+        // The corrupted entry is removed anyway.
+        assert_eq!(map.try_remove(&1).unwrap_err(), err);
+        assert_eq!(map.try_get(&1), Ok(None));
+        assert_eq!(map.len(), 1);
+
+        env::storage_remove(&[b"mv".as_ref(), &0u64.to_le_bytes()].concat());
+        let err = map.try_get(&2).unwrap_err();
+        assert_eq!(err.kind(), CollectionErrorKind::InconsistentState);
+        assert_eq!(err.prefix(), b"m");
+        assert_eq!(err.key(), 2u64.to_le_bytes());
+    }
+
+    #[test]
+    fn storage_cost_of() {
+        let mut map = UnorderedMap::new(b"m");
+        for len in [0, 1, 10, 100, 1000].iter() {
+            let key = "k".repeat(*len);
+            let value = vec![7u8; len * 2];
+            let cost = map.storage_cost_of(&key, &value);
+            let usage = env::storage_usage();
+            map.insert(&key, &value);
+            assert_eq!(env::storage_usage() - usage, cost);
+        }
+    }
+
+    #[test]
+    fn migrate_values_in_chunks() {
+        let mut map: UnorderedMap<u64, u32> = UnorderedMap::new(b"m");
+        for key in 0..25u64 {
+            map.insert(&key, &(key as u32));
+        }
+        let index_before = crate::collections::mocked_storage_suffixes(b"mi", None);
+        let keys_before: Vec<u64> = map.keys().collect();
+
+        let mut converted = Vec::new();
+        let mut remaining = Vec::new();
+        loop {
+            let left = map.migrate_values(7, |key, value| {
+                converted.push(key);
+                (u64::from(value), key.to_string())
+            });
+            remaining.push(left);
+            if left == 0 {
+                break;
+            }
+        }
+        assert_eq!(remaining, [18, 11, 4, 0]);
+        // Every value was converted exactly once.
+        assert_eq!(converted, keys_before);
+        // The cursor was removed.
+        assert!(env::storage_read(b"mv").is_none());
+
+        let map: UnorderedMap<u64, (u64, String)> = borsh::BorshDeserialize::try_from_slice(
+            &borsh::BorshSerialize::try_to_vec(&map).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(map.keys().collect::<Vec<_>>(), keys_before);
+        assert_eq!(crate::collections::mocked_storage_suffixes(b"mi", None), index_before);
+        for key in 0..25u64 {
+            assert_eq!(map.get(&key), Some((key, key.to_string())));
+        }
+    }
+
+    #[test]
+    fn migrate_values_cursor_in_storage() {
+        let mut map: UnorderedMap<u8, u8> = UnorderedMap::new(b"m");
+        for key in 0..4u8 {
+            map.insert(&key, &key);
+        }
+        assert_eq!(map.migrate_values(3, |_, value| u16::from(value) + 100), 1);
+        assert_eq!(env::storage_read(b"mv"), Some(3u64.to_le_bytes().to_vec()));
+        // The next call resumes from the stored cursor.
+        let state = borsh::BorshSerialize::try_to_vec(&map).unwrap();
+        drop(map);
+        let mut map: UnorderedMap<u8, u8> =
+            borsh::BorshDeserialize::try_from_slice(&state).unwrap();
+        let mut converted = Vec::new();
+        assert_eq!(
+            map.migrate_values(3, |key, value| {
+                converted.push(key);
+                u16::from(value) + 100
+            }),
+            0
+        );
+        assert_eq!(converted, [3]);
+    }
+
+    #[test]
+    fn test_failures_write_nothing() {
+        let mut map = UnorderedMap::new(b"m");
+        map.insert(&1u64, &Fragile { serializable: true });
+        map.insert(&2u64, &Fragile { serializable: true });
+
+        // The value can't be serialized.
+        assert_panics_without_writes(|| map.insert(&3, &Fragile { serializable: false }));
+        // The previous value can't be deserialized.
+        assert_panics_without_writes(|| map.insert(&1, &Fragile { serializable: true }));
+        assert_panics_without_writes(|| map.remove(&1));
+        assert_eq!(map.len(), 2);
+        assert!(map.is_storage_clean());
+
+        // The fallible variants still replace and remove undecodable entries.
+        assert!(map.try_remove(&1).is_err());
+        assert_eq!(map.len(), 1);
+        assert!(map.remove(&1).is_none());
+    }
+
+    thread_local! {
+        static DECODED: std::cell::Cell<usize> = std::cell::Cell::new(0);
+    }
+
+    /// A value which counts how many times it is deserialized.
+    #[derive(BorshSerialize, Debug, PartialEq)]
+    struct Counted(u64);
+
+    impl BorshDeserialize for Counted {
+        fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
+            DECODED.with(|decoded| decoded.set(decoded.get() + 1));
+            u64::deserialize(buf).map(Counted)
+        }
+    }
+
+    fn collect<I: IntoIterator>(collection: I) -> Vec<I::Item> {
+        collection.into_iter().collect()
+    }
+
+    #[test]
+    pub fn test_into_iter() {
+        let mut map = UnorderedMap::new(b"m");
+        map.extend((0u64..5).map(|i| (i, i * 10)));
+        assert_eq!(collect(&map), map.to_vec());
+
+        let mut sum = 0;
+        for (key, value) in &map {
+            sum += key + value;
+        }
+        assert_eq!(sum, 110);
+        assert_eq!((&map).into_iter().rev().next(), Some((4, 40)));
+    }
+
+    #[test]
+    pub fn test_iter_skip_does_not_deserialize() {
+        let mut map = UnorderedMap::new(b"m");
+        map.extend((0u64..10).map(|i| (i, Counted(i))));
+        DECODED.with(|decoded| decoded.set(0));
+
+        let mut iter = map.iter();
+        assert_eq!(iter.size_hint(), (10, Some(10)));
+        assert_eq!(iter.nth(3), Some((3, Counted(3))));
+        assert_eq!(iter.len(), 6);
+        assert_eq!(DECODED.with(|decoded| decoded.get()), 1);
+
+        let rest: Vec<_> = map.iter().skip(8).collect();
+        assert_eq!(rest, [(8, Counted(8)), (9, Counted(9))]);
+        assert_eq!(map.iter().count(), 10);
+        assert_eq!(DECODED.with(|decoded| decoded.get()), 3);
+    }
+
+    #[test]
+    pub fn test_count_where_resumes() {
+        let mut map = UnorderedMap::new(b"m");
+        map.extend((0u64..10).map(|i| (i, i * 10)));
+        let is_large = |_: &u64, value: &u64| *value >= 35;
+
+        let mut from_index = 0;
+        let mut counted = 0;
+        let mut calls = 0;
+        loop {
+            let result = map.count_where(from_index, 3, is_large);
+            calls += 1;
+            counted += result.counted;
+            from_index += result.scanned;
+            if result.complete {
+                break;
+            }
+            assert_eq!(result.scanned, 3);
+        }
+        assert_eq!(counted, 6);
+        assert_eq!(calls, 4);
+        assert_eq!(from_index, 10);
+
+        assert_eq!(
+            map.count_where(0, u64::MAX, is_large),
+            CountResult { counted: 6, scanned: 10, complete: true }
+        );
+        assert_eq!(
+            map.count_where(4, 0, is_large),
+            CountResult { counted: 0, scanned: 0, complete: false }
+        );
+        assert_eq!(
+            map.count_where(20, 5, is_large),
+            CountResult { counted: 0, scanned: 0, complete: true }
+        );
+    }
+
+    #[test]
+    pub fn test_count_where_reads_only_scanned_values() {
+        let mut map = UnorderedMap::new(b"m");
+        map.extend((0u64..10).map(|i| (i, Counted(i))));
+        DECODED.with(|decoded| decoded.set(0));
+
+        let result = map.count_where(6, 2, |key, _| key % 2 == 0);
+        assert_eq!(result, CountResult { counted: 1, scanned: 2, complete: false });
+        assert_eq!(DECODED.with(|decoded| decoded.get()), 2);
+    }
 }
@@ -0,0 +1,234 @@
+//! A map from keys to sets of values, for one-to-many relationships such as the tokens owned by
+//! each account.
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::collections::{append, append_slice, UnorderedMap, UnorderedSet};
+use crate::environment::{nested_scope, register_prefix};
+use crate::errors::SdkError;
+use crate::{env, IntoStorageKey};
+
+/// An iterable map from each key to an iterable set of values, stored on the trie.
+///
+/// The values of each key are kept in an [`UnorderedSet`] whose prefix is derived from the
+/// prefix of the multimap and the hash of the serialized key, so the sets of different keys never
+/// share storage keys. A key is removed from the map along with its last value.
+///
+/// # Examples
+/// ```
+/// use near_sdk::collections::UnorderedMultimap;
+///
+/// let mut tokens_per_owner = UnorderedMultimap::new(b"t");
+/// tokens_per_owner.insert(&"alice".to_string(), &1u64);
+/// tokens_per_owner.insert(&"alice".to_string(), &2);
+/// tokens_per_owner.insert(&"bob".to_string(), &3);
+///
+/// assert_eq!(tokens_per_owner.len_of(&"alice".to_string()), 2);
+/// assert_eq!(tokens_per_owner.get(&"bob".to_string()).collect::<Vec<_>>(), [3]);
+/// assert_eq!(tokens_per_owner.len(), 3);
+///
+/// assert_eq!(tokens_per_owner.remove_all(&"alice".to_string()), 2);
+/// assert_eq!(tokens_per_owner.len(), 1);
+/// ```
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct UnorderedMultimap<K, V> {
+    prefix: Vec<u8>,
+    sets: UnorderedMap<K, UnorderedSet<V>>,
+    len: u64,
+}
+
+impl<K, V> UnorderedMultimap<K, V> {
+    /// Create new multimap with zero values. Use `prefix` as a unique identifier.
+    pub fn new<S>(prefix: S) -> Self
+    where
+        S: IntoStorageKey,
+    {
+        let prefix = prefix.into_storage_key();
+        let _scope = register_prefix::<Self>(&prefix);
+        Self { sets: UnorderedMap::new(append(&prefix, b'k')), prefix, len: 0 }
+    }
+
+    /// Returns the number of values of all keys.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns `true` if the multimap contains no values.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of keys with at least one value.
+    pub fn keys_len(&self) -> u64 {
+        self.sets.len()
+    }
+}
+
+impl<K, V> UnorderedMultimap<K, V>
+where
+    K: BorshSerialize + BorshDeserialize,
+    V: BorshSerialize + BorshDeserialize,
+{
+    fn set_prefix(&self, key: &K) -> Vec<u8> {
+        let raw_key =
+            key.try_to_vec().unwrap_or_else(|_| SdkError::Serialization { context: "key" }.panic());
+        append_slice(&append(&self.prefix, b's'), &env::sha256(&raw_key))
+    }
+
+    /// Returns the number of values of `key`.
+    pub fn len_of(&self, key: &K) -> u64 {
+        self.sets.get(key).map_or(0, |set| set.len())
+    }
+
+    /// Returns `true` if `key` has at least one value.
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.sets.get(key).is_some()
+    }
+
+    /// Returns `true` if `value` is one of the values of `key`.
+    pub fn contains(&self, key: &K, value: &V) -> bool {
+        self.sets.get(key).map_or(false, |set| set.contains(value))
+    }
+
+    /// Iterates over the values of `key`.
+    pub fn get(&self, key: &K) -> impl Iterator<Item = V> {
+        self.sets.get(key).into_iter().flat_map(|set| {
+            (0..set.len()).map(move |index| {
+                set.as_vector().get(index).unwrap_or_else(|| {
+                    SdkError::CollectionInconsistent { collection: "UnorderedMultimap" }.panic()
+                })
+            })
+        })
+    }
+
+    /// Iterates over the keys with at least one value.
+    pub fn keys(&self) -> impl Iterator<Item = K> + '_ {
+        self.sets.keys()
+    }
+
+    /// Adds `value` to the values of `key`. Returns `false` if it was already one of them.
+    pub fn insert(&mut self, key: &K, value: &V) -> bool {
+        let _scope = nested_scope();
+        let mut set = self.sets.get(key).unwrap_or_else(|| UnorderedSet::new(self.set_prefix(key)));
+        if !set.insert(value) {
+            return false;
+        }
+        self.sets.insert(key, &set);
+        self.len += 1;
+        true
+    }
+
+    /// Removes `value` from the values of `key`, removing `key` if it was its last value. Returns
+    /// `false` if `value` wasn't one of the values of `key`.
+    pub fn remove(&mut self, key: &K, value: &V) -> bool {
+        let mut set = match self.sets.get(key) {
+            Some(set) => set,
+            None => return false,
+        };
+        if !set.remove(value) {
+            return false;
+        }
+        if set.is_empty() {
+            self.sets.remove(key);
+        } else {
+            self.sets.insert(key, &set);
+        }
+        self.len -= 1;
+        true
+    }
+
+    /// Removes `key` and all of its values. Returns the number of values removed.
+    pub fn remove_all(&mut self, key: &K) -> u64 {
+        let mut set = match self.sets.remove(key) {
+            Some(set) => set,
+            None => return 0,
+        };
+        let removed = set.len();
+        set.clear();
+        self.len -= removed;
+        removed
+    }
+}
+
+impl<K, V> std::fmt::Debug for UnorderedMultimap<K, V>
+where
+    K: std::fmt::Debug + BorshSerialize + BorshDeserialize,
+    V: std::fmt::Debug + BorshSerialize + BorshDeserialize,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UnorderedMultimap")
+            .field("prefix", &self.prefix)
+            .field("len", &self.len)
+            .field("keys_len", &self.keys_len())
+            .finish()
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use super::UnorderedMultimap;
+    use crate::collections::mocked_storage_suffixes;
+    use std::collections::HashSet;
+
+    #[test]
+    fn insert_remove() {
+        let mut map = UnorderedMultimap::new(b"m");
+        assert!(map.insert(&1u8, &10u64));
+        assert!(map.insert(&1, &11));
+        assert!(!map.insert(&1, &11));
+        assert!(map.insert(&2, &20));
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.keys_len(), 2);
+        assert_eq!(map.len_of(&1), 2);
+        assert!(map.contains(&1, &11));
+        assert!(!map.contains(&2, &11));
+        assert_eq!(map.get(&1).collect::<HashSet<_>>(), [10, 11].iter().copied().collect());
+
+        assert!(!map.remove(&1, &20));
+        assert!(map.remove(&1, &10));
+        assert!(map.remove(&1, &11));
+        assert!(!map.contains_key(&1));
+        assert_eq!(map.get(&1).count(), 0);
+        assert_eq!(map.keys().collect::<Vec<_>>(), [2]);
+        assert_eq!(map.len(), 1);
+    }
+
+    #[test]
+    fn remove_all_leaves_no_keys() {
+        let mut map = UnorderedMultimap::new(b"m");
+        for value in 0u64..20 {
+            map.insert(&"alice".to_string(), &value);
+        }
+        map.insert(&"bob".to_string(), &0);
+        map.remove(&"bob".to_string(), &0);
+        assert_eq!(map.remove_all(&"alice".to_string()), 20);
+        assert_eq!(map.remove_all(&"alice".to_string()), 0);
+        assert!(map.is_empty());
+        assert!(mocked_storage_suffixes(b"m", None).is_empty());
+    }
+
+    #[test]
+    fn keys_do_not_collide() {
+        let mut map = UnorderedMultimap::new(b"m");
+        // With the serialized keys used directly as prefixes, the sets of these keys would share
+        // a common prefix.
+        let keys = ["a".to_string(), "ai".to_string(), "a\u{0}".to_string()];
+        for (i, key) in keys.iter().enumerate() {
+            for value in 0..=i as u64 {
+                map.insert(key, &value);
+            }
+        }
+        assert_ne!(map.set_prefix(&keys[0]), map.set_prefix(&keys[1]));
+        for (i, key) in keys.iter().enumerate() {
+            assert_eq!(map.len_of(key), i as u64 + 1);
+            let mut values: Vec<u64> = map.get(key).collect();
+            values.sort_unstable();
+            assert_eq!(values, (0..=i as u64).collect::<Vec<_>>());
+        }
+
+        map.remove_all(&keys[1]);
+        assert_eq!(map.len_of(&keys[0]), 1);
+        assert_eq!(map.len_of(&keys[2]), 3);
+        assert_eq!(map.len(), 4);
+    }
+}
@@ -1,13 +1,17 @@
 //! A set implemented on a trie. Unlike `std::collections::HashSet` the elements in this set are not
 //! hashed but are instead serialized.
-use crate::collections::{append, append_slice, Vector};
+use crate::collections::relocate::{relocate, Relocate, RelocateProgress};
+use crate::collections::{
+    append, append_slice, record_storage_cost, vector, ElementsPage, TooLarge, Vector,
+};
+use crate::environment::register_prefix;
+use crate::errors::SdkError;
 use crate::{env, IntoStorageKey};
 use borsh::{BorshDeserialize, BorshSerialize};
+use std::collections::HashSet;
+use std::hash::Hash;
 use std::mem::size_of;
 
-const ERR_INCONSISTENT_STATE: &str = "The collection is an inconsistent state. Did previous smart contract execution terminate unexpectedly?";
-const ERR_ELEMENT_SERIALIZATION: &str = "Cannot serialize element with Borsh";
-
 /// An iterable implementation of a set that stores its content directly on the trie.
 #[derive(BorshSerialize, BorshDeserialize)]
 pub struct UnorderedSet<T> {
@@ -32,12 +36,22 @@ impl<T> UnorderedSet<T> {
         S: IntoStorageKey,
     {
         let prefix = prefix.into_storage_key();
+        let _scope = register_prefix::<Self>(&prefix);
         let element_index_prefix = append(&prefix, b'i');
         let elements_prefix = append(&prefix, b'e');
 
         Self { element_index_prefix, elements: Vector::new(elements_prefix) }
     }
 
+    /// Creates another handle to this set, intended for read paths. With the
+    /// `check-handle-aliasing` feature enabled, writing through both handles within a call panics.
+    pub fn duplicate_handle(&self) -> Self {
+        Self {
+            element_index_prefix: self.element_index_prefix.clone(),
+            elements: self.elements.duplicate_handle(),
+        }
+    }
+
     fn serialize_index(index: u64) -> [u8; size_of::<u64>()] {
         index.to_le_bytes()
     }
@@ -91,7 +105,9 @@ impl<T> UnorderedSet<T> {
                     // element.
                     let last_element_raw = match self.elements.get_raw(self.len() - 1) {
                         Some(x) => x,
-                        None => env::panic_str(ERR_INCONSISTENT_STATE),
+                        None => {
+                            SdkError::CollectionInconsistent { collection: "UnorderedSet" }.panic()
+                        }
                     };
                     env::storage_remove(&index_lookup);
                     // If the removed element was the last element from keys, then we don't need to
@@ -109,6 +125,48 @@ impl<T> UnorderedSet<T> {
             None => false,
         }
     }
+
+    /// Moves the set to `new_prefix`, processing up to `batch` elements per call and keeping track
+    /// of the progress under `cursor_key`. Call this in consecutive function calls until
+    /// [`RelocateProgress::Done`] is returned. The set keeps reading from the old prefix until
+    /// all elements are copied, after which the old entries are removed.
+    ///
+    /// The set must not be modified while it is being moved. Panics if `batch` is 0.
+    pub fn relocate_to<S>(
+        &mut self,
+        new_prefix: S,
+        batch: u64,
+        cursor_key: &[u8],
+    ) -> RelocateProgress
+    where
+        S: IntoStorageKey,
+    {
+        relocate(self, new_prefix.into_storage_key(), batch, cursor_key)
+    }
+}
+
+impl<T> Relocate for UnorderedSet<T> {
+    fn prefix(&self) -> Vec<u8> {
+        self.element_index_prefix[..self.element_index_prefix.len() - 1].to_vec()
+    }
+
+    fn set_prefix(&mut self, prefix: &[u8]) {
+        self.element_index_prefix = append(prefix, b'i');
+        self.elements.set_prefix(&append(prefix, b'e'));
+    }
+
+    fn entries_len(&self) -> u64 {
+        self.len()
+    }
+
+    fn entry_keys(&self, index: u64, prefix: &[u8]) -> Vec<Vec<u8>> {
+        let raw_element = self.elements.get_raw(index).unwrap_or_else(|| {
+            SdkError::CollectionInconsistent { collection: "UnorderedSet" }.panic()
+        });
+        let mut keys = vec![append_slice(&append(prefix, b'i'), &raw_element)];
+        keys.extend(self.elements.entry_keys(index, &append(prefix, b'e')));
+        keys
+    }
 }
 
 impl<T> UnorderedSet<T>
@@ -118,8 +176,38 @@ where
     fn serialize_element(element: &T) -> Vec<u8> {
         match element.try_to_vec() {
             Ok(x) => x,
-            Err(_) => env::panic_str(ERR_ELEMENT_SERIALIZATION),
+            Err(_) => SdkError::Serialization { context: "set element" }.panic(),
+        }
+    }
+
+    /// Creates a set with the given `prefix` holding the elements of `set`.
+    ///
+    /// The elements are inserted in the order of their serialized form, so that the storage
+    /// layout doesn't depend on the iteration order of the `HashSet`.
+    pub fn from_std<S>(prefix: S, set: HashSet<T>) -> Self
+    where
+        S: IntoStorageKey,
+    {
+        let mut elements: Vec<Vec<u8>> = set.iter().map(Self::serialize_element).collect();
+        elements.sort();
+        let mut result = Self::new(prefix);
+        for element_raw in elements {
+            result.insert_raw(&element_raw);
+        }
+        result
+    }
+
+    /// Copies the elements into a `HashSet`. Returns [`TooLarge`] without reading any element if
+    /// the set has more than `max` elements.
+    pub fn to_std(&self, max: u64) -> Result<HashSet<T>, TooLarge>
+    where
+        T: Hash + Eq,
+    {
+        let len = self.len();
+        if len > max {
+            return Err(TooLarge { len, max });
         }
+        Ok(self.iter().collect())
     }
 
     /// Returns true if the set contains an element.
@@ -132,6 +220,18 @@ where
         self.remove_raw(&Self::serialize_element(element))
     }
 
+    /// Returns the number of bytes of storage inserting `element` adds when it isn't in the set
+    /// yet: the record mapping the element to its index and the record of the element in its
+    /// vector, each with its storage key and the overhead of a storage record.
+    ///
+    /// Contracts charging their users for storage can require the cost before inserting, instead
+    /// of measuring [`env::storage_usage`] before and after.
+    pub fn storage_cost_of(&self, element: &T) -> u64 {
+        let raw_element = Self::serialize_element(element);
+        record_storage_cost(self.element_index_prefix.len() + raw_element.len(), size_of::<u64>())
+            + self.elements.storage_cost_of_raw(&raw_element)
+    }
+
     /// Adds a value to the set.
     /// If the set did not have this value present, `true` is returned.
     /// If the set did have this value present, `false` is returned.
@@ -139,6 +239,14 @@ where
         self.insert_raw(&Self::serialize_element(element))
     }
 
+    /// Returns `true` if the mocked storage holds exactly the elements of this set and their
+    /// indices, with no stale entries. Only available in unit tests.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn is_storage_clean(&self) -> bool {
+        let indices = crate::collections::mocked_storage_suffixes(&self.element_index_prefix, None);
+        indices.len() as u64 == self.len() && self.elements.is_storage_clean()
+    }
+
     /// Clears the map, removing all elements.
     pub fn clear(&mut self) {
         for raw_element in self.elements.iter_raw() {
@@ -154,10 +262,39 @@ where
     }
 
     /// Iterate over deserialized elements.
-    pub fn iter(&self) -> impl Iterator<Item = T> + '_ {
+    pub fn iter(&self) -> vector::Iter<T> {
         self.elements.iter()
     }
 
+    /// Returns up to `limit` elements, starting at the element at `from_index` in the order of
+    /// [`iter`](Self::iter). Removing elements moves the last element into the place of the
+    /// removed one, so pages read across calls can miss or repeat elements if the set changed.
+    pub fn to_page(&self, from_index: u64, limit: u64) -> Vec<T> {
+        self.elements.to_paginated_vec(from_index, limit)
+    }
+
+    /// Returns the page of [`to_page`](Self::to_page) along with the number of elements and the
+    /// index of the next page, for view methods listing the set.
+    ///
+    /// # Examples
+    /// ```
+    /// use near_sdk::collections::{ElementsPage, UnorderedSet};
+    ///
+    /// let mut allowlist = UnorderedSet::new(b"a");
+    /// allowlist.extend(vec!["alice".to_string(), "bob".to_string(), "carol".to_string()]);
+    ///
+    /// let page = allowlist.page(0, 2);
+    /// assert_eq!(page.next_index, Some(2));
+    /// let last = allowlist.page(page.next_index.unwrap(), 2);
+    /// assert_eq!(
+    ///     last,
+    ///     ElementsPage { items: vec!["carol".to_string()], total: 3, next_index: None }
+    /// );
+    /// ```
+    pub fn page(&self, from_index: u64, limit: u64) -> ElementsPage<T> {
+        ElementsPage::new(self.to_page(from_index, limit), from_index, self.len())
+    }
+
     pub fn extend<IT: IntoIterator<Item = T>>(&mut self, iter: IT) {
         for el in iter {
             self.insert(&el);
@@ -171,6 +308,18 @@ where
     }
 }
 
+impl<'a, T> IntoIterator for &'a UnorderedSet<T>
+where
+    T: BorshSerialize + BorshDeserialize,
+{
+    type Item = T;
+    type IntoIter = vector::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
 impl<T> std::fmt::Debug for UnorderedSet<T>
 where
     T: std::fmt::Debug + BorshSerialize + BorshDeserialize,
@@ -186,7 +335,7 @@ where
 #[cfg(not(target_arch = "wasm32"))]
 #[cfg(test)]
 mod tests {
-    use crate::collections::UnorderedSet;
+    use crate::collections::{ElementsPage, TooLarge, UnorderedSet};
     use rand::seq::SliceRandom;
     use rand::{Rng, SeedableRng};
     use std::collections::HashSet;
@@ -290,6 +439,17 @@ mod tests {
         assert_eq!(actual, keys);
     }
 
+    #[test]
+    pub fn test_std_conversion() {
+        let std_set: HashSet<u64> = (0..10).collect();
+        let set = UnorderedSet::from_std(b"s", std_set.clone());
+        assert_eq!(set.len(), 10);
+        assert!(set.contains(&3));
+        assert_eq!(set.to_std(10), Ok(std_set));
+        assert_eq!(set.to_std(0), Err(TooLarge { len: 10, max: 0 }));
+        assert_eq!(set.to_vec(), (0..10).collect::<Vec<_>>());
+    }
+
     #[test]
     pub fn test_clear() {
         let mut set = UnorderedSet::new(b"s");
@@ -305,6 +465,23 @@ mod tests {
         }
     }
 
+    #[test]
+    pub fn test_clear_reuse() {
+        let before = crate::test_utils::storage_snapshot();
+        let mut set = UnorderedSet::new(b"s");
+        for i in 0..10u64 {
+            set.insert(&i);
+        }
+        set.clear();
+        assert!(set.is_storage_clean());
+        assert_eq!(crate::test_utils::storage_snapshot(), before);
+
+        assert!(set.insert(&3));
+        assert!(set.is_storage_clean());
+        assert!(set.contains(&3));
+        assert_eq!(set.to_vec(), vec![3]);
+    }
+
     #[test]
     pub fn test_iter() {
         let mut set = UnorderedSet::new(b"s");
@@ -362,4 +539,74 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn storage_cost_of() {
+        let mut set = UnorderedSet::new(b"s");
+        for len in [0, 1, 10, 100, 1000].iter() {
+            let element = "e".repeat(*len);
+            let cost = set.storage_cost_of(&element);
+            let usage = crate::env::storage_usage();
+            set.insert(&element);
+            assert_eq!(crate::env::storage_usage() - usage, cost);
+        }
+    }
+
+    #[test]
+    pub fn test_page() {
+        let mut set = UnorderedSet::new(b"s");
+        set.extend(0u32..25);
+
+        let mut from_index = 0;
+        let mut pages = Vec::new();
+        loop {
+            let page = set.page(from_index, 10);
+            assert_eq!(page.total, 25);
+            assert_eq!(page.items, set.to_page(from_index, 10));
+            pages.push(page.items.len());
+            match page.next_index {
+                Some(next_index) => from_index = next_index,
+                None => break,
+            }
+        }
+        assert_eq!(pages, [10, 10, 5]);
+
+        // A page ending exactly at the last element has no next page.
+        assert_eq!(set.page(15, 10).next_index, None);
+        assert_eq!(set.page(25, 10), ElementsPage { items: vec![], total: 25, next_index: None });
+        assert_eq!(set.page(0, 0).next_index, Some(0));
+    }
+
+    #[test]
+    pub fn test_page_json() {
+        let mut set = UnorderedSet::new(b"s");
+        set.extend(vec!["alice".to_string(), "bob".to_string(), "carol".to_string()]);
+        assert_eq!(
+            serde_json::to_string(&set.page(0, 2)).unwrap(),
+            r#"{"items":["alice","bob"],"total":3,"next_index":2}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&set.page(2, 2)).unwrap(),
+            r#"{"items":["carol"],"total":3,"next_index":null}"#
+        );
+    }
+
+    fn collect<I: IntoIterator>(collection: I) -> Vec<I::Item> {
+        collection.into_iter().collect()
+    }
+
+    #[test]
+    pub fn test_into_iter() {
+        let mut set = UnorderedSet::new(b"s");
+        set.extend(0u64..5);
+        assert_eq!(collect(&set), [0, 1, 2, 3, 4]);
+        assert_eq!(set.iter().nth(3), Some(3));
+        assert_eq!(set.iter().skip(2).size_hint(), (3, Some(3)));
+
+        let mut sum = 0;
+        for element in &set {
+            sum += element;
+        }
+        assert_eq!(sum, 10);
+    }
 }
@@ -0,0 +1,271 @@
+//! Formats in which the values of [`LookupMap`](crate::collections::LookupMap) and
+//! [`UnorderedMap`](crate::collections::UnorderedMap) are stored.
+//!
+//! Values are stored with Borsh by default. Maps created with `new_json` store them as compact
+//! JSON instead, so that generic JSON tooling such as state viewers can read them, and so that
+//! values of types which only implement serde's `Serialize` and `Deserialize` can be stored.
+//! Maps created with `with_codec` store values with any [`ValueCodec`]. Keys are always
+//! serialized with Borsh, so the storage keys don't depend on the value format.
+//!
+//! # Tradeoffs
+//!
+//! JSON values are larger than their Borsh counterparts, and every stored byte is paid for with
+//! staked storage and gas. For example the value
+//! `Profile { name: "Zoë".to_string(), score: 7 }` takes 12 bytes with Borsh and 26 bytes as
+//! JSON (25 bytes of JSON and the format tag), so a `LookupMap<u64, Profile>` entry under a 1 byte
+//! prefix uses 61 bytes of storage with Borsh and 75 bytes with JSON, including the 40 bytes
+//! charged for each record. Reading and writing the value also costs more gas, both in the
+//! storage host functions, which are charged per byte, and in the contract itself, as parsing JSON
+//! takes more instructions than Borsh. The `value_codec` tests measure these numbers.
+//!
+//! # Format tag
+//!
+//! JSON values are prefixed with the [`JSON_TAG`] byte (`b'J'`), so a raw JSON value looks like
+//! `J{"name":"Zoë","score":7}`. Reading a value written as Borsh through a JSON map, or a value
+//! written as JSON through a Borsh map, panics with
+//! [`SdkError::ValueFormatMismatch`](crate::errors::SdkError::ValueFormatMismatch). Borsh values
+//! have no tag, to keep existing maps readable, so a JSON value is only detected when it fails to
+//! deserialize as Borsh.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::errors::SdkError;
+
+/// Byte prepended to each value stored as JSON.
+pub const JSON_TAG: u8 = b'J';
+
+/// Format in which the values of a map are stored.
+pub trait ValueCodec<V> {
+    /// Serializes `value`.
+    fn try_encode(value: &V) -> Result<Vec<u8>, SdkError>;
+
+    /// Deserializes a value written by [`try_encode`](Self::try_encode). Returns
+    /// [`SdkError::ValueFormatMismatch`] if it was written in another format.
+    fn try_decode(raw_value: &[u8]) -> Result<V, SdkError>;
+
+    /// Serializes `value`, panicking if it can't be serialized.
+    fn encode(value: &V) -> Vec<u8> {
+        Self::try_encode(value).unwrap_or_else(|err| err.panic())
+    }
+
+    /// Deserializes a value written by [`encode`](Self::encode), panicking if it was written in
+    /// another format or can't be deserialized.
+    fn decode(raw_value: &[u8]) -> V {
+        Self::try_decode(raw_value).unwrap_or_else(|err| err.panic())
+    }
+}
+
+/// Stores values with Borsh. This is the default format.
+///
+/// Borsh values are not tagged: maps written before the codecs were added store plain Borsh, and
+/// a tag would make them unreadable after an upgrade, as well as add a byte to every value. A
+/// JSON value read through a Borsh map is therefore only reported as
+/// [`SdkError::ValueFormatMismatch`] if it fails to deserialize as Borsh and looks like tagged
+/// JSON.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BorshCodec;
+
+/// Stores values as compact JSON, prefixed with [`JSON_TAG`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct JsonCodec;
+
+/// Returns `true` if `raw_value` looks like a value written by [`JsonCodec`].
+fn is_tagged_json(raw_value: &[u8]) -> bool {
+    match raw_value.split_first() {
+        Some((&JSON_TAG, json)) => match json.first() {
+            Some(b'{') | Some(b'[') | Some(b'"') | Some(b'-') | Some(b'0'..=b'9') | Some(b't')
+            | Some(b'f') | Some(b'n') => std::str::from_utf8(json).is_ok(),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+impl<V> ValueCodec<V> for BorshCodec
+where
+    V: BorshSerialize + BorshDeserialize,
+{
+    fn try_encode(value: &V) -> Result<Vec<u8>, SdkError> {
+        value.try_to_vec().map_err(|_| SdkError::Serialization { context: "value" })
+    }
+
+    fn try_decode(raw_value: &[u8]) -> Result<V, SdkError> {
+        V::try_from_slice(raw_value).map_err(|_| {
+            if is_tagged_json(raw_value) {
+                SdkError::ValueFormatMismatch { expected: "Borsh", found: "JSON" }
+            } else {
+                SdkError::Deserialization { context: "value" }
+            }
+        })
+    }
+}
+
+impl<V> ValueCodec<V> for JsonCodec
+where
+    V: Serialize + DeserializeOwned,
+{
+    fn try_encode(value: &V) -> Result<Vec<u8>, SdkError> {
+        let mut raw_value = vec![JSON_TAG];
+        serde_json::to_writer(&mut raw_value, value)
+            .map_err(|_| SdkError::JsonSerialization { context: "value" })?;
+        Ok(raw_value)
+    }
+
+    fn try_decode(raw_value: &[u8]) -> Result<V, SdkError> {
+        let json = match raw_value.split_first() {
+            Some((&JSON_TAG, json)) => json,
+            _ => return Err(SdkError::ValueFormatMismatch { expected: "JSON", found: "Borsh" }),
+        };
+        serde_json::from_slice(json).map_err(|_| SdkError::JsonDeserialization { context: "value" })
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collections::{LookupMap, UnorderedMap};
+    use crate::env;
+    use serde::Deserialize;
+
+    #[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+    struct Profile {
+        name: String,
+        score: u32,
+    }
+
+    fn profile(name: &str) -> Profile {
+        Profile { name: name.to_string(), score: 7 }
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let mut map = LookupMap::new_json(b"m");
+        let names = ["Zoë", "Đorđe", "東京", "emoji 🦀", "quote \" and \\ backslash"];
+        for (i, name) in names.iter().enumerate() {
+            assert_eq!(map.insert(&(i as u64), &profile(name)), None);
+        }
+        for (i, name) in names.iter().enumerate() {
+            assert_eq!(map.get(&(i as u64)), Some(profile(name)));
+        }
+        assert_eq!(map.insert(&0, &profile("Chloé")), Some(profile("Zoë")));
+        assert_eq!(map.remove(&0), Some(profile("Chloé")));
+
+        // Non-ASCII characters are stored as UTF-8, without escaping.
+        let raw_value = env::storage_read(&[b'm', 1, 0, 0, 0, 0, 0, 0, 0]).unwrap();
+        assert_eq!(raw_value, "J{\"name\":\"Đorđe\",\"score\":7}".as_bytes());
+    }
+
+    #[test]
+    fn unordered_map_json_round_trip() {
+        let mut map = UnorderedMap::new_json(b"m");
+        map.insert(&"a".to_string(), &profile("Zoë"));
+        map.insert(&"b".to_string(), &profile("東京"));
+        assert_eq!(map.insert(&"a".to_string(), &profile("Chloé")), Some(profile("Zoë")));
+        assert_eq!(map.get(&"b".to_string()), Some(profile("東京")));
+        assert_eq!(
+            map.iter().collect::<Vec<_>>(),
+            [("a".to_string(), profile("Chloé")), ("b".to_string(), profile("東京"))]
+        );
+        assert_eq!(map.values().count(), 2);
+        assert_eq!(map.remove(&"a".to_string()), Some(profile("Chloé")));
+        assert_eq!(map.len(), 1);
+        map.clear();
+        assert!(map.is_empty());
+    }
+
+    /// A type which only implements serde, like many types of third-party crates.
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct SerdeOnly {
+        tags: Vec<String>,
+        weight: Option<f64>,
+    }
+
+    #[test]
+    fn serde_only_value() {
+        let mut map: LookupMap<u8, SerdeOnly, JsonCodec> = LookupMap::with_codec(b"s");
+        let value = SerdeOnly { tags: vec!["a".to_string(), "b".to_string()], weight: Some(0.5) };
+        assert_eq!(map.insert(&1, &value), None);
+        assert_eq!(map.get(&1), Some(value));
+
+        let raw_value = env::storage_read(&[b's', 1]).unwrap();
+        assert_eq!(raw_value, b"J{\"tags\":[\"a\",\"b\"],\"weight\":0.5}");
+        let json: serde_json::Value = serde_json::from_slice(&raw_value[1..]).unwrap();
+        assert_eq!(json["tags"][1], "b");
+    }
+
+    #[test]
+    fn storage_size() {
+        // Each record is charged for its key, its value and 40 extra bytes.
+        let mut borsh_map = LookupMap::new(b"b");
+        let usage = env::storage_usage();
+        borsh_map.insert(&0u64, &profile("Zoë"));
+        let borsh_usage = env::storage_usage() - usage;
+        // 9 bytes of key and 12 bytes of value.
+        assert_eq!(borsh_usage, 61);
+
+        let mut json_map = LookupMap::new_json(b"j");
+        let usage = env::storage_usage();
+        json_map.insert(&0u64, &profile("Zoë"));
+        let json_usage = env::storage_usage() - usage;
+        // 9 bytes of key and 26 bytes of value.
+        assert_eq!(json_usage, 75);
+    }
+
+    #[test]
+    fn gas() {
+        let mut borsh_map = LookupMap::new(b"b");
+        let mut json_map = LookupMap::new_json(b"j");
+        let values: Vec<Profile> = (0..10).map(|i| profile(&"Zoë".repeat(i))).collect();
+
+        let gas = env::used_gas();
+        for (i, value) in values.iter().enumerate() {
+            borsh_map.insert(&(i as u64), value);
+            assert_eq!(borsh_map.get(&(i as u64)).as_ref(), Some(value));
+        }
+        let borsh_gas = env::used_gas() - gas;
+
+        let gas = env::used_gas();
+        for (i, value) in values.iter().enumerate() {
+            json_map.insert(&(i as u64), value);
+            assert_eq!(json_map.get(&(i as u64)).as_ref(), Some(value));
+        }
+        let json_gas = env::used_gas() - gas;
+
+        // Only the host functions are charged in unit tests, so the difference is the cost of the
+        // larger values alone: the JSON values take 400 bytes and the Borsh values 260 bytes, and
+        // each byte costs 47_054_775 gas to read from memory, write to storage, read from storage
+        // into a register and copy back into memory.
+        assert_eq!(borsh_gas.0, 1_398_599_698_821);
+        assert_eq!(json_gas.0, 1_405_187_367_321);
+        assert_eq!((json_gas - borsh_gas).0, 140 * 47_054_775);
+    }
+
+    #[test]
+    #[should_panic(expected = "Stored value was written in a different format than the map reads")]
+    fn json_read_as_borsh() {
+        LookupMap::new_json(b"m").insert(&0u8, &profile("Zoë"));
+        LookupMap::<u8, Profile>::new(b"m").get(&0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Stored value was written in a different format than the map reads")]
+    fn borsh_read_as_json() {
+        LookupMap::new(b"m").insert(&0u8, &profile("Zoë"));
+        LookupMap::<u8, Profile, JsonCodec>::new_json(b"m").get(&0);
+    }
+
+    #[test]
+    fn format_mismatch_message() {
+        assert_eq!(
+            SdkError::ValueFormatMismatch { expected: "Borsh", found: "JSON" }.to_string(),
+            "Value was written as JSON but the map reads Borsh, use the constructor of the map \
+             matching the format the value was written with"
+        );
+        assert!(is_tagged_json(b"J{}"));
+        assert!(!is_tagged_json(b"J"));
+        assert!(!is_tagged_json(&[JSON_TAG, 0xff]));
+    }
+}
@@ -1,21 +1,21 @@
-//! A vector implemented on a trie. Unlike standard vector does not support insertion and removal
-//! of an element results in the last element being placed in the empty position.
-use core::ops::Range;
+//! A vector implemented on a trie. Removing an element with `swap_remove` results in the last
+//! element being placed in the empty position. Order preserving `insert` and `remove` are also
+//! available, but require rewriting every element after the given index.
+use core::cmp::Ordering;
+use core::ops::{Bound, Range, RangeBounds};
 use std::iter::FusedIterator;
 use std::marker::PhantomData;
 
 use borsh::{BorshDeserialize, BorshSerialize};
 
-use crate::collections::append_slice;
+use crate::collections::relocate::{relocate, Relocate, RelocateProgress};
+use crate::collections::{append_slice, record_storage_cost, HandleId, TooLarge};
+use crate::environment::register_prefix;
+use crate::errors::{CollectionError, SdkError};
 use crate::{env, IntoStorageKey};
 
-const ERR_INCONSISTENT_STATE: &str = "The collection is an inconsistent state. Did previous smart contract execution terminate unexpectedly?";
-const ERR_ELEMENT_DESERIALIZATION: &str = "Cannot deserialize element";
-const ERR_ELEMENT_SERIALIZATION: &str = "Cannot serialize element";
-const ERR_INDEX_OUT_OF_BOUNDS: &str = "Index out of bounds";
-
 fn expect_consistent_state<T>(val: Option<T>) -> T {
-    val.unwrap_or_else(|| env::panic_str(ERR_INCONSISTENT_STATE))
+    val.unwrap_or_else(|| SdkError::CollectionInconsistent { collection: "Vector" }.panic())
 }
 
 /// An iterable implementation of vector that stores its content on the trie.
@@ -26,6 +26,8 @@ pub struct Vector<T> {
     prefix: Vec<u8>,
     #[borsh_skip]
     el: PhantomData<T>,
+    #[borsh_skip]
+    handle: HandleId,
 }
 
 impl<T> Vector<T> {
@@ -44,20 +46,54 @@ impl<T> Vector<T> {
     where
         S: IntoStorageKey,
     {
-        Self { len: 0, prefix: prefix.into_storage_key(), el: PhantomData }
+        let prefix = prefix.into_storage_key();
+        let _scope = register_prefix::<Self>(&prefix);
+        Self { len: 0, prefix, el: PhantomData, handle: HandleId::default() }
+    }
+
+    /// Creates another handle to this vector, intended for read paths. The length is copied, so the
+    /// new handle does not observe elements pushed or popped through this one. With the
+    /// `check-handle-aliasing` feature enabled, writing through both handles within a call panics.
+    pub fn duplicate_handle(&self) -> Self {
+        Self {
+            len: self.len,
+            prefix: self.prefix.clone(),
+            el: PhantomData,
+            handle: HandleId::default(),
+        }
     }
 
     fn index_to_lookup_key(&self, index: u64) -> Vec<u8> {
         append_slice(&self.prefix, &index.to_le_bytes()[..])
     }
 
+    /// Extends the length over the elements stored consecutively after the current length, for
+    /// handles recreated from their prefix alone. Reads one key per element.
+    pub(crate) fn recover_len(&mut self) {
+        while env::storage_has_key(&self.index_to_lookup_key(self.len)) {
+            self.len += 1;
+        }
+    }
+
+    fn error(&self, error: SdkError, index: u64) -> CollectionError {
+        CollectionError::new(error, &self.prefix, &index.to_le_bytes())
+    }
+
+    fn inconsistent(&self, index: u64) -> CollectionError {
+        self.error(SdkError::CollectionInconsistent { collection: "Vector" }, index)
+    }
+
     /// Returns the serialized element by index or `None` if it is not present.
     pub fn get_raw(&self, index: u64) -> Option<Vec<u8>> {
+        self.try_get_raw(index).unwrap_or_else(|err| err.panic())
+    }
+
+    pub(crate) fn try_get_raw(&self, index: u64) -> Result<Option<Vec<u8>>, CollectionError> {
         if index >= self.len {
-            return None;
+            return Ok(None);
         }
         let lookup_key = self.index_to_lookup_key(index);
-        Some(expect_consistent_state(env::storage_read(&lookup_key)))
+        env::storage_read(&lookup_key).map(Some).ok_or_else(|| self.inconsistent(index))
     }
 
     /// Removes an element from the vector and returns it in serialized form.
@@ -68,23 +104,34 @@ impl<T> Vector<T> {
     ///
     /// Panics if `index` is out of bounds.
     pub fn swap_remove_raw(&mut self, index: u64) -> Vec<u8> {
+        self.try_swap_remove_raw(index).unwrap_or_else(|err| err.panic())
+    }
+
+    pub(crate) fn try_swap_remove_raw(&mut self, index: u64) -> Result<Vec<u8>, CollectionError> {
         if index >= self.len {
-            env::panic_str(ERR_INDEX_OUT_OF_BOUNDS)
+            SdkError::IndexOutOfBounds { index, len: self.len }.panic()
         } else if index + 1 == self.len {
-            expect_consistent_state(self.pop_raw())
+            self.try_pop_raw()?.ok_or_else(|| self.inconsistent(index))
         } else {
             let lookup_key = self.index_to_lookup_key(index);
-            let raw_last_value = self.pop_raw().expect("checked `index < len` above, so `len > 0`");
+            let raw_last_value =
+                self.try_pop_raw()?.expect("checked `index < len` above, so `len > 0`");
             if env::storage_write(&lookup_key, &raw_last_value) {
-                expect_consistent_state(env::storage_get_evicted())
+                env::storage_get_evicted().ok_or_else(|| self.inconsistent(index))
             } else {
-                env::panic_str(ERR_INCONSISTENT_STATE)
+                Err(self.inconsistent(index))
             }
         }
     }
 
+    /// Returns the bytes of storage pushing a serialized element adds.
+    pub(crate) fn storage_cost_of_raw(&self, raw_element: &[u8]) -> u64 {
+        record_storage_cost(self.prefix.len() + std::mem::size_of::<u64>(), raw_element.len())
+    }
+
     /// Appends a serialized element to the back of the collection.
     pub fn push_raw(&mut self, raw_element: &[u8]) {
+        self.handle.record_write(&self.prefix);
         let lookup_key = self.index_to_lookup_key(self.len);
         self.len += 1;
         env::storage_write(&lookup_key, raw_element);
@@ -92,19 +139,23 @@ impl<T> Vector<T> {
 
     /// Removes the last element from a vector and returns it without deserializing, or `None` if it is empty.
     pub fn pop_raw(&mut self) -> Option<Vec<u8>> {
+        self.try_pop_raw().unwrap_or_else(|err| err.panic())
+    }
+
+    pub(crate) fn try_pop_raw(&mut self) -> Result<Option<Vec<u8>>, CollectionError> {
         if self.is_empty() {
-            None
+            Ok(None)
         } else {
+            self.handle.record_write(&self.prefix);
             let last_index = self.len - 1;
             let last_lookup_key = self.index_to_lookup_key(last_index);
 
             self.len -= 1;
-            let raw_last_value = if env::storage_remove(&last_lookup_key) {
-                expect_consistent_state(env::storage_get_evicted())
+            if env::storage_remove(&last_lookup_key) {
+                env::storage_get_evicted().map(Some).ok_or_else(|| self.inconsistent(last_index))
             } else {
-                env::panic_str(ERR_INCONSISTENT_STATE)
-            };
-            Some(raw_last_value)
+                Err(self.inconsistent(last_index))
+            }
         }
     }
 
@@ -114,14 +165,23 @@ impl<T> Vector<T> {
     ///
     /// If `index` is out of bounds.
     pub fn replace_raw(&mut self, index: u64, raw_element: &[u8]) -> Vec<u8> {
+        self.try_replace_raw(index, raw_element).unwrap_or_else(|err| err.panic())
+    }
+
+    pub(crate) fn try_replace_raw(
+        &mut self,
+        index: u64,
+        raw_element: &[u8],
+    ) -> Result<Vec<u8>, CollectionError> {
         if index >= self.len {
-            env::panic_str(ERR_INDEX_OUT_OF_BOUNDS)
+            SdkError::IndexOutOfBounds { index, len: self.len }.panic()
         } else {
+            self.handle.record_write(&self.prefix);
             let lookup_key = self.index_to_lookup_key(index);
             if env::storage_write(&lookup_key, raw_element) {
-                expect_consistent_state(env::storage_get_evicted())
+                env::storage_get_evicted().ok_or_else(|| self.inconsistent(index))
             } else {
-                env::panic_str(ERR_INCONSISTENT_STATE);
+                Err(self.inconsistent(index))
             }
         }
     }
@@ -137,17 +197,92 @@ impl<T> Vector<T> {
             self.push_raw(&el)
         }
     }
+
+    /// Moves the vector to `new_prefix`, processing up to `batch` elements per call and keeping track
+    /// of the progress under `cursor_key`. Call this in consecutive function calls until
+    /// [`RelocateProgress::Done`] is returned. The vector keeps reading from the old prefix until
+    /// all elements are copied, after which the old entries are removed.
+    ///
+    /// The vector must not be modified while it is being moved. Panics if `batch` is 0.
+    pub fn relocate_to<S>(
+        &mut self,
+        new_prefix: S,
+        batch: u64,
+        cursor_key: &[u8],
+    ) -> RelocateProgress
+    where
+        S: IntoStorageKey,
+    {
+        relocate(self, new_prefix.into_storage_key(), batch, cursor_key)
+    }
+}
+
+impl<T> Relocate for Vector<T> {
+    fn prefix(&self) -> Vec<u8> {
+        self.prefix.clone()
+    }
+
+    fn set_prefix(&mut self, prefix: &[u8]) {
+        self.prefix = prefix.to_vec();
+    }
+
+    fn entries_len(&self) -> u64 {
+        self.len
+    }
+
+    fn entry_keys(&self, index: u64, prefix: &[u8]) -> Vec<Vec<u8>> {
+        vec![append_slice(prefix, &index.to_le_bytes()[..])]
+    }
 }
 
 impl<T> Vector<T> {
     /// Removes all elements from the collection.
     pub fn clear(&mut self) {
+        self.handle.record_write(&self.prefix);
         for i in 0..self.len {
             let lookup_key = self.index_to_lookup_key(i);
             env::storage_remove(&lookup_key);
         }
         self.len = 0;
     }
+
+    /// Removes up to `limit` elements from the end of the vector and returns the number of
+    /// elements left. Unlike [`Vector::clear`], this can be called across multiple function calls
+    /// to clear vectors which are too large to clear within the gas limit of a single call. The
+    /// vector stays usable between calls.
+    pub fn clear_step(&mut self, limit: u64) -> u64 {
+        self.truncate(self.len.saturating_sub(limit));
+        self.len
+    }
+
+    /// Returns `true` if the mocked storage holds exactly the elements of this vector, with no
+    /// stale entries past its length. Only available in unit tests.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn is_storage_clean(&self) -> bool {
+        let indices = crate::collections::mocked_storage_suffixes(&self.prefix, Some(8));
+        indices.len() as u64 == self.len
+            && indices.iter().all(|index| {
+                let mut bytes = [0u8; 8];
+                bytes.copy_from_slice(index);
+                u64::from_le_bytes(bytes) < self.len
+            })
+    }
+
+    /// Shortens the vector, keeping the first `new_len` elements and removing the rest from
+    /// storage. The length is updated once.
+    ///
+    /// If `new_len` is greater than or equal to the current length, this has no effect.
+    pub fn truncate(&mut self, new_len: u64) {
+        if new_len >= self.len {
+            return;
+        }
+        self.handle.record_write(&self.prefix);
+        for i in new_len..self.len {
+            let lookup_key = self.index_to_lookup_key(i);
+            env::storage_remove(&lookup_key);
+        }
+        self.len = new_len;
+    }
 }
 
 impl<T> Vector<T>
@@ -155,7 +290,35 @@ where
     T: BorshSerialize,
 {
     fn serialize_element(element: &T) -> Vec<u8> {
-        element.try_to_vec().unwrap_or_else(|_| env::panic_str(ERR_ELEMENT_SERIALIZATION))
+        element
+            .try_to_vec()
+            .unwrap_or_else(|_| SdkError::Serialization { context: "element" }.panic())
+    }
+
+    fn try_serialize_element(&self, index: u64, element: &T) -> Result<Vec<u8>, CollectionError> {
+        element
+            .try_to_vec()
+            .map_err(|_| self.error(SdkError::Serialization { context: "element" }, index))
+    }
+
+    /// Creates a vector with the given `prefix` holding the elements of `vec`, in the same order.
+    pub fn from_std<S>(prefix: S, vec: Vec<T>) -> Self
+    where
+        S: IntoStorageKey,
+    {
+        let mut result = Self::new(prefix);
+        result.extend(vec);
+        result
+    }
+
+    /// Returns the number of bytes of storage pushing `element` adds: the serialized element, its
+    /// storage key and the overhead of a storage record. The length is kept in the vector itself
+    /// and doesn't use storage of its own.
+    ///
+    /// Contracts charging their users for storage can require the cost before pushing, instead of
+    /// measuring [`env::storage_usage`] before and after.
+    pub fn storage_cost_of(&self, element: &T) -> u64 {
+        self.storage_cost_of_raw(&Self::serialize_element(element))
     }
 
     /// Appends an element to the back of the collection.
@@ -165,11 +328,39 @@ where
     }
 
     /// Extends vector from the given collection.
+    ///
+    /// Each element is written to storage once. The length is kept in the [`Vector`] itself and
+    /// is only persisted when the structure containing the vector is written, so no additional
+    /// writes are needed for it. If serializing an element panics, the elements before it are
+    /// kept and the length stays consistent with them. Use [`Vector::push_many`] to only append if
+    /// all elements can be serialized.
     pub fn extend<IT: IntoIterator<Item = T>>(&mut self, iter: IT) {
         for el in iter {
             self.push(&el)
         }
     }
+
+    /// Appends all `elements` to the back of the collection. All elements are serialized before
+    /// any are written, so nothing is appended if serializing one of them fails.
+    pub fn push_many(&mut self, elements: &[T]) {
+        let raw_elements: Vec<Vec<u8>> = elements.iter().map(Self::serialize_element).collect();
+        self.extend_raw(raw_elements);
+    }
+
+    /// Resizes the vector in place so that its length is equal to `new_len`.
+    ///
+    /// If `new_len` is greater than the current length, the vector is extended with copies of
+    /// `value`. Otherwise the vector is truncated, see [`Vector::truncate`].
+    pub fn resize(&mut self, new_len: u64, value: T) {
+        if new_len <= self.len {
+            self.truncate(new_len);
+        } else {
+            let raw_element = Self::serialize_element(&value);
+            for _ in self.len..new_len {
+                self.push_raw(&raw_element);
+            }
+        }
+    }
 }
 
 impl<T> Vector<T>
@@ -178,24 +369,52 @@ where
 {
     fn deserialize_element(raw_element: &[u8]) -> T {
         T::try_from_slice(raw_element)
-            .unwrap_or_else(|_| env::panic_str(ERR_ELEMENT_DESERIALIZATION))
+            .unwrap_or_else(|_| SdkError::Deserialization { context: "element" }.panic())
+    }
+
+    fn try_deserialize_element(
+        &self,
+        index: u64,
+        raw_element: &[u8],
+    ) -> Result<T, CollectionError> {
+        T::try_from_slice(raw_element)
+            .map_err(|_| self.error(SdkError::Deserialization { context: "element" }, index))
     }
 
     /// Returns the element by index or `None` if it is not present.
+    ///
+    /// The element is a copy of the stored one, so modifying it doesn't change the vector. Use
+    /// [`get_guard`](Self::get_guard) or [`update`](Self::update) to modify an element in place.
     pub fn get(&self, index: u64) -> Option<T> {
-        self.get_raw(index).map(|x| Self::deserialize_element(&x))
+        self.try_get(index).unwrap_or_else(|err| err.panic())
+    }
+
+    /// Returns the element by index or `None` if it is not present, or an error identifying the
+    /// index if the element is missing from storage or can't be deserialized.
+    pub fn try_get(&self, index: u64) -> Result<Option<T>, CollectionError> {
+        self.try_get_raw(index)?.map(|raw| self.try_deserialize_element(index, &raw)).transpose()
     }
 
     /// Removes an element from the vector and returns it.
     /// The removed element is replaced by the last element of the vector.
     /// Does not preserve ordering, but is `O(1)`.
     ///
+    /// The element is read and deserialized before anything is written, so the vector is left
+    /// unchanged if it can't be deserialized.
+    ///
     /// # Panics
     ///
     /// Panics if `index` is out of bounds.
     pub fn swap_remove(&mut self, index: u64) -> T {
-        let raw_evicted = self.swap_remove_raw(index);
-        Self::deserialize_element(&raw_evicted)
+        if index >= self.len {
+            SdkError::IndexOutOfBounds { index, len: self.len }.panic()
+        }
+        let element = self
+            .try_get(index)
+            .unwrap_or_else(|err| err.panic())
+            .expect("checked `index < len` above");
+        self.swap_remove_raw(index);
+        element
     }
 
     /// Removes the last element from a vector and returns it, or `None` if it is empty.
@@ -204,13 +423,186 @@ where
     }
 
     /// Iterate over deserialized elements.
+    ///
+    /// The iterator is double ended, so it can be reversed, and only reads from storage the
+    /// elements it yields. Skipping elements with [`Iterator::nth`] or [`Iterator::skip`] does not
+    /// read the skipped elements.
+    ///
+    /// # Examples
+    /// ```
+    /// use near_sdk::collections::Vector;
+    ///
+    /// let mut vec = Vector::new(b"v");
+    /// vec.extend(0u32..100);
+    ///
+    /// // Only the last three elements are read.
+    /// let last: Vec<u32> = vec.iter().rev().take(3).collect();
+    /// assert_eq!(last, [99, 98, 97]);
+    /// ```
     pub fn iter(&self) -> Iter<T> {
         Iter::new(self)
     }
 
+    /// Iterate over the deserialized elements within `range`. The bounds of the range are clamped
+    /// to the length of the vector, so a range starting past the end yields no elements instead of
+    /// panicking.
+    pub fn iter_range<R>(&self, range: R) -> Iter<T>
+    where
+        R: RangeBounds<u64>,
+    {
+        let start = match range.start_bound() {
+            Bound::Included(i) => *i,
+            Bound::Excluded(i) => i.saturating_add(1),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(i) => i.saturating_add(1),
+            Bound::Excluded(i) => *i,
+            Bound::Unbounded => self.len,
+        };
+        let end = end.min(self.len);
+        Iter { inner: RawIter { vec: self, range: Range { start: start.min(end), end } } }
+    }
+
     pub fn to_vec(&self) -> Vec<T> {
         self.iter().collect()
     }
+
+    /// Copies the elements into a `Vec`. Returns [`TooLarge`] without reading any element if the
+    /// vector has more than `max` elements.
+    pub fn to_std(&self, max: u64) -> Result<Vec<T>, TooLarge> {
+        if self.len > max {
+            return Err(TooLarge { len: self.len, max });
+        }
+        Ok(self.to_vec())
+    }
+
+    /// Returns up to `limit` elements starting at `from_index`, which is the common pattern for
+    /// paginated view methods. Returns an empty vector if `from_index` is past the end.
+    ///
+    /// # Examples
+    /// ```
+    /// use near_sdk::collections::Vector;
+    ///
+    /// let mut vec = Vector::new(b"v");
+    /// vec.extend(0u32..10);
+    ///
+    /// assert_eq!(vec.to_paginated_vec(8, 5), [8, 9]);
+    /// assert!(vec.to_paginated_vec(10, 5).is_empty());
+    /// ```
+    pub fn to_paginated_vec(&self, from_index: u64, limit: u64) -> Vec<T> {
+        self.iter_range(from_index..from_index.saturating_add(limit)).collect()
+    }
+
+    /// Binary searches this sorted vector with a comparator function, mirroring
+    /// [`slice::binary_search_by`]. Only `O(log n)` elements are read from storage.
+    ///
+    /// If the value is found then [`Result::Ok`] is returned, containing the index of the matching
+    /// element. If there are multiple matches, then any one of the matches could be returned.
+    /// If the value is not found then [`Result::Err`] is returned, containing the index where a
+    /// matching element could be inserted while maintaining sorted order.
+    pub fn binary_search_by<F>(&self, mut f: F) -> Result<u64, u64>
+    where
+        F: FnMut(&T) -> Ordering,
+    {
+        let mut left = 0;
+        let mut right = self.len;
+        while left < right {
+            let mid = left + (right - left) / 2;
+            let element = Self::deserialize_element(&expect_consistent_state(self.get_raw(mid)));
+            match f(&element) {
+                Ordering::Less => left = mid + 1,
+                Ordering::Greater => right = mid,
+                Ordering::Equal => return Ok(mid),
+            }
+        }
+        Err(left)
+    }
+
+    /// Binary searches this sorted vector with a key extraction function, mirroring
+    /// [`slice::binary_search_by_key`]. See [`Vector::binary_search_by`].
+    pub fn binary_search_by_key<B, F>(&self, b: &B, mut f: F) -> Result<u64, u64>
+    where
+        F: FnMut(&T) -> B,
+        B: Ord,
+    {
+        self.binary_search_by(|k| f(k).cmp(b))
+    }
+
+    /// Returns the index of the partition point according to the given predicate (the index of
+    /// the first element of the second partition), mirroring [`slice::partition_point`].
+    ///
+    /// The vector is assumed to be partitioned according to the given predicate, meaning that
+    /// all elements for which the predicate returns true are at the start of the vector.
+    pub fn partition_point<P>(&self, mut pred: P) -> u64
+    where
+        P: FnMut(&T) -> bool,
+    {
+        self.binary_search_by(|x| if pred(x) { Ordering::Less } else { Ordering::Greater })
+            .unwrap_or_else(|i| i)
+    }
+
+    /// Removes the elements in `range` from the vector and returns them in order. Elements after
+    /// the range are shifted down to keep their relative order, and the length is updated once.
+    ///
+    /// The elements are removed from storage eagerly, before the returned iterator is consumed.
+    /// This is `O(n)` storage writes, where `n` is the number of elements after the range.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of the range is greater than the end or if the end of the range is
+    /// greater than the length of the vector.
+    pub fn drain<R>(&mut self, range: R) -> impl Iterator<Item = T>
+    where
+        R: RangeBounds<u64>,
+    {
+        let start = match range.start_bound() {
+            Bound::Included(i) => *i,
+            Bound::Excluded(i) => i
+                .checked_add(1)
+                .unwrap_or_else(|| SdkError::IndexOutOfBounds { index: *i, len: self.len }.panic()),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(i) => i
+                .checked_add(1)
+                .unwrap_or_else(|| SdkError::IndexOutOfBounds { index: *i, len: self.len }.panic()),
+            Bound::Excluded(i) => *i,
+            Bound::Unbounded => self.len,
+        };
+        if start > end {
+            env::panic_str(&format!("Drain range start {} is greater than end {}", start, end));
+        }
+        if end > self.len {
+            env::panic_str(&format!(
+                "Drain range end {} is out of bounds for vector of length {}",
+                end, self.len
+            ));
+        }
+
+        let drained: Vec<T> = (start..end)
+            .map(|i| {
+                let raw_element = expect_consistent_state(self.get_raw(i));
+                Self::deserialize_element(&raw_element)
+            })
+            .collect();
+
+        // Shift the tail down to fill the drained range.
+        let removed = end - start;
+        if removed > 0 {
+            self.handle.record_write(&self.prefix);
+            for i in end..self.len {
+                let raw_element = expect_consistent_state(self.get_raw(i));
+                env::storage_write(&self.index_to_lookup_key(i - removed), &raw_element);
+            }
+            for i in (self.len - removed)..self.len {
+                env::storage_remove(&self.index_to_lookup_key(i));
+            }
+            self.len -= removed;
+        }
+
+        drained.into_iter()
+    }
 }
 
 impl<T> Vector<T>
@@ -226,6 +618,184 @@ where
         let raw_element = Self::serialize_element(element);
         Self::deserialize_element(&self.replace_raw(index, &raw_element))
     }
+
+    /// Swaps the elements at indices `a` and `b`. This is two storage reads and two storage
+    /// writes, or none if `a == b`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `a` or `b` is out of bounds.
+    pub fn swap(&mut self, a: u64, b: u64) {
+        for &index in &[a, b] {
+            if index >= self.len {
+                SdkError::IndexOutOfBounds { index, len: self.len }.panic()
+            }
+        }
+        if a == b {
+            return;
+        }
+        let raw_a = expect_consistent_state(self.get_raw(a));
+        let raw_b = self.replace_raw(b, &raw_a);
+        self.replace_raw(a, &raw_b);
+    }
+
+    /// Reads the element at `index`, passes a mutable reference to it to `f` and writes it back
+    /// once `f` returns, returning the result of `f`.
+    ///
+    /// The element is only written after `f` completes, so if `f` panics the element in storage
+    /// is left unchanged.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    ///
+    /// # Examples
+    /// ```
+    /// use near_sdk::collections::Vector;
+    ///
+    /// let mut vec = Vector::new(b"v");
+    /// vec.push(&vec![1u8]);
+    /// let len = vec.update(0, |el| {
+    ///     el.push(2);
+    ///     el.len()
+    /// });
+    /// assert_eq!(len, 2);
+    /// assert_eq!(vec.get(0), Some(vec![1, 2]));
+    /// ```
+    pub fn update<R, F>(&mut self, index: u64, f: F) -> R
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        let raw_element = self
+            .get_raw(index)
+            .unwrap_or_else(|| SdkError::IndexOutOfBounds { index, len: self.len }.panic());
+        let mut element = Self::deserialize_element(&raw_element);
+        let result = f(&mut element);
+        self.replace_raw(index, &Self::serialize_element(&element));
+        result
+    }
+
+    /// Returns a guard to the element at `index`, or `None` if it is not present. The guard
+    /// dereferences to the element and writes it back when dropped, if it was mutably
+    /// dereferenced. This is the recommended way to modify an element in place, as modifying the
+    /// element returned by [`get`](Self::get) doesn't change the vector.
+    ///
+    /// Nothing is written if the guard is dropped during a panic.
+    ///
+    /// # Examples
+    /// ```
+    /// use near_sdk::collections::Vector;
+    ///
+    /// let mut balances = Vector::new(b"b");
+    /// balances.push(&10u64);
+    /// if let Some(mut balance) = balances.get_guard(0) {
+    ///     *balance += 5;
+    /// }
+    /// assert_eq!(balances.get(0), Some(15));
+    /// ```
+    pub fn get_guard(&mut self, index: u64) -> Option<ElementGuard<'_, T>> {
+        let element = self.get(index)?;
+        Some(ElementGuard { vec: self, index, element, dirty: false })
+    }
+
+    /// Inserts an element at position `index`, shifting all elements after it to the right.
+    ///
+    /// **Note:** this is `O(n)` storage reads and writes, where `n` is the number of elements
+    /// after `index`. Prefer [`Vector::push`] when the order of elements does not matter.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index > len`.
+    pub fn insert(&mut self, index: u64, element: &T) {
+        self.try_insert(index, element).unwrap_or_else(|err| err.panic())
+    }
+
+    /// Inserts an element at position `index` like [`Vector::insert`], returning an error
+    /// identifying the index if the element can't be serialized, in which case nothing is written,
+    /// or if an element after it is missing from storage.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index > len`.
+    pub fn try_insert(&mut self, index: u64, element: &T) -> Result<(), CollectionError> {
+        if index > self.len {
+            SdkError::IndexOutOfBounds { index, len: self.len }.panic()
+        }
+        let mut raw_element = self.try_serialize_element(index, element)?;
+        for i in index..self.len {
+            raw_element = self.try_replace_raw(i, &raw_element)?;
+        }
+        self.push_raw(&raw_element);
+        Ok(())
+    }
+
+    /// Removes and returns the element at position `index`, shifting all elements after it to
+    /// the left.
+    ///
+    /// **Note:** this is `O(n)` storage reads and writes, where `n` is the number of elements
+    /// after `index`. Prefer [`Vector::swap_remove`] when the order of elements does not matter.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn remove(&mut self, index: u64) -> T {
+        self.try_remove(index).unwrap_or_else(|err| err.panic())
+    }
+
+    /// Removes and returns the element at position `index` like [`Vector::remove`], returning an
+    /// error identifying the index if an element is missing from storage or the removed element
+    /// can't be deserialized. The element is removed even if it can't be deserialized.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn try_remove(&mut self, index: u64) -> Result<T, CollectionError> {
+        if index >= self.len {
+            SdkError::IndexOutOfBounds { index, len: self.len }.panic()
+        }
+        let last_index = self.len - 1;
+        let mut raw_element = self.try_pop_raw()?.ok_or_else(|| self.inconsistent(last_index))?;
+        for i in (index..self.len).rev() {
+            raw_element = self.try_replace_raw(i, &raw_element)?;
+        }
+        self.try_deserialize_element(index, &raw_element)
+    }
+}
+
+impl<T> Vector<T>
+where
+    T: BorshDeserialize + Ord,
+{
+    /// Binary searches this sorted vector for a given element, mirroring
+    /// [`slice::binary_search`]. Only `O(log n)` elements are read from storage.
+    ///
+    /// If the value is found then [`Result::Ok`] is returned, containing the index of the matching
+    /// element. If there are multiple matches, then any one of the matches could be returned.
+    /// If the value is not found then [`Result::Err`] is returned, containing the index where a
+    /// matching element could be inserted while maintaining sorted order.
+    pub fn binary_search(&self, x: &T) -> Result<u64, u64> {
+        self.binary_search_by(|p| p.cmp(x))
+    }
+}
+
+impl<T> Extend<T> for Vector<T>
+where
+    T: BorshSerialize,
+{
+    fn extend<IT: IntoIterator<Item = T>>(&mut self, iter: IT) {
+        Vector::extend(self, iter)
+    }
+}
+
+impl<'a, T> Extend<&'a T> for Vector<T>
+where
+    T: BorshSerialize,
+{
+    fn extend<IT: IntoIterator<Item = &'a T>>(&mut self, iter: IT) {
+        for el in iter {
+            self.push(el)
+        }
+    }
 }
 
 #[cfg(feature = "expensive-debug")]
@@ -242,6 +812,18 @@ impl<T: std::fmt::Debug + BorshDeserialize> std::fmt::Debug for Vector<T> {
     }
 }
 
+impl<'a, T> IntoIterator for &'a Vector<T>
+where
+    T: BorshDeserialize,
+{
+    type Item = T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
 /// An iterator over raw serialized bytes of each element in the [`Vector`].
 pub struct RawIter<'a, T> {
     vec: &'a Vector<T>,
@@ -277,7 +859,7 @@ impl<'a, T> Iterator for RawIter<'a, T> {
 
     fn nth(&mut self, n: usize) -> Option<Self::Item> {
         let idx = self.range.nth(n)?;
-        self.vec.get_raw(idx)
+        Some(expect_consistent_state(self.vec.get_raw(idx)))
     }
 }
 
@@ -291,7 +873,7 @@ impl<'a, T> DoubleEndedIterator for RawIter<'a, T> {
 
     fn nth_back(&mut self, n: usize) -> Option<Self::Item> {
         let idx = self.range.nth_back(n)?;
-        self.vec.get_raw(idx)
+        Some(expect_consistent_state(self.vec.get_raw(idx)))
     }
 }
 
@@ -346,13 +928,61 @@ where
     }
 }
 
+/// A guard to an element of a [`Vector`], created by [`Vector::get_guard`]. Writes the element
+/// back when dropped, if it was mutably dereferenced.
+pub struct ElementGuard<'a, T>
+where
+    T: BorshSerialize + BorshDeserialize,
+{
+    vec: &'a mut Vector<T>,
+    index: u64,
+    element: T,
+    dirty: bool,
+}
+
+impl<'a, T> core::ops::Deref for ElementGuard<'a, T>
+where
+    T: BorshSerialize + BorshDeserialize,
+{
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.element
+    }
+}
+
+impl<'a, T> core::ops::DerefMut for ElementGuard<'a, T>
+where
+    T: BorshSerialize + BorshDeserialize,
+{
+    fn deref_mut(&mut self) -> &mut T {
+        self.dirty = true;
+        &mut self.element
+    }
+}
+
+impl<'a, T> Drop for ElementGuard<'a, T>
+where
+    T: BorshSerialize + BorshDeserialize,
+{
+    fn drop(&mut self) {
+        if self.dirty && !std::thread::panicking() {
+            self.vec.replace_raw(self.index, &Vector::serialize_element(&self.element));
+        }
+    }
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 #[cfg(test)]
 mod tests {
-    use borsh::BorshDeserialize;
+    use borsh::{BorshDeserialize, BorshSerialize};
     use rand::{Rng, SeedableRng};
 
-    use crate::collections::Vector;
+    use core::ops::Bound;
+
+    use crate::collections::{assert_panics_without_writes, Fragile, TooLarge, Vector};
+    use crate::env;
+    use crate::errors::CollectionErrorKind;
 
     #[test]
     fn test_push_pop() {
@@ -395,6 +1025,122 @@ mod tests {
         assert_eq!(actual, baseline);
     }
 
+    #[test]
+    pub fn test_swap() {
+        let mut vec = Vector::new(b"v".to_vec());
+        vec.extend(0u64..5);
+        vec.swap(0, 4);
+        assert_eq!(vec.to_vec(), vec![4, 1, 2, 3, 0]);
+        vec.swap(2, 2);
+        vec.swap(3, 1);
+        assert_eq!(vec.to_vec(), vec![4, 3, 2, 1, 0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Index out of bounds")]
+    pub fn test_swap_out_of_bounds() {
+        let mut vec = Vector::new(b"v".to_vec());
+        vec.extend(0u64..2);
+        vec.swap(0, 2);
+    }
+
+    #[test]
+    pub fn test_update() {
+        let mut vec = Vector::new(b"v".to_vec());
+        vec.extend(vec![vec![0u8], vec![1], vec![2]]);
+        let first_len = vec.update(0, |el| {
+            el.push(10);
+            el.len()
+        });
+        assert_eq!(first_len, 2);
+        vec.update(2, |el| el[0] = 20);
+        assert_eq!(vec.to_vec(), vec![vec![0, 10], vec![1], vec![20]]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Index out of bounds")]
+    pub fn test_update_out_of_bounds() {
+        let mut vec = Vector::<u64>::new(b"v".to_vec());
+        vec.update(0, |el| *el += 1);
+    }
+
+    #[test]
+    pub fn test_update_panic_leaves_element() {
+        let mut vec = Vector::new(b"v".to_vec());
+        vec.extend(0u64..3);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            vec.update(1, |el| {
+                *el = 100;
+                panic!("update failed");
+            })
+        }));
+        assert!(result.is_err());
+        assert_eq!(vec.to_vec(), vec![0, 1, 2]);
+    }
+
+    #[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+    struct Counter {
+        count: u64,
+    }
+
+    #[test]
+    pub fn test_get_guard() {
+        let mut vec = Vector::new(b"v".to_vec());
+        vec.push(&Counter { count: 0 });
+
+        // Modifying the copy returned by `get` loses the update.
+        let mut counter = vec.get(0).unwrap();
+        counter.count += 1;
+        assert_eq!(vec.get(0), Some(Counter { count: 0 }));
+
+        // The guard writes the element back when dropped.
+        {
+            let mut counter = vec.get_guard(0).unwrap();
+            counter.count += 1;
+            counter.count += 1;
+            assert_eq!(counter.count, 2);
+        }
+        assert_eq!(vec.get(0), Some(Counter { count: 2 }));
+        assert!(vec.get_guard(1).is_none());
+    }
+
+    #[test]
+    pub fn test_get_guard_writes_once() {
+        let storage_writes = || crate::mock::with_mocked_blockchain(|b| b.storage_write_count());
+        let mut vec = Vector::new(b"v".to_vec());
+        vec.extend(0u64..3);
+
+        let writes = storage_writes();
+        {
+            let guard = vec.get_guard(1).unwrap();
+            assert_eq!(*guard, 1);
+        }
+        // An element which was only read isn't written back.
+        assert_eq!(storage_writes(), writes);
+
+        {
+            let mut guard = vec.get_guard(1).unwrap();
+            for _ in 0..10 {
+                *guard += 1;
+            }
+        }
+        assert_eq!(storage_writes(), writes + 1);
+        assert_eq!(vec.to_vec(), vec![0, 11, 2]);
+    }
+
+    #[test]
+    pub fn test_get_guard_panic_leaves_element() {
+        let mut vec = Vector::new(b"v".to_vec());
+        vec.extend(0u64..3);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let mut guard = vec.get_guard(1).unwrap();
+            *guard = 100;
+            panic!("update failed");
+        }));
+        assert!(result.is_err());
+        assert_eq!(vec.to_vec(), vec![0, 1, 2]);
+    }
+
     #[test]
     pub fn test_swap_remove() {
         let mut rng = rand_xorshift::XorShiftRng::seed_from_u64(2);
@@ -435,6 +1181,51 @@ mod tests {
         }
     }
 
+    #[test]
+    pub fn test_clear_reuse() {
+        let before = crate::test_utils::storage_snapshot();
+        let mut vec = Vector::new(b"v".to_vec());
+        vec.extend(0u64..10);
+        vec.clear();
+        assert!(vec.is_storage_clean());
+        assert_eq!(crate::test_utils::storage_snapshot(), before);
+
+        vec.extend(0u64..3);
+        assert!(vec.is_storage_clean());
+        assert_eq!(vec.to_vec(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    pub fn test_clear_step() {
+        let mut vec = Vector::new(b"v".to_vec());
+        let usage = env::storage_usage();
+        vec.extend(0u64..50_000);
+
+        assert_eq!(vec.clear_step(20_000), 30_000);
+        assert_eq!(vec.get(29_999), Some(29_999));
+        assert_eq!(vec.get(30_000), None);
+        // The vector is usable between steps.
+        vec.push(&1);
+        assert_eq!(vec.clear_step(20_000), 10_001);
+        assert_eq!(vec.clear_step(20_000), 0);
+        assert_eq!(vec.clear_step(20_000), 0);
+
+        assert!(vec.is_empty());
+        for i in 0..50_000u64 {
+            assert!(!env::storage_has_key(&[b"v".as_ref(), &i.to_le_bytes()].concat()));
+        }
+        assert_eq!(env::storage_usage(), usage);
+    }
+
+    #[test]
+    pub fn test_std_conversion() {
+        let vec = Vector::from_std(b"v", vec![3u64, 1, 2]);
+        assert_eq!(vec.len(), 3);
+        assert_eq!(vec.get(0), Some(3));
+        assert_eq!(vec.to_std(3), Ok(vec![3, 1, 2]));
+        assert_eq!(vec.to_std(2), Err(TooLarge { len: 3, max: 2 }));
+    }
+
     #[test]
     pub fn test_extend() {
         let mut rng = rand_xorshift::XorShiftRng::seed_from_u64(0);
@@ -459,6 +1250,286 @@ mod tests {
         assert_eq!(actual, baseline);
     }
 
+    #[test]
+    pub fn test_iter_reads_only_yielded() {
+        let mut vec = Vector::new(b"v".to_vec());
+        vec.extend(0u64..100);
+        // Remove all elements but the first 5 and the last 20 from storage. Reading any of the
+        // removed elements panics, so these are never read below.
+        for i in 5..80u64 {
+            assert!(env::storage_remove(&[b"v".as_ref(), &i.to_le_bytes()].concat()));
+        }
+
+        let iter = vec.iter();
+        assert_eq!(iter.len(), 100);
+        let last: Vec<u64> = iter.rev().take(20).collect();
+        assert_eq!(last, (80..100).rev().collect::<Vec<_>>());
+
+        let mut iter = vec.iter();
+        assert_eq!(iter.nth(82), Some(82));
+        assert_eq!(iter.len(), 17);
+        assert_eq!(iter.nth_back(15), Some(84));
+        assert_eq!(iter.len(), 1);
+        assert_eq!(vec.iter().skip(4).take(1).collect::<Vec<_>>(), [4]);
+    }
+
+    #[test]
+    #[should_panic(expected = "The collection is an inconsistent state")]
+    pub fn test_iter_inconsistent_state() {
+        let mut vec = Vector::new(b"v".to_vec());
+        vec.extend(0u64..3);
+        env::storage_remove(&[b"v".as_ref(), &1u64.to_le_bytes()].concat());
+        let _ = vec.iter().collect::<Vec<_>>();
+    }
+
+    #[test]
+    pub fn test_iter_mixed_ends() {
+        let mut vec = Vector::new(b"v".to_vec());
+        vec.extend(0u64..5);
+
+        let mut iter = vec.iter();
+        assert_eq!(iter.next(), Some(0));
+        assert_eq!(iter.next_back(), Some(4));
+        assert_eq!(iter.next_back(), Some(3));
+        assert_eq!(iter.len(), 2);
+        assert_eq!(iter.next(), Some(1));
+        assert_eq!(iter.next(), Some(2));
+        assert_eq!(iter.len(), 0);
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+
+        let mut iter = vec.iter_raw();
+        assert!(iter.nth_back(5).is_none());
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    pub fn test_paginated() {
+        let mut vec = Vector::new(b"v".to_vec());
+        vec.extend(0u64..10);
+
+        assert_eq!(vec.to_paginated_vec(0, 3), [0, 1, 2]);
+        assert_eq!(vec.to_paginated_vec(8, 3), [8, 9]);
+        assert!(vec.to_paginated_vec(10, 3).is_empty());
+        assert!(vec.to_paginated_vec(20, 3).is_empty());
+        assert!(vec.to_paginated_vec(2, 0).is_empty());
+        assert_eq!(vec.to_paginated_vec(7, u64::MAX), [7, 8, 9]);
+        assert!(vec.to_paginated_vec(u64::MAX, u64::MAX).is_empty());
+    }
+
+    #[test]
+    pub fn test_iter_range() {
+        let mut vec = Vector::new(b"v".to_vec());
+        vec.extend(0u64..10);
+
+        assert_eq!(vec.iter_range(..).collect::<Vec<_>>(), vec.to_vec());
+        assert_eq!(vec.iter_range(3..5).collect::<Vec<_>>(), [3, 4]);
+        assert_eq!(vec.iter_range(3..=5).rev().collect::<Vec<_>>(), [5, 4, 3]);
+        assert_eq!(vec.iter_range(8..).len(), 2);
+        assert_eq!(vec.iter_range(..=u64::MAX).len(), 10);
+        assert_eq!(vec.iter_range((Bound::Included(5), Bound::Excluded(3))).len(), 0);
+        assert_eq!(vec.iter_range(12..20).len(), 0);
+        assert_eq!(vec.iter_range((Bound::Excluded(u64::MAX), Bound::Unbounded)).len(), 0);
+    }
+
+    #[test]
+    pub fn test_extend_trait() {
+        let mut vec: Vector<u64> = Vector::new(b"v".to_vec());
+        let before = env::storage_usage();
+        Extend::extend(&mut vec, vec![1u64, 2]);
+        Extend::extend(&mut vec, &[3u64, 4]);
+        vec.push_many(&[5, 6]);
+        assert_eq!(vec.to_vec(), [1, 2, 3, 4, 5, 6]);
+        let used = env::storage_usage() - before;
+
+        // Only one storage record is added per element, no record is used for the length.
+        let mut single = Vector::new(b"s".to_vec());
+        let single_before = env::storage_usage();
+        single.push(&1u64);
+        assert_eq!(used, 6 * (env::storage_usage() - single_before));
+    }
+
+    #[test]
+    pub fn test_push_many_all_or_nothing() {
+        struct Element(bool);
+        impl BorshSerialize for Element {
+            fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+                if self.0 {
+                    self.0.serialize(writer)
+                } else {
+                    Err(std::io::Error::new(std::io::ErrorKind::Other, "unserializable"))
+                }
+            }
+        }
+
+        let mut vec = Vector::new(b"v".to_vec());
+        vec.push_many(&[Element(true)]);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            vec.push_many(&[Element(true), Element(false)]);
+        }));
+        assert!(result.is_err());
+        assert_eq!(vec.len(), 1);
+        assert!(!env::storage_has_key(&[b"v".as_ref(), &1u64.to_le_bytes()].concat()));
+    }
+
+    #[test]
+    pub fn test_swap_remove_undecodable_writes_nothing() {
+        let mut vec = Vector::new(b"v".to_vec());
+        vec.push(&Fragile { serializable: true });
+        vec.push(&Fragile { serializable: true });
+        assert_panics_without_writes(|| vec.swap_remove(0));
+        assert_panics_without_writes(|| vec.swap_remove(1));
+        assert_eq!(vec.len(), 2);
+        assert_panics_without_writes(|| vec.push(&Fragile { serializable: false }));
+        assert_eq!(vec.len(), 2);
+    }
+
+    #[test]
+    pub fn test_binary_search() {
+        let mut vec = Vector::new(b"v".to_vec());
+        assert_eq!(vec.binary_search(&5), Err(0));
+        assert_eq!(vec.partition_point(|&x| x < 5), 0);
+
+        let baseline: Vec<u64> = vec![1, 2, 3, 3, 3, 5, 8, 13];
+        vec.extend(baseline.iter().copied());
+        for x in 0..15 {
+            let expected = baseline.binary_search(&x);
+            let actual = vec.binary_search(&x);
+            match expected {
+                // Any matching index is acceptable with duplicates.
+                Ok(_) => assert_eq!(baseline[actual.unwrap() as usize], x),
+                Err(i) => assert_eq!(actual, Err(i as u64)),
+            }
+            assert_eq!(
+                vec.partition_point(|&v| v < x),
+                baseline.partition_point(|&v| v < x) as u64
+            );
+        }
+        assert_eq!(vec.binary_search_by_key(&26, |&v| v * 2), Ok(7));
+        assert_eq!(vec.binary_search_by(|v| v.cmp(&4)), Err(5));
+    }
+
+    #[test]
+    pub fn test_insert_remove() {
+        let mut rng = rand_xorshift::XorShiftRng::seed_from_u64(5);
+        let mut vec = Vector::new(b"v".to_vec());
+        let mut baseline: Vec<u64> = vec![];
+        for _ in 0..300 {
+            if baseline.is_empty() || rng.gen::<bool>() {
+                let index = rng.gen::<u64>() % (vec.len() + 1);
+                let value = rng.gen::<u64>();
+                vec.insert(index, &value);
+                baseline.insert(index as usize, value);
+            } else {
+                let index = rng.gen::<u64>() % vec.len();
+                assert_eq!(vec.remove(index), baseline.remove(index as usize));
+            }
+            assert_eq!(vec.len(), baseline.len() as u64);
+        }
+        assert_eq!(vec.to_vec(), baseline);
+        for (i, value) in baseline.iter().enumerate() {
+            assert_eq!(vec.get(i as u64).as_ref(), Some(value));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Index out of bounds")]
+    pub fn test_insert_out_of_bounds() {
+        let mut vec = Vector::new(b"v".to_vec());
+        vec.extend(0..2u64);
+        vec.insert(3, &5);
+    }
+
+    #[test]
+    #[should_panic(expected = "Index out of bounds")]
+    pub fn test_remove_out_of_bounds() {
+        let mut vec = Vector::new(b"v".to_vec());
+        vec.extend(0..2u64);
+        vec.remove(2);
+    }
+
+    #[test]
+    pub fn test_truncate() {
+        let mut vec = Vector::new(b"v".to_vec());
+        vec.extend(0..10u64);
+
+        vec.truncate(10);
+        vec.truncate(20);
+        assert_eq!(vec.to_vec(), (0..10).collect::<Vec<u64>>());
+
+        vec.truncate(4);
+        assert_eq!(vec.len(), 4);
+        assert_eq!(vec.to_vec(), vec![0, 1, 2, 3]);
+        for i in 0..10 {
+            assert_eq!(env::storage_has_key(&vec.index_to_lookup_key(i)), i < 4);
+        }
+
+        vec.truncate(0);
+        assert!(vec.is_empty());
+        assert!(!env::storage_has_key(&vec.index_to_lookup_key(0)));
+    }
+
+    #[test]
+    pub fn test_resize() {
+        let mut vec = Vector::new(b"v".to_vec());
+        vec.extend(0..3u64);
+
+        vec.resize(6, 7);
+        assert_eq!(vec.to_vec(), vec![0, 1, 2, 7, 7, 7]);
+
+        vec.resize(2, 7);
+        assert_eq!(vec.to_vec(), vec![0, 1]);
+        for i in 2..6 {
+            assert!(!env::storage_has_key(&vec.index_to_lookup_key(i)));
+        }
+
+        vec.resize(2, 9);
+        assert_eq!(vec.to_vec(), vec![0, 1]);
+    }
+
+    #[test]
+    pub fn test_drain() {
+        let mut vec = Vector::new(b"v".to_vec());
+        vec.extend(0..10u64);
+
+        // Empty range
+        assert_eq!(vec.drain(3..3).count(), 0);
+        assert_eq!(vec.to_vec(), (0..10).collect::<Vec<u64>>());
+
+        // Middle range
+        let drained: Vec<u64> = vec.drain(2..5).collect();
+        assert_eq!(drained, vec![2, 3, 4]);
+        assert_eq!(vec.len(), 7);
+        assert_eq!(vec.to_vec(), vec![0, 1, 5, 6, 7, 8, 9]);
+        for (i, v) in vec.to_vec().into_iter().enumerate() {
+            assert_eq!(vec.get(i as u64), Some(v));
+        }
+        assert_eq!(vec.get(7), None);
+
+        // Inclusive range at the end
+        let drained: Vec<u64> = vec.drain(5..=6).collect();
+        assert_eq!(drained, vec![8, 9]);
+        assert_eq!(vec.to_vec(), vec![0, 1, 5, 6, 7]);
+
+        // Full range
+        let drained: Vec<u64> = vec.drain(..).collect();
+        assert_eq!(drained, vec![0, 1, 5, 6, 7]);
+        assert!(vec.is_empty());
+        assert!(vec.iter_raw().next().is_none());
+
+        // Remaining storage is still usable
+        vec.push(&11);
+        assert_eq!(vec.to_vec(), vec![11]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Drain range end 6 is out of bounds for vector of length 5")]
+    pub fn test_drain_out_of_bounds() {
+        let mut vec = Vector::new(b"v".to_vec());
+        vec.extend(0..5u64);
+        let _ = vec.drain(2..6);
+    }
+
     #[test]
     fn test_debug() {
         let mut rng = rand_xorshift::XorShiftRng::seed_from_u64(4);
@@ -487,8 +1558,12 @@ mod tests {
         #[derive(Debug, BorshDeserialize)]
         struct WithoutBorshSerialize(u64);
 
-        let deserialize_only_vec =
-            Vector::<WithoutBorshSerialize> { len: vec.len(), prefix, el: Default::default() };
+        let deserialize_only_vec = Vector::<WithoutBorshSerialize> {
+            len: vec.len(),
+            prefix,
+            el: Default::default(),
+            handle: Default::default(),
+        };
         let baseline: Vec<_> = baseline.into_iter().map(WithoutBorshSerialize).collect();
         if cfg!(feature = "expensive-debug") {
             assert_eq!(format!("{:#?}", deserialize_only_vec), format!("{:#?}", baseline));
@@ -526,4 +1601,58 @@ mod tests {
         // Count check
         assert_eq!(vec.iter().count(), baseline.len());
     }
+
+    #[test]
+    fn try_accessors_report_corrupted_element() {
+        let mut vec = Vector::new(b"v");
+        vec.extend(0u64..4);
+        env::storage_write(&[b"v".as_ref(), &1u64.to_le_bytes()].concat(), &[0xff]);
+
+        let err = vec.try_get(1).unwrap_err();
+        assert_eq!(err.kind(), CollectionErrorKind::DeserializationFailed);
+        assert_eq!(err.prefix(), b"v");
+        assert_eq!(err.key(), 1u64.to_le_bytes());
+        assert_eq!(vec.try_get(2), Ok(Some(2)));
+        assert_eq!(vec.try_get(4), Ok(None));
+
+        // The corrupted element is removed anyway.
+        assert_eq!(vec.try_remove(1).unwrap_err(), err);
+        assert_eq!(vec.to_vec(), [0, 2, 3]);
+        assert_eq!(vec.try_insert(1, &1), Ok(()));
+        assert_eq!(vec.to_vec(), [0, 1, 2, 3]);
+
+        env::storage_remove(&[b"v".as_ref(), &2u64.to_le_bytes()].concat());
+        let err = vec.try_get(2).unwrap_err();
+        assert_eq!(err.kind(), CollectionErrorKind::InconsistentState);
+        assert_eq!(err.key(), 2u64.to_le_bytes());
+    }
+
+    #[test]
+    fn storage_cost_of() {
+        let mut vec = Vector::new(b"v");
+        for len in [0, 1, 10, 100, 1000].iter() {
+            let element = vec![7u8; *len];
+            let cost = vec.storage_cost_of(&element);
+            let usage = env::storage_usage();
+            vec.push(&element);
+            assert_eq!(env::storage_usage() - usage, cost);
+        }
+    }
+
+    fn collect<I: IntoIterator>(collection: I) -> Vec<I::Item> {
+        collection.into_iter().collect()
+    }
+
+    #[test]
+    fn test_into_iter() {
+        let mut vec = Vector::new(b"v");
+        vec.extend(0u64..5);
+        assert_eq!(collect(&vec), [0, 1, 2, 3, 4]);
+
+        let mut sum = 0;
+        for element in &vec {
+            sum += element;
+        }
+        assert_eq!(sum, 10);
+    }
 }
@@ -0,0 +1,295 @@
+//! Items with weights, from which an item can be sampled with a probability proportional to its
+//! weight, for raffles or validator selection.
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::collections::{append, Vector};
+use crate::environment::register_prefix;
+use crate::errors::SdkError;
+use crate::utils::Rng;
+use crate::{env, IntoStorageKey};
+
+/// Lowest set bit of `i`.
+fn lowbit(i: u64) -> u64 {
+    i & i.wrapping_neg()
+}
+
+/// A list of items with `u128` weights, sampled with a probability proportional to their weight.
+///
+/// The weights are kept in a [Fenwick tree](https://en.wikipedia.org/wiki/Fenwick_tree), stored
+/// in a [`Vector<u128>`] next to the [`Vector`] of items: node `i` (counting from 1) holds the
+/// sum of the `lowbit(i)` weights ending at item `i`. Pushing an item, updating a weight and
+/// sampling each read or write `O(log n)` storage entries, so the index scales to thousands of
+/// items. Items with a weight of zero are never sampled.
+///
+/// The randomness comes from a [`Rng`], so the predictability caveat of its documentation
+/// applies to every sample.
+///
+/// # Examples
+/// ```
+/// use near_sdk::collections::WeightedIndex;
+/// use near_sdk::utils::Rng;
+///
+/// let mut tickets = WeightedIndex::new(b"t");
+/// tickets.push(&"alice".to_string(), 3);
+/// tickets.push(&"bob".to_string(), 1);
+/// assert_eq!(tickets.total_weight(), 4);
+///
+/// let winner = tickets.sample(&mut Rng::new([0; 32])).unwrap();
+/// assert!(winner == "alice" || winner == "bob");
+/// ```
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct WeightedIndex<T> {
+    items: Vector<T>,
+    tree: Vector<u128>,
+    total_weight: u128,
+}
+
+impl<T> WeightedIndex<T> {
+    /// Create new index with no items. Use `prefix` as a unique identifier.
+    pub fn new<S>(prefix: S) -> Self
+    where
+        S: IntoStorageKey,
+    {
+        let prefix = prefix.into_storage_key();
+        let _scope = register_prefix::<Self>(&prefix);
+        Self {
+            items: Vector::new(append(&prefix, b'i')),
+            tree: Vector::new(append(&prefix, b'w')),
+            total_weight: 0,
+        }
+    }
+
+    /// Returns the number of items.
+    pub fn len(&self) -> u64 {
+        self.items.len()
+    }
+
+    /// Returns `true` if there are no items.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Returns the sum of the weights of all items.
+    pub fn total_weight(&self) -> u128 {
+        self.total_weight
+    }
+
+    /// Reads node `i` of the tree, counting from 1.
+    fn node(&self, i: u64) -> u128 {
+        self.tree.get(i - 1).unwrap_or_else(|| {
+            SdkError::CollectionInconsistent { collection: "WeightedIndex" }.panic()
+        })
+    }
+
+    /// Returns the sum of the weights of the first `count` items.
+    fn prefix_weight(&self, mut count: u64) -> u128 {
+        let mut sum = 0;
+        while count > 0 {
+            sum += self.node(count);
+            count -= lowbit(count);
+        }
+        sum
+    }
+
+    /// Returns the weight of the item at `index`, or `None` if it's out of bounds.
+    pub fn weight(&self, index: u64) -> Option<u128> {
+        if index >= self.len() {
+            return None;
+        }
+        Some(self.prefix_weight(index + 1) - self.prefix_weight(index))
+    }
+
+    /// Sets the weight of the item at `index` and returns its previous weight.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds, or if the total weight would overflow a `u128`.
+    pub fn update_weight(&mut self, index: u64, new_weight: u128) -> u128 {
+        let old_weight = self
+            .weight(index)
+            .unwrap_or_else(|| SdkError::IndexOutOfBounds { index, len: self.len() }.panic());
+        self.total_weight = (self.total_weight - old_weight)
+            .checked_add(new_weight)
+            .unwrap_or_else(|| env::panic_str("The total weight overflows a u128"));
+        let mut i = index + 1;
+        while i <= self.len() {
+            // Each node includes the old weight, so neither operation can overflow.
+            let node = self.node(i) - old_weight + new_weight;
+            self.tree.replace(i - 1, &node);
+            i += lowbit(i);
+        }
+        old_weight
+    }
+
+    /// Samples the index of an item, with a probability proportional to its weight. Returns
+    /// `None` if the total weight is zero.
+    pub fn sample_index(&self, rng: &mut Rng) -> Option<u64> {
+        if self.total_weight == 0 {
+            return None;
+        }
+        let mut remaining = rng.rand_range_u128(self.total_weight);
+        // Binary search for the first item whose cumulative weight exceeds `remaining`, going
+        // down the tree from its largest power of two.
+        let len = self.len();
+        let mut position = 0;
+        let mut step = 1 << (63 - len.leading_zeros());
+        while step > 0 {
+            if position + step <= len {
+                let node = self.node(position + step);
+                if node <= remaining {
+                    position += step;
+                    remaining -= node;
+                }
+            }
+            step >>= 1;
+        }
+        Some(position)
+    }
+
+    /// Removes all items.
+    pub fn clear(&mut self) {
+        self.items.clear();
+        self.tree.clear();
+        self.total_weight = 0;
+    }
+}
+
+impl<T> WeightedIndex<T>
+where
+    T: BorshSerialize + BorshDeserialize,
+{
+    /// Returns the item at `index`, or `None` if it's out of bounds.
+    pub fn get(&self, index: u64) -> Option<T> {
+        self.items.get(index)
+    }
+
+    /// Appends `item` with `weight`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the total weight would overflow a `u128`.
+    pub fn push(&mut self, item: &T, weight: u128) {
+        self.total_weight = self
+            .total_weight
+            .checked_add(weight)
+            .unwrap_or_else(|| env::panic_str("The total weight overflows a u128"));
+        // The new node covers the item and the nodes of the previous items in its range.
+        let i = self.len() + 1;
+        let mut node = weight;
+        let mut j = i - 1;
+        while j > i - lowbit(i) {
+            node += self.node(j);
+            j -= lowbit(j);
+        }
+        self.tree.push(&node);
+        self.items.push(item);
+    }
+
+    /// Samples an item, with a probability proportional to its weight. Returns `None` if the
+    /// total weight is zero.
+    pub fn sample(&self, rng: &mut Rng) -> Option<T> {
+        self.sample_index(rng).and_then(|index| self.get(index))
+    }
+}
+
+impl<T> std::fmt::Debug for WeightedIndex<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WeightedIndex")
+            .field("len", &self.len())
+            .field("total_weight", &self.total_weight)
+            .finish()
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use super::WeightedIndex;
+    use crate::utils::Rng;
+
+    fn index_with_weights(weights: &[u128]) -> WeightedIndex<u64> {
+        let mut index = WeightedIndex::new(b"w");
+        for (item, &weight) in weights.iter().enumerate() {
+            index.push(&(item as u64), weight);
+        }
+        index
+    }
+
+    fn sample_counts(index: &WeightedIndex<u64>, samples: u32) -> Vec<u32> {
+        let mut rng = Rng::new([42; 32]);
+        let mut counts = vec![0; index.len() as usize];
+        for _ in 0..samples {
+            counts[index.sample(&mut rng).unwrap() as usize] += 1;
+        }
+        counts
+    }
+
+    #[test]
+    fn weights_and_prefix_sums() {
+        let weights: Vec<u128> = (0..37).map(|i| i * 7 % 11).collect();
+        let index = index_with_weights(&weights);
+        assert_eq!(index.total_weight(), weights.iter().sum::<u128>());
+        for (i, &weight) in weights.iter().enumerate() {
+            assert_eq!(index.weight(i as u64), Some(weight));
+            assert_eq!(index.prefix_weight(i as u64 + 1), weights[..=i].iter().sum::<u128>());
+        }
+        assert_eq!(index.weight(37), None);
+    }
+
+    #[test]
+    fn distribution() {
+        let index = index_with_weights(&[1, 2, 3, 4]);
+        let counts = sample_counts(&index, 10_000);
+        for (count, expected) in counts.iter().zip([1_000, 2_000, 3_000, 4_000]) {
+            assert!((expected * 9 / 10..expected * 11 / 10).contains(count), "{:?}", counts);
+        }
+    }
+
+    #[test]
+    fn zero_weights_never_sampled() {
+        let index = index_with_weights(&[0, 5, 0, 0, 1, 0]);
+        let counts = sample_counts(&index, 2_000);
+        assert_eq!([counts[0], counts[2], counts[3], counts[5]], [0; 4]);
+        assert_eq!(counts[1] + counts[4], 2_000);
+
+        let empty = index_with_weights(&[0, 0]);
+        assert_eq!(empty.sample(&mut Rng::new([0; 32])), None);
+        assert_eq!(WeightedIndex::<u64>::new(b"e").sample(&mut Rng::new([0; 32])), None);
+    }
+
+    #[test]
+    fn update_weight() {
+        let mut index = index_with_weights(&[5, 5, 5, 5, 5]);
+        assert_eq!(index.update_weight(1, 0), 5);
+        assert_eq!(index.update_weight(3, 20), 5);
+        assert_eq!(index.total_weight(), 35);
+        assert_eq!(index.weight(3), Some(20));
+        assert_eq!(index.prefix_weight(5), 35);
+
+        let counts = sample_counts(&index, 7_000);
+        assert_eq!(counts[1], 0);
+        assert!((3_600..4_400).contains(&counts[3]), "{:?}", counts);
+
+        // Items pushed after an update include it in their nodes.
+        index.push(&5, 10);
+        assert_eq!(index.prefix_weight(6), 45);
+        assert_eq!(index.weight(5), Some(10));
+    }
+
+    #[test]
+    #[should_panic(expected = "The total weight overflows a u128")]
+    fn total_weight_overflow() {
+        index_with_weights(&[u128::MAX, 1]);
+    }
+
+    #[test]
+    fn clear() {
+        let mut index = index_with_weights(&[1, 2, 3]);
+        index.clear();
+        assert!(index.is_empty());
+        assert_eq!(index.total_weight(), 0);
+        assert!(crate::collections::mocked_storage_suffixes(b"w", None).is_empty());
+        index.push(&7, 1);
+        assert_eq!(index.sample(&mut Rng::new([0; 32])), Some(7));
+    }
+}
@@ -7,6 +7,7 @@ use std::mem::size_of;
 use std::panic as std_panic;
 use std::{convert::TryFrom, mem::MaybeUninit};
 
+use crate::errors::SdkError;
 #[cfg(not(target_arch = "wasm32"))]
 use crate::mock::MockedBlockchain;
 use crate::types::{
@@ -14,9 +15,6 @@ use crate::types::{
 };
 use near_sys as sys;
 
-const REGISTER_EXPECTED_ERR: &str =
-    "Register was expected to have data because we just wrote it into it.";
-
 /// Register used internally for atomic operations. This register is safe to use by the user,
 /// since it only needs to be untouched while methods of `Environment` execute, which is guaranteed
 /// guest code is not parallel.
@@ -25,7 +23,7 @@ const ATOMIC_OP_REGISTER: u64 = std::u64::MAX - 2;
 const EVICTED_REGISTER: u64 = std::u64::MAX - 1;
 
 /// Key used to store the state of the contract.
-const STATE_KEY: &[u8] = b"STATE";
+pub(crate) const STATE_KEY: &[u8] = b"STATE";
 
 /// The minimum length of a valid account ID.
 const MIN_ACCOUNT_ID_LEN: u64 = 2;
@@ -33,7 +31,7 @@ const MIN_ACCOUNT_ID_LEN: u64 = 2;
 const MAX_ACCOUNT_ID_LEN: u64 = 64;
 
 fn expect_register<T>(option: Option<T>) -> T {
-    option.unwrap_or_else(|| panic_str(REGISTER_EXPECTED_ERR))
+    option.unwrap_or_else(|| SdkError::RegisterEmpty.panic())
 }
 
 /// A simple macro helper to read blob value coming from host's method.
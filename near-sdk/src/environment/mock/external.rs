@@ -16,6 +16,13 @@ pub(crate) struct SdkExternal {
     pub fake_trie: HashMap<Vec<u8>, Vec<u8>>,
     pub receipts: Vec<Receipt>,
     pub validators: HashMap<String, Balance>,
+    /// Number of values written to `fake_trie`.
+    pub storage_writes: u64,
+    /// Number of `storage_has_key` calls.
+    pub storage_has_key_calls: u64,
+    /// Number of `storage_read` calls made by the contract. Not counted in `storage_get`, which is
+    /// also called to read the values evicted by writes and removals.
+    pub storage_reads: u64,
 }
 
 pub struct MockedValuePtr {
@@ -41,6 +48,7 @@ impl SdkExternal {
 impl External for SdkExternal {
     fn storage_set(&mut self, key: &[u8], value: &[u8]) -> Result<()> {
         self.fake_trie.insert(key.to_vec(), value.to_vec());
+        self.storage_writes += 1;
         Ok(())
     }
 
@@ -62,6 +70,7 @@ impl External for SdkExternal {
     }
 
     fn storage_has_key(&mut self, key: &[u8]) -> Result<bool> {
+        self.storage_has_key_calls += 1;
         Ok(self.fake_trie.contains_key(key))
     }
 
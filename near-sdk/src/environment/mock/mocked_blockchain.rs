@@ -6,7 +6,7 @@ use near_vm_logic::mocks::mock_memory::MockedMemory;
 use near_vm_logic::types::PromiseResult as VmPromiseResult;
 use near_vm_logic::{External, MemoryLike, VMConfig, VMContext, VMLogic, VMOutcome};
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 /// Mocked blockchain that can be used in the tests for the smart contracts.
 /// It implements `BlockchainInterface` by redirecting calls to `VMLogic`. It unwraps errors of
@@ -18,11 +18,19 @@ pub struct MockedBlockchain {
     // We keep ownership over logic fixture so that references in `VMLogic` are valid.
     #[allow(dead_code)]
     logic_fixture: LogicFixture,
+    /// Context the current `VMLogic` was created with, used to re-create it on context updates.
+    context: VMContext,
+    /// Whether this instance was configured explicitly, rather than being the default instance
+    /// used before any `testing_env!` call.
+    initialized: bool,
+    /// Prefixes of the collections created in the current call.
+    #[cfg(feature = "check-prefix-collisions")]
+    pub(crate) prefix_registry: super::PrefixRegistry,
 }
 
 impl Default for MockedBlockchain {
     fn default() -> Self {
-        MockedBlockchain::new(
+        let mut blockchain = MockedBlockchain::new(
             VMContextBuilder::new().build(),
             VMConfig::test(),
             RuntimeFeesConfig::test(),
@@ -30,7 +38,9 @@ impl Default for MockedBlockchain {
             Default::default(),
             Default::default(),
             None,
-        )
+        );
+        blockchain.initialized = false;
+        blockchain
     }
 }
 
@@ -61,12 +71,15 @@ impl MockedBlockchain {
         let config = Box::new(config);
         let fees_config = Box::new(fees_config);
 
-        let mut logic_fixture = LogicFixture { ext, memory, promise_results, config, fees_config };
+        let logic_fixture = LogicFixture { ext, memory, promise_results, config, fees_config };
+        Self::from_fixture(context, logic_fixture)
+    }
 
+    fn from_fixture(context: VMContext, mut logic_fixture: LogicFixture) -> Self {
         let logic = unsafe {
             VMLogic::new_with_protocol_version(
                 &mut *(logic_fixture.ext.as_mut() as *mut dyn External),
-                context,
+                context.clone(),
                 &*(logic_fixture.config.as_mut() as *const VMConfig),
                 &*(logic_fixture.fees_config.as_mut() as *const RuntimeFeesConfig),
                 &*(logic_fixture.promise_results.as_ref().as_slice() as *const [VmPromiseResult]),
@@ -76,13 +89,82 @@ impl MockedBlockchain {
         };
 
         let logic = RefCell::new(logic);
-        Self { logic, logic_fixture }
+        Self {
+            logic,
+            logic_fixture,
+            context,
+            initialized: true,
+            #[cfg(feature = "check-prefix-collisions")]
+            prefix_registry: Default::default(),
+        }
+    }
+
+    /// Updates the [`VMContext`] of the current execution in place. Storage, configuration,
+    /// validators, promise results and created receipts are preserved, and the storage usage and
+    /// account balance reflect any changes made so far. Logs and gas usage are reset, as if a new
+    /// function call was started with the updated context.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mocked blockchain was not initialized with `testing_env!`.
+    pub fn update_context<F>(&mut self, f: F)
+    where
+        F: FnOnce(&mut VMContext),
+    {
+        if !self.initialized {
+            panic!("`testing_env!` must be called before updating the mocked context");
+        }
+        let outcome = self.outcome();
+        let mut context = self.context.clone();
+        context.storage_usage = outcome.storage_usage;
+        context.account_balance = outcome.balance;
+        f(&mut context);
+
+        let fixture = &mut self.logic_fixture;
+        let ext = Box::new(SdkExternal {
+            fake_trie: std::mem::take(&mut fixture.ext.fake_trie),
+            receipts: std::mem::take(&mut fixture.ext.receipts),
+            validators: std::mem::take(&mut fixture.ext.validators),
+            storage_writes: fixture.ext.storage_writes,
+            storage_has_key_calls: fixture.ext.storage_has_key_calls,
+            storage_reads: fixture.ext.storage_reads,
+        });
+        let logic_fixture = LogicFixture {
+            ext,
+            memory: Box::new(MockedMemory {}),
+            promise_results: fixture.promise_results.clone(),
+            config: fixture.config.clone(),
+            fees_config: fixture.fees_config.clone(),
+        };
+        *self = Self::from_fixture(context, logic_fixture);
     }
 
     pub fn take_storage(&mut self) -> HashMap<Vec<u8>, Vec<u8>> {
         std::mem::take(&mut self.logic_fixture.ext.fake_trie)
     }
 
+    /// Returns a copy of the current storage, ordered by key.
+    pub fn storage_snapshot(&self) -> BTreeMap<Vec<u8>, Vec<u8>> {
+        self.logic_fixture.ext.fake_trie.iter().map(|(k, v)| (k.clone(), v.clone())).collect()
+    }
+
+    /// Returns the number of storage writes made since the mocked blockchain was created, to
+    /// assert how many writes an operation takes.
+    pub fn storage_write_count(&self) -> u64 {
+        self.logic_fixture.ext.storage_writes
+    }
+
+    /// Returns the number of `storage_read` calls made since the mocked blockchain was created, to
+    /// assert how many reads an operation takes.
+    pub fn storage_read_count(&self) -> u64 {
+        self.logic_fixture.ext.storage_reads
+    }
+
+    /// Returns the number of `storage_has_key` calls made since the mocked blockchain was created.
+    pub fn storage_has_key_count(&self) -> u64 {
+        self.logic_fixture.ext.storage_has_key_calls
+    }
+
     pub fn created_receipts(&self) -> &Vec<Receipt> {
         &self.logic_fixture.ext.receipts
     }
@@ -439,7 +521,10 @@ mod mock_chain {
     }
     #[no_mangle]
     extern "C" fn storage_read(key_len: u64, key_ptr: u64, register_id: u64) -> u64 {
-        with_mock_interface(|b| b.storage_read(key_len, key_ptr, register_id))
+        crate::mock::with_mocked_blockchain(|b| {
+            b.logic_fixture.ext.storage_reads += 1;
+            b.logic.borrow_mut().storage_read(key_len, key_ptr, register_id).unwrap()
+        })
     }
     #[no_mangle]
     extern "C" fn storage_remove(key_len: u64, key_ptr: u64, register_id: u64) -> u64 {
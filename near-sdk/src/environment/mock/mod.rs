@@ -1,9 +1,15 @@
 mod external;
 mod mocked_blockchain;
+#[cfg(feature = "check-prefix-collisions")]
+mod prefix_registry;
 mod receipt;
 
 pub(crate) use self::external::SdkExternal;
 pub use self::mocked_blockchain::MockedBlockchain;
+#[cfg(feature = "check-prefix-collisions")]
+pub(crate) use self::prefix_registry::{nested_scope, register_prefix, PrefixRegistry};
+#[cfg(feature = "check-prefix-collisions")]
+pub use self::prefix_registry::{set_prefix_policy, PrefixPolicy};
 pub use self::receipt::{Receipt, VmAction};
 use crate::AccountId;
 use core::cell::RefCell;
@@ -0,0 +1,170 @@
+//! Detection of collections created with colliding prefixes, enabled with the
+//! `check-prefix-collisions` feature.
+//!
+//! Every collection constructor registers its prefix and the name of its type in the
+//! [`MockedBlockchain`](super::MockedBlockchain), and panics with both type names if the prefix
+//! collides with one registered before. Collections created by the constructor of another
+//! collection, for example the vectors of an `UnorderedMap`, are part of it and aren't registered.
+//! Collections deserialized from storage aren't registered either, as they don't pick a prefix.
+//!
+//! The registry is reset by `testing_env!` and by
+//! [`MockedBlockchain::update_context`](super::MockedBlockchain::update_context), as each call
+//! starts with the collections of the contract state. A collection which is created again with
+//! the same prefix within one call, for example a nested set created again after it was emptied
+//! and removed, is reported as well.
+
+use std::cell::Cell;
+use std::collections::BTreeMap;
+
+/// Which prefixes registered by collection constructors are reported as collisions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrefixPolicy {
+    /// Report prefixes registered twice. This is the default.
+    ExactDuplicates,
+    /// Also report prefixes which are the start of another prefix, for example `b"a"` and `b"ab"`.
+    /// The keys of the two collections may overlap, depending on the keys each one stores.
+    Overlapping,
+}
+
+thread_local! {
+    static POLICY: Cell<PrefixPolicy> = Cell::new(PrefixPolicy::ExactDuplicates);
+    /// Number of collection constructors currently running.
+    static DEPTH: Cell<u32> = Cell::new(0);
+}
+
+/// Sets the policy for the prefixes of the collections created afterwards on this thread.
+pub fn set_prefix_policy(policy: PrefixPolicy) {
+    POLICY.with(|current| current.set(policy))
+}
+
+/// Prefixes registered by the collections created in the current call.
+#[derive(Default)]
+pub(crate) struct PrefixRegistry {
+    prefixes: BTreeMap<Vec<u8>, &'static str>,
+}
+
+impl PrefixRegistry {
+    fn register(
+        &mut self,
+        prefix: &[u8],
+        type_name: &'static str,
+        policy: PrefixPolicy,
+    ) -> Result<(), String> {
+        if let Some(registered) = self.prefixes.get(prefix) {
+            return Err(format!(
+                "Collections {} and {} were both created with the prefix {:?}",
+                registered, type_name, prefix
+            ));
+        }
+        if policy == PrefixPolicy::Overlapping {
+            let overlapping = self.prefixes.iter().find(|(registered, _)| {
+                registered.starts_with(prefix) || prefix.starts_with(registered)
+            });
+            if let Some((registered, registered_type)) = overlapping {
+                return Err(format!(
+                    "The prefix {:?} of collection {} overlaps the prefix {:?} of collection {}",
+                    prefix, type_name, registered, registered_type
+                ));
+            }
+        }
+        self.prefixes.insert(prefix.to_vec(), type_name);
+        Ok(())
+    }
+}
+
+/// Marks a running collection constructor, until dropped.
+pub(crate) struct PrefixScope(());
+
+impl Drop for PrefixScope {
+    fn drop(&mut self) {
+        DEPTH.with(|depth| depth.set(depth.get() - 1));
+    }
+}
+
+/// Registers the prefix of a collection of type `T` being created. Collections created while the
+/// returned scope is alive are considered part of this one.
+///
+/// # Panics
+///
+/// Panics if the prefix collides with a registered prefix under the current [`PrefixPolicy`].
+pub(crate) fn register_prefix<T: ?Sized>(prefix: &[u8]) -> PrefixScope {
+    let outer = DEPTH.with(|depth| {
+        let outer = depth.get() == 0;
+        depth.set(depth.get() + 1);
+        outer
+    });
+    let scope = PrefixScope(());
+    if outer {
+        let policy = POLICY.with(Cell::get);
+        let result = super::with_mocked_blockchain(|b| {
+            b.prefix_registry.register(prefix, std::any::type_name::<T>(), policy)
+        });
+        if let Err(message) = result {
+            crate::env::panic_str(&message)
+        }
+    }
+    scope
+}
+
+/// Marks code creating collections which are part of an existing collection, such as the nested
+/// sets of a multimap, so that they aren't registered.
+pub(crate) fn nested_scope() -> PrefixScope {
+    DEPTH.with(|depth| depth.set(depth.get() + 1));
+    PrefixScope(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collections::{JsonCodec, LookupMap, UnorderedMap, UnorderedSet, Vector};
+    use crate::test_utils::VMContextBuilder;
+    use crate::testing_env;
+
+    #[test]
+    #[should_panic(expected = "Collections near_sdk::collections::lookup_map::LookupMap<u8, u8, \
+                    near_sdk::collections::value_codec::BorshCodec> and \
+                    near_sdk::collections::vector::Vector<u8> were both created with the prefix [97]")]
+    fn exact_duplicate() {
+        let _map: LookupMap<u8, u8> = LookupMap::new(b"a");
+        let _vec: Vector<u8> = Vector::new(b"a");
+    }
+
+    #[test]
+    #[should_panic(expected = "were both created with the prefix [99]")]
+    fn codec_constructors() {
+        let _map: UnorderedMap<u8, u8, JsonCodec> = UnorderedMap::with_codec(b"c");
+        let _map: LookupMap<u8, u8, JsonCodec> = LookupMap::with_codec(b"c");
+    }
+
+    #[test]
+    fn nested_collections_are_not_registered() {
+        set_prefix_policy(PrefixPolicy::Overlapping);
+        // The vectors of the map use the prefixes `b"mk"` and `b"mv"`.
+        let _map: UnorderedMap<u8, u8> = UnorderedMap::new(b"m");
+        let _set: UnorderedSet<u8> = UnorderedSet::new(b"s");
+    }
+
+    #[test]
+    fn shadowing_allowed_by_default() {
+        let _a: LookupMap<u8, u8> = LookupMap::new(b"a");
+        let _ab: LookupMap<u8, u8> = LookupMap::new(b"ab");
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "The prefix [97, 98] of collection near_sdk::collections::vector::Vector<u8> \
+                    overlaps the prefix [97] of collection"
+    )]
+    fn shadowing_with_overlapping_policy() {
+        set_prefix_policy(PrefixPolicy::Overlapping);
+        let _a: LookupMap<u8, u8> = LookupMap::new(b"a");
+        let _ab: Vector<u8> = Vector::new(b"ab");
+    }
+
+    #[test]
+    fn reset_by_testing_env() {
+        let _map: LookupMap<u8, u8> = LookupMap::new(b"a");
+        testing_env!(VMContextBuilder::new().build());
+        let _map: LookupMap<u8, u8> = LookupMap::new(b"a");
+    }
+}
@@ -4,3 +4,26 @@ pub mod env;
 /// Mock blockchain utilities. These can only be used inside tests and are not available for
 /// a wasm32 target.
 pub mod mock;
+
+#[cfg(all(feature = "check-prefix-collisions", not(target_arch = "wasm32")))]
+pub(crate) use mock::{nested_scope, register_prefix};
+
+/// Marks a running collection constructor. Prefixes are only registered with the
+/// `check-prefix-collisions` feature in unit tests.
+#[cfg(not(all(feature = "check-prefix-collisions", not(target_arch = "wasm32"))))]
+pub(crate) struct PrefixScope;
+
+/// Registers the prefix of a collection being created. Prefixes are only registered with the
+/// `check-prefix-collisions` feature in unit tests.
+#[cfg(not(all(feature = "check-prefix-collisions", not(target_arch = "wasm32"))))]
+#[inline]
+pub(crate) fn register_prefix<T: ?Sized>(_prefix: &[u8]) -> PrefixScope {
+    PrefixScope
+}
+
+/// Marks code creating collections which are part of an existing collection.
+#[cfg(not(all(feature = "check-prefix-collisions", not(target_arch = "wasm32"))))]
+#[inline]
+pub(crate) fn nested_scope() -> PrefixScope {
+    PrefixScope
+}
@@ -0,0 +1,440 @@
+//! Errors raised by the SDK itself, for example by collections when a value cannot be
+//! (de)serialized or when the storage is in an unexpected state.
+//!
+//! Each [`SdkError`] has a stable code, returned by [`SdkError::code`], and a stable message,
+//! returned by [`SdkError::as_str`], which is what the contract panics with. The messages are the
+//! ones the SDK panicked with before [`SdkError`] existed. The [`Display`](std::fmt::Display)
+//! implementation includes the code and any extra details about the error and is meant for
+//! logging.
+//!
+//! Methods marked `#[handle_result]` can return a `Result<_, SdkError>`, which aborts the
+//! execution with the stable message of the error, see [`FunctionError`](crate::FunctionError).
+//!
+//! The fallible accessors of the collections return a [`CollectionError`] instead, which also
+//! identifies the collection and the entry that failed.
+
+use crate::{env, Balance};
+use std::fmt;
+
+/// Error raised by the SDK. Internally the SDK panics at its boundary with [`SdkError::as_str`], but
+/// it can also be used by contract code in a `Result<_, SdkError>` and aborted with
+/// [`SdkError::panic`] once it reaches a contract method.
+///
+/// # Examples
+/// ```
+/// use near_sdk::errors::SdkError;
+///
+/// fn require_deposit(attached: u128, required: u128) -> Result<(), SdkError> {
+///     if attached < required {
+///         return Err(SdkError::InsufficientDeposit { required, attached });
+///     }
+///     Ok(())
+/// }
+///
+/// let err = require_deposit(1, 2).unwrap_err();
+/// assert_eq!(err.as_str(), "Insufficient attached deposit");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SdkError {
+    /// A value could not be deserialized. `context` describes the value, e.g. `"element"`.
+    Deserialization { context: &'static str },
+    /// A value could not be serialized. `context` describes the value, e.g. `"key"`.
+    Serialization { context: &'static str },
+    /// A value could not be deserialized from JSON. `context` describes the value.
+    JsonDeserialization { context: &'static str },
+    /// A value could not be serialized to JSON. `context` describes the value.
+    JsonSerialization { context: &'static str },
+    /// A stored value was written in another format than the collection reads, for example by a
+    /// map created with `new_json` and read by a map created with `new`.
+    ValueFormatMismatch { expected: &'static str, found: &'static str },
+    /// Data expected to be in storage for the collection was missing.
+    CollectionInconsistent { collection: &'static str },
+    /// An index was outside of the bounds of a collection.
+    IndexOutOfBounds { index: u64, len: u64 },
+    /// The attached deposit is lower than required.
+    InsufficientDeposit { required: Balance, attached: Balance },
+    /// The key does not exist in a map that was indexed directly.
+    KeyNotFound,
+    /// A register expected to contain data from a host function was empty.
+    RegisterEmpty,
+}
+
+impl SdkError {
+    /// Returns the stable code of this error, which identifies its variant.
+    pub const fn code(&self) -> &'static str {
+        match self {
+            Self::Deserialization { .. } => "DESERIALIZATION",
+            Self::Serialization { .. } => "SERIALIZATION",
+            Self::JsonDeserialization { .. } => "JSON_DESERIALIZATION",
+            Self::JsonSerialization { .. } => "JSON_SERIALIZATION",
+            Self::ValueFormatMismatch { .. } => "VALUE_FORMAT_MISMATCH",
+            Self::CollectionInconsistent { .. } => "COLLECTION_INCONSISTENT",
+            Self::IndexOutOfBounds { .. } => "INDEX_OUT_OF_BOUNDS",
+            Self::InsufficientDeposit { .. } => "INSUFFICIENT_DEPOSIT",
+            Self::KeyNotFound => "KEY_NOT_FOUND",
+            Self::RegisterEmpty => "REGISTER_EMPTY",
+        }
+    }
+
+    /// Returns the stable message of this error. Unlike [`Display`](std::fmt::Display), this does
+    /// not include any variable details, so it can be matched on reliably.
+    ///
+    /// The Borsh errors keep the messages the collections panicked with: `"key"` and `"value"`
+    /// are "with Borsh", `"element"` is not, and `"set element"` is the element of a set.
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Deserialization { context } => {
+                if str_eq(context, "element") {
+                    "Cannot deserialize element"
+                } else if str_eq(context, "set element") {
+                    "Cannot deserialize element with Borsh"
+                } else {
+                    "Cannot deserialize value with Borsh"
+                }
+            }
+            Self::Serialization { context } => {
+                if str_eq(context, "key") {
+                    "Cannot serialize key with Borsh"
+                } else if str_eq(context, "element") {
+                    "Cannot serialize element"
+                } else if str_eq(context, "set element") {
+                    "Cannot serialize element with Borsh"
+                } else {
+                    "Cannot serialize value with Borsh"
+                }
+            }
+            Self::JsonDeserialization { .. } => "Cannot deserialize value from JSON",
+            Self::JsonSerialization { .. } => "Cannot serialize value to JSON",
+            Self::ValueFormatMismatch { .. } => {
+                "Stored value was written in a different format than the map reads"
+            }
+            Self::CollectionInconsistent { .. } => {
+                "The collection is an inconsistent state. Did previous smart contract execution \
+                 terminate unexpectedly?"
+            }
+            Self::IndexOutOfBounds { .. } => "Index out of bounds",
+            Self::InsufficientDeposit { .. } => "Insufficient attached deposit",
+            Self::KeyNotFound => "Key does not exist in map",
+            Self::RegisterEmpty => {
+                "Register was expected to have data because we just wrote it into it."
+            }
+        }
+    }
+
+    /// Aborts the execution with the stable message of this error, see [`SdkError::as_str`].
+    pub fn panic(&self) -> ! {
+        env::panic_str(self.as_str())
+    }
+}
+
+/// Compares strings in a `const fn`.
+const fn str_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+impl AsRef<str> for SdkError {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl fmt::Display for SdkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] ", self.code())?;
+        match self {
+            Self::Deserialization { context } => {
+                write!(f, "Cannot deserialize {} with Borsh", context)
+            }
+            Self::Serialization { context } => write!(f, "Cannot serialize {} with Borsh", context),
+            Self::JsonDeserialization { context } => {
+                write!(f, "Cannot deserialize {} from JSON", context)
+            }
+            Self::JsonSerialization { context } => {
+                write!(f, "Cannot serialize {} to JSON", context)
+            }
+            Self::ValueFormatMismatch { expected, found } => write!(
+                f,
+                "Value was written as {} but the map reads {}, use the constructor of the map \
+                 matching the format the value was written with",
+                found, expected
+            ),
+            Self::CollectionInconsistent { collection } => write!(
+                f,
+                "The collection {} is an inconsistent state. Did previous smart contract \
+                 execution terminate unexpectedly?",
+                collection
+            ),
+            Self::IndexOutOfBounds { index, len } => {
+                write!(f, "Index out of bounds: the len is {} but the index is {}", len, index)
+            }
+            Self::InsufficientDeposit { required, attached } => write!(
+                f,
+                "Insufficient attached deposit: required {} yoctoNEAR, attached {}",
+                required, attached
+            ),
+            Self::KeyNotFound | Self::RegisterEmpty => f.write_str(self.as_str()),
+        }
+    }
+}
+
+impl std::error::Error for SdkError {}
+
+/// Error returned by a method marked `#[handle_result]`, which aborts the execution once the method
+/// returns it. Implemented for every type which is `AsRef<str>`, such as [`SdkError`] and `String`,
+/// panicking with the string, and for [`CollectionError`].
+pub trait FunctionError {
+    /// Aborts the execution with this error.
+    fn panic(&self) -> !;
+}
+
+impl<T> FunctionError for T
+where
+    T: AsRef<str>,
+{
+    fn panic(&self) -> ! {
+        env::panic_str(self.as_ref())
+    }
+}
+
+/// What went wrong in a [`CollectionError`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CollectionErrorKind {
+    /// A key or a value could not be serialized.
+    SerializationFailed,
+    /// A stored value could not be deserialized, or was written in another format.
+    DeserializationFailed,
+    /// Data expected to be in storage for the collection was missing.
+    InconsistentState,
+}
+
+/// Error returned by the fallible accessors of the collections, such as
+/// [`LookupMap::try_get`](crate::collections::LookupMap::try_get), with the prefix of the
+/// collection and the serialized key which failed.
+///
+/// The panicking accessors panic with the [`Display`](fmt::Display) of this error, which starts
+/// with the stable message of the underlying [`SdkError`].
+///
+/// # Examples
+/// ```
+/// use near_sdk::collections::LookupMap;
+/// use near_sdk::errors::CollectionErrorKind;
+///
+/// LookupMap::new(b"m").insert(&1u8, &u64::MAX);
+/// let map: LookupMap<u8, String> = LookupMap::new(b"m");
+/// let err = map.try_get(&1).unwrap_err();
+/// assert_eq!(err.kind(), CollectionErrorKind::DeserializationFailed);
+/// assert_eq!(err.prefix(), b"m");
+/// assert_eq!(err.key(), [1]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CollectionError {
+    error: SdkError,
+    prefix: Vec<u8>,
+    key: Vec<u8>,
+}
+
+impl CollectionError {
+    pub(crate) fn new(error: SdkError, prefix: &[u8], key: &[u8]) -> Self {
+        Self { error, prefix: prefix.to_vec(), key: key.to_vec() }
+    }
+
+    /// Returns what went wrong.
+    pub fn kind(&self) -> CollectionErrorKind {
+        match self.error {
+            SdkError::Serialization { .. } | SdkError::JsonSerialization { .. } => {
+                CollectionErrorKind::SerializationFailed
+            }
+            SdkError::CollectionInconsistent { .. } => CollectionErrorKind::InconsistentState,
+            _ => CollectionErrorKind::DeserializationFailed,
+        }
+    }
+
+    /// Returns the underlying error.
+    pub fn error(&self) -> &SdkError {
+        &self.error
+    }
+
+    /// Returns the prefix the collection was created with.
+    pub fn prefix(&self) -> &[u8] {
+        &self.prefix
+    }
+
+    /// Returns the serialized key or the little-endian index of the entry which failed. Empty if
+    /// the key itself could not be serialized.
+    pub fn key(&self) -> &[u8] {
+        &self.key
+    }
+
+    /// Aborts the execution with the [`Display`](fmt::Display) of this error.
+    pub fn panic(&self) -> ! {
+        env::panic_str(&self.to_string())
+    }
+}
+
+impl fmt::Display for CollectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} (collection prefix {:?}, key {:?})",
+            self.error.as_str(),
+            self.prefix,
+            self.key
+        )
+    }
+}
+
+impl std::error::Error for CollectionError {}
+
+impl FunctionError for CollectionError {
+    fn panic(&self) -> ! {
+        CollectionError::panic(self)
+    }
+}
+
+impl From<CollectionError> for SdkError {
+    fn from(err: CollectionError) -> Self {
+        err.error
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stable_messages() {
+        // The messages the collections panicked with before `SdkError`.
+        assert_eq!(
+            SdkError::Deserialization { context: "element" }.as_str(),
+            "Cannot deserialize element"
+        );
+        assert_eq!(
+            SdkError::Serialization { context: "element" }.as_str(),
+            "Cannot serialize element"
+        );
+        assert_eq!(
+            SdkError::Serialization { context: "set element" }.as_str(),
+            "Cannot serialize element with Borsh"
+        );
+        assert_eq!(
+            SdkError::Serialization { context: "key" }.as_str(),
+            "Cannot serialize key with Borsh"
+        );
+        assert_eq!(
+            SdkError::Serialization { context: "value" }.as_str(),
+            "Cannot serialize value with Borsh"
+        );
+        assert_eq!(
+            SdkError::Deserialization { context: "value" }.as_str(),
+            "Cannot deserialize value with Borsh"
+        );
+        assert_eq!(SdkError::IndexOutOfBounds { index: 3, len: 2 }.as_str(), "Index out of bounds");
+        assert_eq!(
+            SdkError::InsufficientDeposit { required: 2, attached: 1 }.as_ref(),
+            "Insufficient attached deposit"
+        );
+        assert_eq!(SdkError::KeyNotFound.as_str(), "Key does not exist in map");
+        assert_eq!(
+            SdkError::CollectionInconsistent { collection: "Vector" }.as_str(),
+            "The collection is an inconsistent state. Did previous smart contract execution \
+             terminate unexpectedly?"
+        );
+        assert_eq!(
+            SdkError::RegisterEmpty.as_str(),
+            "Register was expected to have data because we just wrote it into it."
+        );
+    }
+
+    #[test]
+    fn display_details() {
+        assert_eq!(
+            SdkError::IndexOutOfBounds { index: 3, len: 2 }.to_string(),
+            "[INDEX_OUT_OF_BOUNDS] Index out of bounds: the len is 2 but the index is 3"
+        );
+        assert_eq!(
+            SdkError::Deserialization { context: "element" }.to_string(),
+            "[DESERIALIZATION] Cannot deserialize element with Borsh"
+        );
+        assert_eq!(SdkError::KeyNotFound.to_string(), "[KEY_NOT_FOUND] Key does not exist in map");
+    }
+
+    #[test]
+    fn codes_from_collections() {
+        use crate::collections::{JsonCodec, LookupMap, Vector};
+
+        let mut vec = Vector::<u64>::new(b"v");
+        vec.push(&1);
+        let err = Vector::<String>::new(b"v").try_get(0).unwrap_err();
+        assert_eq!(err.error().code(), "DESERIALIZATION");
+        assert_eq!(err.error().as_str(), "Cannot deserialize element");
+
+        let mut map = LookupMap::<u8, u64>::new(b"m");
+        map.insert(&1, &1);
+        let err = LookupMap::<u8, String>::new(b"m").try_get(&1).unwrap_err();
+        assert_eq!(err.error().code(), "DESERIALIZATION");
+        assert_eq!(err.error().as_str(), "Cannot deserialize value with Borsh");
+
+        let err = LookupMap::<u8, u64, JsonCodec>::new_json(b"m").try_get(&1).unwrap_err();
+        assert_eq!(err.error().code(), "VALUE_FORMAT_MISMATCH");
+
+        let mut vec = Vector::<u8>::new(b"w");
+        vec.push(&1);
+        env::storage_remove(&[b"w".as_ref(), &0u64.to_le_bytes()].concat());
+        let err = vec.try_get(0).unwrap_err();
+        assert_eq!(err.error().code(), "COLLECTION_INCONSISTENT");
+    }
+
+    #[test]
+    fn legacy_constants() {
+        assert_eq!(
+            crate::collections::ERR_INCONSISTENT_STATE,
+            SdkError::CollectionInconsistent { collection: "Vector" }.as_str()
+        );
+        assert_eq!(
+            crate::collections::ERR_ELEMENT_SERIALIZATION,
+            "Cannot serialize element with Borsh"
+        );
+        assert_eq!(
+            crate::collections::ERR_ELEMENT_DESERIALIZATION,
+            "Cannot deserialize element with Borsh"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Index out of bounds")]
+    fn vector_index_out_of_bounds() {
+        let mut vec = crate::collections::Vector::<u8>::new(b"v");
+        vec.swap_remove(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot deserialize value with Borsh")]
+    fn lookup_map_deserialization() {
+        let mut map = crate::collections::LookupMap::<u8, u64>::new(b"m");
+        map.insert(&1, &1);
+        let invalid = crate::collections::LookupMap::<u8, String>::new(b"m");
+        let _ = invalid.get(&1);
+    }
+
+    #[test]
+    #[should_panic(expected = "The collection is an inconsistent state")]
+    fn vector_inconsistent_state() {
+        let mut vec = crate::collections::Vector::<u8>::new(b"v");
+        vec.push(&1);
+        env::storage_remove(&[b"v".as_ref(), &0u64.to_le_bytes()].concat());
+        vec.pop();
+    }
+}
@@ -0,0 +1,426 @@
+use borsh::{maybestd::io, BorshDeserialize, BorshSerialize};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::convert::TryFrom;
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::Deref;
+
+/// String which is guaranteed to be at most `MAX` bytes long. The length is checked when
+/// deserializing through JSON or Borsh, so arguments can be limited without manual validation.
+///
+/// Deserialization errors can't tell which argument was too long. Methods which should name it
+/// take a `String` and convert it with `try_from`, naming it with
+/// [`LengthExceededError::for_argument`].
+///
+/// # Examples
+/// ```
+/// use near_sdk::json_types::BoundedString;
+/// use std::convert::TryFrom;
+///
+/// let memo: BoundedString<8> = serde_json::from_str("\"memo\"").unwrap();
+/// assert_eq!(memo.as_str(), "memo");
+///
+/// assert!(serde_json::from_str::<BoundedString<2>>("\"memo\"").is_err());
+/// assert!(BoundedString::<2>::try_from("memo".to_string()).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct BoundedString<const MAX: usize>(String);
+
+/// Vector which is guaranteed to contain at most `MAX` elements. The length is checked when
+/// deserializing through JSON or Borsh, so arguments can be limited without manual validation.
+///
+/// As with [`BoundedString`], [`LengthExceededError::for_argument`] names the argument of a
+/// conversion with `try_from`.
+///
+/// # Examples
+/// ```
+/// use near_sdk::json_types::BoundedVec;
+///
+/// let ids: BoundedVec<u8, 3> = serde_json::from_str("[1, 2, 3]").unwrap();
+/// assert_eq!(ids.len(), 3);
+///
+/// assert!(serde_json::from_str::<BoundedVec<u8, 3>>("[1, 2, 3, 4]").is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BoundedVec<T, const MAX: usize>(Vec<T>);
+
+/// Error returned when a value exceeds the maximum length of a bounded type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LengthExceededError {
+    len: usize,
+    max: usize,
+    argument: Option<String>,
+}
+
+impl LengthExceededError {
+    /// Names the argument whose value was too long in the error message.
+    ///
+    /// # Examples
+    /// ```
+    /// use near_sdk::json_types::BoundedString;
+    /// use std::convert::TryFrom;
+    ///
+    /// let err = BoundedString::<2>::try_from("memo").unwrap_err().for_argument("memo");
+    /// assert_eq!(err.to_string(), "length 4 exceeds maximum 2 for `memo`");
+    /// ```
+    pub fn for_argument(mut self, argument: &str) -> Self {
+        self.argument = Some(argument.to_string());
+        self
+    }
+
+    /// Name of the argument whose value was too long, if it was set with
+    /// [`for_argument`](Self::for_argument).
+    pub fn argument(&self) -> Option<&str> {
+        self.argument.as_deref()
+    }
+
+    /// Length of the value that was attempted to be converted.
+    pub fn length(&self) -> usize {
+        self.len
+    }
+
+    /// Maximum length allowed by the bounded type.
+    pub fn max(&self) -> usize {
+        self.max
+    }
+}
+
+impl fmt::Display for LengthExceededError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "length {} exceeds maximum {}", self.len, self.max)?;
+        if let Some(argument) = &self.argument {
+            write!(f, " for `{}`", argument)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for LengthExceededError {}
+
+fn check_len(len: usize, max: usize) -> Result<(), LengthExceededError> {
+    if len > max {
+        Err(LengthExceededError { len, max, argument: None })
+    } else {
+        Ok(())
+    }
+}
+
+impl<const MAX: usize> BoundedString<MAX> {
+    /// Returns reference to the inner string.
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+
+    /// Consumes the bounded string, returning the inner string.
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+impl<const MAX: usize> Deref for BoundedString<MAX> {
+    type Target = String;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<const MAX: usize> AsRef<str> for BoundedString<MAX> {
+    fn as_ref(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+impl<const MAX: usize> fmt::Display for BoundedString<MAX> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl<const MAX: usize> TryFrom<String> for BoundedString<MAX> {
+    type Error = LengthExceededError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        check_len(value.len(), MAX)?;
+        Ok(Self(value))
+    }
+}
+
+impl<const MAX: usize> TryFrom<&str> for BoundedString<MAX> {
+    type Error = LengthExceededError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::try_from(value.to_string())
+    }
+}
+
+impl<const MAX: usize> From<BoundedString<MAX>> for String {
+    fn from(value: BoundedString<MAX>) -> Self {
+        value.0
+    }
+}
+
+impl<const MAX: usize> Serialize for BoundedString<MAX> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        Serialize::serialize(&self.0, serializer)
+    }
+}
+
+impl<'de, const MAX: usize> Deserialize<'de> for BoundedString<MAX> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        <String as Deserialize>::deserialize(deserializer)
+            .and_then(|s| Self::try_from(s).map_err(de::Error::custom))
+    }
+}
+
+impl<const MAX: usize> BorshSerialize for BoundedString<MAX> {
+    fn serialize<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        BorshSerialize::serialize(&self.0, writer)
+    }
+}
+
+impl<const MAX: usize> BorshDeserialize for BoundedString<MAX> {
+    fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+        <String as BorshDeserialize>::deserialize(buf).and_then(|s| {
+            Self::try_from(s).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        })
+    }
+}
+
+impl<T, const MAX: usize> BoundedVec<T, MAX> {
+    /// Returns a slice of the inner elements.
+    pub fn as_slice(&self) -> &[T] {
+        self.0.as_slice()
+    }
+
+    /// Consumes the bounded vector, returning the inner vector.
+    pub fn into_inner(self) -> Vec<T> {
+        self.0
+    }
+}
+
+impl<T, const MAX: usize> Default for BoundedVec<T, MAX> {
+    fn default() -> Self {
+        Self(Vec::new())
+    }
+}
+
+impl<T, const MAX: usize> Deref for BoundedVec<T, MAX> {
+    type Target = Vec<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T, const MAX: usize> AsRef<[T]> for BoundedVec<T, MAX> {
+    fn as_ref(&self) -> &[T] {
+        self.0.as_slice()
+    }
+}
+
+impl<T, const MAX: usize> TryFrom<Vec<T>> for BoundedVec<T, MAX> {
+    type Error = LengthExceededError;
+
+    fn try_from(value: Vec<T>) -> Result<Self, Self::Error> {
+        check_len(value.len(), MAX)?;
+        Ok(Self(value))
+    }
+}
+
+impl<T, const MAX: usize> From<BoundedVec<T, MAX>> for Vec<T> {
+    fn from(value: BoundedVec<T, MAX>) -> Self {
+        value.0
+    }
+}
+
+impl<T, const MAX: usize> IntoIterator for BoundedVec<T, MAX> {
+    type Item = T;
+    type IntoIter = std::vec::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a, T, const MAX: usize> IntoIterator for &'a BoundedVec<T, MAX> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl<T, const MAX: usize> Serialize for BoundedVec<T, MAX>
+where
+    T: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de, T, const MAX: usize> Deserialize<'de> for BoundedVec<T, MAX>
+where
+    T: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(BoundedVecVisitor(PhantomData))
+    }
+}
+
+/// Visits the elements of a [`BoundedVec`] one by one, failing on element `MAX + 1` so that
+/// longer sequences are never fully parsed.
+struct BoundedVecVisitor<T, const MAX: usize>(PhantomData<T>);
+
+impl<'de, T, const MAX: usize> de::Visitor<'de> for BoundedVecVisitor<T, MAX>
+where
+    T: Deserialize<'de>,
+{
+    type Value = BoundedVec<T, MAX>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a sequence of at most {} elements", MAX)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::SeqAccess<'de>,
+    {
+        let mut values = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(MAX));
+        while let Some(value) = seq.next_element()? {
+            if values.len() == MAX {
+                return Err(de::Error::invalid_length(MAX + 1, &self));
+            }
+            values.push(value);
+        }
+        Ok(BoundedVec(values))
+    }
+}
+
+impl<T, const MAX: usize> BorshSerialize for BoundedVec<T, MAX>
+where
+    T: BorshSerialize,
+{
+    fn serialize<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        BorshSerialize::serialize(&self.0, writer)
+    }
+}
+
+impl<T, const MAX: usize> BorshDeserialize for BoundedVec<T, MAX>
+where
+    T: BorshDeserialize,
+{
+    fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+        // The length prefix is checked before any element is read.
+        let len = <u32 as BorshDeserialize>::deserialize(buf)? as usize;
+        check_len(len, MAX).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let mut values = Vec::with_capacity(len);
+        for _ in 0..len {
+            values.push(T::deserialize(buf)?);
+        }
+        Ok(Self(values))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn string_boundary() {
+        let at_max: BoundedString<4> = serde_json::from_str("\"abcd\"").unwrap();
+        assert_eq!(at_max.as_str(), "abcd");
+
+        let err = serde_json::from_str::<BoundedString<4>>("\"abcde\"").unwrap_err();
+        assert!(err.to_string().contains("length 5 exceeds maximum 4"));
+
+        let empty: BoundedString<0> = serde_json::from_str("\"\"").unwrap();
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn string_try_from() {
+        assert!(BoundedString::<3>::try_from("abc").is_ok());
+        let err = BoundedString::<3>::try_from("abcd").unwrap_err();
+        assert_eq!(err, LengthExceededError { len: 4, max: 3, argument: None });
+    }
+
+    #[test]
+    fn error_names_argument() {
+        let err = BoundedString::<3>::try_from("abcd").unwrap_err();
+        assert_eq!(err.to_string(), "length 4 exceeds maximum 3");
+        assert_eq!(err.argument(), None);
+
+        let err = err.for_argument("memo");
+        assert_eq!(err.argument(), Some("memo"));
+        assert_eq!(err.to_string(), "length 4 exceeds maximum 3 for `memo`");
+
+        let err = BoundedVec::<u8, 1>::try_from(vec![1, 2]).unwrap_err().for_argument("ids");
+        assert_eq!(err.to_string(), "length 2 exceeds maximum 1 for `ids`");
+    }
+
+    #[test]
+    fn vec_boundary() {
+        let at_max: BoundedVec<u32, 3> = serde_json::from_str("[1,2,3]").unwrap();
+        assert_eq!(at_max.as_slice(), &[1, 2, 3]);
+
+        let err = serde_json::from_str::<BoundedVec<u32, 3>>("[1,2,3,4]").unwrap_err();
+        assert!(err.to_string().contains("invalid length 4, expected a sequence of at most 3"));
+    }
+
+    #[test]
+    fn vec_stops_at_max() {
+        // The element after the maximum is rejected without parsing the rest of the array, even
+        // if the rest isn't valid.
+        let err = serde_json::from_str::<BoundedVec<u32, 2>>("[1,2,3,\"x\"").unwrap_err();
+        assert!(err.to_string().contains("invalid length 3"));
+
+        // The Borsh length prefix is checked before reading elements.
+        let bytes = u32::MAX.try_to_vec().unwrap();
+        let err = BoundedVec::<u32, 2>::try_from_slice(&bytes).unwrap_err();
+        assert!(err.to_string().contains("length 4294967295 exceeds maximum 2"));
+    }
+
+    #[test]
+    fn nested() {
+        type Nested = BoundedVec<BoundedString<2>, 2>;
+
+        let nested: Nested = serde_json::from_str(r#"["ab","c"]"#).unwrap();
+        assert_eq!(nested[0].as_str(), "ab");
+        assert_eq!(serde_json::to_string(&nested).unwrap(), r#"["ab","c"]"#);
+
+        // Inner element too long
+        assert!(serde_json::from_str::<Nested>(r#"["abc"]"#).is_err());
+        // Too many elements
+        assert!(serde_json::from_str::<Nested>(r#"["a","b","c"]"#).is_err());
+    }
+
+    #[test]
+    fn borsh_round_trip() {
+        let s = BoundedString::<8>::try_from("near").unwrap();
+        let bytes = s.try_to_vec().unwrap();
+        // Serialized identically to the inner string.
+        assert_eq!(bytes, "near".to_string().try_to_vec().unwrap());
+        assert_eq!(BoundedString::<8>::try_from_slice(&bytes).unwrap(), s);
+        assert!(BoundedString::<3>::try_from_slice(&bytes).is_err());
+
+        let v = BoundedVec::<BoundedString<8>, 2>::try_from(vec![s.clone(), s]).unwrap();
+        let bytes = v.try_to_vec().unwrap();
+        assert_eq!(BoundedVec::<BoundedString<8>, 2>::try_from_slice(&bytes).unwrap(), v);
+        assert!(BoundedVec::<BoundedString<8>, 1>::try_from_slice(&bytes).is_err());
+    }
+}
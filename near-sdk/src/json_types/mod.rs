@@ -1,11 +1,13 @@
 //! Helper types for JSON serialization.
 
+mod bounded;
 mod hash;
 mod integers;
 mod vector;
 
 use crate::types::{AccountId, PublicKey};
 
+pub use bounded::{BoundedString, BoundedVec, LengthExceededError};
 pub use hash::Base58CryptoHash;
 pub use integers::{I128, I64, U128, U64};
 pub use vector::Base64VecU8;
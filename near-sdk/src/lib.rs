@@ -6,8 +6,9 @@
 extern crate quickcheck;
 
 pub use near_sdk_macros::{
-    callback, callback_vec, ext_contract, init, metadata, near_bindgen, result_serializer,
-    serializer, BorshStorageKey, PanicOnDefault,
+    access_control, callback, callback_vec, direct_call_only, ext_contract, handle_result, init,
+    metadata, near_bindgen, only_owner, pause, result_serializer, serializer, BorshStorageKey,
+    NearClone, PanicOnDefault,
 };
 
 #[cfg(feature = "unstable")]
@@ -15,7 +16,9 @@ pub mod store;
 
 pub mod collections;
 mod environment;
+pub mod errors;
 pub use environment::env;
+pub use errors::FunctionError;
 
 #[cfg(feature = "unstable")]
 pub use near_sys as sys;
@@ -24,7 +27,7 @@ mod promise;
 pub use promise::{Promise, PromiseOrValue};
 
 mod metadata;
-pub use metadata::{Metadata, MethodMetadata};
+pub use metadata::{ExtMethodMetadata, Metadata, MethodMetadata};
 
 pub mod json_types;
 
@@ -41,6 +44,8 @@ pub use near_vm_logic::VMConfig;
 #[cfg(not(target_arch = "wasm32"))]
 pub use near_vm_logic::VMContext;
 
+pub mod storage_key;
+
 pub mod utils;
 pub use crate::utils::storage_key_impl::*;
 pub use crate::utils::*;
@@ -48,6 +53,8 @@ pub use crate::utils::*;
 #[cfg(not(target_arch = "wasm32"))]
 pub mod test_utils;
 
+pub mod testing;
+
 // Set up global allocator by default if custom-allocator feature is not set in wasm32 architecture.
 #[cfg(all(feature = "wee_alloc", target_arch = "wasm32"))]
 #[global_allocator]
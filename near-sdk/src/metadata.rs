@@ -34,3 +34,14 @@ pub struct MethodMetadata {
     /// Schema of the return type.
     pub result: Option<BorshSchemaContainer>,
 }
+
+/// Metadata of a method of an external contract, listed in the `METHODS` constant of the module
+/// generated by [`ext_contract`](crate::ext_contract).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExtMethodMetadata {
+    /// Name of the method.
+    pub name: &'static str,
+    /// Whether the method takes `&self`, so that it doesn't modify the state and can be called as
+    /// a view.
+    pub is_view: bool,
+}
@@ -351,6 +351,10 @@ impl Promise {
     /// let p4 = Promise::new("eva_near".parse().unwrap()).create_account();
     /// p1.then(p2).and(p3).then(p4);
     /// ```
+    ///
+    /// `then` only waits for the promise it is called on. In `p1.and(p2.then(p3))`, `p3` is
+    /// attached to `p2` alone and may run before `p1` finishes. To wait for several promises, join
+    /// them with [`and`](Self::and) first, or use [`then_after_all`](Self::then_after_all).
     pub fn then(self, mut other: Promise) -> Promise {
         match &mut other.subtype {
             PromiseSubtype::Single(x) => *x.after.borrow_mut() = Some(self),
@@ -359,6 +363,67 @@ impl Promise {
         other
     }
 
+    /// Schedules `callback` to run after this promise and all of `others` finish executing. This is
+    /// the same as joining them with [`and`](Self::and) and calling [`then`](Self::then) on the
+    /// joint promise:
+    /// ```no_run
+    /// # use near_sdk::Promise;
+    /// let p1 = Promise::new("bob_near".parse().unwrap()).create_account();
+    /// let p2 = Promise::new("carol_near".parse().unwrap()).create_account();
+    /// let p3 = Promise::new("dave_near".parse().unwrap()).create_account();
+    /// let callback = Promise::new("eva_near".parse().unwrap()).create_account();
+    /// // `eva_near` is created after `bob_near`, `carol_near` and `dave_near`, like with
+    /// // `p1.and(p2).and(p3).then(callback)`.
+    /// p1.then_after_all(vec![p2, p3], callback);
+    /// ```
+    pub fn then_after_all(self, others: Vec<Promise>, callback: Promise) -> Promise {
+        others.into_iter().fold(self, Promise::and).then(callback)
+    }
+
+    /// Same as [`then`](Self::then), naming the type `T` returned by the method this promise
+    /// calls, which `other` receives as its callback argument. With a method of an
+    /// [`ext_contract`](crate::ext_contract) trait, use the `<Method>Return` alias generated for
+    /// it, and the `<Method>Result` alias for the `#[callback_result]` argument of the callback:
+    /// ```no_run
+    /// # use near_sdk::{env, ext_contract, near_bindgen, Gas, Promise};
+    /// # use borsh::{BorshDeserialize, BorshSerialize};
+    /// #[ext_contract(ext_nft)]
+    /// pub trait Nft {
+    ///     fn nft_token(&self, token_id: String) -> Option<String>;
+    /// }
+    ///
+    /// #[ext_contract(ext_self)]
+    /// pub trait Callbacks {
+    ///     fn on_token(&mut self);
+    /// }
+    ///
+    /// #[near_bindgen]
+    /// #[derive(Default, BorshDeserialize, BorshSerialize)]
+    /// struct Contract {}
+    ///
+    /// #[near_bindgen]
+    /// impl Contract {
+    ///     pub fn lookup(&self, token_id: String) -> Promise {
+    ///         ext_nft::nft_token(token_id, "nft_near".parse().unwrap(), 0, Gas(5_000))
+    ///             .then_with::<ext_nft::NftTokenReturn>(ext_self::on_token(
+    ///                 env::current_account_id(),
+    ///                 0,
+    ///                 Gas(5_000),
+    ///             ))
+    ///     }
+    ///
+    ///     #[private]
+    ///     pub fn on_token(&mut self, #[callback_result] token: ext_nft::NftTokenResult) {
+    ///         if let Ok(Some(owner)) = token {
+    ///             env::log_str(&owner);
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn then_with<T>(self, other: Promise) -> Promise {
+        self.then(other)
+    }
+
     /// A specialized, relatively low-level API method. Allows to mark the given promise as the one
     /// that should be considered as a return value.
     ///
@@ -468,3 +533,98 @@ impl<T: borsh::BorshSerialize> borsh::BorshSerialize for PromiseOrValue<T> {
         }
     }
 }
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{get_created_receipts, get_receipt_dependencies, VMContextBuilder};
+    use crate::testing_env;
+
+    fn promise(account_id: &str) -> Promise {
+        Promise::new(account_id.parse().unwrap()).create_account()
+    }
+
+    /// Returns the dependencies between the created receipts, by the accounts receiving them.
+    fn dependencies() -> Vec<(String, String)> {
+        let receipts = get_created_receipts();
+        let mut dependencies: Vec<_> = get_receipt_dependencies()
+            .into_iter()
+            .map(|(receipt, dependency)| {
+                (
+                    receipts[receipt].receiver_id.to_string(),
+                    receipts[dependency].receiver_id.to_string(),
+                )
+            })
+            .collect();
+        dependencies.sort();
+        dependencies
+    }
+
+    fn edges(edges: &[(&str, &str)]) -> Vec<(String, String)> {
+        edges.iter().map(|(a, b)| (a.to_string(), b.to_string())).collect()
+    }
+
+    #[test]
+    fn then_after_all_waits_for_every_promise() {
+        testing_env!(VMContextBuilder::new().build());
+        drop(
+            promise("a.near").then_after_all(
+                vec![promise("b.near"), promise("c.near")],
+                promise("callback.near"),
+            ),
+        );
+        assert_eq!(
+            dependencies(),
+            edges(&[
+                ("callback.near", "a.near"),
+                ("callback.near", "b.near"),
+                ("callback.near", "c.near")
+            ])
+        );
+    }
+
+    #[test]
+    fn then_after_all_matches_joint_then() {
+        testing_env!(VMContextBuilder::new().build());
+        drop(promise("a.near").and(promise("b.near")).then(promise("callback.near")));
+        let joint = dependencies();
+
+        testing_env!(VMContextBuilder::new().build());
+        drop(promise("a.near").then_after_all(vec![promise("b.near")], promise("callback.near")));
+        assert_eq!(dependencies(), joint);
+    }
+
+    #[test]
+    fn then_inside_and_only_waits_for_one_promise() {
+        testing_env!(VMContextBuilder::new().build());
+        // The misuse `then_after_all` replaces: the callback is attached to `b.near` alone.
+        drop(promise("a.near").and(promise("b.near").then(promise("callback.near"))));
+        assert_eq!(dependencies(), edges(&[("callback.near", "b.near")]));
+    }
+
+    #[test]
+    fn then_after_all_without_others() {
+        testing_env!(VMContextBuilder::new().build());
+        drop(promise("a.near").then_after_all(vec![], promise("callback.near")));
+        assert_eq!(dependencies(), edges(&[("callback.near", "a.near")]));
+    }
+
+    #[test]
+    fn chained_callbacks() {
+        testing_env!(VMContextBuilder::new().build());
+        drop(
+            promise("a.near")
+                .then(promise("b.near"))
+                .then_after_all(vec![promise("c.near")], promise("callback.near")),
+        );
+        assert_eq!(
+            dependencies(),
+            edges(&[
+                ("b.near", "a.near"),
+                ("callback.near", "b.near"),
+                ("callback.near", "c.near")
+            ])
+        );
+    }
+}
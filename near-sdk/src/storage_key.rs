@@ -0,0 +1,77 @@
+//! Namespacing of storage keys for libraries.
+//!
+//! A library storing collections inside a contract, such as the standards, can't know the
+//! prefixes used by the contract itself, so the keys of the two could collide. A library should
+//! prefix its keys with its own namespace instead, using [`namespaced`] or
+//! `#[storage_key(namespace = "...")]` on a type deriving
+//! [`BorshStorageKey`](crate::BorshStorageKey):
+//!
+//! ```ignore
+//! #[derive(BorshStorageKey, BorshSerialize)]
+//! #[storage_key(namespace = "my_library")]
+//! enum StorageKey {
+//!     Accounts,
+//!     Balances,
+//! }
+//! ```
+//!
+//! The namespace is part of the storage keys, so it can't change once the collections store data.
+
+use crate::{env, IntoStorageKey};
+
+/// Length of the prefix of namespaced storage keys.
+pub const NAMESPACE_PREFIX_LEN: usize = 8;
+
+/// Returns the prefix of the storage keys in `namespace`: the first [`NAMESPACE_PREFIX_LEN`]
+/// bytes of the SHA-256 hash of the namespace.
+pub fn namespace_prefix(namespace: &str) -> [u8; NAMESPACE_PREFIX_LEN] {
+    let mut prefix = [0; NAMESPACE_PREFIX_LEN];
+    prefix.copy_from_slice(&env::sha256_array(namespace.as_bytes())[..NAMESPACE_PREFIX_LEN]);
+    prefix
+}
+
+/// Returns `key` prefixed with the [`namespace_prefix`] of `namespace`.
+///
+/// # Examples
+/// ```
+/// use near_sdk::collections::LookupMap;
+/// use near_sdk::storage_key::namespaced;
+///
+/// let balances: LookupMap<String, u128> = LookupMap::new(namespaced("my_library", b"b".to_vec()));
+/// ```
+pub fn namespaced(namespace: &'static str, key: impl IntoStorageKey) -> Vec<u8> {
+    let key = key.into_storage_key();
+    let mut namespaced = Vec::with_capacity(NAMESPACE_PREFIX_LEN + key.len());
+    namespaced.extend_from_slice(&namespace_prefix(namespace));
+    namespaced.extend_from_slice(&key);
+    namespaced
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefix_is_stable() {
+        // Golden values: a change moves all the data stored under these namespaces.
+        assert_eq!(
+            namespace_prefix("nft_standard"),
+            [0x61, 0xd1, 0x55, 0x69, 0xff, 0x19, 0x5b, 0x7a]
+        );
+        assert_eq!(
+            namespace_prefix("ft_standard"),
+            [0x9d, 0x48, 0x93, 0x02, 0xe3, 0x4e, 0xa0, 0x7f]
+        );
+    }
+
+    #[test]
+    fn key_follows_prefix() {
+        assert_eq!(
+            namespaced("my_lib", vec![1, 2]),
+            [0x00, 0xd6, 0xf1, 0x57, 0x5a, 0x00, 0xd9, 0x40, 1, 2]
+        );
+        assert_eq!(namespaced("my_lib", Vec::new()), namespace_prefix("my_lib"));
+        assert_ne!(namespace_prefix("my_lib"), namespace_prefix("my_lib2"));
+    }
+}
@@ -2,6 +2,7 @@ mod iter;
 pub use self::iter::{Drain, Iter, IterMut};
 
 use super::{Vector, ERR_INCONSISTENT_STATE};
+use crate::environment::register_prefix;
 use crate::{env, IntoStorageKey};
 
 use borsh::{BorshDeserialize, BorshSerialize};
@@ -9,12 +10,48 @@ use borsh::{BorshDeserialize, BorshSerialize};
 use std::{fmt, mem};
 
 /// Index for value within a bucket.
+///
+/// The index of a value stays the same until the value is removed, so it can be handed out as an
+/// identifier. It converts to and from `u32` to be stored or returned by a contract.
 #[derive(BorshSerialize, BorshDeserialize, Debug, Hash, PartialEq, Eq, Clone, Copy)]
 pub struct FreeListIndex(pub(crate) u32);
 
+impl From<u32> for FreeListIndex {
+    fn from(index: u32) -> Self {
+        Self(index)
+    }
+}
+
+impl From<FreeListIndex> for u32 {
+    fn from(index: FreeListIndex) -> Self {
+        index.0
+    }
+}
+
 /// Unordered container of values. This is similar to [`Vector`] except that values are not
 /// re-arranged on removal, keeping the indices consistent. When an element is removed, it will
 /// be replaced with an empty cell which will be populated on the next insertion.
+///
+/// This makes the indices usable as identifiers, for example of orders or approvals, where
+/// [`Vector::swap_remove`] would move the last element to the removed index. Removed indices are
+/// reused by later insertions, so an index of a removed value may refer to another value later.
+///
+/// # Examples
+/// ```
+/// use near_sdk::store::FreeList;
+///
+/// let mut orders = FreeList::new(b"o");
+/// let first = orders.insert("buy".to_string());
+/// let second = orders.insert("sell".to_string());
+///
+/// assert_eq!(orders.remove(first), Some("buy".to_string()));
+/// assert_eq!(orders.get(second), Some(&"sell".to_string()));
+/// assert_eq!(orders.len(), 1);
+///
+/// // The removed slot is reused by the next insertion.
+/// assert_eq!(orders.insert("hold".to_string()), first);
+/// assert_eq!(u32::from(first), 0);
+/// ```
 pub struct FreeList<T>
 where
     T: BorshSerialize,
@@ -104,7 +141,10 @@ impl<T> FreeList<T>
 where
     T: BorshSerialize,
 {
+    /// Create new [`FreeList`] with the given `prefix` for its storage keys.
     pub fn new<S: IntoStorageKey>(prefix: S) -> Self {
+        let prefix = prefix.into_storage_key();
+        let _scope = register_prefix::<Self>(&prefix);
         Self { first_free: None, occupied_count: 0, elements: Vector::new(prefix) }
     }
     /// Returns length of values within the bucket.
@@ -312,6 +352,46 @@ mod tests {
         assert_eq!(bucket.insert(9), FreeListIndex(4));
     }
 
+    #[test]
+    fn stable_indices() {
+        let mut bucket = FreeList::new(b"b");
+        let mut expected = HashMap::new();
+        for round in 0u32..5 {
+            for v in 0..10 {
+                let value = round * 10 + v;
+                expected.insert(bucket.insert(value), value);
+            }
+            // Remove every other value inserted so far.
+            let mut indices: Vec<_> = expected.keys().copied().collect();
+            indices.sort_by_key(|&i| u32::from(i));
+            for index in indices.into_iter().step_by(2) {
+                assert_eq!(bucket.remove(index), expected.remove(&index));
+            }
+            for (&index, value) in &expected {
+                assert_eq!(bucket.get(index), Some(value));
+            }
+            assert_eq!(bucket.len() as usize, expected.len());
+            assert_eq!(bucket.iter().count(), expected.len());
+        }
+    }
+
+    #[test]
+    fn reuse_removed_indices() {
+        let mut bucket = FreeList::new(b"b");
+        let indices: Vec<_> = (0u8..4).map(|v| bucket.insert(v)).collect();
+        bucket.remove(indices[1]);
+        bucket.remove(indices[2]);
+        assert_eq!(bucket.get(indices[1]), None);
+        assert_eq!(bucket.remove(indices[1]), None);
+
+        // Freed slots are reused before the list grows.
+        let reused = [bucket.insert(10), bucket.insert(11)];
+        assert!(reused.contains(&indices[1]) && reused.contains(&indices[2]));
+        assert_eq!(bucket.insert(12), FreeListIndex::from(4));
+        assert_eq!(bucket.elements.len(), 5);
+        assert_eq!(bucket.iter().copied().collect::<Vec<_>>(), [0, 11, 10, 3, 12]);
+    }
+
     #[test]
     fn drain() {
         let mut bucket = FreeList::new(b"b");
@@ -3,11 +3,12 @@ use std::fmt;
 use borsh::{BorshDeserialize, BorshSerialize};
 use once_cell::unsync::OnceCell;
 
+use crate::errors::SdkError;
 use crate::utils::StableMap;
 use crate::{env, CacheEntry, EntryState, IntoStorageKey};
 
-const ERR_ELEMENT_DESERIALIZATION: &str = "Cannot deserialize element";
-const ERR_ELEMENT_SERIALIZATION: &str = "Cannot serialize element";
+const ERR_ELEMENT_DESERIALIZATION: &str = SdkError::Deserialization { context: "element" }.as_str();
+const ERR_ELEMENT_SERIALIZATION: &str = SdkError::Serialization { context: "element" }.as_str();
 
 #[derive(BorshSerialize, BorshDeserialize)]
 pub(crate) struct IndexMap<T>
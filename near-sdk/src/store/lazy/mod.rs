@@ -10,11 +10,13 @@ use borsh::{BorshDeserialize, BorshSerialize};
 use once_cell::unsync::OnceCell;
 
 use crate::env;
+use crate::environment::register_prefix;
+use crate::errors::SdkError;
 use crate::utils::{CacheEntry, EntryState};
 use crate::IntoStorageKey;
 
-const ERR_VALUE_SERIALIZATION: &str = "Cannot serialize value with Borsh";
-const ERR_VALUE_DESERIALIZATION: &str = "Cannot deserialize value with Borsh";
+const ERR_VALUE_SERIALIZATION: &str = SdkError::Serialization { context: "value" }.as_str();
+const ERR_VALUE_DESERIALIZATION: &str = SdkError::Deserialization { context: "value" }.as_str();
 const ERR_NOT_FOUND: &str = "No value found for the given key";
 const ERR_DELETED: &str = "The Lazy cell's value has been deleted. Verify the key has not been\
                             deleted manually.";
@@ -80,8 +82,10 @@ where
     where
         S: IntoStorageKey,
     {
+        let storage_key = key.into_storage_key();
+        let _scope = register_prefix::<Self>(&storage_key);
         Self {
-            storage_key: key.into_storage_key().into_boxed_slice(),
+            storage_key: storage_key.into_boxed_slice(),
             cache: OnceCell::from(CacheEntry::new_modified(Some(value))),
         }
     }
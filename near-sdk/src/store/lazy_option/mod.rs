@@ -4,6 +4,7 @@ use borsh::{BorshDeserialize, BorshSerialize};
 use once_cell::unsync::OnceCell;
 
 use crate::env;
+use crate::environment::register_prefix;
 use crate::store::lazy::{load_and_deserialize, serialize_and_store};
 use crate::utils::{CacheEntry, EntryState};
 use crate::IntoStorageKey;
@@ -58,10 +59,9 @@ where
             None => CacheEntry::new_cached(None),
         };
 
-        Self {
-            storage_key: storage_key.into_storage_key().into_boxed_slice(),
-            cache: OnceCell::from(cache),
-        }
+        let storage_key = storage_key.into_storage_key();
+        let _scope = register_prefix::<Self>(&storage_key);
+        Self { storage_key: storage_key.into_boxed_slice(), cache: OnceCell::from(cache) }
     }
 
     /// Updates the value with a new value. This does not load the current value from storage.
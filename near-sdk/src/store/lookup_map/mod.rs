@@ -8,14 +8,16 @@ use borsh::{BorshDeserialize, BorshSerialize};
 use once_cell::unsync::OnceCell;
 
 use super::ERR_NOT_EXIST;
+use crate::environment::register_prefix;
+use crate::errors::SdkError;
 use crate::store::key::{Identity, ToKey};
 use crate::utils::{EntryState, StableMap};
 use crate::{env, CacheEntry, IntoStorageKey};
 
 pub use entry::{Entry, OccupiedEntry, VacantEntry};
 
-const ERR_ELEMENT_DESERIALIZATION: &str = "Cannot deserialize element";
-const ERR_ELEMENT_SERIALIZATION: &str = "Cannot serialize element";
+const ERR_ELEMENT_DESERIALIZATION: &str = SdkError::Deserialization { context: "element" }.as_str();
+const ERR_ELEMENT_SERIALIZATION: &str = SdkError::Serialization { context: "element" }.as_str();
 
 /// A non-iterable, lazily loaded storage map that stores its content directly on the storage trie.
 ///
@@ -74,6 +76,11 @@ const ERR_ELEMENT_SERIALIZATION: &str = "Cannot serialize element";
 /// *stat += random_stat_buff();
 /// ```
 ///
+/// Values are cached in memory once loaded or modified, so reading a key multiple times within a
+/// call reads from storage at most once, and reads after writes see the new value without
+/// touching storage. Modified values are written to storage once, when [`flush`](Self::flush) is
+/// called or the map is dropped, which `near_bindgen` does at the end of a method call.
+///
 /// [`with_hasher`]: Self::with_hasher
 #[derive(BorshSerialize, BorshDeserialize)]
 pub struct LookupMap<K, V, H = Identity>
@@ -155,7 +162,9 @@ where
     where
         S: IntoStorageKey,
     {
-        Self { prefix: prefix.into_storage_key().into_boxed_slice(), cache: Default::default() }
+        let prefix = prefix.into_storage_key();
+        let _scope = register_prefix::<Self>(&prefix);
+        Self { prefix: prefix.into_boxed_slice(), cache: Default::default() }
     }
 
     /// Overwrites the current value for the given key.
@@ -576,6 +585,59 @@ mod tests {
         assert_eq!(dup_map[&5], 8);
     }
 
+    /// Number of storage reads and writes made by `f`.
+    fn storage_ops<F: FnOnce()>(f: F) -> (u64, u64) {
+        let count = || {
+            crate::mock::with_mocked_blockchain(|b| {
+                (b.storage_read_count(), b.storage_write_count())
+            })
+        };
+        let (reads, writes) = count();
+        f();
+        let (new_reads, new_writes) = count();
+        (new_reads - reads, new_writes - writes)
+    }
+
+    #[test]
+    fn cached_reads() {
+        let mut map = LookupMap::new(b"m");
+        map.insert(1u64, 1u64);
+        map.flush();
+        drop(map);
+
+        let map = LookupMap::<u64, u64>::new(b"m");
+        // The first read loads the value from storage.
+        assert_eq!(storage_ops(|| assert_eq!(map.get(&1), Some(&1))), (1, 0));
+        // Subsequent reads, including of missing keys, don't read storage again.
+        assert_eq!(storage_ops(|| assert_eq!(map.get(&1), Some(&1))), (0, 0));
+        assert_eq!(storage_ops(|| assert!(map.get(&2).is_none())), (1, 0));
+        assert_eq!(storage_ops(|| assert!(map.get(&2).is_none())), (0, 0));
+    }
+
+    #[test]
+    fn cached_writes() {
+        let mut map = LookupMap::new(b"m");
+        // Only the first access to a key reads it, and nothing is written before flushing.
+        assert_eq!(
+            storage_ops(|| {
+                map.insert(1u64, 1u64);
+                assert_eq!(map.get(&1), Some(&1));
+                *map.get_mut(&1).unwrap() += 1;
+                map.insert(1, 3);
+                assert_eq!(map[&1], 3);
+            }),
+            (1, 0)
+        );
+        assert_eq!(storage_ops(|| assert_eq!(map.insert(2, 1), None)), (1, 0));
+
+        // Repeated writes to a key are flushed as a single write.
+        assert_eq!(storage_ops(|| map.flush()), (0, 2));
+        // Nothing is written again until the values are modified.
+        assert_eq!(storage_ops(|| map.flush()), (0, 0));
+        map.insert(2, 2);
+        assert_eq!(storage_ops(|| map.flush()), (0, 1));
+    }
+
     #[derive(Arbitrary, Debug)]
     enum Op {
         Insert(u8, u8),
@@ -1,5 +1,6 @@
 mod impls;
 
+use crate::environment::register_prefix;
 use crate::store::key::{Identity, ToKey};
 use crate::{env, IntoStorageKey, StableMap};
 use borsh::{BorshDeserialize, BorshSerialize};
@@ -114,8 +115,10 @@ where
     where
         S: IntoStorageKey,
     {
+        let prefix = prefix.into_storage_key();
+        let _scope = register_prefix::<Self>(&prefix);
         Self {
-            prefix: prefix.into_storage_key().into_boxed_slice(),
+            prefix: prefix.into_boxed_slice(),
             cache: Default::default(),
             hasher: Default::default(),
         }
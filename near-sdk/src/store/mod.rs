@@ -1,3 +1,5 @@
+use crate::errors::SdkError;
+
 mod lazy;
 pub use lazy::Lazy;
 
@@ -25,14 +27,13 @@ pub use self::tree_map::TreeMap;
 mod index_map;
 pub(crate) use self::index_map::IndexMap;
 
-pub(crate) mod free_list;
-pub(crate) use self::free_list::FreeList;
+pub mod free_list;
+pub use self::free_list::FreeList;
 
 /// Storage key hash function types and trait to override map hash functions.
 pub mod key;
 
 pub(crate) const ERR_INCONSISTENT_STATE: &str =
-    "The collection is an inconsistent state. Did previous smart \
-        contract execution terminate unexpectedly?";
+    SdkError::CollectionInconsistent { collection: "store" }.as_str();
 
-pub(crate) const ERR_NOT_EXIST: &str = "Key does not exist in map";
+pub(crate) const ERR_NOT_EXIST: &str = SdkError::KeyNotFound.as_str();
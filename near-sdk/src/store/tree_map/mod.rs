@@ -3,6 +3,7 @@ mod impls;
 mod iter;
 
 use super::lookup_map as lm;
+use crate::environment::register_prefix;
 use crate::store::free_list::{FreeList, FreeListIndex};
 use crate::store::key::{Sha256, ToKey};
 use crate::store::LookupMap;
@@ -165,6 +166,7 @@ where
         S: IntoStorageKey,
     {
         let mut vec_key = prefix.into_storage_key();
+        let _scope = register_prefix::<Self>(&vec_key);
         let map_key = [vec_key.as_slice(), b"v"].concat();
         vec_key.push(b'n');
         Self { values: LookupMap::with_hasher(map_key), tree: Tree::new(vec_key) }
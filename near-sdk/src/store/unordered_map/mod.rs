@@ -7,6 +7,7 @@ use std::{fmt, mem};
 
 use borsh::{BorshDeserialize, BorshSerialize};
 
+use crate::environment::register_prefix;
 use crate::store::key::{Sha256, ToKey};
 use crate::{env, IntoStorageKey};
 
@@ -181,6 +182,7 @@ where
         S: IntoStorageKey,
     {
         let mut vec_key = prefix.into_storage_key();
+        let _scope = register_prefix::<Self>(&vec_key);
         let map_key = [vec_key.as_slice(), b"m"].concat();
         vec_key.push(b'v');
         Self { keys: FreeList::new(vec_key), values: LookupMap::with_hasher(map_key) }
@@ -388,9 +390,24 @@ where
 
     /// Returns a mutable reference to the value corresponding to the key.
     ///
+    /// The value is marked as modified and written back to storage when the map is
+    /// [`flush`](Self::flush)ed or dropped, so there is no need to insert it again after mutating
+    /// it.
+    ///
     /// The key may be any borrowed form of the map's key type, but
     /// [`BorshSerialize`] and [`ToOwned<Owned = K>`](ToOwned) on the borrowed form *must* match
     /// those for the key type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use near_sdk::store::UnorderedMap;
+    ///
+    /// let mut balances: UnorderedMap<String, Vec<u64>> = UnorderedMap::new(b"m");
+    /// balances.insert("alice".to_string(), vec![]);
+    /// balances.get_mut("alice").unwrap().push(10);
+    /// assert_eq!(balances["alice"], [10]);
+    /// ```
     pub fn get_mut<Q: ?Sized>(&mut self, k: &Q) -> Option<&mut V>
     where
         K: Borrow<Q>,
@@ -596,6 +613,51 @@ mod tests {
         assert_eq!(map.keys().collect::<Vec<_>>(), [&0, &2, &3]);
     }
 
+    #[test]
+    fn mutated_value_persists() {
+        #[derive(BorshSerialize, BorshDeserialize, Default)]
+        struct Account {
+            tokens: Vec<u32>,
+        }
+
+        // With `collections::UnorderedMap`, `get` returns a copy of the value, so a change is lost
+        // unless the value is inserted again.
+        let mut legacy = crate::collections::UnorderedMap::<u8, Account>::new(b"l");
+        legacy.insert(&0, &Account::default());
+        legacy.get(&0).unwrap().tokens.push(1);
+        assert!(legacy.get(&0).unwrap().tokens.is_empty());
+
+        let mut map = UnorderedMap::<u8, Account>::new(b"m");
+        map.insert(0, Account::default());
+        map.flush();
+        map.get_mut(&0).unwrap().tokens.push(1);
+        let serialized = map.try_to_vec().unwrap();
+        drop(map);
+
+        let map = UnorderedMap::<u8, Account>::deserialize(&mut serialized.as_slice()).unwrap();
+        assert_eq!(map[&0].tokens, [1]);
+    }
+
+    #[test]
+    fn iter_merges_cached_changes() {
+        let mut map = UnorderedMap::new(b"m");
+        for i in 0u8..4 {
+            map.insert(i, i);
+        }
+        map.flush();
+        let serialized = map.try_to_vec().unwrap();
+        drop(map);
+
+        // Changes made before the map is flushed are visible when iterating.
+        let mut map = UnorderedMap::<u8, u8>::deserialize(&mut serialized.as_slice()).unwrap();
+        assert_eq!(map.remove(&1), Some(1));
+        *map.get_mut(&2).unwrap() = 20;
+        map.insert(4, 4);
+        assert!(!map.contains_key(&1));
+        assert_eq!(map.iter().collect::<Vec<_>>(), [(&0, &0), (&4, &4), (&2, &20), (&3, &3)]);
+        assert_eq!(map.len(), 4);
+    }
+
     #[derive(Arbitrary, Debug)]
     enum Op {
         Insert(u8, u8),
@@ -2,6 +2,7 @@ mod impls;
 mod iter;
 
 use super::{FreeList, LookupMap, ERR_INCONSISTENT_STATE};
+use crate::environment::register_prefix;
 use crate::store::free_list::FreeListIndex;
 use crate::store::key::{Sha256, ToKey};
 use crate::store::unordered_set::iter::{
@@ -144,6 +145,7 @@ where
         S: IntoStorageKey,
     {
         let mut vec_key = prefix.into_storage_key();
+        let _scope = register_prefix::<Self>(&vec_key);
         let map_key = [vec_key.as_slice(), b"m"].concat();
         vec_key.push(b'v');
         Self { elements: FreeList::new(vec_key), index: LookupMap::with_hasher(map_key) }
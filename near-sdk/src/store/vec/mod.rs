@@ -10,11 +10,13 @@ use borsh::{BorshDeserialize, BorshSerialize};
 
 pub use self::iter::{Drain, Iter, IterMut};
 use super::ERR_INCONSISTENT_STATE;
+use crate::environment::register_prefix;
+use crate::errors::SdkError;
 use crate::{env, IntoStorageKey};
 
 use super::IndexMap;
 
-const ERR_INDEX_OUT_OF_BOUNDS: &str = "Index out of bounds";
+const ERR_INDEX_OUT_OF_BOUNDS: &str = SdkError::IndexOutOfBounds { index: 0, len: 0 }.as_str();
 
 fn expect_consistent_state<T>(val: Option<T>) -> T {
     val.unwrap_or_else(|| env::panic_str(ERR_INCONSISTENT_STATE))
@@ -114,6 +116,8 @@ where
     where
         S: IntoStorageKey,
     {
+        let prefix = prefix.into_storage_key();
+        let _scope = register_prefix::<Self>(&prefix);
         Self { len: 0, values: IndexMap::new(prefix) }
     }
 
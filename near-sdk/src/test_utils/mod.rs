@@ -3,8 +3,12 @@ pub mod test_env;
 
 mod context;
 use crate::mock::Receipt;
+#[cfg(feature = "check-prefix-collisions")]
+pub use crate::mock::{set_prefix_policy, PrefixPolicy};
+use crate::{BlockHeight, EpochHeight};
 #[allow(deprecated)]
 pub use context::{accounts, testing_env_with_promise_results, VMContextBuilder};
+use std::convert::TryInto;
 
 /// Initializes a testing environment to mock interactions which would otherwise go through a
 /// validator node. This macro will initialize or overwrite the [`MockedBlockchain`]
@@ -99,6 +103,130 @@ pub fn get_created_receipts() -> Vec<Receipt> {
     crate::mock::with_mocked_blockchain(|b| b.created_receipts().clone())
 }
 
+/// Returns the dependencies between the receipts created by the contract, as pairs of the index
+/// of a receipt in [`get_created_receipts`] and the index of a receipt it waits for. A receipt
+/// only runs after every receipt it waits for. Only available in unit tests.
+///
+/// # Example use
+///
+/// ```
+/// use near_sdk::test_utils::get_receipt_dependencies;
+/// use near_sdk::Promise;
+///
+/// let a = Promise::new("a.near".parse().unwrap()).create_account();
+/// let b = Promise::new("b.near".parse().unwrap()).create_account();
+/// let callback = Promise::new("c.near".parse().unwrap()).create_account();
+/// drop(a.then_after_all(vec![b], callback));
+/// // The callback, created last, waits for both `a` and `b`.
+/// assert_eq!(get_receipt_dependencies(), [(2, 0), (2, 1)]);
+/// ```
+#[allow(dead_code)]
+pub fn get_receipt_dependencies() -> Vec<(usize, usize)> {
+    get_created_receipts()
+        .iter()
+        .enumerate()
+        .flat_map(|(index, receipt)| {
+            receipt.receipt_indices.iter().map(move |&dependency| (index, dependency as usize))
+        })
+        .collect()
+}
+
+/// Returns a copy of the mocked storage, ordered by key. Comparing snapshots shows exactly which
+/// keys an operation added, changed or removed. Only available in unit tests.
+///
+/// # Example use
+///
+/// ```
+/// use near_sdk::collections::Vector;
+/// use near_sdk::test_utils::storage_snapshot;
+///
+/// let before = storage_snapshot();
+/// let mut vec = Vector::new(b"v");
+/// vec.push(&1u8);
+/// vec.clear();
+/// assert_eq!(storage_snapshot(), before);
+/// ```
+pub fn storage_snapshot() -> std::collections::BTreeMap<Vec<u8>, Vec<u8>> {
+    crate::mock::with_mocked_blockchain(|b| b.storage_snapshot())
+}
+
+/// Moves the block timestamp of the current mocked context forward by `duration`. Storage and
+/// the rest of the context are preserved. Only available in unit tests.
+///
+/// # Panics
+///
+/// Panics if called before the context was initialized with [`testing_env!`].
+///
+/// # Example use
+///
+/// ```
+/// use near_sdk::{env, testing_env};
+/// use near_sdk::test_utils::{advance_block_timestamp, VMContextBuilder};
+/// use std::time::Duration;
+///
+/// testing_env!(VMContextBuilder::new().block_timestamp(1_000).build());
+/// advance_block_timestamp(Duration::from_secs(1));
+/// assert_eq!(env::block_timestamp(), 1_000_001_000);
+/// ```
+pub fn advance_block_timestamp(duration: std::time::Duration) {
+    let nanos: u64 = duration
+        .as_nanos()
+        .try_into()
+        .unwrap_or_else(|_| panic!("Duration {:?} overflows the block timestamp", duration));
+    update_context(|context| {
+        context.block_timestamp = context
+            .block_timestamp
+            .checked_add(nanos)
+            .expect("block timestamp overflow when advancing time");
+    })
+}
+
+/// Sets the block timestamp of the current mocked context to `timestamp_ms` milliseconds.
+/// Storage and the rest of the context are preserved. Only available in unit tests.
+///
+/// # Panics
+///
+/// Panics if called before the context was initialized with [`testing_env!`].
+pub fn set_block_timestamp_ms(timestamp_ms: u64) {
+    let timestamp = timestamp_ms
+        .checked_mul(1_000_000)
+        .expect("block timestamp overflow when converting from milliseconds");
+    update_context(|context| context.block_timestamp = timestamp)
+}
+
+/// Moves the block height of the current mocked context forward by `blocks`. Storage and the rest
+/// of the context are preserved. Only available in unit tests.
+///
+/// # Panics
+///
+/// Panics if called before the context was initialized with [`testing_env!`].
+pub fn advance_block_height(blocks: BlockHeight) {
+    update_context(|context| {
+        context.block_index =
+            context.block_index.checked_add(blocks).expect("block height overflow");
+    })
+}
+
+/// Moves the epoch height of the current mocked context forward by `epochs`. Storage and the rest
+/// of the context are preserved. Only available in unit tests.
+///
+/// # Panics
+///
+/// Panics if called before the context was initialized with [`testing_env!`].
+pub fn advance_epoch(epochs: EpochHeight) {
+    update_context(|context| {
+        context.epoch_height =
+            context.epoch_height.checked_add(epochs).expect("epoch height overflow");
+    })
+}
+
+fn update_context<F>(f: F)
+where
+    F: FnOnce(&mut crate::VMContext),
+{
+    crate::mock::with_mocked_blockchain(|b| b.update_context(f))
+}
+
 /// Objects stored on the trie directly should have identifiers. If identifier is not provided
 /// explicitly than `Default` trait would use this index to generate an id.
 #[allow(dead_code)]
@@ -112,3 +240,53 @@ pub(crate) fn next_trie_id() -> Vec<u8> {
         id.to_le_bytes().to_vec()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::env;
+    use std::time::Duration;
+
+    #[test]
+    fn advance_time_preserves_context() {
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(accounts(1))
+            .block_timestamp(5)
+            .block_index(10)
+            .epoch_height(2)
+            .build());
+
+        advance_block_timestamp(Duration::from_nanos(10));
+        assert_eq!(env::block_timestamp(), 15);
+        advance_block_height(3);
+        assert_eq!(env::block_height(), 13);
+        advance_epoch(1);
+        assert_eq!(env::epoch_height(), 3);
+        set_block_timestamp_ms(2);
+        assert_eq!(env::block_timestamp(), 2_000_000);
+
+        // Everything else is untouched.
+        assert_eq!(env::predecessor_account_id(), accounts(1));
+        assert_eq!(env::block_height(), 13);
+        assert_eq!(env::epoch_height(), 3);
+    }
+
+    #[test]
+    fn advance_time_preserves_storage() {
+        testing_env!(VMContextBuilder::new().build());
+        env::storage_write(b"key", b"value");
+        let storage_usage = env::storage_usage();
+
+        advance_block_timestamp(Duration::from_secs(60));
+        assert_eq!(env::storage_read(b"key"), Some(b"value".to_vec()));
+        assert_eq!(env::storage_usage(), storage_usage);
+    }
+
+    #[test]
+    #[should_panic(expected = "`testing_env!` must be called")]
+    fn advance_time_requires_testing_env() {
+        std::thread::spawn(|| advance_epoch(1))
+            .join()
+            .unwrap_or_else(|e| std::panic::resume_unwind(e));
+    }
+}
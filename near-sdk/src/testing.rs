@@ -0,0 +1,145 @@
+//! Test hooks for contracts compiled to wasm and tested against a local sandbox.
+//!
+//! [`impl_test_hooks!`](crate::impl_test_hooks) adds methods to a contract which read and write
+//! arbitrary storage keys, so that tests can set up state directly. The methods are only generated
+//! when the `test-hooks` feature of `near-sdk` is enabled, and using them emits a warning.
+//!
+//! **Never deploy a contract built with `test-hooks` to a real network.** The write methods are
+//! restricted to calls from the contract account itself, but anyone with a full access key to that
+//! account can rewrite any part of the contract state.
+
+use crate::env;
+
+#[cfg(feature = "test-hooks")]
+#[doc(hidden)]
+#[deprecated(
+    note = "the `test-hooks` feature of near-sdk is enabled. Contracts built with it expose \
+            methods which overwrite raw storage and must not be deployed to a real network."
+)]
+pub const TEST_HOOKS_ENABLED: () = ();
+
+/// Checks that a test hook writing to `key` may run: the call must come from the contract account
+/// itself, and the contract state can only be touched if `allow_state` is `Some(true)`.
+#[doc(hidden)]
+pub fn check_test_hook_write(key: &[u8], allow_state: Option<bool>) {
+    if env::current_account_id() != env::predecessor_account_id() {
+        env::panic_str("Test hooks can only be called by the contract account");
+    }
+    if key == env::STATE_KEY && allow_state != Some(true) {
+        env::panic_str("Test hooks must be called with `allow_state` to modify the contract state");
+    }
+}
+
+/// Generates methods on `$contract` to access raw storage from tests, when the `test-hooks`
+/// feature of `near-sdk` is enabled. Otherwise this expands to nothing.
+///
+/// The generated methods are:
+/// - `__set_raw(key: Base64VecU8, value: Base64VecU8, allow_state: Option<bool>)`
+/// - `__remove_raw(key: Base64VecU8, allow_state: Option<bool>) -> bool`
+/// - `__get_raw(key: Base64VecU8) -> Option<Base64VecU8>`
+/// - `__storage_usage() -> u64`
+///
+/// The methods writing to storage can only be called by the contract account, and refuse to
+/// modify the `STATE` key unless `allow_state` is `true`. The methods which read are views, as
+/// the raw state of a contract can already be queried from any node.
+///
+/// See the [module documentation](crate::testing) for why this must never be enabled in contracts
+/// deployed to a real network.
+///
+/// # Examples
+/// ```
+/// use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+/// use near_sdk::near_bindgen;
+///
+/// #[near_bindgen]
+/// #[derive(Default, BorshDeserialize, BorshSerialize)]
+/// pub struct Contract {}
+///
+/// near_sdk::testing::impl_test_hooks!(Contract);
+/// ```
+#[cfg(feature = "test-hooks")]
+#[macro_export]
+macro_rules! impl_test_hooks {
+    ($contract:ident) => {
+        const _: () = $crate::testing::TEST_HOOKS_ENABLED;
+
+        #[$crate::near_bindgen]
+        impl $contract {
+            pub fn __set_raw(
+                key: $crate::json_types::Base64VecU8,
+                value: $crate::json_types::Base64VecU8,
+                allow_state: Option<bool>,
+            ) {
+                $crate::testing::check_test_hook_write(&key.0, allow_state);
+                $crate::env::storage_write(&key.0, &value.0);
+            }
+
+            pub fn __remove_raw(
+                key: $crate::json_types::Base64VecU8,
+                allow_state: Option<bool>,
+            ) -> bool {
+                $crate::testing::check_test_hook_write(&key.0, allow_state);
+                $crate::env::storage_remove(&key.0)
+            }
+
+            pub fn __get_raw(
+                key: $crate::json_types::Base64VecU8,
+            ) -> Option<$crate::json_types::Base64VecU8> {
+                $crate::env::storage_read(&key.0).map($crate::json_types::Base64VecU8)
+            }
+
+            pub fn __storage_usage() -> u64 {
+                $crate::env::storage_usage()
+            }
+        }
+    };
+}
+
+/// Generates methods on `$contract` to access raw storage from tests, when the `test-hooks`
+/// feature of `near-sdk` is enabled. Otherwise this expands to nothing.
+#[cfg(not(feature = "test-hooks"))]
+#[macro_export]
+macro_rules! impl_test_hooks {
+    ($contract:ident) => {};
+}
+
+pub use crate::impl_test_hooks;
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use super::check_test_hook_write;
+    use crate::test_utils::{accounts, VMContextBuilder};
+    use crate::testing_env;
+
+    fn self_call() {
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(0))
+            .build());
+    }
+
+    #[test]
+    fn allows_self_calls() {
+        self_call();
+        check_test_hook_write(b"key", None);
+        check_test_hook_write(b"STATE", Some(true));
+    }
+
+    #[test]
+    #[should_panic(expected = "Test hooks can only be called by the contract account")]
+    fn rejects_other_accounts() {
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(1))
+            .build());
+        check_test_hook_write(b"key", None);
+    }
+
+    #[test]
+    #[should_panic(expected = "must be called with `allow_state`")]
+    fn rejects_state_key() {
+        self_call();
+        check_test_hook_write(b"STATE", Some(false));
+    }
+}
@@ -54,6 +54,53 @@ impl AccountId {
         debug_assert!(is_valid_account_id(id.as_bytes()));
         Self(id)
     }
+
+    /// Parses an account ID typed by a user: trims the whitespace around it, strips a leading
+    /// `@` and lowercases ASCII letters before validating it. The positions in the error refer to
+    /// the normalized input.
+    ///
+    /// Only use it on input coming from users, such as in frontends. Contracts shouldn't normalize
+    /// the account IDs passed to their methods: an account ID which isn't valid can't be the one
+    /// the caller meant to reach, and silently mapping `Alice` to `alice` could send tokens to
+    /// an account belonging to someone else. The standards reject such account IDs instead.
+    ///
+    /// # Examples
+    /// ```
+    /// use near_sdk::AccountId;
+    ///
+    /// assert_eq!(AccountId::normalize(" @Alice.NEAR\n").unwrap().as_str(), "alice.near");
+    /// assert!(AccountId::normalize("alice..near").is_err());
+    /// ```
+    pub fn normalize(input: &str) -> Result<Self, ParseAccountIdError> {
+        let input = input.trim();
+        let input = input.strip_prefix('@').unwrap_or(input);
+        input.to_ascii_lowercase().parse()
+    }
+
+    /// Returns every problem making `id` an invalid account ID, in the order of the characters
+    /// involved, after any problem with its length.
+    ///
+    /// # Examples
+    /// ```
+    /// use near_sdk::{AccountId, ValidationIssue};
+    ///
+    /// assert_eq!(
+    ///     AccountId::validate_verbose("Alice..near"),
+    ///     Err(vec![
+    ///         ValidationIssue::UppercaseChar { char: 'A', pos: 0 },
+    ///         ValidationIssue::ConsecutiveSeparators { char: '.', pos: 6 },
+    ///     ])
+    /// );
+    /// assert_eq!(AccountId::validate_verbose("alice.near"), Ok(()));
+    /// ```
+    pub fn validate_verbose(id: &str) -> Result<(), Vec<ValidationIssue>> {
+        let issues = validation_issues(id);
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(issues)
+        }
+    }
 }
 
 impl fmt::Display for AccountId {
@@ -96,10 +143,45 @@ fn validate_account_id(id: &str) -> Result<(), ParseAccountIdError> {
     if is_valid_account_id(id.as_bytes()) {
         Ok(())
     } else {
-        Err(ParseAccountIdError {})
+        // The checks are only repeated in detail for invalid account IDs, which have an issue.
+        let issue = validation_issues(id).into_iter().next().unwrap_or_else(|| crate::env::abort());
+        Err(ParseAccountIdError { issue })
     }
 }
 
+/// Lists the problems of `id` following the grammar checked by [`is_valid_account_id`].
+fn validation_issues(id: &str) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+    let len = id.len();
+    if len < MIN_ACCOUNT_ID_LEN {
+        issues.push(ValidationIssue::TooShort { len });
+    } else if len > MAX_ACCOUNT_ID_LEN {
+        issues.push(ValidationIssue::TooLong { len });
+    }
+    let mut last = None;
+    for (pos, char) in id.chars().enumerate() {
+        let is_separator = matches!(char, '-' | '_' | '.');
+        if is_separator {
+            match last {
+                None => issues.push(ValidationIssue::LeadingSeparator { char, pos }),
+                Some(true) => issues.push(ValidationIssue::ConsecutiveSeparators { char, pos }),
+                Some(false) => {}
+            }
+        } else if char.is_ascii_uppercase() {
+            issues.push(ValidationIssue::UppercaseChar { char, pos });
+        } else if !matches!(char, 'a'..='z' | '0'..='9') {
+            issues.push(ValidationIssue::InvalidChar { char, pos });
+        }
+        last = Some(is_separator);
+    }
+    if let Some((pos, char)) = id.chars().enumerate().last() {
+        if last == Some(true) && pos > 0 {
+            issues.push(ValidationIssue::TrailingSeparator { char, pos });
+        }
+    }
+    issues
+}
+
 impl TryFrom<String> for AccountId {
     type Error = ParseAccountIdError;
 
@@ -118,18 +200,81 @@ impl std::str::FromStr for AccountId {
     }
 }
 
+const MIN_ACCOUNT_ID_LEN: usize = 2;
+const MAX_ACCOUNT_ID_LEN: usize = 64;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[non_exhaustive]
-pub struct ParseAccountIdError {}
+pub struct ParseAccountIdError {
+    issue: ValidationIssue,
+}
+
+impl ParseAccountIdError {
+    /// Returns the first problem of the account ID, see [`AccountId::validate_verbose`] for all
+    /// of them.
+    pub fn issue(&self) -> &ValidationIssue {
+        &self.issue
+    }
+}
 
 impl fmt::Display for ParseAccountIdError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "the account ID is invalid")
+        write!(f, "the account ID is invalid: {}", self.issue)
     }
 }
 
 impl std::error::Error for ParseAccountIdError {}
 
+/// A problem making a string an invalid account ID. Positions count characters from the start of
+/// the string, and lengths count bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ValidationIssue {
+    /// The account ID is shorter than 2 bytes.
+    TooShort { len: usize },
+    /// The account ID is longer than 64 bytes.
+    TooLong { len: usize },
+    /// An uppercase ASCII letter, which would be valid in lowercase.
+    UppercaseChar { char: char, pos: usize },
+    /// A character other than lowercase ASCII letters, digits and the separators `-`, `_`
+    /// and `.`.
+    InvalidChar { char: char, pos: usize },
+    /// The account ID starts with a separator.
+    LeadingSeparator { char: char, pos: usize },
+    /// The account ID ends with a separator.
+    TrailingSeparator { char: char, pos: usize },
+    /// A separator following another separator.
+    ConsecutiveSeparators { char: char, pos: usize },
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooShort { len } => {
+                write!(f, "{} bytes long, at least {} expected", len, MIN_ACCOUNT_ID_LEN)
+            }
+            Self::TooLong { len } => {
+                write!(f, "{} bytes long, at most {} expected", len, MAX_ACCOUNT_ID_LEN)
+            }
+            Self::UppercaseChar { char, pos } => {
+                write!(f, "uppercase character {:?} at position {}", char, pos)
+            }
+            Self::InvalidChar { char, pos } => {
+                write!(f, "invalid character {:?} at position {}", char, pos)
+            }
+            Self::LeadingSeparator { char, pos } => {
+                write!(f, "separator {:?} at position {} starts the account ID", char, pos)
+            }
+            Self::TrailingSeparator { char, pos } => {
+                write!(f, "separator {:?} at position {} ends the account ID", char, pos)
+            }
+            Self::ConsecutiveSeparators { char, pos } => {
+                write!(f, "separator {:?} at position {} follows another separator", char, pos)
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -164,4 +309,90 @@ mod tests {
         // Test to make sure the account ID is serialized as a string through borsh
         assert_eq!(str::try_to_vec(id).unwrap(), account_id.try_to_vec().unwrap());
     }
+
+    #[test]
+    fn normalize() {
+        for input in ["alice.near", " alice.near\t", "@alice.near", "ALICE.Near", "\n @Alice.near "]
+        {
+            assert_eq!(AccountId::normalize(input).unwrap().as_str(), "alice.near", "{:?}", input);
+        }
+        assert_eq!(
+            AccountId::normalize("@@alice").unwrap_err().issue(),
+            &ValidationIssue::InvalidChar { char: '@', pos: 0 }
+        );
+        assert_eq!(
+            AccountId::normalize(" al ice ").unwrap_err().issue(),
+            &ValidationIssue::InvalidChar { char: ' ', pos: 2 }
+        );
+        assert!(AccountId::normalize("   ").is_err());
+    }
+
+    #[test]
+    fn verbose_validation() {
+        use ValidationIssue::*;
+        let cases: &[(&str, &[ValidationIssue])] = &[
+            ("", &[TooShort { len: 0 }]),
+            ("a", &[TooShort { len: 1 }]),
+            (".", &[TooShort { len: 1 }, LeadingSeparator { char: '.', pos: 0 }]),
+            ("A", &[TooShort { len: 1 }, UppercaseChar { char: 'A', pos: 0 }]),
+            ("-near", &[LeadingSeparator { char: '-', pos: 0 }]),
+            ("near_", &[TrailingSeparator { char: '_', pos: 4 }]),
+            (
+                "a..",
+                &[
+                    ConsecutiveSeparators { char: '.', pos: 2 },
+                    TrailingSeparator { char: '.', pos: 2 },
+                ],
+            ),
+            (
+                "0_-_0",
+                &[
+                    ConsecutiveSeparators { char: '-', pos: 2 },
+                    ConsecutiveSeparators { char: '_', pos: 3 },
+                ],
+            ),
+            ("nEar", &[UppercaseChar { char: 'E', pos: 1 }]),
+            ("a@b", &[InvalidChar { char: '@', pos: 1 }]),
+            (
+                "неар",
+                &[
+                    InvalidChar { char: 'н', pos: 0 },
+                    InvalidChar { char: 'е', pos: 1 },
+                    InvalidChar { char: 'а', pos: 2 },
+                    InvalidChar { char: 'р', pos: 3 },
+                ],
+            ),
+            (&"a".repeat(65), &[TooLong { len: 65 }]),
+        ];
+        for (id, issues) in cases {
+            assert_eq!(AccountId::validate_verbose(id), Err(issues.to_vec()), "{:?}", id);
+            assert_eq!(id.parse::<AccountId>().unwrap_err().issue(), &issues[0], "{:?}", id);
+        }
+        assert_eq!(AccountId::validate_verbose(&"a".repeat(64)), Ok(()));
+    }
+
+    /// Checks `validate_verbose` against `is_valid_account_id` over every string of up to 5
+    /// characters from an alphabet covering each class of characters.
+    #[test]
+    fn validation_matches_grammar() {
+        const ALPHABET: [char; 8] = ['a', 'z', '0', 'A', '-', '_', '.', '@'];
+        let mut ids = vec![String::new()];
+        for _ in 0..5 {
+            let longer: Vec<String> = ids
+                .iter()
+                .filter(|id| id.len() == ids.last().unwrap().len())
+                .flat_map(|id| ALPHABET.iter().map(move |c| format!("{}{}", id, c)))
+                .collect();
+            ids.extend(longer);
+        }
+        for id in &ids {
+            let valid = is_valid_account_id(id.as_bytes());
+            let verbose = AccountId::validate_verbose(id);
+            assert_eq!(valid, verbose.is_ok(), "{:?}", id);
+            match id.parse::<AccountId>() {
+                Ok(_) => assert!(valid),
+                Err(err) => assert_eq!(Some(err.issue()), verbose.unwrap_err().first()),
+            }
+        }
+    }
 }
@@ -1,6 +1,7 @@
 use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
 use core::ops;
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
 
 /// Represents the amount of NEAR tokens in "gas units" which are used to fund transactions.
 #[derive(
@@ -23,8 +24,69 @@ pub struct Gas(pub u64);
 impl Gas {
     /// One Tera gas, which is 10^12 gas units.
     pub const ONE_TERA: Gas = Gas(1_000_000_000_000);
+
+    /// Returns the gas left in the current call after each of the `reserved` amounts: the prepaid
+    /// gas, minus the gas used so far, minus the reservations. Fails if a reservation doesn't fit,
+    /// instead of underflowing.
+    ///
+    /// # Examples
+    /// ```
+    /// use near_sdk::test_utils::VMContextBuilder;
+    /// use near_sdk::{testing_env, Gas};
+    ///
+    /// testing_env!(VMContextBuilder::new().prepaid_gas(Gas::ONE_TERA * 30).build());
+    /// let remaining = Gas::remaining_after(&[Gas::ONE_TERA * 10, Gas::ONE_TERA * 5]).unwrap();
+    /// assert!(remaining < Gas::ONE_TERA * 15);
+    ///
+    /// let err = Gas::remaining_after(&[Gas::ONE_TERA * 10, Gas::ONE_TERA * 25]).unwrap_err();
+    /// assert_eq!(err.index, 1);
+    /// ```
+    pub fn remaining_after(reserved: &[Gas]) -> Result<Gas, InsufficientGas> {
+        Self::remaining_of(crate::env::prepaid_gas(), crate::env::used_gas(), reserved)
+    }
+
+    fn remaining_of(prepaid: Gas, used: Gas, reserved: &[Gas]) -> Result<Gas, InsufficientGas> {
+        let mut remaining = prepaid.0.saturating_sub(used.0);
+        for (index, &reservation) in reserved.iter().enumerate() {
+            remaining = remaining.checked_sub(reservation.0).ok_or(InsufficientGas {
+                prepaid,
+                used,
+                index,
+                reservation,
+                available: Gas(remaining),
+            })?;
+        }
+        Ok(Gas(remaining))
+    }
 }
 
+/// Error returned by [`Gas::remaining_after`] when a reservation doesn't fit in the gas left.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InsufficientGas {
+    /// Gas prepaid for the current call.
+    pub prepaid: Gas,
+    /// Gas used by the current call so far.
+    pub used: Gas,
+    /// Index of the first reservation which didn't fit.
+    pub index: usize,
+    /// Amount of that reservation.
+    pub reservation: Gas,
+    /// Gas left after the previous reservations.
+    pub available: Gas,
+}
+
+impl fmt::Display for InsufficientGas {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Reservation {} of {} gas exceeds the {} gas left of {} prepaid, {} used",
+            self.index, self.reservation.0, self.available.0, self.prepaid.0, self.used.0
+        )
+    }
+}
+
+impl std::error::Error for InsufficientGas {}
+
 impl Serialize for Gas {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -131,6 +193,35 @@ mod tests {
         assert_eq!(de.0, val);
     }
 
+    #[test]
+    fn remaining_of() {
+        let tera = Gas::ONE_TERA;
+        assert_eq!(Gas::remaining_of(tera * 30, tera * 2, &[]), Ok(tera * 28));
+        assert_eq!(Gas::remaining_of(tera * 30, tera * 2, &[tera * 10, tera * 8]), Ok(tera * 10));
+        assert_eq!(Gas::remaining_of(tera * 30, tera * 2, &[tera * 28]), Ok(Gas(0)));
+        assert_eq!(
+            Gas::remaining_of(tera * 30, tera * 2, &[tera * 10, tera * 20, tera * 5]),
+            Err(InsufficientGas {
+                prepaid: tera * 30,
+                used: tera * 2,
+                index: 1,
+                reservation: tera * 20,
+                available: tera * 18,
+            })
+        );
+        // Used gas above the prepaid gas leaves nothing rather than underflowing.
+        assert_eq!(
+            Gas::remaining_of(Gas(5), Gas(7), &[Gas(1)]),
+            Err(InsufficientGas {
+                prepaid: Gas(5),
+                used: Gas(7),
+                index: 0,
+                reservation: Gas(1),
+                available: Gas(0),
+            })
+        );
+    }
+
     #[test]
     fn json_ser() {
         test_json_ser(u64::MAX);
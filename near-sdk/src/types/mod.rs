@@ -8,10 +8,10 @@ mod primitives;
 pub use self::primitives::*;
 
 mod account_id;
-pub use self::account_id::{AccountId, ParseAccountIdError};
+pub use self::account_id::{AccountId, ParseAccountIdError, ValidationIssue};
 
 mod gas;
-pub use self::gas::Gas;
+pub use self::gas::{Gas, InsufficientGas};
 
 /// Raw type for duration in nanoseconds
 pub type Duration = u64;
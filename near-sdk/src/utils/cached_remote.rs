@@ -0,0 +1,231 @@
+use std::convert::TryFrom;
+use std::marker::PhantomData;
+use std::time::Duration;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::de::DeserializeOwned;
+
+use crate::errors::SdkError;
+use crate::{env, log, Gas, IntoStorageKey, Promise, PromiseOrValue, PromiseResult};
+
+/// A value fetched from another contract, such as an oracle price or token metadata, cached in
+/// storage with the time it was fetched at.
+///
+/// The value is read with [`get_if_fresh`](Self::get_if_fresh) while it's younger than the
+/// maximum age the caller accepts. Otherwise, [`refresh_via`](Self::refresh_via) schedules the
+/// view call on the remote contract, followed by a resolver method on this contract which stores
+/// the result with the current block timestamp. The resolver is generated by
+/// [`impl_cached_remote_resolver!`](crate::impl_cached_remote_resolver).
+/// [`get_or_refresh`](Self::get_or_refresh) combines both, returning the cached value if it's
+/// fresh and the refresh promise otherwise.
+///
+/// If the remote call fails or returns a value which can't be deserialized, the resolver logs it
+/// and keeps the previous value and its timestamp.
+///
+/// # Examples
+/// ```
+/// use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+/// use near_sdk::json_types::U128;
+/// use near_sdk::utils::CachedRemote;
+/// use near_sdk::{ext_contract, near_bindgen, AccountId, Gas, PanicOnDefault, PromiseOrValue};
+/// use std::time::Duration;
+///
+/// #[ext_contract(ext_oracle)]
+/// trait Oracle {
+///     fn get_price(&self) -> U128;
+/// }
+///
+/// #[near_bindgen]
+/// #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+/// pub struct Contract {
+///     oracle_id: AccountId,
+///     price: CachedRemote<U128>,
+/// }
+///
+/// #[near_bindgen]
+/// impl Contract {
+///     pub fn price(&self) -> PromiseOrValue<U128> {
+///         let oracle_id = self.oracle_id.clone();
+///         self.price.get_or_refresh(
+///             Duration::from_secs(60),
+///             || ext_oracle::get_price(oracle_id, 0, Gas(5_000_000_000_000)),
+///             "on_price",
+///             Gas(5_000_000_000_000),
+///         )
+///     }
+/// }
+///
+/// near_sdk::impl_cached_remote_resolver!(Contract, price, on_price, U128);
+/// ```
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct CachedRemote<T> {
+    key: Vec<u8>,
+    #[borsh_skip]
+    el: PhantomData<T>,
+}
+
+impl<T> CachedRemote<T> {
+    /// Creates a cache with no value, stored under `key`.
+    pub fn new<S>(key: S) -> Self
+    where
+        S: IntoStorageKey,
+    {
+        Self { key: key.into_storage_key(), el: PhantomData }
+    }
+
+    /// Removes the cached value.
+    pub fn clear(&mut self) {
+        env::storage_remove(&self.key);
+    }
+
+    /// Schedules `promise_builder`, the view call on the remote contract, followed by the
+    /// `resolver` method of this contract with `resolver_gas`, and returns the resulting promise.
+    /// The promise resolves to the new value, or to the previous value if the call failed.
+    pub fn refresh_via<F>(&self, promise_builder: F, resolver: &str, resolver_gas: Gas) -> Promise
+    where
+        F: FnOnce() -> Promise,
+    {
+        promise_builder().then(Promise::new(env::current_account_id()).function_call(
+            resolver.to_string(),
+            Vec::new(),
+            0,
+            resolver_gas,
+        ))
+    }
+}
+
+/// Returns how old something fetched at `fetched_at_ms` is at the current block, in milliseconds.
+fn age_ms(fetched_at_ms: u64) -> u64 {
+    (env::block_timestamp() / 1_000_000).saturating_sub(fetched_at_ms)
+}
+
+impl<T> CachedRemote<T>
+where
+    T: BorshSerialize + BorshDeserialize,
+{
+    /// Returns the cached value and the block timestamp it was fetched at, in milliseconds.
+    pub fn get(&self) -> Option<(T, u64)> {
+        env::storage_read(&self.key).map(|raw| {
+            <(T, u64)>::try_from_slice(&raw)
+                .unwrap_or_else(|_| SdkError::Deserialization { context: "cached value" }.panic())
+        })
+    }
+
+    /// Stores `value` as fetched at the current block timestamp.
+    pub fn set(&mut self, value: &T) {
+        let fetched_at_ms = env::block_timestamp() / 1_000_000;
+        // Serialized like the `(T, u64)` read by `get`.
+        let mut raw = value
+            .try_to_vec()
+            .unwrap_or_else(|_| SdkError::Serialization { context: "cached value" }.panic());
+        raw.extend_from_slice(&fetched_at_ms.to_le_bytes());
+        env::storage_write(&self.key, &raw);
+    }
+
+    /// Returns the cached value if it was fetched at most `max_age` ago.
+    pub fn get_if_fresh(&self, max_age: Duration) -> Option<T> {
+        let max_age_ms = u64::try_from(max_age.as_millis()).unwrap_or(u64::MAX);
+        self.get()
+            .filter(|(_, fetched_at_ms)| age_ms(*fetched_at_ms) <= max_age_ms)
+            .map(|(value, _)| value)
+    }
+
+    /// Returns `true` if there is no cached value fetched at most `max_age` ago.
+    pub fn needs_refresh(&self, max_age: Duration) -> bool {
+        self.get_if_fresh(max_age).is_none()
+    }
+
+    /// Returns the cached value if it was fetched at most `max_age` ago, or refreshes it with
+    /// [`refresh_via`](Self::refresh_via) otherwise. `promise_builder` is only called when
+    /// refreshing, so no call is scheduled for a fresh value.
+    pub fn get_or_refresh<F>(
+        &self,
+        max_age: Duration,
+        promise_builder: F,
+        resolver: &str,
+        resolver_gas: Gas,
+    ) -> PromiseOrValue<T>
+    where
+        F: FnOnce() -> Promise,
+    {
+        match self.get_if_fresh(max_age) {
+            Some(value) => PromiseOrValue::Value(value),
+            None => {
+                PromiseOrValue::Promise(self.refresh_via(promise_builder, resolver, resolver_gas))
+            }
+        }
+    }
+}
+
+impl<T> CachedRemote<T>
+where
+    T: BorshSerialize + BorshDeserialize + DeserializeOwned,
+{
+    /// Stores the result of the remote call scheduled by [`refresh_via`](Self::refresh_via) and
+    /// returns it. Called by the resolver generated by
+    /// [`impl_cached_remote_resolver!`](crate::impl_cached_remote_resolver).
+    ///
+    /// If the call failed or its result can't be deserialized, the failure is logged and the
+    /// previous value is returned and kept with its timestamp.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the call failed and there is no previous value.
+    pub fn resolve_refresh(&mut self) -> T {
+        let result = match env::promise_result(0) {
+            PromiseResult::Successful(data) => {
+                serde_json::from_slice::<T>(&data).map_err(|_| "the result can't be deserialized")
+            }
+            _ => Err("the remote call failed"),
+        };
+        match result {
+            Ok(value) => {
+                self.set(&value);
+                value
+            }
+            Err(reason) => match self.get() {
+                Some((value, fetched_at_ms)) => {
+                    log!(
+                        "Refreshing the cached value failed: {}. Keeping the value fetched at {}",
+                        reason,
+                        fetched_at_ms
+                    );
+                    value
+                }
+                None => env::panic_str(&format!(
+                    "Refreshing the cached value failed: {}. No value is cached",
+                    reason
+                )),
+            },
+        }
+    }
+}
+
+impl<T> std::fmt::Debug for CachedRemote<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CachedRemote").field("key", &self.key).finish()
+    }
+}
+
+/// Implements the resolver of a [`CachedRemote`] field of a contract, a private method storing the
+/// result of the remote call scheduled by [`CachedRemote::refresh_via`].
+///
+/// The arguments are the contract, the `CachedRemote` field, the name of the resolver method and
+/// the type of the cached value. The name of the method is the `resolver` to pass to
+/// [`CachedRemote::refresh_via`] and [`CachedRemote::get_or_refresh`].
+///
+/// ```ignore
+/// near_sdk::impl_cached_remote_resolver!(Contract, price, on_price, U128);
+/// ```
+#[macro_export]
+macro_rules! impl_cached_remote_resolver {
+    ($contract: ident, $field: ident, $resolver: ident, $ty: ty) => {
+        #[$crate::near_bindgen]
+        impl $contract {
+            #[private]
+            pub fn $resolver(&mut self) -> $ty {
+                self.$field.resolve_refresh()
+            }
+        }
+    };
+}
@@ -0,0 +1,143 @@
+use crate::{env, require, AccountId, Balance, Gas};
+
+/// The parts of the environment of a call which helpers commonly check: who made the call, what
+/// was attached to it and when it runs.
+///
+/// Helpers taking a `&CallContext` rather than reading [`env`] directly can be called with a
+/// context built by hand, which makes the logic they guard testable without setting up the
+/// mocked blockchain for each case. Contract methods build it with [`CallContext::from_env`] and
+/// pass it down.
+///
+/// # Examples
+///
+/// ```
+/// use near_sdk::{AccountId, CallContext, Gas};
+///
+/// fn assert_owner(ctx: &CallContext, owner_id: &AccountId) {
+///     near_sdk::require!(&ctx.predecessor_account_id == owner_id, "Unauthorized");
+/// }
+///
+/// let owner_id: AccountId = "alice.near".parse().unwrap();
+/// let ctx = CallContext {
+///     predecessor_account_id: owner_id.clone(),
+///     signer_account_id: owner_id.clone(),
+///     current_account_id: "contract.near".parse().unwrap(),
+///     attached_deposit: 1,
+///     block_timestamp: 0,
+///     prepaid_gas: Gas::ONE_TERA,
+/// };
+/// assert_owner(&ctx, &owner_id);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallContext {
+    /// The account which made the call, see [`env::predecessor_account_id`].
+    pub predecessor_account_id: AccountId,
+    /// The account which signed the transaction, see [`env::signer_account_id`].
+    pub signer_account_id: AccountId,
+    /// The account of the contract, see [`env::current_account_id`].
+    pub current_account_id: AccountId,
+    /// The balance attached to the call, see [`env::attached_deposit`].
+    pub attached_deposit: Balance,
+    /// The timestamp of the block in nanoseconds, see [`env::block_timestamp`].
+    pub block_timestamp: u64,
+    /// The gas attached to the call, see [`env::prepaid_gas`].
+    pub prepaid_gas: Gas,
+}
+
+impl CallContext {
+    /// Reads the context of the current call from the environment.
+    pub fn from_env() -> Self {
+        Self {
+            predecessor_account_id: env::predecessor_account_id(),
+            signer_account_id: env::signer_account_id(),
+            current_account_id: env::current_account_id(),
+            attached_deposit: env::attached_deposit(),
+            block_timestamp: env::block_timestamp(),
+            prepaid_gas: env::prepaid_gas(),
+        }
+    }
+
+    /// Reads only the predecessor and the attached deposit from the environment, for methods
+    /// which only check who called them and what was attached. Reading an account ID costs gas,
+    /// so the signer and the current account are not read and are set to the predecessor, and
+    /// the timestamp and the prepaid gas are set to 0. Use [`from_env`](Self::from_env) if
+    /// anything else reads the context.
+    pub fn caller_from_env() -> Self {
+        let predecessor_account_id = env::predecessor_account_id();
+        Self {
+            signer_account_id: predecessor_account_id.clone(),
+            current_account_id: predecessor_account_id.clone(),
+            predecessor_account_id,
+            attached_deposit: env::attached_deposit(),
+            block_timestamp: 0,
+            prepaid_gas: Gas(0),
+        }
+    }
+
+    /// Panics unless exactly 1 yoctoNEAR was attached, like
+    /// [`assert_one_yocto`](crate::assert_one_yocto).
+    pub fn assert_one_yocto(&self) {
+        require!(self.attached_deposit == 1, "Requires attached deposit of exactly 1 yoctoNEAR")
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use super::CallContext;
+    use crate::test_utils::{accounts, VMContextBuilder};
+    use crate::{testing_env, Gas};
+
+    #[test]
+    fn from_env() {
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(accounts(0))
+            .signer_account_id(accounts(1))
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(7)
+            .block_timestamp(42)
+            .prepaid_gas(Gas(100))
+            .build());
+        assert_eq!(
+            CallContext::from_env(),
+            CallContext {
+                predecessor_account_id: accounts(2),
+                signer_account_id: accounts(1),
+                current_account_id: accounts(0),
+                attached_deposit: 7,
+                block_timestamp: 42,
+                prepaid_gas: Gas(100),
+            }
+        );
+    }
+
+    #[test]
+    fn caller_from_env() {
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(accounts(0))
+            .signer_account_id(accounts(1))
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(7)
+            .block_timestamp(42)
+            .build());
+        let ctx = CallContext::caller_from_env();
+        assert_eq!(ctx.predecessor_account_id, accounts(2));
+        assert_eq!(ctx.attached_deposit, 7);
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn assert_one_yocto() {
+        let mut ctx = CallContext {
+            predecessor_account_id: accounts(1),
+            signer_account_id: accounts(1),
+            current_account_id: accounts(0),
+            attached_deposit: 1,
+            block_timestamp: 0,
+            prepaid_gas: Gas(0),
+        };
+        ctx.assert_one_yocto();
+        ctx.attached_deposit = 2;
+        ctx.assert_one_yocto();
+    }
+}
@@ -0,0 +1,194 @@
+use crate::{env, AccountId, Balance, Promise};
+
+/// Splits the deposit attached to a call across the purposes it pays for, such as storage, fees
+/// and forwarded payments, and refunds what is left.
+///
+/// The splitter starts from [`env::attached_deposit`]. Each [`take`](Self::take) subtracts from the
+/// remaining deposit, panicking if the deposit doesn't cover it. When done, the remainder is
+/// either refunded with [`refund_remainder`](Self::refund_remainder) or kept by the contract with
+/// [`keep_remainder`](Self::keep_remainder). In debug builds, dropping the splitter without doing
+/// either logs a warning, as the remainder would otherwise silently stay with the contract.
+///
+/// # Examples
+///
+/// ```
+/// use near_sdk::test_utils::VMContextBuilder;
+/// use near_sdk::utils::DepositSplitter;
+/// use near_sdk::{env, testing_env, ONE_NEAR};
+///
+/// testing_env!(VMContextBuilder::new().attached_deposit(ONE_NEAR).build());
+///
+/// let mut deposit = DepositSplitter::new();
+/// deposit.take_storage_cost(100);
+/// let fee = deposit.take(ONE_NEAR / 10);
+/// assert_eq!(deposit.remaining(), ONE_NEAR - fee - 100 * env::storage_byte_cost());
+/// deposit.refund_remainder(&env::predecessor_account_id());
+/// ```
+#[derive(Debug)]
+pub struct DepositSplitter {
+    attached: Balance,
+    remaining: Balance,
+    settled: bool,
+}
+
+impl Default for DepositSplitter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DepositSplitter {
+    /// Creates a splitter for the deposit attached to the current call.
+    pub fn new() -> Self {
+        let attached = env::attached_deposit();
+        Self { attached, remaining: attached, settled: false }
+    }
+
+    /// Takes `amount` from the remaining deposit and returns it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if less than `amount` remains, with a message stating the shortfall.
+    pub fn take(&mut self, amount: Balance) -> Balance {
+        if amount > self.remaining {
+            env::panic_str(&format!(
+                "Must attach {} yoctoNEAR, the attached deposit is short by {} yoctoNEAR",
+                self.required(amount),
+                amount - self.remaining
+            ))
+        }
+        self.remaining -= amount;
+        amount
+    }
+
+    /// Takes the cost of storing `bytes` bytes from the remaining deposit and returns it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the remaining deposit doesn't cover the cost, with a message stating the
+    /// shortfall.
+    pub fn take_storage_cost(&mut self, bytes: u64) -> Balance {
+        let cost = env::storage_byte_cost() * Balance::from(bytes);
+        if cost > self.remaining {
+            env::panic_str(&format!(
+                "Must attach {} yoctoNEAR to cover storage, the attached deposit is short by {} \
+                 yoctoNEAR",
+                self.required(cost),
+                cost - self.remaining
+            ))
+        }
+        self.remaining -= cost;
+        cost
+    }
+
+    /// Returns the part of the attached deposit which hasn't been taken yet.
+    pub fn remaining(&self) -> Balance {
+        self.remaining
+    }
+
+    /// Transfers the remaining deposit to `to`. Returns [`None`] if nothing remains.
+    pub fn refund_remainder(mut self, to: &AccountId) -> Option<Promise> {
+        self.settled = true;
+        if self.remaining == 0 {
+            return None;
+        }
+        Some(Promise::new(to.clone()).transfer(self.remaining))
+    }
+
+    /// Keeps the remaining deposit in the contract and returns its amount.
+    pub fn keep_remainder(mut self) -> Balance {
+        self.settled = true;
+        self.remaining
+    }
+
+    /// The deposit which would have to be attached for `amount` to be taken on top of what was
+    /// already taken.
+    fn required(&self, amount: Balance) -> Balance {
+        self.attached - self.remaining + amount
+    }
+}
+
+impl Drop for DepositSplitter {
+    fn drop(&mut self) {
+        if cfg!(debug_assertions) && !self.settled && !std::thread::panicking() {
+            env::log_str(&format!(
+                "Warning: DepositSplitter dropped with {} yoctoNEAR remaining, call \
+                 `refund_remainder` or `keep_remainder`",
+                self.remaining
+            ));
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use super::DepositSplitter;
+    use crate::test_utils::{accounts, get_created_receipts, get_logs, VMContextBuilder};
+    use crate::{env, testing_env, Balance};
+
+    fn attach(deposit: Balance) {
+        testing_env!(VMContextBuilder::new().attached_deposit(deposit).build());
+    }
+
+    #[test]
+    fn exact_split() {
+        attach(1_000);
+        let mut deposit = DepositSplitter::new();
+        assert_eq!(deposit.take(400), 400);
+        assert_eq!(deposit.take(600), 600);
+        assert_eq!(deposit.remaining(), 0);
+        assert!(deposit.refund_remainder(&accounts(1)).is_none());
+        assert!(get_created_receipts().is_empty());
+        assert!(get_logs().is_empty());
+    }
+
+    #[test]
+    fn refunds_remainder() {
+        let storage_cost = env::storage_byte_cost() * 10;
+        attach(storage_cost + 5);
+        let mut deposit = DepositSplitter::new();
+        assert_eq!(deposit.take_storage_cost(10), storage_cost);
+        assert_eq!(deposit.remaining(), 5);
+        assert!(deposit.refund_remainder(&accounts(1)).is_some());
+        assert_eq!(get_created_receipts().len(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Must attach 1100 yoctoNEAR, the attached deposit is short by 100")]
+    fn take_shortfall() {
+        attach(1_000);
+        let mut deposit = DepositSplitter::new();
+        deposit.take(600);
+        deposit.take(500);
+    }
+
+    #[test]
+    #[should_panic(expected = "to cover storage, the attached deposit is short by")]
+    fn storage_shortfall() {
+        attach(1);
+        DepositSplitter::new().take_storage_cost(1);
+    }
+
+    #[test]
+    fn keep_remainder() {
+        attach(1_000);
+        let mut deposit = DepositSplitter::new();
+        deposit.take(1);
+        assert_eq!(deposit.keep_remainder(), 999);
+        assert!(get_logs().is_empty());
+    }
+
+    #[test]
+    fn warns_when_dropped() {
+        attach(1_000);
+        drop(DepositSplitter::new());
+        if cfg!(debug_assertions) {
+            assert_eq!(
+                get_logs(),
+                ["Warning: DepositSplitter dropped with 1000 yoctoNEAR remaining, call \
+                  `refund_remainder` or `keep_remainder`"]
+            );
+        }
+    }
+}
@@ -1,5 +1,17 @@
 pub(crate) mod storage_key_impl;
 
+mod call_context;
+pub use self::call_context::CallContext;
+
+mod deposit_splitter;
+pub use self::deposit_splitter::DepositSplitter;
+
+mod rng;
+pub use self::rng::Rng;
+
+mod cached_remote;
+pub use self::cached_remote::CachedRemote;
+
 #[cfg(feature = "unstable")]
 mod stable_map;
 #[cfg(feature = "unstable")]
@@ -85,6 +97,20 @@ pub fn assert_self() {
     require!(env::predecessor_account_id() == env::current_account_id(), "Method is private");
 }
 
+/// Assert that the predecessor account is the signer account, meaning the method was called
+/// directly by the transaction, and not by another contract.
+///
+/// Authorize calls by the predecessor account, not the signer account, which is shared by every
+/// call a transaction leads to, including the calls made by a malicious contract the signer
+/// called. This check is for methods which should only be triggered by a user directly, on top of
+/// that authorization, see [`direct_call_only`](crate::direct_call_only).
+pub fn assert_predecessor_is_signer() {
+    require!(
+        env::predecessor_account_id() == env::signer_account_id(),
+        "Method must be called directly by the signer, not through another contract"
+    );
+}
+
 /// Assert that 1 yoctoNEAR was attached.
 pub fn assert_one_yocto() {
     require!(env::attached_deposit() == 1, "Requires attached deposit of exactly 1 yoctoNEAR")
@@ -157,7 +183,8 @@ macro_rules! setup_alloc {
 
 #[cfg(test)]
 mod tests {
-    use crate::test_utils::get_logs;
+    use crate::test_utils::{accounts, get_logs, VMContextBuilder};
+    use crate::testing_env;
 
     #[test]
     fn test_log_simple() {
@@ -172,4 +199,26 @@ mod tests {
 
         assert_eq!(get_logs(), vec!["hello user_name (25)".to_string()]);
     }
+
+    #[test]
+    fn direct_call_passes() {
+        testing_env!(VMContextBuilder::new()
+            .signer_account_id(accounts(1))
+            .predecessor_account_id(accounts(1))
+            .build());
+        super::assert_predecessor_is_signer();
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Method must be called directly by the signer, not through another contract"
+    )]
+    fn intermediated_call_panics() {
+        // The signer called a contract, which made the call.
+        testing_env!(VMContextBuilder::new()
+            .signer_account_id(accounts(1))
+            .predecessor_account_id(accounts(2))
+            .build());
+        super::assert_predecessor_is_signer();
+    }
 }
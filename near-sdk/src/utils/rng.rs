@@ -0,0 +1,125 @@
+use crate::env;
+
+/// Deterministic random number generator seeded from 32 bytes, usually
+/// [`env::random_seed_array`].
+///
+/// Each 32 byte block of output is the SHA-256 hash of the seed and a counter, so the same seed
+/// always gives the same numbers.
+///
+/// # Predictability
+///
+/// The random seed of a block is known to the validator producing it, and to every contract
+/// executing in the same block, which can compute any outcome derived from it before deciding
+/// whether to continue. Outcomes worth more than the stake of a validator, or which a contract
+/// call could react to, need a commit-reveal scheme or an oracle instead.
+///
+/// # Examples
+/// ```
+/// use near_sdk::utils::Rng;
+///
+/// let mut rng = Rng::new([7; 32]);
+/// let roll = rng.rand_range_u128(6) + 1;
+/// assert!((1..=6).contains(&roll));
+/// assert_ne!(rng.rand_u128(), Rng::new([7; 32]).rand_u128());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Rng {
+    seed: [u8; 32],
+    counter: u64,
+    block: [u8; 32],
+    offset: usize,
+}
+
+impl Rng {
+    /// Creates a generator from `seed`.
+    pub fn new(seed: [u8; 32]) -> Self {
+        Self { seed, counter: 0, block: [0; 32], offset: 32 }
+    }
+
+    /// Creates a generator from the random seed of the current block.
+    pub fn from_random_seed() -> Self {
+        Self::new(env::random_seed_array())
+    }
+
+    fn fill(&mut self, out: &mut [u8]) {
+        for byte in out {
+            if self.offset == self.block.len() {
+                let input = [&self.seed[..], &self.counter.to_le_bytes()].concat();
+                self.block = env::sha256_array(&input);
+                self.counter += 1;
+                self.offset = 0;
+            }
+            *byte = self.block[self.offset];
+            self.offset += 1;
+        }
+    }
+
+    /// Returns a uniformly distributed `u64`.
+    pub fn rand_u64(&mut self) -> u64 {
+        let mut bytes = [0; 8];
+        self.fill(&mut bytes);
+        u64::from_le_bytes(bytes)
+    }
+
+    /// Returns a uniformly distributed `u128`.
+    pub fn rand_u128(&mut self) -> u128 {
+        let mut bytes = [0; 16];
+        self.fill(&mut bytes);
+        u128::from_le_bytes(bytes)
+    }
+
+    /// Returns a uniformly distributed number in `0..bound`. Numbers from the incomplete last
+    /// multiple of `bound` are rejected, so that small numbers aren't more likely.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bound` is zero.
+    pub fn rand_range_u128(&mut self, bound: u128) -> u128 {
+        if bound == 0 {
+            env::panic_str("The bound of a random number can't be zero");
+        }
+        // Numbers at or above `zone` would be reduced unevenly.
+        let zone = u128::MAX - (u128::MAX - bound + 1) % bound;
+        loop {
+            let value = self.rand_u128();
+            if value <= zone {
+                return value % bound;
+            }
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use super::Rng;
+
+    #[test]
+    fn deterministic() {
+        let mut a = Rng::new([1; 32]);
+        let mut b = Rng::new([1; 32]);
+        let numbers: Vec<_> = (0..10).map(|_| a.rand_u64()).collect();
+        assert_eq!(numbers, (0..10).map(|_| b.rand_u64()).collect::<Vec<_>>());
+        assert_ne!(Rng::new([2; 32]).rand_u64(), numbers[0]);
+        // Numbers don't repeat across blocks of the hash.
+        assert_ne!(numbers[0], numbers[4]);
+    }
+
+    #[test]
+    fn range() {
+        let mut rng = Rng::new([3; 32]);
+        let mut counts = [0u32; 3];
+        for _ in 0..3000 {
+            counts[rng.rand_range_u128(3) as usize] += 1;
+        }
+        assert!(counts.iter().all(|&count| (900..1100).contains(&count)), "{:?}", counts);
+        assert_eq!(rng.rand_range_u128(1), 0);
+        assert!(rng.rand_range_u128(u128::MAX) < u128::MAX);
+    }
+
+    #[test]
+    #[should_panic(expected = "The bound of a random number can't be zero")]
+    fn zero_bound() {
+        Rng::new([0; 32]).rand_range_u128(0);
+    }
+}
@@ -0,0 +1,171 @@
+//! Refreshing a `CachedRemote` value through its generated resolver.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::mock::VmAction;
+use near_sdk::test_utils::{
+    accounts, advance_block_timestamp, get_created_receipts, get_logs, set_block_timestamp_ms,
+    VMContextBuilder,
+};
+use near_sdk::utils::CachedRemote;
+use near_sdk::{
+    ext_contract, near_bindgen, testing_env, AccountId, Gas, PanicOnDefault, PromiseOrValue,
+    PromiseResult, RuntimeFeesConfig, VMConfig,
+};
+use std::time::Duration;
+
+const MAX_AGE: Duration = Duration::from_secs(60);
+const GAS: Gas = Gas(5_000_000_000_000);
+
+#[ext_contract(ext_oracle)]
+trait Oracle {
+    fn get_price(&self) -> U128;
+}
+
+#[near_bindgen]
+#[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+pub struct Contract {
+    oracle_id: AccountId,
+    price: CachedRemote<U128>,
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn price(&self) -> PromiseOrValue<U128> {
+        let oracle_id = self.oracle_id.clone();
+        self.price.get_or_refresh(
+            MAX_AGE,
+            || ext_oracle::get_price(oracle_id, 0, GAS),
+            "on_price",
+            GAS,
+        )
+    }
+}
+
+near_sdk::impl_cached_remote_resolver!(Contract, price, on_price, U128);
+
+/// Sets the context of a call at `timestamp_ms`, with `promise_results` for a resolver.
+fn set_context(timestamp_ms: u64, promise_results: Vec<PromiseResult>) {
+    testing_env!(
+        VMContextBuilder::new()
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(0))
+            .block_timestamp(timestamp_ms * 1_000_000)
+            .build(),
+        VMConfig::test(),
+        RuntimeFeesConfig::test(),
+        Default::default(),
+        promise_results,
+    );
+}
+
+fn contract() -> Contract {
+    set_context(1_000, vec![]);
+    Contract { oracle_id: accounts(1), price: CachedRemote::new(b"p".to_vec()) }
+}
+
+/// Returns the receivers and function names of the calls scheduled in this context.
+fn scheduled_calls() -> Vec<(AccountId, String)> {
+    get_created_receipts()
+        .into_iter()
+        .flat_map(|receipt| {
+            let receiver_id = receipt.receiver_id;
+            receipt.actions.into_iter().filter_map(move |action| match action {
+                VmAction::FunctionCall { function_name, .. } => {
+                    Some((receiver_id.clone(), function_name))
+                }
+                _ => None,
+            })
+        })
+        .collect()
+}
+
+fn price_value(contract: &Contract) -> Option<U128> {
+    match contract.price() {
+        PromiseOrValue::Value(price) => Some(price),
+        PromiseOrValue::Promise(_) => None,
+    }
+}
+
+#[test]
+fn fresh_value_is_returned() {
+    let mut contract = contract();
+    contract.price.set(&U128(7));
+    advance_block_timestamp(MAX_AGE);
+
+    assert_eq!(price_value(&contract), Some(U128(7)));
+    assert!(!contract.price.needs_refresh(MAX_AGE));
+    assert!(scheduled_calls().is_empty());
+}
+
+#[test]
+fn stale_value_is_refreshed() {
+    let mut contract = contract();
+    contract.price.set(&U128(7));
+    advance_block_timestamp(MAX_AGE + Duration::from_millis(1));
+
+    assert!(contract.price.needs_refresh(MAX_AGE));
+    assert_eq!(contract.price.get_if_fresh(MAX_AGE), None);
+    assert_eq!(price_value(&contract), None);
+    assert_eq!(
+        scheduled_calls(),
+        [(accounts(1), "get_price".to_string()), (accounts(0), "on_price".to_string())]
+    );
+
+    set_context(70_000, vec![PromiseResult::Successful(b"\"42\"".to_vec())]);
+    assert_eq!(contract.on_price(), U128(42));
+    assert_eq!(contract.price.get(), Some((U128(42), 70_000)));
+    assert_eq!(price_value(&contract), Some(U128(42)));
+}
+
+#[test]
+fn missing_value_is_fetched() {
+    let contract = contract();
+    assert!(contract.price.needs_refresh(MAX_AGE));
+    assert_eq!(price_value(&contract), None);
+    assert_eq!(scheduled_calls().len(), 2);
+}
+
+#[test]
+fn failed_refresh_keeps_old_value() {
+    let mut contract = contract();
+    contract.price.set(&U128(7));
+
+    set_context(100_000, vec![PromiseResult::Failed]);
+    assert_eq!(contract.on_price(), U128(7));
+    assert_eq!(contract.price.get(), Some((U128(7), 1_000)));
+    assert_eq!(
+        get_logs(),
+        ["Refreshing the cached value failed: the remote call failed. Keeping the value fetched at 1000"]
+    );
+
+    // A result of another type is a failure too, and the value stays stale.
+    set_context(100_000, vec![PromiseResult::Successful(b"{}".to_vec())]);
+    assert_eq!(contract.on_price(), U128(7));
+    assert_eq!(
+        get_logs(),
+        ["Refreshing the cached value failed: the result can't be deserialized. Keeping the value fetched at 1000"]
+    );
+    assert!(contract.price.needs_refresh(MAX_AGE));
+    set_block_timestamp_ms(60_999);
+    assert!(!contract.price.needs_refresh(MAX_AGE));
+}
+
+#[test]
+#[should_panic(
+    expected = "Refreshing the cached value failed: the remote call failed. No value is cached"
+)]
+fn failed_refresh_without_value() {
+    let mut contract = contract();
+    set_context(1_000, vec![PromiseResult::Failed]);
+    contract.on_price();
+}
+
+#[test]
+fn clear() {
+    let mut contract = contract();
+    contract.price.set(&U128(7));
+    contract.price.clear();
+    assert_eq!(contract.price.get(), None);
+    assert!(contract.price.needs_refresh(Duration::from_secs(u64::MAX)));
+}
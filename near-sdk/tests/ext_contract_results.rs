@@ -0,0 +1,101 @@
+//! Typed results of the methods of an `#[ext_contract]` trait.
+
+use near_sdk::mock::VmAction;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::test_utils::{accounts, get_created_receipts, VMContextBuilder};
+use near_sdk::{
+    env, ext_contract, serde_json, testing_env, AccountId, ExtMethodMetadata, Gas, PromiseError,
+    PromiseOrValue, PromiseResult, RuntimeFeesConfig, VMConfig,
+};
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Token {
+    pub token_id: String,
+    pub owner_id: AccountId,
+}
+
+#[ext_contract(ext_nft)]
+pub trait Nft {
+    fn nft_token(&self, token_id: String) -> Option<Token>;
+    fn nft_transfer_call(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: String,
+    ) -> PromiseOrValue<bool>;
+}
+
+#[ext_contract(ext_self)]
+pub trait Callbacks {
+    fn on_token(&mut self);
+}
+
+/// Reads the promise result at `index` like an argument with `#[callback_result]` does.
+fn callback_result<T: near_sdk::serde::de::DeserializeOwned>(
+    index: u64,
+) -> Result<T, PromiseError> {
+    match env::promise_result(index) {
+        PromiseResult::Successful(data) => Ok(serde_json::from_slice(&data).unwrap()),
+        PromiseResult::NotReady => Err(PromiseError::NotReady),
+        PromiseResult::Failed => Err(PromiseError::Failed),
+    }
+}
+
+fn set_context(promise_results: Vec<PromiseResult>) {
+    testing_env!(
+        VMContextBuilder::new().current_account_id(accounts(0)).build(),
+        VMConfig::test(),
+        RuntimeFeesConfig::test(),
+        Default::default(),
+        promise_results,
+    );
+}
+
+#[test]
+fn nft_token_result() {
+    set_context(vec![]);
+    let callback = ext_self::on_token(accounts(0), 0, Gas(5_000_000_000_000));
+    ext_nft::nft_token("1".to_string(), accounts(1), 0, Gas(5_000_000_000_000))
+        .then_with::<ext_nft::NftTokenReturn>(callback);
+    let receipts = get_created_receipts();
+    let function_names: Vec<_> = receipts
+        .iter()
+        .map(|receipt| match &receipt.actions[..] {
+            [VmAction::FunctionCall { function_name, .. }] => function_name.as_str(),
+            actions => panic!("unexpected actions {:?}", actions),
+        })
+        .collect();
+    assert_eq!(function_names, ["nft_token", "on_token"]);
+    assert_eq!(receipts[1].receipt_indices, [0]);
+
+    let token = Token { token_id: "1".to_string(), owner_id: accounts(2) };
+    set_context(vec![PromiseResult::Successful(serde_json::to_vec(&Some(&token)).unwrap())]);
+    let result: ext_nft::NftTokenResult = callback_result(0);
+    assert_eq!(result, Ok(Some(token)));
+
+    set_context(vec![PromiseResult::Successful(b"null".to_vec())]);
+    let result: ext_nft::NftTokenResult = callback_result(0);
+    assert_eq!(result, Ok(None));
+
+    set_context(vec![PromiseResult::Failed]);
+    let result: ext_nft::NftTokenResult = callback_result(0);
+    assert_eq!(result, Err(PromiseError::Failed));
+}
+
+#[test]
+fn promise_or_value_result() {
+    set_context(vec![PromiseResult::Successful(b"true".to_vec())]);
+    let result: ext_nft::NftTransferCallResult = callback_result(0);
+    assert_eq!(result, Ok(true));
+}
+
+#[test]
+fn methods_metadata() {
+    assert_eq!(
+        ext_nft::METHODS,
+        [
+            ExtMethodMetadata { name: "nft_token", is_view: true },
+            ExtMethodMetadata { name: "nft_transfer_call", is_view: false },
+        ]
+    );
+}
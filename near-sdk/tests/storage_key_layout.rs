@@ -0,0 +1,110 @@
+//! Storage keys of enums deriving `BorshStorageKey`.
+
+use near_sdk::borsh::{self, BorshSerialize};
+use near_sdk::storage_key::namespace_prefix;
+use near_sdk::{BorshStorageKey, IntoStorageKey};
+
+#[derive(BorshStorageKey, BorshSerialize)]
+enum Implicit {
+    Accounts,
+    Tokens { owner: String },
+}
+
+#[derive(BorshStorageKey, BorshSerialize)]
+enum Explicit {
+    Accounts = 0,
+    // Added later, without moving `TokensPerOwner`.
+    Approvals = 4,
+    TokensPerOwner = 3,
+}
+
+/// Keys of a library, which can't collide with `Implicit`, the keys of the contract using it.
+#[derive(BorshStorageKey, BorshSerialize)]
+#[storage_key(namespace = "my_library")]
+enum Namespaced {
+    Accounts,
+    Tokens { owner: String },
+}
+
+#[derive(BorshStorageKey, BorshSerialize)]
+#[storage_key(namespace = "my_library")]
+enum NamespacedExplicit {
+    Accounts = 0,
+    Tokens = 1,
+}
+
+#[derive(BorshStorageKey, BorshSerialize)]
+#[storage_key(namespace = "my_library")]
+struct NamespacedStruct {
+    owner: String,
+}
+
+#[test]
+fn implicit_keys() {
+    assert_eq!(Implicit::Accounts.into_storage_key(), [0]);
+    assert_eq!(
+        Implicit::Tokens { owner: "a".to_string() }.into_storage_key(),
+        [1, 1, 0, 0, 0, b'a']
+    );
+    assert_eq!(Implicit::STORAGE_KEY_LAYOUT, [("Accounts", 0), ("Tokens", 1)]);
+}
+
+#[test]
+fn explicit_keys() {
+    assert_eq!(Explicit::Accounts.into_storage_key(), [0]);
+    assert_eq!(Explicit::Approvals.into_storage_key(), [4]);
+    assert_eq!(Explicit::TokensPerOwner.into_storage_key(), [3]);
+    assert_eq!(
+        Explicit::STORAGE_KEY_LAYOUT,
+        [("Accounts", 0), ("Approvals", 4), ("TokensPerOwner", 3)]
+    );
+}
+
+#[test]
+fn layout_fingerprint() {
+    // Golden values: a change means the storage keys of the variants changed.
+    assert_eq!(Implicit::layout_fingerprint(), 0xc10d_3891_c7fe_7a38);
+    const FINGERPRINT: u64 = Explicit::layout_fingerprint();
+    assert_ne!(FINGERPRINT, Implicit::layout_fingerprint());
+}
+
+#[test]
+fn namespaced_keys() {
+    let prefix = namespace_prefix("my_library");
+    assert_eq!(Namespaced::Accounts.into_storage_key(), [&prefix[..], &[0]].concat());
+    assert_eq!(
+        Namespaced::Tokens { owner: "a".to_string() }.into_storage_key(),
+        [&prefix[..], &[1, 1, 0, 0, 0, b'a']].concat()
+    );
+    assert_eq!(NamespacedExplicit::Tokens.into_storage_key(), [&prefix[..], &[1]].concat());
+    assert_eq!(
+        NamespacedStruct { owner: "a".to_string() }.into_storage_key(),
+        [&prefix[..], &[1, 0, 0, 0, b'a']].concat()
+    );
+
+    assert_eq!(Namespaced::STORAGE_KEY_NAMESPACE, Some("my_library"));
+    assert_eq!(Implicit::STORAGE_KEY_NAMESPACE, None);
+    // The layout is the same, but every key moved.
+    assert_eq!(Namespaced::STORAGE_KEY_LAYOUT, Implicit::STORAGE_KEY_LAYOUT);
+    assert_ne!(Namespaced::layout_fingerprint(), Implicit::layout_fingerprint());
+}
+
+#[test]
+fn namespaced_keys_do_not_collide() {
+    let contract_keys = [
+        Implicit::Accounts.into_storage_key(),
+        Implicit::Tokens { owner: "a".to_string() }.into_storage_key(),
+    ];
+    let library_keys = [
+        Namespaced::Accounts.into_storage_key(),
+        Namespaced::Tokens { owner: "a".to_string() }.into_storage_key(),
+    ];
+    // Collections store their entries under their prefix followed by the entry key, so no key
+    // may be a prefix of another.
+    for contract_key in &contract_keys {
+        for library_key in &library_keys {
+            assert!(!library_key.starts_with(contract_key), "{:?}", contract_key);
+            assert!(!contract_key.starts_with(library_key), "{:?}", library_key);
+        }
+    }
+}